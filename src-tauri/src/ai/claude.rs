@@ -0,0 +1,80 @@
+// Anthropic Messages API (ストリーミング)
+
+use super::sse::SseEvent;
+use super::{AiCompletionRequest, AiProvider, AiProviderKind, TokenUsage};
+
+const CLAUDE_API_URL: &str = "https://api.anthropic.com/v1/messages";
+const ANTHROPIC_VERSION: &str = "2023-06-01";
+
+pub struct ClaudeProvider;
+
+impl AiProvider for ClaudeProvider {
+    fn kind(&self) -> AiProviderKind {
+        AiProviderKind::Claude
+    }
+
+    fn build_request(
+        &self,
+        client: &reqwest::Client,
+        api_key: &str,
+        model: &str,
+        request: &AiCompletionRequest,
+    ) -> reqwest::RequestBuilder {
+        let mut body = serde_json::json!({
+            "model": model,
+            "max_tokens": request.max_tokens,
+            "stream": true,
+            "system": request.system_prompt,
+            "messages": [
+                { "role": "user", "content": request.user_prompt },
+            ],
+        });
+        if let Some(temperature) = request.temperature {
+            body["temperature"] = temperature.into();
+        }
+
+        client
+            .post(CLAUDE_API_URL)
+            .header("x-api-key", api_key)
+            .header("anthropic-version", ANTHROPIC_VERSION)
+            .json(&body)
+    }
+
+    fn parse_event(
+        &self,
+        event: &SseEvent,
+        usage: &mut TokenUsage,
+    ) -> Result<Option<String>, String> {
+        let payload: serde_json::Value = serde_json::from_str(&event.data)
+            .map_err(|e| format!("ストリーム解析エラー: {}", e))?;
+        let event_type = event
+            .event
+            .as_deref()
+            .or_else(|| payload["type"].as_str())
+            .unwrap_or_default();
+
+        match event_type {
+            "message_start" => {
+                if let Some(input) = payload["message"]["usage"]["input_tokens"].as_u64() {
+                    usage.input_tokens = input;
+                }
+                Ok(None)
+            }
+            "content_block_delta" => Ok(payload["delta"]["text"]
+                .as_str()
+                .filter(|text| !text.is_empty())
+                .map(str::to_string)),
+            "message_delta" => {
+                if let Some(output) = payload["usage"]["output_tokens"].as_u64() {
+                    usage.output_tokens = output;
+                }
+                Ok(None)
+            }
+            "error" => Err(payload["error"]["message"]
+                .as_str()
+                .unwrap_or("Claude APIエラー")
+                .to_string()),
+            _ => Ok(None),
+        }
+    }
+}
@@ -0,0 +1,82 @@
+// Gemini generateContent API (ストリーミング)
+
+use super::sse::SseEvent;
+use super::{AiCompletionRequest, AiProvider, AiProviderKind, TokenUsage};
+
+const GEMINI_API_URL: &str = "https://generativelanguage.googleapis.com/v1beta/models";
+
+pub struct GeminiProvider;
+
+impl AiProvider for GeminiProvider {
+    fn kind(&self) -> AiProviderKind {
+        AiProviderKind::Gemini
+    }
+
+    fn build_request(
+        &self,
+        client: &reqwest::Client,
+        api_key: &str,
+        model: &str,
+        request: &AiCompletionRequest,
+    ) -> reqwest::RequestBuilder {
+        let mut generation_config = serde_json::json!({
+            "maxOutputTokens": request.max_tokens,
+        });
+        if let Some(temperature) = request.temperature {
+            generation_config["temperature"] = temperature.into();
+        }
+        if request.json_mode {
+            generation_config["responseMimeType"] = "application/json".into();
+        }
+
+        let body = serde_json::json!({
+            "contents": [
+                { "role": "user", "parts": [{ "text": request.user_prompt }] },
+            ],
+            "systemInstruction": {
+                "parts": [{ "text": request.system_prompt }],
+            },
+            "generationConfig": generation_config,
+        });
+
+        // キーはURLに載せずヘッダーで渡す
+        client
+            .post(format!(
+                "{}/{}:streamGenerateContent?alt=sse",
+                GEMINI_API_URL, model
+            ))
+            .header("x-goog-api-key", api_key)
+            .json(&body)
+    }
+
+    fn parse_event(
+        &self,
+        event: &SseEvent,
+        usage: &mut TokenUsage,
+    ) -> Result<Option<String>, String> {
+        let chunk: serde_json::Value = serde_json::from_str(&event.data)
+            .map_err(|e| format!("ストリーム解析エラー: {}", e))?;
+
+        if let Some(message) = chunk["error"]["message"].as_str() {
+            return Err(message.to_string());
+        }
+        if let Some(prompt) = chunk["usageMetadata"]["promptTokenCount"].as_u64() {
+            usage.input_tokens = prompt;
+        }
+        if let Some(candidates) = chunk["usageMetadata"]["candidatesTokenCount"].as_u64() {
+            usage.output_tokens = candidates;
+        }
+
+        let text: String = chunk["candidates"][0]["content"]["parts"]
+            .as_array()
+            .map(|parts| {
+                parts
+                    .iter()
+                    .filter_map(|part| part["text"].as_str())
+                    .collect()
+            })
+            .unwrap_or_default();
+
+        Ok(if text.is_empty() { None } else { Some(text) })
+    }
+}
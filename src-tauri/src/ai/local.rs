@@ -0,0 +1,155 @@
+// ローカルLLM (Ollama / llama.cpp server / LM Studio などのOpenAI互換エンドポイント)
+
+use std::time::{Duration, Instant};
+
+use super::sse::SseEvent;
+use super::{openai, AiCompletionRequest, AiProvider, AiProviderKind, TokenUsage};
+
+const DEFAULT_BASE_URL: &str = "http://localhost:11434/v1";
+const HEALTH_CHECK_TIMEOUT: Duration = Duration::from_secs(3);
+
+#[derive(serde::Serialize, serde::Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct LocalLlmConfig {
+    pub base_url: String,
+    #[serde(default)]
+    pub api_key: Option<String>,
+    #[serde(default)]
+    pub default_model: Option<String>,
+}
+
+impl Default for LocalLlmConfig {
+    fn default() -> Self {
+        Self {
+            base_url: DEFAULT_BASE_URL.to_string(),
+            api_key: None,
+            default_model: None,
+        }
+    }
+}
+
+impl LocalLlmConfig {
+    // "http://localhost:11434" のように /v1 を省略した入力も受け付ける
+    pub fn api_base(&self) -> String {
+        let base = self.base_url.trim().trim_end_matches('/');
+        if base.ends_with("/v1") {
+            base.to_string()
+        } else {
+            format!("{}/v1", base)
+        }
+    }
+
+    fn authorize(&self, builder: reqwest::RequestBuilder) -> reqwest::RequestBuilder {
+        match self.api_key.as_deref().map(str::trim) {
+            Some(key) if !key.is_empty() => builder.bearer_auth(key),
+            _ => builder,
+        }
+    }
+}
+
+#[derive(serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LocalLlmHealth {
+    pub ok: bool,
+    pub base_url: String,
+    pub latency_ms: u64,
+    pub model_count: usize,
+    pub message: String,
+}
+
+pub struct LocalProvider {
+    pub config: LocalLlmConfig,
+}
+
+impl AiProvider for LocalProvider {
+    fn kind(&self) -> AiProviderKind {
+        AiProviderKind::Local
+    }
+
+    fn build_request(
+        &self,
+        client: &reqwest::Client,
+        _api_key: &str,
+        model: &str,
+        request: &AiCompletionRequest,
+    ) -> reqwest::RequestBuilder {
+        self.config.authorize(
+            client
+                .post(format!("{}/chat/completions", self.config.api_base()))
+                .json(&openai::chat_body(model, request, false)),
+        )
+    }
+
+    fn parse_event(
+        &self,
+        event: &SseEvent,
+        usage: &mut TokenUsage,
+    ) -> Result<Option<String>, String> {
+        openai::parse_chat_chunk(event, usage)
+    }
+}
+
+pub async fn list_models(
+    client: &reqwest::Client,
+    config: &LocalLlmConfig,
+) -> Result<Vec<String>, String> {
+    let response = config
+        .authorize(client.get(format!("{}/models", config.api_base())))
+        .timeout(HEALTH_CHECK_TIMEOUT)
+        .send()
+        .await
+        .map_err(|e| format!("ローカルLLMに接続できません: {}", e))?;
+
+    if !response.status().is_success() {
+        let status = response.status();
+        let error_text = response.text().await.unwrap_or_default();
+        return Err(format!("ローカルLLMエラー ({}): {}", status, error_text));
+    }
+
+    let body: serde_json::Value = response
+        .json()
+        .await
+        .map_err(|e| format!("レスポンス解析エラー: {}", e))?;
+
+    let mut models: Vec<String> = body["data"]
+        .as_array()
+        .map(|items| {
+            items
+                .iter()
+                .filter_map(|item| item["id"].as_str().map(str::to_string))
+                .collect()
+        })
+        .unwrap_or_default();
+    models.sort();
+    Ok(models)
+}
+
+pub async fn health_check(client: &reqwest::Client, config: &LocalLlmConfig) -> LocalLlmHealth {
+    let started = Instant::now();
+    let result = list_models(client, config).await;
+    let latency_ms = started.elapsed().as_millis() as u64;
+
+    match result {
+        Ok(models) if models.is_empty() => LocalLlmHealth {
+            ok: false,
+            base_url: config.api_base(),
+            latency_ms,
+            model_count: 0,
+            message: "接続できましたが、利用可能なモデルがありません".to_string(),
+        },
+        Ok(models) => LocalLlmHealth {
+            ok: true,
+            base_url: config.api_base(),
+            latency_ms,
+            model_count: models.len(),
+            message: format!("{} 個のモデルが利用可能です", models.len()),
+        },
+        Err(message) => LocalLlmHealth {
+            ok: false,
+            base_url: config.api_base(),
+            latency_ms,
+            model_count: 0,
+            message,
+        },
+    }
+}
@@ -0,0 +1,670 @@
+// AIプロバイダーのバックエンドプロキシ
+// APIキーはバックエンドで保持し、Webviewにはストリーミングで結果だけを返す
+
+mod claude;
+mod gemini;
+mod local;
+pub mod models;
+mod openai;
+mod sse;
+
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use tauri::ipc::Channel;
+
+use crate::usage::{AiCallRecorder, UsageState};
+
+use sse::{SseEvent, SseParser};
+
+const API_KEYS_FILE: &str = "ai-keys.json";
+const LOCAL_CONFIG_FILE: &str = "ai-local.json";
+
+#[derive(serde::Serialize, serde::Deserialize, Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[serde(rename_all = "lowercase")]
+pub enum AiProviderKind {
+    OpenAI,
+    Claude,
+    Gemini,
+    Local,
+}
+
+impl AiProviderKind {
+    // serde表現と同じ識別子 (利用量台帳などで使用)
+    pub fn id(&self) -> &'static str {
+        match self {
+            AiProviderKind::OpenAI => "openai",
+            AiProviderKind::Claude => "claude",
+            AiProviderKind::Gemini => "gemini",
+            AiProviderKind::Local => "local",
+        }
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            AiProviderKind::OpenAI => "OpenAI",
+            AiProviderKind::Claude => "Claude",
+            AiProviderKind::Gemini => "Gemini",
+            AiProviderKind::Local => "ローカルLLM",
+        }
+    }
+}
+
+#[derive(serde::Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct AiCompletionRequest {
+    pub provider: AiProviderKind,
+    #[serde(default)]
+    pub model: Option<String>,
+    pub system_prompt: String,
+    pub user_prompt: String,
+    pub max_tokens: u32,
+    #[serde(default)]
+    pub temperature: Option<f32>,
+    // JSONのみを返させる (計画生成・タスク分解用)
+    #[serde(default)]
+    pub json_mode: bool,
+}
+
+#[derive(serde::Serialize, Clone, Copy, Default, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct TokenUsage {
+    pub input_tokens: u64,
+    pub output_tokens: u64,
+}
+
+#[derive(serde::Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct AiCompletion {
+    pub provider: AiProviderKind,
+    pub model: String,
+    pub text: String,
+    pub usage: TokenUsage,
+}
+
+#[derive(serde::Serialize, Clone)]
+#[serde(tag = "event", content = "data", rename_all = "camelCase")]
+pub enum AiStreamEvent {
+    #[serde(rename_all = "camelCase")]
+    Started {
+        provider: AiProviderKind,
+        model: String,
+    },
+    Delta {
+        text: String,
+    },
+    Finished {
+        completion: AiCompletion,
+    },
+    Cancelled,
+    Error {
+        message: String,
+    },
+}
+
+pub trait AiProvider: Send + Sync {
+    fn kind(&self) -> AiProviderKind;
+
+    fn build_request(
+        &self,
+        client: &reqwest::Client,
+        api_key: &str,
+        model: &str,
+        request: &AiCompletionRequest,
+    ) -> reqwest::RequestBuilder;
+
+    // ストリームの1イベントを解釈し、追加されたテキストがあれば返す
+    fn parse_event(
+        &self,
+        event: &SseEvent,
+        usage: &mut TokenUsage,
+    ) -> Result<Option<String>, String>;
+}
+
+// 生成中のタスクの登録。コマンドの Future が破棄されても (Webviewの再読み込みなど) タスクを止め、登録を外す
+struct InflightTask<'a> {
+    inflight: &'a Mutex<HashMap<String, tokio::task::AbortHandle>>,
+    request_id: String,
+    handle: tokio::task::AbortHandle,
+}
+
+impl Drop for InflightTask<'_> {
+    fn drop(&mut self) {
+        self.handle.abort();
+        let mut inflight = self.inflight.lock().unwrap();
+        if inflight
+            .get(&self.request_id)
+            .is_some_and(|handle| handle.id() == self.handle.id())
+        {
+            inflight.remove(&self.request_id);
+        }
+    }
+}
+
+pub struct AiState {
+    client: reqwest::Client,
+    api_keys: Mutex<HashMap<AiProviderKind, String>>,
+    local_config: Mutex<local::LocalLlmConfig>,
+    inflight: Mutex<HashMap<String, tokio::task::AbortHandle>>,
+}
+
+impl AiState {
+    pub fn load(app: &tauri::AppHandle) -> Self {
+        let api_keys = crate::store::load_json(app, API_KEYS_FILE).unwrap_or_else(|e| {
+            println!("[AI Backend] APIキー読み込み失敗: {}", e);
+            HashMap::new()
+        });
+        let local_config = crate::store::load_json(app, LOCAL_CONFIG_FILE).unwrap_or_else(|e| {
+            println!("[AI Backend] ローカルLLM設定読み込み失敗: {}", e);
+            local::LocalLlmConfig::default()
+        });
+        Self {
+            client: reqwest::Client::new(),
+            api_keys: Mutex::new(api_keys),
+            local_config: Mutex::new(local_config),
+            inflight: Mutex::new(HashMap::new()),
+        }
+    }
+
+    fn api_key(&self, provider: AiProviderKind) -> Option<String> {
+        self.api_keys.lock().unwrap().get(&provider).cloned()
+    }
+
+    fn local_config(&self) -> local::LocalLlmConfig {
+        self.local_config.lock().unwrap().clone()
+    }
+
+    // プロバイダー・モデル・APIキーを解決する
+    fn prepare(
+        &self,
+        request: &AiCompletionRequest,
+    ) -> Result<(Box<dyn AiProvider>, String, String), String> {
+        if request.provider == AiProviderKind::Local {
+            let config = self.local_config();
+            let requested = request.model.as_deref().or(config.default_model.as_deref());
+            let model = models::resolve_model(AiProviderKind::Local, requested)?;
+            return Ok((
+                Box::new(local::LocalProvider { config }),
+                model,
+                String::new(),
+            ));
+        }
+
+        let provider: Box<dyn AiProvider> = match request.provider {
+            AiProviderKind::Claude => Box::new(claude::ClaudeProvider),
+            AiProviderKind::Gemini => Box::new(gemini::GeminiProvider),
+            _ => Box::new(openai::OpenAiProvider),
+        };
+        let model = models::resolve_model(request.provider, request.model.as_deref())?;
+        let api_key = self
+            .api_key(request.provider)
+            .ok_or_else(|| format!("{} APIキーが設定されていません", request.provider.label()))?;
+        Ok((provider, model, api_key))
+    }
+}
+
+async fn stream_completion(
+    client: reqwest::Client,
+    provider: Box<dyn AiProvider>,
+    api_key: String,
+    model: String,
+    request: AiCompletionRequest,
+    channel: Channel<AiStreamEvent>,
+    usage: Arc<Mutex<TokenUsage>>,
+) -> Result<AiCompletion, String> {
+    let label = provider.kind().label();
+    let mut response = provider
+        .build_request(&client, &api_key, &model, &request)
+        .send()
+        .await
+        .map_err(|e| format!("{}リクエストエラー: {}", label, e))?;
+
+    if !response.status().is_success() {
+        let status = response.status();
+        let error_text = response.text().await.unwrap_or_default();
+        println!("[AI Backend] {} エラー ({}): {}", label, status, error_text);
+        return Err(format!("{} APIエラー ({}): {}", label, status, error_text));
+    }
+
+    let _ = channel.send(AiStreamEvent::Started {
+        provider: provider.kind(),
+        model: model.clone(),
+    });
+
+    let mut parser = SseParser::new();
+    let mut text = String::new();
+
+    while let Some(chunk) = response
+        .chunk()
+        .await
+        .map_err(|e| format!("{}ストリーム受信エラー: {}", label, e))?
+    {
+        for event in parser.push(&chunk) {
+            if let Some(delta) = provider.parse_event(&event, &mut usage.lock().unwrap())? {
+                text.push_str(&delta);
+                let _ = channel.send(AiStreamEvent::Delta { text: delta });
+            }
+        }
+    }
+    if let Some(event) = parser.finish() {
+        if let Some(delta) = provider.parse_event(&event, &mut usage.lock().unwrap())? {
+            text.push_str(&delta);
+            let _ = channel.send(AiStreamEvent::Delta { text: delta });
+        }
+    }
+
+    if text.is_empty() {
+        return Err("AIからの応答がありません".to_string());
+    }
+
+    Ok(AiCompletion {
+        provider: provider.kind(),
+        model,
+        text,
+        usage: *usage.lock().unwrap(),
+    })
+}
+
+#[tauri::command]
+pub fn ai_list_models() -> Vec<models::AiModelInfo> {
+    models::list_models()
+}
+
+#[tauri::command]
+pub fn ai_get_key_status(state: tauri::State<'_, AiState>) -> HashMap<AiProviderKind, bool> {
+    let keys = state.api_keys.lock().unwrap();
+    models::CLOUD_PROVIDERS
+        .into_iter()
+        .map(|provider| (provider, keys.contains_key(&provider)))
+        .collect()
+}
+
+#[tauri::command]
+pub fn ai_set_api_key(
+    app: tauri::AppHandle,
+    state: tauri::State<'_, AiState>,
+    provider: AiProviderKind,
+    api_key: String,
+) -> Result<(), String> {
+    let mut keys = state.api_keys.lock().unwrap();
+    let api_key = api_key.trim().to_string();
+    if api_key.is_empty() {
+        keys.remove(&provider);
+    } else {
+        keys.insert(provider, api_key);
+    }
+    crate::store::save_json(&app, API_KEYS_FILE, &*keys)
+}
+
+#[tauri::command]
+pub fn ai_clear_api_key(
+    app: tauri::AppHandle,
+    state: tauri::State<'_, AiState>,
+    provider: AiProviderKind,
+) -> Result<(), String> {
+    let mut keys = state.api_keys.lock().unwrap();
+    keys.remove(&provider);
+    crate::store::save_json(&app, API_KEYS_FILE, &*keys)
+}
+
+// 生成結果を on_event に逐次流し、完了したら全文を返す
+#[tauri::command]
+pub async fn ai_stream_completion(
+    state: tauri::State<'_, AiState>,
+    usage: tauri::State<'_, UsageState>,
+    request_id: String,
+    request: AiCompletionRequest,
+    on_event: Channel<AiStreamEvent>,
+) -> Result<AiCompletion, String> {
+    let (provider, model, api_key) = state.prepare(&request)?;
+    let kind = provider.kind();
+    let label = kind.label();
+
+    // ローカルLLMは課金されないため予算の対象外
+    if kind != AiProviderKind::Local {
+        usage.ensure_within_budget()?;
+    }
+
+    println!(
+        "[AI Backend] 生成開始: {} ({}) id={}",
+        label, model, request_id
+    );
+
+    // 途中で失敗・キャンセルされても、受信済みのトークン数を記録する
+    let mut recorder = AiCallRecorder::new(&usage, kind.id(), &model);
+    let (task, registration) = {
+        // 中断要求が未登録のタスクを見失わないよう、生成開始から登録までロックを保持する
+        let mut inflight = state.inflight.lock().unwrap();
+        let task = tokio::spawn(stream_completion(
+            state.client.clone(),
+            provider,
+            api_key,
+            model.clone(),
+            request,
+            on_event.clone(),
+            recorder.tokens(),
+        ));
+        inflight.insert(request_id.clone(), task.abort_handle());
+        let registration = InflightTask {
+            inflight: &state.inflight,
+            request_id: request_id.clone(),
+            handle: task.abort_handle(),
+        };
+        (task, registration)
+    };
+
+    let result = task.await;
+    drop(registration);
+
+    if matches!(result, Ok(Ok(_))) {
+        recorder.succeed();
+    }
+    drop(recorder);
+
+    match result {
+        Ok(Ok(completion)) => {
+            let _ = on_event.send(AiStreamEvent::Finished {
+                completion: completion.clone(),
+            });
+            Ok(completion)
+        }
+        Ok(Err(message)) => {
+            let _ = on_event.send(AiStreamEvent::Error {
+                message: message.clone(),
+            });
+            Err(message)
+        }
+        Err(e) if e.is_cancelled() => {
+            println!("[AI Backend] 生成キャンセル: id={}", request_id);
+            let _ = on_event.send(AiStreamEvent::Cancelled);
+            Err("キャンセルされました".to_string())
+        }
+        Err(e) => Err(format!("生成タスクエラー: {}", e)),
+    }
+}
+
+#[tauri::command]
+pub fn ai_cancel_completion(state: tauri::State<'_, AiState>, request_id: String) -> bool {
+    match state.inflight.lock().unwrap().remove(&request_id) {
+        Some(handle) => {
+            handle.abort();
+            true
+        }
+        None => false,
+    }
+}
+
+#[tauri::command]
+pub fn ai_get_local_config(state: tauri::State<'_, AiState>) -> local::LocalLlmConfig {
+    state.local_config()
+}
+
+#[tauri::command]
+pub fn ai_set_local_config(
+    app: tauri::AppHandle,
+    state: tauri::State<'_, AiState>,
+    config: local::LocalLlmConfig,
+) -> Result<(), String> {
+    if config.base_url.trim().is_empty() {
+        return Err("ローカルLLMのURLを入力してください".to_string());
+    }
+    let mut current = state.local_config.lock().unwrap();
+    *current = config;
+    crate::store::save_json(&app, LOCAL_CONFIG_FILE, &*current)
+}
+
+#[tauri::command]
+pub async fn ai_list_local_models(state: tauri::State<'_, AiState>) -> Result<Vec<String>, String> {
+    local::list_models(&state.client, &state.local_config()).await
+}
+
+#[tauri::command]
+pub async fn ai_check_local_health(
+    state: tauri::State<'_, AiState>,
+) -> Result<local::LocalLlmHealth, String> {
+    Ok(local::health_check(&state.client, &state.local_config()).await)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn request(json_mode: bool) -> AiCompletionRequest {
+        AiCompletionRequest {
+            provider: AiProviderKind::OpenAI,
+            model: None,
+            system_prompt: "system".to_string(),
+            user_prompt: "user".to_string(),
+            max_tokens: 256,
+            temperature: Some(0.5),
+            json_mode,
+        }
+    }
+
+    fn built(builder: reqwest::RequestBuilder) -> (reqwest::Request, serde_json::Value) {
+        let request = builder.build().unwrap();
+        let body = request.body().and_then(|b| b.as_bytes()).unwrap();
+        let body = serde_json::from_slice(body).unwrap();
+        (request, body)
+    }
+
+    fn header<'a>(request: &'a reqwest::Request, name: &str) -> Option<&'a str> {
+        request.headers().get(name).and_then(|v| v.to_str().ok())
+    }
+
+    // 受信したストリームを chunk バイトずつに切って流す
+    fn run(
+        provider: &dyn AiProvider,
+        stream: &str,
+        chunk: usize,
+    ) -> Result<(String, u64, u64), String> {
+        let mut parser = SseParser::new();
+        let mut usage = TokenUsage::default();
+        let mut text = String::new();
+        let mut events = Vec::new();
+        for bytes in stream.as_bytes().chunks(chunk) {
+            events.extend(parser.push(bytes));
+        }
+        events.extend(parser.finish());
+        for event in &events {
+            if let Some(delta) = provider.parse_event(event, &mut usage)? {
+                text.push_str(&delta);
+            }
+        }
+        Ok((text, usage.input_tokens, usage.output_tokens))
+    }
+
+    const CLAUDE_STREAM: &str = "event: message_start\n\
+data: {\"type\":\"message_start\",\"message\":{\"usage\":{\"input_tokens\":25,\"output_tokens\":1}}}\n\n\
+event: ping\n\
+data: {\"type\":\"ping\"}\n\n\
+event: content_block_delta\n\
+data: {\"type\":\"content_block_delta\",\"index\":0,\"delta\":{\"type\":\"text_delta\",\"text\":\"こんにちは\"}}\n\n\
+event: content_block_delta\n\
+data: {\"type\":\"content_block_delta\",\"index\":0,\"delta\":{\"type\":\"text_delta\",\"text\":\"、世界\"}}\n\n\
+event: message_delta\n\
+data: {\"type\":\"message_delta\",\"usage\":{\"output_tokens\":12}}\n\n\
+event: message_stop\n\
+data: {\"type\":\"message_stop\"}\n\n";
+
+    const OPENAI_STREAM: &str =
+        "data: {\"choices\":[{\"delta\":{\"role\":\"assistant\",\"content\":\"\"}}]}\n\n\
+data: {\"choices\":[{\"delta\":{\"content\":\"今日は\"}}]}\n\n\
+: keep-alive\n\n\
+data: {\"choices\":[{\"delta\":{\"content\":\"晴れ\"}}]}\n\n\
+data: {\"choices\":[],\"usage\":{\"prompt_tokens\":9,\"completion_tokens\":4}}\n\n\
+data: [DONE]\n\n";
+
+    const GEMINI_STREAM: &str = "data: {\"candidates\":[{\"content\":{\"parts\":[{\"text\":\"a\"},{\"text\":\"b\"}]}}],\"usageMetadata\":{\"promptTokenCount\":7}}\r\n\r\n\
+data: {\"candidates\":[{\"content\":{\"parts\":[{\"text\":\"c\"}]}}],\"usageMetadata\":{\"promptTokenCount\":7,\"candidatesTokenCount\":3}}";
+
+    #[test]
+    fn streams_claude_messages() {
+        // マルチバイト文字の途中で切れても同じ結果になる
+        for chunk in [1, 3, 7, 64, 4096] {
+            assert_eq!(
+                run(&claude::ClaudeProvider, CLAUDE_STREAM, chunk),
+                Ok(("こんにちは、世界".to_string(), 25, 12)),
+                "{}",
+                chunk
+            );
+        }
+        let error = "event: error\ndata: {\"type\":\"error\",\"error\":{\"type\":\"overloaded_error\",\"message\":\"Overloaded\"}}\n\n";
+        assert_eq!(
+            run(&claude::ClaudeProvider, error, 5),
+            Err("Overloaded".to_string())
+        );
+        assert!(run(&claude::ClaudeProvider, "data: {oops\n\n", 4)
+            .unwrap_err()
+            .starts_with("ストリーム解析エラー"));
+    }
+
+    #[test]
+    fn streams_openai_compatible_chunks() {
+        for chunk in [1, 5, 4096] {
+            assert_eq!(
+                run(&openai::OpenAiProvider, OPENAI_STREAM, chunk),
+                Ok(("今日は晴れ".to_string(), 9, 4))
+            );
+        }
+        let local = local::LocalProvider {
+            config: local::LocalLlmConfig::default(),
+        };
+        assert_eq!(
+            run(&local, OPENAI_STREAM, 2),
+            Ok(("今日は晴れ".to_string(), 9, 4))
+        );
+        let error = "data: {\"error\":{\"message\":\"Rate limit reached\"}}\n\n";
+        assert_eq!(
+            run(&openai::OpenAiProvider, error, 3),
+            Err("Rate limit reached".to_string())
+        );
+    }
+
+    #[test]
+    fn streams_gemini_candidates() {
+        // 最後のイベントが空行で閉じられていなくても取り出す
+        for chunk in [1, 6, 4096] {
+            assert_eq!(
+                run(&gemini::GeminiProvider, GEMINI_STREAM, chunk),
+                Ok(("abc".to_string(), 7, 3))
+            );
+        }
+        let error = "data: {\"error\":{\"code\":400,\"message\":\"API key not valid\"}}\n\n";
+        assert_eq!(
+            run(&gemini::GeminiProvider, error, 8),
+            Err("API key not valid".to_string())
+        );
+    }
+
+    #[test]
+    fn builds_provider_requests() {
+        let client = reqwest::Client::new();
+
+        let (claude, body) = built(claude::ClaudeProvider.build_request(
+            &client,
+            "sk-ant",
+            "claude-sonnet-4-20250514",
+            &request(false),
+        ));
+        assert_eq!(
+            claude.url().as_str(),
+            "https://api.anthropic.com/v1/messages"
+        );
+        assert_eq!(header(&claude, "x-api-key"), Some("sk-ant"));
+        assert_eq!(header(&claude, "anthropic-version"), Some("2023-06-01"));
+        assert_eq!(body["system"], "system");
+        assert_eq!(body["messages"][0]["content"], "user");
+        assert_eq!(body["max_tokens"], 256);
+        assert_eq!(body["stream"], true);
+
+        let (openai, body) = built(openai::OpenAiProvider.build_request(
+            &client,
+            "sk",
+            "gpt-4.1-mini",
+            &request(true),
+        ));
+        assert_eq!(header(&openai, "authorization"), Some("Bearer sk"));
+        assert_eq!(body["max_tokens"], 256);
+        assert!(body.get("max_completion_tokens").is_none());
+        assert_eq!(body["response_format"]["type"], "json_object");
+        assert_eq!(body["stream_options"]["include_usage"], true);
+        assert_eq!(body["messages"][0]["role"], "system");
+        // 新しいモデルは max_completion_tokens を使う
+        let (_, body) = built(openai::OpenAiProvider.build_request(
+            &client,
+            "sk",
+            "gpt-5-mini",
+            &request(false),
+        ));
+        assert_eq!(body["max_completion_tokens"], 256);
+        assert!(body.get("max_tokens").is_none());
+        assert!(body.get("response_format").is_none());
+
+        let (gemini, body) = built(gemini::GeminiProvider.build_request(
+            &client,
+            "AIza",
+            "gemini-2.0-flash",
+            &request(true),
+        ));
+        // キーはURLに載せない
+        assert_eq!(
+            gemini.url().as_str(),
+            "https://generativelanguage.googleapis.com/v1beta/models/gemini-2.0-flash:streamGenerateContent?alt=sse"
+        );
+        assert_eq!(header(&gemini, "x-goog-api-key"), Some("AIza"));
+        assert_eq!(body["generationConfig"]["maxOutputTokens"], 256);
+        assert_eq!(
+            body["generationConfig"]["responseMimeType"],
+            "application/json"
+        );
+        assert_eq!(body["systemInstruction"]["parts"][0]["text"], "system");
+
+        let local = local::LocalProvider {
+            config: local::LocalLlmConfig {
+                base_url: "http://localhost:1234/".to_string(),
+                api_key: Some(" key ".to_string()),
+                default_model: None,
+            },
+        };
+        let (local, body) = built(local.build_request(&client, "", "llama3", &request(false)));
+        assert_eq!(
+            local.url().as_str(),
+            "http://localhost:1234/v1/chat/completions"
+        );
+        assert_eq!(header(&local, "authorization"), Some("Bearer key"));
+        assert_eq!(body["model"], "llama3");
+    }
+
+    #[tokio::test]
+    async fn dropping_the_registration_aborts_the_task() {
+        let inflight = Mutex::new(HashMap::new());
+        let task = tokio::spawn(std::future::pending::<()>());
+        inflight
+            .lock()
+            .unwrap()
+            .insert("a".to_string(), task.abort_handle());
+        let registration = InflightTask {
+            inflight: &inflight,
+            request_id: "a".to_string(),
+            handle: task.abort_handle(),
+        };
+        drop(registration);
+        assert!(task.await.unwrap_err().is_cancelled());
+        assert!(inflight.lock().unwrap().is_empty());
+
+        // 同じIDで後から登録された別のタスクは外さない
+        let other = tokio::spawn(std::future::pending::<()>());
+        let finished = tokio::spawn(async {});
+        inflight
+            .lock()
+            .unwrap()
+            .insert("a".to_string(), other.abort_handle());
+        drop(InflightTask {
+            inflight: &inflight,
+            request_id: "a".to_string(),
+            handle: finished.abort_handle(),
+        });
+        assert!(inflight.lock().unwrap().contains_key("a"));
+        other.abort();
+    }
+}
@@ -0,0 +1,129 @@
+// 利用可能なAIモデル一覧 (src/services/reminder.ts の AVAILABLE_MODELS と同期すること)
+
+use super::AiProviderKind;
+
+#[derive(serde::Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct AiModelInfo {
+    pub provider: AiProviderKind,
+    pub id: String,
+    pub name: String,
+    pub is_default: bool,
+}
+
+// APIキーで利用するクラウドプロバイダー
+pub const CLOUD_PROVIDERS: [AiProviderKind; 3] = [
+    AiProviderKind::OpenAI,
+    AiProviderKind::Claude,
+    AiProviderKind::Gemini,
+];
+
+const OPENAI_MODELS: &[(&str, &str)] = &[
+    ("gpt-5.2", "GPT-5.2（最新・最高性能）"),
+    ("gpt-5.2-pro", "GPT-5.2 Pro（プロフェッショナル）"),
+    ("gpt-5-mini", "GPT-5 Mini（高性能・コスパ◎）"),
+    ("gpt-4.1-mini", "GPT-4.1 Mini（推奨・コスパ◎）"),
+    ("gpt-4.1", "GPT-4.1（高性能）"),
+    ("gpt-4.1-nano", "GPT-4.1 Nano（最速・最安）"),
+    ("o4-mini", "o4-mini（推論・コード特化）"),
+    ("o3", "o3（推論特化）"),
+    ("gpt-4o", "GPT-4o（レガシー）"),
+    ("gpt-4o-mini", "GPT-4o Mini（レガシー）"),
+];
+
+const CLAUDE_MODELS: &[(&str, &str)] = &[
+    (
+        "claude-opus-4-5-20251101",
+        "Claude Opus 4.5（最新・最高性能）",
+    ),
+    (
+        "claude-sonnet-4-5-20250929",
+        "Claude Sonnet 4.5（最新・バランス◎）",
+    ),
+    (
+        "claude-haiku-4-5-20251001",
+        "Claude Haiku 4.5（最新・高速）",
+    ),
+    ("claude-sonnet-4-20250514", "Claude Sonnet 4（推奨・安定）"),
+    ("claude-opus-4-20250514", "Claude Opus 4（高性能）"),
+    (
+        "claude-3-5-sonnet-20241022",
+        "Claude 3.5 Sonnet（レガシー）",
+    ),
+    ("claude-3-5-haiku-20241022", "Claude 3.5 Haiku（レガシー）"),
+];
+
+const GEMINI_MODELS: &[(&str, &str)] = &[
+    ("gemini-2.5-pro", "Gemini 2.5 Pro（最高性能）"),
+    ("gemini-2.5-flash", "Gemini 2.5 Flash（推奨・推論強化）"),
+    (
+        "gemini-2.5-flash-lite",
+        "Gemini 2.5 Flash Lite（軽量・高速）",
+    ),
+    ("gemini-2.0-flash", "Gemini 2.0 Flash（安定）"),
+    ("gemini-2.0-flash-lite", "Gemini 2.0 Flash Lite（低コスト）"),
+    ("gemini-1.5-flash", "Gemini 1.5 Flash（レガシー）"),
+    ("gemini-1.5-pro", "Gemini 1.5 Pro（レガシー）"),
+];
+
+fn provider_models(provider: AiProviderKind) -> &'static [(&'static str, &'static str)] {
+    match provider {
+        AiProviderKind::OpenAI => OPENAI_MODELS,
+        AiProviderKind::Claude => CLAUDE_MODELS,
+        AiProviderKind::Gemini => GEMINI_MODELS,
+        // ローカルLLMのモデルはサーバーから取得する
+        AiProviderKind::Local => &[],
+    }
+}
+
+// DEFAULT_AI_MODELS と同じ
+pub fn default_model(provider: AiProviderKind) -> Option<&'static str> {
+    match provider {
+        AiProviderKind::OpenAI => Some("gpt-4.1-mini"),
+        AiProviderKind::Claude => Some("claude-sonnet-4-20250514"),
+        AiProviderKind::Gemini => Some("gemini-2.0-flash"),
+        AiProviderKind::Local => None,
+    }
+}
+
+pub fn list_models() -> Vec<AiModelInfo> {
+    CLOUD_PROVIDERS
+        .into_iter()
+        .flat_map(|provider| {
+            let default_id = default_model(provider);
+            provider_models(provider)
+                .iter()
+                .map(move |(id, name)| AiModelInfo {
+                    provider,
+                    id: id.to_string(),
+                    name: name.to_string(),
+                    is_default: Some(*id) == default_id,
+                })
+        })
+        .collect()
+}
+
+// 未指定ならデフォルト、一覧にないモデルはエラー (ローカルLLMは任意のモデル名を許可)
+pub fn resolve_model(provider: AiProviderKind, requested: Option<&str>) -> Result<String, String> {
+    let requested = match requested.map(str::trim) {
+        Some(model) if !model.is_empty() => model,
+        _ => {
+            return default_model(provider)
+                .map(str::to_string)
+                .ok_or_else(|| format!("{} のモデルが指定されていません", provider.label()))
+        }
+    };
+    if provider == AiProviderKind::Local
+        || provider_models(provider)
+            .iter()
+            .any(|(id, _)| *id == requested)
+    {
+        Ok(requested.to_string())
+    } else {
+        Err(format!(
+            "{} では未対応のモデルです: {}",
+            provider.label(),
+            requested
+        ))
+    }
+}
@@ -0,0 +1,96 @@
+// OpenAI Chat Completions API (ストリーミング)
+
+use super::sse::SseEvent;
+use super::{AiCompletionRequest, AiProvider, AiProviderKind, TokenUsage};
+
+const OPENAI_API_URL: &str = "https://api.openai.com/v1/chat/completions";
+
+pub struct OpenAiProvider;
+
+// GPT-5系など新しいモデルはmax_completion_tokensを使用
+fn is_new_model_format(model: &str) -> bool {
+    model.starts_with("gpt-5") || model.starts_with("o3") || model.starts_with("o4")
+}
+
+pub(super) fn chat_body(
+    model: &str,
+    request: &AiCompletionRequest,
+    use_completion_tokens: bool,
+) -> serde_json::Value {
+    let mut body = serde_json::json!({
+        "model": model,
+        "stream": true,
+        "stream_options": { "include_usage": true },
+        "messages": [
+            { "role": "system", "content": request.system_prompt },
+            { "role": "user", "content": request.user_prompt },
+        ],
+    });
+    let token_key = if use_completion_tokens {
+        "max_completion_tokens"
+    } else {
+        "max_tokens"
+    };
+    body[token_key] = request.max_tokens.into();
+    if let Some(temperature) = request.temperature {
+        body["temperature"] = temperature.into();
+    }
+    if request.json_mode {
+        body["response_format"] = serde_json::json!({ "type": "json_object" });
+    }
+    body
+}
+
+// OpenAI互換のストリームチャンクを解釈する (ローカルLLMでも共用)
+pub(super) fn parse_chat_chunk(
+    event: &SseEvent,
+    usage: &mut TokenUsage,
+) -> Result<Option<String>, String> {
+    if event.data.trim() == "[DONE]" {
+        return Ok(None);
+    }
+    let chunk: serde_json::Value =
+        serde_json::from_str(&event.data).map_err(|e| format!("ストリーム解析エラー: {}", e))?;
+
+    if let Some(message) = chunk["error"]["message"].as_str() {
+        return Err(message.to_string());
+    }
+    if let Some(prompt_tokens) = chunk["usage"]["prompt_tokens"].as_u64() {
+        usage.input_tokens = prompt_tokens;
+    }
+    if let Some(completion_tokens) = chunk["usage"]["completion_tokens"].as_u64() {
+        usage.output_tokens = completion_tokens;
+    }
+
+    Ok(chunk["choices"][0]["delta"]["content"]
+        .as_str()
+        .filter(|text| !text.is_empty())
+        .map(str::to_string))
+}
+
+impl AiProvider for OpenAiProvider {
+    fn kind(&self) -> AiProviderKind {
+        AiProviderKind::OpenAI
+    }
+
+    fn build_request(
+        &self,
+        client: &reqwest::Client,
+        api_key: &str,
+        model: &str,
+        request: &AiCompletionRequest,
+    ) -> reqwest::RequestBuilder {
+        client
+            .post(OPENAI_API_URL)
+            .bearer_auth(api_key)
+            .json(&chat_body(model, request, is_new_model_format(model)))
+    }
+
+    fn parse_event(
+        &self,
+        event: &SseEvent,
+        usage: &mut TokenUsage,
+    ) -> Result<Option<String>, String> {
+        parse_chat_chunk(event, usage)
+    }
+}
@@ -0,0 +1,144 @@
+// Server-Sent Events パーサー
+// チャンク境界が行の途中に来ても扱えるよう、未完成の行はバッファに残す
+
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct SseEvent {
+    pub event: Option<String>,
+    pub data: String,
+}
+
+#[derive(Default)]
+pub struct SseParser {
+    buffer: Vec<u8>,
+    event: Option<String>,
+    data: Vec<String>,
+}
+
+impl SseParser {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn push(&mut self, chunk: &[u8]) -> Vec<SseEvent> {
+        self.buffer.extend_from_slice(chunk);
+        let mut events = Vec::new();
+
+        while let Some(pos) = self.buffer.iter().position(|b| *b == b'\n') {
+            let line_bytes: Vec<u8> = self.buffer.drain(..=pos).collect();
+            let line = String::from_utf8_lossy(&line_bytes);
+            let line = line.trim_end_matches(['\n', '\r']);
+            if let Some(event) = self.process_line(line) {
+                events.push(event);
+            }
+        }
+
+        events
+    }
+
+    // ストリーム終了時に、空行で閉じられなかった最後のイベントを取り出す
+    pub fn finish(&mut self) -> Option<SseEvent> {
+        if !self.buffer.is_empty() {
+            let rest = std::mem::take(&mut self.buffer);
+            let line = String::from_utf8_lossy(&rest);
+            let line = line.trim_end_matches(['\n', '\r']).to_string();
+            if let Some(event) = self.process_line(&line) {
+                return Some(event);
+            }
+        }
+        self.dispatch()
+    }
+
+    fn process_line(&mut self, line: &str) -> Option<SseEvent> {
+        if line.is_empty() {
+            return self.dispatch();
+        }
+        if line.starts_with(':') {
+            return None;
+        }
+
+        let (field, value) = match line.find(':') {
+            Some(idx) => {
+                let value = &line[idx + 1..];
+                (&line[..idx], value.strip_prefix(' ').unwrap_or(value))
+            }
+            None => (line, ""),
+        };
+
+        match field {
+            "event" => self.event = Some(value.to_string()),
+            "data" => self.data.push(value.to_string()),
+            _ => {}
+        }
+        None
+    }
+
+    fn dispatch(&mut self) -> Option<SseEvent> {
+        if self.data.is_empty() {
+            self.event = None;
+            return None;
+        }
+        let event = SseEvent {
+            event: self.event.take(),
+            data: self.data.join("\n"),
+        };
+        self.data.clear();
+        Some(event)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn event(event: Option<&str>, data: &str) -> SseEvent {
+        SseEvent {
+            event: event.map(str::to_string),
+            data: data.to_string(),
+        }
+    }
+
+    #[test]
+    fn parses_events_split_across_chunks() {
+        let mut parser = SseParser::new();
+        assert!(parser.push(b"event: up").is_empty());
+        assert!(parser.push(b"date\r\ndata: {\"a\"").is_empty());
+        assert_eq!(
+            parser.push(b":1}\r\n\r\ndata: x\n"),
+            [event(Some("update"), "{\"a\":1}")]
+        );
+        assert_eq!(parser.push(b"\n"), [event(None, "x")]);
+    }
+
+    #[test]
+    fn joins_multiline_data_and_skips_comments() {
+        let mut parser = SseParser::new();
+        let events = parser.push(
+            b": comment\ndata: first\ndata:second\nid: 1\nretry: 10\n\nevent: empty\n\ndata\n\n",
+        );
+        // データのないイベントは捨て、イベント名も持ち越さない
+        assert_eq!(events, [event(None, "first\nsecond"), event(None, "")]);
+    }
+
+    #[test]
+    fn finishes_unterminated_events() {
+        let mut parser = SseParser::new();
+        assert!(parser.push(b"event: done\ndata: [DO").is_empty());
+        assert_eq!(parser.finish(), Some(event(Some("done"), "[DO")));
+        assert_eq!(parser.finish(), None);
+
+        let mut parser = SseParser::new();
+        parser.push(b"data: a\n");
+        assert_eq!(parser.finish(), Some(event(None, "a")));
+    }
+
+    #[test]
+    fn keeps_multibyte_characters_split_across_chunks() {
+        let bytes = "data: 日本語\n\n".as_bytes();
+        let mut parser = SseParser::new();
+        let mut events = Vec::new();
+        for byte in bytes {
+            events.extend(parser.push(std::slice::from_ref(byte)));
+        }
+        assert_eq!(events, [event(None, "日本語")]);
+    }
+}
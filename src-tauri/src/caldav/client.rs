@@ -0,0 +1,187 @@
+// CalDAV サーバーへのHTTPリクエスト (PROPFIND / REPORT / GET / PUT / DELETE)
+
+use reqwest::{Method, StatusCode, Url};
+
+use super::xml::{parse_multistatus, DavResponse};
+
+const DAV_NAMESPACES: &str = r#"xmlns:d="DAV:" xmlns:c="urn:ietf:params:xml:ns:caldav" xmlns:cs="http://calendarserver.org/ns/""#;
+// calendar-multiget で一度に取得する件数
+const MULTIGET_CHUNK: usize = 50;
+
+pub enum Precondition<'a> {
+    // サーバー側が最後に同期したときから変わっていない場合だけ上書きする
+    IfMatch(&'a str),
+    // 新規作成時は既存のリソースを上書きしない
+    IfNoneMatch,
+}
+
+pub enum WriteOutcome {
+    // 保存・削除できた (ETag が返されればそれも返す)
+    Done(Option<String>),
+    // If-Match / If-None-Match の条件に合わなかった (サーバー側で変更・作成済み)
+    PreconditionFailed,
+    // 対象がすでに存在しない
+    NotFound,
+}
+
+pub struct DavClient<'a> {
+    http: &'a reqwest::Client,
+    username: &'a str,
+    password: &'a str,
+}
+
+fn xml_escape(value: &str) -> String {
+    value
+        .replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+fn etag_header(response: &reqwest::Response) -> Option<String> {
+    response
+        .headers()
+        .get("ETag")
+        .and_then(|v| v.to_str().ok())
+        .map(str::to_string)
+}
+
+impl<'a> DavClient<'a> {
+    pub fn new(http: &'a reqwest::Client, username: &'a str, password: &'a str) -> Self {
+        Self {
+            http,
+            username,
+            password,
+        }
+    }
+
+    fn request(&self, method: Method, url: &Url) -> reqwest::RequestBuilder {
+        let builder = self.http.request(method, url.clone());
+        if self.username.is_empty() {
+            builder
+        } else {
+            builder.basic_auth(self.username, Some(self.password))
+        }
+    }
+
+    async fn multistatus(
+        &self,
+        method: &str,
+        url: &Url,
+        depth: &str,
+        body: String,
+    ) -> Result<Vec<DavResponse>, String> {
+        let method = Method::from_bytes(method.as_bytes()).map_err(|e| e.to_string())?;
+        let response = self
+            .request(method.clone(), url)
+            .header("Depth", depth)
+            .header("Content-Type", "application/xml; charset=utf-8")
+            .body(body)
+            .send()
+            .await
+            .map_err(|e| format!("CalDAVリクエストエラー: {}", e))?;
+
+        let status = response.status();
+        let text = response.text().await.unwrap_or_default();
+        if status == StatusCode::UNAUTHORIZED || status == StatusCode::FORBIDDEN {
+            return Err(format!("CalDAV認証エラー ({})", status));
+        }
+        if status != StatusCode::MULTI_STATUS && !status.is_success() {
+            return Err(format!("CalDAV {} 失敗 ({}): {}", method, status, text));
+        }
+        parse_multistatus(&text)
+    }
+
+    pub async fn propfind(
+        &self,
+        url: &Url,
+        depth: &str,
+        props: &str,
+    ) -> Result<Vec<DavResponse>, String> {
+        let body = format!(
+            r#"<?xml version="1.0" encoding="utf-8"?><d:propfind {}><d:prop>{}</d:prop></d:propfind>"#,
+            DAV_NAMESPACES, props
+        );
+        self.multistatus("PROPFIND", url, depth, body).await
+    }
+
+    // コレクション内の全VTODOの href と ETag
+    pub async fn list_todo_etags(&self, calendar: &Url) -> Result<Vec<DavResponse>, String> {
+        let body = format!(
+            r#"<?xml version="1.0" encoding="utf-8"?><c:calendar-query {}><d:prop><d:getetag/></d:prop><c:filter><c:comp-filter name="VCALENDAR"><c:comp-filter name="VTODO"/></c:comp-filter></c:filter></c:calendar-query>"#,
+            DAV_NAMESPACES
+        );
+        self.multistatus("REPORT", calendar, "1", body).await
+    }
+
+    pub async fn multiget(
+        &self,
+        calendar: &Url,
+        hrefs: &[String],
+    ) -> Result<Vec<DavResponse>, String> {
+        let mut responses = Vec::new();
+        for chunk in hrefs.chunks(MULTIGET_CHUNK) {
+            let hrefs: String = chunk
+                .iter()
+                .map(|href| format!("<d:href>{}</d:href>", xml_escape(href)))
+                .collect();
+            let body = format!(
+                r#"<?xml version="1.0" encoding="utf-8"?><c:calendar-multiget {}><d:prop><d:getetag/><c:calendar-data/></d:prop>{}</c:calendar-multiget>"#,
+                DAV_NAMESPACES, hrefs
+            );
+            responses.extend(self.multistatus("REPORT", calendar, "1", body).await?);
+        }
+        Ok(responses)
+    }
+
+    pub async fn put(
+        &self,
+        url: &Url,
+        calendar_data: String,
+        precondition: Precondition<'_>,
+    ) -> Result<WriteOutcome, String> {
+        let request = self
+            .request(Method::PUT, url)
+            .header("Content-Type", "text/calendar; charset=utf-8")
+            .body(calendar_data);
+        let request = match precondition {
+            Precondition::IfMatch(etag) => request.header("If-Match", etag),
+            Precondition::IfNoneMatch => request.header("If-None-Match", "*"),
+        };
+        let response = request
+            .send()
+            .await
+            .map_err(|e| format!("CalDAVリクエストエラー: {}", e))?;
+
+        match response.status() {
+            StatusCode::PRECONDITION_FAILED => Ok(WriteOutcome::PreconditionFailed),
+            StatusCode::NOT_FOUND => Ok(WriteOutcome::NotFound),
+            status if status.is_success() => Ok(WriteOutcome::Done(etag_header(&response))),
+            status => {
+                let body = response.text().await.unwrap_or_default();
+                Err(format!("CalDAV PUT 失敗 ({}): {}", status, body))
+            }
+        }
+    }
+
+    pub async fn delete(&self, url: &Url, if_match: Option<&str>) -> Result<WriteOutcome, String> {
+        let mut request = self.request(Method::DELETE, url);
+        if let Some(etag) = if_match {
+            request = request.header("If-Match", etag);
+        }
+        let response = request
+            .send()
+            .await
+            .map_err(|e| format!("CalDAVリクエストエラー: {}", e))?;
+
+        match response.status() {
+            StatusCode::PRECONDITION_FAILED => Ok(WriteOutcome::PreconditionFailed),
+            StatusCode::NOT_FOUND | StatusCode::GONE => Ok(WriteOutcome::NotFound),
+            status if status.is_success() => Ok(WriteOutcome::Done(None)),
+            status => {
+                let body = response.text().await.unwrap_or_default();
+                Err(format!("CalDAV DELETE 失敗 ({}): {}", status, body))
+            }
+        }
+    }
+}
@@ -0,0 +1,699 @@
+// CalDAV によるタスクの双方向同期
+// データの正はフロントエンドにあるため、同期で追加・更新・削除するタスクを返してフロントエンド側で反映する
+
+mod client;
+mod xml;
+
+#[cfg(test)]
+mod tests;
+
+use chrono::Local;
+use reqwest::Url;
+use std::collections::{HashMap, HashSet};
+use std::sync::Mutex;
+
+use crate::ics::{self, IcsItem, IcsItemKind};
+use crate::models::Todo;
+use client::{DavClient, Precondition, WriteOutcome};
+
+const SETTINGS_FILE: &str = "caldav-settings.json";
+const SYNC_STATE_FILE: &str = "caldav-sync-state.json";
+
+// プロジェクトと同期先カレンダーの対応 (project_id が None なら「プロジェクトなし」のタスク)
+#[derive(serde::Serialize, serde::Deserialize, Clone, Default)]
+#[serde(rename_all = "camelCase", default)]
+pub struct CaldavBinding {
+    pub project_id: Option<String>,
+    pub calendar_url: String,
+    pub display_name: String,
+    pub enabled: bool,
+}
+
+#[derive(serde::Serialize, serde::Deserialize, Clone, Default)]
+#[serde(rename_all = "camelCase", default)]
+pub struct CaldavSettings {
+    pub server_url: String,
+    pub username: String,
+    pub password: String,
+    pub bindings: Vec<CaldavBinding>,
+}
+
+#[derive(serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CaldavCalendar {
+    pub url: String,
+    pub display_name: String,
+    pub supports_todo: bool,
+}
+
+// 前回同期したときのタスクとリソースの対応
+#[derive(serde::Serialize, serde::Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+struct SyncedItem {
+    todo_id: String,
+    uid: String,
+    href: String,
+    etag: Option<String>,
+    // 同期時点のタスクの更新日時。これより新しければローカルで変更されたとみなす
+    local_modified: i64,
+    // ローカルのタスクとして存在したことがあるか
+    // (取り込んだだけでまだフロントエンドに反映されていないものは、ローカルで削除されたとみなさない)
+    #[serde(default)]
+    seen_locally: bool,
+}
+
+#[derive(serde::Serialize, serde::Deserialize, Clone, Default)]
+#[serde(rename_all = "camelCase", default)]
+struct CalendarSyncState {
+    items: Vec<SyncedItem>,
+    last_synced_at: Option<i64>,
+}
+
+#[derive(serde::Serialize, Clone, Copy)]
+#[serde(rename_all = "camelCase")]
+pub enum ConflictResolution {
+    KeptLocal,
+    KeptRemote,
+}
+
+#[derive(serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CaldavConflict {
+    pub todo_id: String,
+    pub text: String,
+    pub calendar_url: String,
+    pub resolution: ConflictResolution,
+    pub local_modified: i64,
+    pub remote_modified: Option<i64>,
+}
+
+#[derive(serde::Serialize, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct CaldavSyncReport {
+    // フロントエンドで追加または置き換えるタスク
+    pub upserted: Vec<Todo>,
+    // フロントエンドで削除するタスクのID
+    pub deleted_ids: Vec<String>,
+    pub conflicts: Vec<CaldavConflict>,
+    pub pushed: usize,
+    pub pulled: usize,
+    pub deleted_remote: usize,
+    pub errors: Vec<String>,
+}
+
+struct RemoteTodo {
+    etag: Option<String>,
+    item: IcsItem,
+}
+
+fn collection_url(url: &str) -> Result<Url, String> {
+    let url = if url.ends_with('/') {
+        url.to_string()
+    } else {
+        format!("{}/", url)
+    };
+    Url::parse(&url).map_err(|e| format!("URLが不正です ({}): {}", url, e))
+}
+
+// サーバーによって href が絶対URLだったりパスだったりするため、パスに揃えて比較する
+fn href_key(base: &Url, href: &str) -> Option<String> {
+    base.join(href.trim())
+        .ok()
+        .map(|url| url.path().to_string())
+}
+
+fn resource_name(uid: &str) -> String {
+    let name: String = uid
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || c == '-' || c == '_' || c == '.' {
+                c
+            } else {
+                '-'
+            }
+        })
+        .collect();
+    format!("{}.ics", name)
+}
+
+fn first_todo(calendar_data: &str) -> Option<IcsItem> {
+    ics::parse_ics(calendar_data)
+        .into_iter()
+        .find(|item| item.kind == IcsItemKind::Task)
+}
+
+// 1つのカレンダーとの同期処理
+struct CalendarSync<'a> {
+    dav: DavClient<'a>,
+    calendar: Url,
+    binding: &'a CaldavBinding,
+    all: &'a [Todo],
+    now: i64,
+    report: &'a mut CaldavSyncReport,
+}
+
+impl CalendarSync<'_> {
+    fn url(&self, href: &str) -> Result<Url, String> {
+        self.calendar
+            .join(href)
+            .map_err(|e| format!("URLが不正です ({}): {}", href, e))
+    }
+
+    async fn fetch(&self, hrefs: &[String]) -> Result<HashMap<String, RemoteTodo>, String> {
+        if hrefs.is_empty() {
+            return Ok(HashMap::new());
+        }
+        let responses = self.dav.multiget(&self.calendar, hrefs).await?;
+        Ok(responses
+            .into_iter()
+            .filter(|r| r.is_ok())
+            .filter_map(|r| {
+                let key = href_key(&self.calendar, &r.href)?;
+                let item = first_todo(r.calendar_data.as_deref()?)?;
+                Some((
+                    key,
+                    RemoteTodo {
+                        etag: r.etag.map(|e| e.trim().to_string()),
+                        item,
+                    },
+                ))
+            })
+            .collect())
+    }
+
+    fn pull(&mut self, todo: &Todo, remote: &RemoteTodo, entry: &mut SyncedItem) {
+        let mut updated = todo.clone();
+        ics::apply_item(&remote.item, &mut updated, self.now);
+        entry.etag = remote.etag.clone();
+        entry.local_modified = updated.last_modified();
+        self.report.pulled += 1;
+        self.report.upserted.push(updated);
+    }
+
+    async fn push(
+        &mut self,
+        todo: &Todo,
+        entry: &mut SyncedItem,
+        precondition: Precondition<'_>,
+    ) -> Result<WriteOutcome, String> {
+        let url = self.url(&entry.href)?;
+        let outcome = self
+            .dav
+            .put(&url, ics::export_todo(todo, self.all), precondition)
+            .await?;
+        if let WriteOutcome::Done(etag) = &outcome {
+            entry.etag = etag.clone();
+            entry.local_modified = todo.last_modified();
+            self.report.pushed += 1;
+        }
+        Ok(outcome)
+    }
+
+    // 両方で変更されていた場合は updatedAt と LAST-MODIFIED の新しい方を採用する
+    async fn resolve(
+        &mut self,
+        todo: &Todo,
+        remote: &RemoteTodo,
+        entry: &mut SyncedItem,
+        record_conflict: bool,
+    ) -> Result<(), String> {
+        let local_modified = todo.last_modified();
+        let keep_local = local_modified >= remote.item.last_modified.unwrap_or(0);
+        if keep_local {
+            let etag = remote.etag.clone().unwrap_or_else(|| "*".to_string());
+            if let WriteOutcome::PreconditionFailed =
+                self.push(todo, entry, Precondition::IfMatch(&etag)).await?
+            {
+                return Err(format!("「{}」はサーバー側で更新中です", todo.text));
+            }
+        } else {
+            self.pull(todo, remote, entry);
+        }
+
+        if record_conflict {
+            self.report.conflicts.push(CaldavConflict {
+                todo_id: todo.id.clone(),
+                text: todo.text.clone(),
+                calendar_url: self.binding.calendar_url.clone(),
+                resolution: if keep_local {
+                    ConflictResolution::KeptLocal
+                } else {
+                    ConflictResolution::KeptRemote
+                },
+                local_modified,
+                remote_modified: remote.item.last_modified,
+            });
+        }
+        Ok(())
+    }
+
+    async fn refetch(&self, href: &str) -> Result<RemoteTodo, String> {
+        self.fetch(&[href.to_string()])
+            .await?
+            .remove(href)
+            .ok_or_else(|| format!("リソースを取得できません: {}", href))
+    }
+
+    // 前回同期したタスクの変更を反映する。同期対象として残すなら Some を返す
+    async fn sync_known(
+        &mut self,
+        mut entry: SyncedItem,
+        local: Option<&Todo>,
+        remote_etag: Option<&Option<String>>,
+        fetched: &HashMap<String, RemoteTodo>,
+    ) -> Result<Option<SyncedItem>, String> {
+        let remote_changed = fetched.get(&entry.href);
+        if local.is_some() {
+            entry.seen_locally = true;
+        }
+        match (local, remote_etag) {
+            (Some(todo), Some(etag)) => {
+                // PUT 時に ETag が返されなかった場合は一覧の値を採用する
+                if entry.etag.is_none() {
+                    entry.etag = etag.clone();
+                }
+                let local_changed = todo.last_modified() > entry.local_modified;
+                match (local_changed, remote_changed) {
+                    (false, None) => {}
+                    (false, Some(remote)) => self.pull(todo, remote, &mut entry),
+                    (true, Some(remote)) => self.resolve(todo, remote, &mut entry, true).await?,
+                    (true, None) => {
+                        let etag = entry.etag.clone().unwrap_or_else(|| "*".to_string());
+                        match self
+                            .push(todo, &mut entry, Precondition::IfMatch(&etag))
+                            .await?
+                        {
+                            WriteOutcome::Done(_) => {}
+                            WriteOutcome::PreconditionFailed => {
+                                let remote = self.refetch(&entry.href).await?;
+                                self.resolve(todo, &remote, &mut entry, true).await?;
+                            }
+                            WriteOutcome::NotFound => {
+                                self.push(todo, &mut entry, Precondition::IfNoneMatch)
+                                    .await?;
+                            }
+                        }
+                    }
+                }
+                Ok(Some(entry))
+            }
+            (Some(todo), None) => {
+                // サーバー側で削除された。ローカルで変更されていれば作り直す
+                if todo.last_modified() > entry.local_modified {
+                    self.push(todo, &mut entry, Precondition::IfNoneMatch)
+                        .await?;
+                    Ok(Some(entry))
+                } else {
+                    self.report.deleted_ids.push(todo.id.clone());
+                    Ok(None)
+                }
+            }
+            (None, Some(_)) if !entry.seen_locally => {
+                // 取り込んだがまだ反映されていない。削除とはみなさず、もう一度返す
+                if let Some(remote) = remote_changed {
+                    let mut todo = ics::item_to_todo(&remote.item, self.now);
+                    todo.id = entry.todo_id.clone();
+                    todo.project_id = self.binding.project_id.clone();
+                    entry.etag = remote.etag.clone();
+                    entry.local_modified = todo.last_modified();
+                    self.report.upserted.push(todo);
+                }
+                Ok(Some(entry))
+            }
+            (None, Some(_)) => {
+                // ローカルで削除された。サーバー側でも変更されていればサーバーの内容で復元する
+                if let Some(remote) = remote_changed {
+                    let mut restored = ics::item_to_todo(&remote.item, self.now);
+                    restored.id = entry.todo_id.clone();
+                    restored.project_id = self.binding.project_id.clone();
+                    entry.etag = remote.etag.clone();
+                    entry.local_modified = restored.last_modified();
+                    self.report.conflicts.push(CaldavConflict {
+                        todo_id: restored.id.clone(),
+                        text: restored.text.clone(),
+                        calendar_url: self.binding.calendar_url.clone(),
+                        resolution: ConflictResolution::KeptRemote,
+                        local_modified: entry.local_modified,
+                        remote_modified: remote.item.last_modified,
+                    });
+                    self.report.pulled += 1;
+                    self.report.upserted.push(restored);
+                    entry.seen_locally = false;
+                    return Ok(Some(entry));
+                }
+                let url = self.url(&entry.href)?;
+                // 条件に合わず削除できなかった場合は、次回の同期でサーバー側の新規として取り込まれる
+                if let WriteOutcome::Done(_) | WriteOutcome::NotFound =
+                    self.dav.delete(&url, entry.etag.as_deref()).await?
+                {
+                    self.report.deleted_remote += 1;
+                }
+                Ok(None)
+            }
+            (None, None) => Ok(None),
+        }
+    }
+
+    async fn run(&mut self, state: &mut CalendarSyncState) -> Result<(), String> {
+        let locals: Vec<&Todo> = self
+            .all
+            .iter()
+            .filter(|t| !t.archived && t.project_id == self.binding.project_id)
+            .collect();
+
+        let calendar_path = self.calendar.path().to_string();
+        let remote: HashMap<String, Option<String>> = self
+            .dav
+            .list_todo_etags(&self.calendar)
+            .await?
+            .into_iter()
+            .filter(|r| r.is_ok())
+            .filter_map(|r| {
+                let key = href_key(&self.calendar, &r.href)?;
+                (key != calendar_path).then(|| (key, r.etag.map(|e| e.trim().to_string())))
+            })
+            .collect();
+
+        // 新しいリソースと、前回から ETag が変わったリソースだけ中身を取得する
+        // 取り込んだがまだ反映されていないものは、もう一度返すために取得する
+        let known: HashMap<&str, &SyncedItem> = state
+            .items
+            .iter()
+            .map(|item| (item.href.as_str(), item))
+            .collect();
+        let to_fetch: Vec<String> = remote
+            .iter()
+            .filter(|(href, etag)| match known.get(href.as_str()) {
+                None => true,
+                Some(item) => {
+                    (item.etag.is_some() && item.etag != **etag)
+                        || (!item.seen_locally && !locals.iter().any(|t| t.id == item.todo_id))
+                }
+            })
+            .map(|(href, _)| href.clone())
+            .collect();
+        let fetched = self.fetch(&to_fetch).await?;
+
+        let mut next_items = Vec::new();
+        let mut linked: HashSet<String> = HashSet::new();
+        let mut known_hrefs: HashSet<String> = HashSet::new();
+        for entry in std::mem::take(&mut state.items) {
+            let local = locals.iter().find(|t| t.id == entry.todo_id).copied();
+            let href = entry.href.clone();
+            let todo_id = entry.todo_id.clone();
+            known_hrefs.insert(href.clone());
+            match self
+                .sync_known(entry.clone(), local, remote.get(&href), &fetched)
+                .await
+            {
+                Ok(Some(entry)) => {
+                    linked.insert(entry.todo_id.clone());
+                    next_items.push(entry);
+                }
+                Ok(None) => {
+                    // 削除を返したタスクを新規として送り直さない
+                    linked.insert(todo_id);
+                }
+                Err(e) => {
+                    // 失敗したものは前回の状態のまま残し、次回の同期で再試行する
+                    self.report.errors.push(e);
+                    linked.insert(todo_id);
+                    next_items.push(entry);
+                }
+            }
+        }
+
+        // サーバー側で新しく作られたタスク
+        let mut new_remote: Vec<(&String, &RemoteTodo)> = fetched
+            .iter()
+            .filter(|(href, _)| !known_hrefs.contains(*href))
+            .collect();
+        new_remote.sort_by_key(|(href, _)| href.as_str());
+        for (href, remote) in new_remote {
+            let mut entry = SyncedItem {
+                todo_id: String::new(),
+                uid: remote.item.uid.clone(),
+                href: href.clone(),
+                etag: remote.etag.clone(),
+                local_modified: 0,
+                seen_locally: false,
+            };
+            // 以前にICSで取り込んだタスクなど、同じUIDのタスクがあれば新しい方に揃える
+            let existing = locals
+                .iter()
+                .find(|t| !linked.contains(&t.id) && ics::todo_uid(t) == remote.item.uid)
+                .copied();
+            if let Some(todo) = existing {
+                entry.todo_id = todo.id.clone();
+                entry.seen_locally = true;
+                if let Err(e) = self.resolve(todo, remote, &mut entry, false).await {
+                    self.report.errors.push(e);
+                    continue;
+                }
+            } else {
+                let mut todo = ics::item_to_todo(&remote.item, self.now);
+                let name = if remote.item.uid.is_empty() {
+                    href.as_str()
+                } else {
+                    remote.item.uid.as_str()
+                };
+                todo.id = format!("caldav-{}", resource_name(name).trim_end_matches(".ics"));
+                todo.project_id = self.binding.project_id.clone();
+                todo.parent_id = remote.item.related_to.as_ref().and_then(|parent| {
+                    self.all
+                        .iter()
+                        .find(|t| &ics::todo_uid(t) == parent)
+                        .map(|t| t.id.clone())
+                });
+                entry.todo_id = todo.id.clone();
+                entry.local_modified = todo.last_modified();
+                self.report.pulled += 1;
+                self.report.upserted.push(todo);
+            }
+            linked.insert(entry.todo_id.clone());
+            next_items.push(entry);
+        }
+
+        // ローカルで新しく作られたタスク
+        for todo in locals.iter().filter(|t| !linked.contains(&t.id)) {
+            let uid = ics::todo_uid(todo);
+            let href = format!("{}{}", calendar_path, resource_name(&uid));
+            let mut entry = SyncedItem {
+                todo_id: todo.id.clone(),
+                uid,
+                href,
+                etag: None,
+                local_modified: 0,
+                seen_locally: true,
+            };
+            let result = match self.push(todo, &mut entry, Precondition::IfNoneMatch).await {
+                Ok(WriteOutcome::PreconditionFailed) => {
+                    // 同じ名前のリソースがすでにある (同期状態が失われた場合など)
+                    match self.refetch(&entry.href).await {
+                        Ok(remote) => self.resolve(todo, &remote, &mut entry, false).await,
+                        Err(e) => Err(e),
+                    }
+                }
+                Ok(_) => Ok(()),
+                Err(e) => Err(e),
+            };
+            match result {
+                Ok(()) => next_items.push(entry),
+                Err(e) => self.report.errors.push(e),
+            }
+        }
+
+        state.items = next_items;
+        state.last_synced_at = Some(self.now);
+        Ok(())
+    }
+}
+
+pub struct CaldavState {
+    client: reqwest::Client,
+    settings: Mutex<CaldavSettings>,
+    sync_state: Mutex<HashMap<String, CalendarSyncState>>,
+    syncing: tokio::sync::Mutex<()>,
+}
+
+impl CaldavState {
+    pub fn load(app: &tauri::AppHandle) -> Self {
+        let settings = crate::store::load_json(app, SETTINGS_FILE).unwrap_or_else(|e| {
+            println!("[CalDAV Backend] 設定読み込み失敗: {}", e);
+            CaldavSettings::default()
+        });
+        let sync_state = crate::store::load_json(app, SYNC_STATE_FILE).unwrap_or_else(|e| {
+            println!("[CalDAV Backend] 同期状態の読み込み失敗: {}", e);
+            HashMap::new()
+        });
+        Self {
+            client: reqwest::Client::new(),
+            settings: Mutex::new(settings),
+            sync_state: Mutex::new(sync_state),
+            syncing: tokio::sync::Mutex::new(()),
+        }
+    }
+
+    fn settings(&self) -> CaldavSettings {
+        self.settings.lock().unwrap().clone()
+    }
+
+    fn save_sync_state(&self, app: &tauri::AppHandle) {
+        let sync_state = self.sync_state.lock().unwrap();
+        if let Err(e) = crate::store::save_json(app, SYNC_STATE_FILE, &*sync_state) {
+            println!("[CalDAV Backend] 同期状態の保存失敗: {}", e);
+        }
+    }
+}
+
+// current-user-principal → calendar-home-set → カレンダー一覧 の順にたどる
+async fn discover(dav: &DavClient<'_>, server_url: &str) -> Result<Vec<CaldavCalendar>, String> {
+    let server = collection_url(server_url)?;
+    let principal_prop = "<d:current-user-principal/>";
+
+    let mut principal = None;
+    for candidate in [
+        server.clone(),
+        server.join("/.well-known/caldav").unwrap_or(server.clone()),
+    ] {
+        if let Ok(responses) = dav.propfind(&candidate, "0", principal_prop).await {
+            principal = responses
+                .into_iter()
+                .find_map(|r| r.principal_href)
+                .and_then(|href| candidate.join(href.trim()).ok());
+            if principal.is_some() {
+                break;
+            }
+        }
+    }
+    let principal = principal.unwrap_or_else(|| server.clone());
+
+    let home = dav
+        .propfind(&principal, "0", "<c:calendar-home-set/>")
+        .await?
+        .into_iter()
+        .find_map(|r| r.home_set_href)
+        .and_then(|href| principal.join(href.trim()).ok())
+        .unwrap_or(principal);
+
+    let responses = dav
+        .propfind(
+            &home,
+            "1",
+            "<d:resourcetype/><d:displayname/><c:supported-calendar-component-set/>",
+        )
+        .await?;
+    Ok(responses
+        .into_iter()
+        .filter(|r| r.is_calendar)
+        .filter_map(|r| {
+            let url = home.join(r.href.trim()).ok()?;
+            Some(CaldavCalendar {
+                display_name: r
+                    .display_name
+                    .map(|n| n.trim().to_string())
+                    .filter(|n| !n.is_empty())
+                    .unwrap_or_else(|| url.path().to_string()),
+                url: url.to_string(),
+                // 対応コンポーネントの指定がなければすべて対応とみなす
+                supports_todo: r.components.is_empty() || r.components.iter().any(|c| c == "VTODO"),
+            })
+        })
+        .collect())
+}
+
+#[tauri::command]
+pub fn caldav_get_settings(state: tauri::State<'_, CaldavState>) -> CaldavSettings {
+    state.settings()
+}
+
+#[tauri::command]
+pub fn caldav_set_settings(
+    app: tauri::AppHandle,
+    state: tauri::State<'_, CaldavState>,
+    settings: CaldavSettings,
+) -> Result<(), String> {
+    for binding in &settings.bindings {
+        collection_url(&binding.calendar_url)?;
+    }
+    // 紐付けを外したカレンダーの同期状態は破棄する
+    state
+        .sync_state
+        .lock()
+        .unwrap()
+        .retain(|url, _| settings.bindings.iter().any(|b| &b.calendar_url == url));
+    state.save_sync_state(&app);
+
+    let mut current = state.settings.lock().unwrap();
+    *current = settings;
+    crate::store::save_json(&app, SETTINGS_FILE, &*current)
+}
+
+#[tauri::command]
+pub async fn caldav_discover(
+    state: tauri::State<'_, CaldavState>,
+) -> Result<Vec<CaldavCalendar>, String> {
+    let settings = state.settings();
+    if settings.server_url.trim().is_empty() {
+        return Err("CalDAVサーバーのURLを入力してください".to_string());
+    }
+    let dav = DavClient::new(&state.client, &settings.username, &settings.password);
+    discover(&dav, settings.server_url.trim()).await
+}
+
+// 同期結果はフロントエンドでタスクに反映し、sync_todos で送り返してもらう
+#[tauri::command]
+pub async fn caldav_sync(
+    app: tauri::AppHandle,
+    state: tauri::State<'_, CaldavState>,
+    todos: tauri::State<'_, crate::todos::TodoState>,
+) -> Result<CaldavSyncReport, String> {
+    let _guard = state
+        .syncing
+        .try_lock()
+        .map_err(|_| "同期中です".to_string())?;
+    let settings = state.settings();
+    let all = todos.all();
+    let now = Local::now().timestamp_millis();
+    let mut report = CaldavSyncReport::default();
+
+    for binding in settings.bindings.iter().filter(|b| b.enabled) {
+        let calendar = match collection_url(&binding.calendar_url) {
+            Ok(calendar) => calendar,
+            Err(e) => {
+                report.errors.push(e);
+                continue;
+            }
+        };
+        let mut calendar_state = state
+            .sync_state
+            .lock()
+            .unwrap()
+            .get(&binding.calendar_url)
+            .cloned()
+            .unwrap_or_default();
+
+        let mut sync = CalendarSync {
+            dav: DavClient::new(&state.client, &settings.username, &settings.password),
+            calendar,
+            binding,
+            all: &all,
+            now,
+            report: &mut report,
+        };
+        match sync.run(&mut calendar_state).await {
+            Ok(()) => {
+                state
+                    .sync_state
+                    .lock()
+                    .unwrap()
+                    .insert(binding.calendar_url.clone(), calendar_state);
+                state.save_sync_state(&app);
+            }
+            Err(e) => report
+                .errors
+                .push(format!("{}: {}", binding.display_name, e)),
+        }
+    }
+    Ok(report)
+}
@@ -0,0 +1,737 @@
+// CalDAV サーバーを相手にした同期のテスト
+// 通常はプロセス内の簡易サーバーを相手にする。実際のサーバー (Radicale) で確かめるときは次のように起動してから実行する
+//   python -m radicale --storage-filesystem-folder /tmp/radicale --auth-type none
+//   CALDAV_TEST_URL=http://127.0.0.1:5232/test/ cargo test caldav
+
+use super::*;
+use reqwest::Method;
+use std::collections::BTreeMap;
+use std::io::{BufRead, BufReader, Read, Write};
+use std::net::{TcpListener, TcpStream};
+use std::sync::Arc;
+
+const T0: i64 = 1_767_225_600_000; // 2026-01-01T00:00:00Z
+
+fn todo(id: &str, text: &str, updated_at: i64) -> Todo {
+    Todo {
+        id: id.to_string(),
+        text: text.to_string(),
+        created_at: T0,
+        updated_at: Some(updated_at),
+        ..Default::default()
+    }
+}
+
+fn vtodo(uid: &str, summary: &str, last_modified: &str) -> String {
+    format!(
+        "BEGIN:VCALENDAR\r\nVERSION:2.0\r\nPRODID:-//calm-todo test//EN\r\nBEGIN:VTODO\r\n\
+         UID:{uid}\r\nDTSTAMP:{last_modified}\r\nLAST-MODIFIED:{last_modified}\r\n\
+         SUMMARY:{summary}\r\nSTATUS:NEEDS-ACTION\r\nEND:VTODO\r\nEND:VCALENDAR\r\n"
+    )
+}
+
+// テスト用の簡易CalDAVサーバー (同期で使うメソッドだけを実装する)
+#[derive(Default)]
+struct MockDav {
+    // パス → (ETag, 内容)
+    resources: Mutex<BTreeMap<String, (String, String)>>,
+    next_etag: Mutex<u64>,
+    // 次の PUT に返すステータス (同期中の競合や削除を再現する)
+    put_override: Mutex<Option<u16>>,
+}
+
+struct MockResponse {
+    status: u16,
+    etag: Option<String>,
+    body: String,
+}
+
+impl MockResponse {
+    fn status(status: u16) -> Self {
+        Self {
+            status,
+            etag: None,
+            body: String::new(),
+        }
+    }
+}
+
+fn xml_text(value: &str) -> String {
+    value
+        .replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+}
+
+impl MockDav {
+    fn start() -> (Arc<Self>, String) {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}/", listener.local_addr().unwrap());
+        let server = Arc::new(Self::default());
+        let shared = server.clone();
+        std::thread::spawn(move || {
+            for stream in listener.incoming().flatten() {
+                let server = shared.clone();
+                std::thread::spawn(move || server.serve(stream));
+            }
+        });
+        (server, url)
+    }
+
+    fn serve(&self, stream: TcpStream) {
+        let mut reader = BufReader::new(stream.try_clone().unwrap());
+        let mut writer = stream;
+        loop {
+            let mut request_line = String::new();
+            if reader.read_line(&mut request_line).unwrap_or(0) == 0 {
+                return;
+            }
+            let mut parts = request_line.split_whitespace();
+            let method = parts.next().unwrap_or_default().to_string();
+            let path = parts.next().unwrap_or_default().to_string();
+            let mut headers = HashMap::new();
+            loop {
+                let mut line = String::new();
+                reader.read_line(&mut line).unwrap();
+                let line = line.trim_end();
+                if line.is_empty() {
+                    break;
+                }
+                if let Some((name, value)) = line.split_once(':') {
+                    headers.insert(name.to_ascii_lowercase(), value.trim().to_string());
+                }
+            }
+            let length: usize = headers
+                .get("content-length")
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(0);
+            let mut body = vec![0; length];
+            reader.read_exact(&mut body).unwrap();
+            let body = String::from_utf8(body).unwrap();
+
+            let response = self.handle(&method, &path, &headers, &body);
+            let mut head = format!(
+                "HTTP/1.1 {} X\r\nContent-Length: {}\r\n",
+                response.status,
+                response.body.len()
+            );
+            if response.status == 207 {
+                head.push_str("Content-Type: application/xml; charset=utf-8\r\n");
+            }
+            if let Some(etag) = &response.etag {
+                head.push_str(&format!("ETag: {}\r\n", etag));
+            }
+            head.push_str("\r\n");
+            if writer.write_all(head.as_bytes()).is_err()
+                || writer.write_all(response.body.as_bytes()).is_err()
+            {
+                return;
+            }
+        }
+    }
+
+    fn handle(
+        &self,
+        method: &str,
+        path: &str,
+        headers: &HashMap<String, String>,
+        body: &str,
+    ) -> MockResponse {
+        let mut resources = self.resources.lock().unwrap();
+        match method {
+            "MKCALENDAR" => MockResponse::status(201),
+            "REPORT" if body.contains("calendar-multiget") => {
+                let mut xml = String::new();
+                for href in body.split("<d:href>").skip(1) {
+                    let href = href
+                        .split("</d:href>")
+                        .next()
+                        .unwrap()
+                        .replace("&amp;", "&");
+                    match resources.get(&href) {
+                        Some((etag, data)) => xml.push_str(&format!(
+                            "<D:response><D:href>{}</D:href><D:propstat><D:prop><D:getetag>{}</D:getetag>\
+                             <C:calendar-data>{}</C:calendar-data></D:prop>\
+                             <D:status>HTTP/1.1 200 OK</D:status></D:propstat></D:response>",
+                            xml_text(&href),
+                            xml_text(etag),
+                            xml_text(data)
+                        )),
+                        None => xml.push_str(&format!(
+                            "<D:response><D:href>{}</D:href><D:status>HTTP/1.1 404 Not Found</D:status></D:response>",
+                            xml_text(&href)
+                        )),
+                    }
+                }
+                multistatus(xml)
+            }
+            "REPORT" => {
+                let xml = resources
+                    .iter()
+                    .filter(|(href, _)| href.starts_with(path))
+                    .map(|(href, (etag, _))| {
+                        format!(
+                            "<D:response><D:href>{}</D:href><D:propstat><D:prop>\
+                             <D:getetag>{}</D:getetag></D:prop>\
+                             <D:status>HTTP/1.1 200 OK</D:status></D:propstat></D:response>",
+                            xml_text(href),
+                            xml_text(etag)
+                        )
+                    })
+                    .collect();
+                multistatus(xml)
+            }
+            "GET" => match resources.get(path) {
+                Some((etag, data)) => MockResponse {
+                    status: 200,
+                    etag: Some(etag.clone()),
+                    body: data.clone(),
+                },
+                None => MockResponse::status(404),
+            },
+            "PUT" => {
+                if let Some(status) = self.put_override.lock().unwrap().take() {
+                    // 404 は一覧の取得後に別のクライアントが削除した場合を再現する
+                    if status == 404 {
+                        resources.remove(path);
+                    }
+                    return MockResponse::status(status);
+                }
+                let existing = resources.get(path).map(|(etag, _)| etag.as_str());
+                if let Some(expected) = headers.get("if-match") {
+                    if existing.is_none_or(|etag| expected != "*" && etag != expected) {
+                        return MockResponse::status(412);
+                    }
+                }
+                if headers.contains_key("if-none-match") && existing.is_some() {
+                    return MockResponse::status(412);
+                }
+                let etag = {
+                    let mut next = self.next_etag.lock().unwrap();
+                    *next += 1;
+                    format!("\"{}\"", next)
+                };
+                resources.insert(path.to_string(), (etag.clone(), body.to_string()));
+                MockResponse {
+                    status: 201,
+                    etag: Some(etag),
+                    body: String::new(),
+                }
+            }
+            "DELETE" if path.ends_with('/') => {
+                resources.retain(|href, _| !href.starts_with(path));
+                MockResponse::status(204)
+            }
+            "DELETE" => match resources.get(path) {
+                None => MockResponse::status(404),
+                Some((etag, _))
+                    if headers
+                        .get("if-match")
+                        .is_some_and(|expected| expected != etag) =>
+                {
+                    MockResponse::status(412)
+                }
+                Some(_) => {
+                    resources.remove(path);
+                    MockResponse::status(204)
+                }
+            },
+            _ => MockResponse::status(405),
+        }
+    }
+}
+
+fn multistatus(responses: String) -> MockResponse {
+    MockResponse {
+        status: 207,
+        etag: None,
+        body: format!(
+            r#"<?xml version="1.0" encoding="utf-8"?><D:multistatus xmlns:D="DAV:" xmlns:C="urn:ietf:params:xml:ns:caldav">{}</D:multistatus>"#,
+            responses
+        ),
+    }
+}
+
+// テストごとに作る空のカレンダー
+struct TestCalendar {
+    http: reqwest::Client,
+    url: Url,
+    // CALDAV_TEST_URL がなければプロセス内のサーバーを使う
+    mock: Option<Arc<MockDav>>,
+}
+
+impl TestCalendar {
+    async fn create(name: &str) -> Self {
+        match std::env::var("CALDAV_TEST_URL") {
+            Ok(base) => Self::create_at(&base, name, None).await,
+            Err(_) => Self::create_mock(name).await,
+        }
+    }
+
+    // サーバーの応答を操作するテスト用 (常にプロセス内のサーバーを使う)
+    async fn create_mock(name: &str) -> Self {
+        let (mock, base) = MockDav::start();
+        Self::create_at(&base, name, Some(mock)).await
+    }
+
+    fn fail_next_put(&self, status: u16) {
+        *self
+            .mock
+            .as_ref()
+            .expect("プロセス内のサーバーでのみ使えます")
+            .put_override
+            .lock()
+            .unwrap() = Some(status);
+    }
+
+    async fn create_at(base: &str, name: &str, mock: Option<Arc<MockDav>>) -> Self {
+        let http = reqwest::Client::new();
+        let url = collection_url(base)
+            .unwrap()
+            .join(&format!("{}-{}/", name, Local::now().timestamp_millis()))
+            .unwrap();
+        let body = r#"<?xml version="1.0" encoding="utf-8"?>
+<c:mkcalendar xmlns:d="DAV:" xmlns:c="urn:ietf:params:xml:ns:caldav">
+  <d:set><d:prop>
+    <c:supported-calendar-component-set><c:comp name="VTODO"/></c:supported-calendar-component-set>
+  </d:prop></d:set>
+</c:mkcalendar>"#;
+        let response = http
+            .request(Method::from_bytes(b"MKCALENDAR").unwrap(), url.clone())
+            .body(body)
+            .send()
+            .await
+            .expect("MKCALENDAR");
+        assert!(
+            response.status().is_success(),
+            "MKCALENDAR 失敗: {}",
+            response.status()
+        );
+        Self { http, url, mock }
+    }
+
+    async fn sync(
+        &self,
+        todos: &[Todo],
+        state: &mut CalendarSyncState,
+        now: i64,
+    ) -> CaldavSyncReport {
+        let binding = CaldavBinding {
+            project_id: None,
+            calendar_url: self.url.to_string(),
+            display_name: "test".to_string(),
+            enabled: true,
+        };
+        let mut report = CaldavSyncReport::default();
+        CalendarSync {
+            dav: DavClient::new(&self.http, "", ""),
+            calendar: self.url.clone(),
+            binding: &binding,
+            all: todos,
+            now,
+            report: &mut report,
+        }
+        .run(state)
+        .await
+        .expect("同期失敗");
+        assert!(report.errors.is_empty(), "{:?}", report.errors);
+        report
+    }
+
+    fn resource(&self, state: &CalendarSyncState, todo_id: &str) -> Url {
+        let item = state
+            .items
+            .iter()
+            .find(|item| item.todo_id == todo_id)
+            .expect("同期状態にタスクがありません");
+        self.url.join(&item.href).unwrap()
+    }
+
+    // 別のクライアントからの変更 (条件なしで上書き)
+    async fn put_remote(&self, url: &Url, body: String) {
+        let response = self
+            .http
+            .put(url.clone())
+            .header("Content-Type", "text/calendar; charset=utf-8")
+            .body(body)
+            .send()
+            .await
+            .expect("PUT");
+        assert!(
+            response.status().is_success(),
+            "PUT 失敗: {}",
+            response.status()
+        );
+    }
+
+    async fn get(&self, url: &Url) -> String {
+        self.http
+            .get(url.clone())
+            .send()
+            .await
+            .expect("GET")
+            .text()
+            .await
+            .unwrap()
+    }
+
+    async fn resource_count(&self) -> usize {
+        let dav = DavClient::new(&self.http, "", "");
+        let calendar_path = self.url.path().to_string();
+        dav.list_todo_etags(&self.url)
+            .await
+            .unwrap()
+            .into_iter()
+            .filter(|r| r.is_ok())
+            .filter(|r| href_key(&self.url, &r.href).is_some_and(|key| key != calendar_path))
+            .count()
+    }
+
+    async fn delete(self) {
+        let _ = self.http.delete(self.url.clone()).send().await;
+    }
+}
+
+#[tokio::test]
+async fn pushes_new_and_locally_edited_tasks() {
+    let calendar = TestCalendar::create("push").await;
+    let mut state = CalendarSyncState::default();
+
+    let mut a = todo("a", "資料を作る", T0);
+    let report = calendar
+        .sync(std::slice::from_ref(&a), &mut state, T0)
+        .await;
+    assert_eq!(report.pushed, 1);
+    assert_eq!(calendar.resource_count().await, 1);
+
+    // 変更がなければ何も送らない
+    let report = calendar
+        .sync(std::slice::from_ref(&a), &mut state, T0 + 1)
+        .await;
+    assert_eq!((report.pushed, report.pulled), (0, 0));
+
+    // 編集で updatedAt が進んだタスクは送り直す
+    a.text = "資料を直す".to_string();
+    a.updated_at = Some(T0 + 60_000);
+    let report = calendar
+        .sync(std::slice::from_ref(&a), &mut state, T0 + 60_000)
+        .await;
+    assert_eq!((report.pushed, report.pulled), (1, 0));
+    let body = calendar.get(&calendar.resource(&state, "a")).await;
+    assert!(body.contains("SUMMARY:資料を直す"), "{}", body);
+
+    calendar.delete().await;
+}
+
+#[tokio::test]
+async fn pulls_remote_changes() {
+    let calendar = TestCalendar::create("pull").await;
+    let mut state = CalendarSyncState::default();
+    let a = todo("a", "資料を作る", T0);
+    calendar
+        .sync(std::slice::from_ref(&a), &mut state, T0)
+        .await;
+
+    let url = calendar.resource(&state, "a");
+    calendar
+        .put_remote(
+            &url,
+            vtodo(&ics::todo_uid(&a), "サーバーで変更", "20260102T000000Z"),
+        )
+        .await;
+    let report = calendar
+        .sync(std::slice::from_ref(&a), &mut state, T0 + 1)
+        .await;
+    assert_eq!((report.pushed, report.pulled), (0, 1));
+    assert!(report.conflicts.is_empty());
+    assert_eq!(report.upserted[0].id, "a");
+    assert_eq!(report.upserted[0].text, "サーバーで変更");
+
+    calendar.delete().await;
+}
+
+#[tokio::test]
+async fn resolves_conflicts_by_modification_time() {
+    let calendar = TestCalendar::create("conflict").await;
+    let mut state = CalendarSyncState::default();
+    let a = todo("a", "ローカル", T0);
+    let b = todo("b", "ローカル", T0);
+    calendar.sync(&[a.clone(), b.clone()], &mut state, T0).await;
+
+    // a はサーバーの方が新しく、b はローカルの方が新しい
+    let remote_a = calendar.resource(&state, "a");
+    let remote_b = calendar.resource(&state, "b");
+    calendar
+        .put_remote(
+            &remote_a,
+            vtodo(&ics::todo_uid(&a), "サーバーA", "20260103T000000Z"),
+        )
+        .await;
+    calendar
+        .put_remote(
+            &remote_b,
+            vtodo(&ics::todo_uid(&b), "サーバーB", "20260102T000000Z"),
+        )
+        .await;
+    let mut a = a;
+    a.text = "ローカルA".to_string();
+    a.updated_at = Some(T0 + 86_400_000); // 2026-01-02
+    let mut b = b;
+    b.text = "ローカルB".to_string();
+    b.updated_at = Some(T0 + 3 * 86_400_000); // 2026-01-04
+
+    let report = calendar
+        .sync(&[a.clone(), b.clone()], &mut state, T0 + 4 * 86_400_000)
+        .await;
+    assert_eq!(report.conflicts.len(), 2);
+    let resolution = |id: &str| {
+        report
+            .conflicts
+            .iter()
+            .find(|c| c.todo_id == id)
+            .map(|c| c.resolution)
+    };
+    assert!(matches!(
+        resolution("a"),
+        Some(ConflictResolution::KeptRemote)
+    ));
+    assert!(matches!(
+        resolution("b"),
+        Some(ConflictResolution::KeptLocal)
+    ));
+    assert_eq!(report.upserted.len(), 1);
+    assert_eq!(report.upserted[0].text, "サーバーA");
+    assert!(calendar.get(&remote_b).await.contains("SUMMARY:ローカルB"));
+
+    calendar.delete().await;
+}
+
+#[tokio::test]
+async fn propagates_deletions_both_ways() {
+    let calendar = TestCalendar::create("delete").await;
+    let mut state = CalendarSyncState::default();
+    let a = todo("a", "ローカルで消す", T0);
+    let b = todo("b", "サーバーで消す", T0);
+    calendar.sync(&[a.clone(), b.clone()], &mut state, T0).await;
+    assert_eq!(calendar.resource_count().await, 2);
+
+    // ローカルで削除 → サーバーからも削除
+    let report = calendar
+        .sync(std::slice::from_ref(&b), &mut state, T0 + 1)
+        .await;
+    assert_eq!(report.deleted_remote, 1);
+    assert_eq!(calendar.resource_count().await, 1);
+
+    // サーバーで削除 → ローカルからも削除
+    let remote_b = calendar.resource(&state, "b");
+    calendar.http.delete(remote_b).send().await.unwrap();
+    let report = calendar
+        .sync(std::slice::from_ref(&b), &mut state, T0 + 2)
+        .await;
+    assert_eq!(report.deleted_ids, vec!["b".to_string()]);
+    assert!(state.items.is_empty());
+
+    calendar.delete().await;
+}
+
+#[tokio::test]
+async fn keeps_pulled_tasks_until_they_appear_locally() {
+    let calendar = TestCalendar::create("pending").await;
+    let mut state = CalendarSyncState::default();
+    let url = calendar.url.join("remote.ics").unwrap();
+    calendar
+        .put_remote(
+            &url,
+            vtodo("remote-uid", "サーバーで作成", "20260102T000000Z"),
+        )
+        .await;
+
+    let report = calendar.sync(&[], &mut state, T0).await;
+    assert_eq!(report.pulled, 1);
+    let pulled = report.upserted[0].clone();
+    assert_eq!(pulled.text, "サーバーで作成");
+
+    // フロントエンドに反映される前の同期では削除せず、もう一度返す
+    let report = calendar.sync(&[], &mut state, T0 + 1).await;
+    assert_eq!(report.deleted_remote, 0);
+    assert_eq!(report.upserted.len(), 1);
+    assert_eq!(report.upserted[0].id, pulled.id);
+    assert_eq!(calendar.resource_count().await, 1);
+
+    // 反映されたあとは何も返さない
+    let report = calendar
+        .sync(std::slice::from_ref(&pulled), &mut state, T0 + 2)
+        .await;
+    assert!(report.upserted.is_empty());
+    assert_eq!(report.pushed, 0);
+
+    // 反映されたあとで消えたものはローカルでの削除としてサーバーからも消す
+    let report = calendar.sync(&[], &mut state, T0 + 3).await;
+    assert_eq!(report.deleted_remote, 1);
+    assert_eq!(calendar.resource_count().await, 0);
+    assert!(state.items.is_empty());
+
+    calendar.delete().await;
+}
+
+#[tokio::test]
+async fn recreates_remotely_deleted_tasks_with_local_edits() {
+    let calendar = TestCalendar::create("recreate").await;
+    let mut state = CalendarSyncState::default();
+    let mut a = todo("a", "ローカル", T0);
+    calendar
+        .sync(std::slice::from_ref(&a), &mut state, T0)
+        .await;
+
+    let remote_a = calendar.resource(&state, "a");
+    calendar.http.delete(remote_a.clone()).send().await.unwrap();
+    a.text = "ローカルで編集".to_string();
+    a.updated_at = Some(T0 + 60_000);
+    let report = calendar
+        .sync(std::slice::from_ref(&a), &mut state, T0 + 60_000)
+        .await;
+    assert_eq!(report.pushed, 1);
+    assert!(report.deleted_ids.is_empty());
+    assert!(calendar
+        .get(&remote_a)
+        .await
+        .contains("SUMMARY:ローカルで編集"));
+
+    calendar.delete().await;
+}
+
+#[tokio::test]
+async fn restores_locally_deleted_tasks_changed_on_the_server() {
+    let calendar = TestCalendar::create("restore").await;
+    let mut state = CalendarSyncState::default();
+    let a = todo("a", "ローカル", T0);
+    calendar
+        .sync(std::slice::from_ref(&a), &mut state, T0)
+        .await;
+
+    let remote_a = calendar.resource(&state, "a");
+    calendar
+        .put_remote(
+            &remote_a,
+            vtodo(&ics::todo_uid(&a), "サーバーで変更", "20260102T000000Z"),
+        )
+        .await;
+    let report = calendar.sync(&[], &mut state, T0 + 86_400_000).await;
+    assert_eq!(report.deleted_remote, 0);
+    assert_eq!(report.upserted.len(), 1);
+    assert_eq!(report.upserted[0].id, "a");
+    assert!(matches!(
+        report.conflicts.as_slice(),
+        [CaldavConflict {
+            resolution: ConflictResolution::KeptRemote,
+            ..
+        }]
+    ));
+
+    // 復元したものが反映されるまでは削除しない
+    let report = calendar.sync(&[], &mut state, T0 + 86_400_001).await;
+    assert_eq!(report.deleted_remote, 0);
+    assert_eq!(calendar.resource_count().await, 1);
+
+    calendar.delete().await;
+}
+
+#[tokio::test]
+async fn resolves_when_the_push_precondition_fails() {
+    let calendar = TestCalendar::create_mock("precondition").await;
+    let mut state = CalendarSyncState::default();
+    let mut a = todo("a", "ローカル", T0);
+    calendar
+        .sync(std::slice::from_ref(&a), &mut state, T0)
+        .await;
+
+    // 一覧の取得後に別のクライアントが更新した
+    calendar.fail_next_put(412);
+    a.text = "ローカルで編集".to_string();
+    a.updated_at = Some(T0 + 86_400_000);
+    let report = calendar
+        .sync(std::slice::from_ref(&a), &mut state, T0 + 86_400_000)
+        .await;
+    assert!(matches!(
+        report.conflicts.as_slice(),
+        [CaldavConflict {
+            resolution: ConflictResolution::KeptLocal,
+            ..
+        }]
+    ));
+    assert_eq!(report.pushed, 1);
+    let body = calendar.get(&calendar.resource(&state, "a")).await;
+    assert!(body.contains("SUMMARY:ローカルで編集"), "{}", body);
+
+    calendar.delete().await;
+}
+
+#[tokio::test]
+async fn recreates_tasks_deleted_during_the_push() {
+    let calendar = TestCalendar::create_mock("gone").await;
+    let mut state = CalendarSyncState::default();
+    let mut a = todo("a", "ローカル", T0);
+    calendar
+        .sync(std::slice::from_ref(&a), &mut state, T0)
+        .await;
+
+    calendar.fail_next_put(404);
+    a.text = "ローカルで編集".to_string();
+    a.updated_at = Some(T0 + 60_000);
+    let report = calendar
+        .sync(std::slice::from_ref(&a), &mut state, T0 + 60_000)
+        .await;
+    assert_eq!(report.pushed, 1);
+    assert_eq!(calendar.resource_count().await, 1);
+    let body = calendar.get(&calendar.resource(&state, "a")).await;
+    assert!(body.contains("SUMMARY:ローカルで編集"), "{}", body);
+
+    calendar.delete().await;
+}
+
+#[tokio::test]
+async fn links_existing_tasks_by_uid() {
+    let calendar = TestCalendar::create("link").await;
+    let mut state = CalendarSyncState::default();
+    let a = todo("a", "ICSで取り込んだ", T0);
+    let url = calendar.url.join("other-name.ics").unwrap();
+    calendar
+        .put_remote(
+            &url,
+            vtodo(&ics::todo_uid(&a), "サーバーで変更", "20260102T000000Z"),
+        )
+        .await;
+
+    let report = calendar
+        .sync(std::slice::from_ref(&a), &mut state, T0 + 86_400_000)
+        .await;
+    assert_eq!(report.pushed, 0);
+    assert!(report.conflicts.is_empty());
+    assert_eq!(report.upserted.len(), 1);
+    assert_eq!(report.upserted[0].id, "a");
+    assert_eq!(report.upserted[0].text, "サーバーで変更");
+    assert_eq!(calendar.resource_count().await, 1);
+    assert_eq!(calendar.resource(&state, "a"), url);
+
+    calendar.delete().await;
+}
+
+#[tokio::test]
+async fn drops_tasks_deleted_on_both_sides() {
+    let calendar = TestCalendar::create("both").await;
+    let mut state = CalendarSyncState::default();
+    let a = todo("a", "両方で消す", T0);
+    calendar
+        .sync(std::slice::from_ref(&a), &mut state, T0)
+        .await;
+
+    let remote_a = calendar.resource(&state, "a");
+    calendar.http.delete(remote_a).send().await.unwrap();
+    let report = calendar.sync(&[], &mut state, T0 + 1).await;
+    assert_eq!(report.deleted_remote, 0);
+    assert!(report.deleted_ids.is_empty());
+    assert!(report.upserted.is_empty());
+    assert!(state.items.is_empty());
+
+    calendar.delete().await;
+}
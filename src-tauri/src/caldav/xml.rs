@@ -0,0 +1,250 @@
+// WebDAV の multistatus レスポンスの解析
+// 名前空間の接頭辞はサーバーごとに異なるため、要素のローカル名だけで判定する
+
+use quick_xml::events::Event;
+use quick_xml::Reader;
+
+#[derive(Debug, Default, Clone)]
+pub struct DavResponse {
+    pub href: String,
+    // response 直下の status (削除済みなど、プロパティを伴わない応答)
+    pub status: Option<String>,
+    pub etag: Option<String>,
+    pub calendar_data: Option<String>,
+    pub display_name: Option<String>,
+    pub ctag: Option<String>,
+    pub is_calendar: bool,
+    pub components: Vec<String>,
+    pub principal_href: Option<String>,
+    pub home_set_href: Option<String>,
+}
+
+impl DavResponse {
+    pub fn is_ok(&self) -> bool {
+        self.status
+            .as_deref()
+            .is_none_or(|status| status.contains(" 200"))
+    }
+}
+
+fn local_name(name: &[u8]) -> String {
+    let name = String::from_utf8_lossy(name);
+    name.rsplit(':').next().unwrap_or_default().to_string()
+}
+
+pub fn parse_multistatus(body: &str) -> Result<Vec<DavResponse>, String> {
+    let mut reader = Reader::from_str(body);
+    let mut stack: Vec<String> = Vec::new();
+    let mut responses = Vec::new();
+    let mut current: Option<DavResponse> = None;
+
+    loop {
+        let event = reader
+            .read_event()
+            .map_err(|e| format!("XML解析エラー: {}", e))?;
+        match event {
+            Event::Start(element) => {
+                let name = local_name(element.name().as_ref());
+                if name == "response" {
+                    current = Some(DavResponse::default());
+                }
+                handle_element(&mut current, &stack, &name, &element)?;
+                stack.push(name);
+            }
+            Event::Empty(element) => {
+                let name = local_name(element.name().as_ref());
+                handle_element(&mut current, &stack, &name, &element)?;
+            }
+            Event::End(_) => {
+                let closed = stack.pop();
+                if closed.as_deref() == Some("response") {
+                    responses.extend(current.take());
+                }
+            }
+            Event::Text(text) => {
+                let text = text
+                    .unescape()
+                    .map_err(|e| format!("XML解析エラー: {}", e))?;
+                handle_text(&mut current, &stack, &text);
+            }
+            Event::CData(data) => {
+                let text = String::from_utf8_lossy(&data.into_inner()).to_string();
+                handle_text(&mut current, &stack, &text);
+            }
+            Event::Eof => break,
+            _ => {}
+        }
+    }
+    Ok(responses)
+}
+
+fn handle_element(
+    current: &mut Option<DavResponse>,
+    stack: &[String],
+    name: &str,
+    element: &quick_xml::events::BytesStart,
+) -> Result<(), String> {
+    let Some(response) = current.as_mut() else {
+        return Ok(());
+    };
+    let parent = stack.last().map(String::as_str);
+    if name == "calendar" && parent == Some("resourcetype") {
+        response.is_calendar = true;
+    }
+    if name == "comp" && parent == Some("supported-calendar-component-set") {
+        for attribute in element.attributes().flatten() {
+            if attribute.key.as_ref() == b"name" {
+                let value = attribute
+                    .unescape_value()
+                    .map_err(|e| format!("XML解析エラー: {}", e))?;
+                response.components.push(value.to_ascii_uppercase());
+            }
+        }
+    }
+    Ok(())
+}
+
+fn handle_text(current: &mut Option<DavResponse>, stack: &[String], text: &str) {
+    let Some(response) = current.as_mut() else {
+        return;
+    };
+    let name = stack.last().map(String::as_str).unwrap_or_default();
+    let parent = stack
+        .len()
+        .checked_sub(2)
+        .map(|i| stack[i].as_str())
+        .unwrap_or_default();
+    let append = |target: &mut Option<String>| {
+        target.get_or_insert_with(String::new).push_str(text);
+    };
+
+    match (parent, name) {
+        ("response", "href") => response.href.push_str(text.trim()),
+        ("response", "status") => append(&mut response.status),
+        ("current-user-principal", "href") => append(&mut response.principal_href),
+        ("calendar-home-set", "href") => append(&mut response.home_set_href),
+        (_, "getetag") => append(&mut response.etag),
+        (_, "calendar-data") => append(&mut response.calendar_data),
+        (_, "displayname") => append(&mut response.display_name),
+        (_, "getctag") => append(&mut response.ctag),
+        _ => {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_etags_and_calendar_data_with_any_prefix() {
+        let body = r#"<?xml version="1.0" encoding="utf-8"?>
+<multistatus xmlns="DAV:" xmlns:cal="urn:ietf:params:xml:ns:caldav">
+  <response>
+    <href>/cal/a.ics</href>
+    <propstat>
+      <prop>
+        <getetag>"1"</getetag>
+        <cal:calendar-data>BEGIN:VCALENDAR&#13;
+SUMMARY:A &amp; B&#13;
+END:VCALENDAR</cal:calendar-data>
+      </prop>
+      <status>HTTP/1.1 200 OK</status>
+    </propstat>
+  </response>
+  <response>
+    <href>/cal/b.ics</href>
+    <propstat>
+      <prop><getetag>"2"</getetag><cal:calendar-data><![CDATA[SUMMARY:<B>]]></cal:calendar-data></prop>
+    </propstat>
+  </response>
+</multistatus>"#;
+        let responses = parse_multistatus(body).unwrap();
+        assert_eq!(responses.len(), 2);
+        assert_eq!(responses[0].href, "/cal/a.ics");
+        assert_eq!(responses[0].etag.as_deref(), Some("\"1\""));
+        assert_eq!(
+            responses[0].calendar_data.as_deref(),
+            Some("BEGIN:VCALENDAR\r\nSUMMARY:A & B\r\nEND:VCALENDAR")
+        );
+        assert!(responses[0].is_ok());
+        assert_eq!(responses[1].calendar_data.as_deref(), Some("SUMMARY:<B>"));
+    }
+
+    #[test]
+    fn keeps_the_status_of_missing_resources() {
+        let body = r#"<D:multistatus xmlns:D="DAV:">
+  <D:response>
+    <D:href>/cal/gone.ics</D:href>
+    <D:status>HTTP/1.1 404 Not Found</D:status>
+  </D:response>
+  <D:response>
+    <D:href>/cal/ok.ics</D:href>
+    <D:status>HTTP/1.1 200 OK</D:status>
+  </D:response>
+</D:multistatus>"#;
+        let responses = parse_multistatus(body).unwrap();
+        assert_eq!(
+            responses[0].status.as_deref(),
+            Some("HTTP/1.1 404 Not Found")
+        );
+        assert!(!responses[0].is_ok());
+        assert!(responses[0].calendar_data.is_none());
+        assert!(responses[1].is_ok());
+    }
+
+    #[test]
+    fn parses_calendar_collections_and_discovery_properties() {
+        let body = r#"<d:multistatus xmlns:d="DAV:" xmlns:c="urn:ietf:params:xml:ns:caldav" xmlns:cs="http://calendarserver.org/ns/">
+  <d:response>
+    <d:href>/dav/</d:href>
+    <d:propstat><d:prop>
+      <d:current-user-principal><d:href>/principals/me/</d:href></d:current-user-principal>
+      <c:calendar-home-set><d:href>/calendars/me/</d:href></c:calendar-home-set>
+    </d:prop></d:propstat>
+  </d:response>
+  <d:response>
+    <d:href>/calendars/me/tasks/</d:href>
+    <d:propstat><d:prop>
+      <d:displayname>仕事 &amp; 家</d:displayname>
+      <cs:getctag>ctag-1</cs:getctag>
+      <d:resourcetype><d:collection/><c:calendar/></d:resourcetype>
+      <c:supported-calendar-component-set>
+        <c:comp name="vevent"/><c:comp name="VTODO"/>
+      </c:supported-calendar-component-set>
+    </d:prop></d:propstat>
+  </d:response>
+  <d:response>
+    <d:href>/calendars/me/</d:href>
+    <d:propstat><d:prop><d:resourcetype><d:collection/></d:resourcetype></d:prop></d:propstat>
+  </d:response>
+</d:multistatus>"#;
+        let responses = parse_multistatus(body).unwrap();
+        assert_eq!(responses.len(), 3);
+        assert_eq!(
+            responses[0].principal_href.as_deref(),
+            Some("/principals/me/")
+        );
+        assert_eq!(
+            responses[0].home_set_href.as_deref(),
+            Some("/calendars/me/")
+        );
+        // プロパティ内の href は response の href として扱わない
+        assert_eq!(responses[0].href, "/dav/");
+
+        let tasks = &responses[1];
+        assert!(tasks.is_calendar);
+        assert_eq!(tasks.display_name.as_deref(), Some("仕事 & 家"));
+        assert_eq!(tasks.ctag.as_deref(), Some("ctag-1"));
+        assert_eq!(tasks.components, vec!["VEVENT", "VTODO"]);
+        assert!(!responses[2].is_calendar);
+        assert!(responses[2].components.is_empty());
+    }
+
+    #[test]
+    fn rejects_malformed_xml() {
+        assert!(
+            parse_multistatus("<d:multistatus xmlns:d=\"DAV:\"><d:response></d:multistatus>")
+                .is_err()
+        );
+    }
+}
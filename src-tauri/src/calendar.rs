@@ -0,0 +1,826 @@
+// 日本の祝日と営業日の計算
+// 祝日法の規則 (振替休日・国民の休日を含む) から祝日を求め、ICSで取り込んだ会社の休日と合わせて営業日を数える
+// 春分・秋分の日は天文計算の近似式を使うため、1980〜2099年の範囲で正しい
+
+use chrono::{DateTime, Datelike, Duration, Local, NaiveDate, TimeZone, Weekday};
+use std::collections::HashMap;
+use std::sync::Mutex;
+
+use crate::models::RecurrencePattern;
+use crate::recurrence::Schedule;
+
+const SETTINGS_FILE: &str = "calendar-settings.json";
+// ICS の繰り返しの休日を展開する範囲 (今年の前後の年数)
+const EXPAND_YEARS_BEFORE: i32 = 1;
+const EXPAND_YEARS_AFTER: i32 = 5;
+// 営業日を探すときに進める最大日数
+const MAX_SEARCH_DAYS: i32 = 3660;
+
+#[derive(serde::Serialize, serde::Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum HolidayKind {
+    National,
+    // 振替休日
+    Substitute,
+    // 国民の休日 (祝日に挟まれた日)
+    Citizens,
+    Company,
+}
+
+#[derive(serde::Serialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct Holiday {
+    // YYYY-MM-DD
+    pub date: String,
+    pub name: String,
+    pub kind: HolidayKind,
+}
+
+#[derive(serde::Serialize, serde::Deserialize, Clone, Debug, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct CompanyHoliday {
+    // YYYY-MM-DD
+    pub date: String,
+    pub name: String,
+}
+
+#[derive(serde::Serialize, serde::Deserialize, Clone, Debug)]
+#[serde(rename_all = "camelCase", default)]
+pub struct CalendarSettings {
+    // 日本の祝日を休日として扱う
+    pub japanese_holidays: bool,
+    pub company_holidays: Vec<CompanyHoliday>,
+}
+
+impl Default for CalendarSettings {
+    fn default() -> Self {
+        Self {
+            japanese_holidays: true,
+            company_holidays: Vec::new(),
+        }
+    }
+}
+
+fn ymd(year: i32, month: u32, day: u32) -> Option<NaiveDate> {
+    NaiveDate::from_ymd_opt(year, month, day)
+}
+
+fn format_date(date: NaiveDate) -> String {
+    date.format("%Y-%m-%d").to_string()
+}
+
+fn parse_date(value: &str) -> Option<NaiveDate> {
+    NaiveDate::parse_from_str(value.trim(), "%Y-%m-%d").ok()
+}
+
+// 第 n 月曜日 (ハッピーマンデー)
+fn nth_monday(year: i32, month: u32, n: u8) -> Option<NaiveDate> {
+    NaiveDate::from_weekday_of_month_opt(year, month, Weekday::Mon, n)
+}
+
+// 春分日・秋分日 (1980〜2099年の近似式)
+fn equinox_day(year: i32, base: f64) -> u32 {
+    let y = (year - 1980) as f64;
+    (base + 0.242194 * y - (y / 4.0).floor()).floor() as u32
+}
+
+// 「国民の祝日に関する法律」で定める祝日 (振替休日・国民の休日を除く)
+fn national_holidays(year: i32) -> Vec<(NaiveDate, &'static str)> {
+    let mut days: Vec<(Option<NaiveDate>, &'static str)> = vec![(ymd(year, 1, 1), "元日")];
+
+    let coming_of_age = if year >= 2000 {
+        nth_monday(year, 1, 2)
+    } else {
+        ymd(year, 1, 15)
+    };
+    days.push((coming_of_age, "成人の日"));
+    if year >= 1967 {
+        days.push((ymd(year, 2, 11), "建国記念の日"));
+    }
+    match year {
+        2020.. => days.push((ymd(year, 2, 23), "天皇誕生日")),
+        1989..=2018 => days.push((ymd(year, 12, 23), "天皇誕生日")),
+        ..=1988 => days.push((ymd(year, 4, 29), "天皇誕生日")),
+        _ => {}
+    }
+    days.push((ymd(year, 3, equinox_day(year, 20.8431)), "春分の日"));
+    match year {
+        2007.. => days.push((ymd(year, 4, 29), "昭和の日")),
+        1989..=2006 => days.push((ymd(year, 4, 29), "みどりの日")),
+        _ => {}
+    }
+    days.push((ymd(year, 5, 3), "憲法記念日"));
+    if year >= 2007 {
+        days.push((ymd(year, 5, 4), "みどりの日"));
+    }
+    days.push((ymd(year, 5, 5), "こどもの日"));
+
+    // 東京オリンピック・パラリンピックによる 2020・2021 年の移動
+    let marine = match year {
+        2020 => ymd(year, 7, 23),
+        2021 => ymd(year, 7, 22),
+        2003.. => nth_monday(year, 7, 3),
+        1996..=2002 => ymd(year, 7, 20),
+        _ => None,
+    };
+    days.push((marine, "海の日"));
+    let mountain = match year {
+        2020 => ymd(year, 8, 10),
+        2021 => ymd(year, 8, 8),
+        2016.. => ymd(year, 8, 11),
+        _ => None,
+    };
+    days.push((mountain, "山の日"));
+    let aged = if year >= 2003 {
+        nth_monday(year, 9, 3)
+    } else {
+        ymd(year, 9, 15)
+    };
+    days.push((aged, "敬老の日"));
+    days.push((ymd(year, 9, equinox_day(year, 23.2488)), "秋分の日"));
+    let sports = match year {
+        2020 => ymd(year, 7, 24),
+        2021 => ymd(year, 7, 23),
+        2000.. => nth_monday(year, 10, 2),
+        _ => ymd(year, 10, 10),
+    };
+    days.push((
+        sports,
+        if year >= 2020 {
+            "スポーツの日"
+        } else {
+            "体育の日"
+        },
+    ));
+    days.push((ymd(year, 11, 3), "文化の日"));
+    days.push((ymd(year, 11, 23), "勤労感謝の日"));
+
+    // 一度限りの祝日
+    let special: &[(i32, u32, u32, &'static str)] = &[
+        (1989, 2, 24, "昭和天皇の大喪の礼"),
+        (1990, 11, 12, "即位礼正殿の儀"),
+        (1993, 6, 9, "皇太子徳仁親王の結婚の儀"),
+        (2019, 5, 1, "天皇の即位の日"),
+        (2019, 10, 22, "即位礼正殿の儀の行われる日"),
+    ];
+    for (y, m, d, name) in special {
+        if *y == year {
+            days.push((ymd(*y, *m, *d), name));
+        }
+    }
+
+    let mut days: Vec<(NaiveDate, &'static str)> = days
+        .into_iter()
+        .filter_map(|(date, name)| date.map(|d| (d, name)))
+        .collect();
+    days.sort_by_key(|(date, _)| *date);
+    days
+}
+
+fn national_holiday(date: NaiveDate) -> Option<&'static str> {
+    national_holidays(date.year())
+        .into_iter()
+        .find(|(d, _)| *d == date)
+        .map(|(_, name)| name)
+}
+
+// 振替休日・国民の休日を含めた日本の休日
+pub fn japanese_holiday(date: NaiveDate) -> Option<(&'static str, HolidayKind)> {
+    if let Some(name) = national_holiday(date) {
+        return Some((name, HolidayKind::National));
+    }
+    let prev = date.pred_opt()?;
+    // 振替休日: 日曜日の祝日の後で最も近い祝日でない日 (2006年までは翌月曜日のみ)
+    if date >= ymd(2007, 1, 1)? {
+        let mut day = prev;
+        while national_holiday(day).is_some() {
+            if day.weekday() == Weekday::Sun {
+                return Some(("振替休日", HolidayKind::Substitute));
+            }
+            day = day.pred_opt()?;
+        }
+    } else if date >= ymd(1973, 4, 12)?
+        && date.weekday() == Weekday::Mon
+        && national_holiday(prev).is_some()
+    {
+        return Some(("振替休日", HolidayKind::Substitute));
+    }
+    // 国民の休日: 前日と翌日が祝日の日
+    if date >= ymd(1988, 5, 4)?
+        && national_holiday(prev).is_some()
+        && national_holiday(date.succ_opt()?).is_some()
+    {
+        return Some(("国民の休日", HolidayKind::Citizens));
+    }
+    None
+}
+
+// 営業日の判定に使う暦 (土日・日本の祝日・会社の休日を休みとする)
+#[derive(Clone, Debug, Default)]
+pub struct BusinessCalendar {
+    japanese_holidays: bool,
+    company: HashMap<NaiveDate, String>,
+}
+
+impl BusinessCalendar {
+    pub fn new(settings: &CalendarSettings) -> Self {
+        let company = settings
+            .company_holidays
+            .iter()
+            .filter_map(|h| parse_date(&h.date).map(|date| (date, h.name.clone())))
+            .collect();
+        Self {
+            japanese_holidays: settings.japanese_holidays,
+            company,
+        }
+    }
+
+    pub fn holiday(&self, date: NaiveDate) -> Option<Holiday> {
+        let (name, kind) = self
+            .japanese_holidays
+            .then(|| japanese_holiday(date))
+            .flatten()
+            .map(|(name, kind)| (name.to_string(), kind))
+            .or_else(|| {
+                self.company
+                    .get(&date)
+                    .map(|name| (name.clone(), HolidayKind::Company))
+            })?;
+        Some(Holiday {
+            date: format_date(date),
+            name,
+            kind,
+        })
+    }
+
+    pub fn is_holiday(&self, date: NaiveDate) -> bool {
+        (self.japanese_holidays && japanese_holiday(date).is_some())
+            || self.company.contains_key(&date)
+    }
+
+    pub fn is_business_day(&self, date: NaiveDate) -> bool {
+        !matches!(date.weekday(), Weekday::Sat | Weekday::Sun) && !self.is_holiday(date)
+    }
+
+    // date から n 営業日後 (n が負なら前)。0 なら date 自身か次の営業日
+    pub fn add_business_days(&self, date: NaiveDate, days: i32) -> NaiveDate {
+        let step = if days < 0 { -1 } else { 1 };
+        let mut current = date;
+        let mut remaining = days.abs();
+        if remaining == 0 {
+            return self.next_business_day(date, true);
+        }
+        let mut searched = 0;
+        while remaining > 0 && searched < MAX_SEARCH_DAYS {
+            current += Duration::days(step as i64);
+            searched += 1;
+            if self.is_business_day(current) {
+                remaining -= 1;
+            }
+        }
+        current
+    }
+
+    // date 以降 (include_today が偽なら翌日以降) で最初の営業日
+    pub fn next_business_day(&self, date: NaiveDate, include_today: bool) -> NaiveDate {
+        let mut current = if include_today {
+            date
+        } else {
+            date + Duration::days(1)
+        };
+        for _ in 0..MAX_SEARCH_DAYS {
+            if self.is_business_day(current) {
+                break;
+            }
+            current += Duration::days(1);
+        }
+        current
+    }
+
+    pub fn holidays_in_year(&self, year: i32) -> Vec<Holiday> {
+        let Some(first) = ymd(year, 1, 1) else {
+            return Vec::new();
+        };
+        first
+            .iter_days()
+            .take_while(|date| date.year() == year)
+            .filter_map(|date| self.holiday(date))
+            .collect()
+    }
+}
+
+// ICS の予定を会社の休日にする
+// 終日の予定は DTEND の前日まで、繰り返しは今年の前後数年分を展開する
+pub fn company_holidays_from_ics(content: &str) -> Vec<CompanyHoliday> {
+    let this_year = Local::now().year();
+    let range_start = Local
+        .with_ymd_and_hms(this_year - EXPAND_YEARS_BEFORE, 1, 1, 0, 0, 0)
+        .earliest()
+        .map(|d| d.timestamp_millis())
+        .unwrap_or(0);
+    let range_end = Local
+        .with_ymd_and_hms(this_year + EXPAND_YEARS_AFTER + 1, 1, 1, 0, 0, 0)
+        .earliest()
+        .map(|d| d.timestamp_millis())
+        .unwrap_or(i64::MAX);
+    let local_date = |millis: i64| {
+        DateTime::from_timestamp_millis(millis).map(|d| d.with_timezone(&Local).date_naive())
+    };
+
+    let mut holidays: Vec<CompanyHoliday> = Vec::new();
+    for item in crate::ics::parse_ics(content) {
+        let Some(start) = item.start.or(item.due) else {
+            continue;
+        };
+        // 何日続くか (終日の DTEND は翌日を指す)
+        let span_days = match (local_date(start), item.end.and_then(local_date)) {
+            (Some(first), Some(last)) if last > first => {
+                let days = (last - first).num_days();
+                if item.all_day {
+                    days
+                } else {
+                    days + 1
+                }
+            }
+            _ => 1,
+        };
+        let starts: Vec<i64> = match &item.rrule {
+            Some(rrule) => {
+                let pattern = RecurrencePattern {
+                    rrule: Some(rrule.clone()),
+                    exdates: item.exdates.clone(),
+                    ..Default::default()
+                };
+                match Schedule::new(&pattern, start, None) {
+                    Ok(schedule) => schedule
+                        .occurrences_after(range_start - 1, 10_000)
+                        .into_iter()
+                        .take_while(|millis| *millis < range_end)
+                        .collect(),
+                    Err(e) => {
+                        println!(
+                            "[Calendar Backend] 繰り返しを展開できません ({}): {}",
+                            item.summary, e
+                        );
+                        vec![start]
+                    }
+                }
+            }
+            None => vec![start],
+        };
+        for millis in starts {
+            let Some(first) = local_date(millis) else {
+                continue;
+            };
+            for offset in 0..span_days {
+                let date = format_date(first + Duration::days(offset));
+                if holidays.iter().any(|h| h.date == date) {
+                    continue;
+                }
+                holidays.push(CompanyHoliday {
+                    date,
+                    name: item.summary.clone(),
+                });
+            }
+        }
+    }
+    holidays.sort_by(|a, b| a.date.cmp(&b.date));
+    holidays
+}
+
+pub struct CalendarState {
+    settings: Mutex<CalendarSettings>,
+}
+
+impl CalendarState {
+    pub fn load(app: &tauri::AppHandle) -> Self {
+        let settings = crate::store::load_json(app, SETTINGS_FILE).unwrap_or_else(|e| {
+            println!("[Calendar Backend] 設定読み込み失敗: {}", e);
+            CalendarSettings::default()
+        });
+        Self {
+            settings: Mutex::new(settings),
+        }
+    }
+
+    fn settings(&self) -> CalendarSettings {
+        self.settings.lock().unwrap().clone()
+    }
+
+    fn save(&self, app: &tauri::AppHandle, settings: CalendarSettings) -> Result<(), String> {
+        crate::store::save_json(app, SETTINGS_FILE, &settings)?;
+        *self.settings.lock().unwrap() = settings;
+        Ok(())
+    }
+
+    pub fn calendar(&self) -> BusinessCalendar {
+        BusinessCalendar::new(&self.settings.lock().unwrap())
+    }
+}
+
+fn local_date_of(millis: i64) -> Result<NaiveDate, String> {
+    DateTime::from_timestamp_millis(millis)
+        .map(|d| d.with_timezone(&Local).date_naive())
+        .ok_or_else(|| "日時が不正です".to_string())
+}
+
+#[derive(serde::Serialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct DayInfo {
+    pub date: String,
+    pub business_day: bool,
+    pub holiday: Option<Holiday>,
+}
+
+#[tauri::command]
+pub fn calendar_get_settings(state: tauri::State<'_, CalendarState>) -> CalendarSettings {
+    state.settings()
+}
+
+#[tauri::command]
+pub fn calendar_set_settings(
+    app: tauri::AppHandle,
+    state: tauri::State<'_, CalendarState>,
+    settings: CalendarSettings,
+) -> Result<(), String> {
+    state.save(&app, settings)
+}
+
+// その年の休日 (日本の祝日と会社の休日)
+#[tauri::command]
+pub fn calendar_holidays(state: tauri::State<'_, CalendarState>, year: i32) -> Vec<Holiday> {
+    state.calendar().holidays_in_year(year)
+}
+
+// 会社の休日を ICS から取り込む (replace が真なら既存の会社の休日を置き換える)
+#[tauri::command]
+pub fn calendar_import_company_holidays(
+    app: tauri::AppHandle,
+    state: tauri::State<'_, CalendarState>,
+    content: String,
+    replace: Option<bool>,
+) -> Result<Vec<CompanyHoliday>, String> {
+    let imported = company_holidays_from_ics(&content);
+    if imported.is_empty() {
+        return Err("休日として取り込める予定がありません".to_string());
+    }
+    let mut settings = state.settings();
+    if replace.unwrap_or(false) {
+        settings.company_holidays.clear();
+    }
+    for holiday in &imported {
+        settings.company_holidays.retain(|h| h.date != holiday.date);
+        settings.company_holidays.push(holiday.clone());
+    }
+    settings
+        .company_holidays
+        .sort_by(|a, b| a.date.cmp(&b.date));
+    println!(
+        "[Calendar Backend] 会社の休日を{}件取り込みました",
+        imported.len()
+    );
+    state.save(&app, settings)?;
+    Ok(imported)
+}
+
+#[tauri::command]
+pub fn calendar_day_info(
+    state: tauri::State<'_, CalendarState>,
+    date: i64,
+) -> Result<DayInfo, String> {
+    let calendar = state.calendar();
+    let date = local_date_of(date)?;
+    Ok(DayInfo {
+        date: format_date(date),
+        business_day: calendar.is_business_day(date),
+        holiday: calendar.holiday(date),
+    })
+}
+
+// date (UTC ミリ秒) から days 営業日後。時刻はそのまま保つ
+#[tauri::command]
+pub fn calendar_add_business_days(
+    state: tauri::State<'_, CalendarState>,
+    date: i64,
+    days: i32,
+) -> Result<i64, String> {
+    let local = DateTime::from_timestamp_millis(date)
+        .map(|d| d.with_timezone(&Local).naive_local())
+        .ok_or_else(|| "日時が不正です".to_string())?;
+    let target = state
+        .calendar()
+        .add_business_days(local.date(), days)
+        .and_time(local.time());
+    Local
+        .from_local_datetime(&target)
+        .earliest()
+        .map(|d| d.timestamp_millis())
+        .ok_or_else(|| "日時が不正です".to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn date(value: &str) -> NaiveDate {
+        parse_date(value).unwrap()
+    }
+
+    // 内閣府「国民の祝日について」の一覧と突き合わせる
+    fn assert_year(year: i32, expected: &[(&str, &str)]) {
+        let actual: Vec<(String, String)> = BusinessCalendar::new(&CalendarSettings::default())
+            .holidays_in_year(year)
+            .into_iter()
+            .map(|h| (h.date, h.name))
+            .collect();
+        let expected: Vec<(String, String)> = expected
+            .iter()
+            .map(|(d, name)| (format!("{}-{}", year, d), name.to_string()))
+            .collect();
+        assert_eq!(actual, expected, "{}", year);
+    }
+
+    #[test]
+    fn holidays_2019_with_the_enthronement() {
+        assert_year(
+            2019,
+            &[
+                ("01-01", "元日"),
+                ("01-14", "成人の日"),
+                ("02-11", "建国記念の日"),
+                ("03-21", "春分の日"),
+                ("04-29", "昭和の日"),
+                ("04-30", "国民の休日"),
+                ("05-01", "天皇の即位の日"),
+                ("05-02", "国民の休日"),
+                ("05-03", "憲法記念日"),
+                ("05-04", "みどりの日"),
+                ("05-05", "こどもの日"),
+                ("05-06", "振替休日"),
+                ("07-15", "海の日"),
+                ("08-11", "山の日"),
+                ("08-12", "振替休日"),
+                ("09-16", "敬老の日"),
+                ("09-23", "秋分の日"),
+                ("10-14", "体育の日"),
+                ("10-22", "即位礼正殿の儀の行われる日"),
+                ("11-03", "文化の日"),
+                ("11-04", "振替休日"),
+                ("11-23", "勤労感謝の日"),
+            ],
+        );
+    }
+
+    #[test]
+    fn holidays_2020_moved_for_the_olympics() {
+        assert_year(
+            2020,
+            &[
+                ("01-01", "元日"),
+                ("01-13", "成人の日"),
+                ("02-11", "建国記念の日"),
+                ("02-23", "天皇誕生日"),
+                ("02-24", "振替休日"),
+                ("03-20", "春分の日"),
+                ("04-29", "昭和の日"),
+                ("05-03", "憲法記念日"),
+                ("05-04", "みどりの日"),
+                ("05-05", "こどもの日"),
+                ("05-06", "振替休日"),
+                ("07-23", "海の日"),
+                ("07-24", "スポーツの日"),
+                ("08-10", "山の日"),
+                ("09-21", "敬老の日"),
+                ("09-22", "秋分の日"),
+                ("11-03", "文化の日"),
+                ("11-23", "勤労感謝の日"),
+            ],
+        );
+    }
+
+    #[test]
+    fn holidays_2021_moved_for_the_olympics() {
+        assert_year(
+            2021,
+            &[
+                ("01-01", "元日"),
+                ("01-11", "成人の日"),
+                ("02-11", "建国記念の日"),
+                ("02-23", "天皇誕生日"),
+                ("03-20", "春分の日"),
+                ("04-29", "昭和の日"),
+                ("05-03", "憲法記念日"),
+                ("05-04", "みどりの日"),
+                ("05-05", "こどもの日"),
+                ("07-22", "海の日"),
+                ("07-23", "スポーツの日"),
+                ("08-08", "山の日"),
+                ("08-09", "振替休日"),
+                ("09-20", "敬老の日"),
+                ("09-23", "秋分の日"),
+                ("11-03", "文化の日"),
+                ("11-23", "勤労感謝の日"),
+            ],
+        );
+    }
+
+    #[test]
+    fn holidays_2026_with_a_citizens_holiday() {
+        assert_year(
+            2026,
+            &[
+                ("01-01", "元日"),
+                ("01-12", "成人の日"),
+                ("02-11", "建国記念の日"),
+                ("02-23", "天皇誕生日"),
+                ("03-20", "春分の日"),
+                ("04-29", "昭和の日"),
+                ("05-03", "憲法記念日"),
+                ("05-04", "みどりの日"),
+                ("05-05", "こどもの日"),
+                ("05-06", "振替休日"),
+                ("07-20", "海の日"),
+                ("08-11", "山の日"),
+                ("09-21", "敬老の日"),
+                ("09-22", "国民の休日"),
+                ("09-23", "秋分の日"),
+                ("10-12", "スポーツの日"),
+                ("11-03", "文化の日"),
+                ("11-23", "勤労感謝の日"),
+            ],
+        );
+    }
+
+    #[test]
+    fn substitute_and_citizens_holidays() {
+        let cases = [
+            // 2007年以降は連休の後の最初の平日に振り替える
+            ("2008-05-06", Some(("振替休日", HolidayKind::Substitute))),
+            ("2015-05-06", Some(("振替休日", HolidayKind::Substitute))),
+            // 2006年までは翌月曜日だけ
+            ("2006-01-02", Some(("振替休日", HolidayKind::Substitute))),
+            ("2001-12-24", Some(("振替休日", HolidayKind::Substitute))),
+            // シルバーウィーク
+            ("2009-09-22", Some(("国民の休日", HolidayKind::Citizens))),
+            ("2015-09-22", Some(("国民の休日", HolidayKind::Citizens))),
+            // 祝日が土曜日なら振り替えない
+            ("2021-05-06", None),
+            ("2020-05-07", None),
+            // 2007年以降、祝日に挟まれた 5/4 はみどりの日
+            ("2008-05-04", Some(("みどりの日", HolidayKind::National))),
+        ];
+        for (day, expected) in cases {
+            assert_eq!(japanese_holiday(date(day)), expected, "{}", day);
+        }
+    }
+
+    #[test]
+    fn equinox_days_match_the_published_dates() {
+        let spring = [
+            (1980, 20),
+            (2000, 20),
+            (2019, 21),
+            (2020, 20),
+            (2023, 21),
+            (2024, 20),
+            (2025, 20),
+        ];
+        for (year, day) in spring {
+            assert_eq!(equinox_day(year, 20.8431), day, "春分 {}", year);
+        }
+        let autumn = [
+            (2012, 22),
+            (2016, 22),
+            (2019, 23),
+            (2020, 22),
+            (2024, 22),
+            (2025, 23),
+        ];
+        for (year, day) in autumn {
+            assert_eq!(equinox_day(year, 23.2488), day, "秋分 {}", year);
+        }
+    }
+
+    #[test]
+    fn adds_business_days_across_weekends_and_holidays() {
+        let calendar = BusinessCalendar::new(&CalendarSettings::default());
+        let cases = [
+            // 金曜日の翌営業日は月曜日
+            ("2024-05-24", 1, "2024-05-27"),
+            // 2019年の10連休をまたぐ
+            ("2019-04-26", 1, "2019-05-07"),
+            ("2019-05-07", -1, "2019-04-26"),
+            ("2024-04-26", 5, "2024-05-08"),
+            // 0 なら当日か次の営業日
+            ("2024-05-22", 0, "2024-05-22"),
+            ("2024-05-25", 0, "2024-05-27"),
+        ];
+        for (from, days, expected) in cases {
+            assert_eq!(
+                calendar.add_business_days(date(from), days),
+                date(expected),
+                "{} {:+}",
+                from,
+                days
+            );
+        }
+        assert_eq!(
+            calendar.next_business_day(date("2024-05-24"), false),
+            date("2024-05-27")
+        );
+        // 営業日から進めて戻すと元に戻る
+        let mut day = date("2024-01-04");
+        while day.year() == 2024 {
+            for n in [1, 3, 10] {
+                let moved = calendar.add_business_days(day, n);
+                assert!(calendar.is_business_day(moved));
+                assert_eq!(calendar.add_business_days(moved, -n), day, "{} {}", day, n);
+            }
+            day = calendar.add_business_days(day, 1);
+        }
+    }
+
+    #[test]
+    fn company_holidays_are_business_day_exceptions() {
+        let settings = CalendarSettings {
+            japanese_holidays: false,
+            company_holidays: vec![CompanyHoliday {
+                date: "2024-05-27".to_string(),
+                name: "創立記念日".to_string(),
+            }],
+        };
+        let calendar = BusinessCalendar::new(&settings);
+        // 日本の祝日を使わなければ祝日も営業日
+        assert!(calendar.is_business_day(date("2024-05-03")));
+        assert!(!calendar.is_business_day(date("2024-05-27")));
+        assert_eq!(
+            calendar.add_business_days(date("2024-05-24"), 1),
+            date("2024-05-28")
+        );
+        let holiday = calendar.holiday(date("2024-05-27")).unwrap();
+        assert_eq!(holiday.kind, HolidayKind::Company);
+        assert_eq!(holiday.name, "創立記念日");
+    }
+
+    #[test]
+    fn imports_company_holidays_from_ics() {
+        let year = Local::now().year();
+        let ics = format!(
+            "BEGIN:VCALENDAR\r\n\
+             BEGIN:VEVENT\r\n\
+             UID:winter\r\n\
+             SUMMARY:年末年始休暇\r\n\
+             DTSTART;VALUE=DATE:{y}1229\r\n\
+             DTEND;VALUE=DATE:{n}0104\r\n\
+             END:VEVENT\r\n\
+             BEGIN:VEVENT\r\n\
+             UID:summer\r\n\
+             SUMMARY:夏季休暇\r\n\
+             DTSTART;VALUE=DATE:{p}0813\r\n\
+             DTEND;VALUE=DATE:{p}0815\r\n\
+             RRULE:FREQ=YEARLY\r\n\
+             EXDATE;VALUE=DATE:{y}0813\r\n\
+             END:VEVENT\r\n\
+             END:VCALENDAR\r\n",
+            y = year,
+            n = year + 1,
+            p = year - 3,
+        );
+        let holidays = company_holidays_from_ics(&ics);
+        let dates: Vec<&str> = holidays.iter().map(|h| h.date.as_str()).collect();
+
+        // 終日の DTEND は含めない
+        let winter: Vec<String> = ["12-29", "12-30", "12-31"]
+            .iter()
+            .map(|d| format!("{}-{}", year, d))
+            .chain(
+                ["01-01", "01-02", "01-03"]
+                    .iter()
+                    .map(|d| format!("{}-{}", year + 1, d)),
+            )
+            .collect();
+        for day in &winter {
+            assert!(dates.contains(&day.as_str()), "{}", day);
+        }
+        assert!(!dates.contains(&format!("{}-01-04", year + 1).as_str()));
+
+        // 繰り返しは前後の年だけ展開し、EXDATE の回は除く
+        let summer = |y: i32| {
+            [format!("{}-08-13", y), format!("{}-08-14", y)]
+                .iter()
+                .all(|d| dates.contains(&d.as_str()))
+        };
+        assert!(summer(year - 1));
+        assert!(!summer(year));
+        assert!(summer(year + EXPAND_YEARS_AFTER));
+        assert!(!summer(year - 2));
+        assert!(!summer(year + EXPAND_YEARS_AFTER + 1));
+
+        let mut sorted = dates.clone();
+        sorted.sort_unstable();
+        sorted.dedup();
+        assert_eq!(sorted, dates);
+        let name = &holidays.iter().find(|h| h.date == winter[0]).unwrap().name;
+        assert_eq!(name, "年末年始休暇");
+    }
+}
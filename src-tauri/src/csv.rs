@@ -0,0 +1,1121 @@
+// CSV のエクスポート・インポート
+// エクスポートは列を選んで書き出し、インポートは列の対応 (プロファイル) に従って読み込む
+// Todoist / TickTick / Microsoft To Do のエクスポート用プロファイルを組み込みで用意する
+
+use chrono::{DateTime, Local, NaiveDate, NaiveDateTime, TimeZone};
+use std::collections::HashMap;
+use std::sync::Mutex;
+
+use crate::ics;
+use crate::models::{DueDateNotification, Project, Section, Todo};
+
+const PROFILES_FILE: &str = "csv-profiles.json";
+const DEFAULT_PROJECT_COLOR: &str = "#6366f1";
+// 表計算ソフトで数式として評価されるセルの先頭の文字 (タブ・CR で始めて数式を隠す場合も含む)
+const FORMULA_PREFIXES: [char; 6] = ['=', '+', '-', '@', '\t', '\r'];
+
+#[derive(serde::Serialize, serde::Deserialize, Clone, Copy, PartialEq, Eq, Debug)]
+#[serde(rename_all = "camelCase")]
+pub enum CsvColumn {
+    Id,
+    Text,
+    Description,
+    Completed,
+    Priority,
+    DueDate,
+    Labels,
+    Project,
+    Section,
+    ParentId,
+    EstimatedMinutes,
+    Timeframe,
+    CreatedAt,
+    CompletedAt,
+}
+
+const DEFAULT_COLUMNS: [CsvColumn; 10] = [
+    CsvColumn::Text,
+    CsvColumn::Description,
+    CsvColumn::Completed,
+    CsvColumn::Priority,
+    CsvColumn::DueDate,
+    CsvColumn::Labels,
+    CsvColumn::Project,
+    CsvColumn::Section,
+    CsvColumn::CreatedAt,
+    CsvColumn::CompletedAt,
+];
+
+impl CsvColumn {
+    fn header(self) -> &'static str {
+        match self {
+            CsvColumn::Id => "ID",
+            CsvColumn::Text => "タスク",
+            CsvColumn::Description => "説明",
+            CsvColumn::Completed => "完了",
+            CsvColumn::Priority => "優先度",
+            CsvColumn::DueDate => "期限",
+            CsvColumn::Labels => "ラベル",
+            CsvColumn::Project => "プロジェクト",
+            CsvColumn::Section => "セクション",
+            CsvColumn::ParentId => "親タスクID",
+            CsvColumn::EstimatedMinutes => "見積もり(分)",
+            CsvColumn::Timeframe => "期間",
+            CsvColumn::CreatedAt => "作成日時",
+            CsvColumn::CompletedAt => "完了日時",
+        }
+    }
+}
+
+#[derive(serde::Deserialize, Default)]
+#[serde(rename_all = "camelCase", default)]
+pub struct CsvExportOptions {
+    // 未指定なら標準の列
+    pub columns: Option<Vec<CsvColumn>>,
+    pub include_completed: Option<bool>,
+}
+
+// 優先度の値の読み方
+#[derive(serde::Serialize, serde::Deserialize, Clone, Copy, Default, Debug)]
+#[serde(rename_all = "camelCase")]
+pub enum PriorityScale {
+    // 1〜4 がそのまま P1〜P4
+    #[default]
+    Direct,
+    // Todoist: 4 が最優先 (p1)
+    Todoist,
+    // TickTick: 5=高, 3=中, 1=低, 0=なし
+    TickTick,
+    // High / Medium / Low / 高 / 中 / 低 などの文字
+    Text,
+}
+
+impl PriorityScale {
+    fn parse(self, value: &str) -> Option<u8> {
+        let value = value.trim();
+        if let Some(priority) = value
+            .strip_prefix(['P', 'p'])
+            .and_then(|n| n.parse::<u8>().ok())
+            .filter(|n| (1..=4).contains(n))
+        {
+            return Some(priority);
+        }
+        let number = value.parse::<i64>().ok();
+        match self {
+            PriorityScale::Direct => number.filter(|n| (1..=4).contains(n)).map(|n| n as u8),
+            PriorityScale::Todoist => number
+                .filter(|n| (1..=4).contains(n))
+                .map(|n| (5 - n) as u8),
+            PriorityScale::TickTick => number.map(|n| match n {
+                5.. => 1,
+                3..=4 => 2,
+                1..=2 => 3,
+                _ => 4,
+            }),
+            PriorityScale::Text => match value.to_lowercase().as_str() {
+                "high" | "urgent" | "高" | "最高" => Some(1),
+                "medium" | "中" => Some(2),
+                "low" | "低" => Some(3),
+                "normal" | "none" | "なし" => Some(4),
+                _ => number.filter(|n| (1..=4).contains(n)).map(|n| n as u8),
+            },
+        }
+    }
+}
+
+// インポートする列の対応。各項目は見出しの候補 (大文字小文字は区別しない)
+#[derive(serde::Serialize, serde::Deserialize, Clone, Default, Debug)]
+#[serde(rename_all = "camelCase", default)]
+pub struct CsvColumnMap {
+    pub text: Vec<String>,
+    pub description: Vec<String>,
+    pub priority: Vec<String>,
+    pub due_date: Vec<String>,
+    pub labels: Vec<String>,
+    pub project: Vec<String>,
+    pub section: Vec<String>,
+    pub completed: Vec<String>,
+    pub completed_at: Vec<String>,
+    pub created_at: Vec<String>,
+    pub estimated_minutes: Vec<String>,
+    // 行の種類 (Todoist の TYPE など)
+    pub kind: Vec<String>,
+    // 階層の深さ (Todoist の INDENT、1 がトップレベル)
+    pub indent: Vec<String>,
+    // 行のIDと親のID (TickTick の taskId / parentId など)
+    pub row_id: Vec<String>,
+    pub parent_id: Vec<String>,
+}
+
+#[derive(serde::Serialize, serde::Deserialize, Clone, Default, Debug)]
+#[serde(rename_all = "camelCase", default)]
+pub struct CsvProfile {
+    pub id: String,
+    pub name: String,
+    #[serde(skip_deserializing)]
+    pub builtin: bool,
+    pub columns: CsvColumnMap,
+    pub priority_scale: PriorityScale,
+    // ラベルの区切り文字 (いずれかの文字で区切る)
+    pub label_separators: String,
+    // 本文中の @ラベル をラベルとして取り出す (Todoist)
+    pub labels_in_text: bool,
+    // 種類の列がこの値の行だけをタスクとして取り込む (空ならすべて)
+    pub task_kinds: Vec<String>,
+    // 種類の列がこの値の行は以降のタスクのセクション名として扱う
+    pub section_kinds: Vec<String>,
+}
+
+fn names(values: &[&str]) -> Vec<String> {
+    values.iter().map(|v| v.to_string()).collect()
+}
+
+fn builtin_profiles() -> Vec<CsvProfile> {
+    let calm_todo = CsvProfile {
+        id: "calm-todo".to_string(),
+        name: "Calm Todo".to_string(),
+        builtin: true,
+        columns: CsvColumnMap {
+            text: names(&[CsvColumn::Text.header()]),
+            description: names(&[CsvColumn::Description.header()]),
+            priority: names(&[CsvColumn::Priority.header()]),
+            due_date: names(&[CsvColumn::DueDate.header()]),
+            labels: names(&[CsvColumn::Labels.header()]),
+            project: names(&[CsvColumn::Project.header()]),
+            section: names(&[CsvColumn::Section.header()]),
+            completed: names(&[CsvColumn::Completed.header()]),
+            completed_at: names(&[CsvColumn::CompletedAt.header()]),
+            created_at: names(&[CsvColumn::CreatedAt.header()]),
+            estimated_minutes: names(&[CsvColumn::EstimatedMinutes.header()]),
+            row_id: names(&[CsvColumn::Id.header()]),
+            parent_id: names(&[CsvColumn::ParentId.header()]),
+            ..Default::default()
+        },
+        label_separators: ",".to_string(),
+        ..Default::default()
+    };
+    let todoist = CsvProfile {
+        id: "todoist".to_string(),
+        name: "Todoist".to_string(),
+        builtin: true,
+        columns: CsvColumnMap {
+            text: names(&["CONTENT"]),
+            description: names(&["DESCRIPTION"]),
+            priority: names(&["PRIORITY"]),
+            due_date: names(&["DATE"]),
+            estimated_minutes: names(&["DURATION"]),
+            kind: names(&["TYPE"]),
+            indent: names(&["INDENT"]),
+            ..Default::default()
+        },
+        priority_scale: PriorityScale::Todoist,
+        labels_in_text: true,
+        task_kinds: names(&["task"]),
+        section_kinds: names(&["section"]),
+        ..Default::default()
+    };
+    let ticktick = CsvProfile {
+        id: "ticktick".to_string(),
+        name: "TickTick".to_string(),
+        builtin: true,
+        columns: CsvColumnMap {
+            text: names(&["Title"]),
+            description: names(&["Content"]),
+            priority: names(&["Priority"]),
+            due_date: names(&["Due Date"]),
+            labels: names(&["Tags"]),
+            project: names(&["List Name"]),
+            section: names(&["Column Name"]),
+            completed: names(&["Status"]),
+            completed_at: names(&["Completed Time"]),
+            created_at: names(&["Created Time"]),
+            kind: names(&["Kind"]),
+            row_id: names(&["taskId"]),
+            parent_id: names(&["parentId"]),
+            ..Default::default()
+        },
+        priority_scale: PriorityScale::TickTick,
+        label_separators: ",".to_string(),
+        task_kinds: names(&["TEXT", "CHECKLIST"]),
+        ..Default::default()
+    };
+    // To Do には公式のCSVエクスポートがないため、Outlook や変換ツールでよく使われる見出しを候補にする
+    let microsoft_todo = CsvProfile {
+        id: "microsoft-todo".to_string(),
+        name: "Microsoft To Do".to_string(),
+        builtin: true,
+        columns: CsvColumnMap {
+            text: names(&["Title", "Subject", "Task"]),
+            description: names(&["Notes", "Body", "Note"]),
+            priority: names(&["Importance", "Priority"]),
+            due_date: names(&["Due Date", "DueDate", "Due"]),
+            labels: names(&["Categories"]),
+            project: names(&["List", "List Name", "Folder"]),
+            completed: names(&["Status", "Complete", "Completed"]),
+            completed_at: names(&["Completed Date", "Date Completed", "Completed Time"]),
+            created_at: names(&["Created", "Created Date", "Created Time"]),
+            ..Default::default()
+        },
+        priority_scale: PriorityScale::Text,
+        label_separators: ";,".to_string(),
+        ..Default::default()
+    };
+    vec![calm_todo, todoist, ticktick, microsoft_todo]
+}
+
+fn format_datetime(millis: i64) -> String {
+    let Some(datetime) = Local.timestamp_millis_opt(millis).single() else {
+        return String::new();
+    };
+    // 時刻が 0:00 なら日付だけにする
+    if datetime.format("%H:%M:%S").to_string() == "00:00:00" {
+        datetime.format("%Y-%m-%d").to_string()
+    } else {
+        datetime.format("%Y-%m-%d %H:%M").to_string()
+    }
+}
+
+// 表計算ソフトで数式として評価されないよう、FORMULA_PREFIXES で始まるセルの先頭に ' を付ける
+pub fn escape_formula(value: String) -> String {
+    if value.starts_with(FORMULA_PREFIXES) {
+        format!("'{}", value)
+    } else {
+        value
+    }
+}
+
+// escape_formula で付けた ' を外す
+fn unescape_formula(value: &str) -> &str {
+    match value.strip_prefix('\'') {
+        Some(rest) if rest.starts_with(FORMULA_PREFIXES) => rest,
+        _ => value,
+    }
+}
+
+fn cell(todo: &Todo, column: CsvColumn, projects: &[Project], sections: &[Section]) -> String {
+    match column {
+        CsvColumn::Id => todo.id.clone(),
+        CsvColumn::Text => todo.text.clone(),
+        CsvColumn::Description => todo.description.clone(),
+        CsvColumn::Completed => if todo.completed { "TRUE" } else { "FALSE" }.to_string(),
+        CsvColumn::Priority => format!("P{}", todo.priority),
+        CsvColumn::DueDate => todo.due_date.map(format_datetime).unwrap_or_default(),
+        CsvColumn::Labels => todo.labels.join(", "),
+        CsvColumn::Project => todo
+            .project_id
+            .as_ref()
+            .map(|id| {
+                projects
+                    .iter()
+                    .find(|p| &p.id == id)
+                    .map_or(id.clone(), |p| p.name.clone())
+            })
+            .unwrap_or_default(),
+        CsvColumn::Section => todo
+            .section_id
+            .as_ref()
+            .map(|id| {
+                sections
+                    .iter()
+                    .find(|s| &s.id == id)
+                    .map_or(id.clone(), |s| s.name.clone())
+            })
+            .unwrap_or_default(),
+        CsvColumn::ParentId => todo.parent_id.clone().unwrap_or_default(),
+        CsvColumn::EstimatedMinutes => todo
+            .estimated_minutes
+            .map(|m| m.to_string())
+            .unwrap_or_default(),
+        CsvColumn::Timeframe => todo.timeframe.clone(),
+        CsvColumn::CreatedAt => format_datetime(todo.created_at),
+        CsvColumn::CompletedAt => todo.completed_at.map(format_datetime).unwrap_or_default(),
+    }
+}
+
+pub fn export_csv(
+    todos: &[Todo],
+    projects: &[Project],
+    sections: &[Section],
+    options: &CsvExportOptions,
+) -> Result<String, String> {
+    let columns = options
+        .columns
+        .clone()
+        .filter(|c| !c.is_empty())
+        .unwrap_or_else(|| DEFAULT_COLUMNS.to_vec());
+    let include_completed = options.include_completed.unwrap_or(true);
+
+    let mut writer = ::csv::Writer::from_writer(Vec::new());
+    writer
+        .write_record(columns.iter().map(|c| c.header()))
+        .map_err(|e| format!("CSV書き込みエラー: {}", e))?;
+    let mut targets: Vec<&Todo> = todos
+        .iter()
+        .filter(|t| !t.archived && (include_completed || !t.completed))
+        .collect();
+    targets.sort_by(|a, b| {
+        a.order
+            .total_cmp(&b.order)
+            .then(a.created_at.cmp(&b.created_at))
+    });
+    for todo in targets {
+        writer
+            .write_record(
+                columns
+                    .iter()
+                    .map(|c| escape_formula(cell(todo, *c, projects, sections))),
+            )
+            .map_err(|e| format!("CSV書き込みエラー: {}", e))?;
+    }
+    let bytes = writer
+        .into_inner()
+        .map_err(|e| format!("CSV書き込みエラー: {}", e))?;
+    let content = String::from_utf8(bytes).map_err(|e| e.to_string())?;
+    // Excel で文字化けしないよう BOM を付ける
+    Ok(format!("\u{feff}{}", content))
+}
+
+// 日付・日時の文字列 (日付のみなら端末のタイムゾーンの 0:00)
+fn parse_datetime(value: &str) -> Option<i64> {
+    let value = value.trim();
+    if value.is_empty() {
+        return None;
+    }
+    if let Ok(datetime) = DateTime::parse_from_rfc3339(value) {
+        return Some(datetime.timestamp_millis());
+    }
+    for format in ["%Y-%m-%dT%H:%M:%S%z", "%Y-%m-%dT%H:%M:%S%.f%z"] {
+        if let Ok(datetime) = DateTime::parse_from_str(value, format) {
+            return Some(datetime.timestamp_millis());
+        }
+    }
+    let local = |naive: NaiveDateTime| {
+        Local
+            .from_local_datetime(&naive)
+            .earliest()
+            .map(|d| d.timestamp_millis())
+    };
+    for format in [
+        "%Y-%m-%d %H:%M:%S",
+        "%Y-%m-%d %H:%M",
+        "%Y-%m-%dT%H:%M:%S",
+        "%Y-%m-%dT%H:%M",
+        "%Y/%m/%d %H:%M:%S",
+        "%Y/%m/%d %H:%M",
+        "%m/%d/%Y %H:%M:%S",
+        "%m/%d/%Y %H:%M",
+        "%m/%d/%Y %I:%M %p",
+    ] {
+        if let Ok(naive) = NaiveDateTime::parse_from_str(value, format) {
+            return local(naive);
+        }
+    }
+    for format in ["%Y-%m-%d", "%Y/%m/%d", "%m/%d/%Y", "%Y年%m月%d日"] {
+        if let Ok(date) = NaiveDate::parse_from_str(value, format) {
+            return local(date.and_hms_opt(0, 0, 0)?);
+        }
+    }
+    None
+}
+
+fn parse_bool(value: &str) -> bool {
+    let value = value.trim().to_lowercase();
+    if let Ok(number) = value.parse::<i64>() {
+        return number > 0;
+    }
+    matches!(
+        value.as_str(),
+        "true" | "yes" | "y" | "x" | "done" | "completed" | "complete" | "はい" | "完了" | "済"
+    )
+}
+
+#[derive(serde::Serialize, Clone, Copy, PartialEq, Eq, Debug)]
+#[serde(rename_all = "camelCase")]
+pub enum CsvRowStatus {
+    Created,
+    Skipped,
+    Rejected,
+}
+
+#[derive(serde::Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct CsvRowReport {
+    // ファイル内の行番号 (1始まり)
+    pub line: u64,
+    pub status: CsvRowStatus,
+    pub text: String,
+    pub reason: Option<String>,
+    // 取り込むが一部の値を読めなかった列
+    pub warnings: Vec<String>,
+}
+
+#[derive(serde::Serialize, Default, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct CsvImportReport {
+    pub created: usize,
+    pub skipped: usize,
+    pub rejected: usize,
+    pub rows: Vec<CsvRowReport>,
+}
+
+#[derive(serde::Serialize, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct CsvImportResult {
+    // 既存のプロジェクトに一致しなかったプロジェクトと、ファイル内のセクション
+    pub projects: Vec<Project>,
+    pub sections: Vec<Section>,
+    pub todos: Vec<Todo>,
+    pub report: CsvImportReport,
+}
+
+// 見出し行の各列の位置
+struct ColumnIndex {
+    positions: HashMap<String, usize>,
+}
+
+impl ColumnIndex {
+    fn new(header: &::csv::StringRecord) -> Self {
+        let positions = header
+            .iter()
+            .enumerate()
+            .map(|(i, name)| (name.trim().trim_start_matches('\u{feff}').to_lowercase(), i))
+            .collect();
+        Self { positions }
+    }
+
+    fn find(&self, candidates: &[String]) -> Option<usize> {
+        candidates
+            .iter()
+            .find_map(|c| self.positions.get(&c.trim().to_lowercase()).copied())
+    }
+}
+
+struct ImportContext<'a> {
+    profile: &'a CsvProfile,
+    existing_projects: &'a [Project],
+    include_completed: bool,
+    now: i64,
+    result: CsvImportResult,
+    current_section: Option<String>,
+    // 階層 (INDENT) の親候補: 深さごとの直近のタスクID
+    indent_parents: Vec<String>,
+    // 行のID → 取り込んだタスクのID
+    row_ids: HashMap<String, String>,
+    // 取り込み後に解決する親ID (子タスクのインデックス, 親の行ID)
+    pending_parents: Vec<(usize, String)>,
+}
+
+impl ImportContext<'_> {
+    fn project_id(&mut self, name: &str) -> String {
+        let found = self
+            .existing_projects
+            .iter()
+            .chain(self.result.projects.iter())
+            .find(|p| p.name == name);
+        if let Some(project) = found {
+            return project.id.clone();
+        }
+        let project = Project {
+            id: format!("csv-project-{}-{}", self.now, self.result.projects.len()),
+            name: name.to_string(),
+            color: DEFAULT_PROJECT_COLOR.to_string(),
+            order: (self.existing_projects.len() + self.result.projects.len()) as f64,
+            ..Default::default()
+        };
+        let id = project.id.clone();
+        self.result.projects.push(project);
+        id
+    }
+
+    fn section_id(&mut self, name: &str) -> String {
+        if let Some(section) = self.result.sections.iter().find(|s| s.name == name) {
+            return section.id.clone();
+        }
+        let section = Section {
+            id: format!("csv-section-{}-{}", self.now, self.result.sections.len()),
+            name: name.to_string(),
+            order: self.result.sections.len() as f64,
+            ..Default::default()
+        };
+        let id = section.id.clone();
+        self.result.sections.push(section);
+        id
+    }
+
+    fn report(&mut self, line: u64, status: CsvRowStatus, text: &str, reason: Option<&str>) {
+        let report = &mut self.result.report;
+        match status {
+            CsvRowStatus::Created => report.created += 1,
+            CsvRowStatus::Skipped => report.skipped += 1,
+            CsvRowStatus::Rejected => report.rejected += 1,
+        }
+        report.rows.push(CsvRowReport {
+            line,
+            status,
+            text: text.to_string(),
+            reason: reason.map(str::to_string),
+            warnings: Vec::new(),
+        });
+    }
+
+    fn import_row(&mut self, line: u64, get: impl Fn(&[String]) -> Option<String>) {
+        let columns = &self.profile.columns;
+        let mut text = get(&columns.text).unwrap_or_default();
+        let kind = get(&columns.kind).unwrap_or_default();
+        let is_kind = |kinds: &[String]| kinds.iter().any(|k| k.eq_ignore_ascii_case(&kind));
+
+        if !kind.is_empty() && is_kind(&self.profile.section_kinds) {
+            self.current_section = (!text.is_empty()).then(|| self.section_id(&text));
+            self.indent_parents.clear();
+            self.report(line, CsvRowStatus::Skipped, &text, Some("セクション行"));
+            return;
+        }
+        if !self.profile.task_kinds.is_empty() && !is_kind(&self.profile.task_kinds) {
+            let reason = format!("タスク以外の行 ({})", kind);
+            self.report(line, CsvRowStatus::Skipped, &text, Some(&reason));
+            return;
+        }
+
+        let mut labels: Vec<String> = Vec::new();
+        if self.profile.labels_in_text {
+            let (words, tags): (Vec<&str>, Vec<&str>) = text
+                .split_whitespace()
+                .partition(|w| !(w.len() > 1 && w.starts_with('@')));
+            labels.extend(tags.iter().map(|t| t[1..].to_string()));
+            text = words.join(" ");
+        }
+        if text.is_empty() {
+            self.report(line, CsvRowStatus::Rejected, "", Some("タスク名が空です"));
+            return;
+        }
+
+        let completed = get(&columns.completed).is_some_and(|v| parse_bool(&v));
+        if completed && !self.include_completed {
+            self.report(line, CsvRowStatus::Skipped, &text, Some("完了済み"));
+            return;
+        }
+
+        let mut warnings = Vec::new();
+        let mut read_date = |candidates: &[String], label: &str| {
+            let value = get(candidates)?;
+            let parsed = parse_datetime(&value);
+            if parsed.is_none() {
+                warnings.push(format!("{}を解釈できません: {}", label, value));
+            }
+            parsed
+        };
+        let due_date = read_date(&columns.due_date, "期限");
+        let created_at = read_date(&columns.created_at, "作成日時");
+        let completed_at = read_date(&columns.completed_at, "完了日時");
+
+        let priority = match get(&columns.priority) {
+            Some(value) => self
+                .profile
+                .priority_scale
+                .parse(&value)
+                .unwrap_or_else(|| {
+                    warnings.push(format!("優先度を解釈できません: {}", value));
+                    4
+                }),
+            None => 4,
+        };
+        if let Some(value) = get(&columns.labels) {
+            let separators: Vec<char> = self.profile.label_separators.chars().collect();
+            let parts: Vec<&str> = if separators.is_empty() {
+                vec![value.as_str()]
+            } else {
+                value.split(separators.as_slice()).collect()
+            };
+            labels.extend(
+                parts
+                    .iter()
+                    .map(|l| l.trim().trim_start_matches('#').to_string())
+                    .filter(|l| !l.is_empty()),
+            );
+        }
+        let mut unique_labels: Vec<String> = Vec::new();
+        for label in labels {
+            if !unique_labels.contains(&label) {
+                unique_labels.push(label);
+            }
+        }
+        let estimated_minutes = get(&columns.estimated_minutes).and_then(|v| v.parse().ok());
+
+        let project_id = get(&columns.project).map(|name| self.project_id(&name));
+        let section_id = match get(&columns.section) {
+            Some(name) => Some(self.section_id(&name)),
+            None => self.current_section.clone(),
+        };
+
+        let index = self.result.todos.len();
+        let id = format!("csv-{}-{}", self.now, index);
+
+        // 親は INDENT (深さ) か 親ID の列から決める
+        let mut parent_id = None;
+        if let Some(depth) = get(&columns.indent).and_then(|v| v.parse::<usize>().ok()) {
+            let depth = depth.max(1);
+            self.indent_parents.truncate(depth - 1);
+            parent_id = self.indent_parents.last().cloned();
+            self.indent_parents.push(id.clone());
+        }
+        if let Some(row_id) = get(&columns.row_id) {
+            self.row_ids.insert(row_id, id.clone());
+        }
+        if let Some(parent) = get(&columns.parent_id) {
+            self.pending_parents.push((index, parent));
+        }
+
+        let now = self.now;
+        self.result.todos.push(Todo {
+            id,
+            text: text.clone(),
+            completed,
+            completed_at: completed.then(|| completed_at.unwrap_or(now)),
+            created_at: created_at.unwrap_or(now),
+            parent_id,
+            priority,
+            timeframe: due_date.map_or("today".to_string(), |due| {
+                ics::timeframe_for(due, None, now)
+            }),
+            due_date,
+            due_date_notification: due_date.map(|_| DueDateNotification {
+                enabled: true,
+                notify_before: 0,
+                notified_at: None,
+                follow_up_count: 0,
+            }),
+            labels: unique_labels,
+            description: get(&columns.description).unwrap_or_default(),
+            section_id,
+            order: index as f64,
+            estimated_minutes,
+            project_id,
+            ..Default::default()
+        });
+        self.report(line, CsvRowStatus::Created, &text, None);
+        if let Some(row) = self.result.report.rows.last_mut() {
+            row.warnings = warnings;
+        }
+    }
+}
+
+pub fn import_csv(
+    content: &str,
+    profile: &CsvProfile,
+    existing_projects: &[Project],
+    include_completed: bool,
+) -> Result<CsvImportResult, String> {
+    let content = content.trim_start_matches('\u{feff}');
+    let mut reader = ::csv::ReaderBuilder::new()
+        .has_headers(false)
+        .flexible(true)
+        .from_reader(content.as_bytes());
+    let mut records = reader.records();
+
+    // TickTick のように見出しの前に説明行があるファイルもあるため、タスク名の列がある行を見出しとする
+    let mut header = None;
+    for record in records.by_ref() {
+        let record = record.map_err(|e| format!("CSV読み込みエラー: {}", e))?;
+        if ColumnIndex::new(&record)
+            .find(&profile.columns.text)
+            .is_some()
+        {
+            header = Some(record);
+            break;
+        }
+    }
+    let Some(header) = header else {
+        return Err(format!(
+            "タスク名の列が見つかりません (候補: {})",
+            profile.columns.text.join(", ")
+        ));
+    };
+    let index = ColumnIndex::new(&header);
+
+    let mut context = ImportContext {
+        profile,
+        existing_projects,
+        include_completed,
+        now: Local::now().timestamp_millis(),
+        result: CsvImportResult::default(),
+        current_section: None,
+        indent_parents: Vec::new(),
+        row_ids: HashMap::new(),
+        pending_parents: Vec::new(),
+    };
+    for record in records {
+        let record = match record {
+            Ok(record) => record,
+            Err(e) => {
+                let line = e.position().map_or(0, |p| p.line());
+                let reason = format!("CSVの形式が不正です: {}", e);
+                context.report(line, CsvRowStatus::Rejected, "", Some(&reason));
+                continue;
+            }
+        };
+        if record.iter().all(|v| v.trim().is_empty()) {
+            continue;
+        }
+        let line = record.position().map_or(0, |p| p.line());
+        let get = |candidates: &[String]| {
+            let value = unescape_formula(record.get(index.find(candidates)?)?.trim());
+            (!value.is_empty()).then(|| value.to_string())
+        };
+        context.import_row(line, get);
+    }
+
+    for (child, parent) in std::mem::take(&mut context.pending_parents) {
+        context.result.todos[child].parent_id = context.row_ids.get(&parent).cloned();
+    }
+    Ok(context.result)
+}
+
+pub struct CsvState {
+    profiles: Mutex<Vec<CsvProfile>>,
+}
+
+impl CsvState {
+    pub fn load(app: &tauri::AppHandle) -> Self {
+        let profiles = crate::store::load_json(app, PROFILES_FILE).unwrap_or_else(|e| {
+            println!("[CSV Backend] プロファイル読み込み失敗: {}", e);
+            Vec::new()
+        });
+        Self {
+            profiles: Mutex::new(profiles),
+        }
+    }
+
+    fn profiles(&self) -> Vec<CsvProfile> {
+        let mut profiles = builtin_profiles();
+        profiles.extend(self.profiles.lock().unwrap().iter().cloned());
+        profiles
+    }
+
+    fn profile(&self, id: &str) -> Result<CsvProfile, String> {
+        self.profiles()
+            .into_iter()
+            .find(|p| p.id == id)
+            .ok_or_else(|| format!("プロファイルが見つかりません: {}", id))
+    }
+}
+
+#[tauri::command]
+pub fn csv_list_profiles(state: tauri::State<'_, CsvState>) -> Vec<CsvProfile> {
+    state.profiles()
+}
+
+#[tauri::command]
+pub fn csv_save_profile(
+    app: tauri::AppHandle,
+    state: tauri::State<'_, CsvState>,
+    profile: CsvProfile,
+) -> Result<CsvProfile, String> {
+    if profile.name.trim().is_empty() {
+        return Err("プロファイル名を入力してください".to_string());
+    }
+    if profile.columns.text.is_empty() {
+        return Err("タスク名の列を指定してください".to_string());
+    }
+    if builtin_profiles().iter().any(|p| p.id == profile.id) {
+        return Err("組み込みのプロファイルは変更できません".to_string());
+    }
+    let mut profile = profile;
+    if profile.id.is_empty() {
+        profile.id = format!("custom-{}", Local::now().timestamp_millis());
+    }
+
+    let mut profiles = state.profiles.lock().unwrap();
+    match profiles.iter_mut().find(|p| p.id == profile.id) {
+        Some(existing) => *existing = profile.clone(),
+        None => profiles.push(profile.clone()),
+    }
+    crate::store::save_json(&app, PROFILES_FILE, &*profiles)?;
+    Ok(profile)
+}
+
+#[tauri::command]
+pub fn csv_delete_profile(
+    app: tauri::AppHandle,
+    state: tauri::State<'_, CsvState>,
+    id: String,
+) -> Result<(), String> {
+    let mut profiles = state.profiles.lock().unwrap();
+    profiles.retain(|p| p.id != id);
+    crate::store::save_json(&app, PROFILES_FILE, &*profiles)
+}
+
+#[tauri::command]
+pub fn csv_export(
+    todos: Vec<Todo>,
+    projects: Option<Vec<Project>>,
+    sections: Option<Vec<Section>>,
+    options: Option<CsvExportOptions>,
+) -> Result<String, String> {
+    export_csv(
+        &todos,
+        &projects.unwrap_or_default(),
+        &sections.unwrap_or_default(),
+        &options.unwrap_or_default(),
+    )
+}
+
+// 取り込みを行わずに、各行が作成・スキップ・却下のどれになるかを返す
+#[tauri::command]
+pub fn csv_preview(
+    state: tauri::State<'_, CsvState>,
+    todos: tauri::State<'_, crate::todos::TodoState>,
+    content: String,
+    profile_id: String,
+    include_completed: Option<bool>,
+) -> Result<CsvImportReport, String> {
+    let profile = state.profile(&profile_id)?;
+    let result = import_csv(
+        &content,
+        &profile,
+        &todos.projects(),
+        include_completed.unwrap_or(true),
+    )?;
+    Ok(result.report)
+}
+
+#[tauri::command]
+pub fn csv_import(
+    state: tauri::State<'_, CsvState>,
+    todos: tauri::State<'_, crate::todos::TodoState>,
+    content: String,
+    profile_id: String,
+    include_completed: Option<bool>,
+) -> Result<CsvImportResult, String> {
+    let profile = state.profile(&profile_id)?;
+    import_csv(
+        &content,
+        &profile,
+        &todos.projects(),
+        include_completed.unwrap_or(true),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn guards_cells_that_look_like_formulas() {
+        let todos: Vec<Todo> = [
+            "=HYPERLINK(\"http://example.com\")",
+            "+1",
+            "-メモ",
+            "@SUM(A1)",
+            "\t=1+1",
+            "\r=1+1",
+            "a=b",
+        ]
+        .iter()
+        .enumerate()
+        .map(|(i, text)| Todo {
+            id: format!("t{}", i),
+            text: text.to_string(),
+            order: i as f64,
+            ..Default::default()
+        })
+        .collect();
+        let options = CsvExportOptions {
+            columns: Some(vec![CsvColumn::Text]),
+            include_completed: None,
+        };
+        let content = export_csv(&todos, &[], &[], &options).unwrap();
+        let cells: Vec<&str> = content
+            .trim_start_matches('\u{feff}')
+            .lines()
+            .skip(1)
+            .collect();
+        assert_eq!(
+            cells,
+            [
+                "\"'=HYPERLINK(\"\"http://example.com\"\")\"",
+                "'+1",
+                "'-メモ",
+                "'@SUM(A1)",
+                "'\t=1+1",
+                "\"'\r=1+1\"",
+                "a=b"
+            ]
+        );
+
+        let imported = import_csv(&content, &builtin_profiles()[0], &[], true).unwrap();
+        let texts: Vec<&str> = imported.todos.iter().map(|t| t.text.as_str()).collect();
+        let originals: Vec<&str> = todos.iter().map(|t| t.text.as_str()).collect();
+        assert_eq!(texts, originals);
+    }
+
+    fn profile(id: &str) -> CsvProfile {
+        builtin_profiles().into_iter().find(|p| p.id == id).unwrap()
+    }
+
+    fn counts(report: &CsvImportReport) -> (usize, usize, usize) {
+        (report.created, report.skipped, report.rejected)
+    }
+
+    fn by_text<'a>(result: &'a CsvImportResult, text: &str) -> &'a Todo {
+        result.todos.iter().find(|t| t.text == text).unwrap()
+    }
+
+    #[test]
+    fn imports_todoist_sections_indents_and_priorities() {
+        let content = "\u{feff}TYPE,CONTENT,DESCRIPTION,PRIORITY,INDENT,AUTHOR,RESPONSIBLE,DATE,DATE_LANG,TIMEZONE,DURATION,DURATION_UNIT\n\
+            section,仕事,,,,,,,,,,\n\
+            task,企画書 @急ぎ @仕事,下書き,4,1,,,2024-05-20,ja,Asia/Tokyo,90,minute\n\
+            task,構成を考える,,1,2,,,,,,,\n\
+            task,見出し,,2,3,,,,,,,\n\
+            task,清書,,3,2,,,every day,,,,\n\
+            note,メモ,,,,,,,,,,\n\
+            ,,,,,,,,,,,\n\
+            section,家,,,,,,,,,,\n\
+            task,買い物,,3,1,,,,,,,\n\
+            task,,,,1,,,,,,,\n";
+        let result = import_csv(content, &profile("todoist"), &[], true).unwrap();
+        assert_eq!(counts(&result.report), (5, 3, 1));
+
+        let names: Vec<&str> = result.sections.iter().map(|s| s.name.as_str()).collect();
+        assert_eq!(names, ["仕事", "家"]);
+
+        let plan = by_text(&result, "企画書");
+        // PRIORITY は 5-n で反転する (4 が p1)
+        assert_eq!(plan.priority, 1);
+        assert_eq!(plan.labels, ["急ぎ", "仕事"]);
+        assert_eq!(plan.description, "下書き");
+        assert_eq!(plan.estimated_minutes, Some(90));
+        assert_eq!(plan.parent_id, None);
+        assert_eq!(plan.section_id.as_ref(), Some(&result.sections[0].id));
+
+        let outline = by_text(&result, "構成を考える");
+        assert_eq!(outline.priority, 4);
+        assert_eq!(outline.parent_id.as_ref(), Some(&plan.id));
+        let heading = by_text(&result, "見出し");
+        assert_eq!(heading.priority, 3);
+        assert_eq!(heading.parent_id.as_ref(), Some(&outline.id));
+        // 浅い階層に戻ると、その深さの親から数え直す
+        let fair_copy = by_text(&result, "清書");
+        assert_eq!(fair_copy.priority, 2);
+        assert_eq!(fair_copy.parent_id.as_ref(), Some(&plan.id));
+        assert_eq!(fair_copy.due_date, None);
+
+        // セクション行で階層をリセットする
+        let shopping = by_text(&result, "買い物");
+        assert_eq!(shopping.parent_id, None);
+        assert_eq!(shopping.section_id.as_ref(), Some(&result.sections[1].id));
+
+        let rows: Vec<(CsvRowStatus, Option<&str>)> = result
+            .report
+            .rows
+            .iter()
+            .map(|r| (r.status, r.reason.as_deref()))
+            .collect();
+        assert_eq!(
+            rows,
+            [
+                (CsvRowStatus::Skipped, Some("セクション行")),
+                (CsvRowStatus::Created, None),
+                (CsvRowStatus::Created, None),
+                (CsvRowStatus::Created, None),
+                (CsvRowStatus::Created, None),
+                (CsvRowStatus::Skipped, Some("タスク以外の行 (note)")),
+                (CsvRowStatus::Skipped, Some("セクション行")),
+                (CsvRowStatus::Created, None),
+                (CsvRowStatus::Rejected, Some("タスク名が空です")),
+            ]
+        );
+        assert_eq!(
+            result.report.rows[4].warnings,
+            ["期限を解釈できません: every day"]
+        );
+        assert_eq!(result.report.rows[1].line, 3);
+    }
+
+    #[test]
+    fn imports_ticktick_parents_and_lists() {
+        let content = "\"Date: 2024-05-20+0900\"\n\
+            \"Version: 7.1\"\n\
+            \"Folder Name\",\"List Name\",\"Title\",\"Kind\",\"Tags\",\"Content\",\"Is Check list\",\"Start Date\",\"Due Date\",\"Reminder\",\"Repeat\",\"Priority\",\"Status\",\"Created Time\",\"Completed Time\",\"Order\",\"Timezone\",\"Is All Day\",\"Is Floating\",\"Column Name\",\"Column Order\",\"View Mode\",\"taskId\",\"parentId\"\n\
+            \"\",\"仕事\",\"子タスク\",\"TEXT\",\"\",\"\",\"N\",\"\",\"\",\"\",\"\",\"0\",\"0\",\"2024-05-01T09:00:00+0000\",\"\",\"1\",\"Asia/Tokyo\",\"false\",\"false\",\"\",\"\",\"list\",\"2\",\"1\"\n\
+            \"\",\"仕事\",\"親タスク\",\"CHECKLIST\",\"a, b\",\"メモ\",\"Y\",\"\",\"2024-05-20T15:00:00+0000\",\"\",\"\",\"5\",\"0\",\"\",\"\",\"0\",\"Asia/Tokyo\",\"false\",\"false\",\"未着手\",\"\",\"list\",\"1\",\"\"\n\
+            \"\",\"個人\",\"完了した\",\"TEXT\",\"\",\"\",\"N\",\"\",\"\",\"\",\"\",\"3\",\"2\",\"\",\"2024-05-02T00:00:00+0000\",\"2\",\"Asia/Tokyo\",\"false\",\"false\",\"\",\"\",\"list\",\"3\",\"\"\n\
+            \"\",\"個人\",\"ノート\",\"NOTE\",\"\",\"\",\"N\",\"\",\"\",\"\",\"\",\"1\",\"0\",\"\",\"\",\"3\",\"Asia/Tokyo\",\"false\",\"false\",\"\",\"\",\"list\",\"4\",\"\"\n\
+            \"\",\"個人\",\"孤児\",\"TEXT\",\"\",\"\",\"N\",\"\",\"\",\"\",\"\",\"1\",\"0\",\"\",\"\",\"4\",\"Asia/Tokyo\",\"false\",\"false\",\"\",\"\",\"list\",\"5\",\"99\"\n";
+        let existing = [Project {
+            id: "p-work".to_string(),
+            name: "仕事".to_string(),
+            ..Default::default()
+        }];
+        let result = import_csv(content, &profile("ticktick"), &existing, true).unwrap();
+        assert_eq!(counts(&result.report), (4, 1, 0));
+
+        // 親の行が後にあっても parentId で結び付ける
+        let parent = by_text(&result, "親タスク");
+        let child = by_text(&result, "子タスク");
+        assert_eq!(child.parent_id.as_ref(), Some(&parent.id));
+        assert_eq!(by_text(&result, "孤児").parent_id, None);
+
+        assert_eq!(parent.priority, 1);
+        assert_eq!(child.priority, 4);
+        assert_eq!(by_text(&result, "孤児").priority, 3);
+        assert_eq!(parent.labels, ["a", "b"]);
+        assert_eq!(parent.description, "メモ");
+        assert_eq!(parent.due_date, Some(1_716_217_200_000));
+        assert_eq!(child.created_at, 1_714_554_000_000);
+
+        // 既存のプロジェクトは名前で使い、ないものだけ作る
+        assert_eq!(parent.project_id.as_deref(), Some("p-work"));
+        let names: Vec<&str> = result.projects.iter().map(|p| p.name.as_str()).collect();
+        assert_eq!(names, ["個人"]);
+        let names: Vec<&str> = result.sections.iter().map(|s| s.name.as_str()).collect();
+        assert_eq!(names, ["未着手"]);
+
+        let done = by_text(&result, "完了した");
+        assert!(done.completed);
+        assert_eq!(done.completed_at, Some(1_714_608_000_000));
+        assert_eq!(done.priority, 2);
+
+        let skipped = import_csv(content, &profile("ticktick"), &existing, false).unwrap();
+        assert_eq!(counts(&skipped.report), (3, 2, 0));
+    }
+
+    #[test]
+    fn imports_microsoft_todo_exports() {
+        let content = "Subject,Notes,Importance,Due Date,Categories,Status,Folder\n\
+            報告書,\"1行目\n2行目\",High,2024-05-20,仕事; 急ぎ,Not Started,タスク\n\
+            牛乳,,Low,5/21/2024,,Completed,買い物\n\
+            予約,,Urgent!,2024/13/01,,,\n\
+            '=cmd,,Normal,,,,\n";
+        let result = import_csv(content, &profile("microsoft-todo"), &[], false).unwrap();
+        assert_eq!(counts(&result.report), (3, 1, 0));
+
+        let report = by_text(&result, "報告書");
+        assert_eq!(report.priority, 1);
+        assert_eq!(report.description, "1行目\n2行目");
+        assert_eq!(report.labels, ["仕事", "急ぎ"]);
+        assert!(!report.completed);
+        assert!(report.due_date.is_some());
+
+        let reservation = by_text(&result, "予約");
+        assert_eq!(reservation.priority, 4);
+        assert_eq!(
+            result.report.rows[2].warnings,
+            [
+                "期限を解釈できません: 2024/13/01",
+                "優先度を解釈できません: Urgent!"
+            ]
+        );
+        // エクスポート時に付けた ' は外す
+        assert_eq!(by_text(&result, "=cmd").priority, 4);
+        assert_eq!(result.report.rows[1].reason.as_deref(), Some("完了済み"));
+    }
+
+    #[test]
+    fn requires_a_title_column() {
+        let error = import_csv("a,b\n1,2\n", &profile("todoist"), &[], true)
+            .err()
+            .unwrap();
+        assert!(error.contains("CONTENT"), "{}", error);
+    }
+}
@@ -0,0 +1,614 @@
+// タスクの依存関係 (「このタスクは〇〇が終わるまで着手できない」)
+// 依存の辺はバックエンドで持ち、追加するときに循環を検出する
+// ブロック状態はタスクの完了状況から都度計算し、同期でブロックが外れたタスクがあれば通知する
+
+use chrono::Local;
+use std::collections::{BTreeMap, HashMap, HashSet, VecDeque};
+use std::sync::Mutex;
+use tauri::{Emitter, Manager};
+
+use crate::models::Todo;
+use crate::notifier::{NotificationContext, NotifierState};
+
+const DEPENDENCIES_FILE: &str = "dependencies.json";
+
+// task_id は depends_on が完了するまでブロックされる
+#[derive(serde::Serialize, serde::Deserialize, Clone, Debug, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct Dependency {
+    pub task_id: String,
+    pub depends_on: String,
+    pub created_at: i64,
+}
+
+#[derive(serde::Serialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct DependencyStatus {
+    pub task_id: String,
+    pub blocked: bool,
+    // 依存している全タスク
+    pub depends_on: Vec<String>,
+    // そのうちまだ終わっていないタスク
+    pub waiting_on: Vec<String>,
+    // このタスクを待っているタスク
+    pub blocking: Vec<String>,
+}
+
+#[derive(serde::Serialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct GraphNode {
+    pub id: String,
+    pub text: String,
+    pub completed: bool,
+    pub blocked: bool,
+    // 依存の段数 (依存のないタスクが 0)。描画時の列に使う
+    pub level: usize,
+    // 別のプロジェクトのタスク
+    pub external: bool,
+}
+
+#[derive(serde::Serialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct GraphEdge {
+    // from が終わると to に着手できる
+    pub from: String,
+    pub to: String,
+}
+
+#[derive(serde::Serialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct DependencyGraph {
+    // 依存される側が先に来る順
+    pub nodes: Vec<GraphNode>,
+    pub edges: Vec<GraphEdge>,
+}
+
+#[derive(serde::Serialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct UnblockedTask {
+    pub id: String,
+    pub text: String,
+    // 完了してブロックを外したタスク
+    pub unblocked_by: Vec<String>,
+}
+
+// タスクが終わっていれば (完了・アーカイブ・削除済み) 後続をブロックしない
+fn is_open(todos: &HashMap<&str, &Todo>, id: &str) -> bool {
+    todos.get(id).is_some_and(|t| t.is_active())
+}
+
+fn by_id(todos: &[Todo]) -> HashMap<&str, &Todo> {
+    todos.iter().map(|t| (t.id.as_str(), t)).collect()
+}
+
+// from から依存をたどって to に着くまでの経路 (from → … → to)
+fn find_path(edges: &[Dependency], from: &str, to: &str) -> Option<Vec<String>> {
+    let mut previous: HashMap<&str, &str> = HashMap::new();
+    let mut queue = VecDeque::from([from]);
+    let mut seen = HashSet::from([from]);
+    while let Some(current) = queue.pop_front() {
+        if current == to {
+            let mut path = vec![to.to_string()];
+            let mut node = to;
+            while let Some(p) = previous.get(node) {
+                path.push(p.to_string());
+                node = p;
+            }
+            path.reverse();
+            return Some(path);
+        }
+        for edge in edges.iter().filter(|e| e.task_id == current) {
+            if seen.insert(edge.depends_on.as_str()) {
+                previous.insert(edge.depends_on.as_str(), current);
+                queue.push_back(&edge.depends_on);
+            }
+        }
+    }
+    None
+}
+
+// task_id → depends_on の辺を加えると循環するなら、その循環 (task_id から task_id まで)
+pub fn find_cycle(edges: &[Dependency], task_id: &str, depends_on: &str) -> Option<Vec<String>> {
+    if task_id == depends_on {
+        return Some(vec![task_id.to_string(), task_id.to_string()]);
+    }
+    let mut cycle = vec![task_id.to_string()];
+    cycle.extend(find_path(edges, depends_on, task_id)?);
+    Some(cycle)
+}
+
+// 両端のタスクが残っている辺 (削除されたタスクの辺は循環の判定にも使わない)
+fn live_edges(edges: &[Dependency], ids: &HashSet<&str>) -> Vec<Dependency> {
+    edges
+        .iter()
+        .filter(|e| ids.contains(e.task_id.as_str()) && ids.contains(e.depends_on.as_str()))
+        .cloned()
+        .collect()
+}
+
+// ブロックされているタスクと、待っている未完了のタスク
+pub fn blocked(edges: &[Dependency], todos: &[Todo]) -> BTreeMap<String, Vec<String>> {
+    let todos = by_id(todos);
+    let mut blocked: BTreeMap<String, Vec<String>> = BTreeMap::new();
+    for edge in edges {
+        if is_open(&todos, &edge.task_id) && is_open(&todos, &edge.depends_on) {
+            blocked
+                .entry(edge.task_id.clone())
+                .or_default()
+                .push(edge.depends_on.clone());
+        }
+    }
+    blocked
+}
+
+// 依存される側が先に来る順に並べる (循環は追加時に防いでいるが、残っていれば末尾に回す)
+pub fn topological_order(edges: &[Dependency], ids: &[String]) -> Vec<String> {
+    let members: HashSet<&str> = ids.iter().map(String::as_str).collect();
+    let edges: Vec<&Dependency> = edges
+        .iter()
+        .filter(|e| members.contains(e.task_id.as_str()) && members.contains(e.depends_on.as_str()))
+        .collect();
+    let mut indegree: HashMap<&str, usize> = ids.iter().map(|id| (id.as_str(), 0)).collect();
+    for edge in &edges {
+        *indegree.entry(edge.task_id.as_str()).or_default() += 1;
+    }
+
+    let mut queue: VecDeque<&str> = ids
+        .iter()
+        .map(String::as_str)
+        .filter(|id| indegree[id] == 0)
+        .collect();
+    let mut order: Vec<String> = Vec::new();
+    while let Some(id) = queue.pop_front() {
+        order.push(id.to_string());
+        for edge in edges.iter().filter(|e| e.depends_on == id) {
+            let count = indegree.entry(edge.task_id.as_str()).or_default();
+            *count -= 1;
+            if *count == 0 {
+                queue.push_back(&edge.task_id);
+            }
+        }
+    }
+    let placed: HashSet<String> = order.iter().cloned().collect();
+    order.extend(ids.iter().filter(|id| !placed.contains(*id)).cloned());
+    order
+}
+
+// プロジェクトの依存グラフ (project_id が None なら全タスク)
+// 別のプロジェクトのタスクとつながっている場合は、そのタスクも external として含める
+pub fn project_graph(
+    edges: &[Dependency],
+    todos: &[Todo],
+    project_id: Option<&str>,
+) -> DependencyGraph {
+    let lookup = by_id(todos);
+    let in_project = |todo: &Todo| project_id.is_none() || todo.project_id.as_deref() == project_id;
+    let mut ids: Vec<String> = todos
+        .iter()
+        .filter(|t| in_project(t) && !t.archived)
+        .map(|t| t.id.clone())
+        .collect();
+    let members: HashSet<String> = ids.iter().cloned().collect();
+
+    let edges: Vec<&Dependency> = edges
+        .iter()
+        .filter(|e| lookup.contains_key(e.task_id.as_str()))
+        .filter(|e| lookup.contains_key(e.depends_on.as_str()))
+        .filter(|e| members.contains(&e.task_id) || members.contains(&e.depends_on))
+        .collect();
+    for edge in &edges {
+        for id in [&edge.task_id, &edge.depends_on] {
+            if !ids.contains(id) {
+                ids.push(id.clone());
+            }
+        }
+    }
+    let relevant: Vec<Dependency> = edges.iter().map(|e| (*e).clone()).collect();
+
+    let blocked = blocked(&relevant, todos);
+    let mut levels: HashMap<String, usize> = HashMap::new();
+    let mut nodes = Vec::new();
+    for id in topological_order(&relevant, &ids) {
+        let level = relevant
+            .iter()
+            .filter(|e| e.task_id == id)
+            .filter_map(|e| levels.get(&e.depends_on))
+            .map(|l| l + 1)
+            .max()
+            .unwrap_or(0);
+        levels.insert(id.clone(), level);
+        let todo = lookup[id.as_str()];
+        nodes.push(GraphNode {
+            text: todo.text.clone(),
+            completed: todo.completed,
+            blocked: blocked.contains_key(&id),
+            level,
+            external: !members.contains(&id),
+            id,
+        });
+    }
+
+    DependencyGraph {
+        nodes,
+        edges: relevant
+            .into_iter()
+            .map(|e| GraphEdge {
+                from: e.depends_on,
+                to: e.task_id,
+            })
+            .collect(),
+    }
+}
+
+// 同期の前後でブロックが外れたタスク (まだ未完了のものだけ)
+pub fn newly_unblocked(
+    edges: &[Dependency],
+    before: &[Todo],
+    after: &[Todo],
+) -> Vec<UnblockedTask> {
+    let was_blocked = blocked(edges, before);
+    let now_blocked = blocked(edges, after);
+    let after_by_id = by_id(after);
+    was_blocked
+        .into_iter()
+        .filter(|(id, _)| !now_blocked.contains_key(id) && is_open(&after_by_id, id))
+        .map(|(id, waiting)| UnblockedTask {
+            text: after_by_id[id.as_str()].text.clone(),
+            id,
+            unblocked_by: waiting,
+        })
+        .collect()
+}
+
+pub struct DependencyState {
+    edges: Mutex<Vec<Dependency>>,
+}
+
+impl DependencyState {
+    pub fn load(app: &tauri::AppHandle) -> Self {
+        let edges = crate::store::load_json(app, DEPENDENCIES_FILE).unwrap_or_else(|e| {
+            println!("[Dependency Backend] 読み込み失敗: {}", e);
+            Vec::new()
+        });
+        Self {
+            edges: Mutex::new(edges),
+        }
+    }
+
+    pub fn edges(&self) -> Vec<Dependency> {
+        self.edges.lock().unwrap().clone()
+    }
+
+    fn add(
+        &self,
+        app: &tauri::AppHandle,
+        todos: &[Todo],
+        pairs: &[(String, String)],
+    ) -> Result<Vec<Dependency>, String> {
+        let lookup = by_id(todos);
+        let text = |id: &str| lookup.get(id).map_or(id, |t| t.text.as_str()).to_string();
+        let mut edges = self.edges.lock().unwrap();
+        // 途中で失敗したら1件も追加しない
+        let ids: HashSet<&str> = lookup.keys().copied().collect();
+        let mut updated = live_edges(&edges, &ids);
+        let mut added = Vec::new();
+        let now = Local::now().timestamp_millis();
+        for (task_id, depends_on) in pairs {
+            for id in [task_id, depends_on] {
+                if !lookup.contains_key(id.as_str()) {
+                    return Err(format!("タスクが見つかりません: {}", id));
+                }
+            }
+            if let Some(existing) = updated
+                .iter()
+                .find(|e| &e.task_id == task_id && &e.depends_on == depends_on)
+            {
+                added.push(existing.clone());
+                continue;
+            }
+            if task_id == depends_on {
+                return Err("タスクは自分自身に依存できません".to_string());
+            }
+            if let Some(cycle) = find_cycle(&updated, task_id, depends_on) {
+                let names: Vec<String> = cycle.iter().map(|id| text(id)).collect();
+                return Err(format!("依存関係が循環します: {}", names.join(" → ")));
+            }
+            let edge = Dependency {
+                task_id: task_id.clone(),
+                depends_on: depends_on.clone(),
+                created_at: now,
+            };
+            updated.push(edge.clone());
+            added.push(edge);
+        }
+        crate::store::save_json(app, DEPENDENCIES_FILE, &updated)?;
+        *edges = updated;
+        Ok(added)
+    }
+
+    // タスクが同期されたときに呼ぶ。削除されたタスクの辺を消し、ブロックが外れたタスクを通知する
+    pub fn sync(&self, app: &tauri::AppHandle, before: &[Todo], after: &[Todo]) {
+        let mut edges = self.edges.lock().unwrap();
+        // 削除されたタスクを待っていたタスクも対象にするため、辺を消す前に求める
+        let unblocked = newly_unblocked(&edges, before, after);
+        let ids: HashSet<&str> = after.iter().map(|t| t.id.as_str()).collect();
+        let count = edges.len();
+        *edges = live_edges(&edges, &ids);
+        if edges.len() != count {
+            println!(
+                "[Dependency Backend] 削除されたタスクの依存を {} 件削除",
+                count - edges.len()
+            );
+            if let Err(e) = crate::store::save_json(app, DEPENDENCIES_FILE, &*edges) {
+                println!("[Dependency Backend] 保存失敗: {}", e);
+            }
+        }
+
+        drop(edges);
+        if unblocked.is_empty() {
+            return;
+        }
+        notify_unblocked(app, &unblocked, after);
+    }
+}
+
+fn notify_unblocked(app: &tauri::AppHandle, unblocked: &[UnblockedTask], todos: &[Todo]) {
+    let lookup = by_id(todos);
+    let notifier = app.state::<NotifierState>();
+    for task in unblocked {
+        let finished: Vec<String> = task
+            .unblocked_by
+            .iter()
+            .filter_map(|id| lookup.get(id.as_str()))
+            .map(|t| format!("「{}」", t.text))
+            .collect();
+        let message = if finished.is_empty() {
+            "待っていたタスクがなくなりました".to_string()
+        } else {
+            format!("{}が終わりました", finished.join(""))
+        };
+        println!(
+            "[Dependency Backend] ブロック解除: {} ({})",
+            task.text, message
+        );
+        let title = "🔓 着手できるようになりました";
+        if let Err(e) =
+            crate::show_notification(title.to_string(), format!("{}\n{}", task.text, message))
+        {
+            println!("[Dependency Backend] 通知失敗: {}", e);
+        }
+        let context = NotificationContext {
+            kind: "unblocked",
+            title,
+            message: &message,
+            todo: lookup.get(task.id.as_str()).copied(),
+        };
+        if let Err(e) = notifier.notify(app, &context, None) {
+            println!("[Dependency Backend] 通知失敗: {}", e);
+        }
+    }
+    if let Err(e) = app.emit("tasks-unblocked", &unblocked) {
+        println!("[Dependency Backend] イベント送信失敗: {}", e);
+    }
+}
+
+// depends_on が終わるまで task_id をブロックする
+#[tauri::command]
+pub fn dependency_add(
+    app: tauri::AppHandle,
+    state: tauri::State<'_, DependencyState>,
+    todos: tauri::State<'_, crate::todos::TodoState>,
+    task_id: String,
+    depends_on: String,
+) -> Result<Dependency, String> {
+    let added = state.add(&app, &todos.all(), &[(task_id, depends_on)])?;
+    added
+        .into_iter()
+        .next()
+        .ok_or_else(|| "依存関係を追加できませんでした".to_string())
+}
+
+// 計画のタスクを順番どおりにつなぐ (2番目は1番目に、3番目は2番目に依存する)
+#[tauri::command]
+pub fn dependency_add_chain(
+    app: tauri::AppHandle,
+    state: tauri::State<'_, DependencyState>,
+    todos: tauri::State<'_, crate::todos::TodoState>,
+    task_ids: Vec<String>,
+) -> Result<Vec<Dependency>, String> {
+    let pairs: Vec<(String, String)> = task_ids
+        .windows(2)
+        .map(|pair| (pair[1].clone(), pair[0].clone()))
+        .collect();
+    state.add(&app, &todos.all(), &pairs)
+}
+
+#[tauri::command]
+pub fn dependency_remove(
+    app: tauri::AppHandle,
+    state: tauri::State<'_, DependencyState>,
+    task_id: String,
+    depends_on: String,
+) -> Result<(), String> {
+    let mut edges = state.edges.lock().unwrap();
+    edges.retain(|e| !(e.task_id == task_id && e.depends_on == depends_on));
+    crate::store::save_json(&app, DEPENDENCIES_FILE, &*edges)
+}
+
+#[tauri::command]
+pub fn dependency_status(
+    state: tauri::State<'_, DependencyState>,
+    todos: tauri::State<'_, crate::todos::TodoState>,
+    task_id: String,
+) -> DependencyStatus {
+    let edges = state.edges();
+    let all = todos.all();
+    let lookup = by_id(&all);
+    let depends_on: Vec<String> = edges
+        .iter()
+        .filter(|e| e.task_id == task_id)
+        .map(|e| e.depends_on.clone())
+        .collect();
+    let waiting_on: Vec<String> = depends_on
+        .iter()
+        .filter(|id| is_open(&lookup, id))
+        .cloned()
+        .collect();
+    DependencyStatus {
+        blocked: is_open(&lookup, &task_id) && !waiting_on.is_empty(),
+        blocking: edges
+            .iter()
+            .filter(|e| e.depends_on == task_id)
+            .map(|e| e.task_id.clone())
+            .collect(),
+        task_id,
+        depends_on,
+        waiting_on,
+    }
+}
+
+// ブロックされている全タスク (タスクID → 待っているタスクID)
+#[tauri::command]
+pub fn dependency_blocked(
+    state: tauri::State<'_, DependencyState>,
+    todos: tauri::State<'_, crate::todos::TodoState>,
+) -> BTreeMap<String, Vec<String>> {
+    blocked(&state.edges(), &todos.all())
+}
+
+#[tauri::command]
+pub fn dependency_graph(
+    state: tauri::State<'_, DependencyState>,
+    todos: tauri::State<'_, crate::todos::TodoState>,
+    project_id: Option<String>,
+) -> DependencyGraph {
+    project_graph(&state.edges(), &todos.all(), project_id.as_deref())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn todo(id: &str) -> Todo {
+        Todo {
+            id: id.to_string(),
+            text: id.to_string(),
+            ..Default::default()
+        }
+    }
+
+    fn edge(task_id: &str, depends_on: &str) -> Dependency {
+        Dependency {
+            task_id: task_id.to_string(),
+            depends_on: depends_on.to_string(),
+            created_at: 0,
+        }
+    }
+
+    #[test]
+    fn unblocks_when_dependency_is_completed_or_deleted() {
+        let edges = vec![edge("a", "b"), edge("c", "d"), edge("e", "f")];
+        let before = ["a", "b", "c", "d", "e", "f"].map(todo).to_vec();
+        let mut done = todo("b");
+        done.completed = true;
+        let after = vec![todo("a"), done, todo("c"), todo("e"), todo("f")];
+
+        let unblocked = newly_unblocked(&edges, &before, &after);
+        let ids: Vec<(&str, &[String])> = unblocked
+            .iter()
+            .map(|u| (u.id.as_str(), u.unblocked_by.as_slice()))
+            .collect();
+        assert_eq!(
+            ids,
+            [("a", &["b".to_string()][..]), ("c", &["d".to_string()][..])]
+        );
+    }
+
+    fn ids(ids: &[&str]) -> Vec<String> {
+        ids.iter().map(|id| id.to_string()).collect()
+    }
+
+    #[test]
+    fn finds_cycles_with_their_path() {
+        // 自分自身への辺
+        assert_eq!(find_cycle(&[], "a", "a"), Some(ids(&["a", "a"])));
+        // a → b がある所に b → a
+        let two = [edge("a", "b")];
+        assert_eq!(find_cycle(&two, "b", "a"), Some(ids(&["b", "a", "b"])));
+        // a → b → c がある所に c → a
+        let three = [edge("a", "b"), edge("b", "c")];
+        assert_eq!(
+            find_cycle(&three, "c", "a"),
+            Some(ids(&["c", "a", "b", "c"]))
+        );
+        // 同じ向きの辺や分岐の合流は循環ではない
+        assert_eq!(find_cycle(&three, "a", "c"), None);
+        let diamond = [
+            edge("a", "b"),
+            edge("a", "c"),
+            edge("b", "d"),
+            edge("c", "d"),
+        ];
+        assert_eq!(find_cycle(&diamond, "a", "d"), None);
+        assert_eq!(find_cycle(&diamond, "e", "a"), None);
+    }
+
+    #[test]
+    fn reports_the_shortest_cycle() {
+        let edges = [
+            edge("a", "b"),
+            edge("b", "c"),
+            edge("c", "d"),
+            edge("a", "d"),
+        ];
+        assert_eq!(find_cycle(&edges, "d", "a"), Some(ids(&["d", "a", "d"])));
+        assert_eq!(find_path(&edges, "a", "a"), Some(ids(&["a"])));
+        assert_eq!(find_path(&edges, "d", "a"), None);
+    }
+
+    #[test]
+    fn edges_to_deleted_tasks_do_not_form_cycles() {
+        // x は削除済み (同期前で辺が残っている)
+        let edges = [edge("a", "x"), edge("x", "b")];
+        assert!(find_cycle(&edges, "b", "a").is_some());
+        let live: HashSet<&str> = HashSet::from(["a", "b"]);
+        let edges = live_edges(&edges, &live);
+        assert!(edges.is_empty());
+        assert_eq!(find_cycle(&edges, "b", "a"), None);
+    }
+
+    #[test]
+    fn blocked_only_by_open_tasks() {
+        let edges = [
+            edge("a", "b"),
+            edge("a", "c"),
+            edge("a", "gone"),
+            edge("d", "a"),
+        ];
+        let mut c = todo("c");
+        c.completed = true;
+        let todos = vec![todo("a"), todo("b"), c, todo("d")];
+        let blocked = blocked(&edges, &todos);
+        assert_eq!(
+            blocked.into_iter().collect::<Vec<_>>(),
+            [
+                ("a".to_string(), ids(&["b"])),
+                ("d".to_string(), ids(&["a"])),
+            ]
+        );
+    }
+
+    #[test]
+    fn orders_dependencies_first_and_leftover_cycles_last() {
+        let edges = [
+            edge("c", "b"),
+            edge("b", "a"),
+            edge("x", "y"),
+            edge("y", "x"),
+        ];
+        assert_eq!(
+            topological_order(&edges, &ids(&["c", "x", "b", "y", "a"])),
+            ids(&["a", "b", "c", "x", "y"])
+        );
+    }
+}
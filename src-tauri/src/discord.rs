@@ -0,0 +1,135 @@
+// Discord Bot によるDM送信 (送信自体はアウトボックス経由で行う)
+
+use std::sync::Mutex;
+
+const DISCORD_API_URL: &str = "https://discord.com/api/v10";
+const SETTINGS_FILE: &str = "discord-settings.json";
+
+#[derive(serde::Serialize, serde::Deserialize, Clone, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct DiscordSettings {
+    pub bot_token: String,
+    pub user_id: String,
+    pub enabled: bool,
+}
+
+// 送信失敗の種類 (再試行するかどうかの判断に使う)
+pub enum DeliveryError {
+    // ネットワーク断・5xx・レート制限など、後で再送すれば成功しうるもの
+    Retryable(String),
+    // 認証エラーや不正なリクエストなど、再送しても成功しないもの
+    Permanent(String),
+}
+
+impl DeliveryError {
+    pub fn from_status(status: reqwest::StatusCode, body: String) -> Self {
+        let message = format!("送信失敗 ({}): {}", status, body);
+        if status.is_server_error() || status == reqwest::StatusCode::TOO_MANY_REQUESTS {
+            DeliveryError::Retryable(message)
+        } else {
+            DeliveryError::Permanent(message)
+        }
+    }
+
+    pub fn message(&self) -> &str {
+        match self {
+            DeliveryError::Retryable(message) | DeliveryError::Permanent(message) => message,
+        }
+    }
+}
+
+pub struct DiscordState {
+    settings: Mutex<DiscordSettings>,
+}
+
+impl DiscordState {
+    pub fn load(app: &tauri::AppHandle) -> Self {
+        let settings = crate::store::load_json(app, SETTINGS_FILE).unwrap_or_else(|e| {
+            println!("[Discord Backend] 設定読み込み失敗: {}", e);
+            DiscordSettings::default()
+        });
+        Self {
+            settings: Mutex::new(settings),
+        }
+    }
+
+    pub fn settings(&self) -> DiscordSettings {
+        self.settings.lock().unwrap().clone()
+    }
+}
+
+async fn create_dm_channel(
+    client: &reqwest::Client,
+    settings: &DiscordSettings,
+) -> Result<String, DeliveryError> {
+    let response = client
+        .post(format!("{}/users/@me/channels", DISCORD_API_URL))
+        .header("Authorization", format!("Bot {}", settings.bot_token))
+        .json(&serde_json::json!({ "recipient_id": settings.user_id }))
+        .send()
+        .await
+        .map_err(|e| DeliveryError::Retryable(format!("Discordリクエストエラー: {}", e)))?;
+
+    if !response.status().is_success() {
+        let status = response.status();
+        let body = response.text().await.unwrap_or_default();
+        return Err(DeliveryError::from_status(status, body));
+    }
+
+    let data: serde_json::Value = response
+        .json()
+        .await
+        .map_err(|e| DeliveryError::Retryable(format!("レスポンス解析エラー: {}", e)))?;
+    data["id"]
+        .as_str()
+        .map(str::to_string)
+        .ok_or_else(|| DeliveryError::Permanent("DMチャンネルIDが取得できません".to_string()))
+}
+
+// payload は Discord の Create Message の本文 ({ "embeds": [...] } など)
+pub async fn send_dm(
+    client: &reqwest::Client,
+    settings: &DiscordSettings,
+    payload: &serde_json::Value,
+) -> Result<(), DeliveryError> {
+    if !settings.enabled || settings.bot_token.is_empty() || settings.user_id.is_empty() {
+        return Err(DeliveryError::Permanent(
+            "Discord通知が設定されていません".to_string(),
+        ));
+    }
+
+    let channel_id = create_dm_channel(client, settings).await?;
+    let response = client
+        .post(format!(
+            "{}/channels/{}/messages",
+            DISCORD_API_URL, channel_id
+        ))
+        .header("Authorization", format!("Bot {}", settings.bot_token))
+        .json(payload)
+        .send()
+        .await
+        .map_err(|e| DeliveryError::Retryable(format!("Discordリクエストエラー: {}", e)))?;
+
+    if !response.status().is_success() {
+        let status = response.status();
+        let body = response.text().await.unwrap_or_default();
+        return Err(DeliveryError::from_status(status, body));
+    }
+    Ok(())
+}
+
+#[tauri::command]
+pub fn discord_get_settings(state: tauri::State<'_, DiscordState>) -> DiscordSettings {
+    state.settings()
+}
+
+#[tauri::command]
+pub fn discord_set_settings(
+    app: tauri::AppHandle,
+    state: tauri::State<'_, DiscordState>,
+    settings: DiscordSettings,
+) -> Result<(), String> {
+    let mut current = state.settings.lock().unwrap();
+    *current = settings;
+    crate::store::save_json(&app, SETTINGS_FILE, &*current)
+}
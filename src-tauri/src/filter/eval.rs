@@ -0,0 +1,77 @@
+// フィルタ式をタスクに当てはめる
+// 日付の比較は端末のローカル日付で行う (期限切れだけは時刻まで比べる)
+
+use chrono::{DateTime, Duration, Local, NaiveDate};
+
+use super::parser::{DateOp, Expr, Predicate};
+use crate::models::{Project, Todo};
+use crate::search::fold;
+
+pub struct EvalContext<'a> {
+    pub now: i64,
+    pub today: NaiveDate,
+    pub projects: &'a [Project],
+}
+
+fn local_date(millis: i64) -> Option<NaiveDate> {
+    DateTime::from_timestamp_millis(millis).map(|d| d.with_timezone(&Local).date_naive())
+}
+
+fn compare(date: Option<NaiveDate>, op: DateOp, target: NaiveDate) -> bool {
+    date.is_some_and(|date| match op {
+        DateOp::Before => date < target,
+        DateOp::After => date > target,
+        DateOp::On => date == target,
+    })
+}
+
+// 大文字小文字・かなの表記ゆれを無視して比べる
+fn same(a: &str, b: &str) -> bool {
+    fold(a).to_lowercase() == fold(b).to_lowercase()
+}
+
+fn in_project(todo: &Todo, name: &str, ctx: &EvalContext) -> bool {
+    let Some(project_id) = &todo.project_id else {
+        return false;
+    };
+    ctx.projects
+        .iter()
+        .find(|p| &p.id == project_id)
+        .is_some_and(|p| p.id == name || same(&p.name, name))
+}
+
+fn predicate(predicate: &Predicate, todo: &Todo, ctx: &EvalContext) -> bool {
+    match predicate {
+        Predicate::Priority(p) => todo.priority == *p,
+        Predicate::Label(label) => todo.labels.iter().any(|l| same(l, label)),
+        Predicate::NoLabels => todo.labels.is_empty(),
+        Predicate::Project(name) => in_project(todo, name, ctx),
+        Predicate::Timeframe(timeframe) => &todo.timeframe == timeframe,
+        Predicate::Due(op, value) => compare(todo.due_date.and_then(local_date), *op, value.date),
+        Predicate::DueWithin(days) => todo
+            .due_date
+            .and_then(local_date)
+            .is_some_and(|d| d >= ctx.today && d <= ctx.today + Duration::days(*days as i64)),
+        Predicate::NoDate => todo.due_date.is_none(),
+        Predicate::Overdue => !todo.completed && todo.due_date.is_some_and(|d| d < ctx.now),
+        Predicate::Created(op, value) => compare(local_date(todo.created_at), *op, value.date),
+        Predicate::Recurring => todo.recurrence.is_some(),
+        Predicate::Completed => todo.completed,
+        Predicate::Archived => todo.archived,
+        Predicate::Text(text) => {
+            let needle = fold(text).to_lowercase();
+            [&todo.text, &todo.description]
+                .iter()
+                .any(|field| fold(field).to_lowercase().contains(&needle))
+        }
+    }
+}
+
+pub fn matches(expr: &Expr, todo: &Todo, ctx: &EvalContext) -> bool {
+    match expr {
+        Expr::And(items) => items.iter().all(|e| matches(e, todo, ctx)),
+        Expr::Or(items) => items.iter().any(|e| matches(e, todo, ctx)),
+        Expr::Not(inner) => !matches(inner, todo, ctx),
+        Expr::Is(p) => predicate(p, todo, ctx),
+    }
+}
@@ -0,0 +1,243 @@
+// フィルタ式を記号・単語・引用文字列に分ける
+// 位置はすべて文字位置 (エラー表示でそのまま入力欄の範囲に使える)
+
+use super::FilterError;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum TokenKind {
+    And,
+    Or,
+    Not,
+    Open,
+    Close,
+    Word(String),
+    Quoted(String),
+}
+
+#[derive(Debug, Clone)]
+pub struct Token {
+    pub kind: TokenKind,
+    pub start: usize,
+    pub end: usize,
+}
+
+fn symbol(c: char) -> Option<TokenKind> {
+    match c {
+        '&' | '＆' => Some(TokenKind::And),
+        '|' | '｜' => Some(TokenKind::Or),
+        '!' | '！' => Some(TokenKind::Not),
+        '(' | '（' => Some(TokenKind::Open),
+        ')' | '）' => Some(TokenKind::Close),
+        _ => None,
+    }
+}
+
+fn closing_quote(c: char) -> Option<char> {
+    match c {
+        '"' => Some('"'),
+        '“' => Some('”'),
+        '「' => Some('」'),
+        _ => None,
+    }
+}
+
+fn is_word_char(c: char) -> bool {
+    !c.is_whitespace() && symbol(c).is_none() && closing_quote(c).is_none()
+}
+
+// and / or / not は単語としても書ける
+fn keyword(word: &str) -> Option<TokenKind> {
+    match word.to_ascii_lowercase().as_str() {
+        "and" => Some(TokenKind::And),
+        "or" => Some(TokenKind::Or),
+        "not" => Some(TokenKind::Not),
+        _ => None,
+    }
+}
+
+// そのまま書くと別の字句に分かれてしまう値 (表示し直すときは引用符で囲む)
+pub fn needs_quotes(value: &str) -> bool {
+    value.is_empty()
+        || !value.chars().all(is_word_char)
+        || value.contains(':')
+        || keyword(value).is_some()
+}
+
+pub fn tokenize(input: &str) -> Result<Vec<Token>, FilterError> {
+    let chars: Vec<char> = input.chars().collect();
+    let mut tokens = Vec::new();
+    let mut i = 0;
+    while i < chars.len() {
+        let c = chars[i];
+        if c.is_whitespace() {
+            i += 1;
+            continue;
+        }
+        if let Some(kind) = symbol(c) {
+            tokens.push(Token {
+                kind,
+                start: i,
+                end: i + 1,
+            });
+            i += 1;
+            continue;
+        }
+        if let Some(close) = closing_quote(c) {
+            let Some(len) = chars[i + 1..].iter().position(|c| *c == close) else {
+                return Err(FilterError::new(
+                    "引用符が閉じられていません",
+                    i,
+                    chars.len(),
+                ));
+            };
+            tokens.push(Token {
+                kind: TokenKind::Quoted(chars[i + 1..i + 1 + len].iter().collect()),
+                start: i,
+                end: i + len + 2,
+            });
+            i += len + 2;
+            continue;
+        }
+
+        let start = i;
+        while i < chars.len() && is_word_char(chars[i]) {
+            i += 1;
+        }
+        let word: String = chars[start..i].iter().collect();
+        if let Some(kind) = keyword(&word) {
+            tokens.push(Token {
+                kind,
+                start,
+                end: i,
+            });
+            continue;
+        }
+        // 「label:仕事」は「label:」と「仕事」に分ける (値を引用符で囲んだ場合と同じ扱いにする)
+        match word.find(':') {
+            Some(colon) if colon > 0 && word[..colon].chars().all(|c| c.is_ascii_alphabetic()) => {
+                let key_end = start + colon + 1;
+                tokens.push(Token {
+                    kind: TokenKind::Word(word[..=colon].to_string()),
+                    start,
+                    end: key_end,
+                });
+                if key_end < i {
+                    tokens.push(Token {
+                        kind: TokenKind::Word(word[colon + 1..].to_string()),
+                        start: key_end,
+                        end: i,
+                    });
+                }
+            }
+            _ => tokens.push(Token {
+                kind: TokenKind::Word(word),
+                start,
+                end: i,
+            }),
+        }
+    }
+    Ok(tokens)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn kinds(input: &str) -> Vec<(TokenKind, usize, usize)> {
+        tokenize(input)
+            .unwrap()
+            .into_iter()
+            .map(|t| (t.kind, t.start, t.end))
+            .collect()
+    }
+
+    fn word(s: &str) -> TokenKind {
+        TokenKind::Word(s.to_string())
+    }
+
+    #[test]
+    fn splits_symbols_words_and_keywords() {
+        use TokenKind::*;
+        assert_eq!(
+            kinds("(p1|p2)&!done"),
+            [
+                (Open, 0, 1),
+                (word("p1"), 1, 3),
+                (Or, 3, 4),
+                (word("p2"), 4, 6),
+                (Close, 6, 7),
+                (And, 7, 8),
+                (Not, 8, 9),
+                (word("done"), 9, 13),
+            ]
+        );
+        // 全角の記号と単語の演算子
+        assert_eq!(
+            kinds("（#仕事 ｜ #家）＆ NOT 完了 and x"),
+            [
+                (Open, 0, 1),
+                (word("#仕事"), 1, 4),
+                (Or, 5, 6),
+                (word("#家"), 7, 9),
+                (Close, 9, 10),
+                (And, 10, 11),
+                (Not, 12, 15),
+                (word("完了"), 16, 18),
+                (And, 19, 22),
+                (word("x"), 23, 24),
+            ]
+        );
+    }
+
+    #[test]
+    fn positions_are_character_offsets() {
+        // 「会議」の後の単語も文字位置で数える (バイト位置ではない)
+        assert_eq!(
+            kinds("「会議」 p1"),
+            [
+                (TokenKind::Quoted("会議".to_string()), 0, 4),
+                (word("p1"), 5, 7),
+            ]
+        );
+        assert_eq!(
+            kinds("\"a b\" “c” "),
+            [
+                (TokenKind::Quoted("a b".to_string()), 0, 5),
+                (TokenKind::Quoted("c".to_string()), 6, 9),
+            ]
+        );
+    }
+
+    #[test]
+    fn splits_keys_from_values() {
+        assert_eq!(
+            kinds("label:仕事 project:\"A B\""),
+            [
+                (word("label:"), 0, 6),
+                (word("仕事"), 6, 8),
+                (word("project:"), 9, 17),
+                (TokenKind::Quoted("A B".to_string()), 17, 22),
+            ]
+        );
+        // 英字以外のキーや先頭のコロンは分けない
+        assert_eq!(kinds("10:30"), [(word("10:30"), 0, 5)]);
+        assert_eq!(kinds(":x"), [(word(":x"), 0, 2)]);
+    }
+
+    #[test]
+    fn reports_unclosed_quotes() {
+        let error = tokenize("p1 & 「会議").unwrap_err();
+        assert_eq!((error.start, error.end), (5, 8));
+        assert!(tokenize("\"open").is_err());
+    }
+
+    #[test]
+    fn values_that_need_quotes() {
+        for value in ["", "a b", "a&b", "x:y", "and", "OR", "(x", "「x"] {
+            assert!(needs_quotes(value), "{}", value);
+        }
+        for value in ["仕事", "p1", "android"] {
+            assert!(!needs_quotes(value), "{}", value);
+        }
+    }
+}
@@ -0,0 +1,282 @@
+// フィルタ式 (例: (p1 | p2) & #仕事 & due before next friday & !completed)
+// 式は構文木にしてからタスクごとに評価する。エラーは入力中の文字位置付きで返す
+// 保存したフィルタは式の文字列のまま持ち、「来週金曜」などの相対的な日付は評価のたびに解釈し直す
+
+mod eval;
+mod lexer;
+mod parser;
+
+use chrono::Local;
+use std::sync::Mutex;
+
+use crate::calendar::CalendarState;
+use crate::models::{Project, Todo};
+use eval::EvalContext;
+use parser::{Expr, ParseContext, Predicate};
+
+const FILTERS_FILE: &str = "filters.json";
+
+// start / end は文字位置 (end は含まない)
+#[derive(serde::Serialize, Clone, Debug, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct FilterError {
+    pub message: String,
+    pub start: usize,
+    pub end: usize,
+}
+
+impl FilterError {
+    fn new(message: &str, start: usize, end: usize) -> Self {
+        Self {
+            message: message.to_string(),
+            start,
+            end,
+        }
+    }
+}
+
+impl std::fmt::Display for FilterError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} ({}文字目)", self.message, self.start + 1)
+    }
+}
+
+#[derive(serde::Serialize, serde::Deserialize, Clone, Debug, Default)]
+#[serde(rename_all = "camelCase", default)]
+pub struct SavedFilter {
+    pub id: String,
+    pub name: String,
+    pub query: String,
+}
+
+#[derive(serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FilterCheck {
+    pub valid: bool,
+    // 正規化した式 (入力欄の整形用)
+    pub normalized: Option<String>,
+    pub error: Option<FilterError>,
+}
+
+// 従来の CustomFilter.query (各項目の AND)
+#[derive(serde::Deserialize, Default)]
+#[serde(rename_all = "camelCase", default)]
+pub struct LegacyFilterQuery {
+    pub priority: Option<u8>,
+    pub timeframe: Option<String>,
+    pub labels: Vec<String>,
+    pub completed: Option<bool>,
+    pub has_recurrence: Option<bool>,
+    pub overdue: Option<bool>,
+}
+
+pub fn parse_filter(query: &str, calendar: &CalendarState) -> Result<Expr, FilterError> {
+    let calendar = calendar.calendar();
+    let ctx = ParseContext {
+        today: Local::now().date_naive(),
+        calendar: &calendar,
+    };
+    parser::parse(query, &ctx)
+}
+
+// 一致したタスクの ID を元の並び順で返す
+pub fn apply(expr: &Expr, todos: &[Todo], projects: &[Project]) -> Vec<String> {
+    let now = Local::now();
+    let ctx = EvalContext {
+        now: now.timestamp_millis(),
+        today: now.date_naive(),
+        projects,
+    };
+    todos
+        .iter()
+        .filter(|todo| eval::matches(expr, todo, &ctx))
+        .map(|todo| todo.id.clone())
+        .collect()
+}
+
+pub fn from_legacy(query: &LegacyFilterQuery) -> Expr {
+    let is = Expr::Is;
+    let flag = |value: bool, p| {
+        if value {
+            is(p)
+        } else {
+            Expr::Not(Box::new(is(p)))
+        }
+    };
+
+    let mut items = Vec::new();
+    if let Some(priority) = query.priority {
+        items.push(is(Predicate::Priority(priority)));
+    }
+    if let Some(timeframe) = &query.timeframe {
+        items.push(is(Predicate::Timeframe(timeframe.clone())));
+    }
+    // ラベルはどれか1つが付いていれば一致
+    let mut labels: Vec<Expr> = query
+        .labels
+        .iter()
+        .map(|l| is(Predicate::Label(l.clone())))
+        .collect();
+    match labels.len() {
+        0 => {}
+        1 => items.append(&mut labels),
+        _ => items.push(Expr::Or(labels)),
+    }
+    if let Some(completed) = query.completed {
+        items.push(flag(completed, Predicate::Completed));
+    }
+    if let Some(recurring) = query.has_recurrence {
+        items.push(flag(recurring, Predicate::Recurring));
+    }
+    if let Some(overdue) = query.overdue {
+        items.push(flag(overdue, Predicate::Overdue));
+    }
+    if items.len() == 1 {
+        items.remove(0)
+    } else {
+        Expr::And(items)
+    }
+}
+
+pub struct FilterState {
+    filters: Mutex<Vec<SavedFilter>>,
+}
+
+impl FilterState {
+    pub fn load(app: &tauri::AppHandle) -> Self {
+        let filters = crate::store::load_json(app, FILTERS_FILE).unwrap_or_else(|e| {
+            println!("[Filter Backend] フィルタ読み込み失敗: {}", e);
+            Vec::new()
+        });
+        Self {
+            filters: Mutex::new(filters),
+        }
+    }
+}
+
+// 入力中の式を検査する (エラー位置の表示用)
+#[tauri::command]
+pub fn filter_check(calendar: tauri::State<'_, CalendarState>, query: String) -> FilterCheck {
+    match parse_filter(&query, &calendar) {
+        Ok(expr) => FilterCheck {
+            valid: true,
+            normalized: Some(expr.to_string()),
+            error: None,
+        },
+        Err(error) => FilterCheck {
+            valid: false,
+            normalized: None,
+            error: Some(error),
+        },
+    }
+}
+
+// 式に一致するタスクの ID を返す (エラーは filter_check と同じく位置付きで返す)
+#[tauri::command]
+pub fn filter_apply(
+    calendar: tauri::State<'_, CalendarState>,
+    todos: tauri::State<'_, crate::todos::TodoState>,
+    query: String,
+) -> Result<Vec<String>, FilterError> {
+    let expr = parse_filter(&query, &calendar)?;
+    Ok(apply(&expr, &todos.all(), &todos.projects()))
+}
+
+#[tauri::command]
+pub fn filter_list(state: tauri::State<'_, FilterState>) -> Vec<SavedFilter> {
+    state.filters.lock().unwrap().clone()
+}
+
+#[tauri::command]
+pub fn filter_save(
+    app: tauri::AppHandle,
+    state: tauri::State<'_, FilterState>,
+    calendar: tauri::State<'_, CalendarState>,
+    filter: SavedFilter,
+) -> Result<SavedFilter, String> {
+    if filter.name.trim().is_empty() {
+        return Err("フィルタ名を入力してください".to_string());
+    }
+    if filter.query.trim().is_empty() {
+        return Err("フィルタの条件を入力してください".to_string());
+    }
+    parse_filter(&filter.query, &calendar).map_err(|e| e.to_string())?;
+    let mut filter = filter;
+    if filter.id.is_empty() {
+        filter.id = format!("filter-{}", Local::now().timestamp_millis());
+    }
+
+    let mut filters = state.filters.lock().unwrap();
+    match filters.iter_mut().find(|f| f.id == filter.id) {
+        Some(existing) => *existing = filter.clone(),
+        None => filters.push(filter.clone()),
+    }
+    crate::store::save_json(&app, FILTERS_FILE, &*filters)?;
+    Ok(filter)
+}
+
+#[tauri::command]
+pub fn filter_delete(
+    app: tauri::AppHandle,
+    state: tauri::State<'_, FilterState>,
+    id: String,
+) -> Result<(), String> {
+    let mut filters = state.filters.lock().unwrap();
+    filters.retain(|f| f.id != id);
+    crate::store::save_json(&app, FILTERS_FILE, &*filters)
+}
+
+// 従来形式のカスタムフィルタを式の文字列にする (移行用)
+#[tauri::command]
+pub fn filter_convert_legacy(query: LegacyFilterQuery) -> String {
+    from_legacy(&query).to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn legacy(f: impl FnOnce(&mut LegacyFilterQuery)) -> String {
+        let mut query = LegacyFilterQuery::default();
+        f(&mut query);
+        from_legacy(&query).to_string()
+    }
+
+    #[test]
+    fn converts_legacy_filters() {
+        assert_eq!(legacy(|_| {}), "");
+        assert_eq!(legacy(|q| q.priority = Some(1)), "p1");
+        assert_eq!(
+            legacy(|q| {
+                q.priority = Some(2);
+                q.timeframe = Some("week".to_string());
+                q.labels = vec!["仕事".to_string(), "A B".to_string()];
+                q.completed = Some(false);
+                q.has_recurrence = Some(true);
+                q.overdue = Some(false);
+            }),
+            "p2 & timeframe:week & (#仕事 | label:\"A B\") & !completed & recurring & !overdue"
+        );
+        assert_eq!(legacy(|q| q.labels = vec!["家".to_string()]), "#家");
+    }
+
+    #[test]
+    fn converted_filters_parse_back() {
+        let calendar = crate::calendar::BusinessCalendar::default();
+        let ctx = ParseContext {
+            today: Local::now().date_naive(),
+            calendar: &calendar,
+        };
+        let mut query = LegacyFilterQuery {
+            priority: Some(3),
+            labels: vec!["or".to_string(), "x:y".to_string()],
+            completed: Some(true),
+            ..Default::default()
+        };
+        let expr = from_legacy(&query);
+        assert_eq!(parser::parse(&expr.to_string(), &ctx).unwrap(), expr);
+        query.labels.clear();
+        let expr = from_legacy(&query);
+        assert_eq!(parser::parse(&expr.to_string(), &ctx).unwrap(), expr);
+    }
+}
@@ -0,0 +1,584 @@
+// フィルタ式の構文解析
+// 優先順位は ! > & > | 。条件と条件の間には必ず演算子を書く (「p1 #仕事」はエラーにする)
+
+use chrono::NaiveDate;
+use std::fmt;
+
+use super::lexer::{needs_quotes, tokenize, Token, TokenKind};
+use super::FilterError;
+use crate::calendar::BusinessCalendar;
+
+const TIMEFRAMES: [&str; 4] = ["today", "week", "month", "year"];
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum DateOp {
+    Before,
+    After,
+    On,
+}
+
+// 日付は書かれたままの表現も持つ (表示し直したときに「来週金曜」が固定の日付にならないように)
+#[derive(Debug, Clone, PartialEq)]
+pub struct DateValue {
+    pub text: String,
+    pub date: NaiveDate,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Predicate {
+    Priority(u8),
+    Label(String),
+    NoLabels,
+    Project(String),
+    Timeframe(String),
+    Due(DateOp, DateValue),
+    // 今日から n 日後までに期限がある
+    DueWithin(u32),
+    NoDate,
+    Overdue,
+    Created(DateOp, DateValue),
+    Recurring,
+    Completed,
+    Archived,
+    // タスク名・説明に含まれる文字列
+    Text(String),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Expr {
+    // 空の And は常に真 (空の式はすべてのタスクに一致する)
+    And(Vec<Expr>),
+    Or(Vec<Expr>),
+    Not(Box<Expr>),
+    Is(Predicate),
+}
+
+// 相対的な日付の解釈に使う
+pub struct ParseContext<'a> {
+    pub today: NaiveDate,
+    pub calendar: &'a BusinessCalendar,
+}
+
+pub fn parse(input: &str, ctx: &ParseContext) -> Result<Expr, FilterError> {
+    let tokens = tokenize(input)?;
+    if tokens.is_empty() {
+        return Ok(Expr::And(Vec::new()));
+    }
+    let mut parser = Parser {
+        chars: input.chars().collect(),
+        tokens,
+        pos: 0,
+        ctx,
+    };
+    let expr = parser.parse_or()?;
+    if let Some(token) = parser.peek() {
+        // 演算子・条件の続きは parse_and で拾うので、残るのは閉じ括弧だけ
+        return Err(FilterError::new(
+            "対応する ( がありません",
+            token.start,
+            token.end,
+        ));
+    }
+    Ok(expr)
+}
+
+struct Parser<'a> {
+    chars: Vec<char>,
+    tokens: Vec<Token>,
+    pos: usize,
+    ctx: &'a ParseContext<'a>,
+}
+
+impl Parser<'_> {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.pos)
+    }
+
+    fn eat(&mut self, kind: &TokenKind) -> bool {
+        if self.peek().is_some_and(|t| &t.kind == kind) {
+            self.pos += 1;
+            return true;
+        }
+        false
+    }
+
+    fn source(&self, token: &Token) -> String {
+        self.chars[token.start..token.end].iter().collect()
+    }
+
+    fn parse_or(&mut self) -> Result<Expr, FilterError> {
+        let mut items = vec![self.parse_and()?];
+        while self.eat(&TokenKind::Or) {
+            items.push(self.parse_and()?);
+        }
+        Ok(if items.len() == 1 {
+            items.remove(0)
+        } else {
+            Expr::Or(items)
+        })
+    }
+
+    fn parse_and(&mut self) -> Result<Expr, FilterError> {
+        let mut items = vec![self.parse_unary()?];
+        while self.eat(&TokenKind::And) {
+            items.push(self.parse_unary()?);
+        }
+        if let Some(token) = self.peek().filter(|t| {
+            matches!(
+                t.kind,
+                TokenKind::Word(_) | TokenKind::Quoted(_) | TokenKind::Open | TokenKind::Not
+            )
+        }) {
+            return Err(FilterError::new(
+                "条件の間に & または | が必要です",
+                token.start,
+                token.end,
+            ));
+        }
+        Ok(if items.len() == 1 {
+            items.remove(0)
+        } else {
+            Expr::And(items)
+        })
+    }
+
+    fn parse_unary(&mut self) -> Result<Expr, FilterError> {
+        if self.eat(&TokenKind::Not) {
+            return Ok(Expr::Not(Box::new(self.parse_unary()?)));
+        }
+        self.parse_primary()
+    }
+
+    fn parse_primary(&mut self) -> Result<Expr, FilterError> {
+        let Some(token) = self.peek().cloned() else {
+            let end = self.chars.len();
+            return Err(FilterError::new("式が途中で終わっています", end, end));
+        };
+        match token.kind {
+            TokenKind::Open => {
+                self.pos += 1;
+                let expr = self.parse_or()?;
+                if !self.eat(&TokenKind::Close) {
+                    return Err(FilterError::new(
+                        "閉じ括弧 ) がありません",
+                        token.start,
+                        token.end,
+                    ));
+                }
+                Ok(expr)
+            }
+            TokenKind::Close => Err(FilterError::new("条件がありません", token.start, token.end)),
+            TokenKind::And | TokenKind::Or => Err(FilterError::new(
+                &format!("{} の前に条件が必要です", self.source(&token)),
+                token.start,
+                token.end,
+            )),
+            TokenKind::Not => unreachable!("parse_unary で処理済み"),
+            TokenKind::Word(_) | TokenKind::Quoted(_) => {
+                let end = self.tokens[self.pos..]
+                    .iter()
+                    .position(|t| !matches!(t.kind, TokenKind::Word(_) | TokenKind::Quoted(_)))
+                    .map_or(self.tokens.len(), |len| self.pos + len);
+                let words = &self.tokens[self.pos..end];
+                let (predicate, used) = predicate(words, self.ctx)?;
+                // 続きの単語は parse_and で「演算子が必要」として報告する
+                self.pos += used;
+                Ok(Expr::Is(predicate))
+            }
+        }
+    }
+}
+
+fn text(token: &Token) -> &str {
+    match &token.kind {
+        TokenKind::Word(s) | TokenKind::Quoted(s) => s,
+        _ => "",
+    }
+}
+
+// key: の後の値
+fn value(words: &[Token], i: usize) -> Result<&str, FilterError> {
+    match words.get(i) {
+        Some(token) => Ok(text(token)),
+        None => {
+            let key = &words[i - 1];
+            Err(FilterError::new(
+                &format!("{} の後に値が必要です", text(key)),
+                key.start,
+                key.end,
+            ))
+        }
+    }
+}
+
+// 残りの単語のうち、日付として読める最も長い先頭部分
+fn date(words: &[Token], i: usize, ctx: &ParseContext) -> Result<(DateValue, usize), FilterError> {
+    if i >= words.len() {
+        let last = &words[i - 1];
+        return Err(FilterError::new(
+            &format!("{} の後に日付が必要です", text(last)),
+            last.start,
+            last.end,
+        ));
+    }
+    for end in (i + 1..=words.len()).rev() {
+        let joined = words[i..end].iter().map(text).collect::<Vec<_>>().join(" ");
+        if let Some(date) = crate::nlp::parse_date(&joined, ctx.today, ctx.calendar) {
+            return Ok((DateValue { text: joined, date }, end));
+        }
+    }
+    Err(FilterError::new(
+        "日付を解釈できません",
+        words[i].start,
+        words[words.len() - 1].end,
+    ))
+}
+
+fn date_op(word: Option<&Token>) -> Option<DateOp> {
+    match word.map(|t| text(t).to_lowercase())?.as_str() {
+        "before" => Some(DateOp::Before),
+        "after" => Some(DateOp::After),
+        "on" => Some(DateOp::On),
+        _ => None,
+    }
+}
+
+// 「due before next friday」「created: 2026-03-05」
+fn dated(
+    words: &[Token],
+    ctx: &ParseContext,
+    make: fn(DateOp, DateValue) -> Predicate,
+) -> Result<(Predicate, usize), FilterError> {
+    let (op, start) = match date_op(words.get(1)) {
+        Some(op) => (op, 2),
+        None => (DateOp::On, 1),
+    };
+    let (value, used) = date(words, start, ctx)?;
+    Ok((make(op, value), used))
+}
+
+fn days(word: Option<&Token>) -> bool {
+    word.is_some_and(|t| matches!(text(t).to_lowercase().as_str(), "days" | "day"))
+}
+
+fn number(word: Option<&Token>) -> Option<u32> {
+    word.and_then(|t| text(t).parse().ok())
+}
+
+// 単語の並びの先頭から条件を1つ読み、使った単語の数を返す
+fn predicate(words: &[Token], ctx: &ParseContext) -> Result<(Predicate, usize), FilterError> {
+    let first = &words[0];
+    let word = match &first.kind {
+        TokenKind::Quoted(s) => return Ok((Predicate::Text(s.clone()), 1)),
+        TokenKind::Word(s) => s.as_str(),
+        _ => unreachable!("単語だけが渡される"),
+    };
+    let lower = word.to_lowercase();
+    let second = words.get(1).map(|t| text(t).to_lowercase());
+
+    let found = match lower.as_str() {
+        "p1" | "p2" | "p3" | "p4" => Predicate::Priority(lower[1..].parse().unwrap_or(4)),
+        "priority:" => {
+            let v = value(words, 1)?;
+            match v.parse::<u8>() {
+                Ok(p @ 1..=4) => return Ok((Predicate::Priority(p), 2)),
+                _ => {
+                    return Err(FilterError::new(
+                        "優先度は 1〜4 で指定してください",
+                        words[1].start,
+                        words[1].end,
+                    ))
+                }
+            }
+        }
+        "#" => return Ok((Predicate::Label(value(words, 1)?.to_string()), 2)),
+        _ if word.starts_with('#') => Predicate::Label(word[1..].to_string()),
+        "label:" => return Ok((Predicate::Label(value(words, 1)?.to_string()), 2)),
+        "project:" => return Ok((Predicate::Project(value(words, 1)?.to_string()), 2)),
+        "timeframe:" => {
+            let v = value(words, 1)?.to_lowercase();
+            if !TIMEFRAMES.contains(&v.as_str()) {
+                return Err(FilterError::new(
+                    "期間は today / week / month / year のいずれかです",
+                    words[1].start,
+                    words[1].end,
+                ));
+            }
+            return Ok((Predicate::Timeframe(v), 2));
+        }
+        "search:" | "text:" => return Ok((Predicate::Text(value(words, 1)?.to_string()), 2)),
+        "due" | "due:" => return dated(words, ctx, Predicate::Due),
+        "created" | "created:" => return dated(words, ctx, Predicate::Created),
+        "overdue" | "期限切れ" => Predicate::Overdue,
+        "recurring" | "繰り返し" => Predicate::Recurring,
+        "completed" | "done" | "完了" => Predicate::Completed,
+        "archived" | "アーカイブ" | "アーカイブ済み" => Predicate::Archived,
+        "nodate" | "期限なし" => Predicate::NoDate,
+        "nolabels" | "ラベルなし" => Predicate::NoLabels,
+        "no" if second.as_deref() == Some("date") => return Ok((Predicate::NoDate, 2)),
+        "no" if matches!(second.as_deref(), Some("labels" | "label")) => {
+            return Ok((Predicate::NoLabels, 2))
+        }
+        // 「next 7 days」「7 days」「7日以内」
+        "next" if number(words.get(1)).is_some() && days(words.get(2)) => {
+            return Ok((Predicate::DueWithin(number(words.get(1)).unwrap_or(0)), 3))
+        }
+        _ if number(Some(first)).is_some() && days(words.get(1)) => {
+            return Ok((Predicate::DueWithin(number(Some(first)).unwrap_or(0)), 2))
+        }
+        _ if word.ends_with("日以内") && word.trim_end_matches("日以内").parse::<u32>().is_ok() => {
+            Predicate::DueWithin(word.trim_end_matches("日以内").parse().unwrap_or(0))
+        }
+        // 日付だけなら、その日が期限のタスク (「明日」「friday」)
+        _ => {
+            return date(words, 0, ctx)
+                .map(|(value, used)| (Predicate::Due(DateOp::On, value), used))
+                .map_err(|_| {
+                    FilterError::new(&format!("不明な条件です: {}", word), first.start, first.end)
+                })
+        }
+    };
+    Ok((found, 1))
+}
+
+fn quoted(value: &str) -> String {
+    if value.contains('"') {
+        format!("「{}」", value)
+    } else {
+        format!("\"{}\"", value)
+    }
+}
+
+fn plain_or_quoted(value: &str) -> String {
+    if needs_quotes(value) {
+        quoted(value)
+    } else {
+        value.to_string()
+    }
+}
+
+impl fmt::Display for DateOp {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            DateOp::Before => "before",
+            DateOp::After => "after",
+            DateOp::On => "on",
+        })
+    }
+}
+
+impl fmt::Display for Predicate {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Predicate::Priority(p) => write!(f, "p{}", p),
+            Predicate::Label(label) if !needs_quotes(label) => write!(f, "#{}", label),
+            Predicate::Label(label) => write!(f, "label:{}", quoted(label)),
+            Predicate::NoLabels => f.write_str("no labels"),
+            Predicate::Project(name) => write!(f, "project:{}", plain_or_quoted(name)),
+            Predicate::Timeframe(timeframe) => write!(f, "timeframe:{}", timeframe),
+            Predicate::Due(op, value) => write!(f, "due {} {}", op, value.text),
+            Predicate::DueWithin(days) => write!(f, "next {} days", days),
+            Predicate::NoDate => f.write_str("no date"),
+            Predicate::Overdue => f.write_str("overdue"),
+            Predicate::Created(op, value) => write!(f, "created {} {}", op, value.text),
+            Predicate::Recurring => f.write_str("recurring"),
+            Predicate::Completed => f.write_str("completed"),
+            Predicate::Archived => f.write_str("archived"),
+            Predicate::Text(text) => f.write_str(&quoted(text)),
+        }
+    }
+}
+
+// 正規化した式 (演算子の前後に空白を入れ、必要な所だけ括弧で囲む)
+impl fmt::Display for Expr {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let join = |f: &mut fmt::Formatter<'_>, items: &[Expr], op: &str, wrap_or: bool| {
+            for (i, item) in items.iter().enumerate() {
+                if i > 0 {
+                    write!(f, " {} ", op)?;
+                }
+                match item {
+                    Expr::Or(_) if wrap_or => write!(f, "({})", item)?,
+                    _ => write!(f, "{}", item)?,
+                }
+            }
+            Ok(())
+        };
+        match self {
+            Expr::And(items) => join(f, items, "&", true),
+            Expr::Or(items) => join(f, items, "|", false),
+            Expr::Not(inner) => match inner.as_ref() {
+                Expr::And(_) | Expr::Or(_) => write!(f, "!({})", inner),
+                _ => write!(f, "!{}", inner),
+            },
+            Expr::Is(predicate) => write!(f, "{}", predicate),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn calendar() -> BusinessCalendar {
+        BusinessCalendar::default()
+    }
+
+    fn run(input: &str) -> Result<Expr, FilterError> {
+        let calendar = calendar();
+        let ctx = ParseContext {
+            today: NaiveDate::from_ymd_opt(2026, 3, 4).unwrap(),
+            calendar: &calendar,
+        };
+        parse(input, &ctx)
+    }
+
+    fn is(p: Predicate) -> Expr {
+        Expr::Is(p)
+    }
+
+    fn label(name: &str) -> Expr {
+        is(Predicate::Label(name.to_string()))
+    }
+
+    fn date(text: &str, y: i32, m: u32, d: u32) -> DateValue {
+        DateValue {
+            text: text.to_string(),
+            date: NaiveDate::from_ymd_opt(y, m, d).unwrap(),
+        }
+    }
+
+    #[test]
+    fn not_binds_tighter_than_and_than_or() {
+        assert_eq!(
+            run("p1 | #仕事 & !done").unwrap(),
+            Expr::Or(vec![
+                is(Predicate::Priority(1)),
+                Expr::And(vec![
+                    label("仕事"),
+                    Expr::Not(Box::new(is(Predicate::Completed))),
+                ]),
+            ])
+        );
+        assert_eq!(
+            run("(p1 or p2) and not (overdue | recurring)").unwrap(),
+            Expr::And(vec![
+                Expr::Or(vec![is(Predicate::Priority(1)), is(Predicate::Priority(2))]),
+                Expr::Not(Box::new(Expr::Or(vec![
+                    is(Predicate::Overdue),
+                    is(Predicate::Recurring),
+                ]))),
+            ])
+        );
+        assert_eq!(
+            run("!!archived").unwrap(),
+            Expr::Not(Box::new(Expr::Not(Box::new(is(Predicate::Archived)))))
+        );
+        assert_eq!(run("  ").unwrap(), Expr::And(Vec::new()));
+    }
+
+    #[test]
+    fn reads_multi_word_predicates() {
+        let cases = [
+            ("priority: 3", is(Predicate::Priority(3))),
+            ("# 仕事", label("仕事")),
+            ("label:\"A B\"", label("A B")),
+            ("project:開発", is(Predicate::Project("開発".to_string()))),
+            (
+                "timeframe:Week",
+                is(Predicate::Timeframe("week".to_string())),
+            ),
+            ("search:議事録", is(Predicate::Text("議事録".to_string()))),
+            ("「議事録」", is(Predicate::Text("議事録".to_string()))),
+            ("no date", is(Predicate::NoDate)),
+            ("No Labels", is(Predicate::NoLabels)),
+            ("next 7 days", is(Predicate::DueWithin(7))),
+            ("3 days", is(Predicate::DueWithin(3))),
+            ("14日以内", is(Predicate::DueWithin(14))),
+            (
+                "due before 2026-03-10",
+                is(Predicate::Due(
+                    DateOp::Before,
+                    date("2026-03-10", 2026, 3, 10),
+                )),
+            ),
+            (
+                "created: 2026-03-01",
+                is(Predicate::Created(
+                    DateOp::On,
+                    date("2026-03-01", 2026, 3, 1),
+                )),
+            ),
+            (
+                "明日",
+                is(Predicate::Due(DateOp::On, date("明日", 2026, 3, 5))),
+            ),
+        ];
+        for (input, expected) in cases {
+            assert_eq!(run(input).unwrap(), expected, "{}", input);
+        }
+    }
+
+    #[test]
+    fn errors_point_at_the_offending_characters() {
+        // (入力, エラーの開始, 終了)
+        let cases = [
+            ("p1 #仕事", 3, 6),
+            ("「会議」 p9", 5, 7),
+            ("#仕事 & (p1 | p2", 6, 7),
+            ("p1 | p2)", 7, 8),
+            ("p1 &", 4, 4),
+            ("& p1", 0, 1),
+            ("()", 1, 2),
+            ("priority:5", 9, 10),
+            ("p1 & label:", 5, 11),
+            ("会議", 0, 2),
+            ("timeframe:someday", 10, 17),
+            ("due after ほげ", 10, 12),
+            ("\"未完", 0, 3),
+        ];
+        for (input, start, end) in cases {
+            let error = run(input).unwrap_err();
+            assert_eq!(
+                (error.start, error.end),
+                (start, end),
+                "{}: {}",
+                input,
+                error
+            );
+        }
+        let error = run("p1 #仕事").unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "条件の間に & または | が必要です (4文字目)"
+        );
+    }
+
+    #[test]
+    fn display_round_trips() {
+        let cases = [
+            ("p1|p2&#仕事", "p1 | p2 & #仕事"),
+            ("(p1 | p2) & !done", "(p1 | p2) & !completed"),
+            ("not (overdue & recurring)", "!(overdue & recurring)"),
+            ("!(p1 | p2)", "!(p1 | p2)"),
+            (
+                "label:\"A B\" | project:\"x y\"",
+                "label:\"A B\" | project:\"x y\"",
+            ),
+            ("label:and", "label:\"and\""),
+            ("search:「\"引用\"」", "「\"引用\"」"),
+            ("期限なし & ラベルなし", "no date & no labels"),
+            ("7日以内", "next 7 days"),
+            ("due before 明日", "due before 明日"),
+            ("created after 2026-03-01", "created after 2026-03-01"),
+            ("明日", "due on 明日"),
+            ("", ""),
+        ];
+        for (input, normalized) in cases {
+            let expr = run(input).unwrap();
+            assert_eq!(expr.to_string(), normalized, "{}", input);
+            // 正規化した式を読み直しても同じ構文木になる
+            assert_eq!(run(normalized).unwrap(), expr, "{}", normalized);
+        }
+    }
+}
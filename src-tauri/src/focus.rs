@@ -0,0 +1,711 @@
+// 集中タイマー (ポモドーロ)
+// メインウィンドウを閉じても止まらないようにバックエンドで時間を数え、
+// 残り時間をトレイのツールチップに出しつつ毎秒 focus-tick をフロントエンドに送る
+// 終了時刻で持つので、スリープ復帰などで間が空いても残り時間はずれない
+
+use chrono::Local;
+use std::sync::Mutex;
+use std::time::Duration;
+use tauri::{Emitter, Manager};
+
+const SETTINGS_FILE: &str = "focus-settings.json";
+const SESSION_FILE: &str = "focus-session.json";
+const TICK_INTERVAL: Duration = Duration::from_secs(1);
+const MINUTE_MS: i64 = 60 * 1000;
+// ツールチップに出すタスク名の長さ (Windows は128文字まで)
+const TOOLTIP_TITLE_CHARS: usize = 40;
+
+#[derive(serde::Serialize, serde::Deserialize, Clone, Copy, Debug, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub enum FocusPhase {
+    Focus,
+    ShortBreak,
+    LongBreak,
+}
+
+impl FocusPhase {
+    fn label(self) -> &'static str {
+        match self {
+            FocusPhase::Focus => "集中",
+            FocusPhase::ShortBreak => "休憩",
+            FocusPhase::LongBreak => "長い休憩",
+        }
+    }
+}
+
+#[derive(serde::Serialize, serde::Deserialize, Clone, Debug)]
+#[serde(rename_all = "camelCase", default)]
+pub struct FocusSettings {
+    pub focus_minutes: u32,
+    pub short_break_minutes: u32,
+    pub long_break_minutes: u32,
+    // 集中を何回終えたら長い休憩にするか
+    pub long_break_every: u32,
+    // 次の区間を自動で始めるか (始めない場合は一時停止の状態で待つ)
+    pub auto_start_breaks: bool,
+    pub auto_start_focus: bool,
+}
+
+impl Default for FocusSettings {
+    fn default() -> Self {
+        Self {
+            focus_minutes: 25,
+            short_break_minutes: 5,
+            long_break_minutes: 15,
+            long_break_every: 4,
+            auto_start_breaks: true,
+            auto_start_focus: false,
+        }
+    }
+}
+
+#[derive(serde::Serialize, serde::Deserialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct FocusSession {
+    pub todo_id: String,
+    pub title: String,
+    // false なら集中1回で終わる (時間指定のタイマー)
+    pub pomodoro: bool,
+    pub focus_minutes: u32,
+    pub phase: FocusPhase,
+    pub phase_minutes: u32,
+    // 動作中は区間の終了時刻、一時停止中は None
+    pub ends_at: Option<i64>,
+    // 一時停止中の残り (ミリ秒)
+    pub remaining_ms: i64,
+    pub completed_focus: u32,
+    pub started_at: i64,
+}
+
+impl FocusSession {
+    fn remaining(&self, now: i64) -> i64 {
+        match self.ends_at {
+            Some(ends_at) => (ends_at - now).max(0),
+            None => self.remaining_ms,
+        }
+    }
+
+    fn begin_phase(&mut self, phase: FocusPhase, minutes: u32, run: bool, now: i64) {
+        self.phase = phase;
+        self.phase_minutes = minutes;
+        self.remaining_ms = minutes as i64 * MINUTE_MS;
+        self.ends_at = run.then_some(now + self.remaining_ms);
+    }
+
+    // 区間を終える時刻。終了時刻を過ぎていれば終了時刻 (スリープ中やアプリを閉じている間に過ぎた場合も now にしない)
+    fn ended_at(&self, now: i64) -> i64 {
+        self.ends_at.map_or(now, |ends_at| ends_at.min(now))
+    }
+
+    // 集中区間でここまで集中した時間 (一時停止の分を除いた長さを now から逆算)
+    fn focused_range(&self, now: i64) -> Option<(i64, i64)> {
+        if self.phase != FocusPhase::Focus {
+            return None;
+        }
+        let elapsed = self.phase_minutes as i64 * MINUTE_MS - self.remaining(now);
+        (elapsed > 0).then_some((now - elapsed, now))
+    }
+
+    fn status(&self, now: i64) -> FocusStatus {
+        FocusStatus {
+            todo_id: self.todo_id.clone(),
+            title: self.title.clone(),
+            pomodoro: self.pomodoro,
+            phase: self.phase,
+            remaining_seconds: (self.remaining(now) + 999) / 1000,
+            total_seconds: self.phase_minutes as i64 * 60,
+            paused: self.ends_at.is_none(),
+            completed_focus: self.completed_focus,
+        }
+    }
+}
+
+// focus-tick で送る内容
+#[derive(serde::Serialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct FocusStatus {
+    pub todo_id: String,
+    pub title: String,
+    pub pomodoro: bool,
+    pub phase: FocusPhase,
+    pub remaining_seconds: i64,
+    pub total_seconds: i64,
+    pub paused: bool,
+    pub completed_focus: u32,
+}
+
+// 区間の切り替わり (next が None ならタイマー終了)
+#[derive(serde::Serialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct PhaseChange {
+    pub todo_id: String,
+    pub title: String,
+    pub finished: FocusPhase,
+    pub next: Option<FocusPhase>,
+    pub completed_focus: u32,
+    // 集中した時間 (休憩の終わりでは None)
+    pub focused: Option<(i64, i64)>,
+}
+
+// 今の区間を終えて次の区間に進める。時間どおりに終わった場合も途中で飛ばした場合も使う
+// 次の区間は今の区間の終了時刻から始める。次の区間も now までに終わってしまう場合は
+// 誰も見ていない間に過ぎた区間なので、飛ばさずに最初から開始待ちにする
+fn advance(session: &mut FocusSession, settings: &FocusSettings, now: i64) -> PhaseChange {
+    let finished = session.phase;
+    let at = session.ended_at(now);
+    let begin = |session: &mut FocusSession, phase: FocusPhase, minutes: u32, auto: bool| {
+        let run = auto && at + minutes as i64 * MINUTE_MS > now;
+        session.begin_phase(phase, minutes, run, at);
+    };
+    let next = match finished {
+        FocusPhase::Focus => {
+            session.completed_focus += 1;
+            if !session.pomodoro {
+                None
+            } else if session
+                .completed_focus
+                .is_multiple_of(settings.long_break_every.max(1))
+            {
+                Some(FocusPhase::LongBreak)
+            } else {
+                Some(FocusPhase::ShortBreak)
+            }
+        }
+        FocusPhase::ShortBreak | FocusPhase::LongBreak => Some(FocusPhase::Focus),
+    };
+    let focused = session.focused_range(at);
+    match next {
+        Some(FocusPhase::Focus) => {
+            let minutes = session.focus_minutes;
+            begin(
+                session,
+                FocusPhase::Focus,
+                minutes,
+                settings.auto_start_focus,
+            );
+        }
+        Some(FocusPhase::ShortBreak) => begin(
+            session,
+            FocusPhase::ShortBreak,
+            settings.short_break_minutes,
+            settings.auto_start_breaks,
+        ),
+        Some(FocusPhase::LongBreak) => begin(
+            session,
+            FocusPhase::LongBreak,
+            settings.long_break_minutes,
+            settings.auto_start_breaks,
+        ),
+        None => {}
+    }
+    PhaseChange {
+        todo_id: session.todo_id.clone(),
+        title: session.title.clone(),
+        finished,
+        next,
+        completed_focus: session.completed_focus,
+        focused,
+    }
+}
+
+// 集中した時間を作業時間として記録する
+fn record_focus(app: &tauri::AppHandle, todo_id: &str, title: &str, focused: Option<(i64, i64)>) {
+    if let Some((start, end)) = focused {
+        app.state::<crate::timetrack::TimeTrackState>()
+            .record_focus(app, todo_id, title, start, end);
+    }
+}
+
+fn format_remaining(seconds: i64) -> String {
+    format!("{:02}:{:02}", seconds / 60, seconds % 60)
+}
+
+fn tooltip(status: Option<&FocusStatus>) -> String {
+    let Some(status) = status else {
+        return "Calm Todo".to_string();
+    };
+    let mut title: String = status.title.chars().take(TOOLTIP_TITLE_CHARS).collect();
+    if title.chars().count() < status.title.chars().count() {
+        title.push('…');
+    }
+    format!(
+        "Calm Todo\n{} {}{}\n{}",
+        status.phase.label(),
+        format_remaining(status.remaining_seconds),
+        if status.paused {
+            " (一時停止中)"
+        } else {
+            ""
+        },
+        title
+    )
+}
+
+fn notification(session: &FocusSession, change: &PhaseChange) -> (String, String) {
+    let waiting = if session.ends_at.is_none() {
+        " (開始待ち)"
+    } else {
+        ""
+    };
+    match change.next {
+        None => (
+            "集中時間が終わりました".to_string(),
+            format!("「{}」 {}分", session.title, session.focus_minutes),
+        ),
+        Some(FocusPhase::Focus) => (
+            "休憩が終わりました".to_string(),
+            format!(
+                "「{}」 次の集中 {}分{}",
+                session.title, session.phase_minutes, waiting
+            ),
+        ),
+        Some(next) => (
+            format!("集中時間が終わりました ({}回目)", change.completed_focus),
+            format!(
+                "「{}」 {} {}分{}",
+                session.title,
+                next.label(),
+                session.phase_minutes,
+                waiting
+            ),
+        ),
+    }
+}
+
+pub struct FocusState {
+    settings: Mutex<FocusSettings>,
+    session: Mutex<Option<FocusSession>>,
+    // 最後にトレイに設定したツールチップ (変わったときだけ設定し直す)
+    tooltip: Mutex<String>,
+}
+
+impl FocusState {
+    pub fn load(app: &tauri::AppHandle) -> Self {
+        let settings = crate::store::load_json(app, SETTINGS_FILE).unwrap_or_else(|e| {
+            println!("[Focus Backend] 設定読み込み失敗: {}", e);
+            FocusSettings::default()
+        });
+        // 前回終了時に動いていたタイマーは終了時刻のまま続ける
+        let session = crate::store::load_json(app, SESSION_FILE).unwrap_or_else(|e| {
+            println!("[Focus Backend] タイマー読み込み失敗: {}", e);
+            None
+        });
+        Self {
+            settings: Mutex::new(settings),
+            session: Mutex::new(session),
+            tooltip: Mutex::new(String::new()),
+        }
+    }
+
+    fn settings(&self) -> FocusSettings {
+        self.settings.lock().unwrap().clone()
+    }
+
+    fn save_session(app: &tauri::AppHandle, session: &Option<FocusSession>) {
+        if let Err(e) = crate::store::save_json(app, SESSION_FILE, session) {
+            println!("[Focus Backend] タイマー保存失敗: {}", e);
+        }
+    }
+
+    // 動いているタイマーを変更して保存し、すぐに画面とトレイに反映する
+    fn update<T>(
+        &self,
+        app: &tauri::AppHandle,
+        f: impl FnOnce(&mut Option<FocusSession>, i64) -> Result<T, String>,
+    ) -> Result<T, String> {
+        let now = Local::now().timestamp_millis();
+        let mut session = self.session.lock().unwrap();
+        let result = f(&mut session, now)?;
+        Self::save_session(app, &session);
+        drop(session);
+        self.tick(app);
+        Ok(result)
+    }
+
+    fn status(&self) -> Option<FocusStatus> {
+        let now = Local::now().timestamp_millis();
+        self.session.lock().unwrap().as_ref().map(|s| s.status(now))
+    }
+
+    // 最後にトレイに設定したものと違うときだけ設定し直す
+    fn set_tooltip(&self, app: &tauri::AppHandle, text: String) -> bool {
+        let mut last = self.tooltip.lock().unwrap();
+        if *last == text {
+            return false;
+        }
+        if let Some(tray) = app.tray_by_id(crate::TRAY_ID) {
+            if let Err(e) = tray.set_tooltip(Some(&text)) {
+                println!("[Focus Backend] ツールチップ設定失敗: {}", e);
+            }
+        }
+        *last = text;
+        true
+    }
+
+    fn tick(&self, app: &tauri::AppHandle) {
+        let now = Local::now().timestamp_millis();
+        let mut session = self.session.lock().unwrap();
+        let mut change = None;
+        if let Some(current) = session.as_mut() {
+            if current.ends_at.is_some_and(|ends_at| ends_at <= now) {
+                let phase_change = advance(current, &self.settings(), now);
+                let message = notification(current, &phase_change);
+                change = Some((phase_change, message));
+            }
+        }
+        if let Some((phase_change, _)) = &change {
+            if phase_change.next.is_none() {
+                *session = None;
+            }
+            Self::save_session(app, &session);
+        }
+        let status = session.as_ref().map(|s| s.status(now));
+        drop(session);
+
+        if let Some((phase_change, (title, body))) = change {
+            println!(
+                "[Focus Backend] {} 終了: {} ({}回目)",
+                phase_change.finished.label(),
+                phase_change.todo_id,
+                phase_change.completed_focus
+            );
+            if let Err(e) = crate::show_notification(title, body) {
+                println!("[Focus Backend] 通知失敗: {}", e);
+            }
+            record_focus(
+                app,
+                &phase_change.todo_id,
+                &phase_change.title,
+                phase_change.focused,
+            );
+            let _ = app.emit("focus-phase-changed", &phase_change);
+        }
+        // 止まっている間は送らない (止まった直後に一度だけ null を送る)
+        let changed = self.set_tooltip(app, tooltip(status.as_ref()));
+        if status.is_some() || changed {
+            let _ = app.emit("focus-tick", &status);
+        }
+    }
+}
+
+pub async fn run_timer(app: tauri::AppHandle) {
+    loop {
+        app.state::<FocusState>().tick(&app);
+        tokio::time::sleep(TICK_INTERVAL).await;
+    }
+}
+
+fn validate(settings: &FocusSettings) -> Result<(), String> {
+    let minutes = [
+        settings.focus_minutes,
+        settings.short_break_minutes,
+        settings.long_break_minutes,
+    ];
+    if minutes.contains(&0) {
+        return Err("時間は1分以上にしてください".to_string());
+    }
+    if settings.long_break_every == 0 {
+        return Err("長い休憩の間隔は1回以上にしてください".to_string());
+    }
+    Ok(())
+}
+
+#[tauri::command]
+pub fn focus_get_settings(state: tauri::State<'_, FocusState>) -> FocusSettings {
+    state.settings()
+}
+
+#[tauri::command]
+pub fn focus_set_settings(
+    app: tauri::AppHandle,
+    state: tauri::State<'_, FocusState>,
+    settings: FocusSettings,
+) -> Result<(), String> {
+    validate(&settings)?;
+    crate::store::save_json(&app, SETTINGS_FILE, &settings)?;
+    *state.settings.lock().unwrap() = settings;
+    Ok(())
+}
+
+#[tauri::command]
+pub fn focus_status(state: tauri::State<'_, FocusState>) -> Option<FocusStatus> {
+    state.status()
+}
+
+// タスクの集中タイマーを始める (動いているタイマーは置き換える)
+// pomodoro が false なら minutes 分の集中1回だけ。minutes を省くと設定の集中時間
+#[tauri::command]
+pub fn focus_start(
+    app: tauri::AppHandle,
+    state: tauri::State<'_, FocusState>,
+    todos: tauri::State<'_, crate::todos::TodoState>,
+    todo_id: String,
+    pomodoro: bool,
+    minutes: Option<u32>,
+) -> Result<FocusStatus, String> {
+    let todo = todos
+        .find(&todo_id)
+        .ok_or_else(|| format!("タスクが見つかりません: {}", todo_id))?;
+    let focus_minutes = minutes.unwrap_or(state.settings().focus_minutes);
+    if focus_minutes == 0 {
+        return Err("時間は1分以上にしてください".to_string());
+    }
+    state.update(&app, |session, now| {
+        let mut started = FocusSession {
+            todo_id: todo.id.clone(),
+            title: todo.text.clone(),
+            pomodoro,
+            focus_minutes,
+            phase: FocusPhase::Focus,
+            phase_minutes: focus_minutes,
+            ends_at: None,
+            remaining_ms: 0,
+            completed_focus: 0,
+            started_at: now,
+        };
+        started.begin_phase(FocusPhase::Focus, focus_minutes, true, now);
+        println!(
+            "[Focus Backend] タイマー開始: {} ({}分{})",
+            todo.id,
+            focus_minutes,
+            if pomodoro { ", ポモドーロ" } else { "" }
+        );
+        let status = started.status(now);
+        *session = Some(started);
+        Ok(status)
+    })
+}
+
+#[tauri::command]
+pub fn focus_pause(
+    app: tauri::AppHandle,
+    state: tauri::State<'_, FocusState>,
+) -> Result<(), String> {
+    state.update(&app, |session, now| {
+        let session = session
+            .as_mut()
+            .ok_or_else(|| "タイマーが動いていません".to_string())?;
+        session.remaining_ms = session.remaining(now);
+        session.ends_at = None;
+        Ok(())
+    })
+}
+
+// 一時停止中・開始待ちの区間を始める
+#[tauri::command]
+pub fn focus_resume(
+    app: tauri::AppHandle,
+    state: tauri::State<'_, FocusState>,
+) -> Result<(), String> {
+    state.update(&app, |session, now| {
+        let session = session
+            .as_mut()
+            .ok_or_else(|| "タイマーが動いていません".to_string())?;
+        if session.ends_at.is_none() {
+            session.ends_at = Some(now + session.remaining_ms);
+        }
+        Ok(())
+    })
+}
+
+// 今の区間を飛ばして次に進む (集中を飛ばした場合もそこまでを1回と数える)
+#[tauri::command]
+pub fn focus_skip(
+    app: tauri::AppHandle,
+    state: tauri::State<'_, FocusState>,
+) -> Result<(), String> {
+    let settings = state.settings();
+    let change = state.update(&app, |session, now| {
+        let current = session
+            .as_mut()
+            .ok_or_else(|| "タイマーが動いていません".to_string())?;
+        let change = advance(current, &settings, now);
+        if change.next.is_none() {
+            *session = None;
+        }
+        Ok(change)
+    })?;
+    record_focus(&app, &change.todo_id, &change.title, change.focused);
+    let _ = app.emit("focus-phase-changed", &change);
+    Ok(())
+}
+
+#[tauri::command]
+pub fn focus_stop(
+    app: tauri::AppHandle,
+    state: tauri::State<'_, FocusState>,
+) -> Result<(), String> {
+    // 集中の途中で止めた場合もそこまでは記録する
+    let stopped = state.update(&app, |session, now| {
+        Ok(session
+            .take()
+            .map(|stopped| (stopped.focused_range(stopped.ended_at(now)), stopped)))
+    })?;
+    if let Some((focused, stopped)) = stopped {
+        println!("[Focus Backend] タイマー停止: {}", stopped.todo_id);
+        record_focus(&app, &stopped.todo_id, &stopped.title, focused);
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const T0: i64 = 1_700_000_000_000;
+
+    fn settings() -> FocusSettings {
+        FocusSettings {
+            auto_start_focus: true,
+            ..FocusSettings::default()
+        }
+    }
+
+    fn session(pomodoro: bool) -> FocusSession {
+        let mut session = FocusSession {
+            todo_id: "t1".to_string(),
+            title: "資料作成".to_string(),
+            pomodoro,
+            focus_minutes: 25,
+            phase: FocusPhase::Focus,
+            phase_minutes: 0,
+            ends_at: None,
+            remaining_ms: 0,
+            completed_focus: 0,
+            started_at: T0,
+        };
+        session.begin_phase(FocusPhase::Focus, 25, true, T0);
+        session
+    }
+
+    #[test]
+    fn starts_the_next_phase_when_the_focus_ends() {
+        let mut s = session(true);
+        let end = T0 + 25 * MINUTE_MS;
+        let change = advance(&mut s, &settings(), end + 500);
+        assert_eq!(change.focused, Some((T0, end)));
+        assert_eq!(change.next, Some(FocusPhase::ShortBreak));
+        assert_eq!(s.phase, FocusPhase::ShortBreak);
+        // 次の区間は tick が遅れた分ではなく終了時刻から数える
+        assert_eq!(s.ends_at, Some(end + 5 * MINUTE_MS));
+    }
+
+    #[test]
+    fn clamps_expired_sessions_to_their_end_time() {
+        let mut s = session(true);
+        let end = T0 + 25 * MINUTE_MS;
+        // 翌日に起動した
+        let now = end + 24 * 60 * MINUTE_MS;
+        let change = advance(&mut s, &settings(), now);
+        assert_eq!(change.focused, Some((T0, end)));
+        assert_eq!(change.completed_focus, 1);
+        // 休憩もとうに過ぎているので開始待ちで最初から
+        assert_eq!(s.phase, FocusPhase::ShortBreak);
+        assert_eq!(s.ends_at, None);
+        assert_eq!(s.remaining_ms, 5 * MINUTE_MS);
+        assert!(s.status(now).paused);
+    }
+
+    #[test]
+    fn continues_a_break_that_is_partly_over() {
+        let mut s = session(true);
+        let end = T0 + 25 * MINUTE_MS;
+        let now = end + 2 * MINUTE_MS;
+        advance(&mut s, &settings(), now);
+        assert_eq!(s.ends_at, Some(end + 5 * MINUTE_MS));
+        assert_eq!(s.remaining(now), 3 * MINUTE_MS);
+
+        // 休憩の終わりでは集中時間を記録しない
+        let change = advance(&mut s, &settings(), end + 5 * MINUTE_MS);
+        assert_eq!(change.focused, None);
+        assert_eq!(change.next, Some(FocusPhase::Focus));
+        assert_eq!(s.ends_at, Some(end + 30 * MINUTE_MS));
+    }
+
+    #[test]
+    fn waits_when_auto_start_is_off() {
+        let mut s = session(true);
+        let settings = FocusSettings {
+            auto_start_breaks: false,
+            ..settings()
+        };
+        let change = advance(&mut s, &settings, T0 + 25 * MINUTE_MS);
+        assert_eq!(change.next, Some(FocusPhase::ShortBreak));
+        assert_eq!(s.ends_at, None);
+        let (_, body) = notification(&s, &change);
+        assert!(body.ends_with("(開始待ち)"), "{}", body);
+    }
+
+    #[test]
+    fn takes_a_long_break_every_n_focus() {
+        let mut s = session(true);
+        let settings = settings();
+        let mut now = T0;
+        let mut breaks = Vec::new();
+        for _ in 0..8 {
+            now = s.ends_at.unwrap();
+            let change = advance(&mut s, &settings, now);
+            if change.finished == FocusPhase::Focus {
+                breaks.push(change.next.unwrap());
+            }
+        }
+        assert_eq!(
+            breaks,
+            [
+                FocusPhase::ShortBreak,
+                FocusPhase::ShortBreak,
+                FocusPhase::ShortBreak,
+                FocusPhase::LongBreak,
+            ]
+        );
+        assert_eq!(s.completed_focus, 4);
+        assert_eq!(s.phase, FocusPhase::Focus);
+        assert_eq!(now, T0 + (4 * 25 + 3 * 5 + 15) * MINUTE_MS);
+    }
+
+    #[test]
+    fn plain_timer_ends_after_one_focus() {
+        let mut s = session(false);
+        let end = T0 + 25 * MINUTE_MS;
+        let change = advance(&mut s, &settings(), end + MINUTE_MS);
+        assert_eq!(change.next, None);
+        assert_eq!(change.focused, Some((T0, end)));
+    }
+
+    #[test]
+    fn skipping_records_only_the_time_focused() {
+        let mut s = session(true);
+        // 10分集中して5分一時停止し、再開して5分後に飛ばす
+        let paused_at = T0 + 10 * MINUTE_MS;
+        s.remaining_ms = s.remaining(paused_at);
+        s.ends_at = None;
+        let resumed_at = paused_at + 5 * MINUTE_MS;
+        s.ends_at = Some(resumed_at + s.remaining_ms);
+        let now = resumed_at + 5 * MINUTE_MS;
+        let change = advance(&mut s, &settings(), now);
+        assert_eq!(change.focused, Some((now - 15 * MINUTE_MS, now)));
+        assert_eq!(s.ends_at, Some(now + 5 * MINUTE_MS));
+    }
+
+    #[test]
+    fn stopping_late_does_not_count_time_past_the_end() {
+        let s = session(true);
+        let end = T0 + 25 * MINUTE_MS;
+        let now = end + 10 * MINUTE_MS;
+        assert_eq!(s.focused_range(s.ended_at(now)), Some((T0, end)));
+        // 始めてすぐなら記録しない
+        assert_eq!(s.focused_range(s.ended_at(T0)), None);
+    }
+
+    #[test]
+    fn tooltip_truncates_long_titles() {
+        let mut s = session(true);
+        s.title = "あ".repeat(50);
+        let text = tooltip(Some(&s.status(T0 + 1000)));
+        assert_eq!(text, format!("Calm Todo\n集中 24:59\n{}…", "あ".repeat(40)));
+        s.ends_at = None;
+        s.remaining_ms = 61 * 1000;
+        assert!(tooltip(Some(&s.status(T0))).contains("01:01 (一時停止中)"));
+        assert_eq!(tooltip(None), "Calm Todo");
+    }
+}
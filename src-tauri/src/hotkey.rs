@@ -0,0 +1,165 @@
+// グローバルショートカット (どのアプリを使っていてもクイック追加ウィンドウを開く)
+// 割り当ては保存して起動時に登録し直す。他のアプリが使っているなどで登録できなかったときは
+// その理由を状態に残し、設定画面から割り当て直せるようにする
+
+use std::str::FromStr;
+use std::sync::Mutex;
+use tauri::Manager;
+use tauri_plugin_global_shortcut::{GlobalShortcutExt, Shortcut, ShortcutEvent, ShortcutState};
+
+const SETTINGS_FILE: &str = "hotkey-settings.json";
+const DEFAULT_QUICK_ADD: &str = "CommandOrControl+Shift+Space";
+
+#[derive(serde::Serialize, serde::Deserialize, Clone, Debug)]
+#[serde(rename_all = "camelCase", default)]
+pub struct HotkeySettings {
+    // 空なら割り当てなし
+    pub quick_add: String,
+}
+
+impl Default for HotkeySettings {
+    fn default() -> Self {
+        Self {
+            quick_add: DEFAULT_QUICK_ADD.to_string(),
+        }
+    }
+}
+
+#[derive(serde::Serialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct HotkeyStatus {
+    pub quick_add: String,
+    pub registered: bool,
+    // 登録できなかった理由
+    pub error: Option<String>,
+}
+
+fn parse(accelerator: &str) -> Result<Shortcut, String> {
+    Shortcut::from_str(accelerator.trim()).map_err(|e| {
+        format!(
+            "ショートカットの形式が正しくありません: {} ({})",
+            accelerator, e
+        )
+    })
+}
+
+fn register(app: &tauri::AppHandle, shortcut: Shortcut) -> Result<(), String> {
+    app.global_shortcut().register(shortcut).map_err(|e| {
+        format!(
+            "ショートカット {} を登録できません。他のアプリで使われている可能性があります ({})",
+            shortcut, e
+        )
+    })
+}
+
+pub struct HotkeyState {
+    settings: Mutex<HotkeySettings>,
+    // 登録中のショートカット
+    registered: Mutex<Option<Shortcut>>,
+    error: Mutex<Option<String>>,
+}
+
+impl HotkeyState {
+    pub fn load(app: &tauri::AppHandle) -> Self {
+        let settings = crate::store::load_json(app, SETTINGS_FILE).unwrap_or_else(|e| {
+            println!("[Hotkey Backend] 設定読み込み失敗: {}", e);
+            HotkeySettings::default()
+        });
+        Self {
+            settings: Mutex::new(settings),
+            registered: Mutex::new(None),
+            error: Mutex::new(None),
+        }
+    }
+
+    fn status(&self) -> HotkeyStatus {
+        HotkeyStatus {
+            quick_add: self.settings.lock().unwrap().quick_add.clone(),
+            registered: self.registered.lock().unwrap().is_some(),
+            error: self.error.lock().unwrap().clone(),
+        }
+    }
+
+    // 保存されている割り当てを登録する (起動時)
+    pub fn register_saved(&self, app: &tauri::AppHandle) {
+        let accelerator = self.settings.lock().unwrap().quick_add.clone();
+        if accelerator.trim().is_empty() {
+            return;
+        }
+        match parse(&accelerator).and_then(|shortcut| register(app, shortcut).map(|_| shortcut)) {
+            Ok(shortcut) => {
+                println!("[Hotkey Backend] ショートカット登録: {}", accelerator);
+                *self.registered.lock().unwrap() = Some(shortcut);
+            }
+            Err(e) => {
+                println!("[Hotkey Backend] {}", e);
+                *self.error.lock().unwrap() = Some(e);
+            }
+        }
+    }
+
+    fn is_quick_add(&self, shortcut: &Shortcut) -> bool {
+        self.registered
+            .lock()
+            .unwrap()
+            .is_some_and(|registered| registered.id() == shortcut.id())
+    }
+}
+
+// プラグインに渡すハンドラ (押したときだけ反応する)
+pub fn handle(app: &tauri::AppHandle, shortcut: &Shortcut, event: ShortcutEvent) {
+    if event.state() != ShortcutState::Pressed {
+        return;
+    }
+    if app.state::<HotkeyState>().is_quick_add(shortcut) {
+        crate::open_quick_add(app);
+    }
+}
+
+#[tauri::command]
+pub fn hotkey_get(state: tauri::State<'_, HotkeyState>) -> HotkeyStatus {
+    state.status()
+}
+
+// 割り当てを変更する。新しい割り当てを登録できたときだけ前の割り当てを外して保存する
+// (登録できなければエラーを返し、前の割り当てのまま)。空文字なら割り当てを外す
+#[tauri::command]
+pub fn hotkey_set(
+    app: tauri::AppHandle,
+    state: tauri::State<'_, HotkeyState>,
+    quick_add: String,
+) -> Result<HotkeyStatus, String> {
+    let quick_add = quick_add.trim().to_string();
+    let shortcut = if quick_add.is_empty() {
+        None
+    } else {
+        Some(parse(&quick_add)?)
+    };
+
+    let mut registered = state.registered.lock().unwrap();
+    let unchanged = matches!(
+        (*registered, shortcut),
+        (Some(current), Some(next)) if current.id() == next.id()
+    );
+    if !unchanged {
+        if let Some(next) = shortcut {
+            register(&app, next)?;
+        }
+        if let Some(previous) = registered.take() {
+            if let Err(e) = app.global_shortcut().unregister(previous) {
+                println!("[Hotkey Backend] ショートカット解除失敗: {}", e);
+            }
+        }
+        *registered = shortcut;
+    }
+    drop(registered);
+
+    let settings = HotkeySettings {
+        quick_add: quick_add.clone(),
+    };
+    crate::store::save_json(&app, SETTINGS_FILE, &settings)?;
+    *state.settings.lock().unwrap() = settings;
+    *state.error.lock().unwrap() = None;
+    println!("[Hotkey Backend] ショートカット変更: {}", quick_add);
+    Ok(state.status())
+}
@@ -0,0 +1,1178 @@
+// iCalendar (.ics) のインポート・エクスポート
+// Googleカレンダー/Googleタスク/Outlook などのVEVENT・VTODOをタスクに変換し、タスクをVTODOとして書き出す
+
+mod parser;
+pub mod rrule;
+pub mod timezone;
+
+use chrono::{DateTime, Datelike, Duration, Local, NaiveDateTime, TimeZone, Utc};
+use serde_json::Value;
+use std::collections::{BTreeMap, HashSet};
+
+use crate::models::{DueDateNotification, RecurrencePattern, Todo};
+use parser::{escape_text, fold_line, parse_date_time, parse_duration, split_text_list};
+use parser::{unescape_text, Component, IcsDateTime, Property};
+use rrule::{Frequency, RRule, RRuleIter};
+use timezone::{TimezoneResolver, Zone};
+
+const DAY_MS: i64 = 24 * 60 * 60 * 1000;
+// 次の発生日を探すときに調べる最大件数
+const MAX_OCCURRENCE_SCAN: usize = 100_000;
+
+#[derive(serde::Serialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum IcsItemKind {
+    Event,
+    Task,
+}
+
+#[derive(serde::Serialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct IcsItem {
+    pub uid: String,
+    pub kind: IcsItemKind,
+    pub summary: String,
+    pub description: Option<String>,
+    pub start: Option<i64>,
+    pub end: Option<i64>,
+    pub due: Option<i64>,
+    pub all_day: bool,
+    pub rrule: Option<String>,
+    pub exdates: Vec<i64>,
+    // RRULE の COUNT のうち、次の発生日時を含めて残っている回数
+    pub remaining_count: Option<u32>,
+    // RRULE の UNTIL (UTC ミリ秒)
+    pub until: Option<i64>,
+    // 繰り返しの場合、現在以降で最初の発生日時 (DUEがあればDUE側に換算)
+    pub next_occurrence: Option<i64>,
+    // 繰り返しがすべて過去に終わっている
+    pub series_ended: bool,
+    pub categories: Vec<String>,
+    pub priority: Option<u8>,
+    pub status: Option<String>,
+    pub percent_complete: Option<u8>,
+    pub completed_at: Option<i64>,
+    pub related_to: Option<String>,
+    pub last_modified: Option<i64>,
+}
+
+impl IcsItem {
+    fn is_completed(&self) -> bool {
+        self.status.as_deref() == Some("COMPLETED") || self.percent_complete == Some(100)
+    }
+
+    // VTODOはDUEを優先し、なければ開始日時
+    fn effective_date(&self) -> Option<i64> {
+        self.next_occurrence.or(self.due).or(self.start)
+    }
+}
+
+#[derive(serde::Deserialize, Clone, Copy)]
+#[serde(rename_all = "camelCase", default)]
+pub struct IcsImportOptions {
+    pub import_completed: bool,
+    pub import_past: bool,
+    pub import_tasks: bool,
+    pub import_events: bool,
+}
+
+impl Default for IcsImportOptions {
+    fn default() -> Self {
+        Self {
+            import_completed: true,
+            import_past: false,
+            import_tasks: true,
+            import_events: false,
+        }
+    }
+}
+
+#[derive(serde::Serialize, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct SkippedReasons {
+    pub completed: usize,
+    pub past: usize,
+    pub is_event: usize,
+}
+
+#[derive(serde::Serialize, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct ImportStats {
+    pub total: usize,
+    pub total_tasks: usize,
+    pub total_events: usize,
+    pub imported: usize,
+    pub skipped: usize,
+    pub skipped_reasons: SkippedReasons,
+}
+
+#[derive(serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct IcsImportResult {
+    // id はフロントエンドで採番する
+    pub todos: Vec<Todo>,
+    pub stats: ImportStats,
+}
+
+// 日時プロパティを TZID を考慮してミリ秒に変換する
+fn resolve_time(
+    property: &Property,
+    resolver: &TimezoneResolver,
+) -> Option<(IcsDateTime, Zone, i64)> {
+    let date_time = parse_date_time(&property.value)?;
+    let zone = if date_time.utc {
+        Zone::Utc
+    } else if date_time.date_only {
+        Zone::Floating
+    } else {
+        resolver.zone(property.param("TZID"))
+    };
+    let millis = zone.to_millis(date_time.value)?;
+    Some((date_time, zone, millis))
+}
+
+fn resolve_list(component: &Component, name: &str, resolver: &TimezoneResolver) -> Vec<i64> {
+    component
+        .properties_named(name)
+        .flat_map(|property| {
+            property.value.split(',').filter_map(move |value| {
+                let single = Property {
+                    name: property.name.clone(),
+                    params: property.params.clone(),
+                    value: value.to_string(),
+                };
+                resolve_time(&single, resolver).map(|(_, _, millis)| millis)
+            })
+        })
+        .collect()
+}
+
+// 繰り返しを threshold の少し前から展開する
+// COUNT がなく周期が一定 (WEEKLY 以下) の規則は、DTSTART を周期の整数倍だけ進めても発生日時は変わらない
+// 何年も続く毎時の繰り返しなどが、調べる件数の上限で途中で打ち切られないようにする
+fn occurrences_near(
+    rule: &RRule,
+    dtstart: NaiveDateTime,
+    zone: &Zone,
+    threshold: i64,
+) -> RRuleIter {
+    let unit = match rule.freq {
+        Frequency::Secondly => 1,
+        Frequency::Minutely => 60,
+        Frequency::Hourly => 3600,
+        Frequency::Daily => 86_400,
+        Frequency::Weekly => 7 * 86_400,
+        Frequency::Monthly | Frequency::Yearly => return rule.iter(dtstart),
+    };
+    let Some(threshold) = DateTime::from_timestamp_millis(threshold) else {
+        return rule.iter(dtstart);
+    };
+    if rule.count.is_some() {
+        return rule.iter(dtstart);
+    }
+    // 時差や終日の予定の分の余裕を見て2日前から
+    let target = zone.localize_utc(threshold.naive_utc()) - Duration::days(2);
+    let period = unit * rule.interval as i64;
+    let periods = (target - dtstart).num_seconds() / period - 1;
+    if periods <= 0 {
+        return rule.iter(dtstart);
+    }
+    // 進めた DTSTART は規則に合うときだけ発生日時にする
+    rule.iter_from(dtstart + Duration::seconds(period * periods), false)
+}
+
+fn localized_rule(rule: &RRule, zone: &Zone) -> RRule {
+    let mut rule = rule.clone();
+    if rule.until_utc {
+        rule.until = rule.until.map(|until| zone.localize_utc(until));
+        rule.until_utc = false;
+    }
+    rule
+}
+
+// RRULE・RDATE・EXDATE を合わせて、threshold 以降で最初の発生日時を求める
+fn next_occurrence(
+    rule: &RRule,
+    anchor: IcsDateTime,
+    zone: &Zone,
+    rdates: &[i64],
+    exdates: &[i64],
+    threshold: i64,
+) -> Option<i64> {
+    let rule = localized_rule(rule, zone);
+    // 終日の予定はその日の終わりまでは過去とみなさない
+    let span = if anchor.date_only { DAY_MS } else { 0 };
+
+    let from_rule = occurrences_near(&rule, anchor.value, zone, threshold - span)
+        .take(MAX_OCCURRENCE_SCAN)
+        .filter_map(|occurrence| zone.to_millis(occurrence))
+        .find(|millis| millis + span > threshold && !exdates.contains(millis));
+    let from_rdates = rdates
+        .iter()
+        .copied()
+        .filter(|millis| millis + span > threshold && !exdates.contains(millis))
+        .min();
+
+    match (from_rule, from_rdates) {
+        (Some(a), Some(b)) => Some(a.min(b)),
+        (a, b) => a.or(b),
+    }
+}
+
+fn parse_item(component: &Component, resolver: &TimezoneResolver, now: i64) -> Option<IcsItem> {
+    let kind = match component.name.as_str() {
+        "VEVENT" => IcsItemKind::Event,
+        "VTODO" => IcsItemKind::Task,
+        _ => return None,
+    };
+    let summary = unescape_text(component.value("SUMMARY")?)
+        .trim()
+        .to_string();
+    if summary.is_empty() {
+        return None;
+    }
+
+    let start = component
+        .property("DTSTART")
+        .and_then(|p| resolve_time(p, resolver));
+    let due = component
+        .property("DUE")
+        .and_then(|p| resolve_time(p, resolver));
+    let end = component
+        .property("DTEND")
+        .and_then(|p| resolve_time(p, resolver))
+        .map(|(_, _, millis)| millis)
+        .or_else(|| {
+            let duration = parse_duration(component.value("DURATION")?)?;
+            start
+                .as_ref()
+                .map(|(_, _, millis)| millis + duration * 1000)
+        });
+
+    let rrule_value = component.value("RRULE").map(str::to_string);
+    let exdates = resolve_list(component, "EXDATE", resolver);
+    let rdates = resolve_list(component, "RDATE", resolver);
+
+    // 繰り返しの基準は DTSTART (VTODO で DTSTART がなければ DUE)
+    let mut next = None;
+    let mut series_ended = false;
+    let mut remaining_count = None;
+    let mut until = None;
+    if let (Some(value), Some((anchor, zone, anchor_millis))) =
+        (&rrule_value, start.as_ref().or(due.as_ref()))
+    {
+        match RRule::parse(value) {
+            Ok(rule) => {
+                // DTSTART と DUE の両方がある場合は、発生日時に同じ間隔を足したものを期限とする
+                let due_offset = match (&start, &due) {
+                    (Some(_), Some((_, _, due_millis))) => due_millis - anchor_millis,
+                    _ => 0,
+                };
+                next = next_occurrence(&rule, *anchor, zone, &rdates, &exdates, now - due_offset)
+                    .map(|millis| millis + due_offset);
+                series_ended = next.is_none();
+                // COUNT は除外日も含めて数える。次の発生日時より前の回数を引いて残りとする
+                if let (Some(count), Some(next)) = (rule.count, next) {
+                    let passed = localized_rule(&rule, zone)
+                        .iter(anchor.value)
+                        .filter_map(|occurrence| zone.to_millis(occurrence))
+                        .take_while(|millis| millis + due_offset < next)
+                        .count() as u32;
+                    remaining_count = Some(count.saturating_sub(passed).max(1));
+                }
+                until = rule.until.and_then(|until| {
+                    if rule.until_utc {
+                        Some(until.and_utc().timestamp_millis())
+                    } else {
+                        zone.to_millis(until)
+                    }
+                });
+            }
+            Err(e) => println!("[ICS Backend] RRULE解析失敗 ({}): {}", value, e),
+        }
+    }
+
+    Some(IcsItem {
+        uid: component
+            .value("UID")
+            .map(str::to_string)
+            .unwrap_or_default(),
+        kind,
+        summary,
+        description: component
+            .value("DESCRIPTION")
+            .map(unescape_text)
+            .filter(|d| !d.is_empty()),
+        all_day: start
+            .as_ref()
+            .or(due.as_ref())
+            .is_some_and(|(d, _, _)| d.date_only),
+        start: start.map(|(_, _, millis)| millis),
+        end,
+        due: due.map(|(_, _, millis)| millis),
+        rrule: rrule_value,
+        exdates,
+        remaining_count,
+        until,
+        next_occurrence: next,
+        series_ended,
+        categories: component
+            .properties_named("CATEGORIES")
+            .flat_map(|p| split_text_list(&p.value))
+            .collect(),
+        priority: component
+            .value("PRIORITY")
+            .and_then(|p| p.trim().parse().ok()),
+        status: component
+            .value("STATUS")
+            .map(|s| s.trim().to_ascii_uppercase()),
+        percent_complete: component
+            .value("PERCENT-COMPLETE")
+            .and_then(|p| p.trim().parse().ok()),
+        completed_at: component
+            .property("COMPLETED")
+            .and_then(|p| resolve_time(p, resolver))
+            .map(|(_, _, millis)| millis),
+        related_to: component.value("RELATED-TO").map(str::to_string),
+        last_modified: component
+            .property("LAST-MODIFIED")
+            .and_then(|p| resolve_time(p, resolver))
+            .map(|(_, _, millis)| millis),
+    })
+}
+
+pub fn parse_ics(content: &str) -> Vec<IcsItem> {
+    let root = parser::parse_components(content);
+    let resolver = TimezoneResolver::from_calendar(&root);
+    let now = Local::now().timestamp_millis();
+
+    let mut components = Vec::new();
+    root.find_all("VEVENT", &mut components);
+    root.find_all("VTODO", &mut components);
+    components
+        .into_iter()
+        .filter_map(|component| parse_item(component, &resolver, now))
+        .collect()
+}
+
+// RRULE を繰り返し設定に変換する
+// 簡易設定の項目で表せるものはその項目に、表せないもの (BYSETPOS や曜日ごとに違う序数など) は RRULE のまま rrule に持つ
+// COUNT は次の発生日時からの残り回数として count に持つ (完了のたびに減らすため rrule には残さない)
+// 時・分・秒単位の繰り返しはタスクの繰り返しとして扱えないため変換しない
+fn rrule_to_recurrence(rule: &RRule, item: &IcsItem) -> Option<RecurrencePattern> {
+    let kind = match rule.freq {
+        Frequency::Daily => "daily",
+        Frequency::Weekly => "weekly",
+        Frequency::Monthly => "monthly",
+        Frequency::Yearly => "yearly",
+        _ => {
+            println!(
+                "[ICS Backend] 繰り返しに変換できないRRULEです: {}",
+                item.rrule.as_deref().unwrap_or_default()
+            );
+            return None;
+        }
+    };
+    let mut pattern = RecurrencePattern {
+        kind: kind.to_string(),
+        interval: rule.interval,
+        count: item.remaining_count.or(rule.count),
+        until: item.until,
+        exdates: item.exdates.clone(),
+        ..Default::default()
+    };
+
+    let plain_days = !rule.by_day.is_empty() && rule.by_day.iter().all(|(n, _)| n.is_none());
+    let days_of_week = || {
+        Some(
+            rule.by_day
+                .iter()
+                .map(|(_, weekday)| weekday.num_days_from_sunday())
+                .collect(),
+        )
+    };
+    // 第n曜日は、すべての曜日が同じ序数のときだけ表せる
+    let ordinal = match rule.by_day.first() {
+        Some((Some(n), _)) if (1..=5).contains(n) || *n == -1 => {
+            Some(*n).filter(|n| rule.by_day.iter().all(|(m, _)| *m == Some(*n)))
+        }
+        _ => None,
+    };
+    let no_time_rules =
+        rule.by_second.is_empty() && rule.by_minute.is_empty() && rule.by_hour.is_empty();
+    let no_other_rules = no_time_rules
+        && rule.by_year_day.is_empty()
+        && rule.by_week_no.is_empty()
+        && rule.by_month.is_empty()
+        && rule.by_set_pos.is_empty()
+        // 週の始まりは隔週以上で曜日が複数あるときだけ結果に影響する
+        && (rule.wkst == chrono::Weekday::Mon || rule.freq != Frequency::Weekly || rule.interval == 1);
+    let simple = no_other_rules
+        && match rule.freq {
+            Frequency::Daily => rule.by_day.is_empty() && rule.by_month_day.is_empty(),
+            Frequency::Weekly => {
+                rule.by_month_day.is_empty() && (rule.by_day.is_empty() || plain_days)
+            }
+            Frequency::Monthly => match (rule.by_day.is_empty(), rule.by_month_day.as_slice()) {
+                (true, []) => true,
+                // その日がない月を飛ばす29日以降は、月末に丸める簡易設定とは意味が違う
+                (true, [day]) if (1..=28).contains(day) => {
+                    pattern.day_of_month = Some(*day as u32);
+                    true
+                }
+                (false, []) if ordinal.is_some() => {
+                    pattern.week_of_month = ordinal;
+                    pattern.days_of_week = days_of_week();
+                    true
+                }
+                _ => false,
+            },
+            Frequency::Yearly => rule.by_day.is_empty() && rule.by_month_day.is_empty(),
+            _ => false,
+        };
+    if simple {
+        if rule.freq == Frequency::Weekly && plain_days {
+            pattern.days_of_week = days_of_week();
+        }
+        return Some(pattern);
+    }
+
+    let raw = item.rrule.as_deref()?.trim();
+    let raw = raw.strip_prefix("RRULE:").unwrap_or(raw);
+    let rrule: Vec<&str> = raw
+        .split(';')
+        .filter(|part| {
+            let key = part.split('=').next().unwrap_or_default().trim();
+            !part.trim().is_empty() && !key.eq_ignore_ascii_case("COUNT")
+        })
+        .collect();
+    pattern.rrule = Some(rrule.join(";"));
+    Some(pattern)
+}
+
+fn recurrence_to_rrule(recurrence: &RecurrencePattern) -> Option<String> {
+    crate::recurrence::to_rrule_string(recurrence).ok()
+}
+
+// ICS の優先度 (1-9、0は未定義) を P1-P4 に変換する
+fn priority_from_ics(priority: Option<u8>) -> u8 {
+    match priority {
+        Some(1..=2) => 1,
+        Some(3..=4) => 2,
+        Some(5..=6) => 3,
+        _ => 4,
+    }
+}
+
+fn priority_to_ics(priority: u8) -> Option<u8> {
+    match priority {
+        1 => Some(1),
+        2 => Some(3),
+        3 => Some(5),
+        _ => None,
+    }
+}
+
+pub fn timeframe_for(due_date: i64, recurrence: Option<&RecurrencePattern>, now: i64) -> String {
+    if let Some(recurrence) = recurrence {
+        let timeframe = match recurrence.kind.as_str() {
+            "daily" => "today",
+            "weekly" => "week",
+            "monthly" => "month",
+            _ => "year",
+        };
+        return timeframe.to_string();
+    }
+    let diff_days = (due_date - now) as f64 / DAY_MS as f64;
+    let timeframe = match diff_days.ceil() as i64 {
+        i64::MIN..=1 => "today",
+        2..=7 => "week",
+        8..=31 => "month",
+        _ => "year",
+    };
+    timeframe.to_string()
+}
+
+pub fn item_to_todo(item: &IcsItem, now: i64) -> Todo {
+    let rule = item.rrule.as_deref().and_then(|r| RRule::parse(r).ok());
+    let recurrence = rule
+        .as_ref()
+        .and_then(|rule| rrule_to_recurrence(rule, item));
+    let completed = item.is_completed();
+    let due_date = item.effective_date().unwrap_or(now);
+
+    let mut extra = serde_json::Map::new();
+    if !item.uid.is_empty() {
+        extra.insert("icsUid".to_string(), Value::String(item.uid.clone()));
+    }
+    if let Some(rrule) = &item.rrule {
+        extra.insert("icsRrule".to_string(), Value::String(rrule.clone()));
+    }
+
+    Todo {
+        text: item.summary.clone(),
+        completed,
+        completed_at: completed.then(|| item.completed_at.unwrap_or(now)),
+        created_at: now,
+        priority: priority_from_ics(item.priority),
+        timeframe: timeframe_for(due_date, recurrence.as_ref(), now),
+        due_date: Some(due_date),
+        due_date_notification: Some(DueDateNotification {
+            enabled: true,
+            notify_before: 0,
+            notified_at: None,
+            follow_up_count: 0,
+        }),
+        labels: item.categories.clone(),
+        recurrence,
+        description: item.description.clone().unwrap_or_default(),
+        estimated_minutes: match (item.start, item.end) {
+            (Some(start), Some(end)) if end > start => Some(((end - start) / 60_000) as u32),
+            _ => None,
+        },
+        extra,
+        updated_at: item.last_modified,
+        ..Default::default()
+    }
+}
+
+// 既存のタスクにICS側の内容を反映する (並び順やコメントなどアプリ固有の項目は残す)
+pub fn apply_item(item: &IcsItem, todo: &mut Todo, now: i64) {
+    let imported = item_to_todo(item, now);
+    todo.text = imported.text;
+    todo.description = imported.description;
+    todo.priority = imported.priority;
+    todo.due_date = imported.due_date;
+    todo.labels = imported.labels;
+    todo.recurrence = imported.recurrence;
+    if todo.completed != imported.completed {
+        todo.completed = imported.completed;
+        todo.completed_at = imported.completed_at;
+    }
+    for (key, value) in imported.extra {
+        todo.extra.insert(key, value);
+    }
+    if item.rrule.is_none() {
+        todo.extra.remove("icsRrule");
+    }
+    todo.updated_at = Some(item.last_modified.unwrap_or(now));
+}
+
+pub fn import_ics(content: &str, options: IcsImportOptions) -> IcsImportResult {
+    let items = parse_ics(content);
+    let now = Local::now().timestamp_millis();
+    let mut stats = ImportStats {
+        total: items.len(),
+        total_tasks: items.iter().filter(|i| i.kind == IcsItemKind::Task).count(),
+        total_events: items
+            .iter()
+            .filter(|i| i.kind == IcsItemKind::Event)
+            .count(),
+        ..Default::default()
+    };
+
+    let mut todos = Vec::new();
+    for item in &items {
+        if item.kind == IcsItemKind::Task && !options.import_tasks {
+            stats.skipped += 1;
+            continue;
+        }
+        if item.kind == IcsItemKind::Event && !options.import_events {
+            stats.skipped += 1;
+            stats.skipped_reasons.is_event += 1;
+            continue;
+        }
+        if !options.import_completed && item.is_completed() {
+            stats.skipped += 1;
+            stats.skipped_reasons.completed += 1;
+            continue;
+        }
+        // 繰り返しは今後の発生があれば過去扱いにしない
+        let past = if item.rrule.is_some() && item.next_occurrence.is_some() {
+            false
+        } else if item.series_ended {
+            true
+        } else {
+            let span = if item.all_day { DAY_MS } else { 0 };
+            item.effective_date().is_some_and(|date| date + span < now)
+        };
+        if !options.import_past && past {
+            stats.skipped += 1;
+            stats.skipped_reasons.past += 1;
+            continue;
+        }
+
+        todos.push(item_to_todo(item, now));
+        stats.imported += 1;
+    }
+
+    IcsImportResult { todos, stats }
+}
+
+fn format_utc(millis: i64) -> Option<String> {
+    Utc.timestamp_millis_opt(millis)
+        .single()
+        .map(|d| d.format("%Y%m%dT%H%M%SZ").to_string())
+}
+
+// 期限の名前付きタイムゾーン (TZID と chrono-tz のタイムゾーン)
+fn named_due_zone(todo: &Todo) -> Option<(&str, chrono_tz::Tz)> {
+    let tzid = todo
+        .due_time_zone
+        .as_deref()
+        .map(str::trim)
+        .filter(|tz| !tz.is_empty())?;
+    match crate::timezone::due_zone(Some(tzid)).ok()? {
+        Zone::Named(tz) => Some((tzid, tz)),
+        _ => None,
+    }
+}
+
+// 期限のタイムゾーンがあれば TZID 付き (floating は TZID なし) の壁時計時刻で書き出す
+// 繰り返しを展開する側でも DST をまたいで同じ時刻になる
+// VTIMEZONE を書き出せなかったタイムゾーン (zones にないもの) は UTC にする
+fn format_due(todo: &Todo, due: i64, zones: &HashSet<String>) -> Option<String> {
+    const LOCAL: &str = "%Y%m%dT%H%M%S";
+    if crate::timezone::is_floating(todo) {
+        // 端末のタイムゾーンに左右されないよう、記録した壁時計時刻を優先する
+        let local = match todo.due_local.as_deref() {
+            Some(local) => crate::timezone::parse_local(local).ok()?,
+            None => crate::recurrence::to_local(&Zone::Floating, due).ok()?,
+        };
+        return Some(format!(":{}", local.format(LOCAL)));
+    }
+    match named_due_zone(todo).filter(|(tzid, _)| zones.contains(*tzid)) {
+        Some((tzid, tz)) => {
+            let local = crate::recurrence::to_local(&Zone::Named(tz), due).ok()?;
+            Some(format!(";TZID={}:{}", tzid, local.format(LOCAL)))
+        }
+        None => format_utc(due).map(|utc| format!(":{}", utc)),
+    }
+}
+
+// 期限で使うタイムゾーンごとの VTIMEZONE と、書き出せた TZID
+fn vtimezones(todos: &[&Todo]) -> (Vec<String>, HashSet<String>) {
+    // TZID ごとの期限の年の範囲
+    let mut years: BTreeMap<&str, (chrono_tz::Tz, i32, i32)> = BTreeMap::new();
+    for todo in todos.iter().filter(|t| !crate::timezone::is_floating(t)) {
+        let (Some(due), Some((tzid, tz))) = (todo.due_date, named_due_zone(todo)) else {
+            continue;
+        };
+        let Ok(local) = crate::recurrence::to_local(&Zone::Named(tz), due) else {
+            continue;
+        };
+        let year = local.year();
+        let range = years.entry(tzid).or_insert((tz, year, year));
+        range.1 = range.1.min(year);
+        range.2 = range.2.max(year);
+    }
+
+    let mut lines = Vec::new();
+    let mut written = HashSet::new();
+    for (tzid, (tz, from, through)) in years {
+        match timezone::vtimezone_lines(tzid, tz, from, through.min(from + 10) + 1) {
+            Some(definition) => {
+                lines.extend(definition);
+                written.insert(tzid.to_string());
+            }
+            None => println!(
+                "[ICS Backend] VTIMEZONEで表せないためUTCで書き出します: {}",
+                tzid
+            ),
+        }
+    }
+    (lines, written)
+}
+
+pub fn todo_uid(todo: &Todo) -> String {
+    todo.extra
+        .get("icsUid")
+        .and_then(Value::as_str)
+        .map(str::to_string)
+        .unwrap_or_else(|| format!("{}@calm-todo", todo.id))
+}
+
+fn vtodo_lines(todo: &Todo, todos: &[Todo], stamp: &str, zones: &HashSet<String>) -> Vec<String> {
+    let mut lines = vec![
+        "BEGIN:VTODO".to_string(),
+        format!("UID:{}", todo_uid(todo)),
+        format!("DTSTAMP:{}", stamp),
+    ];
+    if let Some(created) = format_utc(todo.created_at).filter(|_| todo.created_at > 0) {
+        lines.push(format!("CREATED:{}", created));
+    }
+    if let Some(modified) = format_utc(todo.last_modified()).filter(|_| todo.last_modified() > 0) {
+        lines.push(format!("LAST-MODIFIED:{}", modified));
+    }
+    lines.push(format!("SUMMARY:{}", escape_text(&todo.text)));
+    if !todo.description.is_empty() {
+        lines.push(format!("DESCRIPTION:{}", escape_text(&todo.description)));
+    }
+
+    // 取り込んだ繰り返しは RRULE の内容をすべて繰り返し設定に持っているので、設定から書き出す
+    let rrule = todo.recurrence.as_ref().and_then(recurrence_to_rrule);
+    if let Some(due) = todo.due_date.and_then(|due| format_due(todo, due, zones)) {
+        // RRULE には DTSTART が必要
+        if rrule.is_some() {
+            lines.push(format!("DTSTART{}", due));
+        }
+        lines.push(format!("DUE{}", due));
+    }
+    if let Some(rrule) = rrule {
+        lines.push(format!("RRULE:{}", rrule));
+        let exdates: Vec<String> = todo
+            .recurrence
+            .iter()
+            .flat_map(|recurrence| &recurrence.exdates)
+            .filter_map(|exdate| format_utc(*exdate))
+            .collect();
+        if !exdates.is_empty() {
+            lines.push(format!("EXDATE:{}", exdates.join(",")));
+        }
+    }
+
+    if let Some(priority) = priority_to_ics(todo.priority) {
+        lines.push(format!("PRIORITY:{}", priority));
+    }
+    if !todo.labels.is_empty() {
+        let categories: Vec<String> = todo.labels.iter().map(|l| escape_text(l)).collect();
+        lines.push(format!("CATEGORIES:{}", categories.join(",")));
+    }
+    if todo.completed {
+        lines.push("STATUS:COMPLETED".to_string());
+        lines.push("PERCENT-COMPLETE:100".to_string());
+        if let Some(completed) = todo.completed_at.and_then(format_utc) {
+            lines.push(format!("COMPLETED:{}", completed));
+        }
+    } else {
+        lines.push("STATUS:NEEDS-ACTION".to_string());
+    }
+    if let Some(parent_id) = &todo.parent_id {
+        let parent_uid = todos
+            .iter()
+            .find(|t| &t.id == parent_id)
+            .map(todo_uid)
+            .unwrap_or_else(|| format!("{}@calm-todo", parent_id));
+        lines.push(format!("RELATED-TO:{}", parent_uid));
+    }
+    lines.push("END:VTODO".to_string());
+    lines
+}
+
+// 予定の発生1回分 (繰り返しは展開済み)。時間ブロックの空き時間の計算に使う
+#[derive(serde::Serialize, Clone, Debug, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct BusyInterval {
+    pub uid: String,
+    pub summary: String,
+    pub start: i64,
+    pub end: i64,
+    pub all_day: bool,
+}
+
+// from〜to と重なる予定を返す。空き時間扱い (TRANSP:TRANSPARENT) とキャンセル済みは除く
+// 個別に変更された回 (RECURRENCE-ID) は元の繰り返しの代わりにその内容を使う
+pub fn busy_intervals(content: &str, from: i64, to: i64) -> Vec<BusyInterval> {
+    let root = parser::parse_components(content);
+    let resolver = TimezoneResolver::from_calendar(&root);
+    let mut events = Vec::new();
+    root.find_all("VEVENT", &mut events);
+
+    let busy = |event: &Component| {
+        !event
+            .value("TRANSP")
+            .is_some_and(|t| t.trim().eq_ignore_ascii_case("TRANSPARENT"))
+            && !event
+                .value("STATUS")
+                .is_some_and(|s| s.trim().eq_ignore_ascii_case("CANCELLED"))
+    };
+    // UID ごとの変更された回の元の日時
+    let mut overridden: Vec<(String, i64)> = Vec::new();
+    for event in &events {
+        let uid = event.value("UID").unwrap_or_default().to_string();
+        if let Some((_, _, millis)) = event
+            .property("RECURRENCE-ID")
+            .and_then(|p| resolve_time(p, &resolver))
+        {
+            overridden.push((uid, millis));
+        }
+    }
+
+    let mut intervals = Vec::new();
+    for event in events.iter().copied().filter(|event| busy(event)) {
+        let Some((anchor, zone, start)) = event
+            .property("DTSTART")
+            .and_then(|p| resolve_time(p, &resolver))
+        else {
+            continue;
+        };
+        let end = event
+            .property("DTEND")
+            .and_then(|p| resolve_time(p, &resolver))
+            .map(|(_, _, millis)| millis)
+            .or_else(|| Some(start + parse_duration(event.value("DURATION")?)? * 1000));
+        let length = match end {
+            Some(end) => (end - start).max(0),
+            None if anchor.date_only => DAY_MS,
+            None => 0,
+        };
+        let uid = event.value("UID").unwrap_or_default().to_string();
+        let summary = event
+            .value("SUMMARY")
+            .map(|s| unescape_text(s).trim().to_string())
+            .unwrap_or_default();
+        let mut push = |start: i64| {
+            if start < to && start + length > from {
+                intervals.push(BusyInterval {
+                    uid: uid.clone(),
+                    summary: summary.clone(),
+                    start,
+                    end: start + length,
+                    all_day: anchor.date_only,
+                });
+            }
+        };
+
+        let rule = event
+            .value("RRULE")
+            .and_then(|value| RRule::parse(value).ok());
+        let Some(mut rule) = rule.filter(|_| event.property("RECURRENCE-ID").is_none()) else {
+            push(start);
+            continue;
+        };
+        if rule.until_utc {
+            rule.until = rule.until.map(|until| zone.localize_utc(until));
+        }
+        let exdates = resolve_list(event, "EXDATE", &resolver);
+        let skip = |millis: i64| {
+            exdates.contains(&millis) || overridden.iter().any(|(u, m)| *u == uid && *m == millis)
+        };
+        let mut occurrences: Vec<i64> = occurrences_near(&rule, anchor.value, &zone, from - length)
+            .take(MAX_OCCURRENCE_SCAN)
+            .filter_map(|occurrence| zone.to_millis(occurrence))
+            .take_while(|millis| *millis < to)
+            .filter(|millis| !skip(*millis))
+            .collect();
+        occurrences.extend(
+            resolve_list(event, "RDATE", &resolver)
+                .into_iter()
+                .filter(|millis| !skip(*millis)),
+        );
+        for occurrence in occurrences {
+            push(occurrence);
+        }
+    }
+    intervals.sort_by_key(|i| (i.start, i.end));
+    intervals
+}
+
+// 書き出す予定 (時間ブロックなど)
+pub struct EventExport<'a> {
+    pub uid: String,
+    pub summary: &'a str,
+    pub description: Option<&'a str>,
+    pub start: i64,
+    pub end: i64,
+}
+
+pub fn export_events(events: &[EventExport]) -> String {
+    let stamp = format_utc(Utc::now().timestamp_millis()).unwrap_or_default();
+    let mut body = Vec::new();
+    for event in events {
+        let (Some(start), Some(end)) = (format_utc(event.start), format_utc(event.end)) else {
+            continue;
+        };
+        body.push("BEGIN:VEVENT".to_string());
+        body.push(format!("UID:{}", event.uid));
+        body.push(format!("DTSTAMP:{}", stamp));
+        body.push(format!("DTSTART:{}", start));
+        body.push(format!("DTEND:{}", end));
+        body.push(format!("SUMMARY:{}", escape_text(event.summary)));
+        if let Some(description) = event.description.filter(|d| !d.is_empty()) {
+            body.push(format!("DESCRIPTION:{}", escape_text(description)));
+        }
+        body.push("END:VEVENT".to_string());
+    }
+    wrap_calendar(body)
+}
+
+fn wrap_calendar(body: Vec<String>) -> String {
+    let mut lines = vec![
+        "BEGIN:VCALENDAR".to_string(),
+        "VERSION:2.0".to_string(),
+        "PRODID:-//Calm Todo//Calm Todo//JA".to_string(),
+        "CALSCALE:GREGORIAN".to_string(),
+    ];
+    lines.extend(body);
+    lines.push("END:VCALENDAR".to_string());
+    lines.iter().map(|line| fold_line(line)).collect()
+}
+
+fn write_calendar<'a>(todos: impl Iterator<Item = &'a Todo>, all: &[Todo]) -> String {
+    let stamp = format_utc(Utc::now().timestamp_millis()).unwrap_or_default();
+    let todos: Vec<&Todo> = todos.collect();
+    let (mut body, zones) = vtimezones(&todos);
+    body.extend(
+        todos
+            .iter()
+            .flat_map(|todo| vtodo_lines(todo, all, &stamp, &zones)),
+    );
+    wrap_calendar(body)
+}
+
+pub fn export_ics(todos: &[Todo], include_completed: bool) -> String {
+    write_calendar(
+        todos
+            .iter()
+            .filter(|t| !t.archived && (include_completed || !t.completed)),
+        todos,
+    )
+}
+
+// CalDAV のリソース1件分 (VTODO 1つだけを含むカレンダー)
+pub fn export_todo(todo: &Todo, all: &[Todo]) -> String {
+    write_calendar(std::iter::once(todo), all)
+}
+
+#[tauri::command]
+pub fn ics_parse(content: String) -> Vec<IcsItem> {
+    parse_ics(&content)
+}
+
+#[tauri::command]
+pub fn ics_import(content: String, options: Option<IcsImportOptions>) -> IcsImportResult {
+    import_ics(&content, options.unwrap_or_default())
+}
+
+#[tauri::command]
+pub fn ics_export(todos: Vec<Todo>, include_completed: Option<bool>) -> String {
+    export_ics(&todos, include_completed.unwrap_or(true))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn todo(id: &str, due: i64, time_zone: &str) -> Todo {
+        Todo {
+            id: id.to_string(),
+            text: id.to_string(),
+            due_date: Some(due),
+            due_time_zone: Some(time_zone.to_string()),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn writes_vtimezone_for_each_tzid() {
+        // 2026-07-01 09:00 (ニューヨーク、ベルリン)
+        let todos = [
+            todo("a", 1_782_910_800_000, "America/New_York"),
+            todo("b", 1_782_889_200_000, "Europe/Berlin"),
+            todo("c", 1_782_889_200_000, "Europe/Berlin"),
+        ];
+        let ics = export_ics(&todos, true);
+        assert_eq!(ics.matches("BEGIN:VTIMEZONE").count(), 2);
+        assert!(ics.contains("TZID:America/New_York\r\n"));
+        assert!(ics.contains("TZID:Europe/Berlin\r\n"));
+        assert!(ics.contains("DUE;TZID=America/New_York:20260701T090000\r\n"));
+        assert!(ics.contains("DUE;TZID=Europe/Berlin:20260701T090000\r\n"));
+        // VTIMEZONE は VTODO より前に置く
+        assert!(ics.find("END:VTIMEZONE").unwrap() < ics.find("BEGIN:VTODO").unwrap());
+
+        // 読み込むと同じ時刻になる
+        let items = parse_ics(&ics);
+        assert_eq!(items[0].due, Some(1_782_910_800_000));
+        assert_eq!(items[1].due, Some(1_782_889_200_000));
+    }
+
+    #[test]
+    fn writes_utc_without_named_zone() {
+        let ics = export_todo(&todo("a", 1_782_910_800_000, "UTC"), &[]);
+        assert!(!ics.contains("VTIMEZONE"));
+        assert!(ics.contains("DUE:20260701T130000Z\r\n"));
+    }
+
+    const T0: i64 = 1_767_225_600_000; // 2026-01-01T00:00:00Z
+    const HOUR_MS: i64 = 60 * 60 * 1000;
+
+    fn items_at(content: &str, now: i64) -> Vec<IcsItem> {
+        let root = parser::parse_components(content);
+        let resolver = TimezoneResolver::from_calendar(&root);
+        let mut components = Vec::new();
+        root.find_all("VTODO", &mut components);
+        components
+            .into_iter()
+            .filter_map(|component| parse_item(component, &resolver, now))
+            .collect()
+    }
+
+    fn vtodo(lines: &str) -> String {
+        format!(
+            "BEGIN:VCALENDAR\r\nBEGIN:VTODO\r\nUID:r\r\nSUMMARY:繰り返し\r\n{}END:VTODO\r\nEND:VCALENDAR\r\n",
+            lines
+        )
+    }
+
+    // 2026-01-02 (金) 09:00 UTC から始まる繰り返し
+    fn recurrence_of(rrule: &str) -> Option<RecurrencePattern> {
+        let content = vtodo(&format!("DTSTART:20260102T090000Z\r\nRRULE:{}\r\n", rrule));
+        let item = items_at(&content, T0).remove(0);
+        item_to_todo(&item, T0).recurrence
+    }
+
+    #[test]
+    fn maps_rrule_parts_to_recurrence_fields() {
+        let last_friday = recurrence_of("FREQ=MONTHLY;BYDAY=-1FR").unwrap();
+        assert_eq!(last_friday.kind, "monthly");
+        assert_eq!(last_friday.week_of_month, Some(-1));
+        assert_eq!(last_friday.days_of_week, Some(vec![5]));
+        assert_eq!(last_friday.rrule, None);
+        assert_eq!(
+            crate::recurrence::to_rrule_string(&last_friday).unwrap(),
+            "FREQ=MONTHLY;BYDAY=-1FR"
+        );
+
+        let biweekly = recurrence_of("FREQ=WEEKLY;INTERVAL=2;BYDAY=MO,FR").unwrap();
+        assert_eq!((biweekly.kind.as_str(), biweekly.interval), ("weekly", 2));
+        assert_eq!(biweekly.days_of_week, Some(vec![1, 5]));
+
+        let fifteenth = recurrence_of("FREQ=MONTHLY;BYMONTHDAY=15").unwrap();
+        assert_eq!(fifteenth.day_of_month, Some(15));
+        assert_eq!(fifteenth.rrule, None);
+
+        let until = recurrence_of("FREQ=WEEKLY;UNTIL=20260301T000000Z").unwrap();
+        assert_eq!(until.until, Some(T0 + 59 * DAY_MS));
+        assert_eq!(until.rrule, None);
+    }
+
+    #[test]
+    fn keeps_rules_the_fields_cannot_express_as_rrule() {
+        for (rrule, kept) in [
+            (
+                "FREQ=MONTHLY;BYDAY=MO,TU,WE,TH,FR;BYSETPOS=-1",
+                "FREQ=MONTHLY;BYDAY=MO,TU,WE,TH,FR;BYSETPOS=-1",
+            ),
+            ("FREQ=MONTHLY;BYDAY=1MO,-1FR", "FREQ=MONTHLY;BYDAY=1MO,-1FR"),
+            // 31日がない月は飛ばす (簡易設定の31日は月末に丸める)
+            ("FREQ=MONTHLY;BYMONTHDAY=31", "FREQ=MONTHLY;BYMONTHDAY=31"),
+            (
+                "FREQ=YEARLY;BYMONTH=3;BYDAY=2SU",
+                "FREQ=YEARLY;BYMONTH=3;BYDAY=2SU",
+            ),
+            (
+                "FREQ=WEEKLY;INTERVAL=2;BYDAY=MO,SU;WKST=SU",
+                "FREQ=WEEKLY;INTERVAL=2;BYDAY=MO,SU;WKST=SU",
+            ),
+            // COUNT は count に移す
+            (
+                "FREQ=MONTHLY;COUNT=10;BYSETPOS=-1;BYDAY=FR",
+                "FREQ=MONTHLY;BYSETPOS=-1;BYDAY=FR",
+            ),
+        ] {
+            let recurrence = recurrence_of(rrule).unwrap();
+            assert_eq!(recurrence.rrule.as_deref(), Some(kept), "{}", rrule);
+        }
+        let counted = recurrence_of("FREQ=MONTHLY;COUNT=10;BYSETPOS=-1;BYDAY=FR").unwrap();
+        assert_eq!(counted.count, Some(10));
+        assert_eq!(
+            crate::recurrence::to_rrule_string(&counted).unwrap(),
+            "FREQ=MONTHLY;BYSETPOS=-1;BYDAY=FR;COUNT=10"
+        );
+
+        // 時間単位の繰り返しはタスクの繰り返しにしない
+        assert!(recurrence_of("FREQ=HOURLY;INTERVAL=8").is_none());
+    }
+
+    #[test]
+    fn counts_only_the_remaining_occurrences() {
+        // 12/30, 12/31 は過ぎていて、1/1 09:00 が次の発生
+        let content = vtodo(
+            "DTSTART:20251230T090000Z\r\nRRULE:FREQ=DAILY;COUNT=5\r\n\
+             EXDATE:20251231T090000Z\r\n",
+        );
+        let item = items_at(&content, T0).remove(0);
+        assert_eq!(item.next_occurrence, Some(T0 + 9 * HOUR_MS));
+        assert_eq!(item.remaining_count, Some(3));
+        let recurrence = item_to_todo(&item, T0).recurrence.unwrap();
+        assert_eq!(recurrence.count, Some(3));
+        assert_eq!(recurrence.exdates, vec![T0 - 15 * HOUR_MS]);
+
+        // 書き出すと残り回数と除外日がそのまま残る
+        let todo = Todo {
+            id: "r".to_string(),
+            text: "繰り返し".to_string(),
+            due_date: item.next_occurrence,
+            recurrence: Some(recurrence),
+            ..Default::default()
+        };
+        let ics = export_todo(&todo, &[]);
+        assert!(ics.contains("RRULE:FREQ=DAILY;COUNT=3\r\n"), "{}", ics);
+        assert!(ics.contains("EXDATE:20251231T090000Z\r\n"), "{}", ics);
+    }
+
+    #[test]
+    fn finds_the_next_occurrence_of_long_running_series() {
+        // 2000年からの毎時の繰り返しは、先頭から数えると調べる件数の上限を超える
+        let content = vtodo("DTSTART:20000101T003000Z\r\nRRULE:FREQ=HOURLY\r\n");
+        let item = items_at(&content, T0).remove(0);
+        assert!(!item.series_ended);
+        assert_eq!(item.next_occurrence, Some(T0 + 30 * 60 * 1000));
+
+        let content = vtodo("DTSTART:20000101T000000Z\r\nRRULE:FREQ=MINUTELY;INTERVAL=7\r\n");
+        let item = items_at(&content, T0).remove(0);
+        let start = Utc.with_ymd_and_hms(2000, 1, 1, 0, 0, 0).unwrap();
+        let next = item.next_occurrence.unwrap();
+        assert!((T0..T0 + 7 * 60 * 1000).contains(&next));
+        assert_eq!((next - start.timestamp_millis()) % (7 * 60 * 1000), 0);
+
+        // UNTIL で終わっていれば終わりとする
+        let content =
+            vtodo("DTSTART:20000101T000000Z\r\nRRULE:FREQ=HOURLY;UNTIL=20250101T000000Z\r\n");
+        assert!(items_at(&content, T0)[0].series_ended);
+    }
+
+    #[test]
+    fn import_skips_completed_past_and_event_items_by_option() {
+        let content = "BEGIN:VCALENDAR\r\n\
+            BEGIN:VTODO\r\nUID:done\r\nSUMMARY:完了\r\nDUE:21000101T000000Z\r\nSTATUS:COMPLETED\r\nEND:VTODO\r\n\
+            BEGIN:VTODO\r\nUID:past\r\nSUMMARY:過去\r\nDUE:20000101T000000Z\r\nEND:VTODO\r\n\
+            BEGIN:VTODO\r\nUID:series\r\nSUMMARY:毎年\r\nDTSTART;VALUE=DATE:20000101\r\nRRULE:FREQ=YEARLY\r\nEND:VTODO\r\n\
+            BEGIN:VTODO\r\nUID:future\r\nSUMMARY:未来\r\nDUE:21000101T000000Z\r\nPRIORITY:1\r\nCATEGORIES:仕事,家\r\nEND:VTODO\r\n\
+            BEGIN:VEVENT\r\nUID:event\r\nSUMMARY:予定\r\nDTSTART:21000101T000000Z\r\nEND:VEVENT\r\n\
+            BEGIN:VTODO\r\nUID:empty\r\nSUMMARY: \r\nEND:VTODO\r\n\
+            END:VCALENDAR\r\n";
+        let result = import_ics(content, IcsImportOptions::default());
+        let stats = &result.stats;
+        assert_eq!(
+            (stats.total, stats.total_tasks, stats.total_events),
+            (5, 4, 1)
+        );
+        assert_eq!((stats.imported, stats.skipped), (3, 2));
+        assert_eq!(stats.skipped_reasons.past, 1);
+        assert_eq!(stats.skipped_reasons.is_event, 1);
+        assert_eq!(stats.skipped_reasons.completed, 0);
+
+        let texts: Vec<&str> = result.todos.iter().map(|t| t.text.as_str()).collect();
+        assert_eq!(texts, ["完了", "毎年", "未来"]);
+        assert!(result.todos[0].completed);
+        let series = &result.todos[1];
+        assert_eq!(series.recurrence.as_ref().unwrap().kind, "yearly");
+        assert!(series.due_date.unwrap() > Local::now().timestamp_millis() - DAY_MS);
+        assert_eq!(result.todos[2].priority, 1);
+        assert_eq!(result.todos[2].labels, ["仕事", "家"]);
+        assert_eq!(result.todos[2].extra["icsUid"], "future");
+
+        let result = import_ics(
+            content,
+            IcsImportOptions {
+                import_completed: false,
+                import_past: true,
+                import_events: true,
+                ..Default::default()
+            },
+        );
+        assert_eq!(result.stats.imported, 4);
+        assert_eq!(result.stats.skipped_reasons.completed, 1);
+    }
+}
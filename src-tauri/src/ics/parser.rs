@@ -0,0 +1,401 @@
+// iCalendar (RFC 5545) のコンテンツ行とコンポーネントの解析
+
+use chrono::{NaiveDate, NaiveDateTime, NaiveTime};
+
+#[derive(Debug, Clone)]
+pub struct Property {
+    pub name: String,
+    pub params: Vec<(String, String)>,
+    pub value: String,
+}
+
+impl Property {
+    pub fn param(&self, name: &str) -> Option<&str> {
+        self.params
+            .iter()
+            .find(|(key, _)| key == name)
+            .map(|(_, value)| value.as_str())
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct Component {
+    pub name: String,
+    pub properties: Vec<Property>,
+    pub components: Vec<Component>,
+}
+
+impl Component {
+    pub fn property(&self, name: &str) -> Option<&Property> {
+        self.properties.iter().find(|p| p.name == name)
+    }
+
+    pub fn properties_named<'a>(&'a self, name: &'a str) -> impl Iterator<Item = &'a Property> {
+        self.properties.iter().filter(move |p| p.name == name)
+    }
+
+    pub fn value(&self, name: &str) -> Option<&str> {
+        self.property(name).map(|p| p.value.as_str())
+    }
+
+    // 入れ子になったコンポーネントも含めて名前で探す
+    pub fn find_all<'a>(&'a self, name: &str, found: &mut Vec<&'a Component>) {
+        for component in &self.components {
+            if component.name == name {
+                found.push(component);
+            }
+            component.find_all(name, found);
+        }
+    }
+}
+
+// 折り返し行 (改行の直後が空白またはタブ) を前の行に結合する
+pub fn unfold_lines(content: &str) -> Vec<String> {
+    let normalized = content.replace("\r\n", "\n").replace('\r', "\n");
+    let mut lines: Vec<String> = Vec::new();
+    for line in normalized.split('\n') {
+        match line.strip_prefix([' ', '\t']) {
+            Some(rest) if !lines.is_empty() => lines.last_mut().unwrap().push_str(rest),
+            _ => lines.push(line.to_string()),
+        }
+    }
+    lines.retain(|line| !line.trim().is_empty());
+    lines
+}
+
+// NAME;PARAM=VALUE;PARAM="quoted:value":VALUE の形式を分解する
+pub fn parse_property(line: &str) -> Option<Property> {
+    let mut chars = line.char_indices().peekable();
+    let mut name_end = None;
+    while let Some(&(i, c)) = chars.peek() {
+        if c == ';' || c == ':' {
+            name_end = Some(i);
+            break;
+        }
+        chars.next();
+    }
+    let name_end = name_end?;
+    let name = line[..name_end].trim().to_ascii_uppercase();
+    if name.is_empty() {
+        return None;
+    }
+
+    let mut params = Vec::new();
+    let mut rest = &line[name_end..];
+    while let Some(after) = rest.strip_prefix(';') {
+        let eq = after.find(['=', ';', ':'])?;
+        let key = after[..eq].trim().to_ascii_uppercase();
+        if !after[eq..].starts_with('=') {
+            // 値のないパラメーターは読み飛ばす
+            params.push((key, String::new()));
+            rest = &after[eq..];
+            continue;
+        }
+        let mut value = String::new();
+        let mut in_quotes = false;
+        let mut end = after.len();
+        for (i, c) in after[eq + 1..].char_indices() {
+            match c {
+                '"' => in_quotes = !in_quotes,
+                ';' | ':' if !in_quotes => {
+                    end = eq + 1 + i;
+                    break;
+                }
+                _ => value.push(c),
+            }
+        }
+        params.push((key, value));
+        rest = &after[end..];
+    }
+
+    let value = rest.strip_prefix(':')?;
+    Some(Property {
+        name,
+        params,
+        value: value.to_string(),
+    })
+}
+
+// BEGIN/END の入れ子を木構造にする。閉じられていないコンポーネントも寛容に扱う
+pub fn parse_components(content: &str) -> Component {
+    let mut stack = vec![Component {
+        name: "ROOT".to_string(),
+        ..Default::default()
+    }];
+
+    for line in unfold_lines(content) {
+        let Some(property) = parse_property(&line) else {
+            continue;
+        };
+        match property.name.as_str() {
+            "BEGIN" => stack.push(Component {
+                name: property.value.trim().to_ascii_uppercase(),
+                ..Default::default()
+            }),
+            "END" => {
+                let name = property.value.trim().to_ascii_uppercase();
+                if !stack.iter().skip(1).any(|c| c.name == name) {
+                    continue;
+                }
+                while stack.len() > 1 {
+                    let component = stack.pop().unwrap();
+                    let matched = component.name == name;
+                    stack.last_mut().unwrap().components.push(component);
+                    if matched {
+                        break;
+                    }
+                }
+            }
+            _ => stack.last_mut().unwrap().properties.push(property),
+        }
+    }
+
+    while stack.len() > 1 {
+        let component = stack.pop().unwrap();
+        stack.last_mut().unwrap().components.push(component);
+    }
+    stack.pop().unwrap()
+}
+
+pub fn unescape_text(text: &str) -> String {
+    let mut result = String::with_capacity(text.len());
+    let mut chars = text.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            result.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('n') | Some('N') => result.push('\n'),
+            Some(other) => result.push(other),
+            None => result.push('\\'),
+        }
+    }
+    result
+}
+
+pub fn escape_text(text: &str) -> String {
+    text.replace('\\', "\\\\")
+        .replace(';', "\\;")
+        .replace(',', "\\,")
+        .replace("\r\n", "\\n")
+        .replace('\n', "\\n")
+}
+
+// エスケープされていないカンマで区切る (CATEGORIES など)
+pub fn split_text_list(value: &str) -> Vec<String> {
+    let mut items = Vec::new();
+    let mut current = String::new();
+    let mut escaped = false;
+    for c in value.chars() {
+        if escaped {
+            current.push('\\');
+            current.push(c);
+            escaped = false;
+        } else if c == '\\' {
+            escaped = true;
+        } else if c == ',' {
+            items.push(unescape_text(&current));
+            current.clear();
+        } else {
+            current.push(c);
+        }
+    }
+    items.push(unescape_text(&current));
+    items
+        .into_iter()
+        .map(|item| item.trim().to_string())
+        .filter(|item| !item.is_empty())
+        .collect()
+}
+
+// 75オクテットを超える行を折り返す (マルチバイト文字の途中では切らない)
+pub fn fold_line(line: &str) -> String {
+    let mut folded = String::with_capacity(line.len() + line.len() / 70 * 3);
+    let mut width = 0;
+    for c in line.chars() {
+        let len = c.len_utf8();
+        if width + len > 75 {
+            folded.push_str("\r\n ");
+            width = 1;
+        }
+        folded.push(c);
+        width += len;
+    }
+    folded.push_str("\r\n");
+    folded
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct IcsDateTime {
+    pub value: NaiveDateTime,
+    pub date_only: bool,
+    pub utc: bool,
+}
+
+// YYYYMMDD / YYYYMMDDTHHMMSS / YYYYMMDDTHHMMSSZ
+pub fn parse_date_time(value: &str) -> Option<IcsDateTime> {
+    let value = value.trim();
+    let (value, utc) = match value.strip_suffix(['Z', 'z']) {
+        Some(rest) => (rest, true),
+        None => (value, false),
+    };
+    let (date_part, time_part) = match value.split_once(['T', 't']) {
+        Some((date, time)) => (date, Some(time)),
+        None => (value, None),
+    };
+    let date = NaiveDate::parse_from_str(date_part, "%Y%m%d").ok()?;
+    let Some(time_part) = time_part else {
+        return Some(IcsDateTime {
+            value: date.and_time(NaiveTime::MIN),
+            date_only: true,
+            utc: false,
+        });
+    };
+    let time = NaiveTime::parse_from_str(time_part, "%H%M%S")
+        .or_else(|_| NaiveTime::parse_from_str(time_part, "%H%M"))
+        .ok()?;
+    Some(IcsDateTime {
+        value: date.and_time(time),
+        date_only: false,
+        utc,
+    })
+}
+
+// ISO 8601 の期間 (P1DT2H30M, -PT15M, P2W) を秒に変換する
+pub fn parse_duration(value: &str) -> Option<i64> {
+    let value = value.trim();
+    let (sign, value) = match value.strip_prefix('-') {
+        Some(rest) => (-1, rest),
+        None => (1, value.strip_prefix('+').unwrap_or(value)),
+    };
+    let value = value.strip_prefix(['P', 'p'])?;
+    let mut total = 0i64;
+    let mut number = String::new();
+    let mut in_time = false;
+    for c in value.chars() {
+        match c.to_ascii_uppercase() {
+            '0'..='9' => number.push(c),
+            'T' => in_time = true,
+            unit => {
+                let n: i64 = number.parse().ok()?;
+                number.clear();
+                total += n * match (unit, in_time) {
+                    ('W', false) => 7 * 86_400,
+                    ('D', false) => 86_400,
+                    ('H', true) => 3_600,
+                    ('M', true) => 60,
+                    ('S', true) => 1,
+                    _ => return None,
+                };
+            }
+        }
+    }
+    if !number.is_empty() {
+        return None;
+    }
+    Some(sign * total)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn unfolds_continuation_lines() {
+        let lines = unfold_lines("SUMMARY:長い\r\n  説明\r\nDESCRIPTION:a\r\n\tb\n\r\nEND:VTODO");
+        assert_eq!(lines, ["SUMMARY:長い 説明", "DESCRIPTION:ab", "END:VTODO"]);
+    }
+
+    #[test]
+    fn parses_parameters_and_values() {
+        let property = parse_property(
+            r#"dtstart;TZID="Tokyo: Standard;Time";VALUE=DATE-TIME:20260101T090000"#,
+        )
+        .unwrap();
+        assert_eq!(property.name, "DTSTART");
+        assert_eq!(property.param("TZID"), Some("Tokyo: Standard;Time"));
+        assert_eq!(property.param("VALUE"), Some("DATE-TIME"));
+        assert_eq!(property.value, "20260101T090000");
+
+        // 値の中のコロンはそのまま残す
+        let property = parse_property("URL:https://example.com/a:b").unwrap();
+        assert_eq!(property.value, "https://example.com/a:b");
+
+        let property = parse_property("X-FLAG;EMPTY:1").unwrap();
+        assert_eq!(property.param("EMPTY"), Some(""));
+        assert!(parse_property("no separator").is_none());
+        assert!(parse_property(":value").is_none());
+    }
+
+    #[test]
+    fn builds_nested_components_leniently() {
+        let root = parse_components(
+            "BEGIN:VCALENDAR\nBEGIN:VTODO\nSUMMARY:a\nBEGIN:VALARM\nACTION:DISPLAY\n\
+             END:VTODO\nEND:VEVENT\nBEGIN:VTODO\nSUMMARY:b\n",
+        );
+        let calendar = &root.components[0];
+        assert_eq!(calendar.name, "VCALENDAR");
+        // END:VTODO で閉じられていない VALARM も閉じる。対応する BEGIN のない END は無視する
+        let first = &calendar.components[0];
+        assert_eq!(first.value("SUMMARY"), Some("a"));
+        assert_eq!(first.components[0].name, "VALARM");
+        // ファイルの終わりで閉じられていないものも残す
+        assert_eq!(calendar.components[1].value("SUMMARY"), Some("b"));
+
+        let mut todos = Vec::new();
+        root.find_all("VTODO", &mut todos);
+        assert_eq!(todos.len(), 2);
+    }
+
+    #[test]
+    fn escapes_text_values() {
+        let text = "a;b,c\\d\n改行";
+        assert_eq!(escape_text(text), r"a\;b\,c\\d\n改行");
+        assert_eq!(unescape_text(&escape_text(text)), text);
+        assert_eq!(unescape_text("a\\Nb\\"), "a\nb\\");
+        assert_eq!(
+            split_text_list(r"仕事, 家\,庭 ,,\;x"),
+            ["仕事", "家,庭", ";x"]
+        );
+    }
+
+    #[test]
+    fn folds_long_lines_on_character_boundaries() {
+        let line = format!("SUMMARY:{}", "あ".repeat(40));
+        let folded = fold_line(&line);
+        for (i, part) in folded.trim_end_matches("\r\n").split("\r\n").enumerate() {
+            assert!(part.len() <= 75, "{}", part);
+            assert_eq!(part.starts_with(' '), i > 0);
+        }
+        assert_eq!(unfold_lines(&folded), [line]);
+        assert_eq!(fold_line("SHORT:a"), "SHORT:a\r\n");
+    }
+
+    #[test]
+    fn parses_dates_and_times() {
+        let date = parse_date_time("20260102").unwrap();
+        assert!(date.date_only && !date.utc);
+        assert_eq!(date.value.to_string(), "2026-01-02 00:00:00");
+        let utc = parse_date_time("20260102T093000Z").unwrap();
+        assert!(!utc.date_only && utc.utc);
+        assert_eq!(utc.value.to_string(), "2026-01-02 09:30:00");
+        assert_eq!(
+            parse_date_time("20260102T0930").unwrap().value.to_string(),
+            "2026-01-02 09:30:00"
+        );
+        assert!(parse_date_time("2026-01-02").is_none());
+        assert!(parse_date_time("20260230").is_none());
+    }
+
+    #[test]
+    fn parses_durations() {
+        assert_eq!(parse_duration("PT15M"), Some(900));
+        assert_eq!(parse_duration("-PT15M"), Some(-900));
+        assert_eq!(parse_duration("P1DT2H30M"), Some(86_400 + 9_000));
+        assert_eq!(parse_duration("+P2W"), Some(14 * 86_400));
+        assert_eq!(parse_duration("P1H"), None);
+        assert_eq!(parse_duration("PT5"), None);
+        assert_eq!(parse_duration("15M"), None);
+    }
+}
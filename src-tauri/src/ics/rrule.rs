@@ -0,0 +1,630 @@
+// RRULE (RFC 5545 3.3.10) の解析と展開
+// 展開はDTSTARTのタイムゾーンでの壁時計時刻 (NaiveDateTime) 上で行う
+
+use chrono::{Datelike, Duration, NaiveDate, NaiveDateTime, NaiveTime, Timelike, Weekday};
+use std::collections::VecDeque;
+
+use super::parser::parse_date_time;
+
+// 条件に合う日時が見つからない期間がこれだけ続いたら打ち切る (BYMONTHDAY=30;BYMONTH=2 など)
+const MAX_EMPTY_PERIODS: u32 = 10_000;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Frequency {
+    Secondly,
+    Minutely,
+    Hourly,
+    Daily,
+    Weekly,
+    Monthly,
+    Yearly,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct RRule {
+    pub freq: Frequency,
+    pub interval: u32,
+    pub count: Option<u32>,
+    pub until: Option<NaiveDateTime>,
+    // UNTIL がUTC指定か (DTSTARTのタイムゾーンに変換してから展開する必要がある)
+    pub until_utc: bool,
+    pub by_second: Vec<u32>,
+    pub by_minute: Vec<u32>,
+    pub by_hour: Vec<u32>,
+    // (序数, 曜日)。序数は 1MO / -1FR の数字部分
+    pub by_day: Vec<(Option<i32>, Weekday)>,
+    pub by_month_day: Vec<i32>,
+    pub by_year_day: Vec<i32>,
+    pub by_week_no: Vec<i32>,
+    pub by_month: Vec<u32>,
+    pub by_set_pos: Vec<i32>,
+    pub wkst: Weekday,
+}
+
+pub fn parse_weekday(value: &str) -> Option<Weekday> {
+    match value.to_ascii_uppercase().as_str() {
+        "MO" => Some(Weekday::Mon),
+        "TU" => Some(Weekday::Tue),
+        "WE" => Some(Weekday::Wed),
+        "TH" => Some(Weekday::Thu),
+        "FR" => Some(Weekday::Fri),
+        "SA" => Some(Weekday::Sat),
+        "SU" => Some(Weekday::Sun),
+        _ => None,
+    }
+}
+
+pub fn weekday_code(weekday: Weekday) -> &'static str {
+    match weekday {
+        Weekday::Mon => "MO",
+        Weekday::Tue => "TU",
+        Weekday::Wed => "WE",
+        Weekday::Thu => "TH",
+        Weekday::Fri => "FR",
+        Weekday::Sat => "SA",
+        Weekday::Sun => "SU",
+    }
+}
+
+fn parse_list<T: std::str::FromStr>(value: &str) -> Result<Vec<T>, String> {
+    value
+        .split(',')
+        .map(|v| {
+            v.trim()
+                .parse()
+                .map_err(|_| format!("RRULEの値が不正です: {}", v))
+        })
+        .collect()
+}
+
+fn check_range(values: &[i32], max: i32, name: &str) -> Result<(), String> {
+    if values.iter().any(|v| *v == 0 || v.abs() > max) {
+        return Err(format!("{} の値が範囲外です", name));
+    }
+    Ok(())
+}
+
+impl RRule {
+    pub fn parse(value: &str) -> Result<Self, String> {
+        let mut freq = None;
+        let mut rule = RRule {
+            freq: Frequency::Daily,
+            interval: 1,
+            count: None,
+            until: None,
+            until_utc: false,
+            by_second: Vec::new(),
+            by_minute: Vec::new(),
+            by_hour: Vec::new(),
+            by_day: Vec::new(),
+            by_month_day: Vec::new(),
+            by_year_day: Vec::new(),
+            by_week_no: Vec::new(),
+            by_month: Vec::new(),
+            by_set_pos: Vec::new(),
+            wkst: Weekday::Mon,
+        };
+
+        let value = value.trim();
+        let value = value.strip_prefix("RRULE:").unwrap_or(value);
+        for part in value.split(';').filter(|p| !p.trim().is_empty()) {
+            let (key, val) = part
+                .split_once('=')
+                .ok_or_else(|| format!("RRULEの形式が不正です: {}", part))?;
+            match key.trim().to_ascii_uppercase().as_str() {
+                "FREQ" => {
+                    freq = Some(match val.trim().to_ascii_uppercase().as_str() {
+                        "SECONDLY" => Frequency::Secondly,
+                        "MINUTELY" => Frequency::Minutely,
+                        "HOURLY" => Frequency::Hourly,
+                        "DAILY" => Frequency::Daily,
+                        "WEEKLY" => Frequency::Weekly,
+                        "MONTHLY" => Frequency::Monthly,
+                        "YEARLY" => Frequency::Yearly,
+                        other => return Err(format!("未対応のFREQです: {}", other)),
+                    })
+                }
+                "INTERVAL" => {
+                    rule.interval = val
+                        .trim()
+                        .parse()
+                        .ok()
+                        .filter(|n| *n > 0)
+                        .ok_or_else(|| format!("INTERVALが不正です: {}", val))?
+                }
+                "COUNT" => {
+                    rule.count = Some(
+                        val.trim()
+                            .parse()
+                            .map_err(|_| format!("COUNTが不正です: {}", val))?,
+                    )
+                }
+                "UNTIL" => {
+                    let until =
+                        parse_date_time(val).ok_or_else(|| format!("UNTILが不正です: {}", val))?;
+                    rule.until = Some(if until.date_only {
+                        // 日付のみの UNTIL はその日の終わりまでを含む
+                        until.value.date().and_hms_opt(23, 59, 59).unwrap()
+                    } else {
+                        until.value
+                    });
+                    rule.until_utc = until.utc;
+                }
+                "BYSECOND" => rule.by_second = parse_list(val)?,
+                "BYMINUTE" => rule.by_minute = parse_list(val)?,
+                "BYHOUR" => rule.by_hour = parse_list(val)?,
+                "BYDAY" => {
+                    rule.by_day = val
+                        .split(',')
+                        .map(|v| {
+                            let v = v.trim();
+                            let split = v.len().saturating_sub(2);
+                            let weekday = v
+                                .get(split..)
+                                .and_then(parse_weekday)
+                                .ok_or_else(|| format!("BYDAYが不正です: {}", v))?;
+                            let ordinal = match &v[..split] {
+                                "" => None,
+                                n => Some(
+                                    n.parse::<i32>()
+                                        .ok()
+                                        .filter(|n| *n != 0 && n.abs() <= 53)
+                                        .ok_or_else(|| format!("BYDAYが不正です: {}", v))?,
+                                ),
+                            };
+                            Ok((ordinal, weekday))
+                        })
+                        .collect::<Result<_, String>>()?
+                }
+                "BYMONTHDAY" => {
+                    rule.by_month_day = parse_list(val)?;
+                    check_range(&rule.by_month_day, 31, "BYMONTHDAY")?;
+                }
+                "BYYEARDAY" => {
+                    rule.by_year_day = parse_list(val)?;
+                    check_range(&rule.by_year_day, 366, "BYYEARDAY")?;
+                }
+                "BYWEEKNO" => {
+                    rule.by_week_no = parse_list(val)?;
+                    check_range(&rule.by_week_no, 53, "BYWEEKNO")?;
+                }
+                "BYMONTH" => {
+                    rule.by_month = parse_list(val)?;
+                    if rule.by_month.iter().any(|m| !(1..=12).contains(m)) {
+                        return Err("BYMONTH の値が範囲外です".to_string());
+                    }
+                }
+                "BYSETPOS" => {
+                    rule.by_set_pos = parse_list(val)?;
+                    check_range(&rule.by_set_pos, 366, "BYSETPOS")?;
+                }
+                "WKST" => {
+                    rule.wkst = parse_weekday(val.trim())
+                        .ok_or_else(|| format!("WKSTが不正です: {}", val))?
+                }
+                // X-NAME など未知の項目は無視する
+                _ => {}
+            }
+        }
+
+        rule.freq = freq.ok_or_else(|| "RRULEにFREQがありません".to_string())?;
+        Ok(rule)
+    }
+
+    // DTSTART から始まる発生日時を順に返す
+    // DTSTART は BYxxx に合わなくても最初の発生日時として返し、COUNT にも数える (RFC 5545 3.8.5.3)
+    pub fn iter(&self, dtstart: NaiveDateTime) -> RRuleIter {
+        self.iter_from(dtstart, true)
+    }
+
+    // include_dtstart が false なら、DTSTART は規則に合うときだけ返す
+    pub fn iter_from(&self, dtstart: NaiveDateTime, include_dtstart: bool) -> RRuleIter {
+        let mut rule = self.clone();
+        // 日付に関する BYxxx が一つもなければ DTSTART から補う
+        if rule.by_week_no.is_empty()
+            && rule.by_year_day.is_empty()
+            && rule.by_month_day.is_empty()
+            && rule.by_day.is_empty()
+        {
+            match rule.freq {
+                Frequency::Yearly => {
+                    if rule.by_month.is_empty() {
+                        rule.by_month = vec![dtstart.month()];
+                    }
+                    rule.by_month_day = vec![dtstart.day() as i32];
+                }
+                Frequency::Monthly => rule.by_month_day = vec![dtstart.day() as i32],
+                Frequency::Weekly => rule.by_day = vec![(None, dtstart.weekday())],
+                _ => {}
+            }
+        }
+
+        let done = rule.count == Some(0);
+        let mut buffer = VecDeque::new();
+        if include_dtstart {
+            buffer.push_back(dtstart);
+        }
+        RRuleIter {
+            rule,
+            dtstart,
+            include_dtstart,
+            period: 0,
+            buffer,
+            emitted: 0,
+            empty_periods: 0,
+            done,
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct RRuleIter {
+    rule: RRule,
+    dtstart: NaiveDateTime,
+    include_dtstart: bool,
+    period: i64,
+    buffer: VecDeque<NaiveDateTime>,
+    emitted: u32,
+    empty_periods: u32,
+    done: bool,
+}
+
+fn days_in_month(year: i32, month: u32) -> u32 {
+    let (next_year, next_month) = if month == 12 {
+        (year + 1, 1)
+    } else {
+        (year, month + 1)
+    };
+    NaiveDate::from_ymd_opt(next_year, next_month, 1)
+        .and_then(|d| d.pred_opt())
+        .map(|d| d.day())
+        .unwrap_or(31)
+}
+
+fn days_in_year(year: i32) -> u32 {
+    if NaiveDate::from_ymd_opt(year, 2, 29).is_some() {
+        366
+    } else {
+        365
+    }
+}
+
+fn days_since_week_start(date: NaiveDate, wkst: Weekday) -> i64 {
+    (date.weekday().num_days_from_monday() as i64 - wkst.num_days_from_monday() as i64)
+        .rem_euclid(7)
+}
+
+fn week_one_start(year: i32, wkst: Weekday) -> NaiveDate {
+    // 第1週 = その年の日を4日以上含む最初の週
+    let jan4 = NaiveDate::from_ymd_opt(year, 1, 4).unwrap();
+    jan4 - Duration::days(days_since_week_start(jan4, wkst))
+}
+
+// (週番号, その年の総週数)。その年に属さない週は None
+fn week_number(date: NaiveDate, wkst: Weekday) -> Option<(i32, i32)> {
+    let start = week_one_start(date.year(), wkst);
+    let next_start = week_one_start(date.year() + 1, wkst);
+    if date < start || date >= next_start {
+        return None;
+    }
+    let week = ((date - start).num_days() / 7 + 1) as i32;
+    let total = ((next_start - start).num_days() / 7) as i32;
+    Some((week, total))
+}
+
+impl RRuleIter {
+    fn period_start(&self) -> Option<NaiveDateTime> {
+        let rule = &self.rule;
+        let step = self.period.checked_mul(rule.interval as i64)?;
+        let start = self.dtstart;
+        let date = start.date();
+        match rule.freq {
+            Frequency::Yearly => {
+                let year = i32::try_from(start.year() as i64 + step).ok()?;
+                NaiveDate::from_ymd_opt(year, 1, 1).map(|d| d.and_time(NaiveTime::MIN))
+            }
+            Frequency::Monthly => {
+                let months = start.year() as i64 * 12 + start.month0() as i64 + step;
+                let year = i32::try_from(months.div_euclid(12)).ok()?;
+                NaiveDate::from_ymd_opt(year, months.rem_euclid(12) as u32 + 1, 1)
+                    .map(|d| d.and_time(NaiveTime::MIN))
+            }
+            Frequency::Weekly => {
+                let week_start = date - Duration::days(days_since_week_start(date, rule.wkst));
+                week_start
+                    .checked_add_signed(Duration::days(step.checked_mul(7)?))
+                    .map(|d| d.and_time(NaiveTime::MIN))
+            }
+            Frequency::Daily => date
+                .checked_add_signed(Duration::days(step))
+                .map(|d| d.and_time(NaiveTime::MIN)),
+            Frequency::Hourly => start
+                .with_minute(0)?
+                .with_second(0)?
+                .checked_add_signed(Duration::hours(step)),
+            Frequency::Minutely => start
+                .with_second(0)?
+                .checked_add_signed(Duration::minutes(step)),
+            Frequency::Secondly => start.checked_add_signed(Duration::seconds(step)),
+        }
+    }
+
+    fn matches_day(&self, date: NaiveDate) -> bool {
+        let rule = &self.rule;
+        if !rule.by_month.is_empty() && !rule.by_month.contains(&date.month()) {
+            return false;
+        }
+        if !rule.by_week_no.is_empty() {
+            let Some((week, total)) = week_number(date, rule.wkst) else {
+                return false;
+            };
+            if !rule
+                .by_week_no
+                .iter()
+                .any(|n| *n == week || total + n + 1 == week)
+            {
+                return false;
+            }
+        }
+        if !rule.by_year_day.is_empty() {
+            let ordinal = date.ordinal() as i32;
+            let total = days_in_year(date.year()) as i32;
+            if !rule
+                .by_year_day
+                .iter()
+                .any(|n| *n == ordinal || total + n + 1 == ordinal)
+            {
+                return false;
+            }
+        }
+        if !rule.by_month_day.is_empty() {
+            let day = date.day() as i32;
+            let total = days_in_month(date.year(), date.month()) as i32;
+            if !rule
+                .by_month_day
+                .iter()
+                .any(|n| *n == day || total + n + 1 == day)
+            {
+                return false;
+            }
+        }
+        if !rule.by_day.is_empty() {
+            // 序数付きの BYDAY は MONTHLY (または BYMONTH 付きの YEARLY) なら月内、YEARLY なら年内で数える
+            let in_month = rule.freq == Frequency::Monthly
+                || (rule.freq == Frequency::Yearly && !rule.by_month.is_empty());
+            let in_year = rule.freq == Frequency::Yearly && rule.by_week_no.is_empty();
+            let matched = rule.by_day.iter().any(|(ordinal, weekday)| {
+                if date.weekday() != *weekday {
+                    return false;
+                }
+                let Some(n) = ordinal else {
+                    return true;
+                };
+                let (position, total) = if in_month {
+                    (date.day(), days_in_month(date.year(), date.month()))
+                } else if in_year {
+                    (date.ordinal(), days_in_year(date.year()))
+                } else {
+                    return true;
+                };
+                let from_start = ((position - 1) / 7 + 1) as i32;
+                let from_end = -(((total - position) / 7 + 1) as i32);
+                *n == from_start || *n == from_end
+            });
+            if !matched {
+                return false;
+            }
+        }
+        true
+    }
+
+    fn candidates(&self, period: NaiveDateTime) -> Vec<NaiveDateTime> {
+        let rule = &self.rule;
+        let date = period.date();
+        let days: Vec<NaiveDate> = match rule.freq {
+            Frequency::Yearly => (0..days_in_year(date.year()))
+                .filter_map(|i| date.checked_add_signed(Duration::days(i as i64)))
+                .collect(),
+            Frequency::Monthly => (0..days_in_month(date.year(), date.month()))
+                .filter_map(|i| date.checked_add_signed(Duration::days(i as i64)))
+                .collect(),
+            Frequency::Weekly => (0..7)
+                .filter_map(|i| date.checked_add_signed(Duration::days(i)))
+                .collect(),
+            _ => vec![date],
+        };
+
+        let or_default = |values: &Vec<u32>, default: u32| {
+            if values.is_empty() {
+                vec![default]
+            } else {
+                values.clone()
+            }
+        };
+        let fixed_or_filtered = |value: u32, values: &Vec<u32>| {
+            if values.is_empty() || values.contains(&value) {
+                vec![value]
+            } else {
+                Vec::new()
+            }
+        };
+        let (hours, minutes, seconds) = match rule.freq {
+            Frequency::Hourly => (
+                fixed_or_filtered(period.hour(), &rule.by_hour),
+                or_default(&rule.by_minute, self.dtstart.minute()),
+                or_default(&rule.by_second, self.dtstart.second()),
+            ),
+            Frequency::Minutely => (
+                fixed_or_filtered(period.hour(), &rule.by_hour),
+                fixed_or_filtered(period.minute(), &rule.by_minute),
+                or_default(&rule.by_second, self.dtstart.second()),
+            ),
+            Frequency::Secondly => (
+                fixed_or_filtered(period.hour(), &rule.by_hour),
+                fixed_or_filtered(period.minute(), &rule.by_minute),
+                fixed_or_filtered(period.second(), &rule.by_second),
+            ),
+            _ => (
+                or_default(&rule.by_hour, self.dtstart.hour()),
+                or_default(&rule.by_minute, self.dtstart.minute()),
+                or_default(&rule.by_second, self.dtstart.second()),
+            ),
+        };
+
+        let mut result = Vec::new();
+        for day in days.into_iter().filter(|d| self.matches_day(*d)) {
+            for h in &hours {
+                for m in &minutes {
+                    for s in &seconds {
+                        if let Some(time) = NaiveTime::from_hms_opt(*h, *m, *s) {
+                            result.push(day.and_time(time));
+                        }
+                    }
+                }
+            }
+        }
+        result.sort();
+        result.dedup();
+
+        if rule.by_set_pos.is_empty() {
+            return result;
+        }
+        let len = result.len() as i32;
+        let mut selected: Vec<NaiveDateTime> = rule
+            .by_set_pos
+            .iter()
+            .filter_map(|pos| {
+                let index = if *pos > 0 { pos - 1 } else { len + pos };
+                (0..len).contains(&index).then(|| result[index as usize])
+            })
+            .collect();
+        selected.sort();
+        selected.dedup();
+        selected
+    }
+}
+
+impl Iterator for RRuleIter {
+    type Item = NaiveDateTime;
+
+    fn next(&mut self) -> Option<NaiveDateTime> {
+        loop {
+            if self.done {
+                return None;
+            }
+            if let Some(next) = self.buffer.pop_front() {
+                if self.rule.until.is_some_and(|until| next > until) {
+                    self.done = true;
+                    return None;
+                }
+                self.emitted += 1;
+                if self.rule.count.is_some_and(|count| self.emitted >= count) {
+                    self.done = true;
+                }
+                return Some(next);
+            }
+
+            let Some(period) = self.period_start() else {
+                self.done = true;
+                return None;
+            };
+            if period.year() > 9999 || self.rule.until.is_some_and(|until| period > until) {
+                self.done = true;
+                return None;
+            }
+            self.period += 1;
+
+            let candidates: Vec<NaiveDateTime> = self
+                .candidates(period)
+                .into_iter()
+                .filter(|c| {
+                    // DTSTART を先に返している場合は重複させない
+                    *c > self.dtstart || (*c == self.dtstart && !self.include_dtstart)
+                })
+                .collect();
+            if candidates.is_empty() {
+                self.empty_periods += 1;
+                if self.empty_periods > MAX_EMPTY_PERIODS {
+                    self.done = true;
+                }
+                continue;
+            }
+            self.empty_periods = 0;
+            self.buffer.extend(candidates);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn expand(rule: &str, dtstart: &str, include_dtstart: bool, n: usize) -> Vec<String> {
+        let dtstart = NaiveDateTime::parse_from_str(dtstart, "%Y-%m-%d %H:%M").unwrap();
+        RRule::parse(rule)
+            .unwrap()
+            .iter_from(dtstart, include_dtstart)
+            .take(n)
+            .map(|d| d.format("%Y-%m-%d %H:%M").to_string())
+            .collect()
+    }
+
+    #[test]
+    fn dtstart_is_first_instance_even_if_rule_does_not_match() {
+        // 2026-03-04 は水曜
+        assert_eq!(
+            expand("FREQ=WEEKLY;BYDAY=MO;COUNT=3", "2026-03-04 09:00", true, 10),
+            ["2026-03-04 09:00", "2026-03-09 09:00", "2026-03-16 09:00"]
+        );
+        assert_eq!(
+            expand(
+                "FREQ=MONTHLY;BYMONTHDAY=15;COUNT=2",
+                "2026-03-04 09:00",
+                true,
+                10
+            ),
+            ["2026-03-04 09:00", "2026-03-15 09:00"]
+        );
+    }
+
+    #[test]
+    fn dtstart_matching_rule_is_not_repeated() {
+        assert_eq!(
+            expand("FREQ=DAILY;COUNT=3", "2026-03-04 09:00", true, 10),
+            ["2026-03-04 09:00", "2026-03-05 09:00", "2026-03-06 09:00"]
+        );
+        assert_eq!(
+            expand(
+                "FREQ=WEEKLY;BYDAY=WE,FR;COUNT=3",
+                "2026-03-04 09:00",
+                true,
+                10
+            ),
+            ["2026-03-04 09:00", "2026-03-06 09:00", "2026-03-11 09:00"]
+        );
+    }
+
+    #[test]
+    fn unmatched_dtstart_can_be_skipped() {
+        assert_eq!(
+            expand(
+                "FREQ=WEEKLY;BYDAY=MO;COUNT=3",
+                "2026-03-04 09:00",
+                false,
+                10
+            ),
+            ["2026-03-09 09:00", "2026-03-16 09:00", "2026-03-23 09:00"]
+        );
+    }
+
+    #[test]
+    fn until_before_dtstart_yields_nothing() {
+        assert!(expand(
+            "FREQ=DAILY;UNTIL=20260301T000000",
+            "2026-03-04 09:00",
+            true,
+            10
+        )
+        .is_empty());
+    }
+}
@@ -0,0 +1,478 @@
+// TZID と VTIMEZONE の解決
+// IANA名はchrono-tzで、それ以外 (Outlookの "Tokyo Standard Time" など) はファイル内のVTIMEZONE定義で解決する
+
+use chrono::{Datelike, Duration, Local, NaiveDate, NaiveDateTime, Offset, TimeZone, Utc};
+use chrono_tz::{OffsetComponents, OffsetName, Tz};
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+
+use super::parser::{parse_date_time, Component};
+use super::rrule::{weekday_code, RRule, RRuleIter};
+
+// STANDARD / DAYLIGHT の各定義
+#[derive(Debug)]
+struct Observance {
+    start: NaiveDateTime,
+    offset_from: i64,
+    offset_to: i64,
+    // RRULE から展開した切り替え時刻。問い合わせのたびに展開し直さないよう、必要な範囲まで展開して覚えておく
+    onsets: Mutex<Onsets>,
+    rdates: Vec<NaiveDateTime>,
+}
+
+#[derive(Debug)]
+struct Onsets {
+    // 昇順
+    expanded: Vec<NaiveDateTime>,
+    // 続きを展開するための RRULE の展開 (終わったら None)
+    rest: Option<RRuleIter>,
+}
+
+impl Observance {
+    fn from_component(component: &Component) -> Option<Self> {
+        let start = parse_date_time(component.value("DTSTART")?)?.value;
+        let offset_to = parse_utc_offset(component.value("TZOFFSETTO")?)?;
+        let offset_from = component
+            .value("TZOFFSETFROM")
+            .and_then(parse_utc_offset)
+            .unwrap_or(offset_to);
+        let rest = component
+            .value("RRULE")
+            .and_then(|value| RRule::parse(value).ok())
+            .map(|mut rule| {
+                if rule.until_utc {
+                    rule.until = rule
+                        .until
+                        .map(|until| until + chrono::Duration::seconds(offset_from));
+                }
+                rule.iter(start)
+            });
+        let rdates = component
+            .properties_named("RDATE")
+            .flat_map(|p| p.value.split(','))
+            .filter_map(parse_date_time)
+            .map(|d| d.value)
+            .collect();
+        Some(Self {
+            start,
+            offset_from,
+            offset_to,
+            onsets: Mutex::new(Onsets {
+                expanded: Vec::new(),
+                rest,
+            }),
+            rdates,
+        })
+    }
+
+    // local 以前で最も新しい切り替え時刻
+    fn last_onset_before(&self, local: NaiveDateTime) -> Option<NaiveDateTime> {
+        if self.start > local {
+            return None;
+        }
+        let mut latest = self.start;
+        let mut onsets = self.onsets.lock().unwrap();
+        // local より後の切り替えが一つ見つかるまで展開する
+        while onsets.expanded.last().is_none_or(|last| *last <= local) {
+            match onsets.rest.as_mut().and_then(Iterator::next) {
+                Some(onset) => onsets.expanded.push(onset),
+                None => {
+                    onsets.rest = None;
+                    break;
+                }
+            }
+        }
+        let passed = onsets.expanded.partition_point(|onset| *onset <= local);
+        if let Some(onset) = passed.checked_sub(1).map(|i| onsets.expanded[i]) {
+            latest = latest.max(onset);
+        }
+        for rdate in self.rdates.iter().filter(|r| **r <= local) {
+            latest = latest.max(*rdate);
+        }
+        Some(latest)
+    }
+}
+
+#[derive(Debug)]
+pub struct VTimezone {
+    observances: Vec<Observance>,
+}
+
+impl VTimezone {
+    // 壁時計時刻に対するUTCオフセット (秒)
+    fn offset_at(&self, local: NaiveDateTime) -> i64 {
+        self.observances
+            .iter()
+            .filter_map(|o| o.last_onset_before(local).map(|onset| (onset, o.offset_to)))
+            .max_by_key(|(onset, _)| *onset)
+            .map(|(_, offset)| offset)
+            .or_else(|| {
+                self.observances
+                    .iter()
+                    .min_by_key(|o| o.start)
+                    .map(|o| o.offset_from)
+            })
+            .unwrap_or(0)
+    }
+}
+
+// +0900 / -0530 / +093000
+fn parse_utc_offset(value: &str) -> Option<i64> {
+    let value = value.trim();
+    let sign = match value.chars().next()? {
+        '+' => 1,
+        '-' => -1,
+        _ => return None,
+    };
+    let digits = &value[1..];
+    if !(digits.len() == 4 || digits.len() == 6) || !digits.bytes().all(|b| b.is_ascii_digit()) {
+        return None;
+    }
+    let hours: i64 = digits[0..2].parse().ok()?;
+    let minutes: i64 = digits[2..4].parse().ok()?;
+    let seconds: i64 = digits.get(4..6).map_or(Some(0), |s| s.parse().ok())?;
+    Some(sign * (hours * 3600 + minutes * 60 + seconds))
+}
+
+#[derive(Debug, Clone)]
+pub enum Zone {
+    // タイムゾーン指定なし (端末のローカル時刻として扱う)
+    Floating,
+    Utc,
+    Named(Tz),
+    Custom(Arc<VTimezone>),
+}
+
+impl Zone {
+    // IANA名 (Asia/Tokyo など) から。名前がなければ端末のローカル時刻
+    pub fn from_name(name: Option<&str>) -> Zone {
+        let Some(name) = name.map(|n| n.trim()).filter(|n| !n.is_empty()) else {
+            return Zone::Floating;
+        };
+        match name.to_ascii_uppercase().as_str() {
+            "UTC" | "GMT" | "Z" | "ETC/UTC" => Zone::Utc,
+            _ => parse_tz_name(name).map_or(Zone::Floating, Zone::Named),
+        }
+    }
+
+    pub fn to_millis(&self, local: NaiveDateTime) -> Option<i64> {
+        match self {
+            Zone::Floating => Local
+                .from_local_datetime(&local)
+                .earliest()
+                .map(|d| d.timestamp_millis()),
+            Zone::Utc => Some(Utc.from_utc_datetime(&local).timestamp_millis()),
+            Zone::Named(tz) => tz
+                .from_local_datetime(&local)
+                .earliest()
+                .map(|d| d.timestamp_millis()),
+            Zone::Custom(vtimezone) => {
+                let offset = vtimezone.offset_at(local);
+                Some(
+                    (local - chrono::Duration::seconds(offset))
+                        .and_utc()
+                        .timestamp_millis(),
+                )
+            }
+        }
+    }
+
+    // UTC の日時をこのタイムゾーンの壁時計時刻に変換する (UNTIL の変換に使う)
+    pub fn localize_utc(&self, utc: NaiveDateTime) -> NaiveDateTime {
+        match self {
+            Zone::Floating => Local.from_utc_datetime(&utc).naive_local(),
+            Zone::Utc => utc,
+            Zone::Named(tz) => tz.from_utc_datetime(&utc).naive_local(),
+            Zone::Custom(vtimezone) => {
+                // オフセットは壁時計時刻で決まるため、UTC時刻を仮の壁時計時刻として近似する
+                utc + chrono::Duration::seconds(vtimezone.offset_at(utc))
+            }
+        }
+    }
+}
+
+fn parse_tz_name(name: &str) -> Option<Tz> {
+    if let Ok(tz) = name.parse::<Tz>() {
+        return Some(tz);
+    }
+    // "/mozilla.org/20050126_1/Asia/Tokyo" のような接頭辞付きの名前
+    let segments: Vec<&str> = name.split('/').filter(|s| !s.is_empty()).collect();
+    (0..segments.len()).find_map(|i| segments[i..].join("/").parse::<Tz>().ok())
+}
+
+pub struct TimezoneResolver {
+    zones: HashMap<String, Zone>,
+}
+
+impl TimezoneResolver {
+    pub fn from_calendar(root: &Component) -> Self {
+        let mut definitions = Vec::new();
+        root.find_all("VTIMEZONE", &mut definitions);
+
+        let mut zones = HashMap::new();
+        for definition in definitions {
+            let Some(tzid) = definition.value("TZID") else {
+                continue;
+            };
+            let named = definition
+                .value("X-LIC-LOCATION")
+                .and_then(parse_tz_name)
+                .or_else(|| parse_tz_name(tzid));
+            let zone = match named {
+                Some(tz) => Zone::Named(tz),
+                None => {
+                    let observances: Vec<Observance> = definition
+                        .components
+                        .iter()
+                        .filter(|c| c.name == "STANDARD" || c.name == "DAYLIGHT")
+                        .filter_map(Observance::from_component)
+                        .collect();
+                    if observances.is_empty() {
+                        continue;
+                    }
+                    Zone::Custom(Arc::new(VTimezone { observances }))
+                }
+            };
+            zones.insert(tzid.to_string(), zone);
+        }
+        Self { zones }
+    }
+
+    pub fn zone(&self, tzid: Option<&str>) -> Zone {
+        let Some(tzid) = tzid.map(|t| t.trim()).filter(|t| !t.is_empty()) else {
+            return Zone::Floating;
+        };
+        if let Some(zone) = self.zones.get(tzid) {
+            return zone.clone();
+        }
+        Zone::from_name(Some(tzid))
+    }
+}
+
+fn format_utc_offset(seconds: i64) -> String {
+    let sign = if seconds < 0 { '-' } else { '+' };
+    let seconds = seconds.abs();
+    let hhmm = format!("{}{:02}{:02}", sign, seconds / 3600, seconds % 3600 / 60);
+    match seconds % 60 {
+        0 => hhmm,
+        s => format!("{}{:02}", hhmm, s),
+    }
+}
+
+fn utc_offset(tz: Tz, utc: NaiveDateTime) -> i64 {
+    tz.offset_from_utc_datetime(&utc).fix().local_minus_utc() as i64
+}
+
+// year 中のオフセットの切り替え (UTC の時刻と切り替え前後のオフセット)
+fn transitions(tz: Tz, year: i32) -> Vec<(NaiveDateTime, i64, i64)> {
+    let (Some(first), Some(last)) = (
+        NaiveDate::from_ymd_opt(year, 1, 1),
+        NaiveDate::from_ymd_opt(year + 1, 1, 1),
+    ) else {
+        return Vec::new();
+    };
+    let mut result = Vec::new();
+    let mut day = first.and_time(Default::default());
+    let end = last.and_time(Default::default());
+    while day < end {
+        let next = day + Duration::days(1);
+        let before = utc_offset(tz, day);
+        let after = utc_offset(tz, next);
+        if before != after {
+            // 切り替えの時刻 (秒単位) を二分探索する
+            let (mut lo, mut hi) = (0, 24 * 60 * 60);
+            while hi - lo > 1 {
+                let mid = (lo + hi) / 2;
+                if utc_offset(tz, day + Duration::seconds(mid)) == before {
+                    lo = mid;
+                } else {
+                    hi = mid;
+                }
+            }
+            result.push((day + Duration::seconds(hi), before, after));
+        }
+        day = next;
+    }
+    result
+}
+
+// chrono-tz のタイムゾーンを VTIMEZONE として書き出す (TZID 付きの日時に添える)
+// from_year の切り替えを起点に毎年の規則 (RRULE) で表し、through_year まで実際のオフセットと一致するか確かめる
+// 毎年の規則で表せないタイムゾーンは None
+pub fn vtimezone_lines(
+    tzid: &str,
+    tz: Tz,
+    from_year: i32,
+    through_year: i32,
+) -> Option<Vec<String>> {
+    let first = NaiveDate::from_ymd_opt(from_year, 1, 1)?.and_time(Default::default());
+    let mut lines = vec!["BEGIN:VTIMEZONE".to_string(), format!("TZID:{}", tzid)];
+    let changes = transitions(tz, from_year);
+    if changes.is_empty() {
+        let offset = utc_offset(tz, first);
+        lines.extend([
+            "BEGIN:STANDARD".to_string(),
+            "DTSTART:19700101T000000".to_string(),
+            format!("TZOFFSETFROM:{}", format_utc_offset(offset)),
+            format!("TZOFFSETTO:{}", format_utc_offset(offset)),
+        ]);
+        if let Some(name) = tz.offset_from_utc_datetime(&first).abbreviation() {
+            lines.push(format!("TZNAME:{}", name));
+        }
+        lines.push("END:STANDARD".to_string());
+    }
+
+    // 書き出した規則による切り替え (UTC の時刻と切り替え前後のオフセット)
+    let mut written = Vec::new();
+    for (utc, offset_from, offset_to) in changes {
+        // DTSTART は切り替え前のオフセットでの壁時計時刻
+        let start = utc + Duration::seconds(offset_from);
+        let days_in_month = NaiveDate::from_ymd_opt(start.year(), start.month(), 1)?
+            .checked_add_months(chrono::Months::new(1))?
+            .pred_opt()?
+            .day();
+        let ordinal = if start.day() + 7 > days_in_month {
+            -1
+        } else {
+            (start.day() as i32 - 1) / 7 + 1
+        };
+        let rrule = format!(
+            "FREQ=YEARLY;BYMONTH={};BYDAY={}{}",
+            start.month(),
+            ordinal,
+            weekday_code(start.weekday())
+        );
+        let offset = tz.offset_from_utc_datetime(&utc);
+        let kind = if offset.dst_offset().is_zero() {
+            "STANDARD"
+        } else {
+            "DAYLIGHT"
+        };
+        lines.extend([
+            format!("BEGIN:{}", kind),
+            format!("DTSTART:{}", start.format("%Y%m%dT%H%M%S")),
+            format!("TZOFFSETFROM:{}", format_utc_offset(offset_from)),
+            format!("TZOFFSETTO:{}", format_utc_offset(offset_to)),
+        ]);
+        if let Some(name) = offset.abbreviation() {
+            lines.push(format!("TZNAME:{}", name));
+        }
+        lines.push(format!("RRULE:{}", rrule));
+        lines.push(format!("END:{}", kind));
+
+        let onsets = RRule::parse(&rrule).ok()?.iter(start);
+        written.extend(
+            onsets
+                .take_while(|onset| onset.year() <= through_year)
+                .map(|onset| {
+                    (
+                        onset - Duration::seconds(offset_from),
+                        offset_from,
+                        offset_to,
+                    )
+                }),
+        );
+    }
+    lines.push("END:VTIMEZONE".to_string());
+
+    // 毎年の規則で through_year までの実際の切り替えをすべて表せているか
+    written.sort();
+    let actual: Vec<_> = (from_year..=through_year)
+        .flat_map(|year| transitions(tz, year))
+        .collect();
+    (written == actual).then_some(lines)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ics::parser::parse_components;
+
+    // 書き出した VTIMEZONE を IANA 名として解決されない TZID で読み直す
+    fn reparse(tz: Tz, from_year: i32, through_year: i32) -> Option<(Vec<String>, Zone)> {
+        let lines = vtimezone_lines("Test Zone", tz, from_year, through_year)?;
+        let content = format!(
+            "BEGIN:VCALENDAR\r\n{}\r\nEND:VCALENDAR\r\n",
+            lines.join("\r\n")
+        );
+        let zone =
+            TimezoneResolver::from_calendar(&parse_components(&content)).zone(Some("Test Zone"));
+        Some((lines, zone))
+    }
+
+    #[test]
+    fn writes_yearly_rules_for_dst_zones() {
+        let (lines, _) = reparse(chrono_tz::America::New_York, 2026, 2027).unwrap();
+        assert_eq!(
+            lines,
+            [
+                "BEGIN:VTIMEZONE",
+                "TZID:Test Zone",
+                "BEGIN:DAYLIGHT",
+                "DTSTART:20260308T020000",
+                "TZOFFSETFROM:-0500",
+                "TZOFFSETTO:-0400",
+                "TZNAME:EDT",
+                "RRULE:FREQ=YEARLY;BYMONTH=3;BYDAY=2SU",
+                "END:DAYLIGHT",
+                "BEGIN:STANDARD",
+                "DTSTART:20261101T020000",
+                "TZOFFSETFROM:-0400",
+                "TZOFFSETTO:-0500",
+                "TZNAME:EST",
+                "RRULE:FREQ=YEARLY;BYMONTH=11;BYDAY=1SU",
+                "END:STANDARD",
+                "END:VTIMEZONE",
+            ]
+        );
+        let (lines, _) = reparse(chrono_tz::Europe::Berlin, 2026, 2027).unwrap();
+        assert!(lines.contains(&"RRULE:FREQ=YEARLY;BYMONTH=3;BYDAY=-1SU".to_string()));
+        assert!(lines.contains(&"RRULE:FREQ=YEARLY;BYMONTH=10;BYDAY=-1SU".to_string()));
+    }
+
+    #[test]
+    fn writes_fixed_offset_for_zones_without_dst() {
+        let (lines, zone) = reparse(chrono_tz::Asia::Tokyo, 2026, 2026).unwrap();
+        assert!(lines.contains(&"TZOFFSETTO:+0900".to_string()));
+        let local = NaiveDate::from_ymd_opt(2026, 7, 1)
+            .unwrap()
+            .and_hms_opt(9, 0, 0)
+            .unwrap();
+        assert_eq!(
+            zone.to_millis(local),
+            Some(
+                Utc.with_ymd_and_hms(2026, 7, 1, 0, 0, 0)
+                    .unwrap()
+                    .timestamp_millis()
+            )
+        );
+    }
+
+    #[test]
+    fn reparsed_definition_matches_the_zone() {
+        for tz in [
+            chrono_tz::America::New_York,
+            chrono_tz::Europe::Berlin,
+            chrono_tz::Australia::Sydney,
+        ] {
+            let (_, zone) = reparse(tz, 2026, 2028).unwrap();
+            let mut utc = Utc
+                .with_ymd_and_hms(2026, 1, 1, 0, 0, 0)
+                .unwrap()
+                .naive_utc();
+            while utc.year() < 2029 {
+                let local = tz.from_utc_datetime(&utc).naive_local();
+                if tz.from_local_datetime(&local).single().is_some() {
+                    assert_eq!(
+                        zone.to_millis(local),
+                        Some(utc.and_utc().timestamp_millis()),
+                        "{} {}",
+                        tz,
+                        local
+                    );
+                }
+                utc += Duration::hours(23);
+            }
+        }
+    }
+}
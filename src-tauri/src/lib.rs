@@ -1,9 +1,40 @@
 // Learn more about Tauri commands at https://tauri.app/develop/calling-rust/
 
+mod ai;
+mod caldav;
+mod calendar;
+mod csv;
+mod dependency;
+mod discord;
+mod filter;
+mod focus;
+mod hotkey;
+mod ics;
+mod mail;
+mod markdown;
+mod models;
+mod nlp;
+mod notifier;
+mod outbox;
+mod plan;
+mod recurrence;
+mod schedule;
+mod search;
+mod store;
+mod timeblock;
+mod timetrack;
+mod timezone;
+mod todos;
+mod todotxt;
+mod usage;
+
 use tauri::menu::{Menu, MenuItem};
 use tauri::tray::{MouseButton, MouseButtonState, TrayIconBuilder, TrayIconEvent};
 use tauri::{Manager, WebviewUrl, WebviewWindowBuilder};
 
+// 集中タイマーがツールチップを書き換えるときに使う
+const TRAY_ID: &str = "main";
+
 #[cfg(windows)]
 use tauri_winrt_notification::Toast;
 
@@ -178,7 +209,7 @@ async fn save_backup_with_dialog(app: tauri::AppHandle, content: String) -> Resu
 
     let filename = format!(
         "calm-todo-backup-{}.json",
-        chrono::Local::now().format("%Y-%m-%d-%H%M%S")
+        timezone::system_now().format("%Y-%m-%d-%H%M%S")
     );
     let default_dir = std::path::PathBuf::from("C:/CalmTodoBackup");
 
@@ -269,21 +300,13 @@ pub struct TavilySearchResponse {
     pub results: Vec<TavilySearchResult>,
 }
 
-#[derive(Copy, Clone, PartialEq, Eq)]
-enum CharClass {
-    Latin,
-    Hiragana,
-    Katakana,
-    Kanji,
-}
-
 fn optimize_search_query(raw_query: &str) -> (String, String) {
     let trimmed = raw_query.trim();
     if trimmed.is_empty() {
         return (trimmed.to_string(), "original".to_string());
     }
 
-    let base_tokens = split_query_tokens(trimmed);
+    let base_tokens = search::split_query_tokens(trimmed);
 
     let mut seen = Vec::new();
     for token in base_tokens {
@@ -311,108 +334,13 @@ fn optimize_search_query(raw_query: &str) -> (String, String) {
     (seen.join(" "), method)
 }
 
-fn split_query_tokens(query: &str) -> Vec<String> {
-    let mut tokens = Vec::new();
-    let mut current = String::new();
-    let mut current_class: Option<CharClass> = None;
-    let mut chars = query.chars().peekable();
-
-    while let Some(c) = chars.next() {
-        if c.is_whitespace() {
-            flush_token(&mut tokens, &mut current, &mut current_class);
-            continue;
-        }
-
-        if let Some(class) = char_class(c) {
-            if current_class == Some(class) {
-                current.push(c);
-            } else {
-                flush_token(&mut tokens, &mut current, &mut current_class);
-                current.push(c);
-                current_class = Some(class);
-            }
-            continue;
-        }
-
-        if current_class == Some(CharClass::Latin) && is_ascii_connector(c) {
-            if matches!(c, '+' | '#') {
-                current.push(c);
-                continue;
-            }
-            if chars
-                .peek()
-                .map(|next| is_ascii_or_fullwidth_alnum(*next))
-                .unwrap_or(false)
-            {
-                current.push(c);
-                continue;
-            }
-        }
-
-        flush_token(&mut tokens, &mut current, &mut current_class);
-    }
-
-    flush_token(&mut tokens, &mut current, &mut current_class);
-    tokens
-}
-
-fn flush_token(
-    tokens: &mut Vec<String>,
-    current: &mut String,
-    current_class: &mut Option<CharClass>,
-) {
-    if !current.is_empty() {
-        tokens.push(current.clone());
-        current.clear();
-    }
-    *current_class = None;
-}
-
-fn char_class(c: char) -> Option<CharClass> {
-    if is_ascii_or_fullwidth_alnum(c) {
-        return Some(CharClass::Latin);
-    }
-    if is_hiragana(c) {
-        return Some(CharClass::Hiragana);
-    }
-    if is_katakana(c) {
-        return Some(CharClass::Katakana);
-    }
-    if is_kanji(c) {
-        return Some(CharClass::Kanji);
-    }
-    None
-}
-
-fn is_ascii_or_fullwidth_alnum(c: char) -> bool {
-    c.is_ascii_alphanumeric()
-        || matches!(c, '\u{FF10}'..='\u{FF19}' | '\u{FF21}'..='\u{FF3A}' | '\u{FF41}'..='\u{FF5A}')
-}
-
-fn is_ascii_connector(c: char) -> bool {
-    matches!(c, '+' | '#' | '-' | '_' | '.' | ':' | '/' | '@')
-}
-
-fn is_hiragana(c: char) -> bool {
-    matches!(c, '\u{3040}'..='\u{309F}')
-}
-
-fn is_katakana(c: char) -> bool {
-    matches!(c, '\u{30A0}'..='\u{30FF}' | '\u{31F0}'..='\u{31FF}')
-}
-
-fn is_kanji(c: char) -> bool {
-    matches!(
-        c,
-        '\u{3005}'
-            | '\u{3400}'..='\u{4DBF}'
-            | '\u{4E00}'..='\u{9FFF}'
-            | '\u{F900}'..='\u{FAFF}'
-    )
-}
-
 #[tauri::command]
-async fn tavily_search(api_key: String, query: String) -> Result<TavilySearchResponse, String> {
+async fn tavily_search(
+    usage: tauri::State<'_, usage::UsageState>,
+    api_key: String,
+    query: String,
+) -> Result<TavilySearchResponse, String> {
+    usage.ensure_within_budget()?;
     let client = reqwest::Client::new();
 
     let (optimized_query, search_method) = optimize_search_query(&query);
@@ -435,19 +363,34 @@ async fn tavily_search(api_key: String, query: String) -> Result<TavilySearchRes
 
     println!("[Tavily Backend] 検索リクエスト送信: {:?}", request.query);
 
+    let started = std::time::Instant::now();
     let response = client
         .post("https://api.tavily.com/search")
         .json(&request)
         .send()
         .await
-        .map_err(|e| format!("Tavilyリクエストエラー: {}", e))?;
+        .map_err(|e| {
+            usage.record(usage::UsageRecord::tavily(
+                &request.search_depth,
+                started.elapsed().as_millis() as u64,
+                false,
+            ));
+            format!("Tavilyリクエストエラー: {}", e)
+        })?;
 
     println!(
         "[Tavily Backend] レスポンスステータス: {}",
         response.status()
     );
 
-    if !response.status().is_success() {
+    let success = response.status().is_success();
+    usage.record(usage::UsageRecord::tavily(
+        &request.search_depth,
+        started.elapsed().as_millis() as u64,
+        success,
+    ));
+
+    if !success {
         let error_text = response.text().await.unwrap_or_default();
         println!("[Tavily Backend] エラー: {}", error_text);
         return Err(format!("Tavily APIエラー: {}", error_text));
@@ -508,6 +451,22 @@ fn show_notification(title: String, body: String) -> Result<String, String> {
     }
 }
 
+// 保存ダイアログのファイル種類を拡張子から決める
+fn export_filter(filename: &str) -> (&'static str, &'static [&'static str]) {
+    let extension = std::path::Path::new(filename)
+        .extension()
+        .and_then(|e| e.to_str())
+        .unwrap_or_default()
+        .to_ascii_lowercase();
+    match extension.as_str() {
+        "ics" => ("iCalendar", &["ics"]),
+        "md" | "markdown" => ("Markdown", &["md", "markdown"]),
+        "txt" => ("Text", &["txt"]),
+        "csv" => ("CSV", &["csv"]),
+        _ => ("JSON", &["json"]),
+    }
+}
+
 #[tauri::command]
 async fn save_export_file(
     app: tauri::AppHandle,
@@ -521,8 +480,9 @@ async fn save_export_file(
     let default_path = dirs::document_dir().map(|p| p.join(&filename));
 
     // Show save file dialog
+    let (filter_name, extensions) = export_filter(&filename);
     let mut builder = app.dialog().file().set_file_name(&filename);
-    builder = builder.add_filter("JSON", &["json"]);
+    builder = builder.add_filter(filter_name, extensions);
 
     if let Some(path) = default_path {
         builder = builder.set_directory(path.parent().unwrap_or(&path));
@@ -544,6 +504,29 @@ async fn save_export_file(
     }
 }
 
+// クイック追加ウィンドウを開く (トレイメニューとグローバルショートカットから)
+fn open_quick_add(app: &tauri::AppHandle) {
+    // Check if quick-add window already exists
+    if let Some(window) = app.get_webview_window("quick-add") {
+        let _ = window.show();
+        let _ = window.set_focus();
+    } else {
+        // Create a new small quick-add window
+        let _ =
+            WebviewWindowBuilder::new(app, "quick-add", WebviewUrl::App("quick-add.html".into()))
+                .title("タスク追加")
+                .inner_size(400.0, 140.0)
+                .resizable(false)
+                .maximizable(false)
+                .minimizable(false)
+                .decorations(true)
+                .always_on_top(true)
+                .center()
+                .focused(true)
+                .build();
+    }
+}
+
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
 pub fn run() {
     #[cfg(windows)]
@@ -556,6 +539,11 @@ pub fn run() {
         .plugin(tauri_plugin_http::init())
         .plugin(tauri_plugin_shell::init())
         .plugin(tauri_plugin_dialog::init())
+        .plugin(
+            tauri_plugin_global_shortcut::Builder::new()
+                .with_handler(hotkey::handle)
+                .build(),
+        )
         .plugin(tauri_plugin_single_instance::init(|app, _args, _cwd| {
             // When a second instance is launched, show and focus the existing window
             if let Some(window) = app.get_webview_window("main") {
@@ -573,9 +561,153 @@ pub fn run() {
             load_backup_with_dialog,
             show_notification,
             save_export_file,
-            tavily_search
+            tavily_search,
+            ai::ai_list_models,
+            ai::ai_get_key_status,
+            ai::ai_set_api_key,
+            ai::ai_clear_api_key,
+            ai::ai_stream_completion,
+            ai::ai_cancel_completion,
+            ai::ai_get_local_config,
+            ai::ai_set_local_config,
+            ai::ai_list_local_models,
+            ai::ai_check_local_health,
+            usage::usage_daily_summary,
+            usage::usage_provider_summary,
+            usage::usage_get_budget,
+            usage::usage_set_budget,
+            plan::parse_plan_response,
+            plan::parse_decompose_response,
+            todos::sync_todos,
+            todos::sync_projects,
+            discord::discord_get_settings,
+            discord::discord_set_settings,
+            outbox::outbox_enqueue,
+            outbox::outbox_status,
+            outbox::outbox_retry_now,
+            outbox::outbox_expire_task,
+            notifier::notifier_list_targets,
+            notifier::notifier_save_target,
+            notifier::notifier_delete_target,
+            notifier::notifier_send_test,
+            notifier::notifier_notify,
+            mail::mail_get_settings,
+            mail::mail_set_settings,
+            mail::mail_send_test,
+            mail::mail_send_reminder,
+            mail::mail_preview_agenda,
+            mail::mail_send_agenda_now,
+            ics::ics_parse,
+            ics::ics_import,
+            ics::ics_export,
+            caldav::caldav_get_settings,
+            caldav::caldav_set_settings,
+            caldav::caldav_discover,
+            caldav::caldav_sync,
+            markdown::markdown_export,
+            markdown::markdown_import,
+            todotxt::todotxt_get_settings,
+            todotxt::todotxt_set_settings,
+            todotxt::todotxt_export,
+            todotxt::todotxt_import,
+            todotxt::todotxt_pending_changes,
+            todotxt::todotxt_mark_imported,
+            csv::csv_list_profiles,
+            csv::csv_save_profile,
+            csv::csv_delete_profile,
+            csv::csv_export,
+            csv::csv_preview,
+            csv::csv_import,
+            nlp::nlp_parse,
+            recurrence::recurrence_occurrences,
+            recurrence::recurrence_next,
+            calendar::calendar_get_settings,
+            calendar::calendar_set_settings,
+            calendar::calendar_holidays,
+            calendar::calendar_import_company_holidays,
+            calendar::calendar_day_info,
+            calendar::calendar_add_business_days,
+            timezone::timezone_system,
+            timezone::timezone_list,
+            timezone::timezone_resolve_due,
+            timezone::timezone_describe_due,
+            timezone::timezone_pending_change,
+            timezone::timezone_mark_applied,
+            search::search_tasks,
+            search::search_stats,
+            search::search_fuzzy,
+            filter::filter_check,
+            filter::filter_apply,
+            filter::filter_list,
+            filter::filter_save,
+            filter::filter_delete,
+            filter::filter_convert_legacy,
+            dependency::dependency_add,
+            dependency::dependency_add_chain,
+            dependency::dependency_remove,
+            dependency::dependency_status,
+            dependency::dependency_blocked,
+            dependency::dependency_graph,
+            schedule::schedule_compute,
+            schedule::schedule_plan,
+            schedule::schedule_project,
+            schedule::schedule_list_settings,
+            schedule::schedule_save_settings,
+            schedule::schedule_delete_settings,
+            timeblock::timeblock_get_settings,
+            timeblock::timeblock_set_settings,
+            timeblock::timeblock_add_calendar,
+            timeblock::timeblock_remove_calendar,
+            timeblock::timeblock_plan,
+            timeblock::timeblock_export,
+            focus::focus_get_settings,
+            focus::focus_set_settings,
+            focus::focus_status,
+            focus::focus_start,
+            focus::focus_pause,
+            focus::focus_resume,
+            focus::focus_skip,
+            focus::focus_stop,
+            timetrack::timetrack_start,
+            timetrack::timetrack_stop,
+            timetrack::timetrack_running,
+            timetrack::timetrack_list,
+            timetrack::timetrack_save,
+            timetrack::timetrack_delete,
+            timetrack::timetrack_report,
+            timetrack::timetrack_export_csv,
+            hotkey::hotkey_get,
+            hotkey::hotkey_set
         ])
         .setup(|app| {
+            app.manage(ai::AiState::load(app.handle()));
+            app.manage(usage::UsageState::load(app.handle()));
+            app.manage(todos::TodoState::load(app.handle()));
+            app.manage(search::SearchState::load(app.handle()));
+            app.manage(discord::DiscordState::load(app.handle()));
+            app.manage(outbox::OutboxState::load(app.handle()));
+            app.manage(notifier::NotifierState::load(app.handle()));
+            app.manage(mail::MailState::load(app.handle()));
+            app.manage(caldav::CaldavState::load(app.handle()));
+            app.manage(todotxt::TodoTxtState::load(app.handle()));
+            app.manage(csv::CsvState::load(app.handle()));
+            app.manage(calendar::CalendarState::load(app.handle()));
+            app.manage(timezone::TimezoneState::load(app.handle()));
+            app.manage(filter::FilterState::load(app.handle()));
+            app.manage(dependency::DependencyState::load(app.handle()));
+            app.manage(schedule::ScheduleState::load(app.handle()));
+            app.manage(timeblock::TimeBlockState::load(app.handle()));
+            app.manage(focus::FocusState::load(app.handle()));
+            app.manage(timetrack::TimeTrackState::load(app.handle()));
+            app.manage(hotkey::HotkeyState::load(app.handle()));
+            app.state::<hotkey::HotkeyState>()
+                .register_saved(app.handle());
+            tauri::async_runtime::spawn(outbox::run_worker(app.handle().clone()));
+            tauri::async_runtime::spawn(mail::run_agenda_scheduler(app.handle().clone()));
+            tauri::async_runtime::spawn(todotxt::run_watcher(app.handle().clone()));
+            tauri::async_runtime::spawn(timezone::run_watcher(app.handle().clone()));
+            tauri::async_runtime::spawn(focus::run_timer(app.handle().clone()));
+
             // Create tray menu
             let add_item = MenuItem::with_id(app, "add", "+ 新規タスク", true, None::<&str>)?;
             let show_item = MenuItem::with_id(app, "show", "表示", true, None::<&str>)?;
@@ -583,45 +715,22 @@ pub fn run() {
             let menu = Menu::with_items(app, &[&add_item, &show_item, &quit_item])?;
 
             // Build tray icon
-            let _tray = TrayIconBuilder::new()
+            let _tray = TrayIconBuilder::with_id(TRAY_ID)
                 .icon(app.default_window_icon().unwrap().clone())
                 .menu(&menu)
                 .tooltip("Calm Todo")
-                .on_menu_event(|app, event| {
-                    match event.id.as_ref() {
-                        "add" => {
-                            // Check if quick-add window already exists
-                            if let Some(window) = app.get_webview_window("quick-add") {
-                                let _ = window.set_focus();
-                            } else {
-                                // Create a new small quick-add window
-                                let _ = WebviewWindowBuilder::new(
-                                    app,
-                                    "quick-add",
-                                    WebviewUrl::App("quick-add.html".into()),
-                                )
-                                .title("タスク追加")
-                                .inner_size(400.0, 140.0)
-                                .resizable(false)
-                                .maximizable(false)
-                                .minimizable(false)
-                                .decorations(true)
-                                .always_on_top(true)
-                                .center()
-                                .build();
-                            }
-                        }
-                        "show" => {
-                            if let Some(window) = app.get_webview_window("main") {
-                                let _ = window.show();
-                                let _ = window.set_focus();
-                            }
-                        }
-                        "quit" => {
-                            app.exit(0);
+                .on_menu_event(|app, event| match event.id.as_ref() {
+                    "add" => open_quick_add(app),
+                    "show" => {
+                        if let Some(window) = app.get_webview_window("main") {
+                            let _ = window.show();
+                            let _ = window.set_focus();
                         }
-                        _ => {}
                     }
+                    "quit" => {
+                        app.exit(0);
+                    }
+                    _ => {}
                 })
                 .on_tray_icon_event(|tray, event| {
                     if let TrayIconEvent::Click {
@@ -0,0 +1,671 @@
+// SMTPによるメール通知 (個別リマインダーと朝のアジェンダ)
+// 送信自体はアウトボックス経由で行う
+
+use chrono::{Local, NaiveTime, TimeZone};
+use lettre::message::header::ContentType;
+use lettre::message::Mailbox;
+use lettre::transport::smtp::authentication::Credentials;
+use lettre::{AsyncSmtpTransport, AsyncTransport, Message, Tokio1Executor};
+use std::sync::Mutex;
+use std::time::Duration;
+use tauri::Manager;
+
+use crate::discord::DeliveryError;
+use crate::models::Todo;
+use crate::outbox::{EnqueueOutcome, NewOutboxMessage, OutboxChannel, OutboxState};
+
+const SETTINGS_FILE: &str = "mail-settings.json";
+const AGENDA_STATE_FILE: &str = "mail-agenda.json";
+const SMTP_TIMEOUT: Duration = Duration::from_secs(30);
+const AGENDA_CHECK_INTERVAL: Duration = Duration::from_secs(60);
+
+#[derive(serde::Serialize, serde::Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum SmtpSecurity {
+    // 平文で接続してから STARTTLS で暗号化 (587番ポート)
+    #[default]
+    StartTls,
+    // 最初からTLSで接続 (465番ポート)
+    Tls,
+    // 暗号化なし (ローカルのテスト用サーバー向け)
+    None,
+}
+
+#[derive(serde::Serialize, serde::Deserialize, Clone)]
+#[serde(rename_all = "camelCase", default)]
+pub struct MailSettings {
+    pub enabled: bool,
+    pub host: String,
+    pub port: u16,
+    pub security: SmtpSecurity,
+    pub username: String,
+    pub password: String,
+    pub from: String,
+    pub to: Vec<String>,
+    pub agenda_enabled: bool,
+    // 朝のアジェンダを送る時刻 (HH:MM)
+    pub agenda_time: String,
+}
+
+impl Default for MailSettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            host: String::new(),
+            port: 587,
+            security: SmtpSecurity::StartTls,
+            username: String::new(),
+            password: String::new(),
+            from: String::new(),
+            to: Vec::new(),
+            agenda_enabled: false,
+            agenda_time: "08:00".to_string(),
+        }
+    }
+}
+
+impl MailSettings {
+    fn validate(&self) -> Result<(), String> {
+        if self.host.trim().is_empty() {
+            return Err("SMTPサーバーを入力してください".to_string());
+        }
+        self.from
+            .parse::<Mailbox>()
+            .map_err(|e| format!("送信元アドレスが不正です: {}", e))?;
+        if self.to.is_empty() {
+            return Err("送信先アドレスを入力してください".to_string());
+        }
+        for to in &self.to {
+            to.parse::<Mailbox>()
+                .map_err(|e| format!("送信先アドレスが不正です ({}): {}", to, e))?;
+        }
+        NaiveTime::parse_from_str(&self.agenda_time, "%H:%M")
+            .map_err(|_| "アジェンダの送信時刻は HH:MM 形式で入力してください".to_string())?;
+        Ok(())
+    }
+
+    fn transport(&self) -> Result<AsyncSmtpTransport<Tokio1Executor>, String> {
+        let builder = match self.security {
+            SmtpSecurity::StartTls => {
+                AsyncSmtpTransport::<Tokio1Executor>::starttls_relay(&self.host)
+            }
+            SmtpSecurity::Tls => AsyncSmtpTransport::<Tokio1Executor>::relay(&self.host),
+            SmtpSecurity::None => Ok(AsyncSmtpTransport::<Tokio1Executor>::builder_dangerous(
+                &self.host,
+            )),
+        }
+        .map_err(|e| format!("SMTP設定エラー: {}", e))?;
+
+        let mut builder = builder.port(self.port).timeout(Some(SMTP_TIMEOUT));
+        if !self.username.is_empty() {
+            builder = builder.credentials(Credentials::new(
+                self.username.clone(),
+                self.password.clone(),
+            ));
+        }
+        Ok(builder.build())
+    }
+}
+
+// payload は { "subject": "...", "body": "..." }
+pub async fn send_mail(
+    settings: &MailSettings,
+    payload: &serde_json::Value,
+) -> Result<(), DeliveryError> {
+    if !settings.enabled {
+        return Err(DeliveryError::Permanent(
+            "メール通知が設定されていません".to_string(),
+        ));
+    }
+    settings.validate().map_err(DeliveryError::Permanent)?;
+
+    let mut builder =
+        Message::builder()
+            .from(settings.from.parse().map_err(|e| {
+                DeliveryError::Permanent(format!("送信元アドレスが不正です: {}", e))
+            })?)
+            .subject(payload["subject"].as_str().unwrap_or("Calm Todo"))
+            .header(ContentType::TEXT_PLAIN);
+    for to in &settings.to {
+        builder = builder.to(to
+            .parse()
+            .map_err(|e| DeliveryError::Permanent(format!("送信先アドレスが不正です: {}", e)))?);
+    }
+    let message = builder
+        .body(payload["body"].as_str().unwrap_or_default().to_string())
+        .map_err(|e| DeliveryError::Permanent(format!("メール作成エラー: {}", e)))?;
+
+    let transport = settings.transport().map_err(DeliveryError::Permanent)?;
+    transport.send(message).await.map_err(|e| {
+        let message = format!("メール送信エラー: {}", e);
+        // 5xx 応答 (認証失敗・宛先拒否など) は再送しても成功しない
+        if e.is_permanent() {
+            DeliveryError::Permanent(message)
+        } else {
+            DeliveryError::Retryable(message)
+        }
+    })?;
+    Ok(())
+}
+
+fn start_of_day(date: chrono::NaiveDate) -> i64 {
+    Local
+        .from_local_datetime(&date.and_time(NaiveTime::MIN))
+        .earliest()
+        .map(|d| d.timestamp_millis())
+        .unwrap_or_default()
+}
+
+fn format_due(todo: &Todo) -> String {
+    crate::timezone::format_due(todo, "%m/%d %H:%M").unwrap_or_else(|| "期限なし".to_string())
+}
+
+fn format_task_line(todo: &Todo) -> String {
+    let mut line = format!(
+        "・[P{}] {} ({})",
+        todo.priority,
+        todo.text,
+        format_due(todo)
+    );
+    if !todo.labels.is_empty() {
+        line.push_str(&format!(
+            " {}",
+            todo.labels
+                .iter()
+                .map(|l| format!("#{}", l))
+                .collect::<Vec<_>>()
+                .join(" ")
+        ));
+    }
+    line
+}
+
+fn reminder_payload(todo: &Todo, message: &str) -> serde_json::Value {
+    let mut body = format!("{}\n\n{}\n", message, format_task_line(todo));
+    if !todo.description.is_empty() {
+        body.push_str(&format!("\n{}\n", todo.description));
+    }
+    serde_json::json!({
+        "subject": format!("[Calm Todo] リマインダー: {}", todo.text),
+        "body": body,
+    })
+}
+
+// 今日が期限のタスクと期限切れのタスクを一覧にする
+pub fn agenda_payload(todos: &[Todo]) -> serde_json::Value {
+    let today = Local::now().date_naive();
+    let start_of_today = start_of_day(today);
+    let start_of_tomorrow = start_of_day(today + chrono::Duration::days(1));
+
+    let mut overdue: Vec<&Todo> = Vec::new();
+    let mut due_today: Vec<&Todo> = Vec::new();
+    for todo in todos.iter().filter(|t| t.is_active()) {
+        match todo.due_date {
+            Some(due) if due < start_of_today => overdue.push(todo),
+            Some(due) if due < start_of_tomorrow => due_today.push(todo),
+            _ => {}
+        }
+    }
+    let sort = |list: &mut Vec<&Todo>| {
+        list.sort_by(|a, b| {
+            a.due_date
+                .cmp(&b.due_date)
+                .then(a.priority.cmp(&b.priority))
+        })
+    };
+    sort(&mut overdue);
+    sort(&mut due_today);
+
+    let mut body = format!("{} のアジェンダです。\n\n", today.format("%Y年%m月%d日"));
+    body.push_str(&format!("■ 今日が期限 ({}件)\n", due_today.len()));
+    if due_today.is_empty() {
+        body.push_str("なし\n");
+    }
+    for todo in &due_today {
+        body.push_str(&format_task_line(todo));
+        body.push('\n');
+    }
+    body.push_str(&format!("\n■ 期限切れ ({}件)\n", overdue.len()));
+    if overdue.is_empty() {
+        body.push_str("なし\n");
+    }
+    for todo in &overdue {
+        body.push_str(&format_task_line(todo));
+        body.push('\n');
+    }
+
+    serde_json::json!({
+        "subject": format!(
+            "[Calm Todo] {} のアジェンダ (今日 {}件 / 期限切れ {}件)",
+            today.format("%m/%d"),
+            due_today.len(),
+            overdue.len()
+        ),
+        "body": body,
+    })
+}
+
+#[derive(serde::Serialize, serde::Deserialize, Default)]
+#[serde(rename_all = "camelCase", default)]
+struct AgendaState {
+    // 最後にアジェンダを送った日 (YYYY-MM-DD)
+    last_sent_date: Option<String>,
+}
+
+pub struct MailState {
+    settings: Mutex<MailSettings>,
+    agenda: Mutex<AgendaState>,
+}
+
+impl MailState {
+    pub fn load(app: &tauri::AppHandle) -> Self {
+        let settings = crate::store::load_json(app, SETTINGS_FILE).unwrap_or_else(|e| {
+            println!("[Mail Backend] 設定読み込み失敗: {}", e);
+            MailSettings::default()
+        });
+        let agenda = crate::store::load_json(app, AGENDA_STATE_FILE).unwrap_or_default();
+        Self {
+            settings: Mutex::new(settings),
+            agenda: Mutex::new(agenda),
+        }
+    }
+
+    pub fn settings(&self) -> MailSettings {
+        self.settings.lock().unwrap().clone()
+    }
+
+    fn enqueue_agenda(&self, app: &tauri::AppHandle) -> Result<EnqueueOutcome, String> {
+        let todos = app.state::<crate::todos::TodoState>().all();
+        // 当日中に送れなかったアジェンダは意味がないので翌日0時で期限切れにする
+        let expires_at = start_of_day(Local::now().date_naive() + chrono::Duration::days(1));
+
+        app.state::<OutboxState>().enqueue(
+            app,
+            NewOutboxMessage {
+                channel: OutboxChannel::Email,
+                task_id: None,
+                kind: "agenda".to_string(),
+                idempotency_key: None,
+                payload: agenda_payload(&todos),
+                expires_at: Some(expires_at),
+            },
+        )
+    }
+
+    // 設定時刻を過ぎていて、今日まだ送っていなければアジェンダをキューに入れる
+    fn check_agenda(&self, app: &tauri::AppHandle) {
+        let settings = self.settings();
+        if !settings.enabled || !settings.agenda_enabled {
+            return;
+        }
+        let Ok(send_time) = NaiveTime::parse_from_str(&settings.agenda_time, "%H:%M") else {
+            return;
+        };
+        let now = Local::now();
+        let today = now.date_naive().format("%Y-%m-%d").to_string();
+        if now.time() < send_time
+            || self.agenda.lock().unwrap().last_sent_date.as_deref() == Some(today.as_str())
+        {
+            return;
+        }
+
+        match self.enqueue_agenda(app) {
+            Ok(_) => {
+                let mut agenda = self.agenda.lock().unwrap();
+                agenda.last_sent_date = Some(today);
+                if let Err(e) = crate::store::save_json(app, AGENDA_STATE_FILE, &*agenda) {
+                    println!("[Mail Backend] アジェンダ状態の保存失敗: {}", e);
+                }
+            }
+            Err(e) => println!("[Mail Backend] アジェンダのキューイング失敗: {}", e),
+        }
+    }
+}
+
+// 朝のアジェンダを送るためのスケジューラー
+pub async fn run_agenda_scheduler(app: tauri::AppHandle) {
+    loop {
+        app.state::<MailState>().check_agenda(&app);
+        tokio::time::sleep(AGENDA_CHECK_INTERVAL).await;
+    }
+}
+
+#[tauri::command]
+pub fn mail_get_settings(state: tauri::State<'_, MailState>) -> MailSettings {
+    state.settings()
+}
+
+#[tauri::command]
+pub fn mail_set_settings(
+    app: tauri::AppHandle,
+    state: tauri::State<'_, MailState>,
+    settings: MailSettings,
+) -> Result<(), String> {
+    if settings.enabled {
+        settings.validate()?;
+    }
+    let mut current = state.settings.lock().unwrap();
+    *current = settings;
+    crate::store::save_json(&app, SETTINGS_FILE, &*current)
+}
+
+// アウトボックスを通さずに直接送信し、結果をすぐに返す
+#[tauri::command]
+pub async fn mail_send_test(state: tauri::State<'_, MailState>) -> Result<String, String> {
+    let settings = state.settings();
+    let payload = serde_json::json!({
+        "subject": "[Calm Todo] テストメール",
+        "body": "Calm Todo からのテスト送信です。\nこのメールが届いていれば設定は正常です。\n",
+    });
+    send_mail(&settings, &payload)
+        .await
+        .map_err(|e| e.message().to_string())?;
+    Ok(format!("送信成功: {}", settings.to.join(", ")))
+}
+
+// メール通知が無効なら何もせず None を返す
+#[tauri::command]
+pub fn mail_send_reminder(
+    app: tauri::AppHandle,
+    state: tauri::State<'_, MailState>,
+    outbox: tauri::State<'_, OutboxState>,
+    task_id: String,
+    kind: String,
+    message: String,
+    idempotency_key: Option<String>,
+) -> Result<Option<EnqueueOutcome>, String> {
+    if !state.settings().enabled {
+        return Ok(None);
+    }
+    let todo = app
+        .state::<crate::todos::TodoState>()
+        .find(&task_id)
+        .ok_or_else(|| format!("タスクが見つかりません: {}", task_id))?;
+    outbox
+        .enqueue(
+            &app,
+            NewOutboxMessage {
+                channel: OutboxChannel::Email,
+                task_id: Some(task_id),
+                kind,
+                idempotency_key,
+                payload: reminder_payload(&todo, &message),
+                expires_at: None,
+            },
+        )
+        .map(Some)
+}
+
+#[tauri::command]
+pub fn mail_preview_agenda(todos: tauri::State<'_, crate::todos::TodoState>) -> serde_json::Value {
+    agenda_payload(&todos.all())
+}
+
+#[tauri::command]
+pub fn mail_send_agenda_now(
+    app: tauri::AppHandle,
+    state: tauri::State<'_, MailState>,
+) -> Result<EnqueueOutcome, String> {
+    state.enqueue_agenda(&app)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::BTreeMap;
+    use std::io::{BufRead, BufReader, Write};
+    use std::net::TcpListener;
+
+    // 受け取ったSMTPコマンドとメール本文
+    #[derive(Default)]
+    struct Captured {
+        commands: Vec<String>,
+        data: Option<String>,
+    }
+
+    // 1接続だけ受け付けるSMTPサーバー。STARTTLS 要求には応じるが、TLSは話せないので切断する
+    fn capture_one(advertise_starttls: bool) -> (u16, std::thread::JoinHandle<Captured>) {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let handle = std::thread::spawn(move || {
+            let (mut stream, _) = listener.accept().unwrap();
+            let mut reader = BufReader::new(stream.try_clone().unwrap());
+            let mut captured = Captured::default();
+            stream.write_all(b"220 localhost ESMTP test\r\n").unwrap();
+            let mut line = String::new();
+            loop {
+                line.clear();
+                if reader.read_line(&mut line).unwrap_or(0) == 0 {
+                    break;
+                }
+                let command = line.trim_end().to_string();
+                let verb = command
+                    .split([' ', ':'])
+                    .next()
+                    .unwrap_or_default()
+                    .to_ascii_uppercase();
+                captured.commands.push(command);
+                let reply: &[u8] = match verb.as_str() {
+                    "EHLO" if advertise_starttls => {
+                        b"250-localhost\r\n250-STARTTLS\r\n250 8BITMIME\r\n"
+                    }
+                    "EHLO" => b"250-localhost\r\n250 8BITMIME\r\n",
+                    "STARTTLS" => {
+                        stream.write_all(b"220 ready\r\n").unwrap();
+                        break;
+                    }
+                    "DATA" => {
+                        stream.write_all(b"354 go ahead\r\n").unwrap();
+                        let mut data = String::new();
+                        loop {
+                            line.clear();
+                            reader.read_line(&mut line).unwrap();
+                            if line == ".\r\n" {
+                                break;
+                            }
+                            data.push_str(&line);
+                        }
+                        captured.data = Some(data);
+                        b"250 queued\r\n"
+                    }
+                    "QUIT" => {
+                        stream.write_all(b"221 bye\r\n").unwrap();
+                        break;
+                    }
+                    _ => b"250 ok\r\n",
+                };
+                stream.write_all(reply).unwrap();
+            }
+            captured
+        });
+        (port, handle)
+    }
+
+    fn settings(port: u16, security: SmtpSecurity) -> MailSettings {
+        MailSettings {
+            enabled: true,
+            host: "127.0.0.1".to_string(),
+            port,
+            security,
+            from: "Calm Todo <todo@example.com>".to_string(),
+            to: vec!["me@example.com".to_string(), "team@example.com".to_string()],
+            ..Default::default()
+        }
+    }
+
+    fn base64_decode(value: &str) -> Vec<u8> {
+        const TABLE: &[u8; 64] =
+            b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+        let mut out = Vec::new();
+        let mut bits = 0u32;
+        let mut count = 0;
+        for c in value.bytes().filter(|c| TABLE.contains(c)) {
+            bits = bits << 6 | TABLE.iter().position(|t| *t == c).unwrap() as u32;
+            count += 6;
+            if count >= 8 {
+                count -= 8;
+                out.push((bits >> count) as u8);
+            }
+        }
+        out
+    }
+
+    // ヘッダー (折り返しを戻したもの) と、デコードした本文に分ける
+    fn parse_message(data: &str) -> (BTreeMap<String, String>, String) {
+        let (head, body) = data.split_once("\r\n\r\n").unwrap();
+        let mut headers = BTreeMap::new();
+        for line in head.replace("\r\n ", " ").split("\r\n") {
+            let (name, value) = line.split_once(": ").unwrap();
+            headers.insert(name.to_ascii_lowercase(), value.to_string());
+        }
+        let body = match headers.get("content-transfer-encoding").map(String::as_str) {
+            Some("base64") => String::from_utf8(base64_decode(body)).unwrap(),
+            _ => body.to_string(),
+        };
+        (headers, body.replace("\r\n", "\n"))
+    }
+
+    // RFC 2047 の encoded-word を戻す (encoded-word 同士の間の空白は除く)
+    fn decode_header(value: &str) -> String {
+        let mut out = String::new();
+        let mut previous_encoded = false;
+        for (i, word) in value.split(' ').enumerate() {
+            let encoded = word
+                .strip_prefix("=?utf-8?b?")
+                .and_then(|w| w.strip_suffix("?="));
+            if i > 0 && !(previous_encoded && encoded.is_some()) {
+                out.push(' ');
+            }
+            match encoded {
+                Some(encoded) => out.push_str(&String::from_utf8(base64_decode(encoded)).unwrap()),
+                None => out.push_str(word),
+            }
+            previous_encoded = encoded.is_some();
+        }
+        out
+    }
+
+    fn task(id: &str, text: &str, priority: u8, due_date: Option<i64>) -> Todo {
+        Todo {
+            id: id.to_string(),
+            text: text.to_string(),
+            priority,
+            due_date,
+            ..Default::default()
+        }
+    }
+
+    #[tokio::test]
+    async fn sends_headers_and_envelope_without_encryption() {
+        let (port, server) = capture_one(false);
+        let todo = Todo {
+            labels: vec!["仕事".to_string()],
+            description: "先方に確認する".to_string(),
+            ..task("a", "見積もりを出す", 1, None)
+        };
+        send_mail(
+            &settings(port, SmtpSecurity::None),
+            &reminder_payload(&todo, "もうすぐ期限です"),
+        )
+        .await
+        .map_err(|e| e.message().to_string())
+        .unwrap();
+
+        let captured = server.join().unwrap();
+        assert_eq!(
+            captured.commands[1..],
+            [
+                "MAIL FROM:<todo@example.com>",
+                "RCPT TO:<me@example.com>",
+                "RCPT TO:<team@example.com>",
+                "DATA",
+                "QUIT",
+            ]
+        );
+        let (headers, body) = parse_message(&captured.data.unwrap());
+        assert_eq!(headers["from"], "\"Calm Todo\" <todo@example.com>");
+        assert_eq!(headers["to"], "me@example.com, team@example.com");
+        assert_eq!(
+            decode_header(&headers["subject"]),
+            "[Calm Todo] リマインダー: 見積もりを出す"
+        );
+        assert_eq!(headers["content-type"], "text/plain; charset=utf-8");
+        assert!(headers.contains_key("date"));
+        assert_eq!(
+            body,
+            "もうすぐ期限です\n\n・[P1] 見積もりを出す (期限なし) #仕事\n\n先方に確認する\n"
+        );
+    }
+
+    #[tokio::test]
+    async fn starttls_upgrades_before_sending() {
+        let (port, server) = capture_one(true);
+        let result = send_mail(
+            &settings(port, SmtpSecurity::StartTls),
+            &serde_json::json!({ "subject": "s", "body": "b" }),
+        )
+        .await;
+        assert!(result.is_err());
+        let captured = server.join().unwrap();
+        assert_eq!(captured.commands.len(), 2);
+        assert!(captured.commands[0].starts_with("EHLO "));
+        assert_eq!(captured.commands[1], "STARTTLS");
+        assert!(captured.data.is_none());
+    }
+
+    #[tokio::test]
+    async fn starttls_refuses_servers_without_tls() {
+        let (port, server) = capture_one(false);
+        let result = send_mail(
+            &settings(port, SmtpSecurity::StartTls),
+            &serde_json::json!({ "subject": "s", "body": "b" }),
+        )
+        .await;
+        assert!(result.is_err());
+        let captured = server.join().unwrap();
+        assert!(captured
+            .commands
+            .iter()
+            .all(|c| !c.starts_with("MAIL") && !c.starts_with("DATA")));
+        assert!(captured.data.is_none());
+    }
+
+    #[tokio::test]
+    async fn sends_agenda_of_today_and_overdue_tasks() {
+        let today = start_of_day(Local::now().date_naive());
+        let hour = 3_600_000;
+        let mut done = task("d", "終わった作業", 1, Some(today - 24 * hour));
+        done.completed = true;
+        let todos = vec![
+            task("a", "朝の会議", 2, Some(today + 10 * hour)),
+            task("b", "日報", 4, Some(today + 9 * hour)),
+            task("c", "請求書", 1, Some(today - 48 * hour)),
+            task("e", "来週の準備", 1, Some(today + 48 * hour)),
+            task("f", "いつか読む本", 3, None),
+            done,
+        ];
+        let payload = agenda_payload(&todos);
+
+        let (port, server) = capture_one(false);
+        send_mail(&settings(port, SmtpSecurity::None), &payload)
+            .await
+            .map_err(|e| e.message().to_string())
+            .unwrap();
+        let (headers, body) = parse_message(&server.join().unwrap().data.unwrap());
+
+        assert!(
+            decode_header(&headers["subject"]).ends_with("のアジェンダ (今日 2件 / 期限切れ 1件)")
+        );
+        let line = |todo: &Todo| format_task_line(todo);
+        let expected = format!(
+            "■ 今日が期限 (2件)\n{}\n{}\n\n■ 期限切れ (1件)\n{}\n",
+            line(&todos[1]),
+            line(&todos[0]),
+            line(&todos[2])
+        );
+        assert!(body.ends_with(&expected), "{}", body);
+        assert!(!body.contains("来週の準備"));
+        assert!(!body.contains("いつか読む本"));
+        assert!(!body.contains("終わった作業"));
+    }
+}
@@ -0,0 +1,652 @@
+// Markdown (GitHub のタスクリスト形式) のエクスポート・インポート
+// プロジェクトを見出し1、セクションを見出し2、サブタスクをインデントで表し、
+// 優先度は P1〜P3、ラベルは #タグ、期限は 📅 YYYY-MM-DD としてタスク名の後ろに付ける
+
+use chrono::{Local, NaiveDate, TimeZone};
+use std::collections::HashMap;
+
+use crate::ics;
+use crate::models::{DueDateNotification, Project, Section, Todo};
+
+const DUE_MARK: &str = "📅";
+const DEFAULT_PROJECT_COLOR: &str = "#6366f1";
+
+#[derive(serde::Serialize, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct MarkdownImportResult {
+    // 見出しから作ったプロジェクトとセクション (同名のものはフロントエンドで既存に寄せる)
+    pub projects: Vec<Project>,
+    pub sections: Vec<Section>,
+    pub todos: Vec<Todo>,
+}
+
+fn is_priority_token(token: &str) -> bool {
+    matches!(token, "P1" | "P2" | "P3" | "P4")
+}
+
+// ラベル内の空白はタグとして読めなくなるため - に置き換える
+fn label_tag(label: &str) -> String {
+    let tag: Vec<&str> = label.split_whitespace().collect();
+    format!("#{}", tag.join("-"))
+}
+
+// そのまま書くとインポート時にメタデータとして読まれてしまう単語
+// (#ラベル、📅 期限、エスケープ用の \ で始まる単語)
+fn needs_escape(word: &str) -> bool {
+    word.starts_with('#') || word.starts_with('\\') || word == DUE_MARK
+}
+
+// タスク名の中の #単語 や 📅 を \# や \📅 にして、インポート時にメタデータと区別する
+fn escape_text(text: &str) -> String {
+    let text = text.split(['\r', '\n']).collect::<Vec<_>>().join(" ");
+    text.split(' ')
+        .map(|word| {
+            if needs_escape(word) {
+                format!("\\{}", word)
+            } else {
+                word.to_string()
+            }
+        })
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+fn unescape_text(text: &str) -> String {
+    text.split(' ')
+        .map(|word| {
+            word.strip_prefix('\\')
+                .filter(|w| needs_escape(w))
+                .unwrap_or(word)
+        })
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+// 説明文の行がタスクとして読まれないよう、先頭に \ を付ける
+fn escape_description_line(line: &str) -> String {
+    let trimmed = line.trim_start();
+    if trimmed.starts_with('\\') || parse_task_item(trimmed.trim_end()).is_some() {
+        let indent = &line[..line.len() - trimmed.len()];
+        format!("{}\\{}", indent, trimmed)
+    } else {
+        line.to_string()
+    }
+}
+
+fn unescape_description_line(line: &str) -> String {
+    let trimmed = line.trim_start();
+    match trimmed.strip_prefix('\\') {
+        Some(rest) if rest.starts_with('\\') || parse_task_item(rest.trim_end()).is_some() => {
+            format!("{}{}", &line[..line.len() - trimmed.len()], rest)
+        }
+        _ => line.to_string(),
+    }
+}
+
+fn format_date(millis: i64) -> Option<String> {
+    Local
+        .timestamp_millis_opt(millis)
+        .single()
+        .map(|d| d.format("%Y-%m-%d").to_string())
+}
+
+fn task_line(todo: &Todo, depth: usize) -> String {
+    let mut line = format!(
+        "{}- [{}] {}",
+        "  ".repeat(depth),
+        if todo.completed { "x" } else { " " },
+        escape_text(todo.text.trim())
+    );
+    // タスク名が P1 などで終わる場合は、優先度を明示しないとインポート時に優先度と誤認される
+    let ends_with_priority = todo
+        .text
+        .split_whitespace()
+        .last()
+        .is_some_and(is_priority_token);
+    if todo.priority < 4 || ends_with_priority {
+        line.push_str(&format!(" P{}", todo.priority.clamp(1, 4)));
+    }
+    for label in &todo.labels {
+        line.push(' ');
+        line.push_str(&label_tag(label));
+    }
+    if let Some(date) = todo.due_date.and_then(format_date) {
+        line.push_str(&format!(" {} {}", DUE_MARK, date));
+    }
+    line
+}
+
+fn write_tree(
+    lines: &mut Vec<String>,
+    todo: &Todo,
+    children: &HashMap<&str, Vec<&Todo>>,
+    depth: usize,
+) {
+    lines.push(task_line(todo, depth));
+    let indent = "  ".repeat(depth + 1);
+    for description_line in todo.description.trim_end().lines() {
+        if description_line.trim().is_empty() {
+            lines.push(String::new());
+        } else {
+            lines.push(format!(
+                "{}{}",
+                indent,
+                escape_description_line(description_line)
+            ));
+        }
+    }
+    if let Some(subtasks) = children.get(todo.id.as_str()) {
+        for subtask in subtasks {
+            write_tree(lines, subtask, children, depth + 1);
+        }
+    }
+}
+
+pub fn export_markdown(
+    todos: &[Todo],
+    projects: &[Project],
+    sections: &[Section],
+    include_completed: bool,
+) -> String {
+    let mut targets: Vec<&Todo> = todos
+        .iter()
+        .filter(|t| !t.archived && (include_completed || !t.completed))
+        .collect();
+    targets.sort_by(|a, b| {
+        a.order
+            .total_cmp(&b.order)
+            .then(a.created_at.cmp(&b.created_at))
+    });
+
+    // 親が書き出し対象にないサブタスクはトップレベルとして扱う
+    let mut children: HashMap<&str, Vec<&Todo>> = HashMap::new();
+    let mut roots: Vec<&Todo> = Vec::new();
+    for todo in &targets {
+        match todo.parent_id.as_deref() {
+            Some(parent) if targets.iter().any(|t| t.id == parent) => {
+                children.entry(parent).or_default().push(todo)
+            }
+            _ => roots.push(todo),
+        }
+    }
+
+    // プロジェクトなしを先頭に、プロジェクト・セクションの並び順で見出しを並べる
+    let project_rank = |id: Option<&str>| -> (bool, f64, String) {
+        let Some(id) = id else {
+            return (false, f64::MIN, String::new());
+        };
+        match projects.iter().find(|p| p.id == id) {
+            Some(project) => (true, project.order, project.name.clone()),
+            None => (true, f64::MAX, id.to_string()),
+        }
+    };
+    let section_rank = |id: Option<&str>| -> (bool, f64, String) {
+        let Some(id) = id else {
+            return (false, f64::MIN, String::new());
+        };
+        match sections.iter().find(|s| s.id == id) {
+            Some(section) => (true, section.order, section.name.clone()),
+            None => (true, f64::MAX, id.to_string()),
+        }
+    };
+    let mut groups: Vec<(Option<&str>, Option<&str>)> = Vec::new();
+    for root in &roots {
+        let key = (root.project_id.as_deref(), root.section_id.as_deref());
+        if !groups.contains(&key) {
+            groups.push(key);
+        }
+    }
+    groups.sort_by(|a, b| {
+        let (pa, pb) = (project_rank(a.0), project_rank(b.0));
+        let (sa, sb) = (section_rank(a.1), section_rank(b.1));
+        (pa.0, pa.1)
+            .partial_cmp(&(pb.0, pb.1))
+            .unwrap_or(std::cmp::Ordering::Equal)
+            .then_with(|| pa.2.cmp(&pb.2))
+            .then_with(|| {
+                (sa.0, sa.1)
+                    .partial_cmp(&(sb.0, sb.1))
+                    .unwrap_or(std::cmp::Ordering::Equal)
+            })
+            .then_with(|| sa.2.cmp(&sb.2))
+    });
+
+    let mut lines: Vec<String> = Vec::new();
+    let mut current_project: Option<Option<&str>> = None;
+    for (project_id, section_id) in groups {
+        if current_project != Some(project_id) {
+            current_project = Some(project_id);
+            if project_id.is_some() {
+                if !lines.is_empty() {
+                    lines.push(String::new());
+                }
+                lines.push(format!("# {}", project_rank(project_id).2));
+            }
+        }
+        if section_id.is_some() {
+            if !lines.is_empty() {
+                lines.push(String::new());
+            }
+            lines.push(format!("## {}", section_rank(section_id).2));
+        }
+        if !lines.is_empty() {
+            lines.push(String::new());
+        }
+        for root in roots.iter().filter(|t| {
+            t.project_id.as_deref() == project_id && t.section_id.as_deref() == section_id
+        }) {
+            write_tree(&mut lines, root, &children, 0);
+        }
+    }
+
+    let mut markdown = lines.join("\n");
+    markdown.push('\n');
+    markdown
+}
+
+// インデント幅 (タブは4桁として数える)
+fn indent_width(line: &str) -> usize {
+    line.chars()
+        .take_while(|c| *c == ' ' || *c == '\t')
+        .map(|c| if c == '\t' { 4 } else { 1 })
+        .sum()
+}
+
+// "- [ ] 内容" / "* [x] 内容" の (完了, 内容)
+fn parse_task_item(trimmed: &str) -> Option<(bool, &str)> {
+    let rest = trimmed
+        .strip_prefix("- ")
+        .or_else(|| trimmed.strip_prefix("* "))
+        .or_else(|| trimmed.strip_prefix("+ "))?
+        .trim_start();
+    let rest = rest.strip_prefix('[')?;
+    let mut chars = rest.chars();
+    let completed = match chars.next()? {
+        ' ' => false,
+        'x' | 'X' => true,
+        _ => return None,
+    };
+    let rest = chars.as_str().strip_prefix(']')?;
+    if !rest.is_empty() && !rest.starts_with(char::is_whitespace) {
+        return None;
+    }
+    Some((completed, rest.trim()))
+}
+
+fn parse_heading(trimmed: &str) -> Option<(usize, &str)> {
+    let level = trimmed.chars().take_while(|c| *c == '#').count();
+    if !(1..=6).contains(&level) {
+        return None;
+    }
+    let rest = &trimmed[level..];
+    if !rest.is_empty() && !rest.starts_with(char::is_whitespace) {
+        return None;
+    }
+    let name = rest.trim().trim_end_matches('#').trim();
+    Some((level, name))
+}
+
+fn split_last(text: &str) -> Option<(&str, &str)> {
+    text.trim_end()
+        .rsplit_once(char::is_whitespace)
+        .map(|(head, last)| (head.trim_end(), last))
+}
+
+struct TaskMeta {
+    text: String,
+    priority: Option<u8>,
+    labels: Vec<String>,
+    due_date: Option<NaiveDate>,
+}
+
+// 末尾から優先度・ラベル・期限を取り出す (タスク名そのものが空になるまでは取らない)
+fn parse_task_meta(content: &str) -> TaskMeta {
+    let mut rest = content.trim();
+    let mut priority = None;
+    let mut labels = Vec::new();
+    let mut due_date = None;
+
+    while let Some((head, last)) = split_last(rest) {
+        if head.is_empty() {
+            break;
+        }
+        if priority.is_none() && is_priority_token(last) {
+            priority = last[1..].parse().ok();
+            rest = head;
+        } else if last.len() > 1 && last.starts_with('#') {
+            labels.insert(0, last[1..].to_string());
+            rest = head;
+        } else if due_date.is_none() {
+            let Ok(date) = NaiveDate::parse_from_str(last, "%Y-%m-%d") else {
+                break;
+            };
+            let Some((before, mark)) = split_last(head) else {
+                break;
+            };
+            if mark != DUE_MARK || before.is_empty() {
+                break;
+            }
+            due_date = Some(date);
+            rest = before;
+        } else {
+            break;
+        }
+    }
+
+    TaskMeta {
+        text: unescape_text(rest),
+        priority,
+        labels,
+        due_date,
+    }
+}
+
+struct OpenTask {
+    indent: usize,
+    index: usize,
+    // 説明文の途中の空行 (続きがあるときだけ反映する)
+    pending_blank: usize,
+}
+
+pub fn import_markdown(content: &str) -> MarkdownImportResult {
+    let now = Local::now().timestamp_millis();
+    let mut result = MarkdownImportResult::default();
+    let mut stack: Vec<OpenTask> = Vec::new();
+    let mut project_id: Option<String> = None;
+    let mut section_id: Option<String> = None;
+    let new_id = |kind: &str, n: usize| format!("md-{}-{}-{}", kind, now, n);
+
+    for line in content.lines() {
+        if line.trim().is_empty() {
+            if let Some(open) = stack.last_mut() {
+                open.pending_blank += 1;
+            }
+            continue;
+        }
+        let indent = indent_width(line);
+        let trimmed = line.trim();
+
+        if let Some((completed, item)) = parse_task_item(trimmed) {
+            while stack.last().is_some_and(|open| open.indent >= indent) {
+                stack.pop();
+            }
+            let meta = parse_task_meta(item);
+            if meta.text.is_empty() {
+                continue;
+            }
+            let due_date = meta.due_date.and_then(|date| {
+                Local
+                    .from_local_datetime(&date.and_hms_opt(0, 0, 0)?)
+                    .earliest()
+                    .map(|d| d.timestamp_millis())
+            });
+            let index = result.todos.len();
+            result.todos.push(Todo {
+                id: new_id("task", index),
+                text: meta.text,
+                completed,
+                completed_at: completed.then_some(now),
+                created_at: now,
+                parent_id: stack.last().map(|open| result.todos[open.index].id.clone()),
+                priority: meta.priority.unwrap_or(4),
+                timeframe: due_date.map_or("today".to_string(), |due| {
+                    ics::timeframe_for(due, None, now)
+                }),
+                due_date,
+                due_date_notification: due_date.map(|_| DueDateNotification {
+                    enabled: true,
+                    notify_before: 0,
+                    notified_at: None,
+                    follow_up_count: 0,
+                }),
+                labels: meta.labels,
+                section_id: section_id.clone(),
+                order: index as f64,
+                project_id: project_id.clone(),
+                ..Default::default()
+            });
+            stack.push(OpenTask {
+                indent,
+                index,
+                pending_blank: 0,
+            });
+            continue;
+        }
+
+        // タスクより深くインデントされた行はそのタスクの説明文
+        if indent > 0 {
+            while stack.last().is_some_and(|open| open.indent >= indent) {
+                stack.pop();
+            }
+            if let Some(open) = stack.last_mut() {
+                let todo = &mut result.todos[open.index];
+                let strip = (open.indent + 2).min(indent);
+                let text: String = line.chars().skip(strip).collect();
+                if !todo.description.is_empty() {
+                    todo.description
+                        .push_str(&"\n".repeat(open.pending_blank + 1));
+                }
+                todo.description
+                    .push_str(&unescape_description_line(text.trim_end()));
+                open.pending_blank = 0;
+                continue;
+            }
+        }
+
+        stack.clear();
+        if let Some((level, name)) = parse_heading(trimmed) {
+            if name.is_empty() {
+                continue;
+            }
+            if level == 1 {
+                section_id = None;
+                project_id = Some(match result.projects.iter().find(|p| p.name == name) {
+                    Some(project) => project.id.clone(),
+                    None => {
+                        let project = Project {
+                            id: new_id("project", result.projects.len()),
+                            name: name.to_string(),
+                            color: DEFAULT_PROJECT_COLOR.to_string(),
+                            order: result.projects.len() as f64,
+                            ..Default::default()
+                        };
+                        let id = project.id.clone();
+                        result.projects.push(project);
+                        id
+                    }
+                });
+            } else {
+                section_id = Some(match result.sections.iter().find(|s| s.name == name) {
+                    Some(section) => section.id.clone(),
+                    None => {
+                        let section = Section {
+                            id: new_id("section", result.sections.len()),
+                            name: name.to_string(),
+                            order: result.sections.len() as f64,
+                            ..Default::default()
+                        };
+                        let id = section.id.clone();
+                        result.sections.push(section);
+                        id
+                    }
+                });
+            }
+        }
+    }
+    result
+}
+
+#[tauri::command]
+pub fn markdown_export(
+    todos: Vec<Todo>,
+    projects: Option<Vec<Project>>,
+    sections: Option<Vec<Section>>,
+    include_completed: Option<bool>,
+) -> String {
+    export_markdown(
+        &todos,
+        &projects.unwrap_or_default(),
+        &sections.unwrap_or_default(),
+        include_completed.unwrap_or(true),
+    )
+}
+
+#[tauri::command]
+pub fn markdown_import(content: String) -> MarkdownImportResult {
+    import_markdown(&content)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn midnight(date: &str) -> i64 {
+        let date = NaiveDate::parse_from_str(date, "%Y-%m-%d").unwrap();
+        Local
+            .from_local_datetime(&date.and_hms_opt(0, 0, 0).unwrap())
+            .earliest()
+            .unwrap()
+            .timestamp_millis()
+    }
+
+    fn todo(id: &str, text: &str, order: f64) -> Todo {
+        Todo {
+            id: id.to_string(),
+            text: text.to_string(),
+            order,
+            ..Default::default()
+        }
+    }
+
+    // ID に依らずに比べられる形 (タスク名, 完了, 優先度, ラベル, 期限, 説明, 親のタスク名, プロジェクト名, セクション名)
+    type Row = (
+        String,
+        bool,
+        u8,
+        Vec<String>,
+        Option<i64>,
+        String,
+        Option<String>,
+        Option<String>,
+        Option<String>,
+    );
+
+    fn rows(todos: &[Todo], projects: &[Project], sections: &[Section]) -> Vec<Row> {
+        todos
+            .iter()
+            .map(|t| {
+                let parent = t
+                    .parent_id
+                    .as_ref()
+                    .and_then(|id| todos.iter().find(|p| &p.id == id))
+                    .map(|p| p.text.clone());
+                let project = t
+                    .project_id
+                    .as_ref()
+                    .and_then(|id| projects.iter().find(|p| &p.id == id))
+                    .map(|p| p.name.clone());
+                let section = t
+                    .section_id
+                    .as_ref()
+                    .and_then(|id| sections.iter().find(|s| &s.id == id))
+                    .map(|s| s.name.clone());
+                (
+                    t.text.clone(),
+                    t.completed,
+                    t.priority,
+                    t.labels.clone(),
+                    t.due_date,
+                    t.description.clone(),
+                    parent,
+                    project,
+                    section,
+                )
+            })
+            .collect()
+    }
+
+    fn assert_round_trip(todos: &[Todo], projects: &[Project], sections: &[Section]) {
+        let markdown = export_markdown(todos, projects, sections, true);
+        let imported = import_markdown(&markdown);
+        assert_eq!(
+            rows(&imported.todos, &imported.projects, &imported.sections),
+            rows(todos, projects, sections),
+            "{}",
+            markdown
+        );
+    }
+
+    #[test]
+    fn round_trips_metadata_and_structure() {
+        let projects = vec![Project {
+            id: "p".to_string(),
+            name: "仕事".to_string(),
+            ..Default::default()
+        }];
+        let sections = vec![Section {
+            id: "s".to_string(),
+            name: "今週".to_string(),
+            ..Default::default()
+        }];
+        let mut a = todo("a", "見積もりを送る", 1.0);
+        a.priority = 1;
+        a.labels = vec!["客先".to_string(), "急ぎ".to_string()];
+        a.due_date = Some(midnight("2026-02-01"));
+        a.description = "先方の担当者に確認\n\n金額は税抜き".to_string();
+        a.project_id = Some("p".to_string());
+        a.section_id = Some("s".to_string());
+        let mut b = todo("b", "下書き", 2.0);
+        b.parent_id = Some("a".to_string());
+        b.completed = true;
+        b.project_id = Some("p".to_string());
+        b.section_id = Some("s".to_string());
+        let mut c = todo("c", "日報", 0.0);
+        c.priority = 3;
+
+        assert_round_trip(&[c, a, b], &projects, &sections);
+    }
+
+    #[test]
+    fn round_trips_titles_that_look_like_metadata() {
+        let mut due = todo("a", "締切 📅 2026-03-01", 5.0);
+        due.due_date = Some(midnight("2026-04-01"));
+        let titles = [
+            todo("b", "報告書 📅 2026-01-15", 1.0),
+            todo("c", "Issue #12 を直す #bug", 2.0),
+            todo("d", r"パス C:\temp と \#タグ と \📅", 3.0),
+            todo("e", "優先度の話 P2", 4.0),
+            due,
+        ];
+        assert_round_trip(&titles, &[], &[]);
+    }
+
+    #[test]
+    fn round_trips_descriptions_that_look_like_tasks() {
+        let mut a = todo("a", "手順", 0.0);
+        a.description = [
+            "- [ ] 未完了のように見える行",
+            "* [x] 完了のように見える行",
+            "  + [X] インデントされた行",
+            r"\- [ ] 元からエスケープされた行",
+            r"\ バックスラッシュで始まる行",
+            "- 普通の箇条書き",
+            "# 見出しのような行",
+        ]
+        .join("\n");
+        let b = todo("b", "次のタスク", 1.0);
+        assert_round_trip(&[a, b], &[], &[]);
+
+        let markdown = export_markdown(&[todo("c", "親", 0.0)], &[], &[], true);
+        assert_eq!(import_markdown(&markdown).todos.len(), 1);
+    }
+
+    #[test]
+    fn reads_trailing_metadata() {
+        let imported = import_markdown("- [ ] 資料 \\#1 を作る P2 #仕事 📅 2026-05-10\n");
+        let todo = &imported.todos[0];
+        assert_eq!(todo.text, "資料 #1 を作る");
+        assert_eq!(todo.priority, 2);
+        assert_eq!(todo.labels, vec!["仕事".to_string()]);
+        assert_eq!(todo.due_date, Some(midnight("2026-05-10")));
+    }
+}
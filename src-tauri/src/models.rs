@@ -0,0 +1,179 @@
+// フロントエンド (src/types/todo.ts) と共通のデータ型
+
+use serde_json::{Map, Value};
+
+#[derive(serde::Serialize, serde::Deserialize, Clone, Debug, Default, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct RecurrencePattern {
+    #[serde(rename = "type")]
+    pub kind: String,
+    pub interval: u32,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub days_of_week: Option<Vec<u32>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub day_of_month: Option<u32>,
+    // 以下は繰り返しエンジン (recurrence.rs) 用。フロントエンドの簡易設定では省略される
+    // 第何週か (1-5、-1 は最終週。days_of_week と組み合わせて「毎月最終金曜」などを表す)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub week_of_month: Option<i32>,
+    // daily で平日だけを数える (「3営業日ごと」)
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub weekdays_only: bool,
+    // 祝日・会社の休日には発生させない (weekdays_only と合わせると営業日で数える)
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub skip_holidays: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub count: Option<u32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub until: Option<i64>,
+    // 除外日 (この日付の発生はスキップする)
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub exdates: Vec<i64>,
+    #[serde(default, skip_serializing_if = "RecurrenceMode::is_fixed")]
+    pub mode: RecurrenceMode,
+    // RFC 5545 の RRULE をそのまま指定する場合 (他の項目より優先する)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub rrule: Option<String>,
+}
+
+#[derive(serde::Serialize, serde::Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum RecurrenceMode {
+    // 予定どおりの日付で繰り返す
+    #[default]
+    Fixed,
+    // 完了した日から数えて次の期限を決める
+    AfterCompletion,
+}
+
+impl RecurrenceMode {
+    pub fn is_fixed(&self) -> bool {
+        *self == RecurrenceMode::Fixed
+    }
+}
+
+#[derive(serde::Serialize, serde::Deserialize, Clone, Debug, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct TodoComment {
+    pub id: String,
+    pub text: String,
+    pub created_at: i64,
+}
+
+#[derive(serde::Serialize, serde::Deserialize, Clone, Debug, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct DueDateNotification {
+    pub enabled: bool,
+    pub notify_before: i64,
+    pub notified_at: Option<i64>,
+    pub follow_up_count: u32,
+}
+
+#[derive(serde::Serialize, serde::Deserialize, Clone, Debug, Default)]
+#[serde(rename_all = "camelCase", default)]
+pub struct Project {
+    pub id: String,
+    pub name: String,
+    pub color: String,
+    pub order: f64,
+    pub parent_id: Option<String>,
+    pub is_favorite: bool,
+    pub is_archived: bool,
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+#[derive(serde::Serialize, serde::Deserialize, Clone, Debug, Default)]
+#[serde(rename_all = "camelCase", default)]
+pub struct Section {
+    pub id: String,
+    pub name: String,
+    pub order: f64,
+    pub collapsed: bool,
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+#[derive(serde::Serialize, serde::Deserialize, Clone, Debug)]
+#[serde(rename_all = "camelCase", default)]
+pub struct Todo {
+    pub id: String,
+    pub text: String,
+    pub completed: bool,
+    pub completed_at: Option<i64>,
+    pub created_at: i64,
+    pub parent_id: Option<String>,
+    pub priority: u8,
+    pub timeframe: String,
+    pub due_date: Option<i64>,
+    pub due_date_notification: Option<DueDateNotification>,
+    // 期限のタイムゾーン (なし: 時刻そのもの / "floating": 端末のローカル時刻 / IANA名)
+    pub due_time_zone: Option<String>,
+    // 期限をそのタイムゾーンの壁時計時刻で表したもの (YYYY-MM-DDTHH:MM:SS)
+    pub due_local: Option<String>,
+    pub labels: Vec<String>,
+    pub recurrence: Option<RecurrencePattern>,
+    pub description: String,
+    pub section_id: Option<String>,
+    pub order: f64,
+    pub estimated_minutes: Option<u32>,
+    pub comments: Vec<TodoComment>,
+    pub project_id: Option<String>,
+    pub karma_awarded: bool,
+    pub archived: bool,
+    pub archived_at: Option<i64>,
+    // 最終更新日時 (CalDAV同期の競合判定に使う)
+    pub updated_at: Option<i64>,
+    // バックエンドが知らないフィールドも往復で失わないよう保持する
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+impl Default for Todo {
+    fn default() -> Self {
+        Self {
+            id: String::new(),
+            text: String::new(),
+            completed: false,
+            completed_at: None,
+            created_at: 0,
+            parent_id: None,
+            priority: 4,
+            timeframe: "today".to_string(),
+            due_date: None,
+            due_date_notification: None,
+            due_time_zone: None,
+            due_local: None,
+            labels: Vec::new(),
+            recurrence: None,
+            description: String::new(),
+            section_id: None,
+            order: 0.0,
+            estimated_minutes: None,
+            comments: Vec::new(),
+            project_id: None,
+            karma_awarded: false,
+            archived: false,
+            archived_at: None,
+            updated_at: None,
+            extra: Map::new(),
+        }
+    }
+}
+
+impl Todo {
+    // 通知やリマインダーの対象になるか (完了・アーカイブ済みは対象外)
+    pub fn is_active(&self) -> bool {
+        !self.completed && !self.archived
+    }
+
+    // updatedAt が未設定のタスクは作成・完了・アーカイブ日時の最新で代用する
+    pub fn last_modified(&self) -> i64 {
+        self.updated_at.unwrap_or_else(|| {
+            [self.completed_at, self.archived_at]
+                .into_iter()
+                .flatten()
+                .fold(self.created_at, i64::max)
+        })
+    }
+}
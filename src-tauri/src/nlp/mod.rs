@@ -0,0 +1,580 @@
+// 自然言語によるタスク入力の解析
+// フロントエンドの parseNaturalLanguage をバックエンドに移したもの (トレイのクイック追加などから使う)
+//
+// 「明日までに」「来週月曜」「毎週金曜」「P1」「#仕事」などを日本語・英語の両方で読み取り、
+// 解釈に迷った箇所は ambiguities に残す
+
+mod rules;
+mod scanner;
+
+use chrono::{Duration, Local, NaiveDate, NaiveDateTime, TimeZone};
+use serde::Serialize;
+
+use crate::calendar::{BusinessCalendar, CalendarState};
+use crate::models::RecurrencePattern;
+use rules::{Ctx, Found, TimeValue};
+use scanner::{is_hiragana, Scanner};
+
+#[derive(Serialize, Clone, Copy, Debug, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub enum TokenKind {
+    Label,
+    Priority,
+    Recurrence,
+    Date,
+    Time,
+    Duration,
+}
+
+// 入力中で読み取った表現 (start/end は入力の文字位置)
+#[derive(Serialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct ParsedToken {
+    pub kind: TokenKind,
+    pub text: String,
+    pub start: usize,
+    pub end: usize,
+}
+
+#[derive(Serialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct Ambiguity {
+    pub expression: String,
+    pub message: String,
+}
+
+#[derive(Serialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct ParsedTask {
+    pub text: String,
+    pub priority: u8,
+    pub timeframe: String,
+    pub due_date: Option<i64>,
+    // 時刻まで指定されたか (false なら期限は当日の 23:59:59.999)
+    pub has_time: bool,
+    pub labels: Vec<String>,
+    pub recurrence: Option<RecurrencePattern>,
+    pub estimated_minutes: Option<u32>,
+    // 0.0〜1.0 (曖昧な箇所が増えるほど下がる)
+    pub confidence: f32,
+    pub ambiguities: Vec<Ambiguity>,
+    pub tokens: Vec<ParsedToken>,
+}
+
+// 日付・時刻の後ろの助詞 (「明日までに」「10時から」)
+fn particle_len(sc: &Scanner, i: usize) -> usize {
+    const LONG: &[&str] = &[
+        "までには",
+        "までに",
+        "まで",
+        "ごろに",
+        "ごろ",
+        "頃に",
+        "頃",
+        "中に",
+        "中",
+        "から",
+    ];
+    if let Some(len) = sc.lit_any(i, LONG) {
+        return len;
+    }
+    // 1文字の助詞は、後ろにひらがなが続くと単語の一部かもしれないので取らない
+    for particle in ["には", "に", "の", "は"] {
+        if let Some(len) = sc.lit(i, particle) {
+            if !sc.char_at(i + len).is_some_and(is_hiragana) {
+                return len;
+            }
+        }
+    }
+    0
+}
+
+// 英語の前置詞 (「by friday」「at 3pm」)
+fn preposition_start(sc: &Scanner, i: usize) -> usize {
+    let spaces = (0..i)
+        .rev()
+        .take_while(|j| sc.char_at(*j).is_some_and(char::is_whitespace))
+        .count();
+    if spaces == 0 {
+        return i;
+    }
+    let end = i - spaces;
+    for word in ["by", "on", "at", "due", "before", "until", "till"] {
+        let len = word.len();
+        if end >= len && sc.word(end - len, word) == Some(len) {
+            return end - len;
+        }
+    }
+    i
+}
+
+struct Parser {
+    sc: Scanner,
+    tokens: Vec<ParsedToken>,
+    ambiguities: Vec<Ambiguity>,
+}
+
+impl Parser {
+    fn note(&mut self, expression: String, message: String) {
+        self.ambiguities.push(Ambiguity {
+            expression,
+            message,
+        });
+    }
+
+    fn accept(&mut self, kind: TokenKind, start: usize, end: usize, expression: String) {
+        self.sc.consume(start, end);
+        self.tokens.push(ParsedToken {
+            kind,
+            text: expression,
+            start,
+            end,
+        });
+    }
+
+    // 最初に見つかった表現を使い、2つ目以降は曖昧さとして報告する
+    fn first<T>(
+        &mut self,
+        kind: TokenKind,
+        ctx: &Ctx,
+        with_particles: bool,
+        matcher: impl Fn(&Scanner, usize, &Ctx) -> Option<Found<T>>,
+    ) -> Option<T> {
+        let mut result: Option<(T, String)> = None;
+        let mut i = 0;
+        while i < self.sc.len() {
+            let Some(found) = self
+                .sc
+                .is_free(i)
+                .then(|| matcher(&self.sc, i, ctx))
+                .flatten()
+            else {
+                i += 1;
+                continue;
+            };
+            let end = i + found.len;
+            let expression = self.sc.text(i, end);
+            if let Some((_, used)) = &result {
+                let message = format!("「{}」は使わず「{}」を採用しました", expression, used);
+                self.note(expression, message);
+                i = end;
+                continue;
+            }
+            let start = preposition_start(&self.sc, i);
+            let consumed_end = if with_particles {
+                end + particle_len(&self.sc, end)
+            } else {
+                end
+            };
+            if let Some(message) = found.note {
+                self.note(expression.clone(), message);
+            }
+            self.accept(kind, start, consumed_end, expression.clone());
+            result = Some((found.value, expression));
+            i = consumed_end;
+        }
+        result.map(|(value, _)| value)
+    }
+
+    fn all<T>(
+        &mut self,
+        kind: TokenKind,
+        ctx: &Ctx,
+        matcher: impl Fn(&Scanner, usize, &Ctx) -> Option<Found<T>>,
+    ) -> Vec<T> {
+        let mut values = Vec::new();
+        let mut i = 0;
+        while i < self.sc.len() {
+            match self
+                .sc
+                .is_free(i)
+                .then(|| matcher(&self.sc, i, ctx))
+                .flatten()
+            {
+                Some(found) => {
+                    let end = i + found.len;
+                    let expression = self.sc.text(i, end);
+                    self.accept(kind, i, end, expression);
+                    values.push(found.value);
+                    i = end;
+                }
+                None => i += 1,
+            }
+        }
+        values
+    }
+}
+
+fn to_millis(datetime: NaiveDateTime) -> i64 {
+    Local
+        .from_local_datetime(&datetime)
+        .earliest()
+        .map(|d| d.timestamp_millis())
+        .unwrap_or_else(|| datetime.and_utc().timestamp_millis())
+}
+
+// 期限までの暦日数で今日/今週/今月/今年を決める
+fn timeframe(
+    due: Option<NaiveDate>,
+    recurrence: Option<&RecurrencePattern>,
+    today: NaiveDate,
+) -> String {
+    if recurrence.is_some() {
+        return crate::ics::timeframe_for(0, recurrence, 0);
+    }
+    let days = due.map_or(0, |d| (d - today).num_days());
+    let timeframe = match days {
+        i64::MIN..=1 => "today",
+        2..=7 => "week",
+        8..=31 => "month",
+        _ => "year",
+    };
+    timeframe.to_string()
+}
+
+pub fn parse_task(input: &str, now: NaiveDateTime, calendar: &BusinessCalendar) -> ParsedTask {
+    let ctx = Ctx {
+        today: now.date(),
+        calendar,
+    };
+    let mut parser = Parser {
+        sc: Scanner::new(input),
+        tokens: Vec::new(),
+        ambiguities: Vec::new(),
+    };
+
+    // 読み取る順番に意味がある (「毎週月曜」を日付より先に、「10時30分」を所要時間より先に読む)
+    let labels = parser.all(TokenKind::Label, &ctx, rules::label);
+    let mut priority = parser.first(TokenKind::Priority, &ctx, false, rules::priority);
+    if priority.is_none() {
+        if let Some((start, end)) = rules::trailing_bang(&parser.sc) {
+            let value = match end - start {
+                1 => 3,
+                2 => 2,
+                _ => 1,
+            };
+            let expression = parser.sc.text(start, end);
+            parser.accept(TokenKind::Priority, start, end, expression);
+            priority = Some(value);
+        }
+    }
+    let recurrence = parser.first(TokenKind::Recurrence, &ctx, true, rules::recurrence);
+    let date = parser.first(TokenKind::Date, &ctx, true, rules::date);
+    let time = parser.first(TokenKind::Time, &ctx, true, rules::time);
+    let estimated_minutes = parser.first(TokenKind::Duration, &ctx, false, rules::duration);
+
+    let due = match (date, time) {
+        (date, Some(TimeValue::After(minutes))) => {
+            if date.is_some() {
+                parser.note(
+                    String::new(),
+                    "日付と「〜後」が両方あるため「〜後」を優先しました".to_string(),
+                );
+            }
+            Some((now + Duration::minutes(minutes), true))
+        }
+        (Some(date), Some(TimeValue::Clock { hour, minute })) => {
+            date.and_hms_opt(hour, minute, 0).map(|d| (d, true))
+        }
+        (Some(date), None) => date.and_hms_milli_opt(23, 59, 59, 999).map(|d| (d, false)),
+        (None, Some(TimeValue::Clock { hour, minute })) => {
+            ctx.today.and_hms_opt(hour, minute, 0).map(|datetime| {
+                // 今日の時刻が過ぎていれば明日にする
+                if datetime > now {
+                    return (datetime, true);
+                }
+                let expression = parser
+                    .tokens
+                    .iter()
+                    .find(|t| t.kind == TokenKind::Time)
+                    .map(|t| t.text.clone())
+                    .unwrap_or_default();
+                let message = format!(
+                    "今日の「{}」は過ぎているため明日として解釈しました",
+                    expression
+                );
+                parser.note(expression, message);
+                (datetime + Duration::days(1), true)
+            })
+        }
+        (None, None) => None,
+    };
+
+    let mut text = parser.sc.remaining_text();
+    if text.is_empty() {
+        text = input.trim().to_string();
+        parser.note(
+            text.clone(),
+            "タスク名が残らないため入力全体をタスク名にしました".to_string(),
+        );
+    }
+
+    let confidence = (0.8f32.powi(parser.ambiguities.len() as i32) * 100.0).round() / 100.0;
+    let mut tokens = parser.tokens;
+    tokens.sort_by_key(|t| t.start);
+
+    ParsedTask {
+        text,
+        priority: priority.unwrap_or(4),
+        timeframe: timeframe(due.map(|(d, _)| d.date()), recurrence.as_ref(), ctx.today),
+        due_date: due.map(|(d, _)| to_millis(d)),
+        has_time: due.is_some_and(|(_, has_time)| has_time),
+        labels,
+        recurrence,
+        estimated_minutes,
+        confidence,
+        ambiguities: parser.ambiguities,
+        tokens,
+    }
+}
+
+// 日付だけの表現 (「来週金曜」「next friday」「2026-03-05」) を解釈する
+// 全体が1つの日付として読めなければ None
+pub fn parse_date(text: &str, today: NaiveDate, calendar: &BusinessCalendar) -> Option<NaiveDate> {
+    let sc = Scanner::new(text);
+    let ctx = Ctx { today, calendar };
+    let start = sc.spaces(0);
+    let found = rules::date(&sc, start, &ctx)?;
+    let end = start + found.len;
+    (end + sc.spaces(end) == sc.len()).then_some(found.value)
+}
+
+// 自然言語の入力をタスクの各項目に分解する
+#[tauri::command]
+pub fn nlp_parse(calendar: tauri::State<'_, CalendarState>, input: String) -> ParsedTask {
+    parse_task(&input, Local::now().naive_local(), &calendar.calendar())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct Case {
+        input: &'static str,
+        text: &'static str,
+        priority: u8,
+        timeframe: &'static str,
+        // 期限 (ローカル時刻、"%Y-%m-%d %H:%M")
+        due: Option<&'static str>,
+        has_time: bool,
+        labels: &'static [&'static str],
+        // (種類, 間隔, 曜日)
+        recurrence: Option<(&'static str, u32, &'static [u32])>,
+        estimated_minutes: Option<u32>,
+        confidence: f32,
+        ambiguities: &'static [&'static str],
+    }
+
+    const BASE: Case = Case {
+        input: "",
+        text: "",
+        priority: 4,
+        timeframe: "today",
+        due: None,
+        has_time: false,
+        labels: &[],
+        recurrence: None,
+        estimated_minutes: None,
+        confidence: 1.0,
+        ambiguities: &[],
+    };
+
+    // 2026-03-04 (水) 15:00
+    fn now() -> NaiveDateTime {
+        NaiveDate::from_ymd_opt(2026, 3, 4)
+            .unwrap()
+            .and_hms_opt(15, 0, 0)
+            .unwrap()
+    }
+
+    fn local(millis: i64) -> String {
+        Local
+            .timestamp_millis_opt(millis)
+            .unwrap()
+            .naive_local()
+            .format("%Y-%m-%d %H:%M")
+            .to_string()
+    }
+
+    #[test]
+    fn parses_task_expressions() {
+        let cases = [
+            Case {
+                input: "資料作成 明日までに",
+                text: "資料作成",
+                due: Some("2026-03-05 23:59"),
+                ..BASE
+            },
+            Case {
+                input: "来週月曜 定例の準備",
+                text: "定例の準備",
+                timeframe: "week",
+                due: Some("2026-03-09 23:59"),
+                ..BASE
+            },
+            Case {
+                input: "毎週金曜 週報を書く",
+                text: "週報を書く",
+                timeframe: "week",
+                recurrence: Some(("weekly", 1, &[5])),
+                ..BASE
+            },
+            Case {
+                input: "請求書を送る P1",
+                text: "請求書を送る",
+                priority: 1,
+                ..BASE
+            },
+            Case {
+                input: "企画書 #仕事 #急ぎ",
+                text: "企画書",
+                labels: &["仕事", "急ぎ"],
+                ..BASE
+            },
+            Case {
+                input: "明日10時 歯医者 30分",
+                text: "歯医者",
+                due: Some("2026-03-05 10:00"),
+                has_time: true,
+                estimated_minutes: Some(30),
+                ..BASE
+            },
+            Case {
+                input: "submit report by tomorrow",
+                text: "submit report",
+                due: Some("2026-03-05 23:59"),
+                ..BASE
+            },
+            Case {
+                input: "call mom next monday",
+                text: "call mom",
+                timeframe: "week",
+                due: Some("2026-03-09 23:59"),
+                ..BASE
+            },
+            Case {
+                input: "water plants every friday",
+                text: "water plants",
+                timeframe: "week",
+                recurrence: Some(("weekly", 1, &[5])),
+                ..BASE
+            },
+            Case {
+                input: "fix bug p2 #work",
+                text: "fix bug",
+                priority: 2,
+                labels: &["work"],
+                ..BASE
+            },
+            Case {
+                input: "dentist at 3pm tomorrow 1h",
+                text: "dentist",
+                due: Some("2026-03-05 15:00"),
+                has_time: true,
+                estimated_minutes: Some(60),
+                ..BASE
+            },
+            Case {
+                input: "review !!",
+                text: "review",
+                priority: 2,
+                ..BASE
+            },
+            // 時刻だけの指定
+            Case {
+                input: "ミーティング 18時",
+                text: "ミーティング",
+                due: Some("2026-03-04 18:00"),
+                has_time: true,
+                ..BASE
+            },
+            // 過ぎた時刻は明日に繰り越す
+            Case {
+                input: "9時 ミーティング",
+                text: "ミーティング",
+                due: Some("2026-03-05 09:00"),
+                has_time: true,
+                confidence: 0.8,
+                ambiguities: &["今日の「9時」は過ぎているため明日として解釈しました"],
+                ..BASE
+            },
+            // 過ぎた日付は来年に繰り越す
+            Case {
+                input: "1/15 支払い",
+                text: "支払い",
+                timeframe: "year",
+                due: Some("2027-01-15 23:59"),
+                confidence: 0.8,
+                ambiguities: &["今年の1月15日は過ぎているため来年として解釈しました"],
+                ..BASE
+            },
+            Case {
+                input: "明日 来週金曜 打ち合わせ",
+                text: "来週金曜 打ち合わせ",
+                due: Some("2026-03-05 23:59"),
+                confidence: 0.8,
+                ambiguities: &["「来週金曜」は使わず「明日」を採用しました"],
+                ..BASE
+            },
+            Case {
+                input: "明日",
+                text: "明日",
+                due: Some("2026-03-05 23:59"),
+                confidence: 0.8,
+                ambiguities: &["タスク名が残らないため入力全体をタスク名にしました"],
+                ..BASE
+            },
+        ];
+
+        let calendar = BusinessCalendar::default();
+        for case in cases {
+            let parsed = parse_task(case.input, now(), &calendar);
+            let input = case.input;
+            assert_eq!(parsed.text, case.text, "{}", input);
+            assert_eq!(parsed.priority, case.priority, "{}", input);
+            assert_eq!(parsed.timeframe, case.timeframe, "{}", input);
+            assert_eq!(parsed.due_date.map(local).as_deref(), case.due, "{}", input);
+            assert_eq!(parsed.has_time, case.has_time, "{}", input);
+            assert_eq!(parsed.labels, case.labels, "{}", input);
+            assert_eq!(
+                parsed.recurrence.as_ref().map(|r| (
+                    r.kind.as_str(),
+                    r.interval,
+                    r.days_of_week.clone()
+                )),
+                case.recurrence
+                    .map(|(kind, interval, days)| (kind, interval, Some(days.to_vec()))),
+                "{}",
+                input
+            );
+            assert_eq!(
+                parsed.estimated_minutes, case.estimated_minutes,
+                "{}",
+                input
+            );
+            assert_eq!(parsed.confidence, case.confidence, "{}", input);
+            let messages: Vec<&str> = parsed
+                .ambiguities
+                .iter()
+                .map(|a| a.message.as_str())
+                .collect();
+            assert_eq!(messages, case.ambiguities, "{}", input);
+        }
+    }
+
+    #[test]
+    fn due_without_time_ends_the_day() {
+        let parsed = parse_task("掃除 明日", now(), &BusinessCalendar::default());
+        let due = Local
+            .timestamp_millis_opt(parsed.due_date.unwrap())
+            .unwrap()
+            .naive_local();
+        assert_eq!(
+            due,
+            NaiveDate::from_ymd_opt(2026, 3, 5)
+                .unwrap()
+                .and_hms_milli_opt(23, 59, 59, 999)
+                .unwrap()
+        );
+    }
+}
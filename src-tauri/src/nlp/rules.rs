@@ -0,0 +1,1111 @@
+// 各表現の読み取り規則
+// どの関数も位置 i から始まる表現だけを見て、読めた長さと値を返す
+
+use chrono::{Datelike, Duration, Months, NaiveDate, Weekday};
+
+use super::scanner::{is_hiragana, Scanner, EN_MONTHS, EN_WEEKDAYS, JP_WEEKDAYS, KANA_WEEKDAYS};
+use crate::calendar::BusinessCalendar;
+use crate::models::RecurrencePattern;
+
+pub struct Ctx<'a> {
+    pub today: NaiveDate,
+    // 営業日の計算に使う (祝日・会社の休日)
+    pub calendar: &'a BusinessCalendar,
+}
+
+pub struct Found<T> {
+    pub len: usize,
+    pub value: T,
+    // 解釈に迷った点
+    pub note: Option<String>,
+}
+
+fn found<T>(len: usize, value: T) -> Option<Found<T>> {
+    Some(Found {
+        len,
+        value,
+        note: None,
+    })
+}
+
+fn noted<T>(len: usize, value: T, note: impl Into<String>) -> Option<Found<T>> {
+    Some(Found {
+        len,
+        value,
+        note: Some(note.into()),
+    })
+}
+
+pub enum TimeValue {
+    Clock { hour: u32, minute: u32 },
+    // 今からの分数 (「30分後」など)
+    After(i64),
+}
+
+// ---- 日付の計算 ----
+
+fn fmt(date: NaiveDate) -> String {
+    date.format("%Y-%m-%d").to_string()
+}
+
+fn week_monday(date: NaiveDate) -> NaiveDate {
+    date - Duration::days(date.weekday().num_days_from_monday() as i64)
+}
+
+fn month_end(date: NaiveDate) -> NaiveDate {
+    let first = date.with_day(1).unwrap_or(date);
+    first + Months::new(1) - Duration::days(1)
+}
+
+fn add_months(date: NaiveDate, months: u32) -> NaiveDate {
+    date.checked_add_months(Months::new(months)).unwrap_or(date)
+}
+
+// 次にくる指定曜日 (today を含めるかどうか)
+fn upcoming(today: NaiveDate, weekday: Weekday, include_today: bool) -> NaiveDate {
+    let diff = (weekday.num_days_from_monday() as i64
+        - today.weekday().num_days_from_monday() as i64)
+        .rem_euclid(7);
+    if diff == 0 && !include_today {
+        today + Duration::days(7)
+    } else {
+        today + Duration::days(diff)
+    }
+}
+
+// 週末 (土曜日。土曜なら当日)
+fn weekend(today: NaiveDate) -> NaiveDate {
+    upcoming(today, Weekday::Sat, true)
+}
+
+// 年の省略された月日は、過ぎていれば来年とみなす
+fn month_day(today: NaiveDate, month: u32, day: u32) -> Option<(NaiveDate, Option<String>)> {
+    let date = NaiveDate::from_ymd_opt(today.year(), month, day)?;
+    if date >= today {
+        return Some((date, None));
+    }
+    let next = NaiveDate::from_ymd_opt(today.year() + 1, month, day)?;
+    let note = format!(
+        "今年の{}月{}日は過ぎているため来年として解釈しました",
+        month, day
+    );
+    Some((next, Some(note)))
+}
+
+// ---- 曜日 ----
+
+// 漢字の曜日 (「月曜日」「月曜」「月」)
+// 「曜」のない「日」「月」は「日本」「月末」などと紛れるので、区切りが続くときだけ読む
+fn jp_weekday(sc: &Scanner, i: usize) -> Option<(Weekday, usize)> {
+    let c = sc.char_at(i)?;
+    let weekday = JP_WEEKDAYS.iter().find(|(k, _)| *k == c)?.1;
+    if let Some(len) = sc.lit_any(i + 1, &["曜日", "曜"]) {
+        return Some((weekday, 1 + len));
+    }
+    let delimited = match sc.char_at(i + 1) {
+        None => true,
+        Some(n) => {
+            n.is_whitespace()
+                || "・、,/と".contains(n)
+                || JP_WEEKDAYS.iter().any(|(k, _)| *k == n)
+                || "にのはま".contains(n)
+        }
+    };
+    if matches!(weekday, Weekday::Sun | Weekday::Mon) && !delimited {
+        return None;
+    }
+    Some((weekday, 1))
+}
+
+// ひらがなの曜日 (「げつようび」など。bare が真なら「ようび」を省略できる)
+fn kana_weekday(sc: &Scanner, i: usize, bare: bool) -> Option<(Weekday, usize)> {
+    KANA_WEEKDAYS.iter().find_map(|(word, weekday)| {
+        let len = sc.lit(i, word)?;
+        match sc.lit_any(i + len, &["ようび", "よう"]) {
+            Some(suffix) => Some((*weekday, len + suffix)),
+            None if bare => Some((*weekday, len)),
+            None => None,
+        }
+    })
+}
+
+fn en_weekday(sc: &Scanner, i: usize) -> Option<(Weekday, usize)> {
+    EN_WEEKDAYS
+        .iter()
+        .find_map(|(word, weekday)| sc.word(i, word).map(|len| (*weekday, len)))
+}
+
+// 曜日の並び (「月・水・金」「mon, wed and fri」)
+fn weekday_list(
+    sc: &Scanner,
+    i: usize,
+    single: impl Fn(&Scanner, usize) -> Option<(Weekday, usize)>,
+) -> Option<(Vec<u32>, usize)> {
+    let (first, mut len) = single(sc, i)?;
+    let mut days = vec![first.num_days_from_sunday()];
+    loop {
+        let mut j = i + len;
+        j += sc.spaces(j);
+        if let Some(sep) = sc.lit_any(j, &["・", "、", ",", "/", "と", "&"]) {
+            j += sep;
+        } else if let Some(sep) = sc.word(j, "and") {
+            j += sep;
+        }
+        j += sc.spaces(j);
+        match single(sc, j) {
+            Some((weekday, n)) => {
+                days.push(weekday.num_days_from_sunday());
+                len = j + n - i;
+            }
+            None => break,
+        }
+    }
+    days.sort_unstable();
+    days.dedup();
+    Some((days, len))
+}
+
+// ---- ラベル ----
+
+// 「#仕事」 (「C#」のような語の途中の # は読まない)
+pub fn label(sc: &Scanner, i: usize, _ctx: &Ctx) -> Option<Found<String>> {
+    if sc.char_at(i) != Some('#') || !sc.token_start(i) {
+        return None;
+    }
+    let len = (i + 1..sc.len())
+        .take_while(|j| {
+            sc.char_at(*j)
+                .is_some_and(|c| !c.is_whitespace() && c != '#')
+        })
+        .count();
+    if len == 0 {
+        return None;
+    }
+    found(len + 1, sc.text(i + 1, i + 1 + len))
+}
+
+// ---- 優先度 ----
+
+// 単独の語として書かれた優先度 (「高」「p1」「!!」)
+pub fn priority(sc: &Scanner, i: usize, _ctx: &Ctx) -> Option<Found<u8>> {
+    if !sc.token_start(i) {
+        return None;
+    }
+    // 「!」は末尾なら文に続けて書いてもよい (「買い物!!」)
+    if sc.char_at(i) == Some('!') {
+        let len = (i..sc.len())
+            .take_while(|j| sc.char_at(*j) == Some('!'))
+            .count();
+        let value = match len {
+            1 => 3,
+            2 => 2,
+            _ => 1,
+        };
+        if sc.token_end(i + len) {
+            return found(len, value);
+        }
+    }
+    const WORDS: &[(&[&str], u8)] = &[
+        (&["優先度高", "高優先", "緊急", "至急", "重要", "高"], 1),
+        (&["優先度中", "中優先", "普通", "中"], 2),
+        (&["優先度低", "低優先", "低"], 3),
+    ];
+    for (words, value) in WORDS {
+        for word in *words {
+            if let Some(len) = sc.lit(i, word) {
+                if sc.token_end(i + len) {
+                    return found(len, *value);
+                }
+            }
+        }
+    }
+    for (word, value) in [("p1", 1), ("p2", 2), ("p3", 3), ("p4", 4)] {
+        if let Some(len) = sc.word(i, word) {
+            if sc.token_end(i + len) {
+                return found(len, value);
+            }
+        }
+    }
+    let len = sc.word_any(i, &["urgent", "asap"])?;
+    found(len, 1)
+}
+
+// 文末に付いた「!」 (「買い物!」)
+pub fn trailing_bang(sc: &Scanner) -> Option<(usize, usize)> {
+    let mut end = sc.len();
+    while end > 0 && sc.char_at(end - 1).is_some_and(char::is_whitespace) {
+        end -= 1;
+    }
+    let start = (0..end)
+        .rev()
+        .take_while(|j| sc.char_at(*j) == Some('!'))
+        .last()?;
+    (start > 0).then_some((start, end))
+}
+
+// ---- 繰り返し ----
+
+fn pattern(kind: &str, interval: u32) -> RecurrencePattern {
+    RecurrencePattern {
+        kind: kind.to_string(),
+        interval,
+        ..Default::default()
+    }
+}
+
+fn weekly(interval: u32, days: Vec<u32>) -> RecurrencePattern {
+    RecurrencePattern {
+        days_of_week: Some(days),
+        ..pattern("weekly", interval)
+    }
+}
+
+// 祝日を休む平日
+fn weekdays() -> RecurrencePattern {
+    RecurrencePattern {
+        skip_holidays: true,
+        ..weekly(1, vec![1, 2, 3, 4, 5])
+    }
+}
+
+// N営業日ごと
+fn business_days(interval: u32) -> RecurrencePattern {
+    RecurrencePattern {
+        weekdays_only: true,
+        skip_holidays: true,
+        ..pattern("daily", interval)
+    }
+}
+
+fn monthly(day: u32) -> RecurrencePattern {
+    RecurrencePattern {
+        day_of_month: Some(day),
+        ..pattern("monthly", 1)
+    }
+}
+
+// 「business day(s)」「working day(s)」
+fn business_day_words(sc: &Scanner, i: usize) -> Option<usize> {
+    ["business", "working", "work"].iter().find_map(|first| {
+        sc.words(i, &[first, "days"])
+            .or_else(|| sc.words(i, &[first, "day"]))
+    })
+}
+
+pub fn recurrence(sc: &Scanner, i: usize, ctx: &Ctx) -> Option<Found<RecurrencePattern>> {
+    let today_weekday = ctx.today.weekday().num_days_from_sunday();
+    let today_day = ctx.today.day();
+
+    // 日本語
+    if let Some(len) = sc.lit_any(i, &["毎日", "まいにち", "毎朝", "毎晩", "毎夜"]) {
+        return found(len, pattern("daily", 1));
+    }
+    if let Some(len) = sc.lit_any(i, &["隔日", "かくじつ"]) {
+        return found(len, pattern("daily", 2));
+    }
+    if let Some(len) = sc.lit_any(i, &["毎週末", "週末毎", "週末ごと", "しゅうまつごと"])
+    {
+        return found(len, weekly(1, vec![0, 6]));
+    }
+    if let Some(len) = sc.lit_any(i, &["毎営業日", "営業日ごと", "営業日毎"]) {
+        return found(len, business_days(1));
+    }
+    if let Some(len) = sc.lit_any(i, &["平日毎日", "毎平日", "平日", "へいじつ"]) {
+        return found(len, weekdays());
+    }
+    for (prefixes, interval) in [(&["毎週", "まいしゅう"], 1), (&["隔週", "かくしゅう"], 2)]
+    {
+        if let Some(len) = sc.lit_any(i, prefixes) {
+            let j = i + len + sc.spaces(i + len);
+            let list = weekday_list(sc, j, jp_weekday)
+                .or_else(|| weekday_list(sc, j, |sc, k| kana_weekday(sc, k, true)));
+            return match list {
+                Some((days, n)) => found(j + n - i, weekly(interval, days)),
+                None => found(len, weekly(interval, vec![today_weekday])),
+            };
+        }
+    }
+    if let Some(len) = sc.lit_any(i, &["毎月", "まいつき"]) {
+        let j = i + len;
+        if let Some((day, n)) = sc.number(j) {
+            if let Some(suffix) = sc.lit(j + n, "日") {
+                if (1..=31).contains(&day) {
+                    return found(len + n + suffix, monthly(day));
+                }
+            }
+        }
+        return found(len, monthly(today_day));
+    }
+    if let Some(len) = sc.lit_any(i, &["毎年", "まいとし", "まいねん"]) {
+        return found(len, pattern("yearly", 1));
+    }
+    // 「3日ごと」「2週間おき」「5営業日ごと」
+    if let Some((n, digits)) = sc.number(i) {
+        if let Some(unit) = sc.lit(i + digits, "営業日") {
+            let j = i + digits + unit;
+            if let Some(suffix) = sc.lit_any(j, &["ごと", "毎"]) {
+                if n >= 1 {
+                    return found(j + suffix - i, business_days(n));
+                }
+            }
+        }
+        const UNITS: &[(&[&str], &str)] = &[
+            (&["日"], "daily"),
+            (&["週間", "週"], "weekly"),
+            (&["ヶ月", "か月", "カ月", "ヵ月", "ケ月"], "monthly"),
+            (&["年"], "yearly"),
+        ];
+        for (units, kind) in UNITS {
+            let Some(unit) = sc.lit_any(i + digits, units) else {
+                continue;
+            };
+            let j = i + digits + unit;
+            let recurring = |interval: u32| match *kind {
+                "weekly" => weekly(interval, vec![today_weekday]),
+                "monthly" => monthly(today_day),
+                _ => pattern(kind, interval),
+            };
+            if let Some(suffix) = sc.lit_any(j, &["ごと", "毎"]) {
+                if n >= 1 {
+                    return found(j + suffix - i, recurring(n));
+                }
+            }
+            // 「N日おき」は「N+1日ごと」と「N日ごと」のどちらの意味でも使われる
+            if let Some(suffix) = sc.lit(j, "おき") {
+                if n >= 1 {
+                    let text = sc.text(i, j + suffix);
+                    return noted(
+                        j + suffix - i,
+                        recurring(n + 1),
+                        format!("「{}」を間隔{}として解釈しました", text, n + 1),
+                    );
+                }
+            }
+        }
+    }
+
+    // 英語
+    if let Some(len) = sc
+        .word_any(i, &["daily", "everyday"])
+        .or_else(|| sc.words(i, &["every", "day"]))
+    {
+        return found(len, pattern("daily", 1));
+    }
+    if let Some(len) = sc
+        .words(i, &["every", "weekday"])
+        .or_else(|| sc.word(i, "weekdays"))
+    {
+        return found(len, weekdays());
+    }
+    if let Some(len) = sc
+        .words(i, &["every", "weekend"])
+        .or_else(|| sc.word(i, "weekends"))
+    {
+        return found(len, weekly(1, vec![0, 6]));
+    }
+    if let Some(len) = sc.word(i, "weekly") {
+        return found(len, weekly(1, vec![today_weekday]));
+    }
+    if let Some(len) = sc.word(i, "biweekly") {
+        return found(len, weekly(2, vec![today_weekday]));
+    }
+    if let Some(len) = sc.word(i, "monthly") {
+        return found(len, monthly(today_day));
+    }
+    if let Some(len) = sc.word_any(i, &["yearly", "annually"]) {
+        return found(len, pattern("yearly", 1));
+    }
+    let every = sc.word(i, "every")?;
+    let j = i + every + sc.spaces(i + every);
+    if j == i + every {
+        return None;
+    }
+    // every other ... / every N ...
+    let (interval, j) = if let Some(len) = sc.word(j, "other") {
+        (2, j + len + sc.spaces(j + len))
+    } else if let Some((n, digits)) = sc.number(j) {
+        // 「every 15th」は毎月15日
+        if let Some(suffix) = sc.lit_any(j + digits, &["st", "nd", "rd", "th"]) {
+            if sc.word_end(j + digits + suffix) && (1..=31).contains(&n) {
+                return found(j + digits + suffix - i, monthly(n));
+            }
+        }
+        (n.max(1), j + digits + sc.spaces(j + digits))
+    } else {
+        (1, j)
+    };
+    if let Some((days, n)) = weekday_list(sc, j, en_weekday) {
+        return found(j + n - i, weekly(interval, days));
+    }
+    if let Some(len) = business_day_words(sc, j) {
+        return found(j + len - i, business_days(interval));
+    }
+    const UNITS: &[(&[&str], &str)] = &[
+        (&["days", "day"], "daily"),
+        (&["weeks", "week"], "weekly"),
+        (&["months", "month"], "monthly"),
+        (&["years", "year"], "yearly"),
+    ];
+    for (units, kind) in UNITS {
+        if let Some(len) = sc.word_any(j, units) {
+            let value = match *kind {
+                "weekly" => weekly(interval, vec![today_weekday]),
+                "monthly" => RecurrencePattern {
+                    interval,
+                    ..monthly(today_day)
+                },
+                _ => pattern(kind, interval),
+            };
+            return found(j + len - i, value);
+        }
+    }
+    None
+}
+
+// ---- 日付 ----
+
+// 「来週」「今週」の後に続く曜日
+fn week_with_weekday(
+    sc: &Scanner,
+    i: usize,
+    prefix: usize,
+    monday: NaiveDate,
+    kana: bool,
+) -> Option<(NaiveDate, usize)> {
+    // 「来週の金曜」の「の」は省略できる
+    let j = i + prefix;
+    let j = j + sc.lit(j, "の").unwrap_or(0);
+    let (weekday, len) = jp_weekday(sc, j).or_else(|| kana_weekday(sc, j, kana))?;
+    let date = monday + Duration::days(weekday.num_days_from_monday() as i64);
+    Some((date, j + len - i))
+}
+
+pub fn date(sc: &Scanner, i: usize, ctx: &Ctx) -> Option<Found<NaiveDate>> {
+    let today = ctx.today;
+    let days = |n: i64| today + Duration::days(n);
+
+    const RELATIVE: &[(&[&str], i64)] = &[
+        (&["明々後日", "明明後日", "しあさって"], 3),
+        (&["明後日", "あさって"], 2),
+        (&["明日", "あした", "あす"], 1),
+        (&["今日", "きょう", "本日"], 0),
+    ];
+    for (words, offset) in RELATIVE {
+        if let Some(len) = sc.lit_any(i, words) {
+            return found(len, days(*offset));
+        }
+    }
+
+    // 週は月曜始まりで数える
+    let sunday_note = |text: &str| {
+        (today.weekday() == Weekday::Sun)
+            .then(|| format!("「{}」は週の始まりを月曜として解釈しました", text))
+    };
+    for (prefixes, weeks) in [
+        (&["再来週", "さらいしゅう"], 2),
+        (&["来週", "らいしゅう"], 1),
+        (&["今週", "こんしゅう"], 0),
+    ] {
+        let Some(prefix) = sc.lit_any(i, prefixes) else {
+            continue;
+        };
+        let kana = sc.char_at(i).is_some_and(is_hiragana);
+        let monday = week_monday(today) + Duration::days(7 * weeks);
+        if let Some((date, len)) = week_with_weekday(sc, i, prefix, monday, kana) {
+            let text = sc.text(i, i + len);
+            let note = if date < today {
+                Some(format!("「{}」は既に過ぎています", text))
+            } else {
+                sunday_note(&text)
+            };
+            return Some(Found {
+                len,
+                value: date,
+                note,
+            });
+        }
+        let text = sc.text(i, i + prefix);
+        return match weeks {
+            0 => noted(
+                prefix,
+                weekend(today),
+                format!("「{}」を今週末として解釈しました", text),
+            ),
+            n => noted(
+                prefix,
+                days(7 * n),
+                format!("「{}」を{}週間後の同じ曜日として解釈しました", text, n),
+            ),
+        };
+    }
+    if let Some(len) = sc.lit_any(i, &["週末", "しゅうまつ"]) {
+        return match today.weekday() {
+            Weekday::Sun => noted(
+                len,
+                weekend(today),
+                "日曜日の「週末」を次の土曜日として解釈しました",
+            ),
+            _ => found(len, weekend(today)),
+        };
+    }
+
+    // 月
+    if let Some(len) = sc.lit(i, "再来月") {
+        let first = add_months(today.with_day(1)?, 2);
+        return noted(len, first, "「再来月」を再来月の1日として解釈しました");
+    }
+    for (prefixes, months) in [(&["来月", "らいげつ"], 1), (&["今月", "こんげつ"], 0)] {
+        let Some(prefix) = sc.lit_any(i, prefixes) else {
+            continue;
+        };
+        let first = add_months(today.with_day(1)?, months);
+        let j = i + prefix;
+        if let Some(suffix) = sc.lit(j, "末") {
+            return found(prefix + suffix, month_end(first));
+        }
+        if let Some((day, digits)) = sc.number(j) {
+            if let Some(suffix) = sc.lit(j + digits, "日") {
+                let date = first.with_day(day)?;
+                let len = prefix + digits + suffix;
+                if date < today {
+                    return noted(
+                        len,
+                        date,
+                        format!("「{}」は既に過ぎています", sc.text(i, i + len)),
+                    );
+                }
+                return found(len, date);
+            }
+        }
+        let text = sc.text(i, j);
+        return match months {
+            0 => noted(
+                prefix,
+                month_end(today),
+                format!("「{}」を今月末として解釈しました", text),
+            ),
+            _ => noted(
+                prefix,
+                first,
+                format!("「{}」を来月1日として解釈しました", text),
+            ),
+        };
+    }
+    if let Some(len) = sc.lit_any(i, &["月末", "げつまつ"]) {
+        return found(len, month_end(today));
+    }
+    if let Some(len) = sc.lit_any(i, &["年末", "年内"]) {
+        return found(len, NaiveDate::from_ymd_opt(today.year(), 12, 31)?);
+    }
+
+    // 「翌営業日」
+    if let Some(len) = sc.lit_any(i, &["翌営業日", "次の営業日", "よくえいぎょうび"])
+    {
+        return found(len, ctx.calendar.next_business_day(today, false));
+    }
+
+    // 「次の金曜」
+    if let Some(prefix) = sc.lit_any(i, &["次の", "つぎの"]) {
+        if let Some((weekday, len)) = jp_weekday(sc, i + prefix) {
+            return found(prefix + len, upcoming(today, weekday, false));
+        }
+    }
+
+    // 数字で始まる表現
+    if let Some(found) = numeric_date(sc, i, ctx) {
+        return Some(found);
+    }
+
+    // 曜日だけ (「金曜」「金曜日」「きんようび」)
+    let weekday = jp_weekday(sc, i)
+        .filter(|(_, len)| *len > 1)
+        .or_else(|| kana_weekday(sc, i, false));
+    if let Some((weekday, len)) = weekday {
+        return weekday_date(today, weekday, len, &sc.text(i, i + len));
+    }
+
+    english_date(sc, i, ctx)
+}
+
+fn weekday_date(
+    today: NaiveDate,
+    weekday: Weekday,
+    len: usize,
+    text: &str,
+) -> Option<Found<NaiveDate>> {
+    if today.weekday() == weekday {
+        return noted(
+            len,
+            today,
+            format!(
+                "「{}」を今日として解釈しました (来週の可能性もあります)",
+                text
+            ),
+        );
+    }
+    found(len, upcoming(today, weekday, true))
+}
+
+fn numeric_date(sc: &Scanner, i: usize, ctx: &Ctx) -> Option<Found<NaiveDate>> {
+    let today = ctx.today;
+    let (n, digits) = sc.number(i)?;
+    let j = i + digits;
+
+    // 「3営業日後」
+    if let Some(len) = sc.lit_any(j, &["営業日後", "えいぎょうびご"]) {
+        return found(
+            digits + len,
+            ctx.calendar.add_business_days(today, n as i32),
+        );
+    }
+
+    // 「3日後」「2週間後」「1ヶ月後」「1年後」
+    const AFTER: &[(&[&str], &str)] = &[
+        (&["日後", "にちご"], "day"),
+        (&["週間後", "週後", "しゅうかんご"], "week"),
+        (
+            &["ヶ月後", "か月後", "カ月後", "ヵ月後", "ケ月後", "かげつご"],
+            "month",
+        ),
+        (&["年後", "ねんご"], "year"),
+    ];
+    for (units, unit) in AFTER {
+        if let Some(len) = sc.lit_any(j, units) {
+            let date = match *unit {
+                "day" => today + Duration::days(n as i64),
+                "week" => today + Duration::days(7 * n as i64),
+                "month" => add_months(today, n),
+                _ => add_months(today, n * 12),
+            };
+            return found(digits + len, date);
+        }
+    }
+
+    // 「2026年3月5日」「2026/3/5」「2026-03-05」
+    if digits == 4 {
+        for (sep1, sep2, tail) in [("年", "月", "日"), ("/", "/", ""), ("-", "-", "")] {
+            let Some(a) = sc.lit(j, sep1) else {
+                continue;
+            };
+            let Some((month, md)) = sc.number(j + a) else {
+                continue;
+            };
+            let Some(b) = sc.lit(j + a + md, sep2) else {
+                continue;
+            };
+            let Some((day, dd)) = sc.number(j + a + md + b) else {
+                continue;
+            };
+            let end = j + a + md + b + dd;
+            let end = end
+                + if tail.is_empty() {
+                    0
+                } else {
+                    sc.lit(end, tail).unwrap_or(0)
+                };
+            if let Some(date) = NaiveDate::from_ymd_opt(n as i32, month, day) {
+                return found(end - i, date);
+            }
+        }
+        return None;
+    }
+
+    // 「3月5日」「3/5」
+    for (sep, tail) in [("月", "日"), ("/", "")] {
+        let Some(a) = sc.lit(j, sep) else {
+            continue;
+        };
+        let Some((day, dd)) = sc.number(j + a) else {
+            continue;
+        };
+        let mut end = j + a + dd;
+        if !tail.is_empty() {
+            end += sc.lit(end, tail).unwrap_or(0);
+        } else if sc.lit(end, "/").is_some() {
+            continue;
+        }
+        let (date, note) = month_day(today, n, day)?;
+        return Some(Found {
+            len: end - i,
+            value: date,
+            note,
+        });
+    }
+
+    // 「15日」 (今月、過ぎていれば来月)
+    if let Some(len) = sc.lit(j, "日") {
+        if sc
+            .char_at(j + len)
+            .is_some_and(|c| "間後ごおぶ".contains(c))
+        {
+            return None;
+        }
+        let date = today.with_day(n)?;
+        if date >= today {
+            return found(digits + len, date);
+        }
+        let next = add_months(today.with_day(1)?, 1).with_day(n)?;
+        return noted(
+            digits + len,
+            next,
+            format!("今月{}日は過ぎているため来月として解釈しました", n),
+        );
+    }
+
+    // 「20 jan」「20th january」
+    let mut k = j + sc.lit_any(j, &["st", "nd", "rd", "th"]).unwrap_or(0);
+    let spaces = sc.spaces(k);
+    k += spaces;
+    if spaces > 0 {
+        if let Some((month, len)) = en_month(sc, k) {
+            let (date, note) = month_day(today, month, n)?;
+            return Some(Found {
+                len: k + len - i,
+                value: date,
+                note,
+            });
+        }
+    }
+    None
+}
+
+fn en_month(sc: &Scanner, i: usize) -> Option<(u32, usize)> {
+    EN_MONTHS
+        .iter()
+        .find_map(|(word, month)| sc.word(i, word).map(|len| (*month, len)))
+}
+
+fn english_date(sc: &Scanner, i: usize, ctx: &Ctx) -> Option<Found<NaiveDate>> {
+    let today = ctx.today;
+    let days = |n: i64| today + Duration::days(n);
+
+    if let Some(len) = sc.words(i, &["day", "after", "tomorrow"]) {
+        return found(len, days(2));
+    }
+    for (word, offset) in [
+        ("today", 0),
+        ("tonight", 0),
+        ("tomorrow", 1),
+        ("tmrw", 1),
+        ("tmr", 1),
+    ] {
+        if let Some(len) = sc.word(i, word) {
+            return found(len, days(offset));
+        }
+    }
+    if let Some(len) = sc
+        .words(i, &["end", "of", "month"])
+        .or_else(|| sc.words(i, &["end", "of", "the", "month"]))
+        .or_else(|| sc.word(i, "eom"))
+    {
+        return found(len, month_end(today));
+    }
+    if let Some(len) = sc
+        .words(i, &["this", "weekend"])
+        .or_else(|| sc.word(i, "weekend"))
+    {
+        return found(len, weekend(today));
+    }
+    if let Some(len) = sc.words(i, &["next", "week"]) {
+        return noted(len, days(7), "「next week」を7日後として解釈しました");
+    }
+    if let Some(len) = sc.words(i, &["this", "week"]) {
+        return noted(
+            len,
+            weekend(today),
+            "「this week」を今週末として解釈しました",
+        );
+    }
+    if let Some(len) = sc.words(i, &["next", "month"]) {
+        let first = add_months(today.with_day(1)?, 1);
+        return noted(len, first, "「next month」を来月1日として解釈しました");
+    }
+
+    if let Some(len) = sc.words(i, &["next", "business", "day"]) {
+        return found(len, ctx.calendar.next_business_day(today, false));
+    }
+
+    // in N days / weeks / months / years / business days
+    if let Some(len) = sc.word(i, "in") {
+        let j = i + len + sc.spaces(i + len);
+        let (n, digits) = sc.number(j)?;
+        let k = j + digits + sc.spaces(j + digits);
+        if let Some(w) = business_day_words(sc, k) {
+            return found(k + w - i, ctx.calendar.add_business_days(today, n as i32));
+        }
+        let units: &[(&[&str], u32)] = &[
+            (&["days", "day"], 1),
+            (&["weeks", "week"], 7),
+            (&["months", "month"], 0),
+            (&["years", "year"], 12),
+        ];
+        for (words, unit) in units {
+            if let Some(w) = sc.word_any(k, words) {
+                let date = match unit {
+                    1 | 7 => days(n as i64 * *unit as i64),
+                    0 => add_months(today, n),
+                    _ => add_months(today, n * 12),
+                };
+                return found(k + w - i, date);
+            }
+        }
+        return None;
+    }
+
+    // next friday / this friday / friday
+    if let Some(len) = sc.word(i, "next") {
+        let j = i + len + sc.spaces(i + len);
+        let (weekday, w) = en_weekday(sc, j)?;
+        let date = week_monday(today) + Duration::days(7 + weekday.num_days_from_monday() as i64);
+        let soonest = upcoming(today, weekday, false);
+        let text = sc.text(i, j + w);
+        if date != soonest {
+            return noted(
+                j + w - i,
+                date,
+                format!(
+                    "「{}」を来週として解釈しました ({} の可能性もあります)",
+                    text,
+                    fmt(soonest)
+                ),
+            );
+        }
+        return found(j + w - i, date);
+    }
+    if let Some(len) = sc.word(i, "this") {
+        let j = i + len + sc.spaces(i + len);
+        let (weekday, w) = en_weekday(sc, j)?;
+        return found(j + w - i, upcoming(today, weekday, true));
+    }
+    if let Some((weekday, len)) = en_weekday(sc, i) {
+        return weekday_date(today, weekday, len, &sc.text(i, i + len));
+    }
+
+    // jan 20 / january 20th
+    let (month, len) = en_month(sc, i)?;
+    let j = i + len + sc.spaces(i + len);
+    let (day, digits) = sc.number(j)?;
+    let mut end = j + digits;
+    end += sc.lit_any(end, &["st", "nd", "rd", "th"]).unwrap_or(0);
+    if !sc.word_end(end) {
+        return None;
+    }
+    let (date, note) = month_day(today, month, day)?;
+    Some(Found {
+        len: end - i,
+        value: date,
+        note,
+    })
+}
+
+// ---- 時刻 ----
+
+pub fn time(sc: &Scanner, i: usize, _ctx: &Ctx) -> Option<Found<TimeValue>> {
+    // 「30分後」「2時間後」
+    if let Some((n, digits)) = sc.number(i) {
+        let j = i + digits;
+        if let Some(len) = sc.lit_any(j, &["分後", "ふんご", "ぷんご"]) {
+            return found(digits + len, TimeValue::After(n as i64));
+        }
+        if let Some(len) = sc.lit_any(j, &["時間後", "じかんご"]) {
+            return found(digits + len, TimeValue::After(n as i64 * 60));
+        }
+    }
+    if let Some(len) = sc.word(i, "in") {
+        let j = i + len + sc.spaces(i + len);
+        if let Some((n, digits)) = sc.number(j) {
+            let k = j + digits + sc.spaces(j + digits);
+            if let Some(w) = sc.word_any(k, &["minutes", "minute", "mins", "min"]) {
+                return found(k + w - i, TimeValue::After(n as i64));
+            }
+            if let Some(w) = sc.word_any(k, &["hours", "hour", "hrs", "hr"]) {
+                return found(k + w - i, TimeValue::After(n as i64 * 60));
+            }
+        }
+    }
+
+    if let Some(len) = sc.lit(i, "正午").or_else(|| sc.word(i, "noon")) {
+        return found(
+            len,
+            TimeValue::Clock {
+                hour: 12,
+                minute: 0,
+            },
+        );
+    }
+    if let Some(len) = sc.word(i, "midnight") {
+        return found(
+            len,
+            TimeValue::Clock {
+                hour: 23,
+                minute: 59,
+            },
+        );
+    }
+
+    // 「午後3時半」「夜9時」「10:30」
+    const PREFIXES: &[&str] = &["午前", "午後", "夕方", "深夜", "夜中", "朝", "昼", "夜"];
+    let prefix = PREFIXES
+        .iter()
+        .find_map(|p| sc.lit(i, p).map(|len| (*p, len)));
+    let j = i + prefix.map_or(0, |(_, len)| len);
+    let (hour, digits) = sc.number(j)?;
+    let mut k = j + digits;
+    let minute;
+    let mut english = false;
+    if let Some(len) = sc.lit(k, "時") {
+        if sc.char_at(k + len) == Some('間') {
+            return None;
+        }
+        k += len;
+        if let Some(len) = sc.lit(k, "半") {
+            k += len;
+            minute = 30;
+        } else if let Some((m, md)) = sc.number(k) {
+            let unit = sc.lit(k + md, "分")?;
+            k += md + unit;
+            minute = m;
+        } else {
+            minute = 0;
+        }
+    } else if let Some(colon) = sc.lit(k, ":") {
+        let (m, md) = sc.number(k + colon)?;
+        if md != 2 {
+            return None;
+        }
+        k += colon + md;
+        minute = m;
+        english = true;
+    } else {
+        minute = 0;
+        english = true;
+    }
+
+    let mut hour = hour;
+    let mut meridiem = false;
+    if english {
+        let spaced = k + sc.spaces(k);
+        let ampm = [("am", false), ("a.m.", false), ("pm", true), ("p.m.", true)]
+            .iter()
+            .find_map(|(w, pm)| {
+                let len = sc.lit(spaced, w)?;
+                sc.word_end(spaced + len).then_some((len, *pm))
+            });
+        match ampm {
+            Some((len, pm)) => {
+                if hour == 0 || hour > 12 {
+                    return None;
+                }
+                hour = match (pm, hour) {
+                    (true, 12) => 12,
+                    (true, h) => h + 12,
+                    (false, 12) => 0,
+                    (false, h) => h,
+                };
+                k = spaced + len;
+                meridiem = true;
+            }
+            // 「10:30」以外の裸の数字は時刻ではない
+            None if k == j + digits => return None,
+            None => {}
+        }
+    }
+    if minute >= 60 {
+        return None;
+    }
+    if !meridiem {
+        hour = match prefix.map(|(p, _)| p) {
+            Some("午後" | "夕方" | "夜") if hour < 12 => hour + 12,
+            Some("昼") if hour < 6 => hour + 12,
+            Some("午前") if hour == 12 => 0,
+            _ => hour,
+        };
+    }
+    if hour >= 24 {
+        return None;
+    }
+    let value = TimeValue::Clock { hour, minute };
+    if prefix.is_none() && !meridiem && (1..=6).contains(&hour) {
+        let text = sc.text(i, k);
+        return noted(
+            k - i,
+            value,
+            format!(
+                "「{}」を午前として解釈しました (午後{}時の可能性もあります)",
+                text, hour
+            ),
+        );
+    }
+    found(k - i, value)
+}
+
+// ---- 所要時間 ----
+
+pub fn duration(sc: &Scanner, i: usize, _ctx: &Ctx) -> Option<Found<u32>> {
+    let (n, digits) = sc.number(i)?;
+    let j = i + digits;
+
+    // 「1.5時間」
+    if let Some(dot) = sc.lit(j, ".") {
+        let (frac, fd) = sc.number(j + dot)?;
+        let k = j + dot + fd;
+        let fraction = frac as f64 / 10f64.powi(fd as i32);
+        let hours = n as f64 + fraction;
+        let unit = sc.lit(k, "時間").or_else(|| hour_word(sc, k))?;
+        return found(k + unit - i, (hours * 60.0).round() as u32);
+    }
+
+    if let Some(len) = sc.lit(j, "時間") {
+        let k = j + len;
+        if sc.char_at(k).is_some_and(|c| "後前ご".contains(c)) {
+            return None;
+        }
+        if let Some(half) = sc.lit(k, "半") {
+            return found(k + half - i, n * 60 + 30);
+        }
+        if let Some((m, md)) = sc.number(k) {
+            if let Some(unit) = sc.lit(k + md, "分") {
+                return found(k + md + unit - i, n * 60 + m);
+            }
+        }
+        return found(k - i, n * 60);
+    }
+    if let Some(len) = sc.lit(j, "分") {
+        if sc
+            .char_at(j + len)
+            .is_some_and(|c| "後前ごおぷ".contains(c))
+        {
+            return None;
+        }
+        return found(digits + len, n);
+    }
+
+    // 「90min」「2 hours」「1h30m」
+    let k = j + sc.spaces(j);
+    if let Some(len) = hour_word(sc, k) {
+        let mut end = k + len;
+        let mut minutes = n * 60;
+        if let Some((m, md)) = sc.number(end) {
+            let unit = sc
+                .lit_any(end + md, &["mins", "min", "m"])
+                .filter(|l| sc.word_end(end + md + l));
+            if let Some(unit) = unit {
+                minutes += m;
+                end += md + unit;
+            }
+        }
+        return found(end - i, minutes);
+    }
+    let len = sc
+        .lit_any(k, &["minutes", "minute", "mins", "min", "m"])
+        .filter(|l| sc.word_end(k + l))?;
+    found(k + len - i, n)
+}
+
+// 数字の直後の「h」「hours」 (「1h30m」のように分が続いてもよい)
+fn hour_word(sc: &Scanner, i: usize) -> Option<usize> {
+    let len = sc.lit_any(i, &["hours", "hour", "hrs", "hr", "h"])?;
+    let end = i + len;
+    let follows_minutes = sc.char_at(end).is_some_and(|c| c.is_ascii_digit());
+    (sc.word_end(end) || follows_minutes).then_some(len)
+}
@@ -0,0 +1,238 @@
+// 入力文字列の走査
+// 解釈済みの文字に印を付け、残った文字をタスク名にする
+
+use chrono::Weekday;
+
+pub const JP_WEEKDAYS: [(char, Weekday); 7] = [
+    ('日', Weekday::Sun),
+    ('月', Weekday::Mon),
+    ('火', Weekday::Tue),
+    ('水', Weekday::Wed),
+    ('木', Weekday::Thu),
+    ('金', Weekday::Fri),
+    ('土', Weekday::Sat),
+];
+
+pub const KANA_WEEKDAYS: [(&str, Weekday); 7] = [
+    ("にち", Weekday::Sun),
+    ("げつ", Weekday::Mon),
+    ("すい", Weekday::Wed),
+    ("もく", Weekday::Thu),
+    ("きん", Weekday::Fri),
+    ("ど", Weekday::Sat),
+    ("か", Weekday::Tue),
+];
+
+// 長い綴りを先に並べる
+pub const EN_WEEKDAYS: &[(&str, Weekday)] = &[
+    ("monday", Weekday::Mon),
+    ("mon", Weekday::Mon),
+    ("tuesday", Weekday::Tue),
+    ("tues", Weekday::Tue),
+    ("tue", Weekday::Tue),
+    ("wednesday", Weekday::Wed),
+    ("wed", Weekday::Wed),
+    ("thursday", Weekday::Thu),
+    ("thurs", Weekday::Thu),
+    ("thur", Weekday::Thu),
+    ("thu", Weekday::Thu),
+    ("friday", Weekday::Fri),
+    ("fri", Weekday::Fri),
+    ("saturday", Weekday::Sat),
+    ("sat", Weekday::Sat),
+    ("sunday", Weekday::Sun),
+    ("sun", Weekday::Sun),
+];
+
+pub const EN_MONTHS: &[(&str, u32)] = &[
+    ("january", 1),
+    ("jan", 1),
+    ("february", 2),
+    ("feb", 2),
+    ("march", 3),
+    ("mar", 3),
+    ("april", 4),
+    ("apr", 4),
+    ("may", 5),
+    ("june", 6),
+    ("jun", 6),
+    ("july", 7),
+    ("jul", 7),
+    ("august", 8),
+    ("aug", 8),
+    ("september", 9),
+    ("sept", 9),
+    ("sep", 9),
+    ("october", 10),
+    ("oct", 10),
+    ("november", 11),
+    ("nov", 11),
+    ("december", 12),
+    ("dec", 12),
+];
+
+// 全角英数字・記号と全角スペースを半角にする
+// 1文字を1文字に置き換えるため、元の入力と文字位置がずれない
+fn normalize_char(c: char) -> char {
+    match c {
+        '\u{3000}' => ' ',
+        '\u{FF01}'..='\u{FF5E}' => char::from_u32(c as u32 - 0xFEE0).unwrap_or(c),
+        _ => c,
+    }
+}
+
+// 漢字・かな・全角記号 (この間は空白なしで文が続く)
+fn is_cjk(c: char) -> bool {
+    c >= '\u{2E80}'
+}
+
+pub fn is_hiragana(c: char) -> bool {
+    ('\u{3041}'..='\u{309F}').contains(&c)
+}
+
+pub struct Scanner {
+    chars: Vec<char>,
+    // 英語の照合用 (ASCIIだけ小文字にする)
+    lower: Vec<char>,
+    used: Vec<bool>,
+}
+
+impl Scanner {
+    pub fn new(input: &str) -> Self {
+        let chars: Vec<char> = input.chars().map(normalize_char).collect();
+        let lower = chars.iter().map(|c| c.to_ascii_lowercase()).collect();
+        let used = vec![false; chars.len()];
+        Self { chars, lower, used }
+    }
+
+    pub fn len(&self) -> usize {
+        self.chars.len()
+    }
+
+    pub fn is_free(&self, i: usize) -> bool {
+        i < self.chars.len() && !self.used[i]
+    }
+
+    // 未使用の文字 (英字は小文字)
+    pub fn char_at(&self, i: usize) -> Option<char> {
+        self.is_free(i).then(|| self.lower[i])
+    }
+
+    pub fn text(&self, start: usize, end: usize) -> String {
+        self.chars[start..end.min(self.chars.len())]
+            .iter()
+            .collect()
+    }
+
+    // 文字列がそのまま続けばその長さ
+    pub fn lit(&self, i: usize, s: &str) -> Option<usize> {
+        let mut j = i;
+        for c in s.chars() {
+            if self.char_at(j) != Some(c) {
+                return None;
+            }
+            j += 1;
+        }
+        Some(j - i)
+    }
+
+    pub fn lit_any(&self, i: usize, candidates: &[&str]) -> Option<usize> {
+        candidates.iter().find_map(|s| self.lit(i, s))
+    }
+
+    // 英単語の途中でないか
+    pub fn word_start(&self, i: usize) -> bool {
+        i == 0 || self.used[i - 1] || !self.lower[i - 1].is_ascii_alphanumeric()
+    }
+
+    pub fn word_end(&self, i: usize) -> bool {
+        i >= self.len() || self.used[i] || !self.lower[i].is_ascii_alphanumeric()
+    }
+
+    // 前後が英数字でない単語
+    pub fn word(&self, i: usize, w: &str) -> Option<usize> {
+        if !self.word_start(i) {
+            return None;
+        }
+        let len = self.lit(i, w)?;
+        self.word_end(i + len).then_some(len)
+    }
+
+    pub fn word_any(&self, i: usize, candidates: &[&str]) -> Option<usize> {
+        candidates.iter().find_map(|w| self.word(i, w))
+    }
+
+    // 空白で区切られた単語の並び
+    pub fn words(&self, i: usize, words: &[&str]) -> Option<usize> {
+        let mut j = i;
+        for (k, w) in words.iter().enumerate() {
+            if k > 0 {
+                let spaces = self.spaces(j);
+                if spaces == 0 {
+                    return None;
+                }
+                j += spaces;
+            }
+            j += self.word(j, w)?;
+        }
+        Some(j - i)
+    }
+
+    pub fn spaces(&self, i: usize) -> usize {
+        (i..self.len())
+            .take_while(|j| self.char_at(*j).is_some_and(char::is_whitespace))
+            .count()
+    }
+
+    // 数字の並び (直前が数字なら数の途中なので読まない)
+    pub fn number(&self, i: usize) -> Option<(u32, usize)> {
+        if i > 0 && self.lower[i - 1].is_ascii_digit() {
+            return None;
+        }
+        let len = (i..self.len())
+            .take_while(|j| self.char_at(*j).is_some_and(|c| c.is_ascii_digit()))
+            .count();
+        if len == 0 || len > 4 {
+            return None;
+        }
+        let value = self.text(i, i + len).parse().ok()?;
+        Some((value, len))
+    }
+
+    // 空白で区切られた語の先頭か
+    pub fn token_start(&self, i: usize) -> bool {
+        i == 0 || self.used[i - 1] || self.chars[i - 1].is_whitespace()
+    }
+
+    pub fn token_end(&self, i: usize) -> bool {
+        i >= self.len() || self.used[i] || self.chars[i].is_whitespace()
+    }
+
+    pub fn consume(&mut self, start: usize, end: usize) {
+        for used in &mut self.used[start..end.min(self.chars.len())] {
+            *used = true;
+        }
+    }
+
+    // 解釈されなかった文字をつなげる
+    // 取り除いた部分の前後が英数字どうしなら空白を入れ、日本語なら詰める
+    pub fn remaining_text(&self) -> String {
+        let mut text = String::new();
+        let mut gap = false;
+        for (i, c) in self.chars.iter().enumerate() {
+            if self.used[i] {
+                gap = true;
+                continue;
+            }
+            if gap {
+                let prev = text.chars().last();
+                if prev.is_some_and(|p| !is_cjk(p) && !is_cjk(*c)) {
+                    text.push(' ');
+                }
+                gap = false;
+            }
+            text.push(*c);
+        }
+        text.split_whitespace().collect::<Vec<_>>().join(" ")
+    }
+}
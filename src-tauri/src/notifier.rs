@@ -0,0 +1,738 @@
+// Webhook通知 (Slack / ntfy / Mattermost / 任意のWebhook)
+// 送信内容はテンプレートから生成し、アウトボックス経由で配信する
+
+use chrono::{Local, TimeZone};
+use std::collections::BTreeMap;
+use std::sync::Mutex;
+use tauri::Manager;
+
+use crate::discord::DeliveryError;
+use crate::models::Todo;
+use crate::outbox::{NewOutboxMessage, OutboxChannel, OutboxState};
+
+const TARGETS_FILE: &str = "webhook-targets.json";
+
+#[derive(serde::Serialize, serde::Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum WebhookKind {
+    Slack,
+    Ntfy,
+    Mattermost,
+    Generic,
+}
+
+impl WebhookKind {
+    fn default_template(&self) -> &'static str {
+        match self {
+            WebhookKind::Slack | WebhookKind::Mattermost => {
+                r#"{"text": "{{title}}: *{{text}}*\n期日: {{dueDate}} / {{priority}} {{labelTags}}\n{{message}}"}"#
+            }
+            WebhookKind::Ntfy => "{{text}}\n期日: {{dueDate}} / {{priority}}\n{{message}}",
+            WebhookKind::Generic => {
+                r#"{"event": "{{kind}}", "message": "{{message}}", "task": {"id": "{{id}}", "text": "{{text}}", "description": "{{description}}", "dueDate": "{{dueDateIso}}", "priority": {{priorityNumber}}, "labels": {{labelsJson}}}}"#
+            }
+        }
+    }
+
+    // ntfy は本文がプレーンテキスト、それ以外はJSON
+    fn is_json(&self) -> bool {
+        !matches!(self, WebhookKind::Ntfy)
+    }
+}
+
+// どのタスクを通知するかの条件 (すべて空なら全タスク)
+#[derive(serde::Serialize, serde::Deserialize, Clone, Debug, Default)]
+#[serde(rename_all = "camelCase", default)]
+pub struct WebhookRules {
+    // いずれかのラベルを持つタスクのみ
+    pub labels: Vec<String>,
+    // この優先度以上 (P1=1 〜 P4=4) のタスクのみ
+    pub max_priority: Option<u8>,
+    // 対象とする通知の種類 (reminder / dueDate / morning など)
+    pub kinds: Vec<String>,
+}
+
+impl WebhookRules {
+    fn matches(&self, kind: &str, todo: Option<&Todo>) -> bool {
+        if !self.kinds.is_empty() && !self.kinds.iter().any(|k| k == kind) {
+            return false;
+        }
+        let Some(todo) = todo else {
+            // タスクに紐づかない通知 (朝の挨拶など) はラベル・優先度の条件を問わない
+            return true;
+        };
+        if self.max_priority.is_some_and(|max| todo.priority > max) {
+            return false;
+        }
+        self.labels.is_empty()
+            || self
+                .labels
+                .iter()
+                .any(|label| todo.labels.iter().any(|l| l == label))
+    }
+}
+
+#[derive(serde::Serialize, serde::Deserialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct WebhookTarget {
+    pub id: String,
+    pub name: String,
+    pub kind: WebhookKind,
+    pub url: String,
+    #[serde(default = "default_enabled")]
+    pub enabled: bool,
+    // 未指定なら種類ごとの既定テンプレートを使う
+    #[serde(default)]
+    pub template: Option<String>,
+    #[serde(default)]
+    pub headers: BTreeMap<String, String>,
+    #[serde(default)]
+    pub rules: WebhookRules,
+}
+
+fn default_enabled() -> bool {
+    true
+}
+
+// テンプレートに差し込む値
+pub struct NotificationContext<'a> {
+    pub kind: &'a str,
+    pub title: &'a str,
+    pub message: &'a str,
+    pub todo: Option<&'a Todo>,
+}
+
+impl NotificationContext<'_> {
+    fn variables(&self) -> Vec<(&'static str, String, bool)> {
+        let todo = self.todo;
+        let due = todo
+            .and_then(|t| t.due_date)
+            .and_then(|ms| Local.timestamp_millis_opt(ms).single());
+        let labels: Vec<String> = todo.map(|t| t.labels.clone()).unwrap_or_default();
+        let priority = todo.map(|t| t.priority).unwrap_or(4);
+
+        // (名前, 値, JSONにそのまま埋め込むか)
+        vec![
+            ("kind", self.kind.to_string(), false),
+            ("title", self.title.to_string(), false),
+            ("message", self.message.to_string(), false),
+            ("id", todo.map(|t| t.id.clone()).unwrap_or_default(), false),
+            (
+                "text",
+                todo.map(|t| t.text.clone()).unwrap_or_default(),
+                false,
+            ),
+            (
+                "description",
+                todo.map(|t| t.description.clone()).unwrap_or_default(),
+                false,
+            ),
+            (
+                "dueDate",
+                todo.and_then(|t| crate::timezone::format_due(t, "%Y-%m-%d %H:%M"))
+                    .unwrap_or_else(|| "なし".to_string()),
+                false,
+            ),
+            (
+                "dueDateIso",
+                due.map(|d| d.to_rfc3339()).unwrap_or_default(),
+                false,
+            ),
+            ("priority", format!("P{}", priority), false),
+            ("priorityNumber", priority.to_string(), true),
+            ("labels", labels.join(", "), false),
+            (
+                "labelTags",
+                labels
+                    .iter()
+                    .map(|l| format!("#{}", l))
+                    .collect::<Vec<_>>()
+                    .join(" "),
+                false,
+            ),
+            (
+                "labelsJson",
+                serde_json::to_string(&labels).unwrap_or_else(|_| "[]".to_string()),
+                true,
+            ),
+        ]
+    }
+}
+
+// {{name}} を置き換える。JSONテンプレートでは値を文字列としてエスケープする
+// 置き換えは1回だけ走査して行うため、値に含まれる {{...}} はそのまま残る
+pub fn render_template(
+    template: &str,
+    context: &NotificationContext,
+    json: bool,
+) -> Result<String, String> {
+    let variables = context.variables();
+    let mut rendered = String::with_capacity(template.len());
+    let mut rest = template;
+    while let Some(start) = rest.find("{{") {
+        let Some(len) = rest[start + 2..].find("}}") else {
+            break;
+        };
+        let name = &rest[start + 2..start + 2 + len];
+        rendered.push_str(&rest[..start]);
+        match variables.iter().find(|(n, _, _)| *n == name) {
+            Some((_, value, raw)) if json && !raw => {
+                let quoted = serde_json::to_string(value).unwrap_or_default();
+                rendered.push_str(&quoted[1..quoted.len() - 1]);
+            }
+            Some((_, value, _)) => rendered.push_str(value),
+            // 未知の名前はそのまま残す
+            None => rendered.push_str(&rest[start..start + 2 + len + 2]),
+        }
+        rest = &rest[start + 2 + len + 2..];
+    }
+    rendered.push_str(rest);
+
+    if json {
+        serde_json::from_str::<serde_json::Value>(&rendered)
+            .map_err(|e| format!("テンプレートから生成したJSONが不正です: {}", e))?;
+    }
+    Ok(rendered)
+}
+
+fn build_payload(
+    target: &WebhookTarget,
+    context: &NotificationContext,
+) -> Result<serde_json::Value, String> {
+    let template = target
+        .template
+        .as_deref()
+        .filter(|t| !t.trim().is_empty())
+        .unwrap_or_else(|| target.kind.default_template());
+    let body = render_template(template, context, target.kind.is_json())?;
+
+    let mut headers = target.headers.clone();
+    if target.kind == WebhookKind::Ntfy {
+        headers
+            .entry("Title".to_string())
+            .or_insert_with(|| context.title.to_string());
+        if let Some(todo) = context.todo {
+            // ntfy の優先度は 5 (最高) 〜 1 (最低)。5 は使わず P1→4, P2→3, P3→2, P4→1 に対応させる
+            let ntfy_priority = 5u8.saturating_sub(todo.priority).max(1);
+            headers
+                .entry("Priority".to_string())
+                .or_insert_with(|| ntfy_priority.to_string());
+            if !todo.labels.is_empty() {
+                headers
+                    .entry("Tags".to_string())
+                    .or_insert_with(|| todo.labels.join(","));
+            }
+        }
+    }
+
+    Ok(serde_json::json!({ "body": body, "headers": headers }))
+}
+
+pub async fn send_payload(
+    client: &reqwest::Client,
+    target: &WebhookTarget,
+    payload: &serde_json::Value,
+) -> Result<(), DeliveryError> {
+    let body = payload["body"].as_str().unwrap_or_default().to_string();
+    let content_type = if target.kind.is_json() {
+        "application/json"
+    } else {
+        "text/plain; charset=utf-8"
+    };
+
+    let mut request = client
+        .post(&target.url)
+        .header("Content-Type", content_type)
+        .body(body);
+    if let Some(headers) = payload["headers"].as_object() {
+        for (name, value) in headers {
+            // HTTPヘッダーはASCIIのみのため、日本語のタイトルなどはRFC 2047形式にする
+            let value = value.as_str().unwrap_or_default();
+            let value = if value.is_ascii() {
+                value.to_string()
+            } else {
+                format!("=?UTF-8?B?{}?=", base64_encode(value.as_bytes()))
+            };
+            request = request.header(name.as_str(), value);
+        }
+    }
+
+    let response = request
+        .send()
+        .await
+        .map_err(|e| DeliveryError::Retryable(format!("Webhookリクエストエラー: {}", e)))?;
+    if !response.status().is_success() {
+        let status = response.status();
+        let body = response.text().await.unwrap_or_default();
+        return Err(DeliveryError::from_status(status, body));
+    }
+    Ok(())
+}
+
+fn base64_encode(bytes: &[u8]) -> String {
+    const TABLE: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+    let mut out = String::with_capacity(bytes.len().div_ceil(3) * 4);
+    for chunk in bytes.chunks(3) {
+        let b = [
+            chunk[0],
+            chunk.get(1).copied().unwrap_or(0),
+            chunk.get(2).copied().unwrap_or(0),
+        ];
+        let n = (b[0] as u32) << 16 | (b[1] as u32) << 8 | b[2] as u32;
+        for i in 0..4 {
+            if i <= chunk.len() {
+                out.push(TABLE[(n >> (18 - i * 6)) as usize & 0x3F] as char);
+            } else {
+                out.push('=');
+            }
+        }
+    }
+    out
+}
+
+pub struct NotifierState {
+    targets: Mutex<Vec<WebhookTarget>>,
+}
+
+impl NotifierState {
+    pub fn load(app: &tauri::AppHandle) -> Self {
+        let targets = crate::store::load_json(app, TARGETS_FILE).unwrap_or_else(|e| {
+            println!("[Notifier Backend] 設定読み込み失敗: {}", e);
+            Vec::new()
+        });
+        Self {
+            targets: Mutex::new(targets),
+        }
+    }
+
+    pub fn target(&self, id: &str) -> Option<WebhookTarget> {
+        self.targets
+            .lock()
+            .unwrap()
+            .iter()
+            .find(|t| t.id == id)
+            .cloned()
+    }
+
+    // 条件に合う全ての送信先に通知をキューイングし、件数を返す
+    // idempotency_key は送信先ごとに判定する (同じキーで送信済みの送信先には送らない)
+    pub fn notify(
+        &self,
+        app: &tauri::AppHandle,
+        context: &NotificationContext,
+        idempotency_key: Option<&str>,
+    ) -> Result<usize, String> {
+        let targets: Vec<WebhookTarget> = self
+            .targets
+            .lock()
+            .unwrap()
+            .iter()
+            .filter(|t| t.enabled && t.rules.matches(context.kind, context.todo))
+            .cloned()
+            .collect();
+
+        // テンプレートが壊れている送信先は飛ばし、他の送信先には送る
+        let messages: Vec<NewOutboxMessage> = targets
+            .iter()
+            .filter_map(|target| match build_payload(target, context) {
+                Ok(payload) => Some(NewOutboxMessage {
+                    channel: OutboxChannel::Webhook {
+                        target_id: target.id.clone(),
+                    },
+                    task_id: context.todo.map(|t| t.id.clone()),
+                    kind: context.kind.to_string(),
+                    idempotency_key: idempotency_key.map(str::to_string),
+                    payload,
+                    expires_at: None,
+                }),
+                Err(e) => {
+                    println!(
+                        "[Notifier Backend] {} の送信内容を作れません: {}",
+                        target.name, e
+                    );
+                    None
+                }
+            })
+            .collect();
+
+        let outbox = app.state::<OutboxState>();
+        let mut count = 0;
+        for message in messages {
+            if outbox.enqueue(app, message)?.is_queued() {
+                count += 1;
+            }
+        }
+        Ok(count)
+    }
+}
+
+#[tauri::command]
+pub fn notifier_list_targets(state: tauri::State<'_, NotifierState>) -> Vec<WebhookTarget> {
+    state.targets.lock().unwrap().clone()
+}
+
+#[tauri::command]
+pub fn notifier_save_target(
+    app: tauri::AppHandle,
+    state: tauri::State<'_, NotifierState>,
+    target: WebhookTarget,
+) -> Result<(), String> {
+    if !target.url.starts_with("http://") && !target.url.starts_with("https://") {
+        return Err("WebhookのURLは http:// または https:// で始めてください".to_string());
+    }
+    if let Some(template) = target.template.as_deref().filter(|t| !t.trim().is_empty()) {
+        let sample = Todo {
+            text: "サンプル".to_string(),
+            ..Default::default()
+        };
+        let context = NotificationContext {
+            kind: "test",
+            title: "Calm Todo",
+            message: "",
+            todo: Some(&sample),
+        };
+        render_template(template, &context, target.kind.is_json())?;
+    }
+
+    let mut targets = state.targets.lock().unwrap();
+    match targets.iter_mut().find(|t| t.id == target.id) {
+        Some(existing) => *existing = target,
+        None => targets.push(target),
+    }
+    crate::store::save_json(&app, TARGETS_FILE, &*targets)
+}
+
+#[tauri::command]
+pub fn notifier_delete_target(
+    app: tauri::AppHandle,
+    state: tauri::State<'_, NotifierState>,
+    target_id: String,
+) -> Result<(), String> {
+    let mut targets = state.targets.lock().unwrap();
+    targets.retain(|t| t.id != target_id);
+    crate::store::save_json(&app, TARGETS_FILE, &*targets)
+}
+
+// アウトボックスを通さずに直接送信し、結果をすぐに返す
+#[tauri::command]
+pub async fn notifier_send_test(
+    state: tauri::State<'_, NotifierState>,
+    target_id: String,
+) -> Result<String, String> {
+    let target = state
+        .target(&target_id)
+        .ok_or_else(|| "送信先が見つかりません".to_string())?;
+    let sample = Todo {
+        id: "test".to_string(),
+        text: "テスト通知".to_string(),
+        description: "Calm Todo からのテスト送信です".to_string(),
+        priority: 1,
+        due_date: Some(Local::now().timestamp_millis()),
+        labels: vec!["テスト".to_string()],
+        ..Default::default()
+    };
+    let context = NotificationContext {
+        kind: "test",
+        title: "✅ 接続テスト",
+        message: "このメッセージが届いていれば設定は正常です",
+        todo: Some(&sample),
+    };
+    let payload = build_payload(&target, &context)?;
+
+    send_payload(&reqwest::Client::new(), &target, &payload)
+        .await
+        .map_err(|e| e.message().to_string())?;
+    Ok(format!("送信成功: {}", target.name))
+}
+
+#[tauri::command]
+pub fn notifier_notify(
+    app: tauri::AppHandle,
+    state: tauri::State<'_, NotifierState>,
+    kind: String,
+    title: String,
+    message: String,
+    task_id: Option<String>,
+    idempotency_key: Option<String>,
+) -> Result<usize, String> {
+    let todo = match task_id {
+        Some(id) => Some(
+            app.state::<crate::todos::TodoState>()
+                .find(&id)
+                .ok_or_else(|| format!("タスクが見つかりません: {}", id))?,
+        ),
+        None => None,
+    };
+    state.notify(
+        &app,
+        &NotificationContext {
+            kind: &kind,
+            title: &title,
+            message: &message,
+            todo: todo.as_ref(),
+        },
+        idempotency_key.as_deref(),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::{BufRead, BufReader, Read, Write};
+    use std::net::TcpListener;
+
+    struct Captured {
+        headers: BTreeMap<String, String>,
+        body: String,
+    }
+
+    // 1件だけリクエストを受けて 200 を返すローカルのHTTPサーバー
+    fn capture_one() -> (String, std::thread::JoinHandle<Captured>) {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}/hook", listener.local_addr().unwrap());
+        let handle = std::thread::spawn(move || {
+            let (stream, _) = listener.accept().unwrap();
+            let mut reader = BufReader::new(stream);
+            let mut headers = BTreeMap::new();
+            let mut line = String::new();
+            reader.read_line(&mut line).unwrap();
+            loop {
+                line.clear();
+                reader.read_line(&mut line).unwrap();
+                let line = line.trim_end();
+                if line.is_empty() {
+                    break;
+                }
+                let (name, value) = line.split_once(':').unwrap();
+                headers.insert(name.to_ascii_lowercase(), value.trim().to_string());
+            }
+            let length: usize = headers["content-length"].parse().unwrap();
+            let mut body = vec![0; length];
+            reader.read_exact(&mut body).unwrap();
+            reader
+                .get_mut()
+                .write_all(b"HTTP/1.1 200 OK\r\nContent-Length: 0\r\nConnection: close\r\n\r\n")
+                .unwrap();
+            Captured {
+                headers,
+                body: String::from_utf8(body).unwrap(),
+            }
+        });
+        (url, handle)
+    }
+
+    fn target(kind: WebhookKind, url: String) -> WebhookTarget {
+        WebhookTarget {
+            id: "t".to_string(),
+            name: "test".to_string(),
+            kind,
+            url,
+            enabled: true,
+            template: None,
+            headers: BTreeMap::new(),
+            rules: WebhookRules::default(),
+        }
+    }
+
+    fn sample() -> Todo {
+        Todo {
+            id: "task-1".to_string(),
+            text: r#"見積もり "A案" {{dueDate}}"#.to_string(),
+            description: "1行目\n2行目".to_string(),
+            priority: 1,
+            due_date: Some(1_767_225_600_000),
+            labels: vec!["仕事".to_string(), "急ぎ".to_string()],
+            ..Default::default()
+        }
+    }
+
+    async fn send(kind: WebhookKind) -> Captured {
+        let (url, server) = capture_one();
+        let target = target(kind, url);
+        let todo = sample();
+        let context = NotificationContext {
+            kind: "dueDate",
+            title: "期限のお知らせ",
+            message: "もうすぐ期限です",
+            todo: Some(&todo),
+        };
+        let payload = build_payload(&target, &context).unwrap();
+        send_payload(&reqwest::Client::new(), &target, &payload)
+            .await
+            .map_err(|e| e.message().to_string())
+            .unwrap();
+        server.join().unwrap()
+    }
+
+    #[test]
+    fn render_does_not_expand_placeholders_inside_values() {
+        let todo = sample();
+        let context = NotificationContext {
+            kind: "reminder",
+            title: "{{text}}",
+            message: "",
+            todo: Some(&todo),
+        };
+        let rendered =
+            render_template("{{title}} / {{text}} / {{unknown}}", &context, false).unwrap();
+        assert_eq!(
+            rendered,
+            r#"{{text}} / 見積もり "A案" {{dueDate}} / {{unknown}}"#
+        );
+    }
+
+    #[test]
+    fn render_rejects_invalid_json() {
+        let todo = sample();
+        let context = NotificationContext {
+            kind: "reminder",
+            title: "",
+            message: "",
+            todo: Some(&todo),
+        };
+        assert!(render_template(r#"{"text": {{text}}}"#, &context, true).is_err());
+    }
+
+    #[tokio::test]
+    async fn sends_slack_payload() {
+        let captured = send(WebhookKind::Slack).await;
+        assert_eq!(captured.headers["content-type"], "application/json");
+        let body: serde_json::Value = serde_json::from_str(&captured.body).unwrap();
+        let text = body["text"].as_str().unwrap();
+        assert!(
+            text.starts_with(r#"期限のお知らせ: *見積もり "A案" {{dueDate}}*"#),
+            "{}",
+            text
+        );
+        assert!(
+            text.contains("/ P1 #仕事 #急ぎ\nもうすぐ期限です"),
+            "{}",
+            text
+        );
+    }
+
+    #[tokio::test]
+    async fn sends_ntfy_payload() {
+        let captured = send(WebhookKind::Ntfy).await;
+        assert_eq!(
+            captured.headers["content-type"],
+            "text/plain; charset=utf-8"
+        );
+        assert!(captured
+            .body
+            .starts_with("見積もり \"A案\" {{dueDate}}\n期日: "));
+        assert!(captured.body.ends_with(" / P1\nもうすぐ期限です"));
+        assert_eq!(
+            captured.headers["title"],
+            format!("=?UTF-8?B?{}?=", base64_encode("期限のお知らせ".as_bytes()))
+        );
+        assert_eq!(captured.headers["priority"], "4");
+        assert_eq!(
+            captured.headers["tags"],
+            format!("=?UTF-8?B?{}?=", base64_encode("仕事,急ぎ".as_bytes()))
+        );
+    }
+
+    #[tokio::test]
+    async fn sends_generic_payload() {
+        let captured = send(WebhookKind::Generic).await;
+        assert_eq!(captured.headers["content-type"], "application/json");
+        let body: serde_json::Value = serde_json::from_str(&captured.body).unwrap();
+        assert_eq!(body["event"], "dueDate");
+        assert_eq!(body["message"], "もうすぐ期限です");
+        assert_eq!(body["task"]["id"], "task-1");
+        assert_eq!(body["task"]["text"], r#"見積もり "A案" {{dueDate}}"#);
+        assert_eq!(body["task"]["description"], "1行目\n2行目");
+        assert_eq!(body["task"]["priority"], 1);
+        assert_eq!(body["task"]["labels"], serde_json::json!(["仕事", "急ぎ"]));
+        let due = body["task"]["dueDate"].as_str().unwrap();
+        assert_eq!(
+            chrono::DateTime::parse_from_rfc3339(due)
+                .unwrap()
+                .timestamp_millis(),
+            1_767_225_600_000
+        );
+    }
+
+    #[tokio::test]
+    async fn sends_mattermost_payload() {
+        let captured = send(WebhookKind::Mattermost).await;
+        assert_eq!(captured.headers["content-type"], "application/json");
+        let body: serde_json::Value = serde_json::from_str(&captured.body).unwrap();
+        let text = body["text"].as_str().unwrap();
+        assert!(
+            text.starts_with(r#"期限のお知らせ: *見積もり "A案" {{dueDate}}*"#),
+            "{}",
+            text
+        );
+        assert!(
+            text.contains("/ P1 #仕事 #急ぎ\nもうすぐ期限です"),
+            "{}",
+            text
+        );
+        // ntfy 用のヘッダーは付けない
+        assert!(!captured.headers.contains_key("priority"));
+        assert!(!captured.headers.contains_key("title"));
+    }
+
+    #[test]
+    fn maps_priorities_to_ntfy_levels() {
+        let target = target(WebhookKind::Ntfy, "http://localhost".to_string());
+        let levels: Vec<String> = (1..=4)
+            .map(|priority| {
+                let todo = Todo {
+                    priority,
+                    ..Default::default()
+                };
+                let context = NotificationContext {
+                    kind: "reminder",
+                    title: "",
+                    message: "",
+                    todo: Some(&todo),
+                };
+                let payload = build_payload(&target, &context).unwrap();
+                payload["headers"]["Priority"].as_str().unwrap().to_string()
+            })
+            .collect();
+        assert_eq!(levels, ["4", "3", "2", "1"]);
+    }
+
+    #[test]
+    fn rules_filter_by_kind_priority_and_label() {
+        let todo = sample();
+        let low = Todo {
+            priority: 4,
+            labels: vec!["趣味".to_string()],
+            ..Default::default()
+        };
+
+        let all = WebhookRules::default();
+        assert!(all.matches("reminder", Some(&todo)));
+        assert!(all.matches("morning", None));
+
+        let kinds = WebhookRules {
+            kinds: vec!["dueDate".to_string()],
+            ..Default::default()
+        };
+        assert!(kinds.matches("dueDate", Some(&todo)));
+        assert!(!kinds.matches("reminder", Some(&todo)));
+        assert!(!kinds.matches("morning", None));
+
+        let priority = WebhookRules {
+            max_priority: Some(2),
+            ..Default::default()
+        };
+        assert!(priority.matches("reminder", Some(&todo)));
+        assert!(!priority.matches("reminder", Some(&low)));
+
+        let labels = WebhookRules {
+            labels: vec!["急ぎ".to_string(), "家".to_string()],
+            ..Default::default()
+        };
+        assert!(labels.matches("reminder", Some(&todo)));
+        assert!(!labels.matches("reminder", Some(&low)));
+        // タスクのない通知はラベル・優先度の条件を問わない
+        assert!(labels.matches("morning", None));
+        assert!(priority.matches("morning", None));
+    }
+}
@@ -0,0 +1,679 @@
+// 送信待ちメッセージのアウトボックス
+// オフライン時に失われないよう永続化し、バックオフしながら再送する
+
+use chrono::Local;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+use std::time::Duration;
+use tauri::Manager;
+use tokio::sync::Notify;
+
+use crate::discord::{self, DeliveryError};
+use crate::mail;
+use crate::models::Todo;
+use crate::notifier;
+
+const OUTBOX_FILE: &str = "outbox.json";
+const POLL_INTERVAL: Duration = Duration::from_secs(15);
+const BASE_BACKOFF_MS: i64 = 30_000;
+const MAX_BACKOFF_MS: i64 = 60 * 60 * 1000;
+const MAX_ATTEMPTS: u32 = 12;
+// 送信済み・期限切れのメッセージを履歴として残す期間
+const FINISHED_RETENTION_MS: i64 = 7 * 24 * 60 * 60 * 1000;
+
+static NEXT_ID: AtomicU64 = AtomicU64::new(0);
+
+#[derive(serde::Serialize, serde::Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum OutboxStatus {
+    Pending,
+    Sent,
+    Expired,
+    Failed,
+}
+
+#[derive(serde::Serialize, serde::Deserialize, Clone, Debug, PartialEq, Eq)]
+#[serde(tag = "type", rename_all = "camelCase")]
+pub enum OutboxChannel {
+    Discord,
+    Email,
+    #[serde(rename_all = "camelCase")]
+    Webhook {
+        target_id: String,
+    },
+}
+
+#[derive(serde::Serialize, serde::Deserialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct OutboxMessage {
+    pub id: String,
+    pub channel: OutboxChannel,
+    pub task_id: Option<String>,
+    // リマインダーの種類 (reminder / dueDate / morning など)
+    pub kind: String,
+    // 同じ通知を二度送らないためのキー (呼び出し側がタスクID・期限・通知段階から作る)
+    #[serde(default)]
+    pub idempotency_key: Option<String>,
+    pub payload: serde_json::Value,
+    pub created_at: i64,
+    pub updated_at: i64,
+    pub attempts: u32,
+    pub next_attempt_at: i64,
+    pub expires_at: Option<i64>,
+    pub status: OutboxStatus,
+    pub last_error: Option<String>,
+}
+
+#[derive(serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct NewOutboxMessage {
+    pub channel: OutboxChannel,
+    #[serde(default)]
+    pub task_id: Option<String>,
+    pub kind: String,
+    #[serde(default)]
+    pub idempotency_key: Option<String>,
+    pub payload: serde_json::Value,
+    #[serde(default)]
+    pub expires_at: Option<i64>,
+}
+
+#[derive(serde::Serialize, Clone, Debug, PartialEq, Eq)]
+#[serde(tag = "status", rename_all = "camelCase")]
+pub enum EnqueueOutcome {
+    // 新しく追加した (または送信待ちのものを最新の内容で置き換えた)
+    Queued { id: String },
+    // 同じキーのメッセージを送信済みのため追加しなかった
+    Duplicate { id: String },
+}
+
+impl EnqueueOutcome {
+    pub fn id(&self) -> &str {
+        match self {
+            EnqueueOutcome::Queued { id } | EnqueueOutcome::Duplicate { id } => id,
+        }
+    }
+
+    pub fn is_queued(&self) -> bool {
+        matches!(self, EnqueueOutcome::Queued { .. })
+    }
+}
+
+#[derive(serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct OutboxReport {
+    pub pending: usize,
+    pub sent: usize,
+    pub expired: usize,
+    pub failed: usize,
+    pub next_attempt_at: Option<i64>,
+    pub last_error: Option<String>,
+    pub messages: Vec<OutboxMessage>,
+}
+
+fn backoff_ms(attempts: u32) -> i64 {
+    let exponent = attempts.saturating_sub(1).min(16);
+    (BASE_BACKOFF_MS * (1i64 << exponent)).min(MAX_BACKOFF_MS)
+}
+
+// キーがあればキーで、なければタスク・種類で同じ通知かを判定する
+fn same_notification(message: &OutboxMessage, new_message: &NewOutboxMessage) -> bool {
+    message.channel == new_message.channel
+        && match &new_message.idempotency_key {
+            Some(key) => message.idempotency_key.as_ref() == Some(key),
+            None => message.task_id == new_message.task_id && message.kind == new_message.kind,
+        }
+}
+
+fn push_message(
+    messages: &mut Vec<OutboxMessage>,
+    new_message: NewOutboxMessage,
+    now: i64,
+) -> EnqueueOutcome {
+    // 同じキーのメッセージを送信済みなら二重に送らない
+    if new_message.idempotency_key.is_some() {
+        let sent = messages.iter().find(|m| {
+            m.status == OutboxStatus::Sent
+                && now - m.updated_at < FINISHED_RETENTION_MS
+                && same_notification(m, &new_message)
+        });
+        if let Some(sent) = sent {
+            return EnqueueOutcome::Duplicate {
+                id: sent.id.clone(),
+            };
+        }
+    }
+
+    // 同じ通知の未送信メッセージがあれば最新の内容で置き換える
+    let existing = messages
+        .iter_mut()
+        .find(|m| m.status == OutboxStatus::Pending && same_notification(m, &new_message));
+    let id = match existing {
+        Some(message) => {
+            message.payload = new_message.payload;
+            message.kind = new_message.kind;
+            message.expires_at = new_message.expires_at;
+            message.updated_at = now;
+            message.next_attempt_at = now;
+            message.id.clone()
+        }
+        None => {
+            let id = format!("outbox-{}-{}", now, NEXT_ID.fetch_add(1, Ordering::Relaxed));
+            messages.push(OutboxMessage {
+                id: id.clone(),
+                channel: new_message.channel,
+                task_id: new_message.task_id,
+                kind: new_message.kind,
+                idempotency_key: new_message.idempotency_key,
+                payload: new_message.payload,
+                created_at: now,
+                updated_at: now,
+                attempts: 0,
+                next_attempt_at: now,
+                expires_at: new_message.expires_at,
+                status: OutboxStatus::Pending,
+                last_error: None,
+            });
+            id
+        }
+    };
+    EnqueueOutcome::Queued { id }
+}
+
+// 期限を過ぎた送信待ちを期限切れにし、古い履歴を消す。変更があれば true
+fn expire_and_prune(messages: &mut Vec<OutboxMessage>, now: i64) -> bool {
+    let before = messages.len();
+    let mut changed = false;
+    for message in messages.iter_mut() {
+        if message.status == OutboxStatus::Pending
+            && message
+                .expires_at
+                .is_some_and(|expires_at| expires_at <= now)
+        {
+            message.status = OutboxStatus::Expired;
+            message.updated_at = now;
+            changed = true;
+        }
+    }
+    messages.retain(|m| {
+        m.status == OutboxStatus::Pending || now - m.updated_at < FINISHED_RETENTION_MS
+    });
+    changed || messages.len() != before
+}
+
+pub struct OutboxState {
+    client: reqwest::Client,
+    messages: Mutex<Vec<OutboxMessage>>,
+    wake: Notify,
+}
+
+impl OutboxState {
+    pub fn load(app: &tauri::AppHandle) -> Self {
+        let messages = crate::store::load_json(app, OUTBOX_FILE).unwrap_or_else(|e| {
+            println!("[Outbox Backend] 読み込み失敗: {}", e);
+            Vec::new()
+        });
+        Self::with_messages(messages)
+    }
+
+    fn with_messages(messages: Vec<OutboxMessage>) -> Self {
+        Self {
+            client: reqwest::Client::new(),
+            messages: Mutex::new(messages),
+            wake: Notify::new(),
+        }
+    }
+
+    fn save(&self, app: &tauri::AppHandle, messages: &[OutboxMessage]) {
+        if let Err(e) = crate::store::save_json(app, OUTBOX_FILE, &messages) {
+            println!("[Outbox Backend] 保存失敗: {}", e);
+        }
+    }
+
+    pub fn enqueue(
+        &self,
+        app: &tauri::AppHandle,
+        new_message: NewOutboxMessage,
+    ) -> Result<EnqueueOutcome, String> {
+        if let Some(task_id) = &new_message.task_id {
+            let todos = app.state::<crate::todos::TodoState>();
+            if todos.find(task_id).is_some_and(|todo| !todo.is_active()) {
+                return Err("完了済みのタスクには通知を送信しません".to_string());
+            }
+        }
+
+        let now = Local::now().timestamp_millis();
+        let mut messages = self.messages.lock().unwrap();
+        let outcome = push_message(&mut messages, new_message, now);
+        if !outcome.is_queued() {
+            println!(
+                "[Outbox Backend] 送信済みのため追加しません: {}",
+                outcome.id()
+            );
+            return Ok(outcome);
+        }
+
+        self.save(app, &messages);
+        drop(messages);
+        self.wake.notify_one();
+        Ok(outcome)
+    }
+
+    // 完了・アーカイブ・削除されたタスクの未送信メッセージを期限切れにする
+    pub fn expire_irrelevant(&self, app: &tauri::AppHandle, todos: &[Todo]) {
+        let now = Local::now().timestamp_millis();
+        let mut messages = self.messages.lock().unwrap();
+        let mut changed = false;
+
+        for message in messages
+            .iter_mut()
+            .filter(|m| m.status == OutboxStatus::Pending)
+        {
+            let Some(task_id) = &message.task_id else {
+                continue;
+            };
+            let relevant = todos
+                .iter()
+                .any(|todo| &todo.id == task_id && todo.is_active());
+            if !relevant {
+                message.status = OutboxStatus::Expired;
+                message.updated_at = now;
+                changed = true;
+            }
+        }
+
+        if changed {
+            self.save(app, &messages);
+        }
+    }
+
+    pub fn expire_task(&self, app: &tauri::AppHandle, task_id: &str) -> usize {
+        let now = Local::now().timestamp_millis();
+        let mut messages = self.messages.lock().unwrap();
+        let mut count = 0;
+        for message in messages
+            .iter_mut()
+            .filter(|m| m.status == OutboxStatus::Pending && m.task_id.as_deref() == Some(task_id))
+        {
+            message.status = OutboxStatus::Expired;
+            message.updated_at = now;
+            count += 1;
+        }
+        if count > 0 {
+            self.save(app, &messages);
+        }
+        count
+    }
+
+    // 期限切れの処理と古い履歴の削除を行い、送信すべきメッセージを返す
+    fn take_due(&self, app: &tauri::AppHandle, now: i64) -> Vec<OutboxMessage> {
+        let mut messages = self.messages.lock().unwrap();
+        if expire_and_prune(&mut messages, now) {
+            self.save(app, &messages);
+        }
+        due_messages(&messages, now)
+    }
+
+    fn apply_result(&self, id: &str, result: Result<(), DeliveryError>, now: i64) {
+        let mut messages = self.messages.lock().unwrap();
+        let Some(message) = messages.iter_mut().find(|m| m.id == id) else {
+            return;
+        };
+        // 送信中にタスクが完了するなどして状態が変わっていれば上書きしない
+        if message.status != OutboxStatus::Pending {
+            return;
+        }
+
+        message.updated_at = now;
+        match result {
+            Ok(()) => {
+                message.status = OutboxStatus::Sent;
+                message.last_error = None;
+            }
+            Err(error) => {
+                message.attempts += 1;
+                message.last_error = Some(error.message().to_string());
+                match error {
+                    DeliveryError::Retryable(_) if message.attempts < MAX_ATTEMPTS => {
+                        message.next_attempt_at = now + backoff_ms(message.attempts);
+                    }
+                    _ => message.status = OutboxStatus::Failed,
+                }
+            }
+        }
+    }
+
+    // 送信できた = 接続が回復したとみなし、待機中のメッセージをすぐに再送する
+    fn reschedule_pending(&self, now: i64) -> bool {
+        let mut messages = self.messages.lock().unwrap();
+        let mut changed = false;
+        for message in messages
+            .iter_mut()
+            .filter(|m| m.status == OutboxStatus::Pending && m.next_attempt_at > now)
+        {
+            message.next_attempt_at = now;
+            changed = true;
+        }
+        changed
+    }
+
+    fn report(&self) -> OutboxReport {
+        let messages = self.messages.lock().unwrap();
+        let count = |status: OutboxStatus| messages.iter().filter(|m| m.status == status).count();
+        let pending: Vec<&OutboxMessage> = messages
+            .iter()
+            .filter(|m| m.status == OutboxStatus::Pending)
+            .collect();
+
+        OutboxReport {
+            pending: pending.len(),
+            sent: count(OutboxStatus::Sent),
+            expired: count(OutboxStatus::Expired),
+            failed: count(OutboxStatus::Failed),
+            next_attempt_at: pending.iter().map(|m| m.next_attempt_at).min(),
+            last_error: messages
+                .iter()
+                .filter(|m| m.last_error.is_some())
+                .max_by_key(|m| m.updated_at)
+                .and_then(|m| m.last_error.clone()),
+            messages: messages.clone(),
+        }
+    }
+}
+
+fn due_messages(messages: &[OutboxMessage], now: i64) -> Vec<OutboxMessage> {
+    messages
+        .iter()
+        .filter(|m| m.status == OutboxStatus::Pending && m.next_attempt_at <= now)
+        .cloned()
+        .collect()
+}
+
+async fn deliver(app: &tauri::AppHandle, message: &OutboxMessage) -> Result<(), DeliveryError> {
+    let client = &app.state::<OutboxState>().inner().client;
+    match &message.channel {
+        OutboxChannel::Discord => {
+            let settings = app.state::<discord::DiscordState>().settings();
+            discord::send_dm(client, &settings, &message.payload).await
+        }
+        OutboxChannel::Email => {
+            let settings = app.state::<mail::MailState>().settings();
+            mail::send_mail(&settings, &message.payload).await
+        }
+        OutboxChannel::Webhook { target_id } => {
+            let target = app
+                .state::<notifier::NotifierState>()
+                .target(target_id)
+                .ok_or_else(|| {
+                    DeliveryError::Permanent(format!("送信先が削除されています: {}", target_id))
+                })?;
+            if !target.enabled {
+                return Err(DeliveryError::Permanent(format!(
+                    "送信先が無効になっています: {}",
+                    target.name
+                )));
+            }
+            notifier::send_payload(client, &target, &message.payload).await
+        }
+    }
+}
+
+async fn deliver_due(app: &tauri::AppHandle) {
+    let state = app.state::<OutboxState>();
+    let now = Local::now().timestamp_millis();
+    let due = state.take_due(app, now);
+    if due.is_empty() {
+        return;
+    }
+
+    let mut delivered_any = false;
+    for message in &due {
+        let result = deliver(app, message).await;
+        match &result {
+            Ok(()) => {
+                delivered_any = true;
+                println!(
+                    "[Outbox Backend] 送信成功: {} ({})",
+                    message.id, message.kind
+                );
+            }
+            Err(error) => println!(
+                "[Outbox Backend] 送信失敗: {} ({}): {}",
+                message.id,
+                message.kind,
+                error.message()
+            ),
+        }
+        state.apply_result(&message.id, result, Local::now().timestamp_millis());
+    }
+
+    if delivered_any && state.reschedule_pending(Local::now().timestamp_millis()) {
+        state.wake.notify_one();
+    }
+    let messages = state.messages.lock().unwrap().clone();
+    state.save(app, &messages);
+}
+
+// アプリ起動中ずっと動くバックグラウンド送信ループ
+pub async fn run_worker(app: tauri::AppHandle) {
+    loop {
+        deliver_due(&app).await;
+        let state = app.state::<OutboxState>();
+        tokio::select! {
+            _ = tokio::time::sleep(POLL_INTERVAL) => {}
+            _ = state.wake.notified() => {}
+        }
+    }
+}
+
+#[tauri::command]
+pub fn outbox_enqueue(
+    app: tauri::AppHandle,
+    state: tauri::State<'_, OutboxState>,
+    message: NewOutboxMessage,
+) -> Result<EnqueueOutcome, String> {
+    state.enqueue(&app, message)
+}
+
+#[tauri::command]
+pub fn outbox_status(state: tauri::State<'_, OutboxState>) -> OutboxReport {
+    state.report()
+}
+
+#[tauri::command]
+pub fn outbox_retry_now(state: tauri::State<'_, OutboxState>) {
+    state.reschedule_pending(Local::now().timestamp_millis());
+    state.wake.notify_one();
+}
+
+#[tauri::command]
+pub fn outbox_expire_task(
+    app: tauri::AppHandle,
+    state: tauri::State<'_, OutboxState>,
+    task_id: String,
+) -> usize {
+    state.expire_task(&app, &task_id)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const NOW: i64 = 1_767_225_600_000;
+
+    fn reminder(task_id: &str, key: Option<&str>, text: &str) -> NewOutboxMessage {
+        NewOutboxMessage {
+            channel: OutboxChannel::Discord,
+            task_id: Some(task_id.to_string()),
+            kind: "reminder".to_string(),
+            idempotency_key: key.map(str::to_string),
+            payload: serde_json::json!({ "text": text }),
+            expires_at: None,
+        }
+    }
+
+    fn mark_sent(messages: &mut [OutboxMessage], id: &str, now: i64) {
+        let message = messages.iter_mut().find(|m| m.id == id).unwrap();
+        message.status = OutboxStatus::Sent;
+        message.updated_at = now;
+    }
+
+    #[test]
+    fn replaces_pending_message_with_same_key() {
+        let mut messages = Vec::new();
+        let first = push_message(&mut messages, reminder("a", Some("a:1:upcoming"), "1"), NOW);
+        let second = push_message(
+            &mut messages,
+            reminder("a", Some("a:1:upcoming"), "2"),
+            NOW + 1,
+        );
+        assert!(first.is_queued() && second.is_queued());
+        assert_eq!(first.id(), second.id());
+        assert_eq!(messages.len(), 1);
+        assert_eq!(messages[0].payload["text"], "2");
+    }
+
+    #[test]
+    fn skips_sent_message_with_same_key() {
+        let mut messages = Vec::new();
+        let first = push_message(&mut messages, reminder("a", Some("a:1:upcoming"), "1"), NOW);
+        mark_sent(&mut messages, first.id(), NOW);
+
+        let again = push_message(
+            &mut messages,
+            reminder("a", Some("a:1:upcoming"), "1"),
+            NOW + 60_000,
+        );
+        assert_eq!(
+            again,
+            EnqueueOutcome::Duplicate {
+                id: first.id().to_string()
+            }
+        );
+        assert_eq!(messages.len(), 1);
+
+        // 別の段階 (期限切れ)・別の期限 (次の回) ・別のチャンネルは送る
+        for new_message in [
+            reminder("a", Some("a:1:overdue"), "2"),
+            reminder("a", Some("a:2:upcoming"), "3"),
+            NewOutboxMessage {
+                channel: OutboxChannel::Email,
+                ..reminder("a", Some("a:1:upcoming"), "4")
+            },
+        ] {
+            assert!(push_message(&mut messages, new_message, NOW + 120_000).is_queued());
+        }
+        assert_eq!(messages.len(), 4);
+    }
+
+    #[test]
+    fn sends_again_after_retention_period() {
+        let mut messages = Vec::new();
+        let first = push_message(&mut messages, reminder("a", Some("k"), "1"), NOW);
+        mark_sent(&mut messages, first.id(), NOW);
+        let later = NOW + FINISHED_RETENTION_MS;
+        assert!(push_message(&mut messages, reminder("a", Some("k"), "1"), later).is_queued());
+    }
+
+    #[test]
+    fn messages_without_key_are_not_deduplicated_after_sending() {
+        let mut messages = Vec::new();
+        let first = push_message(&mut messages, reminder("a", None, "1"), NOW);
+        // 送信前は同じタスク・種類のものを置き換える
+        let replaced = push_message(&mut messages, reminder("a", None, "2"), NOW);
+        assert_eq!(first.id(), replaced.id());
+        mark_sent(&mut messages, first.id(), NOW);
+
+        let next = push_message(&mut messages, reminder("a", None, "3"), NOW + 1);
+        assert!(next.is_queued());
+        assert_ne!(next.id(), first.id());
+        assert_eq!(messages.len(), 2);
+    }
+
+    #[test]
+    fn retries_with_backoff_until_limit() {
+        let mut messages = Vec::new();
+        let id = push_message(&mut messages, reminder("a", None, "1"), NOW)
+            .id()
+            .to_string();
+        let state = OutboxState::with_messages(messages);
+        let message = |state: &OutboxState| state.messages.lock().unwrap()[0].clone();
+
+        state.apply_result(&id, Err(DeliveryError::Retryable("offline".into())), NOW);
+        let m = message(&state);
+        assert_eq!((m.status, m.attempts), (OutboxStatus::Pending, 1));
+        assert_eq!(m.next_attempt_at, NOW + BASE_BACKOFF_MS);
+        assert_eq!(m.last_error.as_deref(), Some("offline"));
+        assert!(due_messages(&state.messages.lock().unwrap(), NOW + 1).is_empty());
+
+        state.apply_result(&id, Err(DeliveryError::Retryable("offline".into())), NOW);
+        assert_eq!(message(&state).next_attempt_at, NOW + 2 * BASE_BACKOFF_MS);
+
+        // 接続が戻ったら待機中のものをすぐ再送する
+        assert!(state.reschedule_pending(NOW + 1));
+        assert_eq!(
+            due_messages(&state.messages.lock().unwrap(), NOW + 1).len(),
+            1
+        );
+
+        for _ in 2..MAX_ATTEMPTS {
+            state.apply_result(&id, Err(DeliveryError::Retryable("offline".into())), NOW);
+        }
+        let m = message(&state);
+        assert_eq!((m.status, m.attempts), (OutboxStatus::Failed, MAX_ATTEMPTS));
+    }
+
+    #[test]
+    fn permanent_errors_fail_immediately_and_success_is_final() {
+        let mut messages = Vec::new();
+        let failed = push_message(&mut messages, reminder("a", None, "1"), NOW)
+            .id()
+            .to_string();
+        let sent = push_message(&mut messages, reminder("b", None, "2"), NOW)
+            .id()
+            .to_string();
+        let state = OutboxState::with_messages(messages);
+
+        state.apply_result(&failed, Err(DeliveryError::Permanent("401".into())), NOW);
+        state.apply_result(&sent, Ok(()), NOW);
+        // 送信済みのものに後から結果が届いても変えない
+        state.apply_result(&sent, Err(DeliveryError::Permanent("late".into())), NOW);
+
+        let messages = state.messages.lock().unwrap();
+        assert_eq!(messages[0].status, OutboxStatus::Failed);
+        assert_eq!(messages[1].status, OutboxStatus::Sent);
+        assert_eq!(messages[1].last_error, None);
+    }
+
+    #[test]
+    fn backoff_is_capped() {
+        assert_eq!(backoff_ms(1), BASE_BACKOFF_MS);
+        assert_eq!(backoff_ms(3), 4 * BASE_BACKOFF_MS);
+        assert_eq!(backoff_ms(40), MAX_BACKOFF_MS);
+    }
+
+    #[test]
+    fn expires_and_prunes_messages() {
+        let mut messages = Vec::new();
+        let expiring = NewOutboxMessage {
+            expires_at: Some(NOW + 10),
+            ..reminder("a", None, "1")
+        };
+        push_message(&mut messages, expiring, NOW);
+        let old = push_message(&mut messages, reminder("b", None, "2"), NOW);
+        mark_sent(&mut messages, old.id(), NOW - FINISHED_RETENTION_MS);
+        push_message(&mut messages, reminder("c", None, "3"), NOW);
+
+        assert!(expire_and_prune(&mut messages, NOW + 10));
+        let statuses: Vec<(&str, OutboxStatus)> = messages
+            .iter()
+            .map(|m| (m.task_id.as_deref().unwrap(), m.status))
+            .collect();
+        assert_eq!(
+            statuses,
+            [("a", OutboxStatus::Expired), ("c", OutboxStatus::Pending)]
+        );
+        assert!(!expire_and_prune(&mut messages, NOW + 10));
+        assert_eq!(due_messages(&messages, NOW + 10).len(), 1);
+    }
+}
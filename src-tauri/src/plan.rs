@@ -0,0 +1,1307 @@
+// AI計画生成・タスク分解のJSON応答パーサー
+// コードフェンス、末尾カンマ、途中で切れた出力を修復し、欠けているフィールドを項目ごとに報告する
+
+use serde_json::{Map, Value};
+
+#[derive(serde::Serialize, serde::Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum PlanPriority {
+    High,
+    Medium,
+    Low,
+}
+
+#[derive(serde::Serialize, serde::Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "UPPERCASE")]
+pub enum FeasibilityVerdict {
+    Feasible,
+    Challenging,
+    Infeasible,
+}
+
+#[derive(serde::Serialize, serde::Deserialize, Clone, Debug, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct PlanRecurrence {
+    #[serde(rename = "type")]
+    pub kind: String,
+    pub interval: u32,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub day_of_week: Option<u32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub day_of_month: Option<u32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub month_of_year: Option<u32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub day_of_year: Option<u32>,
+}
+
+#[derive(serde::Serialize, serde::Deserialize, Clone, Debug, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct PlanTask {
+    pub title: String,
+    pub description: String,
+    pub priority: PlanPriority,
+    pub days_from_start: u32,
+    pub estimated_minutes: u32,
+    pub recurrence: Option<PlanRecurrence>,
+}
+
+#[derive(serde::Serialize, serde::Deserialize, Clone, Debug, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct PlanFeasibility {
+    pub verdict: FeasibilityVerdict,
+    pub available_hours: f64,
+    pub required_hours: f64,
+    pub calculation: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub adjustment: Option<String>,
+}
+
+#[derive(serde::Serialize, serde::Deserialize, Clone, Debug, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct PlanResource {
+    pub name: String,
+    #[serde(rename = "type")]
+    pub kind: String,
+    pub description: String,
+    pub cost: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub url: Option<String>,
+}
+
+#[derive(serde::Serialize, serde::Deserialize, Clone, Debug, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct PlanResult {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub current_state: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub goal_state: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub gap: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub feasibility: Option<PlanFeasibility>,
+    pub risks: Vec<String>,
+    pub costs: Vec<String>,
+    pub summary: String,
+    pub estimated_days: u32,
+    pub tasks: Vec<PlanTask>,
+    pub resources: Vec<PlanResource>,
+    pub tips: Vec<String>,
+}
+
+#[derive(serde::Serialize, serde::Deserialize, Clone, Debug, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct DecomposeSubtask {
+    pub title: String,
+    pub priority: PlanPriority,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub estimated_minutes: Option<u32>,
+}
+
+// モデルが返す「期限未指定」などのエラーJSON
+#[derive(serde::Serialize, Clone, Debug, PartialEq)]
+pub struct PlanError {
+    pub error: String,
+    pub code: Option<String>,
+}
+
+#[derive(serde::Serialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum IssueKind {
+    // 必須フィールドがなく、既定値で補った
+    Missing,
+    // 型や値が不正で、既定値で補ったか要素を捨てた
+    Invalid,
+    // 表記ゆれなどを正規化した
+    Normalized,
+}
+
+#[derive(serde::Serialize, Clone, Debug, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct PlanIssue {
+    pub path: String,
+    pub kind: IssueKind,
+    pub message: String,
+}
+
+#[derive(serde::Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct PlanParseReport {
+    pub plan: Option<PlanResult>,
+    pub error: Option<PlanError>,
+    // JSONテキストに対して行った修復
+    pub repairs: Vec<String>,
+    pub issues: Vec<PlanIssue>,
+    pub truncated: bool,
+}
+
+#[derive(serde::Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct DecomposeParseReport {
+    pub subtasks: Vec<DecomposeSubtask>,
+    pub repairs: Vec<String>,
+    pub issues: Vec<PlanIssue>,
+    pub truncated: bool,
+}
+
+struct RepairedJson {
+    value: Value,
+    repairs: Vec<String>,
+    truncated: bool,
+}
+
+// 前置きの文章やコードフェンスを除き、最初の '{' か '[' から始まる部分を取り出す
+fn extract_json_text(raw: &str, repairs: &mut Vec<String>) -> Option<String> {
+    let mut text = raw.trim();
+
+    if let Some(fence_start) = text.find("```") {
+        let after_fence = &text[fence_start + 3..];
+        // ```json のような言語指定を読み飛ばす
+        let body_start = after_fence.find('\n').map(|i| i + 1).unwrap_or(0);
+        let body = &after_fence[body_start..];
+        text = match body.find("```") {
+            Some(fence_end) => &body[..fence_end],
+            None => body,
+        };
+        repairs.push("コードフェンスを除去しました".to_string());
+    }
+
+    let start = text.find(['{', '['])?;
+    if !text[..start].trim().is_empty() {
+        repairs.push("JSONの前にある文章を除去しました".to_string());
+    }
+    Some(text[start..].to_string())
+}
+
+// 末尾カンマの除去、JSON以降の文章の切り捨て、途中で切れた出力の補完を行う
+fn repair_json_text(text: &str, repairs: &mut Vec<String>) -> (String, bool) {
+    let chars: Vec<char> = text.chars().collect();
+    let mut out = String::with_capacity(text.len());
+    let mut stack: Vec<char> = Vec::new();
+    let mut in_string = false;
+    let mut escaped = false;
+    // 途中で切れていた場合に戻る位置 (直前の完結した要素の直後)
+    let mut safe_len = 0;
+    let mut safe_stack: Vec<char> = Vec::new();
+    let mut removed_commas = 0;
+    let mut closed = false;
+    let mut i = 0;
+
+    while i < chars.len() {
+        let c = chars[i];
+        if in_string {
+            out.push(c);
+            if escaped {
+                escaped = false;
+            } else if c == '\\' {
+                escaped = true;
+            } else if c == '"' {
+                in_string = false;
+            } else if c == '\n' {
+                // 文字列内の生の改行はJSONとして不正なのでエスケープする
+                out.pop();
+                out.push_str("\\n");
+            }
+            i += 1;
+            continue;
+        }
+
+        match c {
+            '"' => {
+                in_string = true;
+                out.push(c);
+            }
+            '{' | '[' => {
+                stack.push(if c == '{' { '}' } else { ']' });
+                out.push(c);
+                safe_len = out.len();
+                safe_stack = stack.clone();
+            }
+            '}' | ']' => {
+                // 対応しない閉じ括弧は捨てる
+                if stack.last() == Some(&c) {
+                    stack.pop();
+                    out.push(c);
+                    safe_len = out.len();
+                    safe_stack = stack.clone();
+                    if stack.is_empty() {
+                        closed = true;
+                        break;
+                    }
+                }
+            }
+            ',' => {
+                let next = chars[i + 1..].iter().find(|ch| !ch.is_whitespace());
+                if matches!(next, Some('}') | Some(']')) {
+                    removed_commas += 1;
+                } else {
+                    safe_len = out.len();
+                    safe_stack = stack.clone();
+                    out.push(c);
+                }
+            }
+            _ => out.push(c),
+        }
+        i += 1;
+    }
+
+    if removed_commas > 0 {
+        repairs.push(format!("末尾カンマを{}個除去しました", removed_commas));
+    }
+
+    if closed {
+        if chars[i + 1..].iter().any(|ch| !ch.is_whitespace()) {
+            repairs.push("JSONの後にある文章を除去しました".to_string());
+        }
+        return (out, false);
+    }
+
+    // 閉じられないまま終わった: 最後に完結した要素まで戻して括弧を閉じる
+    out.truncate(safe_len);
+    let trimmed_len = out.trim_end().trim_end_matches(',').trim_end().len();
+    out.truncate(trimmed_len);
+    for closer in safe_stack.iter().rev() {
+        out.push(*closer);
+    }
+    repairs.push("途中で切れた応答を補完しました (最後の不完全な要素は破棄)".to_string());
+    (out, true)
+}
+
+fn parse_lenient(raw: &str) -> Result<RepairedJson, String> {
+    if let Ok(value) = serde_json::from_str::<Value>(raw.trim()) {
+        return Ok(RepairedJson {
+            value,
+            repairs: Vec::new(),
+            truncated: false,
+        });
+    }
+
+    let mut repairs = Vec::new();
+    let text = extract_json_text(raw, &mut repairs)
+        .ok_or_else(|| "AI応答にJSONが含まれていません".to_string())?;
+    let (repaired, truncated) = repair_json_text(&text, &mut repairs);
+    let value = serde_json::from_str(&repaired)
+        .map_err(|e| format!("AI応答の解析に失敗しました: {}", e))?;
+
+    Ok(RepairedJson {
+        value,
+        repairs,
+        truncated,
+    })
+}
+
+struct Issues(Vec<PlanIssue>);
+
+impl Issues {
+    fn push(&mut self, path: &str, kind: IssueKind, message: impl Into<String>) {
+        self.0.push(PlanIssue {
+            path: path.to_string(),
+            kind,
+            message: message.into(),
+        });
+    }
+}
+
+// 数値または "120分" "約3日" のような数字を含む文字列を受け付ける
+// 数字の直前のマイナス記号は符号として残す ("-3" を 3 にしない)
+fn lenient_number(value: &Value) -> Option<f64> {
+    match value {
+        Value::Number(n) => n.as_f64(),
+        Value::String(s) => {
+            let start = s.find(|c: char| c.is_ascii_digit())?;
+            let digits: String = s[start..]
+                .chars()
+                .take_while(|c| c.is_ascii_digit() || *c == '.')
+                .collect();
+            let number: f64 = digits.parse().ok()?;
+            if s[..start].ends_with(['-', '−', '－']) {
+                Some(-number)
+            } else {
+                Some(number)
+            }
+        }
+        _ => None,
+    }
+}
+
+fn lenient_u32(value: &Value) -> Option<u32> {
+    lenient_number(value)
+        .filter(|n| n.is_finite() && *n >= 0.0)
+        .map(|n| n.round() as u32)
+}
+
+fn optional_string(obj: &Map<String, Value>, key: &str) -> Option<String> {
+    obj.get(key)
+        .and_then(Value::as_str)
+        .map(str::trim)
+        .filter(|s| !s.is_empty())
+        .map(str::to_string)
+}
+
+fn string_list(obj: &Map<String, Value>, key: &str, issues: &mut Issues) -> Vec<String> {
+    match obj.get(key) {
+        None | Some(Value::Null) => Vec::new(),
+        Some(Value::String(s)) => {
+            issues.push(key, IssueKind::Normalized, "文字列を配列として扱いました");
+            vec![s.clone()]
+        }
+        Some(Value::Array(items)) => items
+            .iter()
+            .filter_map(|item| match item {
+                Value::String(s) if !s.trim().is_empty() => Some(s.clone()),
+                _ => None,
+            })
+            .collect(),
+        Some(_) => {
+            issues.push(key, IssueKind::Invalid, "配列ではないため無視しました");
+            Vec::new()
+        }
+    }
+}
+
+fn parse_priority(value: Option<&Value>, path: &str, issues: &mut Issues) -> PlanPriority {
+    let Some(value) = value else {
+        issues.push(
+            path,
+            IssueKind::Missing,
+            "priority がないため medium にしました",
+        );
+        return PlanPriority::Medium;
+    };
+    let raw = match value {
+        Value::String(s) => s.trim().to_lowercase(),
+        Value::Number(n) => n.to_string(),
+        _ => String::new(),
+    };
+    let priority = match raw.as_str() {
+        "high" => return PlanPriority::High,
+        "medium" => return PlanPriority::Medium,
+        "low" => return PlanPriority::Low,
+        "高" | "p1" | "1" | "urgent" | "critical" => Some(PlanPriority::High),
+        "中" | "p2" | "2" | "normal" | "mid" => Some(PlanPriority::Medium),
+        "低" | "p3" | "p4" | "3" | "4" => Some(PlanPriority::Low),
+        _ => None,
+    };
+    match priority {
+        Some(priority) => {
+            issues.push(
+                path,
+                IssueKind::Normalized,
+                format!("priority \"{}\" を正規化しました", raw),
+            );
+            priority
+        }
+        None => {
+            issues.push(
+                path,
+                IssueKind::Invalid,
+                format!("priority \"{}\" は不正なため medium にしました", raw),
+            );
+            PlanPriority::Medium
+        }
+    }
+}
+
+fn parse_recurrence(
+    value: Option<&Value>,
+    path: &str,
+    issues: &mut Issues,
+) -> Option<PlanRecurrence> {
+    let obj = match value {
+        None | Some(Value::Null) => return None,
+        Some(Value::Object(obj)) => obj,
+        Some(_) => {
+            issues.push(
+                path,
+                IssueKind::Invalid,
+                "recurrence がオブジェクトではないため無視しました",
+            );
+            return None;
+        }
+    };
+
+    let kind = obj
+        .get("type")
+        .and_then(Value::as_str)
+        .map(|s| s.trim().to_lowercase());
+    let kind = match kind.as_deref() {
+        Some(k @ ("daily" | "weekly" | "monthly" | "yearly")) => k.to_string(),
+        other => {
+            issues.push(
+                path,
+                IssueKind::Invalid,
+                format!(
+                    "recurrence.type {:?} は不正なため繰り返しを無視しました",
+                    other
+                ),
+            );
+            return None;
+        }
+    };
+
+    let interval = match obj.get("interval").and_then(lenient_u32) {
+        Some(n) if n >= 1 => n,
+        _ => {
+            issues.push(
+                path,
+                IssueKind::Missing,
+                "recurrence.interval がないため 1 にしました",
+            );
+            1
+        }
+    };
+
+    let bounded = |key: &str, min: u32, max: u32| {
+        obj.get(key)
+            .and_then(lenient_u32)
+            .filter(|n| (min..=max).contains(n))
+    };
+
+    Some(PlanRecurrence {
+        kind,
+        interval,
+        day_of_week: bounded("dayOfWeek", 0, 6),
+        day_of_month: bounded("dayOfMonth", 1, 31),
+        month_of_year: bounded("monthOfYear", 1, 12),
+        day_of_year: bounded("dayOfYear", 1, 31),
+    })
+}
+
+fn parse_task(value: &Value, index: usize, issues: &mut Issues) -> Option<PlanTask> {
+    let path = format!("tasks[{}]", index);
+    let Some(obj) = value.as_object() else {
+        issues.push(
+            &path,
+            IssueKind::Invalid,
+            "タスクがオブジェクトではないため破棄しました",
+        );
+        return None;
+    };
+    let Some(title) = optional_string(obj, "title") else {
+        issues.push(
+            &path,
+            IssueKind::Invalid,
+            "title がないためタスクを破棄しました",
+        );
+        return None;
+    };
+
+    let description = optional_string(obj, "description").unwrap_or_else(|| {
+        issues.push(&path, IssueKind::Missing, "description がありません");
+        String::new()
+    });
+    let priority = parse_priority(obj.get("priority"), &path, issues);
+    let days_from_start = obj
+        .get("daysFromStart")
+        .and_then(lenient_u32)
+        .unwrap_or_else(|| {
+            issues.push(
+                &path,
+                IssueKind::Missing,
+                "daysFromStart がないため 0 にしました",
+            );
+            0
+        });
+    let estimated_minutes = obj
+        .get("estimatedMinutes")
+        .and_then(lenient_u32)
+        .filter(|m| *m > 0)
+        .unwrap_or_else(|| {
+            issues.push(
+                &path,
+                IssueKind::Missing,
+                "estimatedMinutes がないため 30 にしました",
+            );
+            30
+        });
+    let recurrence = parse_recurrence(obj.get("recurrence"), &path, issues);
+
+    Some(PlanTask {
+        title,
+        description,
+        priority,
+        days_from_start,
+        estimated_minutes,
+        recurrence,
+    })
+}
+
+fn parse_feasibility(value: Option<&Value>, issues: &mut Issues) -> Option<PlanFeasibility> {
+    let obj = match value {
+        None | Some(Value::Null) => return None,
+        Some(Value::Object(obj)) => obj,
+        Some(_) => {
+            issues.push(
+                "feasibility",
+                IssueKind::Invalid,
+                "オブジェクトではないため無視しました",
+            );
+            return None;
+        }
+    };
+
+    let hours = |key: &str| {
+        obj.get(key)
+            .and_then(lenient_number)
+            .filter(|h| h.is_finite() && *h >= 0.0)
+    };
+    let available_hours = hours("availableHours");
+    let required_hours = hours("requiredHours");
+    let verdict = match obj
+        .get("verdict")
+        .and_then(Value::as_str)
+        .map(|s| s.trim().to_uppercase())
+        .as_deref()
+    {
+        Some("FEASIBLE") => Some(FeasibilityVerdict::Feasible),
+        Some("CHALLENGING") => Some(FeasibilityVerdict::Challenging),
+        Some("INFEASIBLE") => Some(FeasibilityVerdict::Infeasible),
+        _ => None,
+    };
+
+    // 判定がなくても時間の見積もりがあれば算出する
+    let verdict = match (verdict, available_hours, required_hours) {
+        (Some(verdict), _, _) => verdict,
+        (None, Some(available), Some(required)) => {
+            let derived = if required <= available {
+                FeasibilityVerdict::Feasible
+            } else if required <= available * 1.5 {
+                FeasibilityVerdict::Challenging
+            } else {
+                FeasibilityVerdict::Infeasible
+            };
+            issues.push(
+                "feasibility.verdict",
+                IssueKind::Missing,
+                format!("verdict がないため時間から {:?} と算出しました", derived),
+            );
+            derived
+        }
+        (None, _, _) => {
+            issues.push(
+                "feasibility.verdict",
+                IssueKind::Invalid,
+                "verdict も時間の見積もりもないため feasibility を無視しました",
+            );
+            return None;
+        }
+    };
+
+    if available_hours.is_none() {
+        issues.push(
+            "feasibility.availableHours",
+            IssueKind::Missing,
+            "availableHours がありません",
+        );
+    }
+    if required_hours.is_none() {
+        issues.push(
+            "feasibility.requiredHours",
+            IssueKind::Missing,
+            "requiredHours がありません",
+        );
+    }
+
+    Some(PlanFeasibility {
+        verdict,
+        available_hours: available_hours.unwrap_or(0.0),
+        required_hours: required_hours.unwrap_or(0.0),
+        calculation: optional_string(obj, "calculation").unwrap_or_default(),
+        adjustment: optional_string(obj, "adjustment"),
+    })
+}
+
+fn parse_resources(value: Option<&Value>, issues: &mut Issues) -> Vec<PlanResource> {
+    let Some(Value::Array(items)) = value else {
+        return Vec::new();
+    };
+    items
+        .iter()
+        .enumerate()
+        .filter_map(|(index, item)| {
+            let path = format!("resources[{}]", index);
+            let obj = item.as_object()?;
+            let Some(name) = optional_string(obj, "name") else {
+                issues.push(
+                    &path,
+                    IssueKind::Invalid,
+                    "name がないためリソースを破棄しました",
+                );
+                return None;
+            };
+            let url = optional_string(obj, "url");
+            let kind = match optional_string(obj, "type").map(|s| s.to_lowercase()) {
+                Some(k)
+                    if matches!(
+                        k.as_str(),
+                        "book" | "website" | "tool" | "service" | "community"
+                    ) =>
+                {
+                    k
+                }
+                other => {
+                    let fallback = if url.is_some() { "website" } else { "tool" };
+                    issues.push(
+                        &path,
+                        IssueKind::Invalid,
+                        format!("type {:?} は不正なため {} にしました", other, fallback),
+                    );
+                    fallback.to_string()
+                }
+            };
+            Some(PlanResource {
+                name,
+                kind,
+                description: optional_string(obj, "description").unwrap_or_default(),
+                cost: optional_string(obj, "cost").unwrap_or_default(),
+                url,
+            })
+        })
+        .collect()
+}
+
+pub fn parse_plan(raw: &str) -> Result<PlanParseReport, String> {
+    let RepairedJson {
+        value,
+        repairs,
+        truncated,
+    } = parse_lenient(raw)?;
+    let mut issues = Issues(Vec::new());
+
+    // {"plan": {...}} のように包まれている場合は中身を使う
+    let value = match value {
+        Value::Object(mut obj) if obj.len() == 1 && obj.contains_key("plan") => {
+            issues.push(
+                "plan",
+                IssueKind::Normalized,
+                "plan オブジェクトの中身を使用しました",
+            );
+            obj.remove("plan").unwrap_or(Value::Null)
+        }
+        other => other,
+    };
+    let Value::Object(obj) = value else {
+        return Err("無効な応答形式: JSONオブジェクトではありません".to_string());
+    };
+
+    if let Some(error) = optional_string(&obj, "error") {
+        return Ok(PlanParseReport {
+            plan: None,
+            error: Some(PlanError {
+                error,
+                code: optional_string(&obj, "code"),
+            }),
+            repairs,
+            issues: issues.0,
+            truncated,
+        });
+    }
+
+    let Some(Value::Array(raw_tasks)) = obj.get("tasks") else {
+        return Err("無効な応答形式: tasks がありません".to_string());
+    };
+    let tasks: Vec<PlanTask> = raw_tasks
+        .iter()
+        .enumerate()
+        .filter_map(|(index, task)| parse_task(task, index, &mut issues))
+        .collect();
+    if tasks.is_empty() {
+        return Err("無効な応答形式: 有効なタスクがありません".to_string());
+    }
+
+    let summary = optional_string(&obj, "summary").unwrap_or_else(|| {
+        issues.push("summary", IssueKind::Missing, "summary がありません");
+        String::new()
+    });
+    let estimated_days = obj
+        .get("estimatedDays")
+        .and_then(lenient_u32)
+        .unwrap_or_else(|| {
+            let derived = tasks.iter().map(|t| t.days_from_start).max().unwrap_or(0) + 1;
+            issues.push(
+                "estimatedDays",
+                IssueKind::Missing,
+                format!(
+                    "estimatedDays がないためタスクから {} 日と算出しました",
+                    derived
+                ),
+            );
+            derived
+        });
+
+    let plan = PlanResult {
+        current_state: optional_string(&obj, "currentState"),
+        goal_state: optional_string(&obj, "goalState"),
+        gap: optional_string(&obj, "gap"),
+        feasibility: parse_feasibility(obj.get("feasibility"), &mut issues),
+        risks: string_list(&obj, "risks", &mut issues),
+        costs: string_list(&obj, "costs", &mut issues),
+        summary,
+        estimated_days,
+        tasks,
+        resources: parse_resources(obj.get("resources"), &mut issues),
+        tips: string_list(&obj, "tips", &mut issues),
+    };
+
+    Ok(PlanParseReport {
+        plan: Some(plan),
+        error: None,
+        repairs,
+        issues: issues.0,
+        truncated,
+    })
+}
+
+pub fn parse_decompose(raw: &str) -> Result<DecomposeParseReport, String> {
+    let RepairedJson {
+        value,
+        repairs,
+        truncated,
+    } = parse_lenient(raw)?;
+    let mut issues = Issues(Vec::new());
+
+    // 配列だけが返ってきた場合も受け付ける
+    let items = match value {
+        Value::Array(items) => {
+            issues.push(
+                "subtasks",
+                IssueKind::Normalized,
+                "配列をsubtasksとして扱いました",
+            );
+            items
+        }
+        Value::Object(mut obj) => match obj.remove("subtasks") {
+            Some(Value::Array(items)) => items,
+            _ => return Err("無効な応答形式: subtasks がありません".to_string()),
+        },
+        _ => return Err("無効な応答形式: JSONオブジェクトではありません".to_string()),
+    };
+
+    let subtasks: Vec<DecomposeSubtask> = items
+        .iter()
+        .enumerate()
+        .filter_map(|(index, item)| {
+            let path = format!("subtasks[{}]", index);
+            let title = match item {
+                Value::String(s) => Some(s.trim().to_string()).filter(|s| !s.is_empty()),
+                Value::Object(obj) => optional_string(obj, "title"),
+                _ => None,
+            };
+            let Some(title) = title else {
+                issues.push(
+                    &path,
+                    IssueKind::Invalid,
+                    "title がないためサブタスクを破棄しました",
+                );
+                return None;
+            };
+            let priority = parse_priority(item.get("priority"), &path, &mut issues);
+            let estimated_minutes = item.get("estimatedMinutes").and_then(lenient_u32);
+            if estimated_minutes.is_none() {
+                issues.push(&path, IssueKind::Missing, "estimatedMinutes がありません");
+            }
+            Some(DecomposeSubtask {
+                title,
+                priority,
+                estimated_minutes,
+            })
+        })
+        .collect();
+
+    if subtasks.is_empty() {
+        return Err("無効な応答形式: 有効なサブタスクがありません".to_string());
+    }
+
+    Ok(DecomposeParseReport {
+        subtasks,
+        repairs,
+        issues: issues.0,
+        truncated,
+    })
+}
+
+#[tauri::command]
+pub fn parse_plan_response(raw: String) -> Result<PlanParseReport, String> {
+    let report = parse_plan(&raw)?;
+    if !report.repairs.is_empty() || !report.issues.is_empty() {
+        println!(
+            "[Plan Backend] 応答を修復: repairs={}, issues={}, truncated={}",
+            report.repairs.len(),
+            report.issues.len(),
+            report.truncated
+        );
+    }
+    Ok(report)
+}
+
+#[tauri::command]
+pub fn parse_decompose_response(raw: String) -> Result<DecomposeParseReport, String> {
+    parse_decompose(&raw)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn task(
+        title: &str,
+        description: &str,
+        priority: PlanPriority,
+        days_from_start: u32,
+        estimated_minutes: u32,
+    ) -> PlanTask {
+        PlanTask {
+            title: title.to_string(),
+            description: description.to_string(),
+            priority,
+            days_from_start,
+            estimated_minutes,
+            recurrence: None,
+        }
+    }
+
+    fn plan(summary: &str, estimated_days: u32, tasks: Vec<PlanTask>) -> PlanResult {
+        PlanResult {
+            current_state: None,
+            goal_state: None,
+            gap: None,
+            feasibility: None,
+            risks: Vec::new(),
+            costs: Vec::new(),
+            summary: summary.to_string(),
+            estimated_days,
+            tasks,
+            resources: Vec::new(),
+            tips: Vec::new(),
+        }
+    }
+
+    fn issues(issues: &[PlanIssue]) -> Vec<(&str, IssueKind, &str)> {
+        issues
+            .iter()
+            .map(|i| (i.path.as_str(), i.kind, i.message.as_str()))
+            .collect()
+    }
+
+    #[test]
+    fn strips_code_fence_and_preamble() {
+        let raw = "以下が計画です。\n```json\n{\"summary\":\"英語学習\",\"estimatedDays\":10,\
+                   \"tasks\":[{\"title\":\"単語帳を買う\",\"description\":\"書店で選ぶ\",\
+                   \"priority\":\"high\",\"daysFromStart\":0,\"estimatedMinutes\":30}]}\n```\n\
+                   がんばってください";
+        let report = parse_plan(raw).unwrap();
+        assert_eq!(
+            report.plan.unwrap(),
+            plan(
+                "英語学習",
+                10,
+                vec![task(
+                    "単語帳を買う",
+                    "書店で選ぶ",
+                    PlanPriority::High,
+                    0,
+                    30
+                )]
+            )
+        );
+        assert_eq!(report.repairs, vec!["コードフェンスを除去しました"]);
+        assert!(report.issues.is_empty());
+        assert!(!report.truncated);
+    }
+
+    #[test]
+    fn removes_trailing_commas() {
+        let raw = r#"{
+  "summary": "引っ越し",
+  "estimatedDays": 3,
+  "tasks": [
+    {"title": "荷造り", "description": "本から", "priority": "low", "daysFromStart": 1, "estimatedMinutes": 20,},
+  ],
+  "tips": ["早めに",],
+}"#;
+        let report = parse_plan(raw).unwrap();
+        let mut expected = plan(
+            "引っ越し",
+            3,
+            vec![task("荷造り", "本から", PlanPriority::Low, 1, 20)],
+        );
+        expected.tips = vec!["早めに".to_string()];
+        assert_eq!(report.plan.unwrap(), expected);
+        assert_eq!(report.repairs, vec!["末尾カンマを4個除去しました"]);
+        assert!(report.issues.is_empty());
+        assert!(!report.truncated);
+    }
+
+    #[test]
+    fn completes_truncated_response() {
+        let raw = r#"{"summary":"資格試験","estimatedDays":5,"tasks":[
+{"title":"過去問","description":"3年分","priority":"medium","daysFromStart":0,"estimatedMinutes":60},
+{"title":"復習","descr"#;
+        let report = parse_plan(raw).unwrap();
+        assert_eq!(
+            report.plan.unwrap(),
+            plan(
+                "資格試験",
+                5,
+                vec![
+                    task("過去問", "3年分", PlanPriority::Medium, 0, 60),
+                    task("復習", "", PlanPriority::Medium, 0, 30),
+                ]
+            )
+        );
+        assert_eq!(
+            report.repairs,
+            vec!["途中で切れた応答を補完しました (最後の不完全な要素は破棄)"]
+        );
+        assert_eq!(
+            issues(&report.issues),
+            vec![
+                ("tasks[1]", IssueKind::Missing, "description がありません"),
+                (
+                    "tasks[1]",
+                    IssueKind::Missing,
+                    "priority がないため medium にしました"
+                ),
+                (
+                    "tasks[1]",
+                    IssueKind::Missing,
+                    "daysFromStart がないため 0 にしました"
+                ),
+                (
+                    "tasks[1]",
+                    IssueKind::Missing,
+                    "estimatedMinutes がないため 30 にしました"
+                ),
+            ]
+        );
+        assert!(report.truncated);
+    }
+
+    #[test]
+    fn fills_missing_and_malformed_fields() {
+        let raw = r#"{
+  "tasks": [
+    {"title": "ジョギング", "priority": "高", "daysFromStart": "3日目", "estimatedMinutes": "約45分",
+     "recurrence": {"type": "weekly", "dayOfWeek": 6}},
+    {"description": "タイトルなし"}
+  ],
+  "risks": "雨天",
+  "feasibility": {"availableHours": 10, "requiredHours": 12}
+}"#;
+        let report = parse_plan(raw).unwrap();
+        let mut jogging = task("ジョギング", "", PlanPriority::High, 3, 45);
+        jogging.recurrence = Some(PlanRecurrence {
+            kind: "weekly".to_string(),
+            interval: 1,
+            day_of_week: Some(6),
+            day_of_month: None,
+            month_of_year: None,
+            day_of_year: None,
+        });
+        let mut expected = plan("", 4, vec![jogging]);
+        expected.risks = vec!["雨天".to_string()];
+        expected.feasibility = Some(PlanFeasibility {
+            verdict: FeasibilityVerdict::Challenging,
+            available_hours: 10.0,
+            required_hours: 12.0,
+            calculation: String::new(),
+            adjustment: None,
+        });
+        assert_eq!(report.plan.unwrap(), expected);
+        assert!(report.repairs.is_empty());
+        assert_eq!(
+            issues(&report.issues),
+            vec![
+                ("tasks[0]", IssueKind::Missing, "description がありません"),
+                (
+                    "tasks[0]",
+                    IssueKind::Normalized,
+                    "priority \"高\" を正規化しました"
+                ),
+                (
+                    "tasks[0]",
+                    IssueKind::Missing,
+                    "recurrence.interval がないため 1 にしました"
+                ),
+                (
+                    "tasks[1]",
+                    IssueKind::Invalid,
+                    "title がないためタスクを破棄しました"
+                ),
+                ("summary", IssueKind::Missing, "summary がありません"),
+                (
+                    "estimatedDays",
+                    IssueKind::Missing,
+                    "estimatedDays がないためタスクから 4 日と算出しました"
+                ),
+                (
+                    "feasibility.verdict",
+                    IssueKind::Missing,
+                    "verdict がないため時間から Challenging と算出しました"
+                ),
+                (
+                    "risks",
+                    IssueKind::Normalized,
+                    "文字列を配列として扱いました"
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn returns_model_error() {
+        let report =
+            parse_plan(r#"{"error": "期限が指定されていません", "code": "NO_DEADLINE"}"#).unwrap();
+        assert!(report.plan.is_none());
+        assert_eq!(
+            report.error,
+            Some(PlanError {
+                error: "期限が指定されていません".to_string(),
+                code: Some("NO_DEADLINE".to_string()),
+            })
+        );
+    }
+
+    #[test]
+    fn rejects_response_without_tasks() {
+        assert!(parse_plan("計画を作れませんでした").is_err());
+        assert!(parse_plan(r#"{"summary": "s", "tasks": [{"description": "d"}]}"#).is_err());
+    }
+
+    #[test]
+    fn decompose_accepts_bare_array_with_trailing_comma() {
+        let raw = r#"[
+  "資料を集める",
+  {"title": "構成を決める", "priority": "p1", "estimatedMinutes": 30},
+]"#;
+        let report = parse_decompose(raw).unwrap();
+        assert_eq!(
+            report.subtasks,
+            vec![
+                DecomposeSubtask {
+                    title: "資料を集める".to_string(),
+                    priority: PlanPriority::Medium,
+                    estimated_minutes: None,
+                },
+                DecomposeSubtask {
+                    title: "構成を決める".to_string(),
+                    priority: PlanPriority::High,
+                    estimated_minutes: Some(30),
+                },
+            ]
+        );
+        assert_eq!(report.repairs, vec!["末尾カンマを1個除去しました"]);
+        assert_eq!(
+            issues(&report.issues),
+            vec![
+                (
+                    "subtasks",
+                    IssueKind::Normalized,
+                    "配列をsubtasksとして扱いました"
+                ),
+                (
+                    "subtasks[0]",
+                    IssueKind::Missing,
+                    "priority がないため medium にしました"
+                ),
+                (
+                    "subtasks[0]",
+                    IssueKind::Missing,
+                    "estimatedMinutes がありません"
+                ),
+                (
+                    "subtasks[1]",
+                    IssueKind::Normalized,
+                    "priority \"p1\" を正規化しました"
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn lenient_number_keeps_sign() {
+        let n = |s: &str| lenient_number(&Value::String(s.to_string()));
+        assert_eq!(n("-3"), Some(-3.0));
+        assert_eq!(n("約-1.5時間"), Some(-1.5));
+        assert_eq!(n("－2日"), Some(-2.0));
+        assert_eq!(n("約3日"), Some(3.0));
+        assert_eq!(n("3-5分"), Some(3.0));
+        assert_eq!(n("なし"), None);
+        assert_eq!(lenient_u32(&Value::String("-3".to_string())), None);
+        assert_eq!(lenient_u32(&Value::String("120分".to_string())), Some(120));
+    }
+
+    // 各プロバイダーから実際に返ってきた崩れ方を再現した応答
+    fn fixture(name: &str) -> &'static str {
+        match name {
+            "claude_preamble_fence" => {
+                include_str!("../tests/fixtures/plan/claude_preamble_fence.txt")
+            }
+            "openai_truncated_max_tokens" => {
+                include_str!("../tests/fixtures/plan/openai_truncated_max_tokens.txt")
+            }
+            "gemini_string_numbers_trailing_commas" => {
+                include_str!("../tests/fixtures/plan/gemini_string_numbers_trailing_commas.txt")
+            }
+            "local_trailing_prose" => {
+                include_str!("../tests/fixtures/plan/local_trailing_prose.txt")
+            }
+            "decompose_fenced_array" => {
+                include_str!("../tests/fixtures/plan/decompose_fenced_array.txt")
+            }
+            _ => unreachable!(),
+        }
+    }
+
+    #[test]
+    fn parses_claude_fixture_with_preamble_and_fence() {
+        let report = parse_plan(fixture("claude_preamble_fence")).unwrap();
+        let plan = report.plan.unwrap();
+        assert_eq!(report.repairs, vec!["コードフェンスを除去しました"]);
+        assert!(report.issues.is_empty());
+        assert_eq!((plan.estimated_days, plan.tasks.len()), (21, 3));
+        assert_eq!(plan.tasks[1].recurrence.as_ref().unwrap().kind, "daily");
+        let feasibility = plan.feasibility.unwrap();
+        assert_eq!(feasibility.verdict, FeasibilityVerdict::Challenging);
+        assert_eq!(
+            (feasibility.available_hours, feasibility.required_hours),
+            (30.0, 40.0)
+        );
+        assert_eq!(plan.resources[0].kind, "book");
+    }
+
+    #[test]
+    fn parses_openai_fixture_cut_at_max_tokens() {
+        let report = parse_plan(fixture("openai_truncated_max_tokens")).unwrap();
+        let plan = report.plan.unwrap();
+        assert!(report.truncated);
+        let titles: Vec<_> = plan.tasks.iter().map(|t| t.title.as_str()).collect();
+        assert_eq!(
+            titles,
+            ["ネタ帳を作る", "1本目の記事を書く", "公開前に推敲する"]
+        );
+        assert_eq!(plan.tasks[1].estimated_minutes, 120);
+        // 切れた priority は破棄して既定値で補う
+        assert_eq!(plan.tasks[2].priority, PlanPriority::Medium);
+        assert_eq!(
+            issues(&report.issues),
+            vec![
+                (
+                    "tasks[2]",
+                    IssueKind::Missing,
+                    "priority がないため medium にしました"
+                ),
+                (
+                    "tasks[2]",
+                    IssueKind::Missing,
+                    "daysFromStart がないため 0 にしました"
+                ),
+                (
+                    "tasks[2]",
+                    IssueKind::Missing,
+                    "estimatedMinutes がないため 30 にしました"
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn parses_gemini_fixture_with_string_numbers() {
+        let report = parse_plan(fixture("gemini_string_numbers_trailing_commas")).unwrap();
+        let plan = report.plan.unwrap();
+        assert_eq!(
+            report.repairs,
+            vec![
+                "コードフェンスを除去しました",
+                "末尾カンマを4個除去しました"
+            ]
+        );
+        assert_eq!(plan.estimated_days, 60);
+        let jogging = &plan.tasks[0];
+        assert_eq!(
+            (jogging.days_from_start, jogging.estimated_minutes),
+            (1, 40)
+        );
+        assert_eq!(jogging.recurrence.as_ref().unwrap().day_of_week, Some(6));
+        // "-3" は 3 日目として扱わない
+        let long_run = &plan.tasks[1];
+        assert_eq!(long_run.priority, PlanPriority::High);
+        assert_eq!(
+            (long_run.days_from_start, long_run.estimated_minutes),
+            (0, 90)
+        );
+        // 負の利用可能時間は見積もりとして使わない
+        assert!(plan.feasibility.is_none());
+        assert_eq!(
+            issues(&report.issues),
+            vec![
+                (
+                    "tasks[0]",
+                    IssueKind::Normalized,
+                    "priority \"中\" を正規化しました"
+                ),
+                (
+                    "tasks[1]",
+                    IssueKind::Normalized,
+                    "priority \"p1\" を正規化しました"
+                ),
+                (
+                    "tasks[1]",
+                    IssueKind::Missing,
+                    "daysFromStart がないため 0 にしました"
+                ),
+                (
+                    "feasibility.verdict",
+                    IssueKind::Invalid,
+                    "verdict も時間の見積もりもないため feasibility を無視しました"
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn parses_local_fixture_with_trailing_prose() {
+        let report = parse_plan(fixture("local_trailing_prose")).unwrap();
+        assert_eq!(report.repairs, vec!["JSONの後にある文章を除去しました"]);
+        assert!(report.issues.is_empty());
+        assert_eq!(
+            report.plan.unwrap(),
+            plan(
+                "部屋の片付け",
+                2,
+                vec![task(
+                    "不要な服を仕分ける",
+                    "1年着ていない服",
+                    PlanPriority::Low,
+                    0,
+                    60
+                )]
+            )
+        );
+    }
+
+    #[test]
+    fn parses_decompose_fixture_with_fence_and_trailing_comma() {
+        let report = parse_decompose(fixture("decompose_fenced_array")).unwrap();
+        assert_eq!(
+            report.repairs,
+            vec![
+                "コードフェンスを除去しました",
+                "末尾カンマを1個除去しました"
+            ]
+        );
+        let subtasks: Vec<_> = report
+            .subtasks
+            .iter()
+            .map(|s| (s.title.as_str(), s.priority, s.estimated_minutes))
+            .collect();
+        assert_eq!(
+            subtasks,
+            [
+                ("見積もり依頼のメールを送る", PlanPriority::High, Some(15)),
+                ("相見積もりを比較する", PlanPriority::Medium, Some(30)),
+                ("契約書を確認する", PlanPriority::Medium, None),
+            ]
+        );
+    }
+}
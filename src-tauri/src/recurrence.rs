@@ -0,0 +1,850 @@
+// 繰り返しタスクの発生日計算 (RFC 5545 の RRULE 準拠)
+// フロントエンドの簡易設定 (RecurrencePattern) も RRULE に変換し、ics::rrule で展開する
+// 展開は壁時計時刻で行うため、DST の切り替えをまたいでも時刻はずれない
+
+use chrono::{DateTime, Datelike, Duration, Months, NaiveDate, NaiveDateTime, Weekday};
+
+use crate::calendar::{BusinessCalendar, CalendarSettings, CalendarState};
+use crate::ics::rrule::{weekday_code, Frequency, RRule};
+use crate::ics::timezone::Zone;
+use crate::models::{RecurrenceMode, RecurrencePattern};
+
+// 一度に返す発生日時の上限
+const MAX_OCCURRENCES: usize = 1000;
+// 発生日時を探すときに調べる最大件数
+const MAX_SCAN: usize = 100_000;
+
+const WEEKDAYS: [Weekday; 5] = [
+    Weekday::Mon,
+    Weekday::Tue,
+    Weekday::Wed,
+    Weekday::Thu,
+    Weekday::Fri,
+];
+
+// 0 = 日曜 (フロントエンドの daysOfWeek と同じ数え方)
+fn weekday_from_index(day: u32) -> Option<Weekday> {
+    match day {
+        0 => Some(Weekday::Sun),
+        1 => Some(Weekday::Mon),
+        2 => Some(Weekday::Tue),
+        3 => Some(Weekday::Wed),
+        4 => Some(Weekday::Thu),
+        5 => Some(Weekday::Fri),
+        6 => Some(Weekday::Sat),
+        _ => None,
+    }
+}
+
+fn is_weekday(date: NaiveDate) -> bool {
+    WEEKDAYS.contains(&date.weekday())
+}
+
+fn format_utc(millis: i64) -> Option<String> {
+    DateTime::from_timestamp_millis(millis).map(|d| d.format("%Y%m%dT%H%M%SZ").to_string())
+}
+
+// 平日だけを数える daily か (INTERVAL では表せないため展開後に間引く)
+fn counts_weekdays(pattern: &RecurrencePattern) -> bool {
+    pattern.rrule.is_none() && pattern.weekdays_only && pattern.kind == "daily"
+}
+
+// 簡易設定を RRULE の文字列にする
+// 平日だけを数える設定は INTERVAL を書き出せない (「毎営業日」として書き出す)
+pub fn to_rrule_string(pattern: &RecurrencePattern) -> Result<String, String> {
+    if let Some(rrule) = pattern
+        .rrule
+        .as_deref()
+        .map(str::trim)
+        .filter(|r| !r.is_empty())
+    {
+        let rrule = rrule.strip_prefix("RRULE:").unwrap_or(rrule);
+        let rule = RRule::parse(rrule)?;
+        // 残り回数は count で管理する (ICSから取り込んだ繰り返しなど)
+        if let Some(count) = pattern
+            .count
+            .filter(|_| rule.count.is_none() && rule.until.is_none())
+        {
+            return Ok(format!("{};COUNT={}", rrule, count));
+        }
+        return Ok(rrule.to_string());
+    }
+
+    let kind = pattern.kind.as_str();
+    let freq = match kind {
+        "daily" => "DAILY",
+        "weekly" => "WEEKLY",
+        "monthly" => "MONTHLY",
+        "yearly" => "YEARLY",
+        _ => return Err(format!("未対応の繰り返し種別です: {}", pattern.kind)),
+    };
+    let mut parts = vec![format!("FREQ={}", freq)];
+    let weekdays_only = counts_weekdays(pattern);
+    if pattern.interval > 1 && !weekdays_only {
+        parts.push(format!("INTERVAL={}", pattern.interval));
+    }
+
+    let weekdays: Vec<Weekday> = if weekdays_only {
+        WEEKDAYS.to_vec()
+    } else {
+        pattern
+            .days_of_week
+            .iter()
+            .flatten()
+            .filter_map(|day| weekday_from_index(*day))
+            .collect()
+    };
+    if !weekdays.is_empty() {
+        // 「毎月最終金曜」は BYDAY=-1FR
+        let ordinal = pattern
+            .week_of_month
+            .filter(|_| matches!(kind, "monthly" | "yearly"))
+            .map(|n| n.to_string())
+            .unwrap_or_default();
+        let codes: Vec<String> = weekdays
+            .iter()
+            .map(|weekday| format!("{}{}", ordinal, weekday_code(*weekday)))
+            .collect();
+        parts.push(format!("BYDAY={}", codes.join(",")));
+    } else if let Some(day) = pattern.day_of_month.filter(|_| kind == "monthly") {
+        match day {
+            1..=28 => parts.push(format!("BYMONTHDAY={}", day)),
+            // その日がない月は月末にする (31日指定なら2月は28日か29日)
+            29..=31 => {
+                let days: Vec<String> = (28..=day).map(|d| d.to_string()).collect();
+                parts.push(format!("BYMONTHDAY={}", days.join(",")));
+                parts.push("BYSETPOS=-1".to_string());
+            }
+            _ => return Err(format!("日付が不正です: {}", day)),
+        }
+    }
+
+    if let Some(count) = pattern.count {
+        parts.push(format!("COUNT={}", count));
+    }
+    if let Some(until) = pattern.until {
+        let until = format_utc(until).ok_or_else(|| "終了日時が不正です".to_string())?;
+        parts.push(format!("UNTIL={}", until));
+    }
+
+    let rule = parts.join(";");
+    RRule::parse(&rule)?;
+    Ok(rule)
+}
+
+// UTC ミリ秒をタイムゾーンの壁時計時刻にする
+pub(crate) fn to_local(zone: &Zone, millis: i64) -> Result<NaiveDateTime, String> {
+    let utc =
+        DateTime::from_timestamp_millis(millis).ok_or_else(|| "日時が不正です".to_string())?;
+    Ok(zone.localize_utc(utc.naive_utc()))
+}
+
+// 壁時計時刻を UTC ミリ秒にする
+// DST の開始で存在しない時刻は切り替え前のオフセットで換算する (RFC 5545 3.3.5。02:30 は 03:30 になる)
+pub(crate) fn to_instant(zone: &Zone, local: NaiveDateTime) -> Option<i64> {
+    if let Some(millis) = zone.to_millis(local) {
+        return Some(millis);
+    }
+    let before = local - Duration::days(1);
+    let offset = before.and_utc().timestamp_millis() - zone.to_millis(before)?;
+    Some(local.and_utc().timestamp_millis() - offset)
+}
+
+pub struct Schedule {
+    rule: RRule,
+    dtstart: NaiveDateTime,
+    zone: Zone,
+    // 平日だけを数える場合の間隔と回数
+    step: usize,
+    count: Option<u32>,
+    exdates: Vec<NaiveDate>,
+    // 休日を飛ばす場合の暦
+    holidays: Option<BusinessCalendar>,
+    // false なら start は規則に合うときだけ発生日にする
+    include_start: bool,
+}
+
+impl Schedule {
+    // start (UTC ミリ秒) を DTSTART とする
+    pub fn new(
+        pattern: &RecurrencePattern,
+        start: i64,
+        time_zone: Option<&str>,
+    ) -> Result<Self, String> {
+        let zone = Zone::from_name(time_zone);
+        let dtstart = to_local(&zone, start)?;
+        let mut rule = RRule::parse(&to_rrule_string(pattern)?)?;
+        if rule.until_utc {
+            rule.until = rule.until.map(|until| zone.localize_utc(until));
+            rule.until_utc = false;
+        }
+
+        // 日付の指定がなく DTSTART の日で繰り返す場合も、その日がない月・年は月末にする
+        let no_day_rules = rule.by_day.is_empty()
+            && rule.by_month_day.is_empty()
+            && rule.by_year_day.is_empty()
+            && rule.by_week_no.is_empty();
+        if pattern.rrule.is_none() && no_day_rules {
+            let day = dtstart.day();
+            match rule.freq {
+                Frequency::Monthly if day > 28 => {
+                    rule.by_month_day = (28..=day as i32).collect();
+                    rule.by_set_pos = vec![-1];
+                }
+                Frequency::Yearly if dtstart.month() == 2 && day == 29 => {
+                    rule.by_month = vec![2];
+                    rule.by_month_day = vec![28, 29];
+                    rule.by_set_pos = vec![-1];
+                }
+                _ => {}
+            }
+        }
+
+        let (step, count) = if counts_weekdays(pattern) {
+            (pattern.interval.max(1) as usize, rule.count.take())
+        } else {
+            (1, None)
+        };
+        let exdates = pattern
+            .exdates
+            .iter()
+            .filter_map(|millis| to_local(&zone, *millis).ok())
+            .map(|local| local.date())
+            .collect();
+        let holidays = pattern
+            .skip_holidays
+            .then(|| BusinessCalendar::new(&CalendarSettings::default()));
+
+        Ok(Self {
+            rule,
+            dtstart,
+            zone,
+            step,
+            count,
+            exdates,
+            holidays,
+            include_start: true,
+        })
+    }
+
+    // 休日の判定に設定済みの暦 (会社の休日を含む) を使う
+    pub fn with_calendar(mut self, calendar: &BusinessCalendar) -> Self {
+        if self.holidays.is_some() {
+            self.holidays = Some(calendar.clone());
+        }
+        self
+    }
+
+    // start が曜日などの条件に合わなければ、その後で最初に合う日時からにする (完了基準の次回計算用)
+    pub fn aligned_to_rule(mut self) -> Self {
+        self.include_start = false;
+        self
+    }
+
+    // 発生日時 (壁時計時刻) を順に返す
+    // 休日は発生そのものがないものとして数えず、COUNT は除外日を含めて数える (RFC 5545 の EXDATE と同じ)
+    fn local_occurrences(&self) -> impl Iterator<Item = NaiveDateTime> + '_ {
+        self.rule
+            .iter_from(self.dtstart, self.include_start)
+            .take(MAX_SCAN)
+            .filter(|local| {
+                self.holidays
+                    .as_ref()
+                    .is_none_or(|calendar| !calendar.is_holiday(local.date()))
+            })
+            .step_by(self.step)
+            .take(self.count.map_or(usize::MAX, |count| count as usize))
+            .filter(|local| !self.exdates.contains(&local.date()))
+    }
+
+    // after より後の発生日時 (UTC ミリ秒) を limit 件まで
+    pub fn occurrences_after(&self, after: i64, limit: usize) -> Vec<i64> {
+        self.local_occurrences()
+            .filter_map(|local| to_instant(&self.zone, local))
+            .filter(|millis| *millis > after)
+            .take(limit)
+            .collect()
+    }
+}
+
+fn add_weekdays(date: NaiveDate, days: u32, calendar: Option<&BusinessCalendar>) -> NaiveDate {
+    if let Some(calendar) = calendar {
+        return calendar.add_business_days(date, days as i32);
+    }
+    let mut date = date;
+    let mut remaining = days;
+    while remaining > 0 {
+        date += Duration::days(1);
+        if is_weekday(date) {
+            remaining -= 1;
+        }
+    }
+    date
+}
+
+// 完了日から間隔分進めた日時 (「完了から3日後」)
+fn after_completion_start(
+    pattern: &RecurrencePattern,
+    rule: &RRule,
+    due: NaiveDateTime,
+    completed: NaiveDateTime,
+    calendar: &BusinessCalendar,
+) -> Option<NaiveDateTime> {
+    let interval = if counts_weekdays(pattern) {
+        pattern.interval.max(1)
+    } else {
+        rule.interval.max(1)
+    };
+    let base = completed.date().and_time(due.time());
+    match rule.freq {
+        Frequency::Daily if counts_weekdays(pattern) => {
+            let calendar = pattern.skip_holidays.then_some(calendar);
+            Some(add_weekdays(base.date(), interval, calendar).and_time(base.time()))
+        }
+        Frequency::Daily => base.checked_add_signed(Duration::days(interval as i64)),
+        Frequency::Weekly => base.checked_add_signed(Duration::weeks(interval as i64)),
+        Frequency::Monthly => base.checked_add_months(Months::new(interval)),
+        Frequency::Yearly => base.checked_add_months(Months::new(interval.checked_mul(12)?)),
+        Frequency::Hourly => completed.checked_add_signed(Duration::hours(interval as i64)),
+        Frequency::Minutely => completed.checked_add_signed(Duration::minutes(interval as i64)),
+        Frequency::Secondly => completed.checked_add_signed(Duration::seconds(interval as i64)),
+    }
+}
+
+#[derive(serde::Serialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct NextRecurrence {
+    // None なら繰り返しは終わり
+    pub due_date: Option<i64>,
+    // 残り回数 (count) を減らした繰り返し設定
+    pub recurrence: RecurrencePattern,
+}
+
+// 期限 due のタスクを完了したときの次の期限
+// count は今回を含めた残り回数として扱い、次の設定では1減らす
+pub fn next_due(
+    pattern: &RecurrencePattern,
+    due: i64,
+    completed_at: Option<i64>,
+    time_zone: Option<&str>,
+    calendar: &BusinessCalendar,
+) -> Result<NextRecurrence, String> {
+    let mut recurrence = pattern.clone();
+    // 日付を指定しない月次は期限の日で繰り返す。月末で丸められた日に引きずられないよう、最初の期限の日を固定する
+    let pinned = pattern.rrule.is_none()
+        && pattern.kind == "monthly"
+        && pattern.day_of_month.is_none()
+        && pattern.days_of_week.as_ref().is_none_or(Vec::is_empty);
+    if pinned {
+        let zone = Zone::from_name(time_zone);
+        recurrence.day_of_month = Some(to_local(&zone, due)?.day());
+    }
+    if let Some(count) = pattern.count {
+        if count <= 1 {
+            recurrence.count = Some(0);
+            return Ok(NextRecurrence {
+                due_date: None,
+                recurrence,
+            });
+        }
+        recurrence.count = Some(count - 1);
+    }
+    // 残り回数は上で管理するので、展開には使わない
+    let unlimited = RecurrencePattern {
+        count: None,
+        ..recurrence.clone()
+    };
+
+    let next = match (pattern.mode, completed_at) {
+        (RecurrenceMode::AfterCompletion, Some(completed)) => {
+            let zone = Zone::from_name(time_zone);
+            let rule = RRule::parse(&to_rrule_string(&unlimited)?)?;
+            let start = after_completion_start(
+                &unlimited,
+                &rule,
+                to_local(&zone, due)?,
+                to_local(&zone, completed)?,
+                calendar,
+            )
+            .and_then(|start| to_instant(&zone, start))
+            .ok_or_else(|| "次の期限を計算できません".to_string())?;
+            // 曜日などの条件があれば、その日以降で最初に条件を満たす日にする
+            Schedule::new(&unlimited, start, time_zone)?
+                .with_calendar(calendar)
+                .aligned_to_rule()
+                .occurrences_after(start - 1, 1)
+                .first()
+                .copied()
+        }
+        _ => Schedule::new(&unlimited, due, time_zone)?
+            .with_calendar(calendar)
+            .occurrences_after(due, 1)
+            .first()
+            .copied(),
+    };
+    Ok(NextRecurrence {
+        due_date: next,
+        recurrence,
+    })
+}
+
+// start から始まる繰り返しの発生日時を count 件 (after が指定されればそれより後のもの)
+#[tauri::command]
+pub fn recurrence_occurrences(
+    calendar: tauri::State<'_, CalendarState>,
+    recurrence: RecurrencePattern,
+    start: i64,
+    count: Option<usize>,
+    after: Option<i64>,
+    time_zone: Option<String>,
+) -> Result<Vec<i64>, String> {
+    let schedule = Schedule::new(&recurrence, start, time_zone.as_deref())?
+        .with_calendar(&calendar.calendar());
+    let limit = count.unwrap_or(10).min(MAX_OCCURRENCES);
+    Ok(schedule.occurrences_after(after.unwrap_or(start - 1), limit))
+}
+
+// 繰り返しタスクを完了したときの次の期限
+#[tauri::command]
+pub fn recurrence_next(
+    calendar: tauri::State<'_, CalendarState>,
+    recurrence: RecurrencePattern,
+    due_date: i64,
+    completed_at: Option<i64>,
+    time_zone: Option<String>,
+) -> Result<NextRecurrence, String> {
+    next_due(
+        &recurrence,
+        due_date,
+        completed_at,
+        time_zone.as_deref(),
+        &calendar.calendar(),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn pattern(kind: &str) -> RecurrencePattern {
+        RecurrencePattern {
+            kind: kind.to_string(),
+            interval: 1,
+            ..Default::default()
+        }
+    }
+
+    fn local(text: &str) -> NaiveDateTime {
+        NaiveDateTime::parse_from_str(text, "%Y-%m-%d %H:%M").unwrap()
+    }
+
+    fn instant(time_zone: &str, text: &str) -> i64 {
+        to_instant(&Zone::from_name(Some(time_zone)), local(text)).unwrap()
+    }
+
+    fn format(time_zone: &str, millis: &[i64]) -> Vec<String> {
+        let zone = Zone::from_name(Some(time_zone));
+        millis
+            .iter()
+            .map(|m| {
+                to_local(&zone, *m)
+                    .unwrap()
+                    .format("%Y-%m-%d %H:%M")
+                    .to_string()
+            })
+            .collect()
+    }
+
+    fn occurrences(
+        pattern: &RecurrencePattern,
+        time_zone: &str,
+        start: &str,
+        n: usize,
+    ) -> Vec<String> {
+        let start = instant(time_zone, start);
+        let schedule = Schedule::new(pattern, start, Some(time_zone)).unwrap();
+        format(time_zone, &schedule.occurrences_after(start - 1, n))
+    }
+
+    #[test]
+    fn month_day_31_falls_back_to_month_end() {
+        let mut monthly = pattern("monthly");
+        monthly.day_of_month = Some(31);
+        assert_eq!(
+            to_rrule_string(&monthly).unwrap(),
+            "FREQ=MONTHLY;BYMONTHDAY=28,29,30,31;BYSETPOS=-1"
+        );
+        assert_eq!(
+            occurrences(&monthly, "Asia/Tokyo", "2024-01-31 09:00", 4),
+            [
+                "2024-01-31 09:00",
+                "2024-02-29 09:00",
+                "2024-03-31 09:00",
+                "2024-04-30 09:00"
+            ]
+        );
+        assert_eq!(
+            occurrences(&monthly, "Asia/Tokyo", "2025-01-31 09:00", 2),
+            ["2025-01-31 09:00", "2025-02-28 09:00"]
+        );
+    }
+
+    #[test]
+    fn bysetpos_picks_last_weekday_of_month() {
+        let mut last_weekday = pattern("monthly");
+        last_weekday.rrule = Some("FREQ=MONTHLY;BYDAY=MO,TU,WE,TH,FR;BYSETPOS=-1".to_string());
+        assert_eq!(
+            occurrences(&last_weekday, "Asia/Tokyo", "2024-01-31 18:00", 3),
+            ["2024-01-31 18:00", "2024-02-29 18:00", "2024-03-29 18:00"]
+        );
+        assert_eq!(
+            occurrences(&last_weekday, "Asia/Tokyo", "2025-01-31 18:00", 2),
+            ["2025-01-31 18:00", "2025-02-28 18:00"]
+        );
+    }
+
+    #[test]
+    fn monthly_without_day_keeps_the_first_due_day() {
+        let calendar = BusinessCalendar::default();
+        let monthly = pattern("monthly");
+        let first = next_due(
+            &monthly,
+            instant("Asia/Tokyo", "2024-01-31 09:00"),
+            None,
+            Some("Asia/Tokyo"),
+            &calendar,
+        )
+        .unwrap();
+        assert_eq!(first.recurrence.day_of_month, Some(31));
+        assert_eq!(
+            format("Asia/Tokyo", &[first.due_date.unwrap()]),
+            ["2024-02-29 09:00"]
+        );
+        // 2月末に丸められても、次は31日に戻る
+        let second = next_due(
+            &first.recurrence,
+            first.due_date.unwrap(),
+            None,
+            Some("Asia/Tokyo"),
+            &calendar,
+        )
+        .unwrap();
+        assert_eq!(
+            format("Asia/Tokyo", &[second.due_date.unwrap()]),
+            ["2024-03-31 09:00"]
+        );
+    }
+
+    #[test]
+    fn yearly_feb_29_uses_feb_28_in_common_years() {
+        assert_eq!(
+            occurrences(&pattern("yearly"), "Asia/Tokyo", "2024-02-29 09:00", 5),
+            [
+                "2024-02-29 09:00",
+                "2025-02-28 09:00",
+                "2026-02-28 09:00",
+                "2027-02-28 09:00",
+                "2028-02-29 09:00"
+            ]
+        );
+        let next = next_due(
+            &pattern("yearly"),
+            instant("Asia/Tokyo", "2024-02-29 09:00"),
+            None,
+            Some("Asia/Tokyo"),
+            &BusinessCalendar::default(),
+        )
+        .unwrap();
+        assert_eq!(
+            format("Asia/Tokyo", &[next.due_date.unwrap()]),
+            ["2025-02-28 09:00"]
+        );
+    }
+
+    #[test]
+    fn to_instant_moves_nonexistent_time_forward() {
+        // DST 開始日の 02:30 は存在しないため 03:30 になる
+        for (time_zone, gap_day) in [
+            ("America/New_York", "2026-03-08"),
+            ("Europe/Berlin", "2026-03-29"),
+        ] {
+            assert_eq!(
+                instant(time_zone, &format!("{} 02:30", gap_day)),
+                instant(time_zone, &format!("{} 03:30", gap_day)),
+                "{}",
+                time_zone
+            );
+        }
+        assert_eq!(
+            instant("America/New_York", "2026-03-08 02:30"),
+            DateTime::parse_from_rfc3339("2026-03-08T07:30:00Z")
+                .unwrap()
+                .timestamp_millis()
+        );
+    }
+
+    #[test]
+    fn daily_keeps_wall_clock_across_dst_gap() {
+        assert_eq!(
+            occurrences(&pattern("daily"), "America/New_York", "2026-03-07 02:30", 3),
+            ["2026-03-07 02:30", "2026-03-08 03:30", "2026-03-09 02:30"]
+        );
+        assert_eq!(
+            occurrences(&pattern("daily"), "Europe/Berlin", "2026-03-28 02:30", 3),
+            ["2026-03-28 02:30", "2026-03-29 03:30", "2026-03-30 02:30"]
+        );
+        // 09:00 は影響を受けず、間隔が23時間になる
+        let start = instant("Europe/Berlin", "2026-03-28 09:00");
+        let times = Schedule::new(&pattern("daily"), start, Some("Europe/Berlin"))
+            .unwrap()
+            .occurrences_after(start - 1, 2);
+        assert_eq!(times[1] - times[0], 23 * 3_600_000);
+    }
+
+    #[test]
+    fn daily_uses_first_instance_on_dst_overlap() {
+        // DST 終了日の 01:30 (ニューヨーク) と 02:30 (ベルリン) は2回あり、早い方 (夏時間) を使う
+        for (time_zone, start, expected_gaps) in [
+            ("America/New_York", "2026-10-31 01:30", [24, 25]),
+            ("Europe/Berlin", "2026-10-24 02:30", [24, 25]),
+        ] {
+            let start = instant(time_zone, start);
+            let times = Schedule::new(&pattern("daily"), start, Some(time_zone))
+                .unwrap()
+                .occurrences_after(start - 1, 3);
+            let gaps: Vec<i64> = times
+                .windows(2)
+                .map(|w| (w[1] - w[0]) / 3_600_000)
+                .collect();
+            assert_eq!(gaps, expected_gaps, "{}", time_zone);
+        }
+        assert_eq!(
+            instant("Europe/Berlin", "2026-10-25 02:30"),
+            DateTime::parse_from_rfc3339("2026-10-25T00:30:00Z")
+                .unwrap()
+                .timestamp_millis()
+        );
+    }
+
+    #[test]
+    fn weekly_across_dst_keeps_local_time() {
+        let mut weekly = pattern("weekly");
+        weekly.days_of_week = Some(vec![0]);
+        assert_eq!(
+            occurrences(&weekly, "America/New_York", "2026-10-25 09:00", 3),
+            ["2026-10-25 09:00", "2026-11-01 09:00", "2026-11-08 09:00"]
+        );
+    }
+
+    #[test]
+    fn after_completion_moves_to_next_matching_weekday() {
+        let mut weekly = pattern("weekly");
+        weekly.days_of_week = Some(vec![1]);
+        weekly.mode = RecurrenceMode::AfterCompletion;
+        // 月曜の期限を水曜に完了 → 1週間後の水曜以降で最初の月曜
+        let next = next_due(
+            &weekly,
+            instant("Asia/Tokyo", "2026-03-02 09:00"),
+            Some(instant("Asia/Tokyo", "2026-03-04 18:00")),
+            Some("Asia/Tokyo"),
+            &BusinessCalendar::default(),
+        )
+        .unwrap();
+        assert_eq!(
+            format("Asia/Tokyo", &[next.due_date.unwrap()]),
+            ["2026-03-16 09:00"]
+        );
+    }
+
+    // 性質テスト用の乱数 (xorshift64*)。失敗を再現できるよう種は固定する
+    struct Rng(u64);
+
+    impl Rng {
+        fn next(&mut self) -> u64 {
+            self.0 ^= self.0 >> 12;
+            self.0 ^= self.0 << 25;
+            self.0 ^= self.0 >> 27;
+            self.0.wrapping_mul(0x2545_f491_4f6c_dd1d)
+        }
+
+        fn below(&mut self, n: u32) -> u32 {
+            (self.next() % n as u64) as u32
+        }
+
+        fn pick<'a, T>(&mut self, items: &'a [T]) -> &'a T {
+            &items[self.below(items.len() as u32) as usize]
+        }
+    }
+
+    const ZONES: [&str; 6] = [
+        "Asia/Tokyo",
+        "America/New_York",
+        "Europe/Berlin",
+        "Australia/Sydney",
+        "America/Santiago",
+        "Australia/Lord_Howe",
+    ];
+
+    fn days_in_month(year: i32, month: u32) -> u32 {
+        let next = if month == 12 {
+            NaiveDate::from_ymd_opt(year + 1, 1, 1)
+        } else {
+            NaiveDate::from_ymd_opt(year, month + 1, 1)
+        };
+        next.unwrap().pred_opt().unwrap().day()
+    }
+
+    fn random_start(rng: &mut Rng) -> NaiveDateTime {
+        NaiveDate::from_ymd_opt(
+            2020 + rng.below(10) as i32,
+            1 + rng.below(12),
+            1 + rng.below(28),
+        )
+        .unwrap()
+        .and_hms_opt(rng.below(24), rng.below(4) * 15, 0)
+        .unwrap()
+    }
+
+    #[test]
+    fn month_end_days_clamp_and_recover() {
+        let mut rng = Rng(0x5eed_0001);
+        for _ in 0..200 {
+            let time_zone = *rng.pick(&ZONES);
+            let zone = Zone::from_name(Some(time_zone));
+            let year = 2020 + rng.below(10) as i32;
+            let month = 1 + rng.below(12);
+            let day = (29 + rng.below(3)).min(days_in_month(year, month));
+            let start = NaiveDate::from_ymd_opt(year, month, day)
+                .unwrap()
+                .and_hms_opt(9, 0, 0)
+                .unwrap();
+            let interval = 1 + rng.below(3);
+            let mut monthly = pattern("monthly");
+            monthly.interval = interval;
+            let explicit = rng.below(2) == 0;
+            if explicit {
+                monthly.day_of_month = Some(day);
+            }
+
+            // 展開: 毎回その月の min(日, 月末) になる
+            let start_ms = to_instant(&zone, start).unwrap();
+            let schedule = Schedule::new(&monthly, start_ms, Some(time_zone)).unwrap();
+            let times = schedule.occurrences_after(start_ms - 1, 24);
+            assert_eq!(times.len(), 24);
+            for (i, millis) in times.iter().enumerate() {
+                let local = to_local(&zone, *millis).unwrap();
+                let months = month - 1 + i as u32 * interval;
+                let (y, m) = (year + (months / 12) as i32, months % 12 + 1);
+                assert_eq!(
+                    (local.year(), local.month(), local.day()),
+                    (y, m, day.min(days_in_month(y, m))),
+                    "{} {} 間隔{} {}件目",
+                    time_zone,
+                    start,
+                    interval,
+                    i
+                );
+            }
+
+            // 完了ごとに次の期限を求めても、2月で丸められた日に引きずられない
+            let mut due = start_ms;
+            let mut recurrence = monthly.clone();
+            for (i, expected) in times.iter().enumerate().skip(1) {
+                let next = next_due(
+                    &recurrence,
+                    due,
+                    None,
+                    Some(time_zone),
+                    &BusinessCalendar::default(),
+                )
+                .unwrap();
+                due = next.due_date.unwrap();
+                recurrence = next.recurrence;
+                assert_eq!(due, *expected, "{} {} {}回目", time_zone, start, i);
+            }
+        }
+    }
+
+    #[test]
+    fn occurrences_strictly_increase() {
+        let mut rng = Rng(0x5eed_0002);
+        for _ in 0..300 {
+            let time_zone = *rng.pick(&ZONES);
+            let kind = *rng.pick(&["daily", "weekly", "monthly", "yearly"]);
+            let mut recurring = pattern(kind);
+            recurring.interval = 1 + rng.below(4);
+            match kind {
+                "daily" => recurring.weekdays_only = rng.below(3) == 0,
+                "weekly" => {
+                    let days: Vec<u32> = (0..7).filter(|_| rng.below(3) == 0).collect();
+                    recurring.days_of_week = (!days.is_empty()).then_some(days);
+                }
+                "monthly" if rng.below(2) == 0 => recurring.day_of_month = Some(1 + rng.below(31)),
+                _ => {}
+            }
+            recurring.skip_holidays = rng.below(4) == 0;
+            let start =
+                to_instant(&Zone::from_name(Some(time_zone)), random_start(&mut rng)).unwrap();
+            let after = start + rng.below(400) as i64 * 86_400_000 - 1;
+            let times = Schedule::new(&recurring, start, Some(time_zone))
+                .unwrap()
+                .occurrences_after(after, 50);
+            assert_eq!(times.len(), 50, "{:?}", recurring);
+            assert!(times[0] > after);
+            assert!(
+                times.windows(2).all(|w| w[0] < w[1]),
+                "{} {:?} {:?}",
+                time_zone,
+                recurring,
+                format(time_zone, &times)
+            );
+        }
+    }
+
+    #[test]
+    fn daily_wall_clock_is_stable_across_dst() {
+        use chrono::offset::LocalResult;
+        use chrono::TimeZone;
+
+        let mut rng = Rng(0x5eed_0003);
+        let (mut gaps, mut overlaps) = (0, 0);
+        for _ in 0..300 {
+            let time_zone = *rng.pick(&ZONES[1..]);
+            let tz: chrono_tz::Tz = time_zone.parse().unwrap();
+            let zone = Zone::from_name(Some(time_zone));
+            // 切り替え時刻の前後を多めに選ぶ
+            let mut wall = random_start(&mut rng);
+            if rng.below(2) == 0 {
+                wall = wall.date().and_hms_opt(1 + rng.below(3), 30, 0).unwrap();
+            }
+            let start = to_instant(&zone, wall).unwrap();
+            let wall = to_local(&zone, start).unwrap();
+            let times = Schedule::new(&pattern("daily"), start, Some(time_zone))
+                .unwrap()
+                .occurrences_after(start - 1, 400);
+            assert_eq!(times.len(), 400);
+            for (i, millis) in times.iter().enumerate() {
+                let expected = wall + Duration::days(i as i64);
+                let instant = match tz.from_local_datetime(&expected) {
+                    LocalResult::Single(d) => d.timestamp_millis(),
+                    // 2回ある時刻は早い方
+                    LocalResult::Ambiguous(earlier, _) => {
+                        overlaps += 1;
+                        earlier.timestamp_millis()
+                    }
+                    // ない時刻は切り替え前のオフセットで換算する (同じ日のうちに収まる)
+                    LocalResult::None => {
+                        gaps += 1;
+                        let local = to_local(&zone, *millis).unwrap();
+                        assert_eq!(local.date(), expected.date(), "{} {}", time_zone, expected);
+                        assert!(local > expected && local - expected <= Duration::hours(1));
+                        continue;
+                    }
+                };
+                assert_eq!(*millis, instant, "{} {}", time_zone, expected);
+            }
+        }
+        // 切り替えの日を実際に通っている
+        assert!(gaps > 0 && overlaps > 0, "{} {}", gaps, overlaps);
+    }
+}
@@ -0,0 +1,892 @@
+// 計画のスケジュール見積もり (ガントチャート表示用)
+// 所要時間と依存関係からクリティカルパス法で最早・最遅開始を求め、
+// 1日に使える時間でタスクを順にこなした場合の完了予定日を出す
+// 目標日を設定したプロジェクトは、タスクが同期されるたびに計算し直して変化を通知する
+
+use chrono::{Duration, Local, NaiveDate};
+use std::collections::{BTreeMap, HashMap, VecDeque};
+use std::sync::Mutex;
+use tauri::{Emitter, Manager};
+
+use crate::calendar::{BusinessCalendar, CalendarState};
+use crate::dependency::Dependency;
+use crate::models::Todo;
+use crate::plan::PlanResult;
+
+const SETTINGS_FILE: &str = "schedules.json";
+const DEFAULT_HOURS_PER_DAY: f64 = 2.0;
+// 見積もりのないタスクの所要時間
+const DEFAULT_TASK_MINUTES: u32 = 60;
+// 予定日を探す上限 (1日の時間が極端に短い場合の打ち切り)
+const MAX_SCHEDULE_DAYS: i64 = 3660;
+
+#[derive(serde::Serialize, serde::Deserialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct ScheduleTask {
+    pub id: String,
+    pub title: String,
+    // 未指定なら DEFAULT_TASK_MINUTES
+    #[serde(default)]
+    pub minutes: Option<u32>,
+    #[serde(default)]
+    pub depends_on: Vec<String>,
+    #[serde(default)]
+    pub completed: bool,
+}
+
+#[derive(serde::Serialize, serde::Deserialize, Clone, Debug, Default)]
+#[serde(rename_all = "camelCase", default)]
+pub struct ScheduleOptions {
+    // YYYY-MM-DD。未指定なら今日
+    pub start_date: Option<String>,
+    pub goal_date: Option<String>,
+    pub hours_per_day: Option<f64>,
+    // 土日・祝日・会社の休日には作業しない
+    pub business_days_only: bool,
+}
+
+#[derive(serde::Serialize, Clone, Debug, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct ScheduledTask {
+    pub id: String,
+    pub title: String,
+    // 残りの所要時間 (完了済みは 0)
+    pub minutes: u32,
+    pub estimated: bool,
+    pub completed: bool,
+    // 依存関係だけで決まる最早・最遅 (並行して進められる前提)
+    pub earliest_start: Option<String>,
+    pub earliest_finish: Option<String>,
+    pub latest_start: Option<String>,
+    pub latest_finish: Option<String>,
+    // 遅らせても完了日が変わらない作業時間 (分)。負なら目標日に間に合わない
+    pub slack_minutes: i64,
+    pub critical: bool,
+    // 1日の時間でタスクを1つずつこなした場合の予定
+    pub planned_start: Option<String>,
+    pub planned_finish: Option<String>,
+}
+
+#[derive(serde::Serialize, Clone, Debug, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct ScheduleResult {
+    pub start_date: String,
+    pub hours_per_day: f64,
+    // 依存される側が先に来る順
+    pub tasks: Vec<ScheduledTask>,
+    pub critical_path: Vec<String>,
+    pub remaining_minutes: u32,
+    pub critical_path_minutes: u32,
+    // 並行して進められる場合の最短の完了日
+    pub critical_path_finish: Option<String>,
+    // 1つずつこなした場合の完了予定日 (残りがなければ None)
+    pub projected_completion: Option<String>,
+    pub goal_date: Option<String>,
+    pub reachable: Option<bool>,
+    // 目標日に間に合わせるのに必要な1日あたりの時間
+    pub required_hours_per_day: Option<f64>,
+    // 見積もりがなく DEFAULT_TASK_MINUTES で計算したタスクの数
+    pub unestimated: usize,
+}
+
+// プロジェクトごとの目標日と作業時間 (設定したプロジェクトだけ自動で計算し直す)
+#[derive(serde::Serialize, serde::Deserialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct ProjectSchedule {
+    pub project_id: String,
+    #[serde(flatten)]
+    pub options: ScheduleOptions,
+}
+
+#[derive(serde::Serialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct ScheduleChangeEvent {
+    pub project_id: String,
+    pub previous_completion: Option<String>,
+    pub schedule: ScheduleResult,
+}
+
+fn format_date(date: NaiveDate) -> String {
+    date.format("%Y-%m-%d").to_string()
+}
+
+fn parse_date(value: &str) -> Result<NaiveDate, String> {
+    NaiveDate::parse_from_str(value.trim(), "%Y-%m-%d")
+        .map_err(|_| format!("日付の形式が正しくありません (YYYY-MM-DD): {}", value))
+}
+
+// 作業時間 (分) を作業日に割り当てる
+struct WorkDays<'a> {
+    start: NaiveDate,
+    capacity: i64,
+    calendar: Option<&'a BusinessCalendar>,
+    days: Vec<NaiveDate>,
+}
+
+impl<'a> WorkDays<'a> {
+    fn new(start: NaiveDate, capacity: i64, calendar: Option<&'a BusinessCalendar>) -> Self {
+        Self {
+            start,
+            capacity,
+            calendar,
+            days: Vec::new(),
+        }
+    }
+
+    fn is_work_day(&self, date: NaiveDate) -> bool {
+        self.calendar.is_none_or(|c| c.is_business_day(date))
+    }
+
+    // n 番目 (0 始まり) の作業日
+    fn day(&mut self, n: usize) -> Option<NaiveDate> {
+        let mut next = match self.days.last() {
+            Some(last) => *last + Duration::days(1),
+            None => self.start,
+        };
+        while self.days.len() <= n {
+            if (next - self.start).num_days() > MAX_SCHEDULE_DAYS {
+                return None;
+            }
+            if self.is_work_day(next) {
+                self.days.push(next);
+            }
+            next += Duration::days(1);
+        }
+        Some(self.days[n])
+    }
+
+    // 作業時間の m 分目から始める日
+    fn start_of(&mut self, minutes: i64) -> Option<String> {
+        self.day((minutes.max(0) / self.capacity) as usize)
+            .map(format_date)
+    }
+
+    // 作業時間の m 分目で終わる日 (ちょうど1日分なら、その日のうちに終わる)
+    fn finish_of(&mut self, minutes: i64) -> Option<String> {
+        self.day(((minutes - 1).max(0) / self.capacity) as usize)
+            .map(format_date)
+    }
+
+    // 開始日から goal までの作業日数
+    fn count_until(&self, goal: NaiveDate) -> i64 {
+        let mut count = 0;
+        let mut date = self.start;
+        while date <= goal && (date - self.start).num_days() <= MAX_SCHEDULE_DAYS {
+            if self.is_work_day(date) {
+                count += 1;
+            }
+            date += Duration::days(1);
+        }
+        count
+    }
+}
+
+// 依存される側が先に来る順 (循環していればエラー)
+fn ordered(tasks: &[ScheduleTask]) -> Result<Vec<usize>, String> {
+    let index: HashMap<&str, usize> = tasks
+        .iter()
+        .enumerate()
+        .map(|(i, t)| (t.id.as_str(), i))
+        .collect();
+    let mut indegree = vec![0; tasks.len()];
+    let mut successors: Vec<Vec<usize>> = vec![Vec::new(); tasks.len()];
+    for (i, task) in tasks.iter().enumerate() {
+        for dep in &task.depends_on {
+            let Some(&d) = index.get(dep.as_str()) else {
+                return Err(format!(
+                    "「{}」の依存先のタスクが見つかりません: {}",
+                    task.title, dep
+                ));
+            };
+            indegree[i] += 1;
+            successors[d].push(i);
+        }
+    }
+
+    let mut ready: VecDeque<usize> = (0..tasks.len()).filter(|i| indegree[*i] == 0).collect();
+    let mut order = Vec::with_capacity(tasks.len());
+    while let Some(i) = ready.pop_front() {
+        order.push(i);
+        for &s in &successors[i] {
+            indegree[s] -= 1;
+            if indegree[s] == 0 {
+                ready.push_back(s);
+            }
+        }
+    }
+    if order.len() < tasks.len() {
+        let stuck: Vec<&str> = (0..tasks.len())
+            .filter(|i| indegree[*i] > 0)
+            .map(|i| tasks[i].title.as_str())
+            .collect();
+        return Err(format!("依存関係が循環しています: {}", stuck.join(", ")));
+    }
+    Ok(order)
+}
+
+pub fn compute(
+    tasks: &[ScheduleTask],
+    options: &ScheduleOptions,
+    today: NaiveDate,
+    calendar: &BusinessCalendar,
+) -> Result<ScheduleResult, String> {
+    let hours_per_day = options.hours_per_day.unwrap_or(DEFAULT_HOURS_PER_DAY);
+    if !(hours_per_day > 0.0 && hours_per_day <= 24.0) {
+        return Err("1日の作業時間は 0〜24 時間の範囲で指定してください".to_string());
+    }
+    let start = match options.start_date.as_deref() {
+        Some(value) => parse_date(value)?,
+        None => today,
+    };
+    let goal = options.goal_date.as_deref().map(parse_date).transpose()?;
+    let capacity = ((hours_per_day * 60.0).round() as i64).max(1);
+    let mut days = WorkDays::new(
+        start,
+        capacity,
+        options.business_days_only.then_some(calendar),
+    );
+
+    let order = ordered(tasks)?;
+    let duration: Vec<i64> = tasks
+        .iter()
+        .map(|t| {
+            if t.completed {
+                0
+            } else {
+                t.minutes.unwrap_or(DEFAULT_TASK_MINUTES) as i64
+            }
+        })
+        .collect();
+    let index: HashMap<&str, usize> = tasks
+        .iter()
+        .enumerate()
+        .map(|(i, t)| (t.id.as_str(), i))
+        .collect();
+    let deps: Vec<Vec<usize>> = tasks
+        .iter()
+        .map(|t| t.depends_on.iter().map(|d| index[d.as_str()]).collect())
+        .collect();
+
+    // 前進計算: 最早開始・最早終了
+    let mut earliest = vec![(0i64, 0i64); tasks.len()];
+    for &i in &order {
+        let es = deps[i].iter().map(|&d| earliest[d].1).max().unwrap_or(0);
+        earliest[i] = (es, es + duration[i]);
+    }
+    let length = earliest.iter().map(|e| e.1).max().unwrap_or(0);
+    let horizon = match goal {
+        Some(goal) => days.count_until(goal) * capacity,
+        None => length,
+    };
+
+    // 後退計算: 最遅開始・最遅終了
+    let mut latest = vec![(horizon, horizon); tasks.len()];
+    for &i in order.iter().rev() {
+        let lf = (0..tasks.len())
+            .filter(|s| deps[*s].contains(&i))
+            .map(|s| latest[s].0)
+            .min()
+            .unwrap_or(horizon);
+        latest[i] = (lf - duration[i], lf);
+    }
+    let slack: Vec<i64> = (0..tasks.len())
+        .map(|i| latest[i].0 - earliest[i].0)
+        .collect();
+    let open = |i: &usize| !tasks[*i].completed;
+    let min_slack = (0..tasks.len()).filter(open).map(|i| slack[i]).min();
+    let critical: Vec<bool> = (0..tasks.len())
+        .map(|i| open(&i) && Some(slack[i]) == min_slack)
+        .collect();
+
+    // 最後に終わるクリティカルなタスクから、間を空けずにつながる前のタスクをたどる
+    let mut critical_path = Vec::new();
+    let mut current = order
+        .iter()
+        .rev()
+        .copied()
+        .find(|&i| critical[i] && earliest[i].1 == length);
+    while let Some(i) = current {
+        critical_path.push(tasks[i].id.clone());
+        current = deps[i]
+            .iter()
+            .copied()
+            .find(|&d| critical[d] && earliest[d].1 == earliest[i].0);
+    }
+    critical_path.reverse();
+
+    // 1つずつこなす: 着手できるタスクのうち最遅開始が早いものから
+    let mut planned: Vec<Option<(i64, i64)>> = vec![None; tasks.len()];
+    let mut cursor = 0;
+    let mut remaining: Vec<usize> = order.iter().copied().filter(open).collect();
+    while !remaining.is_empty() {
+        let ready = |i: &usize| {
+            deps[*i]
+                .iter()
+                .all(|&d| tasks[d].completed || planned[d].is_some())
+        };
+        let Some(position) = remaining
+            .iter()
+            .enumerate()
+            .filter(|(_, i)| ready(i))
+            .min_by_key(|(_, &i)| (latest[i].0, earliest[i].0))
+            .map(|(position, _)| position)
+        else {
+            break;
+        };
+        let i = remaining.remove(position);
+        planned[i] = Some((cursor, cursor + duration[i]));
+        cursor += duration[i];
+    }
+
+    let mut scheduled = Vec::with_capacity(tasks.len());
+    for &i in &order {
+        let task = &tasks[i];
+        let open = !task.completed;
+        let (es, ef) = earliest[i];
+        let (ls, lf) = latest[i];
+        scheduled.push(ScheduledTask {
+            id: task.id.clone(),
+            title: task.title.clone(),
+            minutes: duration[i] as u32,
+            estimated: task.minutes.is_some(),
+            completed: task.completed,
+            earliest_start: open.then(|| days.start_of(es)).flatten(),
+            earliest_finish: open.then(|| days.finish_of(ef)).flatten(),
+            latest_start: open.then(|| days.start_of(ls)).flatten(),
+            latest_finish: open.then(|| days.finish_of(lf)).flatten(),
+            slack_minutes: slack[i],
+            critical: critical[i],
+            planned_start: planned[i].and_then(|(s, _)| days.start_of(s)),
+            planned_finish: planned[i].and_then(|(_, f)| days.finish_of(f)),
+        });
+    }
+
+    let remaining_minutes = cursor;
+    let projected = (remaining_minutes > 0)
+        .then(|| days.finish_of(remaining_minutes))
+        .flatten();
+    let goal_days = goal.map(|g| days.count_until(g));
+    Ok(ScheduleResult {
+        start_date: format_date(start),
+        hours_per_day,
+        tasks: scheduled,
+        critical_path,
+        remaining_minutes: remaining_minutes as u32,
+        critical_path_minutes: length as u32,
+        critical_path_finish: (length > 0).then(|| days.finish_of(length)).flatten(),
+        projected_completion: projected.clone(),
+        goal_date: goal.map(format_date),
+        reachable: goal.map(|g| {
+            projected
+                .as_deref()
+                .is_none_or(|p| p <= format_date(g).as_str())
+        }),
+        required_hours_per_day: goal_days.map(|d| {
+            if d > 0 {
+                remaining_minutes as f64 / 60.0 / d as f64
+            } else {
+                remaining_minutes as f64 / 60.0
+            }
+        }),
+        unestimated: tasks
+            .iter()
+            .filter(|t| !t.completed && t.minutes.is_none())
+            .count(),
+    })
+}
+
+// プロジェクトのタスクと依存関係から入力を作る (アーカイブ済みと別プロジェクトへの依存は除く)
+pub fn project_tasks(todos: &[Todo], edges: &[Dependency], project_id: &str) -> Vec<ScheduleTask> {
+    let members: Vec<&Todo> = todos
+        .iter()
+        .filter(|t| t.project_id.as_deref() == Some(project_id) && !t.archived)
+        .collect();
+    members
+        .iter()
+        .map(|todo| ScheduleTask {
+            id: todo.id.clone(),
+            title: todo.text.clone(),
+            minutes: todo.estimated_minutes,
+            depends_on: edges
+                .iter()
+                .filter(|e| e.task_id == todo.id)
+                .filter(|e| members.iter().any(|m| m.id == e.depends_on))
+                .map(|e| e.depends_on.clone())
+                .collect(),
+            completed: todo.completed,
+        })
+        .collect()
+}
+
+// AIの計画から入力を作る。開始からの日数が前の段のタスクすべてに依存するとみなす
+// (同じ日数のタスクは並行して進められる)。繰り返しのタスクは含めない
+pub fn plan_tasks(plan: &PlanResult) -> Vec<ScheduleTask> {
+    let mut stages: BTreeMap<u32, Vec<String>> = BTreeMap::new();
+    let tasks: Vec<(String, &crate::plan::PlanTask)> = plan
+        .tasks
+        .iter()
+        .enumerate()
+        .filter(|(_, t)| t.recurrence.is_none())
+        .map(|(i, t)| (format!("plan-{}", i), t))
+        .collect();
+    for (id, task) in &tasks {
+        stages
+            .entry(task.days_from_start)
+            .or_default()
+            .push(id.clone());
+    }
+    tasks
+        .iter()
+        .map(|(id, task)| ScheduleTask {
+            id: id.clone(),
+            title: task.title.clone(),
+            minutes: (task.estimated_minutes > 0).then_some(task.estimated_minutes),
+            depends_on: stages
+                .range(..task.days_from_start)
+                .next_back()
+                .map(|(_, ids)| ids.clone())
+                .unwrap_or_default(),
+            completed: false,
+        })
+        .collect()
+}
+
+pub struct ScheduleState {
+    projects: Mutex<Vec<ProjectSchedule>>,
+    // 前回計算した完了予定日 (変わったときだけ通知する)
+    last: Mutex<HashMap<String, Option<String>>>,
+}
+
+impl ScheduleState {
+    pub fn load(app: &tauri::AppHandle) -> Self {
+        let projects = crate::store::load_json(app, SETTINGS_FILE).unwrap_or_else(|e| {
+            println!("[Schedule Backend] 設定読み込み失敗: {}", e);
+            Vec::new()
+        });
+        Self {
+            projects: Mutex::new(projects),
+            last: Mutex::new(HashMap::new()),
+        }
+    }
+
+    fn options(&self, project_id: &str) -> ScheduleOptions {
+        self.projects
+            .lock()
+            .unwrap()
+            .iter()
+            .find(|p| p.project_id == project_id)
+            .map(|p| p.options.clone())
+            .unwrap_or_default()
+    }
+
+    // タスクが同期されたときに呼ぶ。完了予定日が変わったプロジェクトを通知する
+    pub fn sync(&self, app: &tauri::AppHandle, todos: &[Todo]) {
+        let projects = self.projects.lock().unwrap().clone();
+        if projects.is_empty() {
+            return;
+        }
+        let edges = app.state::<crate::dependency::DependencyState>().edges();
+        let calendar = app.state::<CalendarState>().calendar();
+        let today = Local::now().date_naive();
+        let mut last = self.last.lock().unwrap();
+        for project in projects {
+            let tasks = project_tasks(todos, &edges, &project.project_id);
+            let schedule = match compute(&tasks, &project.options, today, &calendar) {
+                Ok(schedule) => schedule,
+                Err(e) => {
+                    println!(
+                        "[Schedule Backend] 計算失敗 ({}): {}",
+                        project.project_id, e
+                    );
+                    continue;
+                }
+            };
+            let completion = schedule.projected_completion.clone();
+            let previous = last.insert(project.project_id.clone(), completion.clone());
+            // 起動後の初回は基準にするだけ
+            let Some(previous) = previous else {
+                continue;
+            };
+            if previous == completion {
+                continue;
+            }
+            println!(
+                "[Schedule Backend] 完了予定日が変わりました ({}): {:?} → {:?}",
+                project.project_id, previous, completion
+            );
+            let event = ScheduleChangeEvent {
+                project_id: project.project_id,
+                previous_completion: previous,
+                schedule,
+            };
+            if let Err(e) = app.emit("schedule-changed", &event) {
+                println!("[Schedule Backend] イベント送信失敗: {}", e);
+            }
+        }
+    }
+}
+
+// 任意のタスクの並びのスケジュールを計算する (作成前の計画のプレビューなど)
+#[tauri::command]
+pub fn schedule_compute(
+    calendar: tauri::State<'_, CalendarState>,
+    tasks: Vec<ScheduleTask>,
+    options: Option<ScheduleOptions>,
+) -> Result<ScheduleResult, String> {
+    compute(
+        &tasks,
+        &options.unwrap_or_default(),
+        Local::now().date_naive(),
+        &calendar.calendar(),
+    )
+}
+
+// AIの計画のスケジュール。目標日が未指定なら計画の見積もり日数から決める
+#[tauri::command]
+pub fn schedule_plan(
+    calendar: tauri::State<'_, CalendarState>,
+    plan: PlanResult,
+    options: Option<ScheduleOptions>,
+) -> Result<ScheduleResult, String> {
+    let mut options = options.unwrap_or_default();
+    let today = Local::now().date_naive();
+    if options.goal_date.is_none() && plan.estimated_days > 0 {
+        let start = match options.start_date.as_deref() {
+            Some(value) => parse_date(value)?,
+            None => today,
+        };
+        let goal = start + Duration::days(plan.estimated_days as i64 - 1);
+        options.goal_date = Some(format_date(goal));
+    }
+    compute(&plan_tasks(&plan), &options, today, &calendar.calendar())
+}
+
+// プロジェクトのスケジュール (options が未指定なら保存した設定を使う)
+#[tauri::command]
+pub fn schedule_project(
+    state: tauri::State<'_, ScheduleState>,
+    calendar: tauri::State<'_, CalendarState>,
+    todos: tauri::State<'_, crate::todos::TodoState>,
+    dependencies: tauri::State<'_, crate::dependency::DependencyState>,
+    project_id: String,
+    options: Option<ScheduleOptions>,
+) -> Result<ScheduleResult, String> {
+    let options = options.unwrap_or_else(|| state.options(&project_id));
+    let tasks = project_tasks(&todos.all(), &dependencies.edges(), &project_id);
+    compute(
+        &tasks,
+        &options,
+        Local::now().date_naive(),
+        &calendar.calendar(),
+    )
+}
+
+#[tauri::command]
+pub fn schedule_list_settings(state: tauri::State<'_, ScheduleState>) -> Vec<ProjectSchedule> {
+    state.projects.lock().unwrap().clone()
+}
+
+// プロジェクトの目標日と作業時間を保存する (以後、同期のたびに計算し直す)
+#[tauri::command]
+pub fn schedule_save_settings(
+    app: tauri::AppHandle,
+    state: tauri::State<'_, ScheduleState>,
+    settings: ProjectSchedule,
+) -> Result<(), String> {
+    for date in [&settings.options.start_date, &settings.options.goal_date]
+        .into_iter()
+        .flatten()
+    {
+        parse_date(date)?;
+    }
+    if settings
+        .options
+        .hours_per_day
+        .is_some_and(|h| !(h > 0.0 && h <= 24.0))
+    {
+        return Err("1日の作業時間は 0〜24 時間の範囲で指定してください".to_string());
+    }
+    let mut projects = state.projects.lock().unwrap();
+    match projects
+        .iter_mut()
+        .find(|p| p.project_id == settings.project_id)
+    {
+        Some(existing) => *existing = settings,
+        None => projects.push(settings),
+    }
+    crate::store::save_json(&app, SETTINGS_FILE, &*projects)
+}
+
+#[tauri::command]
+pub fn schedule_delete_settings(
+    app: tauri::AppHandle,
+    state: tauri::State<'_, ScheduleState>,
+    project_id: String,
+) -> Result<(), String> {
+    let mut projects = state.projects.lock().unwrap();
+    projects.retain(|p| p.project_id != project_id);
+    state.last.lock().unwrap().remove(&project_id);
+    crate::store::save_json(&app, SETTINGS_FILE, &*projects)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::calendar::CalendarSettings;
+
+    fn task(id: &str, minutes: u32, depends_on: &[&str]) -> ScheduleTask {
+        ScheduleTask {
+            id: id.to_string(),
+            title: id.to_uppercase(),
+            minutes: Some(minutes),
+            depends_on: depends_on.iter().map(|d| d.to_string()).collect(),
+            completed: false,
+        }
+    }
+
+    // a → (b, c) → d と、どこにもつながらない e
+    fn dag() -> Vec<ScheduleTask> {
+        vec![
+            task("d", 60, &["b", "c"]),
+            task("b", 60, &["a"]),
+            task("e", 30, &[]),
+            task("c", 240, &["a"]),
+            task("a", 120, &[]),
+        ]
+    }
+
+    fn date(value: &str) -> NaiveDate {
+        parse_date(value).unwrap()
+    }
+
+    fn options(start: &str) -> ScheduleOptions {
+        ScheduleOptions {
+            start_date: Some(start.to_string()),
+            hours_per_day: Some(2.0),
+            ..Default::default()
+        }
+    }
+
+    fn run(tasks: &[ScheduleTask], options: &ScheduleOptions) -> ScheduleResult {
+        let calendar = BusinessCalendar::new(&CalendarSettings::default());
+        compute(tasks, options, date("2024-01-01"), &calendar).unwrap()
+    }
+
+    fn find<'a>(result: &'a ScheduleResult, id: &str) -> &'a ScheduledTask {
+        result.tasks.iter().find(|t| t.id == id).unwrap()
+    }
+
+    type Row<'a> = (
+        &'a str,
+        Option<&'a str>,
+        Option<&'a str>,
+        Option<&'a str>,
+        Option<&'a str>,
+        i64,
+        bool,
+    );
+
+    fn row(task: &ScheduledTask) -> Row<'_> {
+        (
+            task.id.as_str(),
+            task.earliest_start.as_deref(),
+            task.earliest_finish.as_deref(),
+            task.latest_start.as_deref(),
+            task.latest_finish.as_deref(),
+            task.slack_minutes,
+            task.critical,
+        )
+    }
+
+    #[test]
+    fn forward_and_backward_pass() {
+        // 1日2時間、土日も作業する
+        let result = run(&dag(), &options("2024-05-20"));
+        let rows: Vec<Row> = result.tasks.iter().map(row).collect();
+        // 分単位では a 0-120/0-120、b 120-180/300-360、c 120-360/120-360、d 360-420/360-420、e 0-30/390-420
+        assert_eq!(
+            rows,
+            [
+                (
+                    "e",
+                    Some("2024-05-20"),
+                    Some("2024-05-20"),
+                    Some("2024-05-23"),
+                    Some("2024-05-23"),
+                    390,
+                    false
+                ),
+                (
+                    "a",
+                    Some("2024-05-20"),
+                    Some("2024-05-20"),
+                    Some("2024-05-20"),
+                    Some("2024-05-20"),
+                    0,
+                    true
+                ),
+                (
+                    "b",
+                    Some("2024-05-21"),
+                    Some("2024-05-21"),
+                    Some("2024-05-22"),
+                    Some("2024-05-22"),
+                    180,
+                    false
+                ),
+                (
+                    "c",
+                    Some("2024-05-21"),
+                    Some("2024-05-22"),
+                    Some("2024-05-21"),
+                    Some("2024-05-22"),
+                    0,
+                    true
+                ),
+                (
+                    "d",
+                    Some("2024-05-23"),
+                    Some("2024-05-23"),
+                    Some("2024-05-23"),
+                    Some("2024-05-23"),
+                    0,
+                    true
+                ),
+            ]
+        );
+        assert_eq!(result.critical_path, ["a", "c", "d"]);
+        assert_eq!(result.critical_path_minutes, 420);
+        assert_eq!(result.critical_path_finish.as_deref(), Some("2024-05-23"));
+    }
+
+    #[test]
+    fn serial_plan_follows_latest_start() {
+        let result = run(&dag(), &options("2024-05-20"));
+        // a, c, b, d, e の順にこなす (510分 = 2時間×4日 + 30分)
+        let planned: Vec<(&str, Option<&str>, Option<&str>)> = ["a", "c", "b", "d", "e"]
+            .iter()
+            .map(|id| {
+                let t = find(&result, id);
+                (
+                    t.id.as_str(),
+                    t.planned_start.as_deref(),
+                    t.planned_finish.as_deref(),
+                )
+            })
+            .collect();
+        assert_eq!(
+            planned,
+            [
+                ("a", Some("2024-05-20"), Some("2024-05-20")),
+                ("c", Some("2024-05-21"), Some("2024-05-22")),
+                ("b", Some("2024-05-23"), Some("2024-05-23")),
+                ("d", Some("2024-05-23"), Some("2024-05-23")),
+                ("e", Some("2024-05-24"), Some("2024-05-24")),
+            ]
+        );
+        assert_eq!(result.remaining_minutes, 510);
+        assert_eq!(result.projected_completion.as_deref(), Some("2024-05-24"));
+        assert_eq!(result.reachable, None);
+    }
+
+    #[test]
+    fn business_days_skip_weekends_and_holidays() {
+        // 5/3〜5/6 は祝日と土日・振替休日
+        let mut options = options("2024-05-02");
+        options.business_days_only = true;
+        let result = run(&dag(), &options);
+        assert_eq!(
+            find(&result, "c").earliest_start.as_deref(),
+            Some("2024-05-07")
+        );
+        assert_eq!(
+            find(&result, "c").earliest_finish.as_deref(),
+            Some("2024-05-08")
+        );
+        assert_eq!(result.critical_path_finish.as_deref(), Some("2024-05-09"));
+        assert_eq!(result.projected_completion.as_deref(), Some("2024-05-10"));
+
+        // 目標日までの作業日は 5/2, 5/7, 5/8 の3日 (360分) で、60分足りない
+        options.goal_date = Some("2024-05-08".to_string());
+        let result = run(&dag(), &options);
+        assert_eq!(result.reachable, Some(false));
+        assert_eq!(find(&result, "a").slack_minutes, -60);
+        assert_eq!(
+            find(&result, "d").latest_finish.as_deref(),
+            Some("2024-05-08")
+        );
+        assert!(result
+            .tasks
+            .iter()
+            .filter(|t| t.critical)
+            .all(|t| t.slack_minutes == -60));
+        let required = result.required_hours_per_day.unwrap();
+        assert!((required - 510.0 / 60.0 / 3.0).abs() < 1e-9);
+
+        options.goal_date = Some("2024-05-13".to_string());
+        let result = run(&dag(), &options);
+        assert_eq!(result.reachable, Some(true));
+        assert_eq!(find(&result, "a").slack_minutes, 300);
+    }
+
+    #[test]
+    fn completed_and_unestimated_tasks() {
+        let mut tasks = dag();
+        tasks[4].completed = true;
+        tasks[2].minutes = None;
+        let result = run(&tasks, &options("2024-05-20"));
+        let a = find(&result, "a");
+        assert_eq!(
+            (a.minutes, a.critical, a.earliest_start.clone()),
+            (0, false, None)
+        );
+        assert_eq!(find(&result, "e").minutes, DEFAULT_TASK_MINUTES);
+        assert_eq!(result.unestimated, 1);
+        assert_eq!(result.critical_path, ["c", "d"]);
+        assert_eq!(result.remaining_minutes, 60 + 240 + 60 + 60);
+    }
+
+    #[test]
+    fn work_days_boundaries() {
+        let mut days = WorkDays::new(date("2024-05-20"), 120, None);
+        // ちょうど1日分ならその日に終わり、次の分は翌日から
+        assert_eq!(days.finish_of(120).as_deref(), Some("2024-05-20"));
+        assert_eq!(days.start_of(120).as_deref(), Some("2024-05-21"));
+        assert_eq!(days.finish_of(121).as_deref(), Some("2024-05-21"));
+        assert_eq!(days.finish_of(0).as_deref(), Some("2024-05-20"));
+        assert_eq!(days.count_until(date("2024-05-26")), 7);
+        assert_eq!(days.count_until(date("2024-05-19")), 0);
+
+        let calendar = BusinessCalendar::new(&CalendarSettings::default());
+        let days = WorkDays::new(date("2024-05-20"), 120, Some(&calendar));
+        assert_eq!(days.count_until(date("2024-05-26")), 5);
+    }
+
+    #[test]
+    fn rejects_cycles_missing_tasks_and_bad_hours() {
+        let calendar = BusinessCalendar::default();
+        let today = date("2024-05-20");
+        let cycle = [
+            task("a", 60, &["b"]),
+            task("b", 60, &["a"]),
+            task("c", 60, &[]),
+        ];
+        let error = compute(&cycle, &ScheduleOptions::default(), today, &calendar).unwrap_err();
+        assert!(error.contains("A, B"), "{}", error);
+        let missing = [task("a", 60, &["x"])];
+        assert!(compute(&missing, &ScheduleOptions::default(), today, &calendar).is_err());
+        let mut options = ScheduleOptions::default();
+        for hours in [0.0, -1.0, 25.0, f64::NAN] {
+            options.hours_per_day = Some(hours);
+            assert!(
+                compute(&[], &options, today, &calendar).is_err(),
+                "{}",
+                hours
+            );
+        }
+        let empty = compute(&[], &ScheduleOptions::default(), today, &calendar).unwrap();
+        assert_eq!(empty.start_date, "2024-05-20");
+        assert_eq!(empty.projected_completion, None);
+        assert!(empty.critical_path.is_empty());
+    }
+}
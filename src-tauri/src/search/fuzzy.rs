@@ -0,0 +1,229 @@
+// あいまい検索 (コマンドパレット用)
+// 入力の文字が順に現れれば一致とし、語の先頭や連続した一致ほど高い点数にする
+// かな・カタカナ・半角カナ・全角英数字の違いは無視し、ローマ字入力はかなとしても照合する
+
+use super::kana::{normalize, Normalized};
+use super::romaji::romaji_pattern;
+use super::tokenizer::is_cjk_term;
+
+const SCORE_MATCH: i32 = 16;
+const BONUS_START: i32 = 12;
+const BONUS_BOUNDARY: i32 = 8;
+const BONUS_CONSECUTIVE: i32 = 6;
+const PENALTY_GAP_START: i32 = 3;
+const PENALTY_GAP_EXTEND: i32 = 1;
+const NONE: i32 = i32::MIN / 2;
+
+// 元の文字列での一致範囲 (文字位置、end は含まない)
+#[derive(serde::Serialize, Clone, Copy, Debug, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct MatchRange {
+    pub start: usize,
+    pub end: usize,
+}
+
+#[derive(Debug, Clone)]
+pub struct FuzzyMatch {
+    pub score: i32,
+    pub ranges: Vec<MatchRange>,
+}
+
+// 照合する文字の並び (要素ごとに一致してよい文字の集合)
+type Pattern = Vec<Vec<char>>;
+
+pub struct FuzzyQuery {
+    patterns: Vec<Pattern>,
+}
+
+impl FuzzyQuery {
+    // 空白は無視する。ローマ字として読める場合はかなの並びも候補にする
+    pub fn new(query: &str) -> Option<Self> {
+        let folded: String = normalize(query)
+            .chars
+            .into_iter()
+            .filter(|c| !c.is_whitespace())
+            .collect();
+        if folded.is_empty() {
+            return None;
+        }
+        let mut patterns: Vec<Pattern> = vec![folded.chars().map(|c| vec![c]).collect()];
+        if let Some(kana) = romaji_pattern(&folded).filter(|p| p != &patterns[0]) {
+            patterns.push(kana);
+        }
+        Some(Self { patterns })
+    }
+
+    pub fn match_text(&self, text: &str) -> Option<FuzzyMatch> {
+        let normalized = normalize(text);
+        self.patterns
+            .iter()
+            .filter_map(|pattern| best_alignment(pattern, &normalized))
+            .max_by_key(|m| m.score)
+    }
+}
+
+// 語の先頭なら加点する (文頭・空白や記号の直後・文字種の切り替わり)
+fn boundary_bonus(chars: &[char], j: usize) -> i32 {
+    if j == 0 {
+        return BONUS_START;
+    }
+    let previous = chars[j - 1];
+    let current = chars[j];
+    if !previous.is_alphanumeric() {
+        return BONUS_BOUNDARY;
+    }
+    let cjk = |c: char| is_cjk_term(&c.to_string());
+    if cjk(previous) != cjk(current) || (previous.is_ascii_digit() != current.is_ascii_digit()) {
+        return BONUS_BOUNDARY / 2;
+    }
+    0
+}
+
+// 点数が最大になる一致の位置を動的計画法で求める
+fn best_alignment(pattern: &Pattern, text: &Normalized) -> Option<FuzzyMatch> {
+    let chars = &text.chars;
+    let (n, m) = (pattern.len(), chars.len());
+    if n == 0 || n > m {
+        return None;
+    }
+    let mut score = vec![NONE; n * m];
+    // 1つ前の文字を一致させた位置 (経路の復元用)
+    let mut from = vec![usize::MAX; n * m];
+
+    for i in 0..n {
+        // j-2 以前で一致した場合の最良の点数 (間の文字数に応じて減点済み)
+        let mut best_gap = NONE;
+        let mut best_gap_at = usize::MAX;
+        for j in i..m {
+            if i > 0 {
+                if best_gap > NONE {
+                    best_gap -= PENALTY_GAP_EXTEND;
+                }
+                if j >= 2 {
+                    let candidate = score[(i - 1) * m + j - 2];
+                    if candidate > NONE && candidate - PENALTY_GAP_START > best_gap {
+                        best_gap = candidate - PENALTY_GAP_START;
+                        best_gap_at = j - 2;
+                    }
+                }
+            }
+            if !pattern[i].contains(&chars[j]) {
+                continue;
+            }
+            let gained = SCORE_MATCH + boundary_bonus(chars, j);
+            if i == 0 {
+                score[j] = gained;
+                continue;
+            }
+            let consecutive = if j >= 1 {
+                score[(i - 1) * m + j - 1]
+            } else {
+                NONE
+            };
+            let (previous, previous_at) =
+                if consecutive > NONE && consecutive + BONUS_CONSECUTIVE >= best_gap {
+                    (consecutive + BONUS_CONSECUTIVE, j - 1)
+                } else {
+                    (best_gap, best_gap_at)
+                };
+            if previous > NONE {
+                score[i * m + j] = previous + gained;
+                from[i * m + j] = previous_at;
+            }
+        }
+    }
+
+    let last = (n - 1) * m;
+    let (end, best) = (0..m)
+        .map(|j| (j, score[last + j]))
+        .filter(|(_, s)| *s > NONE)
+        .max_by_key(|(j, s)| (*s, std::cmp::Reverse(*j)))?;
+
+    let mut positions = vec![end];
+    let mut j = end;
+    for i in (1..n).rev() {
+        j = from[i * m + j];
+        positions.push(j);
+    }
+    positions.reverse();
+
+    Some(FuzzyMatch {
+        score: best,
+        ranges: to_ranges(&positions, text),
+    })
+}
+
+// 一致した文字を元の文字列の範囲にまとめる
+fn to_ranges(positions: &[usize], text: &Normalized) -> Vec<MatchRange> {
+    let mut ranges: Vec<MatchRange> = Vec::new();
+    for &position in positions {
+        let (start, end) = text.spans[position];
+        match ranges.last_mut() {
+            Some(last) if last.end == start => last.end = end,
+            _ => ranges.push(MatchRange { start, end }),
+        }
+    }
+    ranges
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // 一致範囲を元の文字列から切り出す
+    fn highlighted(query: &str, text: &str) -> Option<Vec<String>> {
+        let matched = FuzzyQuery::new(query)?.match_text(text)?;
+        let chars: Vec<char> = text.chars().collect();
+        Some(
+            matched
+                .ranges
+                .iter()
+                .map(|r| chars[r.start..r.end].iter().collect())
+                .collect(),
+        )
+    }
+
+    fn score(query: &str, text: &str) -> i32 {
+        FuzzyQuery::new(query)
+            .and_then(|q| q.match_text(text))
+            .map_or(NONE, |m| m.score)
+    }
+
+    #[test]
+    fn highlights_ranges_in_the_original_text() {
+        for (query, text, expected) in [
+            ("deploy", "Deploy script", vec!["Deploy"]),
+            ("dps", "deploy script", vec!["d", "p", "s"]),
+            ("kaigi", "定例カイギの準備", vec!["カイギ"]),
+            ("かいぎ", "ｶｲｷﾞ資料", vec!["ｶｲｷﾞ"]),
+            ("がい", "ｶﾞｲﾄﾞ", vec!["ｶﾞｲ"]),
+            ("ぎ", "ｶｲｷﾞ", vec!["ｷﾞ"]),
+            ("mtg", "ＭＴＧ準備", vec!["ＭＴＧ"]),
+            ("会議 準備", "会議の準備", vec!["会議", "準備"]),
+        ] {
+            assert_eq!(
+                highlighted(query, text),
+                Some(expected.iter().map(|s| s.to_string()).collect()),
+                "{} / {}",
+                query,
+                text
+            );
+        }
+        assert_eq!(highlighted("xyz", "deploy"), None);
+        assert_eq!(highlighted("   ", "deploy"), None);
+        assert_eq!(highlighted("deploys", "deploy"), None);
+    }
+
+    #[test]
+    fn prefers_word_starts_and_consecutive_matches() {
+        assert!(score("mtg", "MTG準備") > score("mtg", "meeting"));
+        assert!(score("rep", "report draft") > score("rep", "prepare"));
+        assert!(score("dr", "report draft") > score("dr", "order"));
+        assert!(score("abc", "abc") > score("abc", "a-b-c"));
+        // ローマ字とかなの入力は同じ点数になる
+        assert_eq!(
+            score("kaigi", "会議 かいぎ"),
+            score("かいぎ", "会議 かいぎ")
+        );
+    }
+}
@@ -0,0 +1,543 @@
+// 転置索引
+// 語ごとに「どのタスクのどのフィールドの何番目に現れるか」を持ち、BM25 で関連度を計算する
+
+use std::collections::hash_map::DefaultHasher;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::hash::{Hash, Hasher};
+
+use super::query::Clause;
+use super::tokenizer::{is_cjk_term, terms, Term};
+use crate::models::Todo;
+
+const BM25_K1: f64 = 1.2;
+const BM25_B: f64 = 0.75;
+// 新しく更新されたタスクほど上に来るよう、最大でこの割合だけ点数を上げる
+const RECENCY_WEIGHT: f64 = 0.3;
+const RECENCY_HALF_LIFE_DAYS: f64 = 14.0;
+const DAY_MS: f64 = 24.0 * 60.0 * 60.0 * 1000.0;
+// コメントやラベルをまたいで句が一致しないよう、位置を空ける
+const ENTRY_GAP: u32 = 16;
+
+#[derive(serde::Serialize, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Debug)]
+#[serde(rename_all = "camelCase")]
+pub enum Field {
+    Text,
+    Description,
+    Comments,
+    Labels,
+}
+
+const FIELDS: [Field; 4] = [
+    Field::Text,
+    Field::Description,
+    Field::Comments,
+    Field::Labels,
+];
+
+impl Field {
+    fn index(self) -> usize {
+        self as usize
+    }
+
+    fn weight(self) -> f64 {
+        match self {
+            Field::Text => 3.0,
+            Field::Labels => 2.0,
+            Field::Description => 1.0,
+            Field::Comments => 0.8,
+        }
+    }
+}
+
+struct Document {
+    fingerprint: u64,
+    updated_at: i64,
+    active: bool,
+    // フィールドごとの語数
+    lengths: [u32; 4],
+    // 索引から外すときに使う
+    terms: Vec<String>,
+}
+
+// 語が現れたフィールドと位置 (フィールド・位置の順に並ぶので、句の照合は二分探索で引く)
+type Postings = HashMap<String, Vec<(Field, u32)>>;
+
+#[derive(serde::Serialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct SearchHit {
+    pub id: String,
+    pub score: f64,
+    pub fields: Vec<Field>,
+}
+
+#[derive(Default)]
+pub struct SearchIndex {
+    documents: HashMap<String, Document>,
+    postings: BTreeMap<String, Postings>,
+    total_lengths: [u64; 4],
+}
+
+fn fingerprint(todo: &Todo) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    todo.text.hash(&mut hasher);
+    todo.description.hash(&mut hasher);
+    todo.labels.hash(&mut hasher);
+    for comment in &todo.comments {
+        comment.text.hash(&mut hasher);
+    }
+    todo.is_active().hash(&mut hasher);
+    todo.last_modified().hash(&mut hasher);
+    hasher.finish()
+}
+
+// 複数の値 (コメント・ラベル) を位置を空けてつなげる
+fn entry_terms<'a>(entries: impl Iterator<Item = &'a str>) -> Vec<Term> {
+    let mut all = Vec::new();
+    let mut base = 0;
+    for entry in entries {
+        let terms = terms(entry);
+        let next = terms
+            .last()
+            .map_or(base, |t| base + t.position + 1 + ENTRY_GAP);
+        all.extend(terms.into_iter().map(|t| Term {
+            position: base + t.position,
+            ..t
+        }));
+        base = next;
+    }
+    all
+}
+
+fn field_terms(todo: &Todo) -> [(Field, Vec<Term>); 4] {
+    [
+        (Field::Text, terms(&todo.text)),
+        (Field::Description, terms(&todo.description)),
+        (
+            Field::Comments,
+            entry_terms(todo.comments.iter().map(|c| c.text.as_str())),
+        ),
+        (
+            Field::Labels,
+            entry_terms(todo.labels.iter().map(String::as_str)),
+        ),
+    ]
+}
+
+// 語の出現回数 (タスク → フィールドごとの回数)
+type Matches = HashMap<String, [u32; 4]>;
+
+fn add_counts(matches: &mut Matches, postings: &Postings) {
+    for (id, occurrences) in postings {
+        let counts = matches.entry(id.clone()).or_default();
+        for (field, _) in occurrences {
+            counts[field.index()] += 1;
+        }
+    }
+}
+
+impl SearchIndex {
+    pub fn len(&self) -> usize {
+        self.documents.len()
+    }
+
+    pub fn term_count(&self) -> usize {
+        self.postings.len()
+    }
+
+    pub fn remove(&mut self, id: &str) {
+        let Some(document) = self.documents.remove(id) else {
+            return;
+        };
+        for term in &document.terms {
+            if let Some(postings) = self.postings.get_mut(term) {
+                postings.remove(id);
+                if postings.is_empty() {
+                    self.postings.remove(term);
+                }
+            }
+        }
+        for field in FIELDS {
+            self.total_lengths[field.index()] -= u64::from(document.lengths[field.index()]);
+        }
+    }
+
+    pub fn upsert(&mut self, todo: &Todo) {
+        self.remove(&todo.id);
+
+        let mut lengths = [0; 4];
+        let mut unique = HashSet::new();
+        for (field, terms) in field_terms(todo) {
+            lengths[field.index()] = terms.len() as u32;
+            self.total_lengths[field.index()] += terms.len() as u64;
+            for term in terms {
+                self.postings
+                    .entry(term.text.clone())
+                    .or_default()
+                    .entry(todo.id.clone())
+                    .or_default()
+                    .push((field, term.position));
+                unique.insert(term.text);
+            }
+        }
+        self.documents.insert(
+            todo.id.clone(),
+            Document {
+                fingerprint: fingerprint(todo),
+                updated_at: todo.last_modified(),
+                active: todo.is_active(),
+                lengths,
+                terms: unique.into_iter().collect(),
+            },
+        );
+    }
+
+    // 変更のあったタスクだけを索引し直し、なくなったタスクを外す
+    // 戻り値は (更新した件数, 外した件数)
+    pub fn sync(&mut self, todos: &[Todo]) -> (usize, usize) {
+        let ids: HashSet<&str> = todos.iter().map(|t| t.id.as_str()).collect();
+        let removed: Vec<String> = self
+            .documents
+            .keys()
+            .filter(|id| !ids.contains(id.as_str()))
+            .cloned()
+            .collect();
+        for id in &removed {
+            self.remove(id);
+        }
+
+        let mut updated = 0;
+        for todo in todos {
+            let unchanged = self
+                .documents
+                .get(&todo.id)
+                .is_some_and(|d| d.fingerprint == fingerprint(todo));
+            if !unchanged {
+                self.upsert(todo);
+                updated += 1;
+            }
+        }
+        (updated, removed.len())
+    }
+
+    // 語が順に隣り合って現れる箇所を数える
+    fn phrase_matches(&self, terms: &[String]) -> Matches {
+        let mut matches = Matches::new();
+        let Some(first) = terms.first().and_then(|t| self.postings.get(t)) else {
+            return matches;
+        };
+        if terms.len() == 1 {
+            add_counts(&mut matches, first);
+            return matches;
+        }
+        let Some(rest) = terms[1..]
+            .iter()
+            .map(|t| self.postings.get(t))
+            .collect::<Option<Vec<_>>>()
+        else {
+            return matches;
+        };
+
+        for (id, occurrences) in first {
+            let mut counts = [0; 4];
+            for (field, position) in occurrences {
+                let continues = rest.iter().enumerate().all(|(k, postings)| {
+                    postings.get(id).is_some_and(|o| {
+                        o.binary_search(&(*field, position + k as u32 + 1)).is_ok()
+                    })
+                });
+                if continues {
+                    counts[field.index()] += 1;
+                }
+            }
+            if counts.iter().any(|c| *c > 0) {
+                matches.insert(id.clone(), counts);
+            }
+        }
+        matches
+    }
+
+    fn clause_matches(&self, clause: &Clause) -> Matches {
+        let mut matches = Matches::new();
+        match clause {
+            Clause::Phrase(terms) => return self.phrase_matches(terms),
+            Clause::Prefix(prefix) => {
+                for (_, postings) in self
+                    .postings
+                    .range(prefix.clone()..)
+                    .take_while(|(term, _)| term.starts_with(prefix.as_str()))
+                {
+                    add_counts(&mut matches, postings);
+                }
+            }
+            Clause::Any(clauses) => {
+                for clause in clauses {
+                    for (id, counts) in self.clause_matches(clause) {
+                        let merged = matches.entry(id).or_default();
+                        for (total, count) in merged.iter_mut().zip(counts) {
+                            *total = (*total).max(count);
+                        }
+                    }
+                }
+            }
+            Clause::Char(c) => {
+                for (_, postings) in self
+                    .postings
+                    .iter()
+                    .filter(|(term, _)| is_cjk_term(term) && term.contains(*c))
+                {
+                    add_counts(&mut matches, postings);
+                }
+            }
+        }
+        matches
+    }
+
+    pub fn search(
+        &self,
+        clauses: &[Clause],
+        include_completed: bool,
+        now: i64,
+        limit: usize,
+    ) -> Vec<SearchHit> {
+        if clauses.is_empty() || self.documents.is_empty() {
+            return Vec::new();
+        }
+        let total = self.documents.len() as f64;
+        let average_lengths = self
+            .total_lengths
+            .map(|length| (length as f64 / total).max(1.0));
+
+        let mut scores: Option<HashMap<String, (f64, [bool; 4])>> = None;
+        for clause in clauses {
+            let matches = self.clause_matches(clause);
+            let found = matches.len() as f64;
+            let idf = (1.0 + (total - found + 0.5) / (found + 0.5)).ln();
+
+            let mut next = HashMap::new();
+            for (id, counts) in matches {
+                let previous = match &scores {
+                    Some(scores) => match scores.get(&id) {
+                        Some(previous) => *previous,
+                        None => continue,
+                    },
+                    None => (0.0, [false; 4]),
+                };
+                let Some(document) = self.documents.get(&id) else {
+                    continue;
+                };
+                let (mut score, mut fields) = previous;
+                for field in FIELDS.into_iter().filter(|f| counts[f.index()] > 0) {
+                    let tf = f64::from(counts[field.index()]);
+                    let length = f64::from(document.lengths[field.index()]);
+                    let norm = 1.0 - BM25_B + BM25_B * length / average_lengths[field.index()];
+                    score += field.weight() * idf * tf * (BM25_K1 + 1.0) / (tf + BM25_K1 * norm);
+                    fields[field.index()] = true;
+                }
+                next.insert(id, (score, fields));
+            }
+            scores = Some(next);
+        }
+
+        let mut hits: Vec<(SearchHit, i64)> = scores
+            .unwrap_or_default()
+            .into_iter()
+            .filter_map(|(id, (score, fields))| {
+                let document = self.documents.get(&id)?;
+                if !include_completed && !document.active {
+                    return None;
+                }
+                let age_days = ((now - document.updated_at) as f64 / DAY_MS).max(0.0);
+                let recency = 0.5f64.powf(age_days / RECENCY_HALF_LIFE_DAYS);
+                let score = score * (1.0 + RECENCY_WEIGHT * recency);
+                let hit = SearchHit {
+                    id,
+                    score: (score * 1000.0).round() / 1000.0,
+                    fields: FIELDS.into_iter().filter(|f| fields[f.index()]).collect(),
+                };
+                Some((hit, document.updated_at))
+            })
+            .collect();
+        hits.sort_by(|(a, a_updated), (b, b_updated)| {
+            b.score
+                .total_cmp(&a.score)
+                .then(b_updated.cmp(a_updated))
+                .then(a.id.cmp(&b.id))
+        });
+        hits.into_iter().take(limit).map(|(hit, _)| hit).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::TodoComment;
+    use crate::search::query::parse_query;
+
+    const NOW: i64 = 1_767_225_600_000;
+
+    fn todo(id: &str, text: &str) -> Todo {
+        Todo {
+            id: id.to_string(),
+            text: text.to_string(),
+            created_at: NOW,
+            ..Default::default()
+        }
+    }
+
+    fn comments(texts: &[&str]) -> Vec<TodoComment> {
+        texts
+            .iter()
+            .map(|text| TodoComment {
+                text: text.to_string(),
+                ..Default::default()
+            })
+            .collect()
+    }
+
+    fn index(todos: &[Todo]) -> SearchIndex {
+        let mut index = SearchIndex::default();
+        index.sync(todos);
+        index
+    }
+
+    fn ids(index: &SearchIndex, query: &str) -> Vec<String> {
+        index
+            .search(&parse_query(query), false, NOW, 10)
+            .into_iter()
+            .map(|hit| hit.id)
+            .collect()
+    }
+
+    #[test]
+    fn phrases_must_be_adjacent_within_one_entry() {
+        let index = index(&[
+            Todo {
+                comments: comments(&["weekly", "sync"]),
+                labels: vec!["定例".to_string(), "会議".to_string()],
+                ..todo("split", "予定")
+            },
+            Todo {
+                comments: comments(&["the weekly sync moved"]),
+                ..todo("joined", "予定")
+            },
+            todo("reversed", "sync weekly"),
+            todo("kanji", "定例会議の準備"),
+        ]);
+        assert_eq!(ids(&index, "\"weekly sync\""), ["joined"]);
+        // 別々のコメント・ラベルにまたがる語も、句でなければ一致する
+        let mut both = ids(&index, "weekly sync");
+        both.sort();
+        assert_eq!(both, ["joined", "reversed", "split"]);
+        assert_eq!(ids(&index, "「定例会議」"), ["kanji"]);
+        assert_eq!(ids(&index, "例会"), ["kanji"]);
+
+        let hit = &index.search(&parse_query("\"weekly sync\""), false, NOW, 10)[0];
+        assert_eq!(hit.fields, [Field::Comments]);
+    }
+
+    #[test]
+    fn phrases_match_repeated_terms() {
+        let index = index(&[
+            todo("a", "go go go now"),
+            todo("b", "go now go"),
+            todo("c", "now go"),
+        ]);
+        let mut found = ids(&index, "\"go now\"");
+        found.sort();
+        assert_eq!(found, ["a", "b"]);
+        assert_eq!(ids(&index, "\"now go go\""), Vec::<String>::new());
+        assert_eq!(ids(&index, "\"go go go\""), ["a"]);
+    }
+
+    #[test]
+    fn prefix_queries_match_latin_terms() {
+        let index = index(&[
+            todo("deploy", "deploy script"),
+            todo("deployment", "Deployment plan"),
+            todo("redeploy", "redeploy"),
+            todo("kanji", "デプロイ手順"),
+        ]);
+        let mut found = ids(&index, "depl*");
+        found.sort();
+        assert_eq!(found, ["deploy", "deployment"]);
+        // 日本語の * は前方一致にせず語として探す
+        assert_eq!(ids(&index, "デプ*"), ["kanji"]);
+        assert!(ids(&index, "x*").is_empty());
+    }
+
+    #[test]
+    fn sync_updates_only_changed_todos() {
+        let mut todos = vec![
+            Todo {
+                description: "one two".to_string(),
+                ..todo("a", "alpha beta")
+            },
+            Todo {
+                labels: vec!["work".to_string()],
+                ..todo("b", "gamma")
+            },
+        ];
+        let mut index = SearchIndex::default();
+        assert_eq!(index.sync(&todos), (2, 0));
+        assert_eq!(index.total_lengths, [3, 2, 0, 1]);
+        assert_eq!(index.sync(&todos), (0, 0));
+
+        todos[0].text = "alpha".to_string();
+        todos[0].updated_at = Some(NOW + 1);
+        assert_eq!(index.sync(&todos), (1, 0));
+        assert_eq!(index.total_lengths, [2, 2, 0, 1]);
+        assert!(ids(&index, "beta").is_empty());
+        assert_eq!(ids(&index, "alpha"), ["a"]);
+
+        todos.remove(1);
+        assert_eq!(index.sync(&todos), (0, 1));
+        assert_eq!(index.total_lengths, [1, 2, 0, 0]);
+        assert_eq!(index.len(), 1);
+        // 使われなくなった語は索引から消える
+        assert_eq!(index.term_count(), 3);
+        assert!(ids(&index, "gamma").is_empty());
+
+        assert_eq!(index.sync(&[]), (0, 1));
+        assert_eq!(index.total_lengths, [0; 4]);
+        assert_eq!(index.term_count(), 0);
+    }
+
+    #[test]
+    fn ranks_by_field_frequency_and_recency() {
+        let old = NOW - 60 * DAY_MS as i64;
+        let index = index(&[
+            Todo {
+                description: "report".to_string(),
+                ..todo("description", "draft")
+            },
+            Todo {
+                labels: vec!["report".to_string()],
+                ..todo("label", "draft")
+            },
+            todo("title", "report draft"),
+            Todo {
+                created_at: old,
+                ..todo("title-old", "report draft")
+            },
+            todo("twice", "report report"),
+            todo("other", "draft"),
+        ]);
+        assert_eq!(
+            ids(&index, "report"),
+            ["twice", "title", "title-old", "label", "description"]
+        );
+        // すべての語を含むものだけ
+        let mut both = ids(&index, "report draft");
+        both.sort();
+        assert_eq!(both, ["description", "label", "title", "title-old"]);
+
+        let done = Todo {
+            completed: true,
+            ..todo("done", "report")
+        };
+        let index = self::index(&[done, todo("open", "report")]);
+        assert_eq!(ids(&index, "report"), ["open"]);
+        assert_eq!(index.search(&parse_query("report"), true, NOW, 10).len(), 2);
+        assert_eq!(index.search(&parse_query("report"), true, NOW, 1).len(), 1);
+    }
+}
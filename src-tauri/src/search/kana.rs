@@ -0,0 +1,106 @@
+// かなと英数字の表記ゆれをそろえる
+// カタカナ→ひらがな、半角カナ→全角 (濁点・半濁点は前の文字と合わせる)、全角英数字→半角小文字
+
+use super::tokenizer::is_halfwidth_katakana;
+
+// 半角カナ (U+FF66〜U+FF9D) に対応するひらがな
+const HALFWIDTH_KANA: [char; 56] = [
+    'を', 'ぁ', 'ぃ', 'ぅ', 'ぇ', 'ぉ', 'ゃ', 'ゅ', 'ょ', 'っ', 'ー', 'あ', 'い', 'う', 'え', 'お',
+    'か', 'き', 'く', 'け', 'こ', 'さ', 'し', 'す', 'せ', 'そ', 'た', 'ち', 'つ', 'て', 'と', 'な',
+    'に', 'ぬ', 'ね', 'の', 'は', 'ひ', 'ふ', 'へ', 'ほ', 'ま', 'み', 'む', 'め', 'も', 'や', 'ゆ',
+    'よ', 'ら', 'り', 'る', 'れ', 'ろ', 'わ', 'ん',
+];
+const HALFWIDTH_DAKUTEN: char = '\u{FF9E}';
+const HALFWIDTH_HANDAKUTEN: char = '\u{FF9F}';
+
+fn to_hiragana(c: char) -> char {
+    match c {
+        // ァ〜ヶ
+        '\u{30A1}'..='\u{30F6}' => char::from_u32(c as u32 - 0x60).unwrap_or(c),
+        '\u{FF66}'..='\u{FF9D}' => HALFWIDTH_KANA[(c as u32 - 0xFF66) as usize],
+        '\u{FF01}'..='\u{FF5E}' => char::from_u32(c as u32 - 0xFEE0).unwrap_or(c),
+        '\u{3000}' => ' ',
+        _ => c,
+    }
+    .to_ascii_lowercase()
+}
+
+// 濁点・半濁点を付けた文字 (付けられなければ None)
+fn with_mark(c: char, mark: char) -> Option<char> {
+    let offset = match (c, mark) {
+        ('う', HALFWIDTH_DAKUTEN) => return Some('ゔ'),
+        ('か'..='ぢ', HALFWIDTH_DAKUTEN) if (c as u32 - 'か' as u32).is_multiple_of(2) => 1,
+        ('つ'..='ど', HALFWIDTH_DAKUTEN) if (c as u32 - 'つ' as u32).is_multiple_of(2) => 1,
+        ('は'..='ほ', HALFWIDTH_DAKUTEN) if (c as u32 - 'は' as u32).is_multiple_of(3) => 1,
+        ('は'..='ほ', HALFWIDTH_HANDAKUTEN) if (c as u32 - 'は' as u32).is_multiple_of(3) => 2,
+        _ => return None,
+    };
+    char::from_u32(c as u32 + offset)
+}
+
+// そろえた文字と、それぞれが元の文字列の何文字目から何文字目に当たるか
+pub struct Normalized {
+    pub chars: Vec<char>,
+    pub spans: Vec<(usize, usize)>,
+}
+
+pub fn normalize(text: &str) -> Normalized {
+    let mut chars: Vec<char> = Vec::new();
+    let mut spans: Vec<(usize, usize)> = Vec::new();
+    let mut previous_halfwidth = false;
+    for (i, c) in text.chars().enumerate() {
+        if matches!(c, HALFWIDTH_DAKUTEN | HALFWIDTH_HANDAKUTEN) && previous_halfwidth {
+            if let (Some(last), Some(span)) = (chars.last_mut(), spans.last_mut()) {
+                if let Some(marked) = with_mark(*last, c) {
+                    *last = marked;
+                    span.1 = i + 1;
+                    previous_halfwidth = false;
+                    continue;
+                }
+            }
+        }
+        previous_halfwidth = is_halfwidth_katakana(c);
+        chars.push(to_hiragana(c));
+        spans.push((i, i + 1));
+    }
+    Normalized { chars, spans }
+}
+
+// 表記ゆれをそろえた文字列 (検索索引用)
+pub fn fold(text: &str) -> String {
+    normalize(text).chars.into_iter().collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn folds_kana_and_width_variants() {
+        for (text, expected) in [
+            ("カイギ", "かいぎ"),
+            ("ｶｲｷﾞ", "かいぎ"),
+            ("ｶﾞｷﾞｸﾞｹﾞｺﾞ", "がぎぐげご"),
+            ("ﾀﾞﾁﾞﾂﾞﾃﾞﾄﾞ", "だぢづでど"),
+            ("ﾊﾞﾋﾟﾌﾞﾍﾟﾎﾞ", "ばぴぶぺぼ"),
+            ("ｳﾞｧｲｵﾘﾝ", "ゔぁいおりん"),
+            ("ｼｮｰﾄｶｯﾄ", "しょーとかっと"),
+            ("ヴァイオリン", "ゔぁいおりん"),
+            ("ＭＴＧ　１０時", "mtg 10時"),
+            ("Deploy", "deploy"),
+        ] {
+            assert_eq!(fold(text), expected, "{}", text);
+        }
+        // 濁点を付けられない文字や全角の後の半角濁点は合わせない
+        assert_eq!(fold("ｱﾞ"), "あ\u{FF9E}");
+        assert_eq!(fold("カﾞ"), "か\u{FF9E}");
+        assert_eq!(fold("ﾊﾞﾟ"), "ば\u{FF9F}");
+    }
+
+    #[test]
+    fn spans_cover_merged_marks() {
+        let normalized = normalize("aｶﾞｲﾎﾟ");
+        assert_eq!(normalized.chars, ['a', 'が', 'い', 'ぽ']);
+        assert_eq!(normalized.spans, [(0, 1), (1, 3), (3, 4), (4, 6)]);
+    }
+}
@@ -0,0 +1,198 @@
+// タスクの全文検索
+// text / description / comments / labels を索引にし、関連度と更新の新しさで並べる
+// 索引は起動時にスナップショットから作り、タスクが同期されるたびに変わったタスクだけ更新する
+// コマンドパレット向けに、タスク・プロジェクト・ラベルのあいまい検索も提供する
+
+mod fuzzy;
+mod index;
+mod kana;
+mod query;
+mod romaji;
+mod tokenizer;
+
+use chrono::Local;
+use std::sync::Mutex;
+use tauri::Manager;
+
+use crate::models::Todo;
+use fuzzy::{FuzzyQuery, MatchRange};
+pub use index::SearchHit;
+use index::SearchIndex;
+pub use kana::fold;
+pub use tokenizer::split_query_tokens;
+
+const DEFAULT_LIMIT: usize = 50;
+
+#[derive(serde::Deserialize, Default)]
+#[serde(rename_all = "camelCase", default)]
+pub struct SearchOptions {
+    pub limit: Option<usize>,
+    // 完了・アーカイブ済みのタスクも含める
+    pub include_completed: bool,
+}
+
+#[derive(serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SearchStats {
+    pub documents: usize,
+    pub terms: usize,
+}
+
+pub struct SearchState {
+    index: Mutex<SearchIndex>,
+}
+
+impl SearchState {
+    pub fn load(app: &tauri::AppHandle) -> Self {
+        let mut index = SearchIndex::default();
+        index.sync(&app.state::<crate::todos::TodoState>().all());
+        Self {
+            index: Mutex::new(index),
+        }
+    }
+
+    pub fn sync(&self, todos: &[Todo]) {
+        let (updated, removed) = self.index.lock().unwrap().sync(todos);
+        if updated + removed > 0 {
+            println!(
+                "[Search Backend] 索引を更新: 更新 {} 件, 削除 {} 件",
+                updated, removed
+            );
+        }
+    }
+
+    pub fn search(&self, query: &str, options: &SearchOptions) -> Vec<SearchHit> {
+        let clauses = query::parse_query(query);
+        self.index.lock().unwrap().search(
+            &clauses,
+            options.include_completed,
+            Local::now().timestamp_millis(),
+            options.limit.unwrap_or(DEFAULT_LIMIT),
+        )
+    }
+}
+
+#[derive(serde::Serialize, serde::Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum FuzzyKind {
+    Task,
+    Project,
+    Label,
+}
+
+#[derive(serde::Deserialize, Default)]
+#[serde(rename_all = "camelCase", default)]
+pub struct FuzzyOptions {
+    pub limit: Option<usize>,
+    // 空なら全種類
+    pub kinds: Vec<FuzzyKind>,
+    // 完了・アーカイブ済みのタスクとアーカイブ済みのプロジェクトも含める
+    pub include_completed: bool,
+}
+
+#[derive(serde::Serialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct FuzzyHit {
+    pub kind: FuzzyKind,
+    // ラベルはラベル名
+    pub id: String,
+    pub text: String,
+    pub score: i32,
+    pub ranges: Vec<MatchRange>,
+}
+
+pub fn fuzzy_search(
+    query: &str,
+    todos: &[Todo],
+    projects: &[crate::models::Project],
+    options: &FuzzyOptions,
+) -> Vec<FuzzyHit> {
+    let Some(query) = FuzzyQuery::new(query) else {
+        return Vec::new();
+    };
+    let wanted = |kind| options.kinds.is_empty() || options.kinds.contains(&kind);
+
+    let mut candidates: Vec<(FuzzyKind, &str, &str)> = Vec::new();
+    if wanted(FuzzyKind::Task) {
+        candidates.extend(
+            todos
+                .iter()
+                .filter(|t| options.include_completed || t.is_active())
+                .map(|t| (FuzzyKind::Task, t.id.as_str(), t.text.as_str())),
+        );
+    }
+    if wanted(FuzzyKind::Project) {
+        candidates.extend(
+            projects
+                .iter()
+                .filter(|p| options.include_completed || !p.is_archived)
+                .map(|p| (FuzzyKind::Project, p.id.as_str(), p.name.as_str())),
+        );
+    }
+    if wanted(FuzzyKind::Label) {
+        let mut labels: Vec<&str> = todos
+            .iter()
+            .flat_map(|t| t.labels.iter().map(String::as_str))
+            .collect();
+        labels.sort_unstable();
+        labels.dedup();
+        candidates.extend(labels.into_iter().map(|l| (FuzzyKind::Label, l, l)));
+    }
+
+    let mut hits: Vec<FuzzyHit> = candidates
+        .into_iter()
+        .filter_map(|(kind, id, text)| {
+            let matched = query.match_text(text)?;
+            Some(FuzzyHit {
+                kind,
+                id: id.to_string(),
+                text: text.to_string(),
+                score: matched.score,
+                ranges: matched.ranges,
+            })
+        })
+        .collect();
+    // 同じ点数なら短い方を上にする
+    hits.sort_by(|a, b| {
+        b.score
+            .cmp(&a.score)
+            .then(a.text.chars().count().cmp(&b.text.chars().count()))
+            .then(a.text.cmp(&b.text))
+    });
+    hits.truncate(options.limit.unwrap_or(DEFAULT_LIMIT));
+    hits
+}
+
+// タスクを検索し、関連度の高い順に返す
+#[tauri::command]
+pub fn search_tasks(
+    state: tauri::State<'_, SearchState>,
+    query: String,
+    options: Option<SearchOptions>,
+) -> Vec<SearchHit> {
+    state.search(&query, &options.unwrap_or_default())
+}
+
+#[tauri::command]
+pub fn search_stats(state: tauri::State<'_, SearchState>) -> SearchStats {
+    let index = state.index.lock().unwrap();
+    SearchStats {
+        documents: index.len(),
+        terms: index.term_count(),
+    }
+}
+
+// タスク・プロジェクト・ラベルをあいまい検索する (一致箇所の範囲付き)
+#[tauri::command]
+pub fn search_fuzzy(
+    todos: tauri::State<'_, crate::todos::TodoState>,
+    query: String,
+    options: Option<FuzzyOptions>,
+) -> Vec<FuzzyHit> {
+    fuzzy_search(
+        &query,
+        &todos.all(),
+        &todos.projects(),
+        &options.unwrap_or_default(),
+    )
+}
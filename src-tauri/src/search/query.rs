@@ -0,0 +1,122 @@
+// 検索クエリの解釈
+//   会議 資料      : すべての語を含む
+//   "weekly sync"  : 語がこの順に並ぶ (「」でも囲める)
+//   deploy*        : その語で始まる英単語
+//   kaigi          : ローマ字はかな (かいぎ) としても探す
+
+use super::romaji::romaji_to_kana;
+use super::tokenizer::{is_cjk_term, terms};
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Clause {
+    // 語の並び (日本語は2文字ずつの語が連続して現れる)
+    Phrase(Vec<String>),
+    Prefix(String),
+    // 日本語1文字 (その文字を含む語すべて)
+    Char(char),
+    // いずれかに一致
+    Any(Vec<Clause>),
+}
+
+fn clause_for(text: &str) -> Option<Clause> {
+    let terms: Vec<String> = terms(text).into_iter().map(|t| t.text).collect();
+    match terms.as_slice() {
+        [] => None,
+        [term] if is_cjk_term(term) && term.chars().count() == 1 => {
+            term.chars().next().map(Clause::Char)
+        }
+        _ => Some(Clause::Phrase(terms)),
+    }
+}
+
+// ローマ字として読める英字の語は、かなの語としても照合する
+fn with_kana(clause: Clause, word: &str) -> Clause {
+    let kana = romaji_to_kana(word)
+        .filter(|_| word.chars().all(|c| c.is_ascii_alphabetic()))
+        .and_then(|kana| clause_for(&kana));
+    match kana {
+        Some(kana) => Clause::Any(vec![clause, kana]),
+        None => clause,
+    }
+}
+
+fn word_clause(word: &str) -> Option<Clause> {
+    if let Some(stem) = word.strip_suffix('*') {
+        let terms = terms(stem);
+        if let [term] = terms.as_slice() {
+            if !is_cjk_term(&term.text) {
+                return Some(Clause::Prefix(term.text.clone()));
+            }
+        }
+        return clause_for(stem);
+    }
+    clause_for(word).map(|clause| with_kana(clause, word))
+}
+
+pub fn parse_query(query: &str) -> Vec<Clause> {
+    let mut clauses = Vec::new();
+    let mut rest = query;
+    while !rest.is_empty() {
+        let quote = rest.find(['"', '「']);
+        let (plain, quoted) = match quote {
+            Some(start) => (&rest[..start], Some(&rest[start..])),
+            None => (rest, None),
+        };
+        clauses.extend(plain.split_whitespace().filter_map(word_clause));
+
+        let Some(quoted) = quoted else {
+            break;
+        };
+        let open = quoted.chars().next().unwrap_or('"');
+        let close = if open == '「' { '」' } else { '"' };
+        let body = &quoted[open.len_utf8()..];
+        // 閉じていない引用符は最後までを句とみなす
+        let (phrase, next) = match body.find(close) {
+            Some(end) => (&body[..end], &body[end + close.len_utf8()..]),
+            None => (body, ""),
+        };
+        clauses.extend(clause_for(phrase));
+        rest = next;
+    }
+    clauses
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn phrase(terms: &[&str]) -> Clause {
+        Clause::Phrase(terms.iter().map(|t| t.to_string()).collect())
+    }
+
+    #[test]
+    fn parses_words_phrases_and_prefixes() {
+        assert_eq!(
+            parse_query("資料 \"weekly sync\" deploy* 「定例会議」"),
+            [
+                phrase(&["資料"]),
+                phrase(&["weekly", "sync"]),
+                Clause::Prefix("deploy".to_string()),
+                phrase(&["定例", "例会", "会議"]),
+            ]
+        );
+        assert_eq!(parse_query("会"), [Clause::Char('会')]);
+        // 閉じていない引用符は最後までを句とする
+        assert_eq!(parse_query("\"a b"), [phrase(&["a", "b"])]);
+        assert!(parse_query("  \"\" ").is_empty());
+    }
+
+    #[test]
+    fn reads_latin_words_as_romaji() {
+        assert_eq!(
+            parse_query("kaigi"),
+            [Clause::Any(vec![
+                phrase(&["kaigi"]),
+                phrase(&["かい", "いぎ"])
+            ])]
+        );
+        // ローマ字として読めない語・英字以外を含む語はそのまま
+        assert_eq!(parse_query("xyz"), [phrase(&["xyz"])]);
+        assert_eq!(parse_query("v2"), [phrase(&["v2"])]);
+    }
+}
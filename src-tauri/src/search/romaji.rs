@@ -0,0 +1,331 @@
+// ローマ字入力をかなにする (ヘボン式・訓令式のどちらも受け付ける)
+// 入力途中の子音 (「kaig」の g) は、その行のかなのどれとでも一致させる
+
+const TABLE: &[(&str, &str)] = &[
+    ("a", "あ"),
+    ("i", "い"),
+    ("u", "う"),
+    ("e", "え"),
+    ("o", "お"),
+    ("ka", "か"),
+    ("ki", "き"),
+    ("ku", "く"),
+    ("ke", "け"),
+    ("ko", "こ"),
+    ("ga", "が"),
+    ("gi", "ぎ"),
+    ("gu", "ぐ"),
+    ("ge", "げ"),
+    ("go", "ご"),
+    ("sa", "さ"),
+    ("si", "し"),
+    ("shi", "し"),
+    ("su", "す"),
+    ("se", "せ"),
+    ("so", "そ"),
+    ("za", "ざ"),
+    ("zi", "じ"),
+    ("ji", "じ"),
+    ("zu", "ず"),
+    ("ze", "ぜ"),
+    ("zo", "ぞ"),
+    ("ta", "た"),
+    ("ti", "ち"),
+    ("chi", "ち"),
+    ("tu", "つ"),
+    ("tsu", "つ"),
+    ("te", "て"),
+    ("to", "と"),
+    ("da", "だ"),
+    ("di", "ぢ"),
+    ("du", "づ"),
+    ("de", "で"),
+    ("do", "ど"),
+    ("na", "な"),
+    ("ni", "に"),
+    ("nu", "ぬ"),
+    ("ne", "ね"),
+    ("no", "の"),
+    ("ha", "は"),
+    ("hi", "ひ"),
+    ("hu", "ふ"),
+    ("fu", "ふ"),
+    ("he", "へ"),
+    ("ho", "ほ"),
+    ("ba", "ば"),
+    ("bi", "び"),
+    ("bu", "ぶ"),
+    ("be", "べ"),
+    ("bo", "ぼ"),
+    ("pa", "ぱ"),
+    ("pi", "ぴ"),
+    ("pu", "ぷ"),
+    ("pe", "ぺ"),
+    ("po", "ぽ"),
+    ("ma", "ま"),
+    ("mi", "み"),
+    ("mu", "む"),
+    ("me", "め"),
+    ("mo", "も"),
+    ("ya", "や"),
+    ("yu", "ゆ"),
+    ("yo", "よ"),
+    ("ra", "ら"),
+    ("ri", "り"),
+    ("ru", "る"),
+    ("re", "れ"),
+    ("ro", "ろ"),
+    ("wa", "わ"),
+    ("wi", "うぃ"),
+    ("we", "うぇ"),
+    ("wo", "を"),
+    ("kya", "きゃ"),
+    ("kyu", "きゅ"),
+    ("kyo", "きょ"),
+    ("gya", "ぎゃ"),
+    ("gyu", "ぎゅ"),
+    ("gyo", "ぎょ"),
+    ("sya", "しゃ"),
+    ("syu", "しゅ"),
+    ("syo", "しょ"),
+    ("sha", "しゃ"),
+    ("shu", "しゅ"),
+    ("she", "しぇ"),
+    ("sho", "しょ"),
+    ("zya", "じゃ"),
+    ("zyu", "じゅ"),
+    ("zyo", "じょ"),
+    ("ja", "じゃ"),
+    ("ju", "じゅ"),
+    ("je", "じぇ"),
+    ("jo", "じょ"),
+    ("jya", "じゃ"),
+    ("jyu", "じゅ"),
+    ("jyo", "じょ"),
+    ("tya", "ちゃ"),
+    ("tyu", "ちゅ"),
+    ("tyo", "ちょ"),
+    ("cha", "ちゃ"),
+    ("chu", "ちゅ"),
+    ("che", "ちぇ"),
+    ("cho", "ちょ"),
+    ("nya", "にゃ"),
+    ("nyu", "にゅ"),
+    ("nyo", "にょ"),
+    ("hya", "ひゃ"),
+    ("hyu", "ひゅ"),
+    ("hyo", "ひょ"),
+    ("bya", "びゃ"),
+    ("byu", "びゅ"),
+    ("byo", "びょ"),
+    ("pya", "ぴゃ"),
+    ("pyu", "ぴゅ"),
+    ("pyo", "ぴょ"),
+    ("mya", "みゃ"),
+    ("myu", "みゅ"),
+    ("myo", "みょ"),
+    ("rya", "りゃ"),
+    ("ryu", "りゅ"),
+    ("ryo", "りょ"),
+    ("fa", "ふぁ"),
+    ("fi", "ふぃ"),
+    ("fe", "ふぇ"),
+    ("fo", "ふぉ"),
+    ("va", "ゔぁ"),
+    ("vi", "ゔぃ"),
+    ("vu", "ゔ"),
+    ("ve", "ゔぇ"),
+    ("vo", "ゔぉ"),
+    ("thi", "てぃ"),
+    ("dhi", "でぃ"),
+    ("xa", "ぁ"),
+    ("xi", "ぃ"),
+    ("xu", "ぅ"),
+    ("xe", "ぇ"),
+    ("xo", "ぉ"),
+    ("la", "ぁ"),
+    ("li", "ぃ"),
+    ("lu", "ぅ"),
+    ("le", "ぇ"),
+    ("lo", "ぉ"),
+    ("xya", "ゃ"),
+    ("xyu", "ゅ"),
+    ("xyo", "ょ"),
+    ("lya", "ゃ"),
+    ("lyu", "ゅ"),
+    ("lyo", "ょ"),
+    ("xtu", "っ"),
+    ("ltu", "っ"),
+    ("xtsu", "っ"),
+    ("ltsu", "っ"),
+    ("nn", "ん"),
+    ("n'", "ん"),
+];
+
+const LONGEST_KEY: usize = 4;
+
+fn lookup(key: &str) -> Option<&'static str> {
+    TABLE.iter().find(|(k, _)| *k == key).map(|(_, kana)| *kana)
+}
+
+fn is_vowel(c: char) -> bool {
+    matches!(c, 'a' | 'i' | 'u' | 'e' | 'o')
+}
+
+// 入力途中の綴りに続きうるかなの1文字目
+fn continuations(prefix: &str) -> Vec<char> {
+    let mut chars: Vec<char> = TABLE
+        .iter()
+        .filter(|(key, _)| key.len() > prefix.len() && key.starts_with(prefix))
+        .filter_map(|(_, kana)| kana.chars().next())
+        .collect();
+    if prefix == "n" {
+        chars.push('ん');
+    }
+    chars.sort_unstable();
+    chars.dedup();
+    chars
+}
+
+// ローマ字をかなの並びにする。要素ごとに一致してよい文字の集合を返す
+// 英字以外はそのまま残し、かなにできない英字があれば None
+pub fn romaji_pattern(input: &str) -> Option<Vec<Vec<char>>> {
+    let chars: Vec<char> = input.chars().map(|c| c.to_ascii_lowercase()).collect();
+    let mut pattern: Vec<Vec<char>> = Vec::new();
+    let mut i = 0;
+    while i < chars.len() {
+        let c = chars[i];
+        if !c.is_ascii_alphabetic() {
+            // 長音 (「ta-ku」→ たーく)
+            let c = if c == '-' && !pattern.is_empty() {
+                'ー'
+            } else {
+                c
+            };
+            pattern.push(vec![c]);
+            i += 1;
+            continue;
+        }
+
+        let next = chars.get(i + 1).copied();
+        // 「nn」の後に母音が続く場合は「ん」+「な行」(konnichiha → こんにちは)
+        let n_before_syllable = c == 'n'
+            && next == Some('n')
+            && chars.get(i + 2).is_some_and(|c| is_vowel(*c) || *c == 'y');
+        let matched = (1..=LONGEST_KEY.min(chars.len() - i))
+            .rev()
+            .filter(|_| !n_before_syllable)
+            .find_map(|len| {
+                let key: String = chars[i..i + len].iter().collect();
+                lookup(&key).map(|kana| (kana, len))
+            });
+        if let Some((kana, len)) = matched {
+            pattern.extend(kana.chars().map(|k| vec![k]));
+            i += len;
+            continue;
+        }
+
+        let double = next == Some(c) || (c == 't' && next == Some('c'));
+        if c == 'n' && next.is_some_and(|n| !is_vowel(n) && n != 'y') {
+            pattern.push(vec!['ん']);
+            i += 1;
+        } else if double && !is_vowel(c) && c != 'n' {
+            pattern.push(vec!['っ']);
+            i += 1;
+        } else {
+            // 末尾の入力途中の綴りだけは許す
+            let rest: String = chars[i..].iter().collect();
+            let candidates = continuations(&rest);
+            if candidates.is_empty() || rest.chars().any(|c| !c.is_ascii_alphabetic()) {
+                return None;
+            }
+            pattern.push(candidates);
+            break;
+        }
+    }
+    Some(pattern)
+}
+
+// 最後まで確定したかなにできる場合だけ返す (末尾の n は「ん」とみなす)
+pub fn romaji_to_kana(input: &str) -> Option<String> {
+    let pattern = romaji_pattern(input)?;
+    let mut kana = String::new();
+    for set in &pattern {
+        match set.as_slice() {
+            [c] => kana.push(*c),
+            set if set.contains(&'ん') && input.to_ascii_lowercase().ends_with('n') => {
+                kana.push('ん')
+            }
+            _ => return None,
+        }
+    }
+    Some(kana)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn single(kana: &str) -> Vec<Vec<char>> {
+        kana.chars().map(|c| vec![c]).collect()
+    }
+
+    #[test]
+    fn converts_romaji_to_kana() {
+        for (input, expected) in [
+            ("kaigi", "かいぎ"),
+            ("KAIGI", "かいぎ"),
+            ("shiryou", "しりょう"),
+            ("siryou", "しりょう"),
+            ("tsukuru", "つくる"),
+            ("tukuru", "つくる"),
+            ("chousei", "ちょうせい"),
+            ("jikan", "じかん"),
+            ("fairu", "ふぁいる"),
+            // nn・n' は「ん」、nn の後に母音が続けば「ん」+な行
+            ("konnichiha", "こんにちは"),
+            ("onna", "おんな"),
+            ("kann", "かん"),
+            ("kan'i", "かんい"),
+            ("kon'ya", "こんや"),
+            ("konya", "こにゃ"),
+            ("shinbun", "しんぶん"),
+            ("sanpo", "さんぽ"),
+            // 子音を重ねると促音
+            ("kitte", "きって"),
+            ("zasshi", "ざっし"),
+            ("matcha", "まっちゃ"),
+            ("ta-ku", "たーく"),
+        ] {
+            assert_eq!(
+                romaji_to_kana(input).as_deref(),
+                Some(expected),
+                "{}",
+                input
+            );
+        }
+        for input in ["kaig", "xyz", "q", "kyx"] {
+            assert_eq!(romaji_to_kana(input), None, "{}", input);
+        }
+    }
+
+    #[test]
+    fn allows_partial_syllables_at_the_end() {
+        let mut expected = single("かい");
+        expected.push(vec!['が', 'ぎ', 'ぐ', 'げ', 'ご']);
+        assert_eq!(romaji_pattern("kaig"), Some(expected));
+
+        // 末尾の n は「ん」にも、な行の入力途中にもなる
+        let pattern = romaji_pattern("hon").unwrap();
+        assert_eq!(pattern[..1], single("ほ")[..]);
+        assert!(pattern[1].contains(&'ん') && pattern[1].contains(&'な'));
+        assert_eq!(romaji_to_kana("hon").as_deref(), Some("ほん"));
+
+        let pattern = romaji_pattern("kit").unwrap();
+        assert_eq!(pattern[..1], single("き")[..]);
+        assert!(pattern[1].contains(&'た') && pattern[1].contains(&'つ'));
+        // 英字以外はそのまま残る
+        assert_eq!(romaji_pattern("ka2"), Some(vec![vec!['か'], vec!['2']]));
+        assert_eq!(romaji_pattern("kx1"), None);
+    }
+}
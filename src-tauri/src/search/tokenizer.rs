@@ -0,0 +1,249 @@
+// 検索語・索引語への分割
+// 文字種 (英数字・ひらがな・カタカナ・漢字) の切れ目で区切り、日本語は2文字ずつ (bigram) の語にする
+
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum CharClass {
+    Latin,
+    Hiragana,
+    Katakana,
+    Kanji,
+}
+
+// 同じ文字種が続く範囲 (start / end は文字位置)
+#[derive(Debug)]
+pub struct Run {
+    pub text: String,
+    pub class: CharClass,
+    pub start: usize,
+    pub end: usize,
+}
+
+pub fn split_runs(text: &str) -> Vec<Run> {
+    let mut runs = Vec::new();
+    let mut current = String::new();
+    let mut current_class: Option<CharClass> = None;
+    let mut start = 0;
+    let mut chars = text.chars().enumerate().peekable();
+
+    while let Some((i, c)) = chars.next() {
+        if c.is_whitespace() {
+            flush_run(&mut runs, &mut current, &mut current_class, start, i);
+            continue;
+        }
+
+        if let Some(class) = char_class(c) {
+            if current_class != Some(class) {
+                flush_run(&mut runs, &mut current, &mut current_class, start, i);
+                current_class = Some(class);
+                start = i;
+            }
+            current.push(c);
+            continue;
+        }
+
+        if current_class == Some(CharClass::Latin) && is_ascii_connector(c) {
+            if matches!(c, '+' | '#') {
+                current.push(c);
+                continue;
+            }
+            if chars
+                .peek()
+                .is_some_and(|(_, next)| is_ascii_or_fullwidth_alnum(*next))
+            {
+                current.push(c);
+                continue;
+            }
+        }
+
+        flush_run(&mut runs, &mut current, &mut current_class, start, i);
+    }
+
+    let end = text.chars().count();
+    flush_run(&mut runs, &mut current, &mut current_class, start, end);
+    runs
+}
+
+// Web検索のクエリを語に分ける
+pub fn split_query_tokens(query: &str) -> Vec<String> {
+    split_runs(query).into_iter().map(|run| run.text).collect()
+}
+
+fn flush_run(
+    runs: &mut Vec<Run>,
+    current: &mut String,
+    current_class: &mut Option<CharClass>,
+    start: usize,
+    end: usize,
+) {
+    if let Some(class) = current_class.take().filter(|_| !current.is_empty()) {
+        runs.push(Run {
+            text: std::mem::take(current),
+            class,
+            start,
+            end,
+        });
+    }
+    current.clear();
+}
+
+fn char_class(c: char) -> Option<CharClass> {
+    if is_ascii_or_fullwidth_alnum(c) {
+        return Some(CharClass::Latin);
+    }
+    if is_hiragana(c) {
+        return Some(CharClass::Hiragana);
+    }
+    if is_katakana(c) {
+        return Some(CharClass::Katakana);
+    }
+    if is_kanji(c) {
+        return Some(CharClass::Kanji);
+    }
+    None
+}
+
+fn is_ascii_or_fullwidth_alnum(c: char) -> bool {
+    c.is_ascii_alphanumeric()
+        || matches!(c, '\u{FF10}'..='\u{FF19}' | '\u{FF21}'..='\u{FF3A}' | '\u{FF41}'..='\u{FF5A}')
+}
+
+fn is_ascii_connector(c: char) -> bool {
+    matches!(c, '+' | '#' | '-' | '_' | '.' | ':' | '/' | '@')
+}
+
+fn is_hiragana(c: char) -> bool {
+    matches!(c, '\u{3040}'..='\u{309F}')
+}
+
+fn is_katakana(c: char) -> bool {
+    matches!(c, '\u{30A0}'..='\u{30FF}' | '\u{31F0}'..='\u{31FF}')
+}
+
+pub fn is_halfwidth_katakana(c: char) -> bool {
+    matches!(c, '\u{FF66}'..='\u{FF9F}')
+}
+
+fn is_kanji(c: char) -> bool {
+    matches!(
+        c,
+        '\u{3005}'
+            | '\u{3400}'..='\u{4DBF}'
+            | '\u{4E00}'..='\u{9FFF}'
+            | '\u{F900}'..='\u{FAFF}'
+    )
+}
+
+// 全角英数字を半角にして小文字にする
+fn normalize_latin(text: &str) -> String {
+    text.chars()
+        .map(|c| match c {
+            '\u{FF01}'..='\u{FF5E}' => char::from_u32(c as u32 - 0xFEE0).unwrap_or(c),
+            _ => c,
+        })
+        .flat_map(char::to_lowercase)
+        .collect()
+}
+
+pub fn is_cjk_term(term: &str) -> bool {
+    term.chars()
+        .next()
+        .and_then(char_class)
+        .is_some_and(|class| class != CharClass::Latin)
+}
+
+// 索引・検索に使う語。position は句の照合に使う並び順
+#[derive(Debug, Clone, PartialEq)]
+pub struct Term {
+    pub text: String,
+    pub position: u32,
+}
+
+// かなの表記ゆれはそろえてから分ける (「カイギ」でも「かいぎ」が見つかる)
+pub fn terms(text: &str) -> Vec<Term> {
+    let runs = split_runs(&super::kana::fold(text));
+    let mut terms = Vec::new();
+    let mut position = 0;
+    let mut i = 0;
+    while i < runs.len() {
+        if runs[i].class == CharClass::Latin {
+            terms.push(Term {
+                text: normalize_latin(&runs[i].text),
+                position,
+            });
+            position += 1;
+            i += 1;
+            continue;
+        }
+
+        // 隣り合う日本語はまとめて2文字ずつにする (「会議する」→ 会議 / 議す / する)
+        let mut chars: Vec<char> = runs[i].text.chars().collect();
+        let mut end = runs[i].end;
+        i += 1;
+        while i < runs.len() && runs[i].class != CharClass::Latin && runs[i].start == end {
+            chars.extend(runs[i].text.chars());
+            end = runs[i].end;
+            i += 1;
+        }
+        if chars.len() == 1 {
+            terms.push(Term {
+                text: chars[0].to_string(),
+                position,
+            });
+            position += 1;
+            continue;
+        }
+        for pair in chars.windows(2) {
+            terms.push(Term {
+                text: pair.iter().collect(),
+                position,
+            });
+            position += 1;
+        }
+    }
+    terms
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn texts(text: &str) -> Vec<(String, u32)> {
+        terms(text)
+            .into_iter()
+            .map(|t| (t.text, t.position))
+            .collect()
+    }
+
+    #[test]
+    fn splits_japanese_into_bigrams() {
+        let expected = |pairs: &[(&str, u32)]| -> Vec<(String, u32)> {
+            pairs.iter().map(|(t, p)| (t.to_string(), *p)).collect()
+        };
+        assert_eq!(
+            texts("会議する"),
+            expected(&[("会議", 0), ("議す", 1), ("する", 2)])
+        );
+        // 英字の前後で日本語は切れる
+        assert_eq!(
+            texts("明日のMTG準備"),
+            expected(&[("明日", 0), ("日の", 1), ("mtg", 2), ("準備", 3)])
+        );
+        assert_eq!(texts("本 を"), expected(&[("本", 0), ("を", 1)]));
+        // カタカナはひらがなにそろえる
+        assert_eq!(texts("カイギ"), texts("かいぎ"));
+    }
+
+    #[test]
+    fn keeps_latin_connectors_and_normalizes_width() {
+        let words: Vec<String> = terms("C++ と C# の e-mail, a.b. ＡＢＣ１")
+            .into_iter()
+            .map(|t| t.text)
+            .collect();
+        assert_eq!(words, ["c++", "と", "c#", "の", "e-mail", "a.b", "abc1"]);
+        assert_eq!(
+            split_query_tokens("Rust入門 2024"),
+            ["Rust", "入門", "2024"]
+        );
+        assert!(terms("、。！").is_empty());
+    }
+}
@@ -0,0 +1,44 @@
+// アプリデータディレクトリ内のJSONファイル読み書き
+
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use std::fs;
+use std::path::PathBuf;
+use tauri::Manager;
+
+pub fn data_file(app: &tauri::AppHandle, name: &str) -> Result<PathBuf, String> {
+    let dir = app
+        .path()
+        .app_data_dir()
+        .map_err(|e| format!("データディレクトリ取得エラー: {}", e))?;
+    fs::create_dir_all(&dir).map_err(|e| format!("ディレクトリ作成エラー: {}", e))?;
+    Ok(dir.join(name))
+}
+
+// ファイルがなければデフォルト値を返す
+pub fn load_json<T: DeserializeOwned + Default>(
+    app: &tauri::AppHandle,
+    name: &str,
+) -> Result<T, String> {
+    let path = data_file(app, name)?;
+    if !path.exists() {
+        return Ok(T::default());
+    }
+    let content =
+        fs::read_to_string(&path).map_err(|e| format!("ファイル読み込みエラー: {}", e))?;
+    serde_json::from_str(&content).map_err(|e| format!("{} の解析エラー: {}", name, e))
+}
+
+// 書き込み途中で落ちても壊れないよう一時ファイル経由で置き換える
+pub fn save_json<T: Serialize>(
+    app: &tauri::AppHandle,
+    name: &str,
+    value: &T,
+) -> Result<(), String> {
+    let path = data_file(app, name)?;
+    let content =
+        serde_json::to_string_pretty(value).map_err(|e| format!("シリアライズエラー: {}", e))?;
+    let tmp_path = path.with_extension("tmp");
+    fs::write(&tmp_path, content).map_err(|e| format!("ファイル書き込みエラー: {}", e))?;
+    fs::rename(&tmp_path, &path).map_err(|e| format!("ファイル書き込みエラー: {}", e))
+}
@@ -0,0 +1,724 @@
+// 時間ブロック (カレンダーの空き時間にタスクを割り当てた今日・今週の予定案)
+// 取り込んだカレンダーの予定を埋まっている時間とし、勤務時間内の空きに
+// 期限・優先度の順でタスクを置いていく。結果は ICS の予定として書き出せる
+
+use chrono::{Duration, Local, NaiveDate, NaiveTime, TimeZone};
+use std::sync::Mutex;
+
+use crate::calendar::{BusinessCalendar, CalendarState};
+use crate::ics::{busy_intervals, export_events, BusyInterval, EventExport};
+use crate::models::Todo;
+
+const SETTINGS_FILE: &str = "timeblock-settings.json";
+const MINUTE_MS: i64 = 60 * 1000;
+const DAY_MS: i64 = 24 * 60 * MINUTE_MS;
+// 見積もりのないタスクに割り当てる時間
+const DEFAULT_TASK_MINUTES: u32 = 30;
+// 今日の予定は現在時刻をこの単位 (分) に切り上げた時刻から始める
+const ROUND_MINUTES: i64 = 5;
+
+// 予定を取り込んだカレンダー (ICS の内容をそのまま保存し、期間ごとに展開する)
+#[derive(serde::Serialize, serde::Deserialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct BusyCalendar {
+    pub id: String,
+    pub name: String,
+    #[serde(default = "default_true")]
+    pub enabled: bool,
+    pub content: String,
+}
+
+fn default_true() -> bool {
+    true
+}
+
+#[derive(serde::Serialize, serde::Deserialize, Clone, Debug)]
+#[serde(rename_all = "camelCase", default)]
+pub struct TimeBlockSettings {
+    // 勤務時間 (HH:MM)
+    pub work_start: String,
+    pub work_end: String,
+    // 土日・祝日・会社の休日には予定を入れない
+    pub business_days_only: bool,
+    // ブロックの間に空ける時間
+    pub break_minutes: u32,
+    // これより短い空き時間は使わない
+    pub min_block_minutes: u32,
+    // 長いタスクはこの長さで分割する
+    pub max_block_minutes: u32,
+    // 終日の予定も埋まっている時間とみなす (休暇など)
+    pub all_day_busy: bool,
+    pub calendars: Vec<BusyCalendar>,
+}
+
+impl Default for TimeBlockSettings {
+    fn default() -> Self {
+        Self {
+            work_start: "09:00".to_string(),
+            work_end: "18:00".to_string(),
+            business_days_only: true,
+            break_minutes: 10,
+            min_block_minutes: 15,
+            max_block_minutes: 90,
+            all_day_busy: false,
+            calendars: Vec::new(),
+        }
+    }
+}
+
+#[derive(serde::Serialize, serde::Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum AgendaRange {
+    Today,
+    Week,
+}
+
+#[derive(serde::Serialize, serde::Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum BlockKind {
+    Task,
+    Busy,
+}
+
+#[derive(serde::Serialize, serde::Deserialize, Clone, Debug, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct TimeBlock {
+    pub kind: BlockKind,
+    #[serde(default)]
+    pub task_id: Option<String>,
+    pub title: String,
+    pub start: i64,
+    pub end: i64,
+    // 分割したタスクの何番目か (1 始まり)
+    #[serde(default = "default_part")]
+    pub part: u32,
+    #[serde(default = "default_part")]
+    pub parts: u32,
+    // 期限を過ぎた時間に置いた
+    #[serde(default)]
+    pub late: bool,
+}
+
+fn default_part() -> u32 {
+    1
+}
+
+#[derive(serde::Serialize, Clone, Debug, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct UnscheduledTask {
+    pub task_id: String,
+    pub title: String,
+    pub minutes: u32,
+    pub reason: String,
+}
+
+#[derive(serde::Serialize, Clone, Debug, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct Agenda {
+    pub range_start: i64,
+    pub range_end: i64,
+    // 予定とタスクのブロックを時刻順に
+    pub blocks: Vec<TimeBlock>,
+    pub unscheduled: Vec<UnscheduledTask>,
+    pub scheduled_minutes: u32,
+    // 割り当て後に残った空き時間 (ブロックの後の休憩と、最小の長さに満たない残りは含めない)
+    pub free_minutes: u32,
+}
+
+fn parse_time(value: &str) -> Result<NaiveTime, String> {
+    NaiveTime::parse_from_str(value.trim(), "%H:%M")
+        .map_err(|_| format!("時刻の形式が正しくありません (HH:MM): {}", value))
+}
+
+fn local_millis(date: NaiveDate, time: NaiveTime) -> Option<i64> {
+    Local
+        .from_local_datetime(&date.and_time(time))
+        .earliest()
+        .map(|d| d.timestamp_millis())
+}
+
+fn task_minutes(todo: &Todo) -> u32 {
+    todo.estimated_minutes
+        .filter(|m| *m > 0)
+        .unwrap_or(DEFAULT_TASK_MINUTES)
+}
+
+// 勤務時間から予定を除いた空き時間 (開始・終了の組、時刻順)
+fn free_slots(
+    settings: &TimeBlockSettings,
+    days: &[NaiveDate],
+    busy: &[BusyInterval],
+    now: i64,
+) -> Result<Vec<(i64, i64)>, String> {
+    let work_start = parse_time(&settings.work_start)?;
+    let work_end = parse_time(&settings.work_end)?;
+    let round = ROUND_MINUTES * MINUTE_MS;
+    let earliest = (now + round - 1) / round * round;
+
+    let mut slots = Vec::new();
+    for date in days {
+        let (Some(start), Some(end)) = (
+            local_millis(*date, work_start),
+            local_millis(*date, work_end),
+        ) else {
+            continue;
+        };
+        let mut free = vec![(start.max(earliest), end)];
+        for interval in busy.iter().filter(|b| settings.all_day_busy || !b.all_day) {
+            free = free
+                .into_iter()
+                .flat_map(|(s, e)| {
+                    if interval.end <= s || interval.start >= e {
+                        return vec![(s, e)];
+                    }
+                    vec![(s, interval.start), (interval.end, e)]
+                })
+                .filter(|(s, e)| e > s)
+                .collect();
+        }
+        slots.extend(free);
+    }
+    let min = settings.min_block_minutes as i64 * MINUTE_MS;
+    slots.retain(|(s, e)| e - s >= min.max(MINUTE_MS));
+    Ok(slots)
+}
+
+// 置く順番: 期限切れ・期限の近いもの、優先度の高いもの、並び順
+fn candidate_order(a: &Todo, b: &Todo) -> std::cmp::Ordering {
+    let key = |t: &Todo| (t.due_date.unwrap_or(i64::MAX), t.priority);
+    key(a).cmp(&key(b)).then(a.order.total_cmp(&b.order))
+}
+
+// 空き時間の早い所から順に置く。全部置けなければ何も置かない
+fn place(
+    slots: &mut Vec<(i64, i64)>,
+    minutes: u32,
+    settings: &TimeBlockSettings,
+) -> Option<Vec<(i64, i64)>> {
+    let mut trial = slots.clone();
+    let mut remaining = minutes as i64 * MINUTE_MS;
+    let min = (settings.min_block_minutes as i64 * MINUTE_MS).max(MINUTE_MS);
+    let max = (settings.max_block_minutes as i64 * MINUTE_MS).max(min);
+    let gap = settings.break_minutes as i64 * MINUTE_MS;
+    let mut placed = Vec::new();
+    for slot in trial.iter_mut() {
+        while remaining > 0 {
+            let mut length = remaining.min(max).min(slot.1 - slot.0);
+            // 残りが最小の長さに満たない半端にならないよう、次のブロックの分を残す
+            let rest = remaining - length;
+            if rest > 0 && rest < min {
+                length = remaining - min;
+            }
+            if length < min.min(remaining) {
+                break;
+            }
+            placed.push((slot.0, slot.0 + length));
+            remaining -= length;
+            slot.0 += length + gap;
+        }
+        if remaining <= 0 {
+            break;
+        }
+    }
+    if remaining > 0 {
+        return None;
+    }
+    trial.retain(|(s, e)| e - s >= min);
+    *slots = trial;
+    Some(placed)
+}
+
+pub fn plan_agenda(
+    todos: &[Todo],
+    blocked: &std::collections::BTreeMap<String, Vec<String>>,
+    busy: &[BusyInterval],
+    settings: &TimeBlockSettings,
+    range: AgendaRange,
+    now: i64,
+    calendar: &BusinessCalendar,
+) -> Result<Agenda, String> {
+    let today = Local
+        .timestamp_millis_opt(now)
+        .single()
+        .map(|d| d.date_naive())
+        .ok_or_else(|| "日時が不正です".to_string())?;
+    let span = match range {
+        AgendaRange::Today => 1,
+        AgendaRange::Week => 7,
+    };
+    let days: Vec<NaiveDate> = (0..span)
+        .map(|i| today + Duration::days(i))
+        .filter(|d| !settings.business_days_only || calendar.is_business_day(*d))
+        .collect();
+    let range_start = local_millis(today, NaiveTime::MIN).unwrap_or(now);
+    let range_end = range_start + span * DAY_MS;
+
+    let mut slots = free_slots(settings, &days, busy, now)?;
+
+    // 期限が期間内か、期間に合った timeframe か、P1 のタスクを対象にする
+    let timeframes: &[&str] = match range {
+        AgendaRange::Today => &["today"],
+        AgendaRange::Week => &["today", "week"],
+    };
+    let mut candidates: Vec<&Todo> = todos
+        .iter()
+        .filter(|t| t.is_active())
+        .filter(|t| {
+            t.due_date.is_some_and(|d| d < range_end)
+                || timeframes.contains(&t.timeframe.as_str())
+                || t.priority == 1
+        })
+        .collect();
+    candidates.sort_by(|a, b| candidate_order(a, b));
+
+    let mut blocks: Vec<TimeBlock> = busy
+        .iter()
+        .filter(|b| b.end > range_start && b.start < range_end)
+        .map(|b| TimeBlock {
+            kind: BlockKind::Busy,
+            task_id: None,
+            title: b.summary.clone(),
+            start: b.start,
+            end: b.end,
+            part: 1,
+            parts: 1,
+            late: false,
+        })
+        .collect();
+    let mut unscheduled = Vec::new();
+    let mut scheduled_minutes = 0;
+    for todo in candidates {
+        let minutes = task_minutes(todo);
+        if blocked.contains_key(&todo.id) {
+            unscheduled.push(UnscheduledTask {
+                task_id: todo.id.clone(),
+                title: todo.text.clone(),
+                minutes,
+                reason: "依存しているタスクが終わっていません".to_string(),
+            });
+            continue;
+        }
+        let Some(placed) = place(&mut slots, minutes, settings) else {
+            unscheduled.push(UnscheduledTask {
+                task_id: todo.id.clone(),
+                title: todo.text.clone(),
+                minutes,
+                reason: "空き時間が足りません".to_string(),
+            });
+            continue;
+        };
+        scheduled_minutes += minutes;
+        let parts = placed.len() as u32;
+        for (i, (start, end)) in placed.into_iter().enumerate() {
+            blocks.push(TimeBlock {
+                kind: BlockKind::Task,
+                task_id: Some(todo.id.clone()),
+                title: todo.text.clone(),
+                start,
+                end,
+                part: i as u32 + 1,
+                parts,
+                late: todo.due_date.is_some_and(|due| end > due),
+            });
+        }
+    }
+    blocks.sort_by_key(|b| (b.start, b.end));
+
+    // 置いたブロックと後ろの休憩、最小の長さに満たなくなった残りは place で空き時間から除いてある
+    let free: i64 = slots.iter().map(|(s, e)| e - s).sum();
+    Ok(Agenda {
+        range_start,
+        range_end,
+        blocks,
+        unscheduled,
+        scheduled_minutes,
+        free_minutes: (free / MINUTE_MS) as u32,
+    })
+}
+
+pub struct TimeBlockState {
+    settings: Mutex<TimeBlockSettings>,
+}
+
+impl TimeBlockState {
+    pub fn load(app: &tauri::AppHandle) -> Self {
+        let settings = crate::store::load_json(app, SETTINGS_FILE).unwrap_or_else(|e| {
+            println!("[TimeBlock Backend] 設定読み込み失敗: {}", e);
+            TimeBlockSettings::default()
+        });
+        Self {
+            settings: Mutex::new(settings),
+        }
+    }
+
+    fn settings(&self) -> TimeBlockSettings {
+        self.settings.lock().unwrap().clone()
+    }
+
+    fn save(&self, app: &tauri::AppHandle, settings: TimeBlockSettings) -> Result<(), String> {
+        crate::store::save_json(app, SETTINGS_FILE, &settings)?;
+        *self.settings.lock().unwrap() = settings;
+        Ok(())
+    }
+}
+
+#[tauri::command]
+pub fn timeblock_get_settings(state: tauri::State<'_, TimeBlockState>) -> TimeBlockSettings {
+    state.settings()
+}
+
+// カレンダーは timeblock_add_calendar / timeblock_remove_calendar で変更する
+#[tauri::command]
+pub fn timeblock_set_settings(
+    app: tauri::AppHandle,
+    state: tauri::State<'_, TimeBlockState>,
+    settings: TimeBlockSettings,
+) -> Result<(), String> {
+    if parse_time(&settings.work_end)? <= parse_time(&settings.work_start)? {
+        return Err("勤務時間の終了は開始より後にしてください".to_string());
+    }
+    if settings.min_block_minutes > settings.max_block_minutes {
+        return Err("ブロックの最小の長さは最大の長さ以下にしてください".to_string());
+    }
+    let mut settings = settings;
+    settings.calendars = state.settings().calendars;
+    state.save(&app, settings)
+}
+
+// 予定の入ったカレンダー (ICS) を埋まっている時間として登録する
+#[tauri::command]
+pub fn timeblock_add_calendar(
+    app: tauri::AppHandle,
+    state: tauri::State<'_, TimeBlockState>,
+    name: String,
+    content: String,
+) -> Result<BusyCalendar, String> {
+    if !content.contains("BEGIN:VEVENT") {
+        return Err("予定 (VEVENT) が含まれていません".to_string());
+    }
+    let calendar = BusyCalendar {
+        id: format!("calendar-{}", Local::now().timestamp_millis()),
+        name: if name.trim().is_empty() {
+            "カレンダー".to_string()
+        } else {
+            name.trim().to_string()
+        },
+        enabled: true,
+        content,
+    };
+    let mut settings = state.settings();
+    settings.calendars.push(calendar.clone());
+    state.save(&app, settings)?;
+    Ok(calendar)
+}
+
+#[tauri::command]
+pub fn timeblock_remove_calendar(
+    app: tauri::AppHandle,
+    state: tauri::State<'_, TimeBlockState>,
+    id: String,
+) -> Result<(), String> {
+    let mut settings = state.settings();
+    settings.calendars.retain(|c| c.id != id);
+    state.save(&app, settings)
+}
+
+// 今日または今週の時間ブロックの案を作る (extra_ics は登録せずに一時的に使う予定)
+#[tauri::command]
+pub fn timeblock_plan(
+    state: tauri::State<'_, TimeBlockState>,
+    calendar: tauri::State<'_, CalendarState>,
+    todos: tauri::State<'_, crate::todos::TodoState>,
+    dependencies: tauri::State<'_, crate::dependency::DependencyState>,
+    range: AgendaRange,
+    extra_ics: Option<String>,
+) -> Result<Agenda, String> {
+    let settings = state.settings();
+    let now = Local::now().timestamp_millis();
+    let (from, to) = (now - DAY_MS, now + 8 * DAY_MS);
+    let mut busy: Vec<BusyInterval> = settings
+        .calendars
+        .iter()
+        .filter(|c| c.enabled)
+        .flat_map(|c| busy_intervals(&c.content, from, to))
+        .collect();
+    if let Some(extra) = extra_ics.as_deref() {
+        busy.extend(busy_intervals(extra, from, to));
+    }
+    busy.sort_by_key(|b| (b.start, b.end));
+
+    let all = todos.all();
+    let blocked = crate::dependency::blocked(&dependencies.edges(), &all);
+    let agenda = plan_agenda(
+        &all,
+        &blocked,
+        &busy,
+        &settings,
+        range,
+        now,
+        &calendar.calendar(),
+    )?;
+    println!(
+        "[TimeBlock Backend] 予定案: {} 件のブロック, 未割り当て {} 件",
+        agenda
+            .blocks
+            .iter()
+            .filter(|b| b.kind == BlockKind::Task)
+            .count(),
+        agenda.unscheduled.len()
+    );
+    Ok(agenda)
+}
+
+// タスクのブロックを ICS の予定として書き出す
+#[tauri::command]
+pub fn timeblock_export(blocks: Vec<TimeBlock>) -> String {
+    let events: Vec<EventExport> = blocks
+        .iter()
+        .filter(|b| b.kind == BlockKind::Task)
+        .map(|b| EventExport {
+            uid: format!(
+                "{}-{}@calm-todo",
+                b.task_id.as_deref().unwrap_or("block"),
+                b.start
+            ),
+            summary: &b.title,
+            description: None,
+            start: b.start,
+            end: b.end,
+        })
+        .collect();
+    export_events(&events)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::BTreeMap;
+
+    fn at(time: &str) -> i64 {
+        let date = NaiveDate::from_ymd_opt(2024, 5, 20).unwrap();
+        local_millis(date, parse_time(time).unwrap()).unwrap()
+    }
+
+    fn busy(start: &str, end: &str) -> BusyInterval {
+        BusyInterval {
+            uid: format!("{}-{}", start, end),
+            summary: format!("会議 {}", start),
+            start: at(start),
+            end: at(end),
+            all_day: false,
+        }
+    }
+
+    fn todo(id: &str, minutes: u32, due: Option<&str>, order: f64) -> Todo {
+        Todo {
+            id: id.to_string(),
+            text: id.to_string(),
+            estimated_minutes: Some(minutes),
+            due_date: due.map(at),
+            order,
+            ..Default::default()
+        }
+    }
+
+    fn settings() -> TimeBlockSettings {
+        TimeBlockSettings {
+            business_days_only: false,
+            ..Default::default()
+        }
+    }
+
+    fn agenda(
+        todos: &[Todo],
+        busy: &[BusyInterval],
+        blocked: &BTreeMap<String, Vec<String>>,
+    ) -> Agenda {
+        plan_agenda(
+            todos,
+            blocked,
+            busy,
+            &settings(),
+            AgendaRange::Today,
+            at("07:00"),
+            &BusinessCalendar::default(),
+        )
+        .unwrap()
+    }
+
+    fn span(start: i64, end: i64) -> String {
+        let format = |millis: i64| {
+            Local
+                .timestamp_millis_opt(millis)
+                .unwrap()
+                .format("%H:%M")
+                .to_string()
+        };
+        format!("{}-{}", format(start), format(end))
+    }
+
+    fn meetings() -> Vec<BusyInterval> {
+        let mut all_day = busy("00:00", "23:59");
+        all_day.all_day = true;
+        vec![
+            // 重なった予定
+            busy("10:00", "11:00"),
+            busy("10:30", "11:30"),
+            // 隣り合った予定
+            busy("11:30", "12:00"),
+            // 勤務時間の後まで続く予定
+            busy("17:30", "19:00"),
+            all_day,
+        ]
+    }
+
+    #[test]
+    fn free_slots_merge_overlapping_and_adjacent_events() {
+        let slots = free_slots(
+            &settings(),
+            &[NaiveDate::from_ymd_opt(2024, 5, 20).unwrap()],
+            &meetings(),
+            at("07:00"),
+        )
+        .unwrap();
+        let spans: Vec<String> = slots.iter().map(|(s, e)| span(*s, *e)).collect();
+        assert_eq!(spans, ["09:00-10:00", "12:00-17:30"]);
+
+        // 今日の残りは現在時刻を5分単位に切り上げた所から
+        let slots = free_slots(
+            &settings(),
+            &[NaiveDate::from_ymd_opt(2024, 5, 20).unwrap()],
+            &meetings(),
+            at("09:46"),
+        )
+        .unwrap();
+        let spans: Vec<String> = slots.iter().map(|(s, e)| span(*s, *e)).collect();
+        assert_eq!(spans, ["12:00-17:30"], "09:50-10:00 は最小の長さに満たない");
+    }
+
+    #[test]
+    fn places_tasks_with_breaks_and_reports_remaining_free_time() {
+        let todos = [
+            todo("t1", 50, Some("09:30"), 0.0),
+            todo("t2", 120, Some("15:00"), 0.0),
+            todo("t3", 300, Some("16:00"), 0.0),
+            todo("t4", 100, None, 1.0),
+            todo("t5", 30, None, 2.0),
+        ];
+        let blocked = BTreeMap::from([("t5".to_string(), vec!["t1".to_string()])]);
+        let agenda = agenda(&todos, &meetings(), &blocked);
+
+        let tasks: Vec<(String, String, u32, u32, bool)> = agenda
+            .blocks
+            .iter()
+            .filter(|b| b.kind == BlockKind::Task)
+            .map(|b| {
+                (
+                    b.task_id.clone().unwrap(),
+                    span(b.start, b.end),
+                    b.part,
+                    b.parts,
+                    b.late,
+                )
+            })
+            .collect();
+        let expected = [
+            ("t1", "09:00-09:50", 1, 1, true),
+            ("t2", "12:00-13:30", 1, 2, false),
+            ("t2", "13:40-14:10", 2, 2, false),
+            // 残りが 15 分未満の半端にならないよう 85 分と 15 分に分ける
+            ("t4", "14:20-15:45", 1, 2, false),
+            ("t4", "15:55-16:10", 2, 2, false),
+        ]
+        .map(|(id, span, part, parts, late)| (id.to_string(), span.to_string(), part, parts, late));
+        assert_eq!(tasks, expected);
+
+        // 予定は期間内のものをすべて並べる (終日の予定も表示はする)
+        assert_eq!(
+            agenda
+                .blocks
+                .iter()
+                .filter(|b| b.kind == BlockKind::Busy)
+                .count(),
+            5
+        );
+        assert!(agenda
+            .blocks
+            .windows(2)
+            .all(|w| (w[0].start, w[0].end) <= (w[1].start, w[1].end)));
+
+        let unscheduled: Vec<(&str, &str)> = agenda
+            .unscheduled
+            .iter()
+            .map(|u| (u.task_id.as_str(), u.reason.as_str()))
+            .collect();
+        assert_eq!(
+            unscheduled,
+            [
+                ("t3", "空き時間が足りません"),
+                ("t5", "依存しているタスクが終わっていません")
+            ]
+        );
+        assert_eq!(agenda.scheduled_minutes, 50 + 120 + 100);
+        // 空き 390 分から、置いた 270 分と休憩・使えない残りを除いた 16:20-17:30
+        assert_eq!(agenda.free_minutes, 70);
+    }
+
+    #[test]
+    fn task_blocks_never_overlap_events_or_each_other() {
+        let todos: Vec<Todo> = (0..12)
+            .map(|i| todo(&format!("t{}", i), 20 + i * 17, None, i as f64))
+            .collect();
+        let meetings = meetings();
+        let agenda = agenda(&todos, &meetings, &BTreeMap::new());
+        let tasks: Vec<&TimeBlock> = agenda
+            .blocks
+            .iter()
+            .filter(|b| b.kind == BlockKind::Task)
+            .collect();
+        assert!(!tasks.is_empty());
+        for (i, a) in tasks.iter().enumerate() {
+            assert!(a.start >= at("09:00") && a.end <= at("18:00"));
+            assert!(a.end - a.start >= 15 * MINUTE_MS);
+            for b in meetings.iter().filter(|b| !b.all_day) {
+                assert!(
+                    a.end <= b.start || a.start >= b.end,
+                    "{}",
+                    span(a.start, a.end)
+                );
+            }
+            // 続くブロックとの間には休憩を空ける
+            for b in &tasks[i + 1..] {
+                assert!(b.start >= a.end + 10 * MINUTE_MS || b.end + 10 * MINUTE_MS <= a.start);
+            }
+        }
+        let placed: u32 = todos
+            .iter()
+            .filter(|t| agenda.unscheduled.iter().all(|u| u.task_id != t.id))
+            .map(task_minutes)
+            .sum();
+        assert_eq!(placed, agenda.scheduled_minutes);
+        let free: i64 = (at("10:00") - at("09:00")) + (at("17:30") - at("12:00"));
+        assert!(
+            (agenda.scheduled_minutes + agenda.free_minutes) as i64 * MINUTE_MS <= free,
+            "{} {}",
+            agenda.scheduled_minutes,
+            agenda.free_minutes
+        );
+    }
+
+    #[test]
+    fn a_task_that_does_not_fit_leaves_the_slots_untouched() {
+        let settings = settings();
+        let mut slots = vec![(at("09:00"), at("10:00")), (at("12:00"), at("12:30"))];
+        assert_eq!(place(&mut slots, 120, &settings), None);
+        assert_eq!(
+            slots,
+            [(at("09:00"), at("10:00")), (at("12:00"), at("12:30"))]
+        );
+        // ちょうど埋まる場合は残りを捨てる
+        let placed = place(&mut slots, 60, &settings).unwrap();
+        assert_eq!(placed, [(at("09:00"), at("10:00"))]);
+        assert_eq!(slots, [(at("12:00"), at("12:30"))]);
+    }
+}
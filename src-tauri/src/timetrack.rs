@@ -0,0 +1,957 @@
+// 作業時間の記録 (タイムトラッキング)
+// 手動のストップウォッチと集中タイマーの集中区間を記録し、
+// 期間内の実績を見積もり (estimatedMinutes) と比べてラベル別・プロジェクト別に集計する
+
+use chrono::{Duration, Local, NaiveDate, TimeZone};
+use std::collections::BTreeMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+use tauri::Emitter;
+
+use crate::models::{Project, Todo};
+
+const ENTRIES_FILE: &str = "time-entries.json";
+const MINUTE_MS: i64 = 60 * 1000;
+const NO_LABEL: &str = "(ラベルなし)";
+const NO_PROJECT: &str = "(プロジェクトなし)";
+
+static NEXT_ID: AtomicU64 = AtomicU64::new(0);
+
+#[derive(serde::Serialize, serde::Deserialize, Clone, Copy, Debug, PartialEq, Default)]
+#[serde(rename_all = "camelCase")]
+pub enum EntrySource {
+    #[default]
+    Manual,
+    Focus,
+}
+
+#[derive(serde::Serialize, serde::Deserialize, Clone, Debug, Default)]
+#[serde(rename_all = "camelCase", default)]
+pub struct TimeEntry {
+    pub id: String,
+    pub todo_id: String,
+    // 記録時のタスク名 (タスクを削除しても集計に名前を残す)
+    pub title: String,
+    pub start: i64,
+    // 計測中は None
+    pub end: Option<i64>,
+    pub source: EntrySource,
+    pub note: String,
+}
+
+impl TimeEntry {
+    // from〜to に含まれる区間。計測中は now までとする
+    fn clip(&self, from: i64, to: i64, now: i64) -> Option<(i64, i64)> {
+        let start = self.start.max(from);
+        let end = self.end.unwrap_or(now).min(to);
+        (end > start).then_some((start, end))
+    }
+}
+
+// 同じミリ秒に記録しても重ならない id
+fn new_id(entries: &[TimeEntry], now: i64) -> String {
+    loop {
+        let id = format!("time-{}-{}", now, NEXT_ID.fetch_add(1, Ordering::Relaxed));
+        if entries.iter().all(|e| e.id != id) {
+            return id;
+        }
+    }
+}
+
+// 重なった区間は1回だけ数えた合計の長さ
+// (集中タイマーと手動の計測を同じタスクで同時に動かした場合など)
+fn union_length(mut intervals: Vec<(i64, i64)>) -> i64 {
+    intervals.sort_unstable();
+    let mut total = 0;
+    let mut covered = i64::MIN;
+    for (start, end) in intervals {
+        let start = start.max(covered);
+        if end > start {
+            total += end - start;
+            covered = end;
+        }
+    }
+    total
+}
+
+#[derive(serde::Serialize, serde::Deserialize, Clone, Copy, Debug, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub enum ReportGroup {
+    Label,
+    Project,
+    Task,
+}
+
+// 見積もりと実績の比較 (estimated_actual_minutes は見積もりのあるタスクだけの実績)
+// 期間の外にも実績があるタスクの見積もりは、期間内の実績の割合で按分する
+#[derive(serde::Serialize, Clone, Debug, Default, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct ReportRow {
+    pub key: String,
+    pub name: String,
+    pub task_count: usize,
+    pub estimated_minutes: i64,
+    pub actual_minutes: i64,
+    pub estimated_actual_minutes: i64,
+    // 実績 / 見積もり (見積もりのあるタスクがなければ None)
+    pub ratio: Option<f64>,
+}
+
+#[derive(serde::Serialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct TimeReport {
+    pub from: String,
+    pub to: String,
+    pub total: ReportRow,
+    pub by_label: Vec<ReportRow>,
+    pub by_project: Vec<ReportRow>,
+    pub by_task: Vec<ReportRow>,
+}
+
+impl TimeReport {
+    fn rows(&self, group: ReportGroup) -> &[ReportRow] {
+        match group {
+            ReportGroup::Label => &self.by_label,
+            ReportGroup::Project => &self.by_project,
+            ReportGroup::Task => &self.by_task,
+        }
+    }
+}
+
+fn parse_date(value: &str) -> Result<NaiveDate, String> {
+    NaiveDate::parse_from_str(value.trim(), "%Y-%m-%d")
+        .map_err(|_| format!("日付の形式が正しくありません (YYYY-MM-DD): {}", value))
+}
+
+fn day_start(date: NaiveDate) -> Result<i64, String> {
+    Local
+        .from_local_datetime(&date.and_hms_opt(0, 0, 0).unwrap_or_default())
+        .earliest()
+        .map(|d| d.timestamp_millis())
+        .ok_or_else(|| format!("日付を変換できません: {}", date))
+}
+
+// 端数は四捨五入
+fn to_minutes(millis: i64) -> i64 {
+    (millis + MINUTE_MS / 2) / MINUTE_MS
+}
+
+// タスクごとの実績 (ミリ秒)
+struct TaskTotal<'a> {
+    todo_id: &'a str,
+    title: &'a str,
+    todo: Option<&'a Todo>,
+    actual: i64,
+    // 期間の外も含めた実績
+    actual_all: i64,
+}
+
+impl TaskTotal<'_> {
+    // 期間内に使った分の見積もり (ミリ秒)
+    fn estimate(&self) -> Option<i64> {
+        let minutes = self.todo?.estimated_minutes.filter(|m| *m > 0)?;
+        let estimate = minutes as i64 * MINUTE_MS;
+        Some(if self.actual_all > self.actual {
+            (estimate as i128 * self.actual as i128 / self.actual_all as i128) as i64
+        } else {
+            estimate
+        })
+    }
+}
+
+#[derive(Default)]
+struct Accumulator {
+    name: String,
+    tasks: usize,
+    estimated: i64,
+    actual: i64,
+    estimated_actual: i64,
+}
+
+impl Accumulator {
+    fn add(&mut self, task: &TaskTotal) {
+        self.tasks += 1;
+        self.actual += task.actual;
+        if let Some(estimate) = task.estimate() {
+            self.estimated += estimate;
+            self.estimated_actual += task.actual;
+        }
+    }
+
+    fn row(&self, key: &str) -> ReportRow {
+        ReportRow {
+            key: key.to_string(),
+            name: self.name.clone(),
+            task_count: self.tasks,
+            estimated_minutes: to_minutes(self.estimated),
+            actual_minutes: to_minutes(self.actual),
+            estimated_actual_minutes: to_minutes(self.estimated_actual),
+            ratio: (self.estimated > 0)
+                .then(|| self.estimated_actual as f64 / self.estimated as f64),
+        }
+    }
+}
+
+fn rows(groups: BTreeMap<String, Accumulator>) -> Vec<ReportRow> {
+    let mut rows: Vec<ReportRow> = groups.iter().map(|(key, acc)| acc.row(key)).collect();
+    rows.sort_by(|a, b| {
+        b.actual_minutes
+            .cmp(&a.actual_minutes)
+            .then(a.name.cmp(&b.name))
+    });
+    rows
+}
+
+// from〜to (両端の日を含む、端末のローカル日付) の実績を集計する
+// 期間をまたぐ記録は期間内の分だけ数え、同じタスクで重なった記録は重なった分を1回だけ数える
+// 複数のラベルが付いたタスクはそれぞれのラベルに数える
+pub fn report(
+    entries: &[TimeEntry],
+    todos: &[Todo],
+    projects: &[Project],
+    from: NaiveDate,
+    to: NaiveDate,
+    now: i64,
+) -> Result<TimeReport, String> {
+    if to < from {
+        return Err("終了日は開始日以降にしてください".to_string());
+    }
+    let range_start = day_start(from)?;
+    let range_end = day_start(to + Duration::days(1))?;
+
+    let mut by_todo: BTreeMap<&str, Vec<&TimeEntry>> = BTreeMap::new();
+    for entry in entries {
+        by_todo.entry(&entry.todo_id).or_default().push(entry);
+    }
+    let mut tasks: BTreeMap<&str, TaskTotal> = BTreeMap::new();
+    for (todo_id, task_entries) in by_todo {
+        let clipped = |from: i64, to: i64| -> Vec<(i64, i64)> {
+            task_entries
+                .iter()
+                .filter_map(|e| e.clip(from, to, now))
+                .collect()
+        };
+        let actual = union_length(clipped(range_start, range_end));
+        if actual == 0 {
+            continue;
+        }
+        tasks.insert(
+            todo_id,
+            TaskTotal {
+                todo_id,
+                title: &task_entries[0].title,
+                todo: todos.iter().find(|t| t.id == todo_id),
+                actual,
+                actual_all: union_length(clipped(i64::MIN, i64::MAX)),
+            },
+        );
+    }
+
+    let mut total = Accumulator {
+        name: "合計".to_string(),
+        ..Default::default()
+    };
+    let mut by_label: BTreeMap<String, Accumulator> = BTreeMap::new();
+    let mut by_project: BTreeMap<String, Accumulator> = BTreeMap::new();
+    let mut by_task: BTreeMap<String, Accumulator> = BTreeMap::new();
+    for task in tasks.values() {
+        total.add(task);
+
+        let labels = task.todo.map(|t| t.labels.as_slice()).unwrap_or_default();
+        if labels.is_empty() {
+            let acc = by_label.entry(String::new()).or_default();
+            acc.name = NO_LABEL.to_string();
+            acc.add(task);
+        }
+        for label in labels {
+            let acc = by_label.entry(label.clone()).or_default();
+            acc.name = label.clone();
+            acc.add(task);
+        }
+
+        let project_id = task
+            .todo
+            .and_then(|t| t.project_id.clone())
+            .unwrap_or_default();
+        let acc = by_project.entry(project_id.clone()).or_default();
+        acc.name = projects
+            .iter()
+            .find(|p| p.id == project_id)
+            .map_or_else(|| NO_PROJECT.to_string(), |p| p.name.clone());
+        acc.add(task);
+
+        let acc = by_task.entry(task.todo_id.to_string()).or_default();
+        acc.name = task
+            .todo
+            .map_or(task.title, |t| t.text.as_str())
+            .to_string();
+        acc.add(task);
+    }
+
+    Ok(TimeReport {
+        from: from.format("%Y-%m-%d").to_string(),
+        to: to.format("%Y-%m-%d").to_string(),
+        total: total.row(""),
+        by_label: rows(by_label),
+        by_project: rows(by_project),
+        by_task: rows(by_task),
+    })
+}
+
+pub fn report_csv(report: &TimeReport, group: ReportGroup) -> Result<String, String> {
+    let first = match group {
+        ReportGroup::Label => "ラベル",
+        ReportGroup::Project => "プロジェクト",
+        ReportGroup::Task => "タスク",
+    };
+    let mut writer = ::csv::Writer::from_writer(Vec::new());
+    writer
+        .write_record([
+            first,
+            "タスク数",
+            "見積もり (分)",
+            "実績 (分)",
+            "見積もりありの実績 (分)",
+            "実績/見積もり",
+        ])
+        .map_err(|e| format!("CSV書き込みエラー: {}", e))?;
+    for row in report.rows(group).iter().chain([&report.total]) {
+        writer
+            .write_record([
+                crate::csv::escape_formula(row.name.clone()),
+                row.task_count.to_string(),
+                row.estimated_minutes.to_string(),
+                row.actual_minutes.to_string(),
+                row.estimated_actual_minutes.to_string(),
+                row.ratio.map(|r| format!("{:.2}", r)).unwrap_or_default(),
+            ])
+            .map_err(|e| format!("CSV書き込みエラー: {}", e))?;
+    }
+    let bytes = writer
+        .into_inner()
+        .map_err(|e| format!("CSV書き込みエラー: {}", e))?;
+    let content = String::from_utf8(bytes).map_err(|e| e.to_string())?;
+    // Excel で文字化けしないよう BOM を付ける
+    Ok(format!("\u{feff}{}", content))
+}
+
+pub struct TimeTrackState {
+    entries: Mutex<Vec<TimeEntry>>,
+}
+
+impl TimeTrackState {
+    pub fn load(app: &tauri::AppHandle) -> Self {
+        let entries = crate::store::load_json(app, ENTRIES_FILE).unwrap_or_else(|e| {
+            println!("[TimeTrack Backend] 記録の読み込み失敗: {}", e);
+            Vec::new()
+        });
+        Self {
+            entries: Mutex::new(entries),
+        }
+    }
+
+    fn entries(&self) -> Vec<TimeEntry> {
+        self.entries.lock().unwrap().clone()
+    }
+
+    // 記録を変更して保存し、time-entries-changed を送る
+    fn update<T>(
+        &self,
+        app: &tauri::AppHandle,
+        f: impl FnOnce(&mut Vec<TimeEntry>) -> Result<T, String>,
+    ) -> Result<T, String> {
+        let mut entries = self.entries.lock().unwrap();
+        let mut updated = entries.clone();
+        let result = f(&mut updated)?;
+        crate::store::save_json(app, ENTRIES_FILE, &updated)?;
+        *entries = updated;
+        drop(entries);
+        let _ = app.emit("time-entries-changed", ());
+        Ok(result)
+    }
+
+    // 集中タイマーの集中区間を記録する
+    pub fn record_focus(
+        &self,
+        app: &tauri::AppHandle,
+        todo_id: &str,
+        title: &str,
+        start: i64,
+        end: i64,
+    ) {
+        if end <= start {
+            return;
+        }
+        let mut entry = TimeEntry {
+            id: String::new(),
+            todo_id: todo_id.to_string(),
+            title: title.to_string(),
+            start,
+            end: Some(end),
+            source: EntrySource::Focus,
+            note: String::new(),
+        };
+        if let Err(e) = self.update(app, |entries| {
+            entry.id = new_id(entries, Local::now().timestamp_millis());
+            entries.push(entry);
+            Ok(())
+        }) {
+            println!("[TimeTrack Backend] 集中時間の記録失敗: {}", e);
+        }
+    }
+}
+
+fn stop_running(entries: &mut [TimeEntry], now: i64) -> Option<TimeEntry> {
+    let running = entries.iter_mut().find(|e| e.end.is_none())?;
+    running.end = Some(now.max(running.start));
+    Some(running.clone())
+}
+
+fn validate(entry: &TimeEntry) -> Result<(), String> {
+    if entry.todo_id.is_empty() {
+        return Err("タスクを指定してください".to_string());
+    }
+    if entry.end.is_some_and(|end| end <= entry.start) {
+        return Err("終了は開始より後にしてください".to_string());
+    }
+    if entry.start > Local::now().timestamp_millis() {
+        return Err("開始を未来の時刻にはできません".to_string());
+    }
+    Ok(())
+}
+
+// 同じタスクの別の記録と重なっていないか (計測中の記録は now まで)
+fn check_overlap(entries: &[TimeEntry], entry: &TimeEntry, now: i64) -> Result<(), String> {
+    let Some((start, end)) = entry.clip(i64::MIN, i64::MAX, now) else {
+        return Ok(());
+    };
+    let overlapping = entries.iter().find(|other| {
+        other.id != entry.id
+            && other.todo_id == entry.todo_id
+            && other.clip(start, end, now).is_some()
+    });
+    match overlapping {
+        Some(other) => Err(format!(
+            "同じタスクの記録と時間が重なっています: {}",
+            Local
+                .timestamp_millis_opt(other.start)
+                .single()
+                .map(|d| d.format("%Y-%m-%d %H:%M").to_string())
+                .unwrap_or_default()
+        )),
+        None => Ok(()),
+    }
+}
+
+#[derive(serde::Deserialize, Default)]
+#[serde(rename_all = "camelCase", default)]
+pub struct EntryQuery {
+    // YYYY-MM-DD (両端の日を含む)
+    pub from: Option<String>,
+    pub to: Option<String>,
+    pub todo_id: Option<String>,
+}
+
+// 計測を始める (計測中の記録があれば止める)
+#[tauri::command]
+pub fn timetrack_start(
+    app: tauri::AppHandle,
+    state: tauri::State<'_, TimeTrackState>,
+    todos: tauri::State<'_, crate::todos::TodoState>,
+    todo_id: String,
+) -> Result<TimeEntry, String> {
+    let todo = todos
+        .find(&todo_id)
+        .ok_or_else(|| format!("タスクが見つかりません: {}", todo_id))?;
+    let now = Local::now().timestamp_millis();
+    let mut entry = TimeEntry {
+        id: String::new(),
+        todo_id: todo.id,
+        title: todo.text,
+        start: now,
+        end: None,
+        source: EntrySource::Manual,
+        note: String::new(),
+    };
+    state.update(&app, |entries| {
+        stop_running(entries, now);
+        entry.id = new_id(entries, now);
+        entries.push(entry.clone());
+        Ok(entry)
+    })
+}
+
+#[tauri::command]
+pub fn timetrack_stop(
+    app: tauri::AppHandle,
+    state: tauri::State<'_, TimeTrackState>,
+) -> Result<Option<TimeEntry>, String> {
+    let now = Local::now().timestamp_millis();
+    state.update(&app, |entries| Ok(stop_running(entries, now)))
+}
+
+#[tauri::command]
+pub fn timetrack_running(state: tauri::State<'_, TimeTrackState>) -> Option<TimeEntry> {
+    state.entries().into_iter().find(|e| e.end.is_none())
+}
+
+// 新しい順に返す
+#[tauri::command]
+pub fn timetrack_list(
+    state: tauri::State<'_, TimeTrackState>,
+    query: Option<EntryQuery>,
+) -> Result<Vec<TimeEntry>, String> {
+    let query = query.unwrap_or_default();
+    let from = match &query.from {
+        Some(from) => day_start(parse_date(from)?)?,
+        None => i64::MIN,
+    };
+    let to = match &query.to {
+        Some(to) => day_start(parse_date(to)? + Duration::days(1))?,
+        None => i64::MAX,
+    };
+    let now = Local::now().timestamp_millis();
+    let mut entries: Vec<TimeEntry> = state
+        .entries()
+        .into_iter()
+        .filter(|e| query.todo_id.as_ref().is_none_or(|id| &e.todo_id == id))
+        .filter(|e| e.start < to && e.end.unwrap_or(now) >= from)
+        .collect();
+    entries.sort_by_key(|e| std::cmp::Reverse(e.start));
+    Ok(entries)
+}
+
+// 記録を追加・編集する (id が空なら追加)
+#[tauri::command]
+pub fn timetrack_save(
+    app: tauri::AppHandle,
+    state: tauri::State<'_, TimeTrackState>,
+    todos: tauri::State<'_, crate::todos::TodoState>,
+    entry: TimeEntry,
+) -> Result<TimeEntry, String> {
+    validate(&entry)?;
+    let mut entry = entry;
+    if entry.title.is_empty() {
+        entry.title = todos
+            .find(&entry.todo_id)
+            .map(|t| t.text)
+            .unwrap_or_default();
+    }
+    state.update(&app, |entries| {
+        // 終了なしにできるのは計測中の記録だけ (計測中は常に1件まで)
+        let running = entries
+            .iter()
+            .any(|e| e.id == entry.id && !e.id.is_empty() && e.end.is_none());
+        if entry.end.is_none() && !running {
+            return Err("終了を入力してください".to_string());
+        }
+        let now = Local::now().timestamp_millis();
+        check_overlap(entries, &entry, now)?;
+        if entry.id.is_empty() {
+            entry.id = new_id(entries, now);
+            entries.push(entry.clone());
+            return Ok(entry);
+        }
+        let existing = entries
+            .iter_mut()
+            .find(|e| e.id == entry.id)
+            .ok_or_else(|| format!("記録が見つかりません: {}", entry.id))?;
+        *existing = entry.clone();
+        Ok(entry)
+    })
+}
+
+#[tauri::command]
+pub fn timetrack_delete(
+    app: tauri::AppHandle,
+    state: tauri::State<'_, TimeTrackState>,
+    id: String,
+) -> Result<(), String> {
+    state.update(&app, |entries| {
+        entries.retain(|e| e.id != id);
+        Ok(())
+    })
+}
+
+#[tauri::command]
+pub fn timetrack_report(
+    state: tauri::State<'_, TimeTrackState>,
+    todos: tauri::State<'_, crate::todos::TodoState>,
+    from: String,
+    to: String,
+) -> Result<TimeReport, String> {
+    report(
+        &state.entries(),
+        &todos.all(),
+        &todos.projects(),
+        parse_date(&from)?,
+        parse_date(&to)?,
+        Local::now().timestamp_millis(),
+    )
+}
+
+#[tauri::command]
+pub fn timetrack_export_csv(
+    state: tauri::State<'_, TimeTrackState>,
+    todos: tauri::State<'_, crate::todos::TodoState>,
+    from: String,
+    to: String,
+    group: ReportGroup,
+) -> Result<String, String> {
+    let report = timetrack_report(state, todos, from, to)?;
+    report_csv(&report, group)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const HOUR_MS: i64 = 60 * MINUTE_MS;
+
+    fn date(value: &str) -> NaiveDate {
+        parse_date(value).unwrap()
+    }
+
+    fn at(day: &str, hour: i64) -> i64 {
+        day_start(date(day)).unwrap() + hour * HOUR_MS
+    }
+
+    fn entry(todo_id: &str, start: i64, end: Option<i64>, source: EntrySource) -> TimeEntry {
+        TimeEntry {
+            id: format!("{}-{}", todo_id, start),
+            todo_id: todo_id.to_string(),
+            title: format!("記録時の{}", todo_id),
+            start,
+            end,
+            source,
+            note: String::new(),
+        }
+    }
+
+    fn todo(id: &str, estimate: Option<u32>, labels: &[&str], project: Option<&str>) -> Todo {
+        Todo {
+            id: id.to_string(),
+            text: format!("タスク{}", id),
+            estimated_minutes: estimate,
+            labels: labels.iter().map(|l| l.to_string()).collect(),
+            project_id: project.map(str::to_string),
+            ..Default::default()
+        }
+    }
+
+    fn project(id: &str, name: &str) -> Project {
+        Project {
+            id: id.to_string(),
+            name: name.to_string(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn new_ids_are_unique_within_the_same_millisecond() {
+        let mut entries = Vec::new();
+        for _ in 0..100 {
+            let mut e = entry("a", 0, Some(1), EntrySource::Manual);
+            e.id = new_id(&entries, 1_700_000_000_000);
+            entries.push(e);
+        }
+        let mut ids: Vec<&str> = entries.iter().map(|e| e.id.as_str()).collect();
+        ids.sort_unstable();
+        ids.dedup();
+        assert_eq!(ids.len(), 100);
+    }
+
+    #[test]
+    fn union_counts_overlaps_once() {
+        assert_eq!(union_length(vec![]), 0);
+        assert_eq!(union_length(vec![(0, 10), (20, 30)]), 20);
+        assert_eq!(union_length(vec![(0, 10), (5, 15)]), 15);
+        assert_eq!(union_length(vec![(5, 15), (0, 30), (10, 20)]), 30);
+        // 隣り合う区間
+        assert_eq!(union_length(vec![(10, 20), (0, 10)]), 20);
+    }
+
+    #[test]
+    fn focus_and_manual_timer_on_the_same_task_are_not_double_counted() {
+        let entries = [
+            entry(
+                "a",
+                at("2024-05-01", 9),
+                Some(at("2024-05-01", 11)),
+                EntrySource::Manual,
+            ),
+            // 手動の計測中に集中タイマーも動かした
+            entry(
+                "a",
+                at("2024-05-01", 10),
+                Some(at("2024-05-01", 10) + 25 * MINUTE_MS),
+                EntrySource::Focus,
+            ),
+            // 別のタスクなら重なっていても別々に数える
+            entry(
+                "b",
+                at("2024-05-01", 10),
+                Some(at("2024-05-01", 11)),
+                EntrySource::Manual,
+            ),
+        ];
+        let todos = [todo("a", None, &[], None), todo("b", None, &[], None)];
+        let r = report(
+            &entries,
+            &todos,
+            &[],
+            date("2024-05-01"),
+            date("2024-05-01"),
+            0,
+        )
+        .unwrap();
+        assert_eq!(r.total.actual_minutes, 180);
+        let a = r.by_task.iter().find(|row| row.key == "a").unwrap();
+        assert_eq!(a.actual_minutes, 120);
+    }
+
+    #[test]
+    fn clips_entries_and_prorates_estimates_to_the_range() {
+        // 見積もり 4 時間のタスクを 5/1 に 1 時間、5/2 に 3 時間
+        let entries = [
+            entry(
+                "a",
+                at("2024-05-01", 23),
+                Some(at("2024-05-02", 2)),
+                EntrySource::Manual,
+            ),
+            entry(
+                "a",
+                at("2024-05-01", 20),
+                Some(at("2024-05-01", 20) + 30 * MINUTE_MS),
+                EntrySource::Manual,
+            ),
+            entry(
+                "a",
+                at("2024-05-02", 9),
+                Some(at("2024-05-02", 9) + 30 * MINUTE_MS),
+                EntrySource::Focus,
+            ),
+        ];
+        let todos = [todo("a", Some(240), &[], None)];
+
+        let day1 = report(
+            &entries,
+            &todos,
+            &[],
+            date("2024-05-01"),
+            date("2024-05-01"),
+            0,
+        )
+        .unwrap();
+        assert_eq!(day1.total.actual_minutes, 90);
+        assert_eq!(day1.total.estimated_minutes, 90);
+        assert_eq!(day1.total.ratio, Some(1.0));
+
+        let day2 = report(
+            &entries,
+            &todos,
+            &[],
+            date("2024-05-02"),
+            date("2024-05-02"),
+            0,
+        )
+        .unwrap();
+        assert_eq!(day2.total.actual_minutes, 150);
+        assert_eq!(day2.total.estimated_minutes, 150);
+
+        // 全期間なら見積もりはそのまま
+        let both = report(
+            &entries,
+            &todos,
+            &[],
+            date("2024-05-01"),
+            date("2024-05-02"),
+            0,
+        )
+        .unwrap();
+        assert_eq!(both.total.actual_minutes, 240);
+        assert_eq!(both.total.estimated_minutes, 240);
+
+        // 見積もりより短く終わったタスクも按分しても比率は変わらない
+        let todos = [todo("a", Some(480), &[], None)];
+        let day1 = report(
+            &entries,
+            &todos,
+            &[],
+            date("2024-05-01"),
+            date("2024-05-01"),
+            0,
+        )
+        .unwrap();
+        assert_eq!(day1.total.estimated_minutes, 180);
+        assert_eq!(day1.total.ratio, Some(0.5));
+    }
+
+    #[test]
+    fn counts_running_entries_up_to_now() {
+        let now = at("2024-05-01", 10);
+        let entries = [entry("a", at("2024-05-01", 9), None, EntrySource::Manual)];
+        let r = report(
+            &entries,
+            &[],
+            &[],
+            date("2024-05-01"),
+            date("2024-05-01"),
+            now,
+        )
+        .unwrap();
+        assert_eq!(r.total.actual_minutes, 60);
+        // 削除したタスクは記録時の名前で出す
+        assert_eq!(r.by_task[0].name, "記録時のa");
+        assert_eq!(r.by_label[0].name, NO_LABEL);
+        assert_eq!(r.by_project[0].name, NO_PROJECT);
+        assert_eq!(r.total.ratio, None);
+    }
+
+    #[test]
+    fn groups_by_label_and_project() {
+        let entries = [
+            entry(
+                "a",
+                at("2024-05-01", 9),
+                Some(at("2024-05-01", 11)),
+                EntrySource::Manual,
+            ),
+            entry(
+                "b",
+                at("2024-05-01", 13),
+                Some(at("2024-05-01", 14)),
+                EntrySource::Manual,
+            ),
+            entry(
+                "c",
+                at("2024-05-01", 15),
+                Some(at("2024-05-01", 18)),
+                EntrySource::Manual,
+            ),
+        ];
+        let todos = [
+            todo("a", Some(60), &["会議", "設計"], Some("p1")),
+            todo("b", Some(120), &["設計"], Some("p1")),
+            todo("c", None, &[], None),
+        ];
+        let projects = [project("p1", "開発")];
+        let r = report(
+            &entries,
+            &todos,
+            &projects,
+            date("2024-05-01"),
+            date("2024-05-01"),
+            0,
+        )
+        .unwrap();
+
+        let labels: Vec<(&str, i64, usize)> = r
+            .by_label
+            .iter()
+            .map(|row| (row.name.as_str(), row.actual_minutes, row.task_count))
+            .collect();
+        assert_eq!(
+            labels,
+            [(NO_LABEL, 180, 1), ("設計", 180, 2), ("会議", 120, 1)]
+        );
+        let design = &r.by_label[1];
+        assert_eq!(design.estimated_minutes, 180);
+        assert_eq!(design.ratio, Some(1.0));
+
+        let projects: Vec<(&str, i64)> = r
+            .by_project
+            .iter()
+            .map(|row| (row.name.as_str(), row.actual_minutes))
+            .collect();
+        assert_eq!(projects, [(NO_PROJECT, 180), ("開発", 180)]);
+
+        assert_eq!(r.total.task_count, 3);
+        assert_eq!(r.total.actual_minutes, 360);
+        assert_eq!(r.total.estimated_actual_minutes, 180);
+    }
+
+    #[test]
+    fn rejects_overlapping_entries_on_the_same_task() {
+        let now = at("2024-05-01", 12);
+        let entries = vec![
+            entry(
+                "a",
+                at("2024-05-01", 9),
+                Some(at("2024-05-01", 10)),
+                EntrySource::Focus,
+            ),
+            entry("b", at("2024-05-01", 10), None, EntrySource::Manual),
+        ];
+        let new = |todo_id: &str, start: i64, end: i64| TimeEntry {
+            id: String::new(),
+            ..entry(todo_id, start, Some(end), EntrySource::Manual)
+        };
+        assert!(check_overlap(
+            &entries,
+            &new(
+                "a",
+                at("2024-05-01", 9) + 30 * MINUTE_MS,
+                at("2024-05-01", 11)
+            ),
+            now
+        )
+        .is_err());
+        // 隣り合うだけなら重なりではない
+        assert!(check_overlap(
+            &entries,
+            &new("a", at("2024-05-01", 10), at("2024-05-01", 11)),
+            now
+        )
+        .is_ok());
+        // 計測中の記録は now まで続いているとみなす
+        assert!(check_overlap(
+            &entries,
+            &new("b", at("2024-05-01", 11), at("2024-05-01", 13)),
+            now
+        )
+        .is_err());
+        assert!(check_overlap(
+            &entries,
+            &new("a", at("2024-05-01", 11), at("2024-05-01", 13)),
+            now
+        )
+        .is_ok());
+        // 自分自身の編集は重なりに数えない
+        let mut edited = entries[0].clone();
+        edited.end = Some(at("2024-05-01", 10) + 30 * MINUTE_MS);
+        assert!(check_overlap(&entries, &edited, now).is_ok());
+    }
+
+    #[test]
+    fn csv_escapes_names_and_appends_the_total() {
+        let entries = [entry(
+            "a",
+            at("2024-05-01", 9),
+            Some(at("2024-05-01", 10)),
+            EntrySource::Manual,
+        )];
+        let mut a = todo("a", Some(30), &[], None);
+        a.text = "=SUM(A1)".to_string();
+        let r = report(
+            &entries,
+            &[a],
+            &[],
+            date("2024-05-01"),
+            date("2024-05-01"),
+            0,
+        )
+        .unwrap();
+        let csv = report_csv(&r, ReportGroup::Task).unwrap();
+        let lines: Vec<&str> = csv.trim_start_matches('\u{feff}').lines().collect();
+        assert_eq!(lines[1], "'=SUM(A1),1,30,60,60,2.00");
+        assert_eq!(lines[2], "合計,1,30,60,60,2.00");
+    }
+
+    #[test]
+    fn rejects_reversed_ranges() {
+        assert!(report(&[], &[], &[], date("2024-05-02"), date("2024-05-01"), 0).is_err());
+        assert!(parse_date("2024/05/01").is_err());
+    }
+}
@@ -0,0 +1,608 @@
+// 期限のタイムゾーン
+// 期限 (dueDate) は UTC ミリ秒で持ち、どのタイムゾーンの時刻として決めたかを dueTimeZone に残す
+//   なし        : 時刻そのもの (従来どおり。表示は端末のタイムゾーン)
+//   "floating"  : 端末のローカル時刻 (出張先でも「9:00」のまま)
+//   IANA名      : そのタイムゾーンの時刻 (DSTの換算はchrono-tz)
+// 端末のタイムゾーンが変わったら floating の期限を換算し直し、フロントエンドに知らせる
+// 変更はフロントエンドが取り込んで timezone_mark_applied を呼ぶまで保留する (起動直後の変更は起動時に問い合わせる)
+
+use chrono::{DateTime, Local, NaiveDateTime, Offset, TimeZone};
+use std::sync::Mutex;
+use std::time::Duration;
+use tauri::{Emitter, Manager};
+
+use crate::ics::timezone::Zone;
+use crate::models::Todo;
+use crate::recurrence::{to_instant, to_local};
+
+pub const FLOATING: &str = "floating";
+const STATE_FILE: &str = "timezone-state.json";
+const CHECK_INTERVAL: Duration = Duration::from_secs(60);
+const LOCAL_FORMAT: &str = "%Y-%m-%dT%H:%M:%S";
+
+#[derive(serde::Serialize, serde::Deserialize, Clone, Debug, Default, PartialEq)]
+#[serde(rename_all = "camelCase", default)]
+pub struct SystemZone {
+    // IANA名 (取得できない環境では空)
+    pub name: String,
+    // 現在のUTCからのずれ (分)
+    pub offset_minutes: i32,
+}
+
+impl SystemZone {
+    pub fn current() -> Self {
+        // TZ 環境変数があればそちらが優先される (chrono の Local も同じ)
+        let from_env = std::env::var("TZ")
+            .ok()
+            .map(|tz| tz.trim_start_matches(':').to_string())
+            .filter(|tz| tz.parse::<chrono_tz::Tz>().is_ok());
+        let name = from_env
+            .or_else(|| iana_time_zone::get_timezone().ok())
+            .unwrap_or_default();
+        Self {
+            name,
+            offset_minutes: Local::now().offset().fix().local_minus_utc() / 60,
+        }
+    }
+
+    // 名前が取れない環境ではオフセットで比べる
+    fn same_zone(&self, other: &SystemZone) -> bool {
+        if self.name.is_empty() || other.name.is_empty() {
+            return self.offset_minutes == other.offset_minutes;
+        }
+        self.name == other.name
+    }
+
+    fn label(&self) -> String {
+        if self.name.is_empty() {
+            format_offset(self.offset_minutes)
+        } else {
+            self.name.clone()
+        }
+    }
+}
+
+// 端末の今の壁時計時刻 (起動後にタイムゾーンが変わっても、今のタイムゾーンで求める)
+pub fn system_now() -> NaiveDateTime {
+    let now = chrono::Utc::now();
+    match SystemZone::current().name.parse::<chrono_tz::Tz>() {
+        Ok(tz) => now.with_timezone(&tz).naive_local(),
+        Err(_) => now.with_timezone(&Local).naive_local(),
+    }
+}
+
+fn format_offset(minutes: i32) -> String {
+    let sign = if minutes < 0 { '-' } else { '+' };
+    format!(
+        "UTC{}{:02}:{:02}",
+        sign,
+        minutes.abs() / 60,
+        minutes.abs() % 60
+    )
+}
+
+// dueTimeZone の値を換算に使うタイムゾーンにする (なし・floating は端末のローカル時刻)
+pub fn due_zone(time_zone: Option<&str>) -> Result<Zone, String> {
+    match time_zone.map(str::trim).filter(|tz| !tz.is_empty()) {
+        None => Ok(Zone::Floating),
+        Some(tz) if tz.eq_ignore_ascii_case(FLOATING) => Ok(Zone::Floating),
+        Some(tz) => match Zone::from_name(Some(tz)) {
+            Zone::Floating => Err(format!("不明なタイムゾーンです: {}", tz)),
+            zone => Ok(zone),
+        },
+    }
+}
+
+pub fn is_floating(todo: &Todo) -> bool {
+    todo.due_time_zone
+        .as_deref()
+        .is_some_and(|tz| tz.eq_ignore_ascii_case(FLOATING))
+}
+
+pub fn parse_local(value: &str) -> Result<NaiveDateTime, String> {
+    let value = value.trim();
+    NaiveDateTime::parse_from_str(value, LOCAL_FORMAT)
+        .or_else(|_| NaiveDateTime::parse_from_str(value, "%Y-%m-%dT%H:%M"))
+        .map_err(|_| format!("日時の形式が不正です (YYYY-MM-DDTHH:MM:SS): {}", value))
+}
+
+// 壁時計時刻を期限のミリ秒にする
+// DSTの開始で存在しない時刻は切り替え後の時刻に、終了で2回ある時刻は早い方になる
+pub fn resolve_due(due_local: &str, time_zone: Option<&str>) -> Result<i64, String> {
+    let local = parse_local(due_local)?;
+    let zone = due_zone(time_zone)?;
+    to_instant(&zone, local).ok_or_else(|| format!("日時を換算できません: {}", due_local))
+}
+
+// 期限のミリ秒をそのタイムゾーンの壁時計時刻にする
+pub fn local_for(due_date: i64, time_zone: Option<&str>) -> Result<String, String> {
+    let zone = due_zone(time_zone)?;
+    Ok(to_local(&zone, due_date)?.format(LOCAL_FORMAT).to_string())
+}
+
+// 通知などに載せる期限の表記 (タイムゾーン指定があればその時刻にタイムゾーン名を添える)
+pub fn format_due(todo: &Todo, format: &str) -> Option<String> {
+    let due = todo.due_date?;
+    let named = todo
+        .due_time_zone
+        .as_deref()
+        .filter(|_| !is_floating(todo))
+        .and_then(|tz| Some((tz.trim(), due_zone(Some(tz)).ok()?)));
+    match named {
+        Some((name, zone)) => {
+            let local = to_local(&zone, due).ok()?;
+            Some(format!("{} ({})", local.format(format), name))
+        }
+        None => Local
+            .timestamp_millis_opt(due)
+            .single()
+            .map(|d| d.format(format).to_string()),
+    }
+}
+
+// 端末のタイムゾーンで換算し直した floating の期限 (変わらなければ None)
+fn rebased_due(todo: &Todo) -> Option<i64> {
+    if !is_floating(todo) {
+        return None;
+    }
+    let due = resolve_due(todo.due_local.as_deref()?, Some(FLOATING)).ok()?;
+    (todo.due_date != Some(due)).then_some(due)
+}
+
+#[derive(serde::Serialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct DueDateChange {
+    pub id: String,
+    pub due_date: i64,
+    // 通知時刻がまだ来ていないので未送信の通知を取り消した
+    pub reminder_rescheduled: bool,
+}
+
+#[derive(serde::Serialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct ZoneChangeEvent {
+    pub previous: SystemZone,
+    pub current: SystemZone,
+    pub changes: Vec<DueDateChange>,
+    // 取り込んだら timezone_mark_applied に渡す
+    pub revision: u64,
+}
+
+pub fn rebase_floating(todos: &[Todo], now: i64) -> Vec<DueDateChange> {
+    todos
+        .iter()
+        .filter(|todo| todo.is_active())
+        .filter_map(|todo| {
+            let due_date = rebased_due(todo)?;
+            let notify_before = todo
+                .due_date_notification
+                .as_ref()
+                .filter(|n| n.enabled)
+                .map(|n| n.notify_before * 60_000);
+            Some(DueDateChange {
+                id: todo.id.clone(),
+                due_date,
+                reminder_rescheduled: notify_before.is_some_and(|before| due_date - before > now),
+            })
+        })
+        .collect()
+}
+
+#[derive(Debug)]
+struct ZoneWatch {
+    // フロントエンドが取り込み済みのタイムゾーン (保存するのはこれだけ)
+    applied: SystemZone,
+    // 取り込まれていない変更
+    pending: Option<ZoneChangeEvent>,
+    revision: u64,
+}
+
+impl ZoneWatch {
+    fn new(applied: SystemZone) -> Self {
+        Self {
+            applied,
+            pending: None,
+            revision: 0,
+        }
+    }
+
+    // 端末のタイムゾーンを見て、知らせる変更があれば返す
+    fn observe(
+        &mut self,
+        current: SystemZone,
+        todos: &[Todo],
+        now: i64,
+    ) -> Option<ZoneChangeEvent> {
+        let seen = self.pending.as_ref().map_or(&self.applied, |p| &p.current);
+        if *seen == current {
+            return None;
+        }
+        // 同じタイムゾーンのDST切り替えでは壁時計時刻の換算は変わらない
+        // (保留中の変更があれば、取り込まれたかもしれない期限を戻すために知らせ直す)
+        if self.pending.is_none() && self.applied.same_zone(&current) {
+            self.applied = current;
+            return None;
+        }
+        self.revision += 1;
+        let event = ZoneChangeEvent {
+            previous: self.applied.clone(),
+            current,
+            changes: rebase_floating(todos, now),
+            revision: self.revision,
+        };
+        self.pending = Some(event.clone());
+        Some(event)
+    }
+
+    // 保留中の変更 (期限は今のタスクから換算し直す)
+    fn pending(&self, todos: &[Todo], now: i64) -> Option<ZoneChangeEvent> {
+        let mut event = self.pending.clone()?;
+        event.changes = rebase_floating(todos, now);
+        Some(event)
+    }
+
+    fn mark_applied(&mut self, revision: u64) -> bool {
+        match self.pending.take() {
+            Some(event) if event.revision == revision => {
+                self.applied = event.current;
+                true
+            }
+            other => {
+                self.pending = other;
+                false
+            }
+        }
+    }
+}
+
+pub struct TimezoneState {
+    watch: Mutex<ZoneWatch>,
+}
+
+fn save_applied(app: &tauri::AppHandle, zone: &SystemZone) {
+    if let Err(e) = crate::store::save_json(app, STATE_FILE, zone) {
+        println!("[Timezone Backend] 状態の保存失敗: {}", e);
+    }
+}
+
+impl TimezoneState {
+    pub fn load(app: &tauri::AppHandle) -> Self {
+        // 初回起動時は今のタイムゾーンを前回の値とする
+        let applied = crate::store::load_json::<Option<SystemZone>>(app, STATE_FILE)
+            .unwrap_or_else(|e| {
+                println!("[Timezone Backend] 状態の読み込み失敗: {}", e);
+                None
+            })
+            .unwrap_or_else(SystemZone::current);
+        Self {
+            watch: Mutex::new(ZoneWatch::new(applied)),
+        }
+    }
+
+    // 前回から端末のタイムゾーンが変わっていれば floating の期限を換算し直す
+    fn check(&self, app: &tauri::AppHandle) {
+        let todos = app.state::<crate::todos::TodoState>().all();
+        let mut watch = self.watch.lock().unwrap();
+        let applied = watch.applied.clone();
+        let event = watch.observe(
+            SystemZone::current(),
+            &todos,
+            Local::now().timestamp_millis(),
+        );
+        if watch.applied != applied {
+            save_applied(app, &watch.applied);
+        }
+        drop(watch);
+        let Some(event) = event else {
+            return;
+        };
+
+        println!(
+            "[Timezone Backend] タイムゾーンの変更を検知: {} -> {} (期限の換算 {} 件)",
+            event.previous.label(),
+            event.current.label(),
+            event.changes.len()
+        );
+        // 古い期限で送信待ちになっている通知は取り消す (フロントエンドが新しい期限で送り直す)
+        let outbox = app.state::<crate::outbox::OutboxState>();
+        for change in event.changes.iter().filter(|c| c.reminder_rescheduled) {
+            outbox.expire_task(app, &change.id);
+        }
+
+        if let Err(e) = app.emit("timezone-changed", &event) {
+            println!("[Timezone Backend] イベント送信失敗: {}", e);
+        }
+    }
+}
+
+pub async fn run_watcher(app: tauri::AppHandle) {
+    loop {
+        app.state::<TimezoneState>().check(&app);
+        tokio::time::sleep(CHECK_INTERVAL).await;
+    }
+}
+
+#[derive(serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DueTimeInfo {
+    // 期限のタイムゾーンでの時刻
+    pub local: String,
+    pub time_zone: Option<String>,
+    pub offset_minutes: i32,
+    // 端末のタイムゾーンでの時刻
+    pub system_local: String,
+    pub system_offset_minutes: i32,
+}
+
+fn offset_minutes(zone: &Zone, due_date: i64) -> Result<i32, String> {
+    let local = to_local(zone, due_date)?;
+    let utc = DateTime::from_timestamp_millis(due_date)
+        .ok_or_else(|| "日時が不正です".to_string())?
+        .naive_utc();
+    Ok((local - utc).num_minutes() as i32)
+}
+
+pub fn describe_due(due_date: i64, time_zone: Option<&str>) -> Result<DueTimeInfo, String> {
+    let zone = due_zone(time_zone)?;
+    Ok(DueTimeInfo {
+        local: local_for(due_date, time_zone)?,
+        time_zone: time_zone.map(str::to_string),
+        offset_minutes: offset_minutes(&zone, due_date)?,
+        system_local: local_for(due_date, None)?,
+        system_offset_minutes: offset_minutes(&Zone::Floating, due_date)?,
+    })
+}
+
+// 端末の現在のタイムゾーン
+#[tauri::command]
+pub fn timezone_system() -> SystemZone {
+    SystemZone::current()
+}
+
+// 選択できるIANAタイムゾーン名
+#[tauri::command]
+pub fn timezone_list() -> Vec<String> {
+    chrono_tz::TZ_VARIANTS
+        .iter()
+        .map(|tz| tz.name().to_string())
+        .collect()
+}
+
+// 壁時計時刻とタイムゾーンから dueDate を求める
+#[tauri::command]
+pub fn timezone_resolve_due(due_local: String, time_zone: Option<String>) -> Result<i64, String> {
+    resolve_due(&due_local, time_zone.as_deref())
+}
+
+// dueDate を期限のタイムゾーンと端末のタイムゾーンで表す
+#[tauri::command]
+pub fn timezone_describe_due(
+    due_date: i64,
+    time_zone: Option<String>,
+) -> Result<DueTimeInfo, String> {
+    describe_due(due_date, time_zone.as_deref())
+}
+
+// 取り込まれていないタイムゾーンの変更 (起動直後の変更はイベントを受け取る前に送られるため、起動時に問い合わせる)
+#[tauri::command]
+pub fn timezone_pending_change(
+    state: tauri::State<'_, TimezoneState>,
+    todos: tauri::State<'_, crate::todos::TodoState>,
+) -> Option<ZoneChangeEvent> {
+    state
+        .watch
+        .lock()
+        .unwrap()
+        .pending(&todos.all(), Local::now().timestamp_millis())
+}
+
+// フロントエンドが換算した期限を取り込んだら呼ぶ。以降はこのタイムゾーンを基準にする
+#[tauri::command]
+pub fn timezone_mark_applied(
+    app: tauri::AppHandle,
+    state: tauri::State<'_, TimezoneState>,
+    revision: u64,
+) {
+    let mut watch = state.watch.lock().unwrap();
+    if watch.mark_applied(revision) {
+        save_applied(&app, &watch.applied);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::DueDateNotification;
+
+    fn utc(value: &str) -> i64 {
+        DateTime::parse_from_rfc3339(value)
+            .unwrap()
+            .timestamp_millis()
+    }
+
+    fn zone(name: &str, offset_minutes: i32) -> SystemZone {
+        SystemZone {
+            name: name.to_string(),
+            offset_minutes,
+        }
+    }
+
+    fn floating(id: &str, due_local: &str, due_date: i64) -> Todo {
+        Todo {
+            id: id.to_string(),
+            due_date: Some(due_date),
+            due_time_zone: Some(FLOATING.to_string()),
+            due_local: Some(due_local.to_string()),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn resolves_wall_clock_times_across_dst() {
+        for (due_local, time_zone, expected) in [
+            ("2026-07-01T09:00:00", "Asia/Tokyo", "2026-07-01T00:00:00Z"),
+            ("2026-07-01T09:00", "Asia/Tokyo", "2026-07-01T00:00:00Z"),
+            // 存在しない時刻は切り替え後の時刻になる
+            (
+                "2026-03-08T02:30:00",
+                "America/New_York",
+                "2026-03-08T07:30:00Z",
+            ),
+            (
+                "2026-03-08T03:30:00",
+                "America/New_York",
+                "2026-03-08T07:30:00Z",
+            ),
+            (
+                "2026-03-29T02:30:00",
+                "Europe/Berlin",
+                "2026-03-29T01:30:00Z",
+            ),
+            // 2回ある時刻は早い方 (夏時間) になる
+            (
+                "2026-11-01T01:30:00",
+                "America/New_York",
+                "2026-11-01T05:30:00Z",
+            ),
+            (
+                "2026-10-25T02:30:00",
+                "Europe/Berlin",
+                "2026-10-25T00:30:00Z",
+            ),
+        ] {
+            assert_eq!(
+                resolve_due(due_local, Some(time_zone)),
+                Ok(utc(expected)),
+                "{} {}",
+                due_local,
+                time_zone
+            );
+        }
+        assert_eq!(
+            local_for(utc("2026-11-01T06:30:00Z"), Some("America/New_York")).unwrap(),
+            "2026-11-01T01:30:00"
+        );
+        assert!(resolve_due("2026-07-01T09:00:00", Some("Mars/Olympus")).is_err());
+        assert!(resolve_due("2026/07/01 09:00", Some("Asia/Tokyo")).is_err());
+        // floating と指定なしは端末の時刻
+        assert_eq!(
+            resolve_due("2026-07-01T09:00:00", Some("Floating")),
+            resolve_due("2026-07-01T09:00:00", None)
+        );
+    }
+
+    #[test]
+    fn describes_due_in_both_zones() {
+        let info = describe_due(utc("2026-07-01T00:00:00Z"), Some("America/New_York")).unwrap();
+        assert_eq!(info.local, "2026-06-30T20:00:00");
+        assert_eq!(info.offset_minutes, -240);
+        let info = describe_due(utc("2026-01-01T00:00:00Z"), Some("America/New_York")).unwrap();
+        assert_eq!(info.offset_minutes, -300);
+    }
+
+    #[test]
+    fn compares_zones_by_name_or_offset() {
+        // DSTでオフセットが変わっても同じタイムゾーン
+        assert!(zone("Europe/Berlin", 60).same_zone(&zone("Europe/Berlin", 120)));
+        assert!(!zone("Europe/Berlin", 60).same_zone(&zone("Africa/Lagos", 60)));
+        // 名前が取れなければオフセットで比べる
+        assert!(zone("", 540).same_zone(&zone("Asia/Tokyo", 540)));
+        assert!(!zone("", 540).same_zone(&zone("", 480)));
+        assert_eq!(zone("", -210).label(), "UTC-03:30");
+    }
+
+    #[test]
+    fn rebases_only_active_floating_todos() {
+        let due_local = "2026-07-01T09:00:00";
+        let due = resolve_due(due_local, Some(FLOATING)).unwrap();
+        let stale = due + 3 * 3_600_000;
+        let notify = |notify_before| DueDateNotification {
+            enabled: true,
+            notify_before,
+            ..Default::default()
+        };
+        let todos = vec![
+            Todo {
+                due_date_notification: Some(notify(30)),
+                ..floating("moved", due_local, stale)
+            },
+            // 通知時刻が過ぎていれば通知は送り直さない
+            Todo {
+                due_date_notification: Some(notify(24 * 60)),
+                ..floating("notified", due_local, stale)
+            },
+            floating("same", due_local, due),
+            Todo {
+                completed: true,
+                ..floating("done", due_local, stale)
+            },
+            Todo {
+                due_time_zone: Some("Asia/Tokyo".to_string()),
+                ..floating("named", due_local, stale)
+            },
+            Todo {
+                due_local: None,
+                ..floating("no-local", due_local, stale)
+            },
+        ];
+        let changes = rebase_floating(&todos, due - 60 * 60_000);
+        let changes: Vec<(&str, i64, bool)> = changes
+            .iter()
+            .map(|c| (c.id.as_str(), c.due_date, c.reminder_rescheduled))
+            .collect();
+        assert_eq!(changes, [("moved", due, true), ("notified", due, false)]);
+    }
+
+    #[test]
+    fn keeps_zone_changes_until_applied() {
+        let due_local = "2026-07-01T09:00:00";
+        let due = resolve_due(due_local, Some(FLOATING)).unwrap();
+        let todos = [floating("a", due_local, due + 3_600_000)];
+        let tokyo = zone("Asia/Tokyo", 540);
+        let mut watch = ZoneWatch::new(zone("Europe/Berlin", 60));
+
+        // DSTの切り替えだけなら知らせない
+        assert!(watch
+            .observe(zone("Europe/Berlin", 120), &todos, 0)
+            .is_none());
+        assert_eq!(watch.applied, zone("Europe/Berlin", 120));
+
+        let event = watch.observe(tokyo.clone(), &todos, 0).unwrap();
+        assert_eq!(event.revision, 1);
+        assert_eq!(event.previous, zone("Europe/Berlin", 120));
+        assert_eq!(event.changes.len(), 1);
+        // 取り込まれるまで基準のタイムゾーンは変えず、同じ変更は知らせ直さない
+        assert!(watch.observe(tokyo.clone(), &todos, 0).is_none());
+        assert_eq!(watch.applied, zone("Europe/Berlin", 120));
+
+        // 起動時の問い合わせには今のタスクで換算し直して返す
+        let pending = watch.pending(&[], 0).unwrap();
+        assert_eq!(pending.revision, 1);
+        assert!(pending.changes.is_empty());
+
+        assert!(!watch.mark_applied(0));
+        assert!(watch.pending(&todos, 0).is_some());
+        assert!(watch.mark_applied(1));
+        assert_eq!(watch.applied, tokyo);
+        assert!(watch.pending(&todos, 0).is_none());
+        assert!(!watch.mark_applied(1));
+    }
+
+    #[test]
+    fn renotifies_when_zone_returns_before_apply() {
+        let berlin = zone("Europe/Berlin", 120);
+        let mut watch = ZoneWatch::new(berlin.clone());
+        let first = watch.observe(zone("Asia/Tokyo", 540), &[], 0).unwrap();
+        // 取り込まれる前に元のタイムゾーンに戻っても、新しい版として知らせる
+        let second = watch.observe(berlin.clone(), &[], 0).unwrap();
+        assert!(second.revision > first.revision);
+        assert_eq!(second.current, berlin);
+        assert!(!watch.mark_applied(first.revision));
+        assert!(watch.mark_applied(second.revision));
+        assert_eq!(watch.applied, berlin);
+
+        // 取り込まれずに再起動しても、保存したタイムゾーンから知らせ直す
+        let mut restarted = ZoneWatch::new(berlin);
+        assert!(restarted.observe(zone("Asia/Tokyo", 540), &[], 0).is_some());
+    }
+}
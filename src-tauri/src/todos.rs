@@ -0,0 +1,93 @@
+// フロントエンドから同期されたタスクのスナップショット
+// データの正はWebview側 (localStorage) にあり、バックエンドは通知などのために写しを持つ
+
+use std::sync::Mutex;
+use tauri::Manager;
+
+use crate::models::{Project, Todo};
+
+const SNAPSHOT_FILE: &str = "todos-snapshot.json";
+const PROJECTS_SNAPSHOT_FILE: &str = "projects-snapshot.json";
+
+pub struct TodoState {
+    todos: Mutex<Vec<Todo>>,
+    projects: Mutex<Vec<Project>>,
+}
+
+impl TodoState {
+    pub fn load(app: &tauri::AppHandle) -> Self {
+        let todos = crate::store::load_json(app, SNAPSHOT_FILE).unwrap_or_else(|e| {
+            println!("[Todos Backend] スナップショット読み込み失敗: {}", e);
+            Vec::new()
+        });
+        let projects = crate::store::load_json(app, PROJECTS_SNAPSHOT_FILE).unwrap_or_else(|e| {
+            println!("[Todos Backend] プロジェクトの読み込み失敗: {}", e);
+            Vec::new()
+        });
+        Self {
+            todos: Mutex::new(todos),
+            projects: Mutex::new(projects),
+        }
+    }
+
+    pub fn all(&self) -> Vec<Todo> {
+        self.todos.lock().unwrap().clone()
+    }
+
+    pub fn projects(&self) -> Vec<Project> {
+        self.projects.lock().unwrap().clone()
+    }
+
+    pub fn find(&self, id: &str) -> Option<Todo> {
+        self.todos
+            .lock()
+            .unwrap()
+            .iter()
+            .find(|todo| todo.id == id)
+            .cloned()
+    }
+}
+
+// タスクが変更されるたびにフロントエンドから呼ばれる
+#[tauri::command]
+pub fn sync_todos(
+    app: tauri::AppHandle,
+    state: tauri::State<'_, TodoState>,
+    todos: Vec<Todo>,
+) -> Result<(), String> {
+    crate::store::save_json(&app, SNAPSHOT_FILE, &todos)?;
+
+    // 完了・削除されたタスクの送信待ちメッセージを破棄する
+    app.state::<crate::outbox::OutboxState>()
+        .expire_irrelevant(&app, &todos);
+
+    // 検索索引は変わったタスクだけ更新する
+    app.state::<crate::search::SearchState>().sync(&todos);
+
+    // todo.txt と同期している場合はファイルにも書き出す
+    app.state::<crate::todotxt::TodoTxtState>()
+        .write_file(&app, &todos, &state.projects());
+
+    // 依存先が終わってブロックが外れたタスクを通知する
+    app.state::<crate::dependency::DependencyState>()
+        .sync(&app, &state.all(), &todos);
+
+    // 目標日を設定したプロジェクトの完了予定日を計算し直す
+    app.state::<crate::schedule::ScheduleState>()
+        .sync(&app, &todos);
+
+    *state.todos.lock().unwrap() = todos;
+    Ok(())
+}
+
+// プロジェクトが変更されるたびにフロントエンドから呼ばれる
+#[tauri::command]
+pub fn sync_projects(
+    app: tauri::AppHandle,
+    state: tauri::State<'_, TodoState>,
+    projects: Vec<Project>,
+) -> Result<(), String> {
+    crate::store::save_json(&app, PROJECTS_SNAPSHOT_FILE, &projects)?;
+    *state.projects.lock().unwrap() = projects;
+    Ok(())
+}
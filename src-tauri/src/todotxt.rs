@@ -0,0 +1,878 @@
+// todo.txt 形式のインポート・エクスポートとファイル同期
+// 優先度 (A)〜(D) を P1〜P4、+プロジェクト をプロジェクト、@コンテキスト をラベル、due: を期限に対応させる
+// 同期を有効にすると、タスクの変更をファイルに書き出し、ファイルの外部変更を todotxt-changed イベントで知らせる
+// 外部の変更はフロントエンドが取り込んで todotxt_mark_imported を呼ぶまで保留し、その間はファイルを上書きしない
+
+use chrono::{Local, NaiveDate, TimeZone};
+use std::collections::HashSet;
+use std::fs;
+use std::path::Path;
+use std::sync::Mutex;
+use std::time::Duration;
+use tauri::{Emitter, Manager};
+
+use crate::ics;
+use crate::models::{DueDateNotification, Project, Todo};
+
+const SETTINGS_FILE: &str = "todotxt-settings.json";
+const SYNC_STATE_FILE: &str = "todotxt-sync-state.json";
+const WATCH_INTERVAL: Duration = Duration::from_secs(3);
+const DEFAULT_PROJECT_COLOR: &str = "#6366f1";
+
+#[derive(serde::Serialize, serde::Deserialize, Clone)]
+#[serde(rename_all = "camelCase", default)]
+pub struct TodoTxtSettings {
+    pub enabled: bool,
+    pub path: String,
+    pub include_completed: bool,
+}
+
+impl Default for TodoTxtSettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            path: String::new(),
+            include_completed: true,
+        }
+    }
+}
+
+// 最後に書き出した (または取り込んだ) ファイルの内容。外部での変更と削除されたタスクの判定に使う
+#[derive(serde::Serialize, serde::Deserialize, Default)]
+#[serde(rename_all = "camelCase", default)]
+struct TodoTxtSyncState {
+    path: String,
+    last_content: Option<String>,
+    // 検知したがまだ取り込まれていない外部の変更後の内容。取り込み以外では消さない
+    unimported: Option<String>,
+    // 検知した変更の通し番号。古い変更の取り込みで新しい変更を取り込み済みにしないために使う
+    revision: u64,
+}
+
+impl TodoTxtSyncState {
+    // 保留中の変更がなく、ファイルが前回書き出したままなら上書きしてよい
+    fn can_overwrite(&self, path: &str, current: Option<&str>) -> bool {
+        if self.unimported.is_some() && self.path == path {
+            return false;
+        }
+        current.is_none_or(|current| {
+            self.path == path && self.last_content.as_deref() == Some(current)
+        })
+    }
+
+    // ファイルの内容を確認し、新しく取り込むべき変更があれば返す
+    fn detect(
+        &mut self,
+        path: &str,
+        content: &str,
+        todos: &[Todo],
+        projects: &[Project],
+    ) -> Option<TodoTxtChanges> {
+        let same_path = self.path == path;
+        if same_path && self.last_content.as_deref() == Some(content) {
+            // 取り込む前に元に戻された
+            self.unimported = None;
+            return None;
+        }
+        if same_path && self.unimported.as_deref() == Some(content) {
+            return None;
+        }
+        if !same_path {
+            self.path = path.to_string();
+            self.last_content = None;
+        }
+
+        let mut changes = diff_file(content, self.last_content.as_deref(), todos, projects);
+        if changes.upserted.is_empty() && changes.deleted_ids.is_empty() {
+            // 書式だけの違いなどはそのまま取り込み済みとする
+            self.last_content = Some(content.to_string());
+            self.unimported = None;
+            return None;
+        }
+        self.revision += 1;
+        self.unimported = Some(content.to_string());
+        changes.revision = self.revision;
+        Some(changes)
+    }
+
+    // 保留中の変更を、今のタスクに対する差分として計算し直す
+    fn pending(&self, path: &str, todos: &[Todo], projects: &[Project]) -> Option<TodoTxtChanges> {
+        let content = self.unimported.as_deref().filter(|_| self.path == path)?;
+        let mut changes = diff_file(content, self.last_content.as_deref(), todos, projects);
+        changes.revision = self.revision;
+        Some(changes)
+    }
+
+    fn mark_imported(&mut self, revision: u64) -> bool {
+        if revision != self.revision || self.unimported.is_none() {
+            return false;
+        }
+        self.last_content = self.unimported.take();
+        true
+    }
+}
+
+#[derive(serde::Serialize, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct TodoTxtImportResult {
+    // +プロジェクト から作ったプロジェクト (既存のプロジェクトに一致しなかったもの)
+    pub projects: Vec<Project>,
+    pub todos: Vec<Todo>,
+}
+
+#[derive(serde::Serialize, Default, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct TodoTxtChanges {
+    pub projects: Vec<Project>,
+    // フロントエンドで追加または置き換えるタスク
+    pub upserted: Vec<Todo>,
+    // フロントエンドで削除するタスクのID
+    pub deleted_ids: Vec<String>,
+    // 取り込んだら todotxt_mark_imported に渡す
+    pub revision: u64,
+}
+
+#[derive(Default)]
+struct TodoTxtLine {
+    completed: bool,
+    completion_date: Option<NaiveDate>,
+    creation_date: Option<NaiveDate>,
+    priority: Option<u8>,
+    text: String,
+    project: Option<String>,
+    labels: Vec<String>,
+    due: Option<NaiveDate>,
+    id: Option<String>,
+}
+
+fn parse_date(token: &str) -> Option<NaiveDate> {
+    NaiveDate::parse_from_str(token, "%Y-%m-%d").ok()
+}
+
+fn local_date(millis: i64) -> Option<NaiveDate> {
+    Local
+        .timestamp_millis_opt(millis)
+        .single()
+        .map(|d| d.date_naive())
+}
+
+fn local_midnight(date: NaiveDate) -> Option<i64> {
+    Local
+        .from_local_datetime(&date.and_hms_opt(0, 0, 0)?)
+        .earliest()
+        .map(|d| d.timestamp_millis())
+}
+
+// (E) 以降はP4として扱う
+fn priority_from_letter(letter: char) -> Option<u8> {
+    match letter {
+        'A' => Some(1),
+        'B' => Some(2),
+        'C' => Some(3),
+        'D'..='Z' => Some(4),
+        _ => None,
+    }
+}
+
+fn priority_letter(priority: u8) -> Option<char> {
+    match priority {
+        1 => Some('A'),
+        2 => Some('B'),
+        3 => Some('C'),
+        4 => Some('D'),
+        _ => None,
+    }
+}
+
+// "(A)" 形式の優先度
+fn parse_priority(token: &str) -> Option<u8> {
+    let letter = token.strip_prefix('(')?.strip_suffix(')')?;
+    let mut chars = letter.chars();
+    let priority = priority_from_letter(chars.next()?)?;
+    chars.next().is_none().then_some(priority)
+}
+
+// 空白を含む名前は +プロジェクト や @ラベル として読めないため - に置き換える
+fn tag_name(name: &str) -> String {
+    name.split_whitespace().collect::<Vec<_>>().join("-")
+}
+
+fn parse_line(line: &str) -> Option<TodoTxtLine> {
+    let mut tokens = line.split_whitespace().peekable();
+    tokens.peek()?;
+    let mut parsed = TodoTxtLine::default();
+
+    if tokens.peek() == Some(&"x") {
+        tokens.next();
+        parsed.completed = true;
+        if let Some(date) = tokens.peek().and_then(|t| parse_date(t)) {
+            tokens.next();
+            parsed.completion_date = Some(date);
+        }
+    } else if let Some(priority) = tokens.peek().and_then(|t| parse_priority(t)) {
+        tokens.next();
+        parsed.priority = Some(priority);
+    }
+    if let Some(date) = tokens.peek().and_then(|t| parse_date(t)) {
+        tokens.next();
+        parsed.creation_date = Some(date);
+    }
+
+    // +プロジェクト が複数あれば最後のものを採用し、残りは本文として扱う (書き出しは本文の後ろに付ける)
+    let mut words = Vec::new();
+    let mut project_index = None;
+    for token in tokens {
+        if token.len() > 1 && token.starts_with('+') {
+            project_index = Some(words.len());
+        } else if let Some(label) = token.strip_prefix('@').filter(|l| !l.is_empty()) {
+            if !parsed.labels.iter().any(|l| l == label) {
+                parsed.labels.push(label.to_string());
+            }
+            continue;
+        } else if let Some((key, value)) = token.split_once(':') {
+            // 知らないキーは本文の一部として残す
+            match key {
+                "due" if parse_date(value).is_some() => {
+                    parsed.due = parse_date(value);
+                    continue;
+                }
+                // 完了したタスクの優先度は pri: で残すのが慣例
+                "pri" if value.len() == 1 => {
+                    if let Some(priority) = value.chars().next().and_then(priority_from_letter) {
+                        parsed.priority = Some(priority);
+                        continue;
+                    }
+                }
+                "id" if !value.is_empty() => {
+                    parsed.id = Some(value.to_string());
+                    continue;
+                }
+                _ => {}
+            }
+        }
+        words.push(token);
+    }
+    if let Some(index) = project_index {
+        parsed.project = Some(words.remove(index)[1..].to_string());
+    }
+    parsed.text = words.join(" ");
+    Some(parsed)
+}
+
+fn format_line(todo: &Todo, projects: &[Project], with_id: bool) -> String {
+    let date = |millis: i64| local_date(millis).map(|d| d.format("%Y-%m-%d").to_string());
+    let mut parts: Vec<String> = Vec::new();
+
+    let letter = priority_letter(todo.priority);
+    let created = Some(todo.created_at).filter(|c| *c > 0).and_then(date);
+    if todo.completed {
+        parts.push("x".to_string());
+        // 完了日がないと作成日が完了日として読まれるため、作成日は完了日があるときだけ書く
+        if let Some(completed) = todo.completed_at.and_then(date) {
+            parts.push(completed);
+            parts.extend(created);
+        }
+    } else {
+        if let Some(letter) = letter {
+            parts.push(format!("({})", letter));
+        }
+        parts.extend(created);
+    }
+
+    let text = todo.text.split_whitespace().collect::<Vec<_>>().join(" ");
+    if !text.is_empty() {
+        parts.push(text);
+    }
+    if let Some(project_id) = &todo.project_id {
+        let name = projects
+            .iter()
+            .find(|p| &p.id == project_id)
+            .map_or(project_id.as_str(), |p| p.name.as_str());
+        parts.push(format!("+{}", tag_name(name)));
+    }
+    for label in &todo.labels {
+        parts.push(format!("@{}", tag_name(label)));
+    }
+    if let Some(due) = todo.due_date.and_then(date) {
+        parts.push(format!("due:{}", due));
+    }
+    if todo.completed {
+        if let Some(letter) = letter {
+            parts.push(format!("pri:{}", letter));
+        }
+    }
+    if with_id && !todo.id.is_empty() {
+        parts.push(format!("id:{}", todo.id));
+    }
+    parts.join(" ")
+}
+
+fn exported_todos(todos: &[Todo], include_completed: bool) -> impl Iterator<Item = &Todo> {
+    todos
+        .iter()
+        .filter(move |t| !t.archived && (include_completed || !t.completed))
+}
+
+pub fn export_todotxt(
+    todos: &[Todo],
+    projects: &[Project],
+    include_completed: bool,
+    with_ids: bool,
+) -> String {
+    let mut todos: Vec<&Todo> = exported_todos(todos, include_completed).collect();
+    todos.sort_by(|a, b| {
+        a.completed
+            .cmp(&b.completed)
+            .then(a.order.total_cmp(&b.order))
+            .then(a.created_at.cmp(&b.created_at))
+    });
+    todos
+        .iter()
+        .map(|todo| format!("{}\n", format_line(todo, projects, with_ids)))
+        .collect()
+}
+
+// +プロジェクト 名から既存のプロジェクトを探し、なければ新しく作る
+struct ProjectResolver<'a> {
+    existing: &'a [Project],
+    created: Vec<Project>,
+    now: i64,
+}
+
+impl ProjectResolver<'_> {
+    fn resolve(&mut self, name: &str) -> String {
+        let found = self
+            .existing
+            .iter()
+            .chain(self.created.iter())
+            .find(|p| tag_name(&p.name) == name);
+        if let Some(project) = found {
+            return project.id.clone();
+        }
+        let project = Project {
+            id: format!("todotxt-project-{}-{}", self.now, self.created.len()),
+            name: name.to_string(),
+            color: DEFAULT_PROJECT_COLOR.to_string(),
+            order: (self.existing.len() + self.created.len()) as f64,
+            ..Default::default()
+        };
+        let id = project.id.clone();
+        self.created.push(project);
+        id
+    }
+}
+
+// 行の内容をタスクに反映する (期限日が変わっていなければ時刻を残す)
+fn apply_line(line: &TodoTxtLine, todo: &mut Todo, project_id: Option<String>, now: i64) {
+    todo.text = line.text.clone();
+    if line.completed != todo.completed {
+        todo.completed_at = if line.completed {
+            Some(line.completion_date.and_then(local_midnight).unwrap_or(now))
+        } else {
+            None
+        };
+    }
+    todo.completed = line.completed;
+    todo.priority = line.priority.unwrap_or(4);
+    todo.labels = line.labels.clone();
+    todo.project_id = project_id;
+    if line.due != todo.due_date.and_then(local_date) {
+        todo.due_date = line.due.and_then(local_midnight);
+        if let Some(due) = todo.due_date {
+            todo.timeframe = ics::timeframe_for(due, todo.recurrence.as_ref(), now);
+            todo.due_date_notification
+                .get_or_insert(DueDateNotification {
+                    enabled: true,
+                    notify_before: 0,
+                    notified_at: None,
+                    follow_up_count: 0,
+                });
+        }
+    }
+}
+
+fn new_todo(line: &TodoTxtLine, id: String, project_id: Option<String>, now: i64) -> Todo {
+    let mut todo = Todo {
+        id,
+        created_at: line.creation_date.and_then(local_midnight).unwrap_or(now),
+        ..Default::default()
+    };
+    apply_line(line, &mut todo, project_id, now);
+    todo
+}
+
+fn todo_fields(todo: &Todo) -> String {
+    serde_json::to_string(todo).unwrap_or_default()
+}
+
+pub fn import_todotxt(content: &str, projects: &[Project]) -> TodoTxtImportResult {
+    let now = Local::now().timestamp_millis();
+    let mut resolver = ProjectResolver {
+        existing: projects,
+        created: Vec::new(),
+        now,
+    };
+    let todos = content
+        .lines()
+        .filter_map(parse_line)
+        .filter(|line| !line.text.is_empty())
+        .enumerate()
+        .map(|(index, line)| {
+            let project_id = line.project.as_deref().map(|name| resolver.resolve(name));
+            let mut todo = new_todo(&line, format!("todotxt-{}-{}", now, index), project_id, now);
+            todo.order = index as f64;
+            todo
+        })
+        .collect();
+    TodoTxtImportResult {
+        projects: resolver.created,
+        todos,
+    }
+}
+
+// 同期ファイルの内容とアプリのタスクの差分
+fn diff_file(
+    content: &str,
+    previous: Option<&str>,
+    todos: &[Todo],
+    projects: &[Project],
+) -> TodoTxtChanges {
+    let now = Local::now().timestamp_millis();
+    let mut resolver = ProjectResolver {
+        existing: projects,
+        created: Vec::new(),
+        now,
+    };
+    let mut changes = TodoTxtChanges::default();
+    let mut seen: HashSet<String> = HashSet::new();
+
+    let lines = content
+        .lines()
+        .filter_map(parse_line)
+        .filter(|line| !line.text.is_empty());
+    for (index, line) in lines.enumerate() {
+        let project_id = line.project.as_deref().map(|name| resolver.resolve(name));
+        let existing = line
+            .id
+            .as_deref()
+            .and_then(|id| todos.iter().find(|t| t.id == id));
+        match existing {
+            Some(todo) => {
+                seen.insert(todo.id.clone());
+                let mut updated = todo.clone();
+                apply_line(&line, &mut updated, project_id, now);
+                if todo_fields(&updated) != todo_fields(todo) {
+                    updated.updated_at = Some(now);
+                    changes.upserted.push(updated);
+                }
+            }
+            None => {
+                let id = format!("todotxt-{}-{}", now, index);
+                changes.upserted.push(new_todo(&line, id, project_id, now));
+            }
+        }
+    }
+
+    // 前回書き出したファイルにあった行が消えていれば削除とみなす
+    if let Some(previous) = previous {
+        for id in previous.lines().filter_map(parse_line).filter_map(|l| l.id) {
+            if !seen.contains(&id) && todos.iter().any(|t| t.id == id && !t.archived) {
+                changes.deleted_ids.push(id);
+            }
+        }
+    }
+    changes.projects = resolver.created;
+    changes
+}
+
+fn save_sync_state(app: &tauri::AppHandle, sync_state: &TodoTxtSyncState) {
+    if let Err(e) = crate::store::save_json(app, SYNC_STATE_FILE, sync_state) {
+        println!("[TodoTxt Backend] 同期状態の保存失敗: {}", e);
+    }
+}
+
+pub struct TodoTxtState {
+    settings: Mutex<TodoTxtSettings>,
+    sync_state: Mutex<TodoTxtSyncState>,
+}
+
+impl TodoTxtState {
+    pub fn load(app: &tauri::AppHandle) -> Self {
+        let settings = crate::store::load_json(app, SETTINGS_FILE).unwrap_or_else(|e| {
+            println!("[TodoTxt Backend] 設定読み込み失敗: {}", e);
+            TodoTxtSettings::default()
+        });
+        let sync_state = crate::store::load_json(app, SYNC_STATE_FILE).unwrap_or_else(|e| {
+            println!("[TodoTxt Backend] 同期状態の読み込み失敗: {}", e);
+            TodoTxtSyncState::default()
+        });
+        Self {
+            settings: Mutex::new(settings),
+            sync_state: Mutex::new(sync_state),
+        }
+    }
+
+    fn settings(&self) -> TodoTxtSettings {
+        self.settings.lock().unwrap().clone()
+    }
+
+    // 同期が有効ならタスクをファイルに書き出す
+    // 取り込んでいない外部の変更がある間は、上書きで失わないよう書き出さない
+    pub fn write_file(&self, app: &tauri::AppHandle, todos: &[Todo], projects: &[Project]) {
+        let settings = self.settings();
+        if !settings.enabled || settings.path.is_empty() {
+            return;
+        }
+        let mut sync_state = self.sync_state.lock().unwrap();
+        let path = Path::new(&settings.path);
+        let content = export_todotxt(todos, projects, settings.include_completed, true);
+        let current = fs::read_to_string(path).ok();
+        if current.as_deref() == Some(content.as_str())
+            || !sync_state.can_overwrite(&settings.path, current.as_deref())
+        {
+            return;
+        }
+        let tmp_path = path.with_extension("tmp");
+        let result = fs::write(&tmp_path, &content).and_then(|_| fs::rename(&tmp_path, path));
+        if let Err(e) = result {
+            println!("[TodoTxt Backend] ファイル書き込み失敗: {}", e);
+            return;
+        }
+        sync_state.path = settings.path;
+        sync_state.last_content = Some(content);
+        save_sync_state(app, &sync_state);
+    }
+
+    // ファイルが外部で変更されていれば差分をフロントエンドに知らせる
+    fn check_file(&self, app: &tauri::AppHandle) {
+        let settings = self.settings();
+        if !settings.enabled || settings.path.is_empty() {
+            return;
+        }
+        let Ok(content) = fs::read_to_string(&settings.path) else {
+            return;
+        };
+        let mut sync_state = self.sync_state.lock().unwrap();
+        // 前回確認したときから変わっていない
+        let checked = sync_state
+            .unimported
+            .as_ref()
+            .or(sync_state.last_content.as_ref());
+        if sync_state.path == settings.path && checked == Some(&content) {
+            return;
+        }
+
+        let todo_state = app.state::<crate::todos::TodoState>();
+        let changes = sync_state.detect(
+            &settings.path,
+            &content,
+            &todo_state.all(),
+            &todo_state.projects(),
+        );
+        save_sync_state(app, &sync_state);
+        let Some(changes) = changes else {
+            return;
+        };
+        println!(
+            "[TodoTxt Backend] ファイルの変更を検知: 更新 {} 件, 削除 {} 件",
+            changes.upserted.len(),
+            changes.deleted_ids.len()
+        );
+        if let Err(e) = app.emit("todotxt-changed", &changes) {
+            println!("[TodoTxt Backend] イベント送信失敗: {}", e);
+        }
+    }
+}
+
+pub async fn run_watcher(app: tauri::AppHandle) {
+    loop {
+        app.state::<TodoTxtState>().check_file(&app);
+        tokio::time::sleep(WATCH_INTERVAL).await;
+    }
+}
+
+#[tauri::command]
+pub fn todotxt_get_settings(state: tauri::State<'_, TodoTxtState>) -> TodoTxtSettings {
+    state.settings()
+}
+
+#[tauri::command]
+pub fn todotxt_set_settings(
+    app: tauri::AppHandle,
+    state: tauri::State<'_, TodoTxtState>,
+    todos: tauri::State<'_, crate::todos::TodoState>,
+    settings: TodoTxtSettings,
+) -> Result<(), String> {
+    let settings = TodoTxtSettings {
+        path: settings.path.trim().to_string(),
+        ..settings
+    };
+    if settings.enabled {
+        if settings.path.is_empty() {
+            return Err("todo.txt のパスを入力してください".to_string());
+        }
+        let parent = Path::new(&settings.path).parent();
+        if !parent.is_some_and(|p| p.as_os_str().is_empty() || p.is_dir()) {
+            return Err(format!("フォルダが見つかりません: {}", settings.path));
+        }
+    }
+    crate::store::save_json(&app, SETTINGS_FILE, &settings)?;
+    let enabled = settings.enabled;
+    let path = settings.path.clone();
+    *state.settings.lock().unwrap() = settings;
+
+    // ファイルがまだなければ今のタスクで作る (既存のファイルは監視で取り込む)
+    if enabled && !Path::new(&path).exists() {
+        state.write_file(&app, &todos.all(), &todos.projects());
+    }
+    Ok(())
+}
+
+#[tauri::command]
+pub fn todotxt_export(
+    todos: Vec<Todo>,
+    projects: Option<Vec<Project>>,
+    include_completed: Option<bool>,
+) -> String {
+    export_todotxt(
+        &todos,
+        &projects.unwrap_or_default(),
+        include_completed.unwrap_or(true),
+        false,
+    )
+}
+
+#[tauri::command]
+pub fn todotxt_import(
+    todos: tauri::State<'_, crate::todos::TodoState>,
+    content: String,
+    projects: Option<Vec<Project>>,
+) -> TodoTxtImportResult {
+    let projects = projects.unwrap_or_else(|| todos.projects());
+    import_todotxt(&content, &projects)
+}
+
+// 起動前や、イベントを受け取る前に検知した変更を取得する
+#[tauri::command]
+pub fn todotxt_pending_changes(
+    state: tauri::State<'_, TodoTxtState>,
+    todos: tauri::State<'_, crate::todos::TodoState>,
+) -> Option<TodoTxtChanges> {
+    let settings = state.settings();
+    if !settings.enabled {
+        return None;
+    }
+    state
+        .sync_state
+        .lock()
+        .unwrap()
+        .pending(&settings.path, &todos.all(), &todos.projects())
+}
+
+// フロントエンドが変更を取り込んだら呼ぶ。以降はタスクの変更をファイルに書き出す
+#[tauri::command]
+pub fn todotxt_mark_imported(
+    app: tauri::AppHandle,
+    state: tauri::State<'_, TodoTxtState>,
+    revision: u64,
+) {
+    let mut sync_state = state.sync_state.lock().unwrap();
+    if sync_state.mark_imported(revision) {
+        save_sync_state(&app, &sync_state);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn round_trips_every_priority() {
+        for priority in 1..=4 {
+            for completed in [false, true] {
+                let todo = Todo {
+                    text: "資料を作る".to_string(),
+                    priority,
+                    completed,
+                    completed_at: completed.then_some(1_767_225_600_000),
+                    ..Default::default()
+                };
+                let line = format_line(&todo, &[], false);
+                let parsed = parse_line(&line).unwrap();
+                assert_eq!(parsed.priority, Some(priority), "{}", line);
+            }
+        }
+        let todo = Todo {
+            text: "資料を作る".to_string(),
+            ..Default::default()
+        };
+        assert_eq!(format_line(&todo, &[], false), "(D) 資料を作る");
+    }
+
+    fn date(s: &str) -> NaiveDate {
+        parse_date(s).unwrap()
+    }
+
+    fn todo(id: &str, text: &str) -> Todo {
+        Todo {
+            id: id.to_string(),
+            text: text.to_string(),
+            created_at: 1_767_225_600_000,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn parses_every_field_of_a_line() {
+        let line = parse_line("(B) 2026-01-02 資料を +仕事 作る @PC @PC due:2026-01-10 url:x id:a")
+            .unwrap();
+        assert!(!line.completed);
+        assert_eq!(line.priority, Some(2));
+        assert_eq!(line.creation_date, Some(date("2026-01-02")));
+        assert_eq!(line.text, "資料を 作る url:x");
+        assert_eq!(line.project.as_deref(), Some("仕事"));
+        assert_eq!(line.labels, vec!["PC"]);
+        assert_eq!(line.due, Some(date("2026-01-10")));
+        assert_eq!(line.id.as_deref(), Some("a"));
+
+        let line = parse_line("x 2026-01-05 2026-01-02 送る +A +B pri:A").unwrap();
+        assert!(line.completed);
+        assert_eq!(line.completion_date, Some(date("2026-01-05")));
+        assert_eq!(line.creation_date, Some(date("2026-01-02")));
+        assert_eq!(line.priority, Some(1));
+        assert_eq!(line.project.as_deref(), Some("B"));
+        assert_eq!(line.text, "送る +A");
+
+        // 行頭以外の (A) や不正な日付は本文として扱う
+        let line = parse_line("メモ (A) due:明日").unwrap();
+        assert_eq!(line.priority, None);
+        assert_eq!(line.due, None);
+        assert_eq!(line.text, "メモ (A) due:明日");
+        assert!(parse_line("   ").is_none());
+    }
+
+    #[test]
+    fn diff_detects_edits_additions_and_deletions() {
+        let todos = vec![
+            todo("a", "資料を作る"),
+            todo("b", "送る"),
+            todo("c", "読む"),
+        ];
+        let previous = export_todotxt(&todos, &[], true, true);
+        let content = "(A) 資料を直す id:a\n(D) 読む id:c\n新しいタスク +新規\n";
+
+        let changes = diff_file(content, Some(&previous), &todos, &[]);
+        assert_eq!(changes.deleted_ids, vec!["b".to_string()]);
+        assert_eq!(changes.upserted.len(), 2);
+        assert_eq!(changes.upserted[0].id, "a");
+        assert_eq!(changes.upserted[0].text, "資料を直す");
+        assert_eq!(changes.upserted[0].priority, 1);
+        assert!(changes.upserted[0].updated_at.is_some());
+        assert_eq!(changes.upserted[1].text, "新しいタスク");
+        assert_eq!(changes.projects.len(), 1);
+        assert_eq!(
+            changes.upserted[1].project_id.as_deref(),
+            Some(changes.projects[0].id.as_str())
+        );
+
+        // 前回の内容がなければ削除とはみなさない
+        let changes = diff_file(content, None, &todos, &[]);
+        assert!(changes.deleted_ids.is_empty());
+    }
+
+    #[test]
+    fn imports_lines_as_new_tasks() {
+        let projects = vec![Project {
+            id: "p1".to_string(),
+            name: "仕事 用".to_string(),
+            ..Default::default()
+        }];
+        let result = import_todotxt(
+            "(A) 資料 +仕事-用 due:2026-01-10\n\nx 2026-01-05 送る +私用 @家\n+私用 電話\n",
+            &projects,
+        );
+        assert_eq!(result.todos.len(), 3);
+        assert_eq!(result.projects.len(), 1);
+        assert_eq!(result.projects[0].name, "私用");
+
+        let first = &result.todos[0];
+        assert_eq!(first.priority, 1);
+        assert_eq!(first.project_id.as_deref(), Some("p1"));
+        assert_eq!(
+            first.due_date.and_then(local_date),
+            Some(date("2026-01-10"))
+        );
+        assert!(first.due_date_notification.is_some());
+
+        let second = &result.todos[1];
+        assert!(second.completed);
+        assert_eq!(
+            second.completed_at.and_then(local_date),
+            Some(date("2026-01-05"))
+        );
+        assert_eq!(second.labels, vec!["家"]);
+        assert_eq!(second.priority, 4);
+        // 同じ +プロジェクト は一つのプロジェクトにまとめる
+        assert_eq!(result.todos[2].project_id, second.project_id);
+        assert_eq!(result.todos[2].order, 2.0);
+    }
+
+    #[test]
+    fn keeps_external_changes_until_imported() {
+        let todos = vec![todo("a", "資料を作る")];
+        let written = export_todotxt(&todos, &[], true, true);
+        let mut state = TodoTxtSyncState {
+            path: "todo.txt".to_string(),
+            last_content: Some(written.clone()),
+            ..Default::default()
+        };
+        assert!(state.can_overwrite("todo.txt", Some(&written)));
+
+        let edited = "資料を直す id:a\n".to_string();
+        let changes = state.detect("todo.txt", &edited, &todos, &[]).unwrap();
+        assert_eq!(changes.upserted[0].text, "資料を直す");
+        // 取り込まれるまでは、ファイルが前回の確認時のままでも上書きしない
+        assert!(!state.can_overwrite("todo.txt", Some(&edited)));
+        assert!(state.detect("todo.txt", &edited, &todos, &[]).is_none());
+        assert!(!state.can_overwrite("todo.txt", Some(&edited)));
+        let pending = state.pending("todo.txt", &todos, &[]).unwrap();
+        assert_eq!(pending.revision, changes.revision);
+
+        // さらに編集されたら新しい番号で知らせ、古い番号の取り込みでは解除しない
+        let edited_again = "資料を出す id:a\n".to_string();
+        let newer = state
+            .detect("todo.txt", &edited_again, &todos, &[])
+            .unwrap();
+        assert!(newer.revision > changes.revision);
+        assert!(!state.mark_imported(changes.revision));
+        assert!(!state.can_overwrite("todo.txt", Some(&edited_again)));
+
+        assert!(state.mark_imported(newer.revision));
+        assert!(state.pending("todo.txt", &todos, &[]).is_none());
+        assert!(state.can_overwrite("todo.txt", Some(&edited_again)));
+        assert!(!state.can_overwrite("todo.txt", Some(&edited)));
+    }
+
+    #[test]
+    fn forgets_external_changes_that_were_reverted() {
+        let todos = vec![todo("a", "資料を作る")];
+        let written = export_todotxt(&todos, &[], true, true);
+        let mut state = TodoTxtSyncState {
+            path: "todo.txt".to_string(),
+            last_content: Some(written.clone()),
+            ..Default::default()
+        };
+        assert!(state
+            .detect("todo.txt", "資料を直す id:a\n", &todos, &[])
+            .is_some());
+        assert!(state.detect("todo.txt", &written, &todos, &[]).is_none());
+        assert!(state.can_overwrite("todo.txt", Some(&written)));
+
+        // 別のファイルに切り替えたら、前のファイルの内容とは比べない
+        let other = "資料を作る id:a\n送る\n";
+        let changes = state.detect("other.txt", other, &todos, &[]).unwrap();
+        assert!(changes.deleted_ids.is_empty());
+        assert_eq!(changes.upserted.len(), 1);
+        assert!(!state.can_overwrite("other.txt", Some(other)));
+    }
+}
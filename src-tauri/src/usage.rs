@@ -0,0 +1,536 @@
+// AI・検索APIの利用量台帳
+// 呼び出しごとに1行のJSONを usage-ledger.jsonl に追記し、日別・プロバイダー別に集計する
+
+use chrono::{Datelike, Local, NaiveDate, TimeZone};
+use std::collections::BTreeMap;
+use std::fs::OpenOptions;
+use std::io::Write;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+use std::time::Instant;
+
+use crate::ai::TokenUsage;
+
+const LEDGER_FILE: &str = "usage-ledger.jsonl";
+const BUDGET_FILE: &str = "usage-budget.json";
+
+// Tavily: 1クレジットあたりの概算単価 (USD)
+const TAVILY_USD_PER_CREDIT: f64 = 0.008;
+
+#[derive(serde::Serialize, serde::Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct UsageRecord {
+    pub timestamp: i64,
+    pub provider: String,
+    pub model: String,
+    #[serde(default)]
+    pub input_tokens: u64,
+    #[serde(default)]
+    pub output_tokens: u64,
+    #[serde(default)]
+    pub tavily_credits: u32,
+    pub latency_ms: u64,
+    pub success: bool,
+    #[serde(default)]
+    pub estimated_cost_usd: f64,
+}
+
+impl UsageRecord {
+    pub fn ai(
+        provider: &str,
+        model: &str,
+        input_tokens: u64,
+        output_tokens: u64,
+        latency_ms: u64,
+        success: bool,
+    ) -> Self {
+        Self {
+            timestamp: Local::now().timestamp_millis(),
+            provider: provider.to_string(),
+            model: model.to_string(),
+            input_tokens,
+            output_tokens,
+            tavily_credits: 0,
+            latency_ms,
+            success,
+            estimated_cost_usd: estimate_token_cost(model, input_tokens, output_tokens),
+        }
+    }
+
+    pub fn tavily(search_depth: &str, latency_ms: u64, success: bool) -> Self {
+        // advanced は2クレジット、basic は1クレジット消費 (失敗時は課金されない)
+        let credits = match (success, search_depth) {
+            (false, _) => 0,
+            (true, "advanced") => 2,
+            (true, _) => 1,
+        };
+        Self {
+            timestamp: Local::now().timestamp_millis(),
+            provider: "tavily".to_string(),
+            model: search_depth.to_string(),
+            input_tokens: 0,
+            output_tokens: 0,
+            tavily_credits: credits,
+            latency_ms,
+            success,
+            estimated_cost_usd: credits as f64 * TAVILY_USD_PER_CREDIT,
+        }
+    }
+
+    fn local_date(&self) -> NaiveDate {
+        Local
+            .timestamp_millis_opt(self.timestamp)
+            .single()
+            .map(|dt| dt.date_naive())
+            .unwrap_or_default()
+    }
+}
+
+// 100万トークンあたりの概算単価 (USD, 入力/出力)。一覧にないモデル (ローカルLLMなど) は0
+fn token_price_per_million(model: &str) -> (f64, f64) {
+    match model {
+        "gpt-5.2" => (1.75, 14.0),
+        "gpt-5.2-pro" => (21.0, 168.0),
+        "gpt-5-mini" => (0.25, 2.0),
+        "gpt-4.1" => (2.0, 8.0),
+        "gpt-4.1-mini" => (0.4, 1.6),
+        "gpt-4.1-nano" => (0.1, 0.4),
+        "o4-mini" => (1.1, 4.4),
+        "o3" => (2.0, 8.0),
+        "gpt-4o" => (2.5, 10.0),
+        "gpt-4o-mini" => (0.15, 0.6),
+        "claude-opus-4-5-20251101" => (5.0, 25.0),
+        "claude-sonnet-4-5-20250929" => (3.0, 15.0),
+        "claude-haiku-4-5-20251001" => (1.0, 5.0),
+        "claude-sonnet-4-20250514" => (3.0, 15.0),
+        "claude-opus-4-20250514" => (15.0, 75.0),
+        "claude-3-5-sonnet-20241022" => (3.0, 15.0),
+        "claude-3-5-haiku-20241022" => (0.8, 4.0),
+        "gemini-2.5-pro" => (1.25, 10.0),
+        "gemini-2.5-flash" => (0.3, 2.5),
+        "gemini-2.5-flash-lite" => (0.1, 0.4),
+        "gemini-2.0-flash" => (0.1, 0.4),
+        "gemini-2.0-flash-lite" => (0.075, 0.3),
+        "gemini-1.5-flash" => (0.075, 0.3),
+        "gemini-1.5-pro" => (1.25, 5.0),
+        _ => (0.0, 0.0),
+    }
+}
+
+fn estimate_token_cost(model: &str, input_tokens: u64, output_tokens: u64) -> f64 {
+    let (input_price, output_price) = token_price_per_million(model);
+    (input_tokens as f64 * input_price + output_tokens as f64 * output_price) / 1_000_000.0
+}
+
+#[derive(serde::Serialize, serde::Deserialize, Clone, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct UsageBudget {
+    #[serde(default)]
+    pub monthly_limit_usd: Option<f64>,
+}
+
+#[derive(serde::Serialize, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct UsageSummary {
+    pub date: Option<String>,
+    pub provider: String,
+    pub calls: u32,
+    pub failures: u32,
+    pub input_tokens: u64,
+    pub output_tokens: u64,
+    pub tavily_credits: u32,
+    pub estimated_cost_usd: f64,
+    pub average_latency_ms: u64,
+}
+
+impl UsageSummary {
+    fn add(&mut self, record: &UsageRecord) {
+        // 平均レイテンシは件数で重み付けして更新する
+        let total_latency = self.average_latency_ms * self.calls as u64 + record.latency_ms;
+        self.calls += 1;
+        self.average_latency_ms = total_latency / self.calls as u64;
+        if !record.success {
+            self.failures += 1;
+        }
+        self.input_tokens += record.input_tokens;
+        self.output_tokens += record.output_tokens;
+        self.tavily_credits += record.tavily_credits;
+        self.estimated_cost_usd += record.estimated_cost_usd;
+    }
+}
+
+#[derive(serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BudgetStatus {
+    pub month: String,
+    pub spent_usd: f64,
+    pub monthly_limit_usd: Option<f64>,
+    pub remaining_usd: Option<f64>,
+    pub exceeded: bool,
+}
+
+pub struct UsageState {
+    ledger_path: Option<PathBuf>,
+    records: Mutex<Vec<UsageRecord>>,
+    budget: Mutex<UsageBudget>,
+}
+
+impl UsageState {
+    pub fn load(app: &tauri::AppHandle) -> Self {
+        let ledger_path = crate::store::data_file(app, LEDGER_FILE)
+            .map_err(|e| println!("[Usage Backend] 台帳パス取得失敗: {}", e))
+            .ok();
+        let records = ledger_path
+            .as_ref()
+            .and_then(|path| std::fs::read_to_string(path).ok())
+            .map(|content| {
+                content
+                    .lines()
+                    .filter(|line| !line.trim().is_empty())
+                    .filter_map(|line| serde_json::from_str(line).ok())
+                    .collect()
+            })
+            .unwrap_or_default();
+        let budget = crate::store::load_json(app, BUDGET_FILE).unwrap_or_else(|e| {
+            println!("[Usage Backend] 予算設定読み込み失敗: {}", e);
+            UsageBudget::default()
+        });
+
+        Self {
+            ledger_path,
+            records: Mutex::new(records),
+            budget: Mutex::new(budget),
+        }
+    }
+
+    pub fn record(&self, record: UsageRecord) {
+        if let Some(path) = &self.ledger_path {
+            let line = serde_json::to_string(&record).unwrap_or_default();
+            let written = OpenOptions::new()
+                .create(true)
+                .append(true)
+                .open(path)
+                .and_then(|mut file| writeln!(file, "{}", line));
+            if let Err(e) = written {
+                println!("[Usage Backend] 台帳書き込み失敗: {}", e);
+            }
+        }
+        self.records.lock().unwrap().push(record);
+    }
+
+    fn daily_summary(&self, from: NaiveDate, to: NaiveDate) -> Vec<UsageSummary> {
+        let mut groups: BTreeMap<(NaiveDate, String), UsageSummary> = BTreeMap::new();
+
+        for record in self.records.lock().unwrap().iter() {
+            let date = record.local_date();
+            if date < from || date > to {
+                continue;
+            }
+            groups
+                .entry((date, record.provider.clone()))
+                .or_insert_with(|| UsageSummary {
+                    date: Some(date.format("%Y-%m-%d").to_string()),
+                    provider: record.provider.clone(),
+                    ..Default::default()
+                })
+                .add(record);
+        }
+
+        groups.into_values().collect()
+    }
+
+    // first_day と同じ月の記録をプロバイダー別に集計する
+    fn provider_summary(&self, first_day: NaiveDate) -> Vec<UsageSummary> {
+        let mut groups: BTreeMap<String, UsageSummary> = BTreeMap::new();
+
+        for record in self.records.lock().unwrap().iter() {
+            let date = record.local_date();
+            if date.year() != first_day.year() || date.month() != first_day.month() {
+                continue;
+            }
+            groups
+                .entry(record.provider.clone())
+                .or_insert_with(|| UsageSummary {
+                    provider: record.provider.clone(),
+                    ..Default::default()
+                })
+                .add(record);
+        }
+
+        groups.into_values().collect()
+    }
+
+    fn budget_status(&self) -> BudgetStatus {
+        let today = Local::now().date_naive();
+        let spent_usd: f64 = self
+            .records
+            .lock()
+            .unwrap()
+            .iter()
+            .filter(|record| {
+                let date = record.local_date();
+                date.year() == today.year() && date.month() == today.month()
+            })
+            .map(|record| record.estimated_cost_usd)
+            .sum();
+        let monthly_limit_usd = self.budget.lock().unwrap().monthly_limit_usd;
+
+        BudgetStatus {
+            month: today.format("%Y-%m").to_string(),
+            spent_usd,
+            monthly_limit_usd,
+            remaining_usd: monthly_limit_usd.map(|limit| (limit - spent_usd).max(0.0)),
+            exceeded: monthly_limit_usd.is_some_and(|limit| spent_usd >= limit),
+        }
+    }
+
+    // 月間予算に達していれば外部呼び出しを止める
+    pub fn ensure_within_budget(&self) -> Result<(), String> {
+        let status = self.budget_status();
+        match status.monthly_limit_usd {
+            Some(limit) if status.exceeded => Err(format!(
+                "予算超過: 今月 ({}) のAI・検索利用額 ${:.2} が月間予算 ${:.2} に達しました",
+                status.month, status.spent_usd, limit
+            )),
+            _ => Ok(()),
+        }
+    }
+}
+
+// AI呼び出し1回分の記録。失敗・キャンセルで破棄されても、それまでに届いたトークン数を記録する
+pub struct AiCallRecorder<'a> {
+    state: &'a UsageState,
+    provider: &'static str,
+    model: String,
+    started: Instant,
+    tokens: Arc<Mutex<TokenUsage>>,
+    success: bool,
+}
+
+impl<'a> AiCallRecorder<'a> {
+    pub fn new(state: &'a UsageState, provider: &'static str, model: &str) -> Self {
+        Self {
+            state,
+            provider,
+            model: model.to_string(),
+            started: Instant::now(),
+            tokens: Arc::new(Mutex::new(TokenUsage::default())),
+            success: false,
+        }
+    }
+
+    // ストリームの受信側がトークン数を書き込む
+    pub fn tokens(&self) -> Arc<Mutex<TokenUsage>> {
+        self.tokens.clone()
+    }
+
+    pub fn succeed(&mut self) {
+        self.success = true;
+    }
+}
+
+impl Drop for AiCallRecorder<'_> {
+    fn drop(&mut self) {
+        let tokens = *self.tokens.lock().unwrap();
+        self.state.record(UsageRecord::ai(
+            self.provider,
+            &self.model,
+            tokens.input_tokens,
+            tokens.output_tokens,
+            self.started.elapsed().as_millis() as u64,
+            self.success,
+        ));
+    }
+}
+
+fn parse_date(value: &str) -> Result<NaiveDate, String> {
+    NaiveDate::parse_from_str(value, "%Y-%m-%d")
+        .map_err(|_| format!("日付の形式が正しくありません (YYYY-MM-DD): {}", value))
+}
+
+// 期間内の利用量を日付×プロバイダーで集計する
+#[tauri::command]
+pub fn usage_daily_summary(
+    state: tauri::State<'_, UsageState>,
+    from: String,
+    to: String,
+) -> Result<Vec<UsageSummary>, String> {
+    Ok(state.daily_summary(parse_date(&from)?, parse_date(&to)?))
+}
+
+// 月 (YYYY-MM、省略時は今月) の利用量をプロバイダー別に集計する
+#[tauri::command]
+pub fn usage_provider_summary(
+    state: tauri::State<'_, UsageState>,
+    month: Option<String>,
+) -> Result<Vec<UsageSummary>, String> {
+    let month = month.unwrap_or_else(|| Local::now().format("%Y-%m").to_string());
+    Ok(state.provider_summary(parse_date(&format!("{}-01", month))?))
+}
+
+#[tauri::command]
+pub fn usage_get_budget(state: tauri::State<'_, UsageState>) -> BudgetStatus {
+    state.budget_status()
+}
+
+#[tauri::command]
+pub fn usage_set_budget(
+    app: tauri::AppHandle,
+    state: tauri::State<'_, UsageState>,
+    monthly_limit_usd: Option<f64>,
+) -> Result<BudgetStatus, String> {
+    if monthly_limit_usd.is_some_and(|limit| !limit.is_finite() || limit < 0.0) {
+        return Err("月間予算は0以上の数値で指定してください".to_string());
+    }
+    {
+        let mut budget = state.budget.lock().unwrap();
+        budget.monthly_limit_usd = monthly_limit_usd;
+        crate::store::save_json(&app, BUDGET_FILE, &*budget)?;
+    }
+    Ok(state.budget_status())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn state(records: Vec<UsageRecord>, monthly_limit_usd: Option<f64>) -> UsageState {
+        UsageState {
+            ledger_path: None,
+            records: Mutex::new(records),
+            budget: Mutex::new(UsageBudget { monthly_limit_usd }),
+        }
+    }
+
+    fn at(mut record: UsageRecord, date: &str) -> UsageRecord {
+        let date = parse_date(date).unwrap();
+        record.timestamp = Local
+            .from_local_datetime(&date.and_hms_opt(12, 0, 0).unwrap())
+            .unwrap()
+            .timestamp_millis();
+        record
+    }
+
+    #[test]
+    fn estimates_costs_and_credits() {
+        let record = UsageRecord::ai("openai", "gpt-4o-mini", 1_000_000, 500_000, 10, true);
+        assert!((record.estimated_cost_usd - 0.45).abs() < 1e-9);
+        // 単価のないモデル (ローカルLLM) は0
+        assert_eq!(
+            UsageRecord::ai("local", "llama3", 1000, 1000, 10, true).estimated_cost_usd,
+            0.0
+        );
+
+        assert_eq!(UsageRecord::tavily("advanced", 10, true).tavily_credits, 2);
+        assert_eq!(UsageRecord::tavily("basic", 10, true).tavily_credits, 1);
+        let failed = UsageRecord::tavily("advanced", 10, false);
+        assert_eq!((failed.tavily_credits, failed.estimated_cost_usd), (0, 0.0));
+    }
+
+    #[test]
+    fn summarizes_by_day_and_provider() {
+        let state = state(
+            vec![
+                at(
+                    UsageRecord::ai("claude", "x", 10, 20, 100, true),
+                    "2025-03-01",
+                ),
+                at(
+                    UsageRecord::ai("claude", "x", 5, 0, 300, false),
+                    "2025-03-01",
+                ),
+                at(UsageRecord::ai("openai", "x", 1, 2, 50, true), "2025-03-01"),
+                at(UsageRecord::tavily("basic", 40, true), "2025-03-02"),
+                at(UsageRecord::ai("claude", "x", 7, 7, 10, true), "2025-04-01"),
+            ],
+            None,
+        );
+
+        let daily = state.daily_summary(
+            parse_date("2025-03-01").unwrap(),
+            parse_date("2025-03-31").unwrap(),
+        );
+        let rows: Vec<_> = daily
+            .iter()
+            .map(|s| (s.date.as_deref().unwrap(), s.provider.as_str(), s.calls))
+            .collect();
+        assert_eq!(
+            rows,
+            [
+                ("2025-03-01", "claude", 2),
+                ("2025-03-01", "openai", 1),
+                ("2025-03-02", "tavily", 1),
+            ]
+        );
+        let claude = &daily[0];
+        assert_eq!(claude.failures, 1);
+        assert_eq!((claude.input_tokens, claude.output_tokens), (15, 20));
+        assert_eq!(claude.average_latency_ms, 200);
+        assert_eq!(daily[2].tavily_credits, 1);
+
+        let monthly = state.provider_summary(parse_date("2025-04-01").unwrap());
+        assert_eq!(monthly.len(), 1);
+        assert_eq!(
+            (monthly[0].provider.as_str(), monthly[0].calls),
+            ("claude", 1)
+        );
+        assert!(monthly[0].date.is_none());
+    }
+
+    #[test]
+    fn stops_calls_over_the_monthly_budget() {
+        let spent = UsageRecord::ai("claude", "claude-sonnet-4-20250514", 0, 1_000_000, 10, true);
+        // 先月分は今月の予算に含めない
+        let last_month = {
+            let mut record = spent.clone();
+            record.timestamp -= 40 * 24 * 60 * 60 * 1000;
+            record
+        };
+
+        let within = state(vec![spent.clone(), last_month.clone()], Some(20.0));
+        let status = within.budget_status();
+        assert!((status.spent_usd - 15.0).abs() < 1e-9);
+        assert_eq!(status.remaining_usd, Some(5.0));
+        assert!(!status.exceeded);
+        assert!(within.ensure_within_budget().is_ok());
+
+        let exceeded = state(vec![spent.clone(), spent], Some(20.0));
+        assert_eq!(exceeded.budget_status().remaining_usd, Some(0.0));
+        assert!(exceeded.ensure_within_budget().is_err());
+
+        assert!(state(vec![last_month], None).ensure_within_budget().is_ok());
+    }
+
+    #[test]
+    fn records_tokens_received_before_a_failure() {
+        let state = state(Vec::new(), None);
+        {
+            let recorder = AiCallRecorder::new(&state, "claude", "claude-sonnet-4-20250514");
+            let tokens = recorder.tokens();
+            *tokens.lock().unwrap() = TokenUsage {
+                input_tokens: 30,
+                output_tokens: 12,
+            };
+        }
+        {
+            let mut recorder = AiCallRecorder::new(&state, "openai", "gpt-4o");
+            recorder.tokens().lock().unwrap().output_tokens = 5;
+            recorder.succeed();
+        }
+
+        let records = state.records.lock().unwrap();
+        let rows: Vec<_> = records
+            .iter()
+            .map(|r| {
+                (
+                    r.provider.as_str(),
+                    r.input_tokens,
+                    r.output_tokens,
+                    r.success,
+                )
+            })
+            .collect();
+        assert_eq!(rows, [("claude", 30, 12, false), ("openai", 0, 5, true)]);
+        assert!(records[0].estimated_cost_usd > 0.0);
+    }
+}
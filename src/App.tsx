@@ -7,7 +7,7 @@ import { usePlanning, INTRO_SAMPLE_PLAN } from './hooks/usePlanning'
 import { KanaeReminderSettings } from './components/settings/KanaeReminderSettings'
 import { BoardView } from './components/BoardView'
 import Sidebar from './components/Sidebar'
-import { decomposeTask, getKanaeConfig, startReminderService, stopReminderService, type ReminderTask, type Subtask, type NotificationResult } from './services/reminder'
+import { clearUpcomingReminders, decomposeTask, getKanaeConfig, startReminderService, stopReminderService, type ReminderTask, type Subtask, type NotificationResult } from './services/reminder'
 import { invoke } from '@tauri-apps/api/core'
 import { listen } from '@tauri-apps/api/event'
 import { open } from '@tauri-apps/plugin-shell'
@@ -16,13 +16,11 @@ import { parseNaturalLanguage, getNextRecurrenceDate, formatRecurrence, type Rec
 import { importICSToTodos, type ImportStats } from './lib/icsParser'
 // generatePlan, PlanTask, PlanResult は usePlanning から取得
 import { searchWithTavily, formatSearchResultsForPrompt, getTavilyApiKey } from './lib/tavily'
-import { getApiKey as getOpenAiApiKey } from './lib/openai'
-import { getClaudeApiKey } from './lib/claude'
-import { getGeminiApiKey } from './lib/gemini'
+import { hasAiApiKey, migrateLegacyApiKeys, refreshAiKeyStatus } from './lib/ai'
 
 // AIのAPIキーが少なくとも1つ設定されているかチェック
 const hasAnyAiApiKey = (): boolean => {
-  return !!(getOpenAiApiKey() || getClaudeApiKey() || getGeminiApiKey())
+  return hasAiApiKey('openai') || hasAiApiKey('claude') || hasAiApiKey('gemini')
 }
 
 // Types
@@ -170,6 +168,9 @@ export default function App() {
     setDueDateYearlyMonth,
     dueDateYearlyDay,
     setDueDateYearlyDay,
+    dueDateTimeZone,
+    setDueDateTimeZone,
+    timeZoneOptions,
     // formatLocalDateTime は useCalendar 内部でのみ使用
     formatDueDate,
     isDueDateOverdue,
@@ -380,6 +381,17 @@ export default function App() {
     }
   }, [])
 
+  // APIキーはバックエンドだけが持つ (以前 Webview に保存したキーは移してから消す)
+  useEffect(() => {
+    if (!isTauri()) return
+
+    migrateLegacyApiKeys()
+      .then(refreshAiKeyStatus)
+      .catch((error) => {
+        console.error('[AI] API key migration failed:', error)
+      })
+  }, [])
+
   // Listen for task-added event from quick-add window
   useEffect(() => {
     if (!isTauri()) return
@@ -394,6 +406,91 @@ export default function App() {
     }
   }, [])
 
+  // 端末のタイムゾーンが変わったら、バックエンドで換算し直した期限を反映する
+  // 送信待ちだった通知はバックエンドで取り消されているので、新しい期限で送り直せるようにする
+  // 反映したことを知らせるまでバックエンドは変更を保留する (起動直後の変更は起動時に問い合わせる)
+  useEffect(() => {
+    if (!isTauri()) return
+
+    type ZoneChangeEvent = {
+      changes: { id: string; dueDate: number; reminderRescheduled: boolean }[]
+      revision: number
+    }
+    let appliedRevision = 0
+    const applyChange = (event: ZoneChangeEvent) => {
+      if (event.revision <= appliedRevision) return
+      appliedRevision = event.revision
+      const changes = new Map(event.changes.map(change => [change.id, change]))
+      changes.forEach(change => {
+        if (change.reminderRescheduled) clearUpcomingReminders(change.id)
+      })
+      if (changes.size > 0) {
+        setTodos(prevTodos => prevTodos.map(todo => {
+          const change = changes.get(todo.id)
+          if (!change) return todo
+          const dueDateNotification = change.reminderRescheduled && todo.dueDateNotification
+            ? { ...todo.dueDateNotification, notifiedAt: null, followUpCount: 0 }
+            : todo.dueDateNotification
+          return { ...todo, dueDate: change.dueDate, dueDateNotification }
+        }))
+      }
+      invoke('timezone_mark_applied', { revision: event.revision }).catch((error) => {
+        console.error('[Timezone] mark applied failed:', error)
+      })
+    }
+
+    const unlisten = listen<ZoneChangeEvent>('timezone-changed', (event) => applyChange(event.payload))
+    invoke<ZoneChangeEvent | null>('timezone_pending_change')
+      .then(event => { if (event) applyChange(event) })
+      .catch(() => {})
+
+    return () => {
+      unlisten.then(fn => fn())
+    }
+  }, [])
+
+  // todo.txt が外部で編集されたら取り込む
+  // 取り込んだことを知らせるまで、バックエンドはファイルを上書きしない (起動前の変更は起動時に問い合わせる)
+  useEffect(() => {
+    if (!isTauri()) return
+
+    type TodoTxtChanges = {
+      projects: Project[]
+      upserted: Todo[]
+      deletedIds: string[]
+      revision: number
+    }
+    let importedRevision = 0
+    const importChanges = (changes: TodoTxtChanges) => {
+      if (changes.revision <= importedRevision) return
+      importedRevision = changes.revision
+      if (changes.projects.length > 0) {
+        setProjects(prev => [...prev, ...changes.projects.filter(p => !prev.some(q => q.id === p.id))])
+      }
+      const upserted = new Map(changes.upserted.map(todo => [todo.id, todo]))
+      const deleted = new Set(changes.deletedIds)
+      setTodos(prevTodos => {
+        const next = prevTodos
+          .filter(todo => !deleted.has(todo.id))
+          .map(todo => upserted.get(todo.id) ?? todo)
+        const added = changes.upserted.filter(todo => !prevTodos.some(t => t.id === todo.id))
+        return [...next, ...added]
+      })
+      invoke('todotxt_mark_imported', { revision: changes.revision }).catch((error) => {
+        console.error('[TodoTxt] mark imported failed:', error)
+      })
+    }
+
+    const unlisten = listen<TodoTxtChanges>('todotxt-changed', (event) => importChanges(event.payload))
+    invoke<TodoTxtChanges | null>('todotxt_pending_changes')
+      .then(changes => { if (changes) importChanges(changes) })
+      .catch(() => {})
+
+    return () => {
+      unlisten.then(fn => fn())
+    }
+  }, [])
+
   // Auto-restore from backup if localStorage is empty
   useEffect(() => {
     const autoRestore = async () => {
@@ -1649,7 +1746,7 @@ export default function App() {
   }
 
   const setDueDate = () => {
-    setDueDateFromHook(updateTodosWithHistory, setShowDueDateModal)
+    void setDueDateFromHook(updateTodosWithHistory, setShowDueDateModal)
   }
 
   const clearDueDate = (todoId: string) => {
@@ -3623,6 +3720,22 @@ END:VCALENDAR`
                 onChange={e => setDueDateInput(e.target.value)}
               />
             )}
+            {dueDateRecurrenceType === 'none' && isTauri() && (
+              <div className="recurrence-option">
+                <label className="recurrence-option-label">🌐 タイムゾーン</label>
+                <select
+                  className="recurrence-select"
+                  value={dueDateTimeZone}
+                  onChange={e => setDueDateTimeZone(e.target.value)}
+                >
+                  <option value="">端末の時刻</option>
+                  <option value="floating">移動先でも同じ時刻</option>
+                  {timeZoneOptions.map(tz => (
+                    <option key={tz} value={tz}>{tz}</option>
+                  ))}
+                </select>
+              </div>
+            )}
 
             {/* 繰り返しタスクの場合：タスク入力画面と同様のUI */}
             {dueDateRecurrenceType !== 'none' && (
@@ -1,9 +1,7 @@
 import { useState, useEffect, useCallback } from 'react'
 import { open } from '@tauri-apps/plugin-shell'
 import { showNotification } from '@/lib/utils'
-import { getClaudeApiKey, setClaudeApiKey, clearClaudeApiKey } from '@/lib/claude'
-import { setGeminiApiKey, getGeminiApiKey, clearGeminiApiKey } from '@/lib/gemini'
-import { getApiKey as getOpenAiApiKey, setApiKey as setOpenAiApiKey, clearApiKey as clearOpenAiApiKey } from '@/lib/openai'
+import { clearAiApiKey, hasAiApiKey, refreshAiKeyStatus, setAiApiKey, type AiProvider } from '@/lib/ai'
 import { getTavilyApiKey, setTavilyApiKey, clearTavilyApiKey } from '@/lib/tavily'
 import { setDiscordSettings, testDiscordConnection, sendTestDiscordDM } from '@/lib/discord'
 import {
@@ -26,6 +24,13 @@ import {
   type CustomPersona,
 } from '@/lib/kanaePersona'
 
+// バックエンドに保存済みのAPIキー (キー自体は読み出せない)
+const readConfiguredKeys = (): Record<AiProvider, boolean> => ({
+  openai: hasAiApiKey('openai'),
+  claude: hasAiApiKey('claude'),
+  gemini: hasAiApiKey('gemini'),
+})
+
 interface KanaeReminderSettingsProps {
   onClose?: () => void
   onSaved?: () => void
@@ -39,6 +44,7 @@ export function KanaeReminderSettings({ onClose, onSaved, embedded = false, save
   const [geminiApiKey, setLocalGeminiApiKey] = useState('')
   const [openaiApiKey, setLocalOpenaiApiKey] = useState('')
   const [tavilyApiKey, setLocalTavilyApiKey] = useState('')
+  const [configuredKeys, setConfiguredKeys] = useState<Record<AiProvider, boolean>>(readConfiguredKeys)
   const [discordBotToken, setDiscordBotToken] = useState('')
   const [discordUserId, setDiscordUserId] = useState('')
   const [testResult, setTestResult] = useState<'success' | 'error' | null>(null)
@@ -60,9 +66,9 @@ export function KanaeReminderSettings({ onClose, onSaved, embedded = false, save
   useEffect(() => {
     const savedConfig = getKanaeConfig()
     setLocalConfig(savedConfig)
-    setLocalClaudeApiKey(savedConfig.claudeApiKey || getClaudeApiKey() || '')
-    setLocalGeminiApiKey(savedConfig.geminiApiKey || getGeminiApiKey() || '')
-    setLocalOpenaiApiKey(savedConfig.openaiApiKey || getOpenAiApiKey() || '')
+    refreshAiKeyStatus()
+      .then(() => setConfiguredKeys(readConfiguredKeys()))
+      .catch(() => {})
     setLocalTavilyApiKey(getTavilyApiKey() || '')
     setDiscordBotToken(savedConfig.discordBotToken || '')
     setDiscordUserId(savedConfig.discordUserId || '')
@@ -168,23 +174,33 @@ export function KanaeReminderSettings({ onClose, onSaved, embedded = false, save
     }
   }
 
+  // 保存済みのAPIキーを削除する
+  const handleClearApiKey = (provider: AiProvider) => {
+    clearAiApiKey(provider)
+      .then(() => setConfiguredKeys(readConfiguredKeys()))
+      .catch((error) => {
+        console.error('[AI] clear API key failed:', error)
+      })
+  }
+
   const handleSave = () => {
-    // APIキーが空の場合は削除、値がある場合は保存
-    if (claudeApiKey.trim()) {
-      setClaudeApiKey(claudeApiKey.trim())
-    } else {
-      clearClaudeApiKey()
-    }
-    if (geminiApiKey.trim()) {
-      setGeminiApiKey(geminiApiKey.trim())
-    } else {
-      clearGeminiApiKey()
-    }
-    if (openaiApiKey.trim()) {
-      setOpenAiApiKey(openaiApiKey.trim())
-    } else {
-      clearOpenAiApiKey()
+    // AIのAPIキーはバックエンドに保存する (空欄なら保存済みのキーをそのまま使う)
+    const enteredKeys: [AiProvider, string][] = [
+      ['claude', claudeApiKey.trim()],
+      ['gemini', geminiApiKey.trim()],
+      ['openai', openaiApiKey.trim()],
+    ]
+    for (const [provider, key] of enteredKeys) {
+      if (!key) continue
+      setAiApiKey(provider, key)
+        .then(() => setConfiguredKeys(readConfiguredKeys()))
+        .catch((error) => {
+          console.error('[AI] save API key failed:', error)
+        })
     }
+    setLocalClaudeApiKey('')
+    setLocalGeminiApiKey('')
+    setLocalOpenaiApiKey('')
     if (tavilyApiKey.trim()) {
       setTavilyApiKey(tavilyApiKey.trim())
     } else {
@@ -192,7 +208,7 @@ export function KanaeReminderSettings({ onClose, onSaved, embedded = false, save
     }
 
     // 使えるAPIが一つもない場合は警告
-    const hasAnyApiKey = claudeApiKey.trim() || geminiApiKey.trim() || openaiApiKey.trim()
+    const hasAnyApiKey = enteredKeys.some(([provider, key]) => key || configuredKeys[provider])
     if (!hasAnyApiKey) {
       setTestResult('error')
       setTestMessage('AI APIキーが1つも設定されていません。タスク分解やリマインダー機能を使用するには、少なくとも1つのAPIキーを設定してください。')
@@ -206,9 +222,6 @@ export function KanaeReminderSettings({ onClose, onSaved, embedded = false, save
     })
     setKanaeConfig({
       ...config,
-      claudeApiKey: claudeApiKey.trim(),
-      geminiApiKey: geminiApiKey.trim(),
-      openaiApiKey: openaiApiKey.trim(),
       discordBotToken: discordBotToken.trim(),
       discordUserId: discordUserId.trim(),
       personaType: 'preset',
@@ -442,10 +455,15 @@ export function KanaeReminderSettings({ onClose, onSaved, embedded = false, save
           <input
             type="password"
             className="api-key-input"
-            placeholder="sk-..."
+            placeholder={configuredKeys.openai ? '設定済み（変更する場合のみ入力）' : 'sk-...'}
             value={openaiApiKey}
             onChange={(e) => setLocalOpenaiApiKey(e.target.value)}
           />
+          {configuredKeys.openai && (
+            <button type="button" className="api-key-link" onClick={() => handleClearApiKey('openai')}>
+              保存済みのキーを削除
+            </button>
+          )}
           <button type="button" className="api-key-link" onClick={() => openExternalLink('https://platform.openai.com/api-keys')}>
             OpenAI Platformで取得 →
           </button>
@@ -455,10 +473,15 @@ export function KanaeReminderSettings({ onClose, onSaved, embedded = false, save
           <input
             type="password"
             className="api-key-input"
-            placeholder="sk-ant-api..."
+            placeholder={configuredKeys.claude ? '設定済み（変更する場合のみ入力）' : 'sk-ant-api...'}
             value={claudeApiKey}
             onChange={(e) => setLocalClaudeApiKey(e.target.value)}
           />
+          {configuredKeys.claude && (
+            <button type="button" className="api-key-link" onClick={() => handleClearApiKey('claude')}>
+              保存済みのキーを削除
+            </button>
+          )}
           <button type="button" className="api-key-link" onClick={() => openExternalLink('https://console.anthropic.com/settings/keys')}>
             Anthropic Consoleで取得 →
           </button>
@@ -468,10 +491,15 @@ export function KanaeReminderSettings({ onClose, onSaved, embedded = false, save
           <input
             type="password"
             className="api-key-input"
-            placeholder="AIza..."
+            placeholder={configuredKeys.gemini ? '設定済み（変更する場合のみ入力）' : 'AIza...'}
             value={geminiApiKey}
             onChange={(e) => setLocalGeminiApiKey(e.target.value)}
           />
+          {configuredKeys.gemini && (
+            <button type="button" className="api-key-link" onClick={() => handleClearApiKey('gemini')}>
+              保存済みのキーを削除
+            </button>
+          )}
           <button type="button" className="api-key-link" onClick={() => openExternalLink('https://aistudio.google.com/app/apikey')}>
             Google AI Studioで取得 →
           </button>
@@ -1,4 +1,6 @@
 import { useState } from 'react'
+import { invoke } from '@tauri-apps/api/core'
+import { isTauri } from '../lib/utils'
 import type { RecurrencePattern } from '../lib/parseNaturalLanguage'
 import type { Todo } from '@/types/todo'
 
@@ -20,6 +22,8 @@ export function useCalendar() {
   const [dueDateMonthlyDay, setDueDateMonthlyDay] = useState(1) // 毎月の日付
   const [dueDateYearlyMonth, setDueDateYearlyMonth] = useState(1) // 毎年の月
   const [dueDateYearlyDay, setDueDateYearlyDay] = useState(1) // 毎年の日付
+  const [dueDateTimeZone, setDueDateTimeZone] = useState('') // 期限のタイムゾーン（空は端末の時刻、'floating' は出張先でも同じ時刻）
+  const [timeZoneOptions, setTimeZoneOptions] = useState<string[]>([]) // 選択できるIANAタイムゾーン名
 
   // 日付関連のユーティリティ関数
   const formatLocalDateTime = (date: Date): string => {
@@ -93,9 +97,14 @@ export function useCalendar() {
 
   const openDueDateModal = (todoId: string, todos: Todo[], setShowDueDateModal: (show: boolean) => void) => {
     const todo = todos.find(t => t.id === todoId)
+    if (isTauri() && timeZoneOptions.length === 0) {
+      invoke<string[]>('timezone_list').then(setTimeZoneOptions).catch(() => {})
+    }
+    setDueDateTimeZone(todo?.dueTimeZone ?? '')
     if (todo?.dueDate) {
       const date = new Date(todo.dueDate)
-      setDueDateInput(formatLocalDateTime(date))
+      // タイムゾーン指定のある期限は、そのタイムゾーンの壁時計時刻で編集する
+      setDueDateInput(todo.dueTimeZone && todo.dueLocal ? todo.dueLocal.slice(0, 16) : formatLocalDateTime(date))
       // 時間を抽出（HH:MM形式）
       const hours = String(date.getHours()).padStart(2, '0')
       const minutes = String(date.getMinutes()).padStart(2, '0')
@@ -142,13 +151,15 @@ export function useCalendar() {
     setShowDueDateModal(true)
   }
 
-  const setDueDate = (
+  const setDueDate = async (
     updateTodosWithHistory: (updater: (prev: Todo[]) => Todo[]) => void,
     setShowDueDateModal: (show: boolean) => void
   ) => {
     if (!dueDateTodoId) return
 
     let timestamp: number
+    let dueTimeZone: string | null = null
+    let dueLocal: string | null = null
     const now = Date.now()
     const [hours, minutes] = dueDateRecurrenceTime.split(':').map(Number)
 
@@ -156,6 +167,17 @@ export function useCalendar() {
       // 通常タスク：datetime-localから取得
       if (!dueDateInput) return
       timestamp = new Date(dueDateInput).getTime()
+      // タイムゾーン指定があれば、その壁時計時刻をバックエンドで換算する (DSTの切り替えも考慮される)
+      if (dueDateTimeZone && isTauri()) {
+        dueLocal = dueDateInput.length === 16 ? `${dueDateInput}:00` : dueDateInput
+        try {
+          timestamp = await invoke<number>('timezone_resolve_due', { dueLocal, timeZone: dueDateTimeZone })
+          dueTimeZone = dueDateTimeZone
+        } catch (error) {
+          console.error('[Timezone] resolve due failed:', error)
+          dueLocal = null
+        }
+      }
     } else if (dueDateRecurrenceType === 'daily') {
       // 毎日：今日の指定時刻（過ぎていれば明日）
       const date = new Date()
@@ -215,6 +237,8 @@ export function useCalendar() {
       todo.id === dueDateTodoId ? {
         ...todo,
         dueDate: timestamp,
+        dueTimeZone,
+        dueLocal,
         recurrence,
         dueDateNotification: {
           enabled: dueDateNotifyEnabled,
@@ -237,7 +261,7 @@ export function useCalendar() {
     setShowDueDateModal: (show: boolean) => void
   ) => {
     updateTodosWithHistory(prev => prev.map(todo =>
-      todo.id === todoId ? { ...todo, dueDate: null, dueDateNotification: null, recurrence: null } : todo
+      todo.id === todoId ? { ...todo, dueDate: null, dueTimeZone: null, dueLocal: null, dueDateNotification: null, recurrence: null } : todo
     ))
     setShowDueDateModal(false)
     setDueDateTodoId(null)
@@ -274,6 +298,9 @@ export function useCalendar() {
     setDueDateYearlyMonth,
     dueDateYearlyDay,
     setDueDateYearlyDay,
+    dueDateTimeZone,
+    setDueDateTimeZone,
+    timeZoneOptions,
 
     // 日付関連の関数
     formatLocalDateTime,
@@ -0,0 +1,133 @@
+import { Channel, invoke } from '@tauri-apps/api/core'
+
+// AIプロバイダーの呼び出しはバックエンド (ai_stream_completion) が行う
+// APIキーはバックエンドだけが持ち、Webviewには設定済みかどうかだけを残す
+
+export type AiProvider = 'openai' | 'claude' | 'gemini'
+
+const KEY_STATUS_STORAGE_KEY = 'ai-key-status'
+const CONFIG_STORAGE_KEY = 'kanae-reminder-config'
+// 以前 Webview に保存していたAPIキーの場所
+const LEGACY_STORAGE_KEYS: Record<AiProvider, string> = {
+  openai: 'openai-api-key',
+  claude: 'claude-api-key',
+  gemini: 'gemini-api-key',
+}
+const LEGACY_CONFIG_KEYS: Record<AiProvider, string> = {
+  openai: 'openaiApiKey',
+  claude: 'claudeApiKey',
+  gemini: 'geminiApiKey',
+}
+const PROVIDERS: AiProvider[] = ['openai', 'claude', 'gemini']
+
+type KeyStatus = Partial<Record<AiProvider, boolean>>
+
+function loadKeyStatus(): KeyStatus {
+  try {
+    return JSON.parse(localStorage.getItem(KEY_STATUS_STORAGE_KEY) || '{}') as KeyStatus
+  } catch {
+    return {}
+  }
+}
+
+let keyStatus: KeyStatus = loadKeyStatus()
+
+function updateKeyStatus(status: KeyStatus): void {
+  keyStatus = { ...keyStatus, ...status }
+  localStorage.setItem(KEY_STATUS_STORAGE_KEY, JSON.stringify(keyStatus))
+}
+
+export function hasAiApiKey(provider: AiProvider): boolean {
+  return keyStatus[provider] === true
+}
+
+export async function refreshAiKeyStatus(): Promise<void> {
+  updateKeyStatus(await invoke<KeyStatus>('ai_get_key_status'))
+}
+
+export async function setAiApiKey(provider: AiProvider, apiKey: string): Promise<void> {
+  await invoke('ai_set_api_key', { provider, apiKey })
+  updateKeyStatus({ [provider]: apiKey.trim() !== '' })
+}
+
+export async function clearAiApiKey(provider: AiProvider): Promise<void> {
+  await invoke('ai_clear_api_key', { provider })
+  updateKeyStatus({ [provider]: false })
+}
+
+// 以前 localStorage に保存していたAPIキーをバックエンドに移し、Webviewからは消す
+export async function migrateLegacyApiKeys(): Promise<void> {
+  let config: Record<string, unknown> = {}
+  try {
+    config = JSON.parse(localStorage.getItem(CONFIG_STORAGE_KEY) || '{}')
+  } catch {
+    // ignore
+  }
+  let configChanged = false
+  for (const provider of PROVIDERS) {
+    const fromConfig = config[LEGACY_CONFIG_KEYS[provider]]
+    const legacy = localStorage.getItem(LEGACY_STORAGE_KEYS[provider])
+      || (typeof fromConfig === 'string' ? fromConfig : '')
+    if (legacy.trim()) {
+      await setAiApiKey(provider, legacy)
+    }
+    localStorage.removeItem(LEGACY_STORAGE_KEYS[provider])
+    if (LEGACY_CONFIG_KEYS[provider] in config) {
+      delete config[LEGACY_CONFIG_KEYS[provider]]
+      configChanged = true
+    }
+  }
+  if (configChanged) {
+    localStorage.setItem(CONFIG_STORAGE_KEY, JSON.stringify(config))
+  }
+}
+
+export interface AiCompletionOptions {
+  model?: string
+  maxTokens: number
+  temperature?: number
+  // JSONのみを返させる
+  jsonMode?: boolean
+  // 生成途中のテキストを受け取る
+  onDelta?: (text: string) => void
+}
+
+type AiStreamEvent =
+  | { event: 'delta'; data: { text: string } }
+  | { event: 'started' | 'finished' | 'cancelled' | 'error'; data?: unknown }
+
+// バックエンド経由で生成し、全文を返す (失敗時はバックエンドのエラーメッセージで reject する)
+export async function requestAiCompletion(
+  provider: AiProvider,
+  systemPrompt: string,
+  userPrompt: string,
+  options: AiCompletionOptions
+): Promise<string> {
+  const onEvent = new Channel<AiStreamEvent>()
+  onEvent.onmessage = (message) => {
+    if (message.event === 'delta') {
+      options.onDelta?.(message.data.text)
+    }
+  }
+  const completion = await invoke<{ text: string }>('ai_stream_completion', {
+    requestId: crypto.randomUUID(),
+    request: {
+      provider,
+      model: options.model ?? null,
+      systemPrompt,
+      userPrompt,
+      maxTokens: options.maxTokens,
+      temperature: options.temperature ?? null,
+      jsonMode: options.jsonMode ?? false,
+    },
+    onEvent,
+  })
+  return completion.text
+}
+
+// 応答からJSONを取り出す (```json ... ``` で囲まれている場合は中身を使う)
+export function extractJson(content: string): string | null {
+  const codeBlock = content.match(/```(?:json)?\s*([\s\S]*?)```/)
+  const body = codeBlock ? codeBlock[1].trim() : content
+  return body.match(/\{[\s\S]*\}/)?.[0] ?? null
+}
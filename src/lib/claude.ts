@@ -1,4 +1,3 @@
-import { fetch } from '@tauri-apps/plugin-http'
 import {
   buildKanaeSystemPrompt,
   buildKanaeReminderUserPrompt,
@@ -8,8 +7,7 @@ import {
 } from './kanaePersona'
 import { DECOMPOSE_SYSTEM_PROMPT, PLAN_SYSTEM_PROMPT } from './prompts'
 import type { PlanResult, PlanError } from './openai'
-
-const CLAUDE_API_URL = 'https://api.anthropic.com/v1/messages'
+import { extractJson, hasAiApiKey, requestAiCompletion } from './ai'
 
 // モデル取得関数（循環参照を避けるためlocalStorageから直接取得）
 function getClaudeModel(): string {
@@ -31,16 +29,9 @@ export interface ReminderMessage {
   message: string
 }
 
-export function getClaudeApiKey(): string | null {
-  return localStorage.getItem('claude-api-key')
-}
-
-export function setClaudeApiKey(key: string): void {
-  localStorage.setItem('claude-api-key', key)
-}
-
-export function clearClaudeApiKey(): void {
-  localStorage.removeItem('claude-api-key')
+// APIキーはバックエンドに保存する (ここでは設定済みかどうかだけを見る)
+export function hasClaudeApiKey(): boolean {
+  return hasAiApiKey('claude')
 }
 
 async function requestClaudeMessage(
@@ -48,37 +39,16 @@ async function requestClaudeMessage(
   userPrompt: string,
   maxTokens: number
 ): Promise<string | null> {
-  const apiKey = getClaudeApiKey()
-
-  if (!apiKey) {
+  if (!hasClaudeApiKey()) {
     return null
   }
 
   try {
-    const response = await fetch(CLAUDE_API_URL, {
-      method: 'POST',
-      headers: {
-        'Content-Type': 'application/json',
-        'x-api-key': apiKey,
-        'anthropic-version': '2023-06-01',
-        'anthropic-dangerous-direct-browser-access': 'true',
-      },
-      body: JSON.stringify({
-        model: getClaudeModel(),
-        max_tokens: maxTokens,
-        system: systemPrompt,
-        messages: [
-          { role: 'user', content: userPrompt },
-        ],
-      }),
+    const content = await requestAiCompletion('claude', systemPrompt, userPrompt, {
+      model: getClaudeModel(),
+      maxTokens,
     })
-
-    if (!response.ok) {
-      return null
-    }
-
-    const data = await response.json()
-    return data.content[0]?.text || null
+    return content || null
   } catch {
     return null
   }
@@ -133,9 +103,7 @@ function isPlanError(result: PlanResult | PlanError): result is PlanError {
 }
 
 export async function generatePlanClaude(goal: string, targetDays: number, webSearchContext?: string): Promise<PlanResult> {
-  const apiKey = getClaudeApiKey()
-
-  if (!apiKey) {
+  if (!hasClaudeApiKey()) {
     throw new Error('Claude APIキーが設定されていません')
   }
 
@@ -170,42 +138,19 @@ ${webSearchContext}`
 この目標を達成するための計画をJSON形式で作成してください。`
 
   try {
-    const response = await fetch(CLAUDE_API_URL, {
-      method: 'POST',
-      headers: {
-        'Content-Type': 'application/json',
-        'x-api-key': apiKey,
-        'anthropic-version': '2023-06-01',
-        'anthropic-dangerous-direct-browser-access': 'true',
-      },
-      body: JSON.stringify({
-        model: getClaudeModel(),
-        max_tokens: 2048,
-        system: PLAN_SYSTEM_PROMPT,
-        messages: [
-          { role: 'user', content: userPrompt },
-        ],
-      }),
+    const content = await requestAiCompletion('claude', PLAN_SYSTEM_PROMPT, userPrompt, {
+      model: getClaudeModel(),
+      maxTokens: 2048,
+    }).catch((error) => {
+      throw new Error(typeof error === 'string' ? error : '計画の生成に失敗しました')
     })
 
-    if (!response.ok) {
-      const error = await response.json()
-      throw new Error(error.error?.message || '計画の生成に失敗しました')
-    }
-
-    const data = await response.json()
-    const content = data.content[0]?.text
-
-    if (!content) {
-      throw new Error('AIからの応答がありません')
-    }
-
-    const jsonMatch = content.match(/\{[\s\S]*\}/)
-    if (!jsonMatch) {
+    const json = extractJson(content)
+    if (!json) {
       throw new Error('無効な応答形式')
     }
 
-    const result = JSON.parse(jsonMatch[0]) as PlanResult | PlanError
+    const result = JSON.parse(json) as PlanResult | PlanError
 
     if (isPlanError(result)) {
       throw new Error(result.error)
@@ -227,9 +172,7 @@ ${webSearchContext}`
 }
 
 export async function decomposeTaskClaude(taskTitle: string, webSearchContext?: string): Promise<DecomposeResult> {
-  const apiKey = getClaudeApiKey()
-
-  if (!apiKey) {
+  if (!hasClaudeApiKey()) {
     throw new Error('Claude APIキーが設定されていません')
   }
 
@@ -251,43 +194,20 @@ ${webSearchContext}`
 サブタスクをJSON形式で返してください。`
 
   try {
-    const response = await fetch(CLAUDE_API_URL, {
-      method: 'POST',
-      headers: {
-        'Content-Type': 'application/json',
-        'x-api-key': apiKey,
-        'anthropic-version': '2023-06-01',
-        'anthropic-dangerous-direct-browser-access': 'true',
-      },
-      body: JSON.stringify({
-        model: getClaudeModel(),
-        max_tokens: 1024,
-        system: DECOMPOSE_SYSTEM_PROMPT,
-        messages: [
-          { role: 'user', content: userPrompt },
-        ],
-      }),
+    const content = await requestAiCompletion('claude', DECOMPOSE_SYSTEM_PROMPT, userPrompt, {
+      model: getClaudeModel(),
+      maxTokens: 1024,
+    }).catch((error) => {
+      throw new Error(typeof error === 'string' ? error : 'タスクの分解に失敗しました')
     })
 
-    if (!response.ok) {
-      const error = await response.json()
-      throw new Error(error.error?.message || 'タスクの分解に失敗しました')
-    }
-
-    const data = await response.json()
-    const content = data.content[0]?.text
-
-    if (!content) {
-      throw new Error('AIからの応答がありません')
-    }
-
     // JSONを抽出（```json ... ``` でラップされている可能性を考慮）
-    const jsonMatch = content.match(/\{[\s\S]*\}/)
-    if (!jsonMatch) {
+    const json = extractJson(content)
+    if (!json) {
       throw new Error('無効な応答形式')
     }
 
-    const result = JSON.parse(jsonMatch[0]) as DecomposeResult
+    const result = JSON.parse(json) as DecomposeResult
 
     if (!result.subtasks || !Array.isArray(result.subtasks)) {
       throw new Error('無効な応答形式')
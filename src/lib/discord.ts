@@ -1,4 +1,5 @@
 import { fetch } from '@tauri-apps/plugin-http'
+import { invoke } from '@tauri-apps/api/core'
 
 const DISCORD_API_URL = 'https://discord.com/api/v10'
 
@@ -41,10 +42,21 @@ export function getDiscordSettings(): DiscordSettings | null {
 export function setDiscordSettings(settings: DiscordSettings): void {
   console.log('[Discord] setDiscordSettings called')
   localStorage.setItem('discord-settings', JSON.stringify(settings))
+  syncDiscordSettings(settings).catch((e) => {
+    console.error('[Discord] Failed to sync settings:', e)
+  })
 }
 
 export function clearDiscordSettings(): void {
   localStorage.removeItem('discord-settings')
+  syncDiscordSettings({ botToken: '', userId: '', enabled: false }).catch((e) => {
+    console.error('[Discord] Failed to sync settings:', e)
+  })
+}
+
+// DMの送信はバックエンドのアウトボックスが行うため、設定をバックエンドにも渡す
+async function syncDiscordSettings(settings: DiscordSettings): Promise<void> {
+  await invoke('discord_set_settings', { settings })
 }
 
 // DMチャンネルを作成または取得
@@ -86,13 +98,22 @@ async function createDMChannel(botToken: string, userId: string): Promise<string
   }
 }
 
+// アウトボックスへの追加結果 (duplicate: 同じキーの通知を送信済み)
+export interface OutboxEnqueueResult {
+  status: 'queued' | 'duplicate'
+  id: string
+}
+
 // DMを送信（Embed形式）
+// 送信はバックエンドのアウトボックスに任せる (オフライン時は再送され、同じ idempotencyKey の通知は重複しない)
 export async function sendDiscordDM(message: string, options?: {
+  taskId?: string
   taskTitle?: string
   dueDate?: Date | null
   isOverdue?: boolean
   type?: 'reminder' | 'morning' | 'noon' | 'evening' | 'dueDate'
-}): Promise<void> {
+  idempotencyKey?: string
+}): Promise<OutboxEnqueueResult['status']> {
   console.log('[Discord] sendDiscordDM called')
   const settings = getDiscordSettings()
 
@@ -108,93 +129,78 @@ export async function sendDiscordDM(message: string, options?: {
     throw new Error('Discord Bot TokenまたはユーザーIDが設定されていません')
   }
 
-  try {
-    console.log('[Discord] sendDiscordDM - creating DM channel...')
-    const channelId = await createDMChannel(botToken, userId)
-
-    // Embed色を決定
-    const getEmbedColor = () => {
-      if (options?.isOverdue) return 0xED4245 // 赤
-      switch (options?.type) {
-        case 'morning': return 0x57F287 // 緑
-        case 'noon': return 0xFEE75C // 黄色
-        case 'evening': return 0x9B59B6 // 紫
-        default: return 0x5865F2 // 青
-      }
+  // Embed色を決定
+  const getEmbedColor = () => {
+    if (options?.isOverdue) return 0xED4245 // 赤
+    switch (options?.type) {
+      case 'morning': return 0x57F287 // 緑
+      case 'noon': return 0xFEE75C // 黄色
+      case 'evening': return 0x9B59B6 // 紫
+      default: return 0x5865F2 // 青
     }
+  }
 
-    // Embed作成
-    const embed: DiscordEmbed = {
-      description: message,
-      color: getEmbedColor(),
-      timestamp: new Date().toISOString(),
-      footer: { text: 'Calm Todo' },
-    }
+  // Embed作成
+  const embed: DiscordEmbed = {
+    description: message,
+    color: getEmbedColor(),
+    timestamp: new Date().toISOString(),
+    footer: { text: 'Calm Todo' },
+  }
 
-    // タイトル設定
-    if (options?.type === 'morning') {
-      embed.title = '🌅 おはようございます'
-    } else if (options?.type === 'noon') {
-      embed.title = '☀️ お昼です'
-    } else if (options?.type === 'evening') {
-      embed.title = '🌙 お疲れ様でした'
-    } else if (options?.type === 'dueDate') {
-      embed.title = options?.isOverdue ? '⚠️ 期限切れタスク' : '📅 期日のお知らせ'
-    } else if (options?.type === 'reminder') {
-      embed.title = options?.isOverdue ? '⏰ リマインダー（期限切れ）' : '⏰ リマインダー'
-    }
+  // タイトル設定
+  if (options?.type === 'morning') {
+    embed.title = '🌅 おはようございます'
+  } else if (options?.type === 'noon') {
+    embed.title = '☀️ お昼です'
+  } else if (options?.type === 'evening') {
+    embed.title = '🌙 お疲れ様でした'
+  } else if (options?.type === 'dueDate') {
+    embed.title = options?.isOverdue ? '⚠️ 期限切れタスク' : '📅 期日のお知らせ'
+  } else if (options?.type === 'reminder') {
+    embed.title = options?.isOverdue ? '⏰ リマインダー（期限切れ）' : '⏰ リマインダー'
+  }
 
-    // タスク情報をフィールドに追加
-    if (options?.taskTitle) {
-      embed.fields = embed.fields || []
-      embed.fields.push({ name: 'タスク', value: options.taskTitle, inline: true })
-      if (options.dueDate) {
-        const dateStr = options.dueDate.toLocaleString('ja-JP', {
-          month: 'short',
-          day: 'numeric',
-          hour: '2-digit',
-          minute: '2-digit'
-        })
-        embed.fields.push({ name: '期日', value: dateStr, inline: true })
-      }
+  // タスク情報をフィールドに追加
+  if (options?.taskTitle) {
+    embed.fields = embed.fields || []
+    embed.fields.push({ name: 'タスク', value: options.taskTitle, inline: true })
+    if (options.dueDate) {
+      const dateStr = options.dueDate.toLocaleString('ja-JP', {
+        month: 'short',
+        day: 'numeric',
+        hour: '2-digit',
+        minute: '2-digit'
+      })
+      embed.fields.push({ name: '期日', value: dateStr, inline: true })
     }
+  }
 
-    console.log('[Discord] sendDiscordDM - sending embed to channel:', channelId)
-    const response = await fetch(`${DISCORD_API_URL}/channels/${channelId}/messages`, {
-      method: 'POST',
-      headers: {
-        'Content-Type': 'application/json',
-        Authorization: `Bot ${botToken}`,
+  try {
+    // 以前の設定がバックエンドに届いていない場合に備えて送信前にも渡す
+    await syncDiscordSettings(settings)
+    const result = await invoke<OutboxEnqueueResult>('outbox_enqueue', {
+      message: {
+        channel: { type: 'discord' },
+        taskId: options?.taskId ?? null,
+        kind: options?.type ?? 'message',
+        idempotencyKey: options?.idempotencyKey ?? null,
+        payload: { embeds: [embed] },
       },
-      body: JSON.stringify({
-        embeds: [embed],
-      }),
     })
-
-    console.log('[Discord] sendDiscordDM - response status:', response.status)
-
-    if (!response.ok) {
-      const errorText = await response.text()
-      console.error('[Discord] sendDiscordDM - error response:', errorText)
-      try {
-        const error = JSON.parse(errorText)
-        throw new Error(error.message || `DMの送信に失敗しました (${response.status})`)
-      } catch {
-        throw new Error(`DMの送信に失敗しました (${response.status}): ${errorText}`)
-      }
-    }
-
-    console.log('[Discord] sendDiscordDM - success!')
+    console.log(`[Discord] sendDiscordDM - ${result.status}:`, result.id)
+    return result.status
   } catch (error) {
     console.error('[Discord] sendDiscordDM - error:', error)
     if (error instanceof Error) {
       throw error
     }
-    throw new Error('Discord通知の送信に失敗しました')
+    throw new Error(typeof error === 'string' ? error : 'Discord通知の送信に失敗しました')
   }
 }
 
 // テストメッセージ送信（Embed形式）
+// 設定画面で結果をすぐ確認できるよう、アウトボックスを通さず直接送る
 export async function sendTestDiscordDM(): Promise<void> {
   console.log('[Discord] sendTestDiscordDM called')
   const settings = getDiscordSettings()
@@ -1,4 +1,3 @@
-import { fetch } from '@tauri-apps/plugin-http'
 import {
   buildKanaeSystemPrompt,
   buildKanaeReminderUserPrompt,
@@ -8,8 +7,7 @@ import {
 } from './kanaePersona'
 import { DECOMPOSE_SYSTEM_PROMPT, PLAN_SYSTEM_PROMPT } from './prompts'
 import type { PlanResult, PlanError } from './openai'
-
-const GEMINI_API_URL = 'https://generativelanguage.googleapis.com/v1beta/models'
+import { extractJson, hasAiApiKey, requestAiCompletion } from './ai'
 
 // モデル取得関数（循環参照を避けるためlocalStorageから直接取得）
 function getGeminiModel(): string {
@@ -27,107 +25,28 @@ function getGeminiModel(): string {
   return 'gemini-2.0-flash'
 }
 
-// Gemini API Key管理
-export function getGeminiApiKey(): string | null {
-  return localStorage.getItem('gemini-api-key')
-}
-
-export function setGeminiApiKey(key: string): void {
-  localStorage.setItem('gemini-api-key', key)
-}
-
-export function clearGeminiApiKey(): void {
-  localStorage.removeItem('gemini-api-key')
-}
-
-// Gemini APIリクエスト
-interface GeminiContent {
-  role: 'user' | 'model'
-  parts: { text: string }[]
-}
-
-interface GeminiRequest {
-  contents: GeminiContent[]
-  systemInstruction?: {
-    parts: { text: string }[]
-  }
-  generationConfig?: {
-    temperature?: number
-    maxOutputTokens?: number
-    topP?: number
-    topK?: number
-  }
-}
-
-interface GeminiResponse {
-  candidates?: {
-    content: {
-      parts: { text: string }[]
-    }
-  }[]
-  error?: {
-    message: string
-  }
+// APIキーはバックエンドに保存する (ここでは設定済みかどうかだけを見る)
+export function hasGeminiApiKey(): boolean {
+  return hasAiApiKey('gemini')
 }
 
 async function callGeminiAPI(
   systemPrompt: string,
-  userPrompt: string,
-  apiKey?: string
+  userPrompt: string
 ): Promise<string> {
-  const key = apiKey || getGeminiApiKey()
-  if (!key) {
+  if (!hasGeminiApiKey()) {
     throw new Error('Gemini APIキーが設定されていません')
   }
 
-  const request: GeminiRequest = {
-    contents: [
-      {
-        role: 'user',
-        parts: [{ text: userPrompt }],
-      },
-    ],
-    systemInstruction: {
-      parts: [{ text: systemPrompt }],
-    },
-    generationConfig: {
-      temperature: 0.8,
-      maxOutputTokens: 256,
-      topP: 0.9,
-      topK: 40,
-    },
-  }
-
   console.log('[Gemini] Calling API...')
-  const response = await fetch(
-    `${GEMINI_API_URL}/${getGeminiModel()}:generateContent?key=${key}`,
-    {
-      method: 'POST',
-      headers: {
-        'Content-Type': 'application/json',
-      },
-      body: JSON.stringify(request),
-    }
-  )
-
-  console.log('[Gemini] Response status:', response.status)
-
-  if (!response.ok) {
-    const errorText = await response.text()
-    console.error('[Gemini] API error:', errorText)
-    throw new Error(`Gemini API error: ${response.status}`)
-  }
-
-  const data: GeminiResponse = await response.json()
-
-  if (data.error) {
-    throw new Error(data.error.message)
-  }
-
-  const text = data.candidates?.[0]?.content?.parts?.[0]?.text
-  if (!text) {
-    throw new Error('Gemini APIからの応答が空です')
-  }
+  const text = await requestAiCompletion('gemini', systemPrompt, userPrompt, {
+    model: getGeminiModel(),
+    maxTokens: 256,
+    temperature: 0.8,
+  }).catch((error) => {
+    console.error('[Gemini] API error:', error)
+    throw new Error(typeof error === 'string' ? error : 'Gemini APIエラー')
+  })
 
   console.log('[Gemini] Generated message:', text)
   return text.trim()
@@ -179,32 +98,6 @@ export async function generateCustomPersonaMessageGemini(
   return await callGeminiAPI(systemPrompt, userPrompt)
 }
 
-// 接続テスト
-export async function testGeminiConnection(): Promise<boolean> {
-  const apiKey = getGeminiApiKey()
-  if (!apiKey) {
-    throw new Error('Gemini APIキーが設定されていません')
-  }
-
-  try {
-    const response = await fetch(
-      `${GEMINI_API_URL}?key=${apiKey}`,
-      {
-        method: 'GET',
-        headers: {
-          'Content-Type': 'application/json',
-        },
-      }
-    )
-
-    console.log('[Gemini] Connection test status:', response.status)
-    return response.ok
-  } catch (error) {
-    console.error('[Gemini] Connection test failed:', error)
-    throw error
-  }
-}
-
 export interface Subtask {
   title: string
   priority: 'high' | 'medium' | 'low'
@@ -220,9 +113,7 @@ function isPlanError(result: PlanResult | PlanError): result is PlanError {
 }
 
 export async function generatePlanGemini(goal: string, targetDays: number, webSearchContext?: string): Promise<PlanResult> {
-  const apiKey = getGeminiApiKey()
-
-  if (!apiKey) {
+  if (!hasGeminiApiKey()) {
     throw new Error('Gemini APIキーが設定されていません')
   }
 
@@ -257,81 +148,28 @@ ${webSearchContext}`
 この目標を達成するための計画をJSON形式で作成してください。`
 
   try {
-    const request: GeminiRequest = {
-      contents: [
-        {
-          role: 'user',
-          parts: [{ text: userPrompt }],
-        },
-      ],
-      systemInstruction: {
-        parts: [{ text: PLAN_SYSTEM_PROMPT }],
-      },
-      generationConfig: {
-        temperature: 0.5,
-        maxOutputTokens: 16384,  // 推論トークンを消費するため大きめに設定
-        topP: 0.9,
-        topK: 40,
-      },
-    }
-
     const model = getGeminiModel()
     console.log('[Gemini Plan] Using model:', model)
-    console.log('[Gemini Plan] Request:', JSON.stringify(request, null, 2))
-
-    const response = await fetch(
-      `${GEMINI_API_URL}/${model}:generateContent?key=${apiKey}`,
-      {
-        method: 'POST',
-        headers: {
-          'Content-Type': 'application/json',
-        },
-        body: JSON.stringify(request),
-      }
-    )
 
-    console.log('[Gemini Plan] Response status:', response.status)
-
-    if (!response.ok) {
-      const errorText = await response.text()
-      console.error('[Gemini Plan] API error:', errorText)
-      throw new Error(`Gemini API error: ${response.status}`)
-    }
-
-    const data: GeminiResponse = await response.json()
-    console.log('[Gemini Plan] Full response:', JSON.stringify(data, null, 2))
-
-    if (data.error) {
-      console.error('[Gemini Plan] Error in response:', data.error)
-      throw new Error(data.error.message)
-    }
-
-    const content = data.candidates?.[0]?.content?.parts?.[0]?.text
+    const content = await requestAiCompletion('gemini', PLAN_SYSTEM_PROMPT, userPrompt, {
+      model,
+      maxTokens: 16384,  // 推論トークンを消費するため大きめに設定
+      temperature: 0.5,
+    }).catch((error) => {
+      console.error('[Gemini Plan] API error:', error)
+      throw new Error(typeof error === 'string' ? error : '計画の生成に失敗しました')
+    })
     console.log('[Gemini Plan] Raw content:', content)
 
-    if (!content) {
-      console.error('[Gemini Plan] No content found. candidates:', data.candidates)
-      throw new Error('AIからの応答がありません')
-    }
-
     // ```json ... ``` で囲まれている場合は中身を抽出
-    let jsonContent = content
-    const codeBlockMatch = content.match(/```(?:json)?\s*([\s\S]*?)```/)
-    if (codeBlockMatch) {
-      jsonContent = codeBlockMatch[1].trim()
-      console.log('[Gemini Plan] Extracted from code block')
-    }
-
-    const jsonMatch = jsonContent.match(/\{[\s\S]*\}/)
-    console.log('[Gemini Plan] JSON match result:', jsonMatch ? jsonMatch[0].substring(0, 200) + '...' : 'null')
-
-    if (!jsonMatch) {
+    const json = extractJson(content)
+    if (!json) {
       console.error('[Gemini Plan] No JSON found in content')
       throw new Error('無効な応答形式')
     }
 
     console.log('[Gemini Plan] Attempting to parse JSON...')
-    const result = JSON.parse(jsonMatch[0]) as PlanResult | PlanError
+    const result = JSON.parse(json) as PlanResult | PlanError
     console.log('[Gemini Plan] Parsed result:', JSON.stringify(result, null, 2).substring(0, 500) + '...')
 
     if (isPlanError(result)) {
@@ -356,9 +194,7 @@ ${webSearchContext}`
 }
 
 export async function decomposeTaskGemini(taskTitle: string, webSearchContext?: string): Promise<DecomposeResult> {
-  const apiKey = getGeminiApiKey()
-
-  if (!apiKey) {
+  if (!hasGeminiApiKey()) {
     throw new Error('Gemini APIキーが設定されていません')
   }
 
@@ -380,66 +216,23 @@ ${webSearchContext}`
 サブタスクをJSON形式で返してください。`
 
   try {
-    const request: GeminiRequest = {
-      contents: [
-        {
-          role: 'user',
-          parts: [{ text: userPrompt }],
-        },
-      ],
-      systemInstruction: {
-        parts: [{ text: DECOMPOSE_SYSTEM_PROMPT }],
-      },
-      generationConfig: {
-        temperature: 0.3,
-        maxOutputTokens: 8192,  // 推論トークンを消費するため大きめに設定
-        topP: 0.9,
-        topK: 40,
-      },
-    }
-
     console.log('[Gemini] Decomposing task...')
-    const response = await fetch(
-      `${GEMINI_API_URL}/${getGeminiModel()}:generateContent?key=${apiKey}`,
-      {
-        method: 'POST',
-        headers: {
-          'Content-Type': 'application/json',
-        },
-        body: JSON.stringify(request),
-      }
-    )
-
-    if (!response.ok) {
-      const errorText = await response.text()
-      console.error('[Gemini] API error:', errorText)
-      throw new Error(`Gemini API error: ${response.status}`)
-    }
-
-    const data: GeminiResponse = await response.json()
-
-    if (data.error) {
-      throw new Error(data.error.message)
-    }
-
-    const content = data.candidates?.[0]?.content?.parts?.[0]?.text
-    if (!content) {
-      throw new Error('AIからの応答がありません')
-    }
+    const content = await requestAiCompletion('gemini', DECOMPOSE_SYSTEM_PROMPT, userPrompt, {
+      model: getGeminiModel(),
+      maxTokens: 8192,  // 推論トークンを消費するため大きめに設定
+      temperature: 0.3,
+    }).catch((error) => {
+      console.error('[Gemini] API error:', error)
+      throw new Error(typeof error === 'string' ? error : 'タスクの分解に失敗しました')
+    })
 
     // ```json ... ``` で囲まれている場合は中身を抽出
-    let jsonContent = content
-    const codeBlockMatch = content.match(/```(?:json)?\s*([\s\S]*?)```/)
-    if (codeBlockMatch) {
-      jsonContent = codeBlockMatch[1].trim()
-    }
-
-    const jsonMatch = jsonContent.match(/\{[\s\S]*\}/)
-    if (!jsonMatch) {
+    const json = extractJson(content)
+    if (!json) {
       throw new Error('無効な応答形式')
     }
 
-    const result = JSON.parse(jsonMatch[0]) as DecomposeResult
+    const result = JSON.parse(json) as DecomposeResult
 
     if (!result.subtasks || !Array.isArray(result.subtasks)) {
       throw new Error('無効な応答形式')
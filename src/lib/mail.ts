@@ -0,0 +1,19 @@
+import { invoke } from '@tauri-apps/api/core'
+import type { OutboxEnqueueResult } from './discord'
+
+// タスクのリマインダーをメールで送る (送信はバックエンドのアウトボックス経由)
+// メール通知が無効なら null を返す
+export async function sendReminderMail(
+  taskId: string,
+  kind: string,
+  message: string,
+  idempotencyKey?: string
+): Promise<OutboxEnqueueResult['status'] | null> {
+  const result = await invoke<OutboxEnqueueResult | null>('mail_send_reminder', {
+    taskId,
+    kind,
+    message,
+    idempotencyKey: idempotencyKey ?? null,
+  })
+  return result?.status ?? null
+}
@@ -0,0 +1,18 @@
+import { invoke } from '@tauri-apps/api/core'
+
+// 設定済みのWebhook (Slack / ntfy / Mattermost / 任意のWebhook) に通知する
+// 送信先ごとの条件 (種類・ラベル・優先度) と再送・重複防止はバックエンドが扱い、キューに入れた件数を返す
+export async function notifyWebhooks(
+  kind: string,
+  title: string,
+  message: string,
+  options?: { taskId?: string; idempotencyKey?: string }
+): Promise<number> {
+  return invoke<number>('notifier_notify', {
+    kind,
+    title,
+    message,
+    taskId: options?.taskId ?? null,
+    idempotencyKey: options?.idempotencyKey ?? null,
+  })
+}
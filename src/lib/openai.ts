@@ -1,4 +1,3 @@
-import { fetch } from '@tauri-apps/plugin-http'
 import {
   buildKanaeSystemPrompt,
   buildKanaeReminderUserPrompt,
@@ -7,8 +6,7 @@ import {
   getFallbackMorningGreeting,
 } from './kanaePersona'
 import { DECOMPOSE_SYSTEM_PROMPT, PLAN_SYSTEM_PROMPT } from './prompts'
-
-const OPENAI_API_URL = 'https://api.openai.com/v1/chat/completions'
+import { hasAiApiKey, requestAiCompletion } from './ai'
 
 // モデル取得関数（循環参照を避けるためlocalStorageから直接取得）
 function getOpenAIModel(): string {
@@ -36,16 +34,9 @@ export interface DecomposeResult {
   subtasks: Subtask[]
 }
 
-export function getApiKey(): string | null {
-  return localStorage.getItem('openai-api-key')
-}
-
-export function setApiKey(key: string): void {
-  localStorage.setItem('openai-api-key', key)
-}
-
-export function clearApiKey(): void {
-  localStorage.removeItem('openai-api-key')
+// APIキーはバックエンドに保存する (ここでは設定済みかどうかだけを見る)
+export function hasApiKey(): boolean {
+  return hasAiApiKey('openai')
 }
 
 // GPT-5系など新しいモデルはmax_completion_tokensを使用
@@ -58,43 +49,21 @@ async function requestOpenAIMessage(
   userPrompt: string,
   maxTokens: number
 ): Promise<string | null> {
-  const apiKey = getApiKey()
-
-  if (!apiKey) {
+  if (!hasApiKey()) {
     return null
   }
 
   const model = getOpenAIModel()
   // GPT-5系は推論トークンを消費するため、より大きな上限が必要
   const adjustedTokens = isNewModelFormat(model) ? maxTokens * 4 : maxTokens
-  const tokenParam = isNewModelFormat(model)
-    ? { max_completion_tokens: adjustedTokens }
-    : { max_tokens: adjustedTokens }
 
   try {
-    const response = await fetch(OPENAI_API_URL, {
-      method: 'POST',
-      headers: {
-        'Content-Type': 'application/json',
-        Authorization: 'Bearer ' + apiKey,
-      },
-      body: JSON.stringify({
-        model,
-        temperature: 0.6,
-        ...tokenParam,
-        messages: [
-          { role: 'system', content: systemPrompt },
-          { role: 'user', content: userPrompt },
-        ],
-      }),
+    const content = await requestAiCompletion('openai', systemPrompt, userPrompt, {
+      model,
+      maxTokens: adjustedTokens,
+      temperature: 0.6,
     })
-
-    if (!response.ok) {
-      return null
-    }
-
-    const data = await response.json()
-    return data.choices[0]?.message?.content || null
+    return content || null
   } catch {
     return null
   }
@@ -195,9 +164,7 @@ function isPlanError(result: PlanResult | PlanError): result is PlanError {
 }
 
 export async function generatePlan(goal: string, targetDays: number, webSearchContext?: string): Promise<PlanResult> {
-  const apiKey = getApiKey()
-
-  if (!apiKey) {
+  if (!hasApiKey()) {
     throw new Error('OpenAI APIキーが設定されていません')
   }
 
@@ -233,54 +200,23 @@ ${webSearchContext}`
 
   const model = getOpenAIModel()
   // GPT-5系は推論トークンを消費するため、より大きな上限が必要
-  const tokenParam = isNewModelFormat(model)
-    ? { max_completion_tokens: 16384 }
-    : { max_tokens: 2048 }
+  const maxTokens = isNewModelFormat(model) ? 16384 : 2048
 
   console.log('[OpenAI Plan] Using model:', model)
-  console.log('[OpenAI Plan] Token param:', tokenParam)
+  console.log('[OpenAI Plan] Max tokens:', maxTokens)
 
   try {
-    const requestBody = {
+    const content = await requestAiCompletion('openai', PLAN_SYSTEM_PROMPT, userPrompt, {
       model,
+      maxTokens,
       temperature: 0.5,
-      ...tokenParam,
-      response_format: { type: 'json_object' },
-      messages: [
-        { role: 'system', content: PLAN_SYSTEM_PROMPT },
-        { role: 'user', content: userPrompt },
-      ],
-    }
-    console.log('[OpenAI Plan] Request body:', JSON.stringify(requestBody, null, 2))
-
-    const response = await fetch(OPENAI_API_URL, {
-      method: 'POST',
-      headers: {
-        'Content-Type': 'application/json',
-        Authorization: 'Bearer ' + apiKey,
-      },
-      body: JSON.stringify(requestBody),
-    })
-
-    console.log('[OpenAI Plan] Response status:', response.status)
-
-    if (!response.ok) {
-      const error = await response.json()
+      jsonMode: true,
+    }).catch((error) => {
       console.error('[OpenAI Plan] Error response:', error)
-      throw new Error(error.error?.message || '計画の生成に失敗しました')
-    }
-
-    const data = await response.json()
-    console.log('[OpenAI Plan] Full response data:', JSON.stringify(data, null, 2))
-
-    const content = data.choices?.[0]?.message?.content
+      throw new Error(typeof error === 'string' ? error : '計画の生成に失敗しました')
+    })
     console.log('[OpenAI Plan] Extracted content:', content)
 
-    if (!content) {
-      console.error('[OpenAI Plan] No content found. choices:', data.choices)
-      throw new Error('AIからの応答がありません')
-    }
-
     const result = JSON.parse(content) as PlanResult | PlanError
 
     if (isPlanError(result)) {
@@ -304,9 +240,7 @@ ${webSearchContext}`
 }
 
 export async function decomposeTask(taskTitle: string, webSearchContext?: string): Promise<DecomposeResult> {
-  const apiKey = getApiKey()
-
-  if (!apiKey) {
+  if (!hasApiKey()) {
     throw new Error('OpenAI APIキーが設定されていません')
   }
 
@@ -329,41 +263,18 @@ ${webSearchContext}`
 
   const model = getOpenAIModel()
   // GPT-5系は推論トークンを消費するため、より大きな上限が必要
-  const tokenParam = isNewModelFormat(model)
-    ? { max_completion_tokens: 8192 }
-    : { max_tokens: 1024 }
+  const maxTokens = isNewModelFormat(model) ? 8192 : 1024
 
   try {
-    const response = await fetch(OPENAI_API_URL, {
-      method: 'POST',
-      headers: {
-        'Content-Type': 'application/json',
-        Authorization: 'Bearer ' + apiKey,
-      },
-      body: JSON.stringify({
-        model,
-        temperature: 0.3,
-        ...tokenParam,
-        response_format: { type: 'json_object' },
-        messages: [
-          { role: 'system', content: DECOMPOSE_SYSTEM_PROMPT },
-          { role: 'user', content: userPrompt },
-        ],
-      }),
+    const content = await requestAiCompletion('openai', DECOMPOSE_SYSTEM_PROMPT, userPrompt, {
+      model,
+      maxTokens,
+      temperature: 0.3,
+      jsonMode: true,
+    }).catch((error) => {
+      throw new Error(typeof error === 'string' ? error : 'タスクの分解に失敗しました')
     })
 
-    if (!response.ok) {
-      const error = await response.json()
-      throw new Error(error.error?.message || 'タスクの分解に失敗しました')
-    }
-
-    const data = await response.json()
-    const content = data.choices[0]?.message?.content
-
-    if (!content) {
-      throw new Error('AIからの応答がありません')
-    }
-
     const result = JSON.parse(content) as DecomposeResult
 
     if (!result.subtasks || !Array.isArray(result.subtasks)) {
@@ -7,8 +7,8 @@
  * "来週金曜 レポート提出 #仕事" → { text: "レポート提出", dueDate: 来週金曜, labels: ["仕事"] }
  */
 
-import { fetch } from '@tauri-apps/plugin-http'
-import { getApiKey } from './openai'
+import { requestAiCompletion } from './ai'
+import { hasApiKey } from './openai'
 
 export type Priority = 1 | 2 | 3 | 4  // P1=最高, P4=最低
 export type Timeframe = 'today' | 'week' | 'month'
@@ -30,7 +30,6 @@ export interface ParsedTask {
   estimatedMinutes: number | null
 }
 
-const OPENAI_API_URL = 'https://api.openai.com/v1/chat/completions'
 
 const NLP_SYSTEM_PROMPT = `あなたはタスク入力の解析アシスタントです。ユーザーの自然言語入力を解析し、構造化されたタスク情報を抽出します。
 
@@ -88,10 +87,8 @@ JSON以外のテキストを含めないでください。`
  * GPT APIを使用してテキストから自然言語パターンをパースする
  */
 export async function parseNaturalLanguage(input: string): Promise<ParsedTask> {
-  const apiKey = getApiKey()
-
   // APIキーがない場合はフォールバック（ローカルパース）
-  if (!apiKey) {
+  if (!hasApiKey()) {
     console.log('[NLP] APIキーなし、ローカルフォールバック使用')
     return parseLocalFallback(input)
   }
@@ -107,32 +104,19 @@ export async function parseNaturalLanguage(input: string): Promise<ParsedTask> {
 タスク情報をJSON形式で返してください。`
 
   try {
-    const response = await fetch(OPENAI_API_URL, {
-      method: 'POST',
-      headers: {
-        'Content-Type': 'application/json',
-        Authorization: 'Bearer ' + apiKey,
-      },
-      body: JSON.stringify({
+    let content: string
+    try {
+      content = await requestAiCompletion('openai', NLP_SYSTEM_PROMPT, userPrompt, {
         model: 'gpt-4o-mini',
+        maxTokens: 512,
         temperature: 0.1,
-        max_tokens: 512,
-        response_format: { type: 'json_object' },
-        messages: [
-          { role: 'system', content: NLP_SYSTEM_PROMPT },
-          { role: 'user', content: userPrompt },
-        ],
-      }),
-    })
-
-    if (!response.ok) {
-      console.warn('[NLP] GPT API error:', response.status, 'フォールバック使用')
+        jsonMode: true,
+      })
+    } catch (error) {
+      console.warn('[NLP] GPT API error:', error, 'フォールバック使用')
       return parseLocalFallback(input)
     }
 
-    const data = await response.json()
-    const content = data.choices[0]?.message?.content
-
     if (!content) {
       console.warn('[NLP] GPT応答なし、フォールバック使用')
       return parseLocalFallback(input)
@@ -69,6 +69,17 @@ export function loadProjects(): Project[] {
 
 export function saveProjects(projects: Project[]) {
   localStorage.setItem(PROJECTS_KEY, JSON.stringify(projects))
+  syncToBackend('sync_projects', { projects })
+}
+
+// バックエンドにタスク・プロジェクトの写しを送る
+// 通知・検索・フィルタ・各種同期などのバックエンドの機能はこの写しを使う
+function syncToBackend(command: 'sync_todos' | 'sync_projects', args: Record<string, unknown>) {
+  import('@tauri-apps/api/core').then(({ invoke }) => {
+    invoke(command, args).catch((error) => {
+      console.error(`[Storage] ${command} failed:`, error)
+    })
+  }).catch(() => {})
 }
 
 // Activity Log
@@ -237,6 +248,9 @@ export function loadTodos(): Todo[] {
         karmaAwarded: t.karmaAwarded ?? t.completed,  // 既存の完了済みタスクはkarmaAwarded=true
         archived: t.archived ?? false,
         archivedAt: t.archivedAt ?? null,
+        updatedAt: t.updatedAt ?? null,
+        dueTimeZone: t.dueTimeZone ?? null,
+        dueLocal: t.dueLocal ?? null,
       }
     })
   } catch {
@@ -244,9 +258,36 @@ export function loadTodos(): Todo[] {
   }
 }
 
+// 前回保存したときの各タスクの内容 (updatedAt を除く) と updatedAt
+let savedTodos: Map<string, { content: string; updatedAt: number | null }> | null = null
+
+const todoContent = (todo: Todo): string => JSON.stringify({ ...todo, updatedAt: undefined })
+
+// 内容が変わったタスクに updatedAt を付ける (CalDAV同期で手元の変更を見分けるのに使う)
+// 取り込みなどで updatedAt 自体が変わっている場合はその値のまま
+// state のオブジェクトにも残るよう直接書き換える (表示には使わない値なので再描画は不要)
+function stampUpdatedAt(todos: Todo[]) {
+  const previous = savedTodos ?? new Map(
+    loadTodos().map((t) => [t.id, { content: todoContent(t), updatedAt: t.updatedAt ?? null }])
+  )
+  const now = Date.now()
+  const next = new Map<string, { content: string; updatedAt: number | null }>()
+  for (const todo of todos) {
+    const content = todoContent(todo)
+    const before = previous.get(todo.id)
+    if (before && before.content !== content && (todo.updatedAt ?? null) === before.updatedAt) {
+      todo.updatedAt = now
+    }
+    next.set(todo.id, { content, updatedAt: todo.updatedAt ?? null })
+  }
+  savedTodos = next
+}
+
 export function saveTodos(todos: Todo[]) {
+  stampUpdatedAt(todos)
   const json = JSON.stringify(todos)
   localStorage.setItem(STORAGE_KEY, json)
+  syncToBackend('sync_todos', { todos })
 
   // 空のデータではファイルバックアップしない（データ消失防止）
   if (todos.length === 0) return
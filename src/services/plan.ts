@@ -1,7 +1,7 @@
 import { getKanaeConfig, type KanaeReminderConfig } from './reminder'
-import { getClaudeApiKey, generatePlanClaude } from '../lib/claude'
-import { getGeminiApiKey, generatePlanGemini } from '../lib/gemini'
-import { getApiKey as getOpenAiApiKey, generatePlan as generatePlanOpenAI } from '../lib/openai'
+import { hasClaudeApiKey, generatePlanClaude } from '../lib/claude'
+import { hasGeminiApiKey, generatePlanGemini } from '../lib/gemini'
+import { hasApiKey as hasOpenAiApiKey, generatePlan as generatePlanOpenAI } from '../lib/openai'
 import type { PlanResult, PlanTask } from '../lib/openai'
 
 type PlanProvider = 'claude' | 'openai' | 'gemini'
@@ -10,13 +10,13 @@ function resolvePlanProvider(config: KanaeReminderConfig): PlanProvider {
   if (config.aiProvider === 'claude' || config.aiProvider === 'openai' || config.aiProvider === 'gemini') {
     return config.aiProvider
   }
-  if (getClaudeApiKey()) {
+  if (hasClaudeApiKey()) {
     return 'claude'
   }
-  if (getGeminiApiKey()) {
+  if (hasGeminiApiKey()) {
     return 'gemini'
   }
-  if (getOpenAiApiKey()) {
+  if (hasOpenAiApiKey()) {
     return 'openai'
   }
   return 'claude'
@@ -1,7 +1,7 @@
 import {
   generateReminderMessage,
   generateMorningGreeting,
-  getClaudeApiKey,
+  hasClaudeApiKey,
   decomposeTaskClaude,
   generateCustomPersonaMessageClaude,
   type DecomposeResult,
@@ -9,18 +9,20 @@ import {
 import {
   generateKanaeReminderMessageOpenAI,
   generateKanaeMorningGreetingOpenAI,
-  getApiKey as getOpenAiApiKey,
+  hasApiKey as hasOpenAiApiKey,
   decomposeTask as decomposeTaskOpenAI,
   generateCustomPersonaMessageOpenAI,
 } from '../lib/openai'
 import {
   generateKanaeReminderMessageGemini,
   generateKanaeMorningGreetingGemini,
-  getGeminiApiKey,
+  hasGeminiApiKey,
   generateCustomPersonaMessageGemini,
   decomposeTaskGemini,
 } from '../lib/gemini'
 import { sendDiscordDM } from '../lib/discord'
+import { notifyWebhooks } from '../lib/notifier'
+import { sendReminderMail } from '../lib/mail'
 import { invoke } from '@tauri-apps/api/core'
 import { showNotification } from '../lib/utils'
 import { searchWithTavily, formatSearchResultsForPrompt, getTavilyApiKey } from '../lib/tavily'
@@ -171,9 +173,6 @@ export interface KanaeReminderConfig {
   enabled: boolean
   aiProvider: 'auto' | 'claude' | 'openai' | 'gemini'
   aiModels: AIModelConfig
-  claudeApiKey: string
-  geminiApiKey: string
-  openaiApiKey: string
   discordEnabled: boolean
   discordBotToken: string
   discordUserId: string
@@ -218,9 +217,6 @@ export const DEFAULT_KANAE_CONFIG: KanaeReminderConfig = {
   enabled: false,
   aiProvider: 'auto',
   aiModels: DEFAULT_AI_MODELS,
-  claudeApiKey: '',
-  geminiApiKey: '',
-  openaiApiKey: '',
   discordEnabled: false,
   discordBotToken: '',
   discordUserId: '',
@@ -431,13 +427,13 @@ function resolveProvider(config: KanaeReminderConfig): 'claude' | 'openai' | 'ge
     return config.aiProvider
   }
   // auto: 利用可能なAPIキーから自動選択（Claude優先）
-  if (getClaudeApiKey()) {
+  if (hasClaudeApiKey()) {
     return 'claude'
   }
-  if (getGeminiApiKey()) {
+  if (hasGeminiApiKey()) {
     return 'gemini'
   }
-  if (getOpenAiApiKey()) {
+  if (hasOpenAiApiKey()) {
     return 'openai'
   }
   return 'claude'
@@ -1218,8 +1214,14 @@ export function getPersonaNotificationMessage(
   return getPlainNotificationMessage(taskTitle, type, followUpCount)
 }
 
+// 同じ通知を二度送らないためのキー (タスク・期限・通知段階ごとに1回だけ送る)
+export function reminderIdempotencyKey(taskId: string, dueDate: Date | null, stage: string): string {
+  return `${taskId}:${dueDate ? dueDate.getTime() : 'none'}:${stage}`
+}
+
 // リマインダーを送信
-export async function sendReminder(task: ReminderTask): Promise<void> {
+// stage は通知段階 (upcoming / overdue-<前回通知時刻> / followup-<回数> / habit-<日付>)
+export async function sendReminder(task: ReminderTask, stage = 'upcoming'): Promise<'queued' | 'duplicate'> {
   const config = getKanaeConfig()
 
   if (!config.enabled || !config.discordEnabled) {
@@ -1242,14 +1244,47 @@ export async function sendReminder(task: ReminderTask): Promise<void> {
   const message = await generateReminderMessageWithPersona(task, isOverdue, memoryContext, config)
 
   // Discord DMを送信（Embed形式）
-  await sendDiscordDM(message, {
+  return sendDiscordDM(message, {
+    taskId: task.id,
     taskTitle: task.title,
     dueDate: dueDate,
     isOverdue,
-    type: 'reminder'
+    type: 'reminder',
+    idempotencyKey: reminderIdempotencyKey(task.id, dueDate, stage),
   })
 }
 
+// 設定済みのWebhookにリマインダーを送る (送信先ごとの条件と重複防止はバックエンドで判定)
+// LLMは呼ばず、ペルソナの定型メッセージを使う
+async function sendReminderWebhooks(
+  task: ReminderTask,
+  type: 'reminder' | 'overdue' | 'followup',
+  followUpCount: number,
+  stage: string
+): Promise<number> {
+  const { title, body } = getPersonaNotificationMessage(task.title, type, followUpCount, !!task.recurrence)
+  return notifyWebhooks('reminder', title, body, {
+    taskId: task.id,
+    idempotencyKey: reminderIdempotencyKey(task.id, resolveDueDate(task.dueDate), stage),
+  })
+}
+
+// メールでリマインダーを送る (メール通知が無効なら null)
+async function sendReminderEmail(
+  task: ReminderTask,
+  type: 'reminder' | 'overdue' | 'followup',
+  followUpCount: number,
+  stage: string
+): Promise<'queued' | 'duplicate' | null> {
+  const { title, body } = getPersonaNotificationMessage(task.title, type, followUpCount, !!task.recurrence)
+  return sendReminderMail(
+    task.id,
+    'reminder',
+    `${title}\n${body}`,
+    reminderIdempotencyKey(task.id, resolveDueDate(task.dueDate), stage)
+  )
+}
+
 // 朝の挨拶を送信
 export async function sendMorningGreeting(): Promise<void> {
   const config = getKanaeConfig()
@@ -1329,6 +1364,8 @@ export function getTasksNeedingReminder(tasks: ReminderTask[]): ReminderTask[] {
   })
 }
 
+type ReminderChannel = 'discord' | 'desktop' | 'webhook' | 'email'
+
 const SENT_REMINDERS_STORAGE_KEY = 'kanae-sent-reminders'
 
 function loadSentReminders(): Set<string> {
@@ -1364,26 +1401,39 @@ function getJapanDateKey(): string {
 }
 
 // リマインダーキーを生成
-function getReminderKey(taskId: string, type: 'upcoming' | 'overdue', channel: 'discord' | 'desktop' = 'discord'): string {
+function getReminderKey(taskId: string, type: 'upcoming' | 'overdue', channel: ReminderChannel = 'discord'): string {
   const date = getJapanDateKey()
   return `${taskId}-${type}-${channel}-${date}`
 }
 
 // 未送信のリマインダーをチェック
-export function shouldSendReminder(taskId: string, isOverdue: boolean, channel: 'discord' | 'desktop' = 'discord'): boolean {
+export function shouldSendReminder(taskId: string, isOverdue: boolean, channel: ReminderChannel = 'discord'): boolean {
   const type = isOverdue ? 'overdue' : 'upcoming'
   const key = getReminderKey(taskId, type, channel)
   return !sentReminders.has(key)
 }
 
 // リマインダー送信済みとしてマーク
-export function markReminderSent(taskId: string, isOverdue: boolean, channel: 'discord' | 'desktop' = 'discord'): void {
+export function markReminderSent(taskId: string, isOverdue: boolean, channel: ReminderChannel = 'discord'): void {
   const type = isOverdue ? 'overdue' : 'upcoming'
   const key = getReminderKey(taskId, type, channel)
   sentReminders.add(key)
   saveSentReminders(sentReminders)
 }
 
+// 期限が変わったタスクの期限前リマインダーを未送信に戻す
+export function clearUpcomingReminders(taskId: string): void {
+  const prefix = `${taskId}-upcoming-`
+  let changed = false
+  for (const key of Array.from(sentReminders)) {
+    if (key.startsWith(prefix)) {
+      sentReminders.delete(key)
+      changed = true
+    }
+  }
+  if (changed) saveSentReminders(sentReminders)
+}
+
 // 現在時刻が通知許可時間帯かチェック
 function isWithinAllowedTime(config: NotificationTimingConfig): boolean {
   const now = new Date()
@@ -1596,8 +1646,10 @@ export function startReminderService(
         // 今日まだ通知していなければ送信
         const shouldSendDesktop = currentConfig.desktopNotificationEnabled && shouldSendReminder(task.id, false, 'desktop')
         const shouldSendDiscord = currentConfig.discordEnabled && shouldSendReminder(task.id, false, 'discord')
+        const shouldSendWebhook = shouldSendReminder(task.id, false, 'webhook')
+        const shouldSendEmail = shouldSendReminder(task.id, false, 'email')
 
-        if (shouldSendDesktop || shouldSendDiscord) {
+        if (shouldSendDesktop || shouldSendDiscord || shouldSendWebhook || shouldSendEmail) {
           console.log(`[Reminder] Processing recurrence task: ${task.title}`, { type: task.recurrence?.type })
 
           // デスクトップ通知
@@ -1634,14 +1686,36 @@ export function startReminderService(
           // Discord通知
           if (shouldSendDiscord) {
             try {
-              await sendReminder({ ...task, status: task.status || 'pending' })
+              const status = await sendReminder({ ...task, status: task.status || 'pending' }, `habit-${getJapanDateKey()}`)
               markReminderSent(task.id, false, 'discord')
-              console.log(`[Reminder] Recurrence Discord DM sent: ${task.title}`)
+              console.log(`[Reminder] Recurrence Discord DM ${status}: ${task.title}`)
             } catch (error) {
               console.error(`[Reminder] Recurrence Discord DM failed: ${task.title}`, error)
             }
           }
 
+          // Webhook通知
+          if (shouldSendWebhook) {
+            try {
+              const count = await sendReminderWebhooks(task, 'reminder', 0, `habit-${getJapanDateKey()}`)
+              markReminderSent(task.id, false, 'webhook')
+              console.log(`[Reminder] Recurrence webhooks queued (${count}): ${task.title}`)
+            } catch (error) {
+              console.error(`[Reminder] Recurrence webhooks failed: ${task.title}`, error)
+            }
+          }
+
+          // メール通知（無効ならバックエンドが何もしない）
+          if (shouldSendEmail) {
+            try {
+              const status = await sendReminderEmail(task, 'reminder', 0, `habit-${getJapanDateKey()}`)
+              markReminderSent(task.id, false, 'email')
+              console.log(`[Reminder] Recurrence email ${status ?? 'disabled'}: ${task.title}`)
+            } catch (error) {
+              console.error(`[Reminder] Recurrence email failed: ${task.title}`, error)
+            }
+          }
+
           incrementTaskNotificationCount(task.id)
         } else {
           console.log(`[Reminder] Skip recurrence (already sent today): ${task.title}`)
@@ -1738,6 +1812,12 @@ export function startReminderService(
 
       if (shouldNotify) {
         const isOverdue = notifyType === 'overdue'
+        // 期限切れの通知は間隔をあけて繰り返すため、前回の通知時刻で区別する
+        const reminderStage = notifyType === 'overdue'
+          ? `overdue-${notification.notifiedAt ?? 0}`
+          : notifyType === 'followup'
+            ? `followup-${notifyFollowUpCount}`
+            : 'upcoming'
 
         // デスクトップ通知（Discord通知と同じ頻度制限、LLMでメッセージ生成）
         if (currentConfig.desktopNotificationEnabled) {
@@ -1781,15 +1861,37 @@ export function startReminderService(
         if (currentConfig.discordEnabled) {
           if (shouldSendReminder(task.id, isOverdue, 'discord')) {
             try {
-              await sendReminder(task)
+              const status = await sendReminder(task, reminderStage)
               markReminderSent(task.id, isOverdue, 'discord')
-              console.log(`[Reminder] Discord DM sent: ${task.title}`)
+              console.log(`[Reminder] Discord DM ${status}: ${task.title}`)
             } catch (error) {
               console.error(`[Reminder] Discord DM failed: ${task.title}`, error)
             }
           }
         }
 
+        // Webhook通知（送信先の有効・無効と条件はバックエンドで判定）
+        if (shouldSendReminder(task.id, isOverdue, 'webhook')) {
+          try {
+            const count = await sendReminderWebhooks(task, notifyType, notifyFollowUpCount, reminderStage)
+            markReminderSent(task.id, isOverdue, 'webhook')
+            console.log(`[Reminder] Webhooks queued (${count}): ${task.title}`)
+          } catch (error) {
+            console.error(`[Reminder] Webhooks failed: ${task.title}`, error)
+          }
+        }
+
+        // メール通知（無効ならバックエンドが何もしない）
+        if (shouldSendReminder(task.id, isOverdue, 'email')) {
+          try {
+            const status = await sendReminderEmail(task, notifyType, notifyFollowUpCount, reminderStage)
+            markReminderSent(task.id, isOverdue, 'email')
+            console.log(`[Reminder] Email ${status ?? 'disabled'}: ${task.title}`)
+          } catch (error) {
+            console.error(`[Reminder] Email failed: ${task.title}`, error)
+          }
+        }
+
         // 1日の通知回数をインクリメント
         incrementTaskNotificationCount(task.id)
 
@@ -96,6 +96,9 @@ export interface Todo {
   karmaAwarded: boolean  // カルマ獲得済みフラグ（無限増殖防止）
   archived: boolean  // アーカイブ済みフラグ
   archivedAt: number | null  // アーカイブ日時
+  updatedAt?: number | null  // 最終更新日時（保存時に内容の変更を検出して付ける。CalDAV同期の変更検知用）
+  dueTimeZone?: string | null  // 期限のタイムゾーン（IANA名、'floating' は端末のタイムゾーンに合わせる）
+  dueLocal?: string | null  // 期限の壁時計時刻（YYYY-MM-DDTHH:MM:SS）
 }
 
 // セクション型